@@ -1,6 +1,17 @@
 use std::env;
+use std::process::Command;
 
 fn main() {
+    // Plugins are only loaded if they were built with the same compiler, see src/plugins
+    let rustc = env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    let version = Command::new(rustc)
+        .arg("--version")
+        .output()
+        .ok()
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .unwrap_or_default();
+    println!("cargo:rustc-env=FERRUMC_RUSTC_VERSION={}", version.trim());
+
     if cfg!(not(target_os = "windows")) {
         return;
     }
@@ -0,0 +1,352 @@
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use tracing::{error, info};
+use uuid::Uuid;
+
+use ferrumc_macros::event_handler;
+
+use crate::access::{load_json_list, save_json_list};
+use crate::events::login_events::LoginStartEvent;
+use crate::state::GlobalState;
+use crate::utils::prelude::*;
+
+/// What every ban has, whoever or whatever is banned.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BanDetails {
+    /// Unix timestamp, in seconds.
+    pub created: u64,
+    /// Who issued the ban.
+    pub source: String,
+    /// Unix timestamp, in seconds. Bans without one are permanent.
+    #[serde(default)]
+    pub expires: Option<u64>,
+    pub reason: String,
+}
+
+impl BanDetails {
+    /// A ban starting now, lasting `duration` seconds or forever.
+    pub fn new(source: &str, duration: Option<u64>, reason: &str) -> Self {
+        let created = unix_now();
+        Self {
+            created,
+            source: source.to_string(),
+            expires: duration.map(|duration| created + duration),
+            reason: reason.to_string(),
+        }
+    }
+
+    pub fn is_expired(&self, now: u64) -> bool {
+        self.expires.is_some_and(|expires| expires <= now)
+    }
+
+    /// What's shown on the disconnect screen.
+    pub fn kick_message(&self, now: u64) -> String {
+        let mut message = format!("You are banned from this server.\nReason: {}", self.reason);
+        if let Some(expires) = self.expires {
+            message.push_str(&format!(
+                "\nYour ban will be removed in {}",
+                format_duration(expires.saturating_sub(now))
+            ));
+        }
+        message
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlayerBan {
+    /// Players banned while offline only have a name, until they next try to join.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uuid: Option<Uuid>,
+    pub name: String,
+    #[serde(flatten)]
+    pub details: BanDetails,
+}
+
+impl PlayerBan {
+    fn matches(&self, uuid: Uuid, name: &str) -> bool {
+        match self.uuid {
+            Some(banned) => banned == uuid,
+            None => self.name.eq_ignore_ascii_case(name),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IpBan {
+    pub ip: IpAddr,
+    #[serde(flatten)]
+    pub details: BanDetails,
+}
+
+/// Player and IP bans, saved to `banned-players.json` and `banned-ips.json`.
+///
+/// Expired bans are dropped the next time they're looked at.
+pub struct BanManager {
+    players_path: PathBuf,
+    ips_path: PathBuf,
+    players: RwLock<Vec<PlayerBan>>,
+    ips: RwLock<Vec<IpBan>>,
+}
+
+impl BanManager {
+    pub async fn load(players_path: impl Into<PathBuf>, ips_path: impl Into<PathBuf>) -> Result<Self> {
+        let players_path = players_path.into();
+        let ips_path = ips_path.into();
+
+        Ok(Self {
+            players: RwLock::new(load_json_list(&players_path).await?),
+            ips: RwLock::new(load_json_list(&ips_path).await?),
+            players_path,
+            ips_path,
+        })
+    }
+
+    /// Bans a player, replacing any ban they already had.
+    pub async fn ban_player(&self, uuid: Option<Uuid>, name: &str, details: BanDetails) -> Result<()> {
+        {
+            let mut players = self.players.write();
+            players.retain(|ban| !ban.name.eq_ignore_ascii_case(name) && (uuid.is_none() || ban.uuid != uuid));
+            players.push(PlayerBan {
+                uuid,
+                name: name.to_string(),
+                details,
+            });
+        }
+        self.save_players().await
+    }
+
+    /// Bans an IP address, replacing any ban it already had.
+    pub async fn ban_ip(&self, ip: IpAddr, details: BanDetails) -> Result<()> {
+        {
+            let mut ips = self.ips.write();
+            ips.retain(|ban| ban.ip != ip);
+            ips.push(IpBan { ip, details });
+        }
+        self.save_ips().await
+    }
+
+    /// Lifts a player's ban, returning false if they weren't banned.
+    pub async fn pardon_player(&self, name: &str) -> Result<bool> {
+        let removed = {
+            let mut players = self.players.write();
+            let before = players.len();
+            players.retain(|ban| !ban.name.eq_ignore_ascii_case(name));
+            players.len() != before
+        };
+
+        if removed {
+            self.save_players().await?;
+        }
+        Ok(removed)
+    }
+
+    /// Lifts an IP ban, returning false if the address wasn't banned.
+    pub async fn pardon_ip(&self, ip: IpAddr) -> Result<bool> {
+        let removed = {
+            let mut ips = self.ips.write();
+            let before = ips.len();
+            ips.retain(|ban| ban.ip != ip);
+            ips.len() != before
+        };
+
+        if removed {
+            self.save_ips().await?;
+        }
+        Ok(removed)
+    }
+
+    /// The player's ban, if they have one that hasn't expired.
+    pub async fn player_ban(&self, uuid: Uuid, name: &str) -> Result<Option<PlayerBan>> {
+        let now = unix_now();
+        let (ban, pruned) = {
+            let mut players = self.players.write();
+            let before = players.len();
+            players.retain(|ban| !ban.details.is_expired(now));
+            let pruned = players.len() != before;
+
+            let ban = players.iter_mut().find(|ban| ban.matches(uuid, name)).map(|ban| {
+                // Pin name-only bans to the player's uuid, so changing name doesn't get around it
+                ban.uuid = Some(uuid);
+                ban.clone()
+            });
+            (ban, pruned)
+        };
+
+        if pruned || ban.is_some() {
+            self.save_players().await?;
+        }
+        Ok(ban)
+    }
+
+    /// The address's ban, if it has one that hasn't expired.
+    pub async fn ip_ban(&self, ip: IpAddr) -> Result<Option<IpBan>> {
+        let now = unix_now();
+        let (ban, pruned) = {
+            let mut ips = self.ips.write();
+            let before = ips.len();
+            ips.retain(|ban| !ban.details.is_expired(now));
+            (ips.iter().find(|ban| ban.ip == ip).cloned(), ips.len() != before)
+        };
+
+        if pruned {
+            self.save_ips().await?;
+        }
+        Ok(ban)
+    }
+
+    async fn save_players(&self) -> Result<()> {
+        let players = self.players.read().clone();
+        save_json_list(&self.players_path, &players).await
+    }
+
+    async fn save_ips(&self) -> Result<()> {
+        let ips = self.ips.read().clone();
+        save_json_list(&self.ips_path, &ips).await
+    }
+}
+
+#[event_handler(priority = "fastest")]
+async fn check_bans(event: Arc<LoginStartEvent>, state: GlobalState) {
+    match find_ban(&event, &state).await {
+        Ok(Some(ban)) => {
+            info!("{} is banned: {}", event.username, ban.reason);
+            event.deny(ban.kick_message(unix_now()));
+        }
+        Ok(None) => {}
+        Err(e) => {
+            error!("Failed to check bans for {}: {}", event.username, e);
+            event.deny("Failed to check bans");
+        }
+    }
+}
+
+async fn find_ban(event: &LoginStartEvent, state: &GlobalState) -> Result<Option<BanDetails>> {
+    let uuid = Uuid::from_u128(event.uuid);
+    if let Some(ban) = state.bans.player_ban(uuid, &event.username).await? {
+        return Ok(Some(ban.details));
+    }
+
+    match event.address {
+        Some(ip) => Ok(state.bans.ip_ban(ip).await?.map(|ban| ban.details)),
+        None => Ok(None),
+    }
+}
+
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |now| now.as_secs())
+}
+
+/// Parses a duration like `30s`, `10m`, `12h`, `7d` or `2w` into seconds.
+pub fn parse_duration(duration: &str) -> Option<u64> {
+    let unit = duration.chars().last()?;
+    let amount = duration[..duration.len() - unit.len_utf8()].parse::<u64>().ok()?;
+
+    let seconds = match unit {
+        's' => 1,
+        'm' => 60,
+        'h' => 60 * 60,
+        'd' => 24 * 60 * 60,
+        'w' => 7 * 24 * 60 * 60,
+        _ => return None,
+    };
+    amount.checked_mul(seconds).filter(|&seconds| seconds > 0)
+}
+
+/// Formats seconds as e.g. `2d 3h 10m`, leaving out the seconds unless it's under a minute.
+pub fn format_duration(seconds: u64) -> String {
+    if seconds < 60 {
+        return format!("{}s", seconds);
+    }
+
+    let parts = [
+        (seconds / (24 * 60 * 60), "d"),
+        (seconds / (60 * 60) % 24, "h"),
+        (seconds / 60 % 60, "m"),
+    ];
+    parts
+        .iter()
+        .filter(|(amount, _)| *amount > 0)
+        .map(|(amount, unit)| format!("{}{}", amount, unit))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::access::temp_path;
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("30s"), Some(30));
+        assert_eq!(parse_duration("10m"), Some(600));
+        assert_eq!(parse_duration("2d"), Some(172800));
+        assert_eq!(parse_duration("0h"), None);
+        assert_eq!(parse_duration("5y"), None);
+        assert_eq!(parse_duration("h"), None);
+        assert_eq!(parse_duration(""), None);
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(45), "45s");
+        assert_eq!(format_duration(3600), "1h");
+        assert_eq!(format_duration(2 * 86400 + 3 * 3600 + 10 * 60 + 5), "2d 3h 10m");
+    }
+
+    #[test]
+    fn test_ban_expiry() {
+        let mut details = BanDetails::new("Server", Some(60), "Griefing");
+        assert!(!details.is_expired(details.created));
+        assert!(details.is_expired(details.created + 60));
+        assert!(details.kick_message(details.created).contains("1m"));
+
+        details.expires = None;
+        assert!(!details.is_expired(u64::MAX));
+    }
+
+    #[tokio::test]
+    async fn test_player_bans() {
+        let (players, ips) = (temp_path("banned_players"), temp_path("banned_ips"));
+        let bans = BanManager::load(&players, &ips).await.unwrap();
+        let uuid = Uuid::new_v4();
+
+        bans.ban_player(None, "steve", BanDetails::new("Server", None, "Griefing"))
+            .await
+            .unwrap();
+        let ban = bans.player_ban(uuid, "Steve").await.unwrap().unwrap();
+        assert_eq!(ban.details.reason, "Griefing");
+
+        // The ban stuck to the uuid, and was saved
+        let reloaded = BanManager::load(&players, &ips).await.unwrap();
+        assert!(reloaded.player_ban(uuid, "Alex").await.unwrap().is_some());
+
+        assert!(bans.pardon_player("Steve").await.unwrap());
+        assert!(bans.player_ban(uuid, "Steve").await.unwrap().is_none());
+
+        tokio::fs::remove_file(players).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_expired_ip_ban() {
+        let (players, ips) = (temp_path("expired_players"), temp_path("expired_ips"));
+        let bans = BanManager::load(&players, &ips).await.unwrap();
+        let ip: IpAddr = "127.0.0.1".parse().unwrap();
+
+        let mut details = BanDetails::new("Server", Some(60), "Spam");
+        details.expires = Some(details.created - 1);
+        bans.ban_ip(ip, details).await.unwrap();
+
+        assert!(bans.ip_ban(ip).await.unwrap().is_none());
+        assert!(!bans.pardon_ip(ip).await.unwrap());
+
+        tokio::fs::remove_file(ips).await.unwrap();
+    }
+}
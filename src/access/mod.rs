@@ -0,0 +1,38 @@
+//! Who's allowed on the server: the whitelist, bans and operators, each kept in a JSON file next to
+//! the config like vanilla does.
+
+use std::path::Path;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::utils::prelude::*;
+
+pub mod bans;
+pub mod ops;
+pub mod whitelist;
+
+/// Reads a JSON list from a file. A file that doesn't exist yet is an empty list.
+pub(crate) async fn load_json_list<T: DeserializeOwned>(path: &Path) -> Result<Vec<T>> {
+    let contents = match tokio::fs::read_to_string(path).await {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+
+    serde_json::from_str(&contents)
+        .map_err(|e| Error::DeserializationError(format!("{}: {}", path.display(), e)))
+}
+
+pub(crate) async fn save_json_list<T: Serialize>(path: &Path, entries: &[T]) -> Result<()> {
+    let contents = serde_json::to_string_pretty(entries)
+        .map_err(|e| Error::SerializationError(format!("{}: {}", path.display(), e)))?;
+    tokio::fs::write(path, contents).await?;
+    Ok(())
+}
+
+/// A file in the temp directory for a test to keep a list in, unique to this run.
+#[cfg(test)]
+pub(crate) fn temp_path(name: &str) -> std::path::PathBuf {
+    std::env::temp_dir().join(format!("ferrumc_{}_{}.json", name, std::process::id()))
+}
@@ -0,0 +1,149 @@
+use std::path::PathBuf;
+
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::access::{load_json_list, save_json_list};
+use crate::utils::prelude::*;
+
+/// Permission levels, the same as vanilla's. Each level can do everything the ones below it can.
+pub mod levels {
+    /// Everyone.
+    pub const ALL: u8 = 0;
+    pub const MODERATOR: u8 = 1;
+    /// Cheats, e.g. /tp and /gamemode.
+    pub const GAMEMASTER: u8 = 2;
+    /// Managing players, e.g. /ban and /op.
+    pub const ADMIN: u8 = 3;
+    /// Managing the server, e.g. /stop.
+    pub const OWNER: u8 = 4;
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OpEntry {
+    /// Players opped while offline only have a name, until they next join.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uuid: Option<Uuid>,
+    pub name: String,
+    pub level: u8,
+}
+
+impl OpEntry {
+    fn matches(&self, uuid: Uuid, name: &str) -> bool {
+        match self.uuid {
+            Some(op) => op == uuid,
+            None => self.name.eq_ignore_ascii_case(name),
+        }
+    }
+}
+
+/// The server operators and their permission levels, saved to `ops.json`.
+pub struct Operators {
+    path: PathBuf,
+    entries: RwLock<Vec<OpEntry>>,
+}
+
+impl Operators {
+    pub async fn load(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let entries = load_json_list(&path).await?;
+
+        Ok(Self {
+            path,
+            entries: RwLock::new(entries),
+        })
+    }
+
+    pub async fn save(&self) -> Result<()> {
+        let entries = self.entries.read().clone();
+        save_json_list(&self.path, &entries).await
+    }
+
+    /// The player's permission level. Players who aren't ops are [levels::ALL].
+    pub fn level(&self, uuid: Uuid, name: &str) -> u8 {
+        self.entries
+            .read()
+            .iter()
+            .find(|entry| entry.matches(uuid, name))
+            .map_or(levels::ALL, |entry| entry.level)
+    }
+
+    /// Makes a player an op, or changes their level if they already are one.
+    pub async fn op(&self, name: &str, uuid: Option<Uuid>, level: u8) -> Result<()> {
+        {
+            let mut entries = self.entries.write();
+            entries.retain(|entry| !entry.name.eq_ignore_ascii_case(name) && (uuid.is_none() || entry.uuid != uuid));
+            entries.push(OpEntry {
+                uuid,
+                name: name.to_string(),
+                level,
+            });
+        }
+        self.save().await
+    }
+
+    /// Takes away a player's op, returning false if they weren't one.
+    pub async fn deop(&self, name: &str) -> Result<bool> {
+        let removed = {
+            let mut entries = self.entries.write();
+            let before = entries.len();
+            entries.retain(|entry| !entry.name.eq_ignore_ascii_case(name));
+            entries.len() != before
+        };
+
+        if removed {
+            self.save().await?;
+        }
+        Ok(removed)
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.entries
+            .read()
+            .iter()
+            .map(|entry| entry.name.clone())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::access::temp_path;
+
+    #[tokio::test]
+    async fn test_op_deop() {
+        let path = temp_path("ops");
+        let ops = Operators::load(&path).await.unwrap();
+        let uuid = Uuid::new_v4();
+
+        assert_eq!(ops.level(uuid, "Steve"), levels::ALL);
+        ops.op("Steve", Some(uuid), levels::ADMIN).await.unwrap();
+        assert_eq!(ops.level(uuid, "Steve"), levels::ADMIN);
+
+        // Opping again changes the level rather than adding another entry
+        ops.op("Steve", Some(uuid), levels::OWNER).await.unwrap();
+        let reloaded = Operators::load(&path).await.unwrap();
+        assert_eq!(reloaded.names(), vec!["Steve".to_string()]);
+        assert_eq!(reloaded.level(uuid, "Steve"), levels::OWNER);
+
+        assert!(ops.deop("steve").await.unwrap());
+        assert!(!ops.deop("Steve").await.unwrap());
+        assert_eq!(ops.level(uuid, "Steve"), levels::ALL);
+
+        tokio::fs::remove_file(path).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_name_only_op() {
+        let path = temp_path("ops_by_name");
+        let ops = Operators::load(&path).await.unwrap();
+
+        ops.op("Alex", None, levels::GAMEMASTER).await.unwrap();
+        assert_eq!(ops.level(Uuid::new_v4(), "alex"), levels::GAMEMASTER);
+        assert_eq!(ops.level(Uuid::new_v4(), "Steve"), levels::ALL);
+
+        tokio::fs::remove_file(path).await.unwrap();
+    }
+}
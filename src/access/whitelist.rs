@@ -0,0 +1,214 @@
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use tracing::{error, info};
+use uuid::Uuid;
+
+use ferrumc_macros::event_handler;
+
+use crate::access::{load_json_list, save_json_list};
+use crate::events::config_events::ConfigReloadEvent;
+use crate::events::login_events::LoginStartEvent;
+use crate::state::GlobalState;
+use crate::utils::prelude::*;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WhitelistEntry {
+    /// Players added while offline only have a name, until they first join.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uuid: Option<Uuid>,
+    pub name: String,
+}
+
+/// The players allowed to join while the whitelist is on, saved to `whitelist.json`.
+pub struct Whitelist {
+    path: PathBuf,
+    enabled: AtomicBool,
+    entries: RwLock<Vec<WhitelistEntry>>,
+}
+
+impl Whitelist {
+    pub async fn load(path: impl Into<PathBuf>, enabled: bool) -> Result<Self> {
+        let path = path.into();
+        let entries = load_json_list(&path).await?;
+
+        Ok(Self {
+            path,
+            enabled: AtomicBool::new(enabled),
+            entries: RwLock::new(entries),
+        })
+    }
+
+    /// Reads the file again, e.g. after it was edited by hand.
+    pub async fn reload(&self) -> Result<()> {
+        let entries = load_json_list(&self.path).await?;
+        *self.entries.write() = entries;
+        Ok(())
+    }
+
+    pub async fn save(&self) -> Result<()> {
+        let entries = self.entries.read().clone();
+        save_json_list(&self.path, &entries).await
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Checks if a player can join. Always true while the whitelist is off.
+    ///
+    /// Entries that only have a name match the first player to join with it, and take their uuid.
+    pub async fn allows(&self, uuid: Uuid, name: &str) -> Result<bool> {
+        if !self.is_enabled() {
+            return Ok(true);
+        }
+
+        let claimed = {
+            let mut entries = self.entries.write();
+            if entries.iter().any(|entry| entry.uuid == Some(uuid)) {
+                return Ok(true);
+            }
+
+            let unclaimed = entries
+                .iter_mut()
+                .find(|entry| entry.uuid.is_none() && entry.name.eq_ignore_ascii_case(name));
+            match unclaimed {
+                Some(entry) => {
+                    entry.uuid = Some(uuid);
+                    entry.name = name.to_string();
+                    true
+                }
+                None => false,
+            }
+        };
+
+        if claimed {
+            self.save().await?;
+        }
+        Ok(claimed)
+    }
+
+    /// Adds a player, returning false if they were already on the whitelist.
+    pub async fn add(&self, name: &str, uuid: Option<Uuid>) -> Result<bool> {
+        {
+            let mut entries = self.entries.write();
+            if entries.iter().any(|entry| entry.name.eq_ignore_ascii_case(name)) {
+                return Ok(false);
+            }
+            entries.push(WhitelistEntry {
+                uuid,
+                name: name.to_string(),
+            });
+        }
+
+        self.save().await?;
+        Ok(true)
+    }
+
+    /// Removes a player, returning false if they weren't on the whitelist.
+    pub async fn remove(&self, name: &str) -> Result<bool> {
+        let removed = {
+            let mut entries = self.entries.write();
+            let before = entries.len();
+            entries.retain(|entry| !entry.name.eq_ignore_ascii_case(name));
+            entries.len() != before
+        };
+
+        if removed {
+            self.save().await?;
+        }
+        Ok(removed)
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.entries
+            .read()
+            .iter()
+            .map(|entry| entry.name.clone())
+            .collect()
+    }
+}
+
+#[event_handler(priority = "fastest")]
+async fn check_whitelist(event: Arc<LoginStartEvent>, state: GlobalState) {
+    let uuid = Uuid::from_u128(event.uuid);
+    match state.whitelist.allows(uuid, &event.username).await {
+        Ok(true) => {}
+        Ok(false) => {
+            info!("{} isn't whitelisted", event.username);
+            event.deny("You are not whitelisted on this server!");
+        }
+        Err(e) => {
+            error!("Failed to check the whitelist for {}: {}", event.username, e);
+            event.deny("Failed to check the whitelist");
+        }
+    }
+}
+
+#[event_handler]
+async fn apply_whitelist_config(event: Arc<ConfigReloadEvent>, state: GlobalState) {
+    // Only if it was changed in the file, so /whitelist on isn't undone by an unrelated reload
+    if event.old.whitelist != event.new.whitelist {
+        state.whitelist.set_enabled(event.new.whitelist);
+        info!("Whitelist is now turned {}", if event.new.whitelist { "on" } else { "off" });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::access::temp_path;
+
+    #[tokio::test]
+    async fn test_whitelist_disabled_allows_everyone() {
+        let whitelist = Whitelist::load(temp_path("whitelist_disabled"), false)
+            .await
+            .unwrap();
+
+        assert!(whitelist.allows(Uuid::new_v4(), "Steve").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_whitelist_add_remove() {
+        let path = temp_path("whitelist_add_remove");
+        let whitelist = Whitelist::load(&path, true).await.unwrap();
+        let uuid = Uuid::new_v4();
+
+        assert!(!whitelist.allows(uuid, "Steve").await.unwrap());
+        assert!(whitelist.add("Steve", Some(uuid)).await.unwrap());
+        assert!(!whitelist.add("steve", None).await.unwrap());
+        assert!(whitelist.allows(uuid, "Steve").await.unwrap());
+
+        // Saved to the file
+        let reloaded = Whitelist::load(&path, true).await.unwrap();
+        assert_eq!(reloaded.names(), vec!["Steve".to_string()]);
+
+        assert!(whitelist.remove("STEVE").await.unwrap());
+        assert!(!whitelist.remove("Steve").await.unwrap());
+        assert!(!whitelist.allows(uuid, "Steve").await.unwrap());
+
+        tokio::fs::remove_file(path).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_whitelist_name_entry_claimed_on_join() {
+        let path = temp_path("whitelist_claim");
+        let whitelist = Whitelist::load(&path, true).await.unwrap();
+        let uuid = Uuid::new_v4();
+
+        whitelist.add("steve", None).await.unwrap();
+        assert!(whitelist.allows(uuid, "Steve").await.unwrap());
+        // Someone else can't use the name once it's been claimed
+        assert!(!whitelist.allows(Uuid::new_v4(), "Steve").await.unwrap());
+        assert_eq!(whitelist.names(), vec!["Steve".to_string()]);
+
+        tokio::fs::remove_file(path).await.unwrap();
+    }
+}
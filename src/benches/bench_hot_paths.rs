@@ -0,0 +1,140 @@
+//! Benches for the paths every player hits all the time, so it shows up when one of them gets
+//! slower. Run with `cargo bench --bench hot_paths`.
+
+use std::io::Cursor;
+
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use ferrumc::ecs::component::ComponentStorage;
+use ferrumc::net::packets::outgoing::chunk_and_light_data::ChunkDataAndUpdateLight;
+use ferrumc::net::protocol::NATIVE_PROTOCOL;
+use ferrumc::utils::encoding::position::Position;
+use ferrumc::world::chunk_format::Chunk;
+use ferrumc::world::dimension::Dimension;
+use ferrumc::world::generation::multi_noise::MultiNoiseGenerator;
+use ferrumc::world::generation::WorldGenerator;
+use ferrumc_codec::enc::NetEncode;
+use ferrumc_codec::network_types::varint::{encode_varints, VarInt, MAX_VARINT_LENGTH};
+use futures::executor::block_on;
+use nbt_lib::{NBTDeserializeBytes, NBTSerialize};
+
+/// One small, one that takes a couple of bytes and the longest there is.
+const VARINTS: [i32; 3] = [1, 300, -1];
+const ENTITIES: usize = 1000;
+
+/// A generated chunk in the network format, the same as players get sent.
+fn generate_chunk() -> Chunk {
+    let mut chunk = MultiNoiseGenerator::new(0)
+        .generate_chunk(0, 0, Dimension::Overworld.name())
+        .unwrap();
+    chunk.convert_to_net_mode().unwrap();
+    chunk
+}
+
+fn bench_varint(c: &mut Criterion) {
+    let mut group = c.benchmark_group("varint");
+    let encoded: Vec<Vec<u8>> = VARINTS
+        .iter()
+        .map(|&value| {
+            let mut bytes = Vec::new();
+            block_on(VarInt::new(value).write(&mut bytes)).unwrap();
+            bytes
+        })
+        .collect();
+
+    group.bench_function("encode", |b| {
+        let mut bytes = Vec::with_capacity(16);
+        b.iter(|| {
+            for value in VARINTS {
+                bytes.clear();
+                block_on(VarInt::new(black_box(value)).write(&mut bytes)).unwrap();
+            }
+        })
+    });
+    group.bench_function("decode", |b| {
+        b.iter(|| {
+            for bytes in &encoded {
+                black_box(block_on(VarInt::read(&mut Cursor::new(black_box(bytes)))).unwrap());
+            }
+        })
+    });
+    group.bench_function("encode_batch", |b| {
+        let values: Vec<i32> = (0..4096).map(|i| i * 31).collect();
+        let mut bytes = Vec::with_capacity(values.len() * MAX_VARINT_LENGTH);
+        b.iter(|| {
+            bytes.clear();
+            encode_varints(black_box(&values).iter().copied(), &mut bytes);
+        })
+    });
+    group.finish();
+}
+
+fn bench_chunk_packet(c: &mut Criterion) {
+    let chunk = generate_chunk();
+    c.bench_function("chunk_packet_encode", |b| {
+        let mut bytes = Vec::new();
+        b.iter(|| {
+            bytes.clear();
+            block_on(async {
+                let packet = ChunkDataAndUpdateLight::from_chunk(
+                    black_box(&chunk),
+                    0,
+                    0,
+                    Dimension::Overworld,
+                    &NATIVE_PROTOCOL,
+                )
+                .await
+                .unwrap();
+                packet.net_encode(&mut bytes).await.unwrap();
+            })
+        })
+    });
+}
+
+fn bench_nbt(c: &mut Criterion) {
+    let mut nbt = Vec::new();
+    generate_chunk().nbt_serialize(&mut nbt).unwrap();
+    c.bench_function("nbt_parse_chunk", |b| {
+        b.iter_batched(
+            || Cursor::new(nbt.clone()),
+            |mut cursor| black_box(Chunk::read_from_bytes(&mut cursor).unwrap()),
+            BatchSize::SmallInput,
+        )
+    });
+}
+
+fn bench_components(c: &mut Criterion) {
+    let mut group = c.benchmark_group("components");
+    let storage = ComponentStorage::new();
+    for entity in 0..ENTITIES {
+        storage.insert(entity, Position::new(entity as i32, 64, 0));
+    }
+
+    group.bench_function("get", |b| {
+        b.iter(|| {
+            block_on(async {
+                for entity in 0..ENTITIES {
+                    black_box(storage.get::<Position>(entity).await.unwrap().x);
+                }
+            })
+        })
+    });
+    group.bench_function("get_mut", |b| {
+        b.iter(|| {
+            block_on(async {
+                for entity in 0..ENTITIES {
+                    storage.get_mut::<Position>(entity).await.unwrap().y += 1;
+                }
+            })
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_varint,
+    bench_chunk_packet,
+    bench_nbt,
+    bench_components
+);
+criterion_main!(benches);
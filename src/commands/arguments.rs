@@ -0,0 +1,253 @@
+use ferrumc_codec::network_types::varint::VarInt;
+
+use crate::world::dimension::Dimension;
+use crate::world::mobs::MobType;
+
+/// An argument a command takes, e.g. the `<target>` in `/tp <target>`.
+#[derive(Debug, Clone)]
+pub struct Argument {
+    pub name: String,
+    pub parser: ArgumentParser,
+}
+
+impl Argument {
+    pub fn new(name: &str, parser: ArgumentParser) -> Self {
+        Self {
+            name: name.to_string(),
+            parser,
+        }
+    }
+
+    /// A word that has to be typed as is, e.g. the `add` in `/whitelist add <player>`.
+    pub fn literal(name: &str) -> Self {
+        Self::new(name, ArgumentParser::Literal)
+    }
+
+    pub fn is_literal(&self) -> bool {
+        self.parser == ArgumentParser::Literal
+    }
+
+    /// Checks the words make a valid value for this argument.
+    pub fn accepts(&self, words: &[&str]) -> bool {
+        match self.parser {
+            ArgumentParser::Literal => words == [self.name.as_str()],
+            parser => parser.accepts(words),
+        }
+    }
+}
+
+/// How an argument is parsed, both by the client while typing and by us when the command comes in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArgumentParser {
+    /// Not really a parser, the argument's name has to be typed as is. See [Argument::literal].
+    Literal,
+    Bool,
+    Integer,
+    Double,
+    /// A single word.
+    Word,
+    /// Everything up to the end of the command.
+    GreedyString,
+    /// The name of a single online player.
+    Player,
+    /// Three coordinates, each of which can be relative (`~`).
+    Vec3,
+    GameMode,
+    /// A dimension, e.g. `minecraft:the_nether`. The client suggests the ones from Login (play).
+    Dimension,
+    /// A kind of mob from [MOB_TYPES](crate::world::mobs::MOB_TYPES), e.g. `minecraft:pig`.
+    EntityType,
+    /// A sound, e.g. `minecraft:entity.cat.ambient`. The server suggests the ones in the sound
+    /// registry, but any name is sent on for resource packs.
+    Sound,
+    /// A particle from the particle registry, e.g. `minecraft:flame`.
+    Particle,
+}
+
+impl ArgumentParser {
+    /// The id of the parser in the `minecraft:command_argument_type` registry. Literals don't have one.
+    pub fn id(&self) -> Option<VarInt> {
+        let id = match self {
+            ArgumentParser::Literal => return None,
+            ArgumentParser::Bool => 0,
+            ArgumentParser::Double => 2,
+            ArgumentParser::Integer => 3,
+            ArgumentParser::Word | ArgumentParser::GreedyString => 5,
+            ArgumentParser::Player => 6,
+            ArgumentParser::Vec3 => 10,
+            ArgumentParser::EntityType | ArgumentParser::Sound | ArgumentParser::Particle => 33,
+            ArgumentParser::Dimension => 38,
+            ArgumentParser::GameMode => 39,
+        };
+        Some(VarInt::new(id))
+    }
+
+    /// The parser's properties as they're encoded in the Commands packet.
+    pub fn properties(&self) -> Vec<u8> {
+        match self {
+            // No min or max
+            ArgumentParser::Integer | ArgumentParser::Double => vec![0],
+            // SINGLE_WORD
+            ArgumentParser::Word => vec![0],
+            // GREEDY_PHRASE
+            ArgumentParser::GreedyString => vec![2],
+            // Only one entity, and it has to be a player
+            ArgumentParser::Player => vec![0x01 | 0x02],
+            _ => vec![],
+        }
+    }
+
+    /// Where the client gets completions for the argument from, if not from the parser itself.
+    pub fn suggestions_type(&self) -> Option<&'static str> {
+        match self {
+            ArgumentParser::Player
+            | ArgumentParser::Vec3
+            | ArgumentParser::EntityType
+            | ArgumentParser::Sound
+            | ArgumentParser::Particle => Some("minecraft:ask_server"),
+            _ => None,
+        }
+    }
+
+    /// How many space separated words the argument takes up. `None` means all the remaining ones.
+    pub fn word_count(&self) -> Option<usize> {
+        match self {
+            ArgumentParser::GreedyString => None,
+            ArgumentParser::Vec3 => Some(3),
+            _ => Some(1),
+        }
+    }
+
+    /// Checks the words make a valid value for this parser.
+    pub fn accepts(&self, words: &[&str]) -> bool {
+        match self {
+            ArgumentParser::Bool => matches!(words, ["true"] | ["false"]),
+            ArgumentParser::Integer => words[0].parse::<i32>().is_ok(),
+            ArgumentParser::Double => words[0].parse::<f64>().is_ok(),
+            ArgumentParser::Literal | ArgumentParser::Word | ArgumentParser::Player => true,
+            ArgumentParser::GreedyString => !words.is_empty(),
+            ArgumentParser::Vec3 => words.iter().all(|word| parse_coordinate(word, 0.0).is_some()),
+            ArgumentParser::GameMode => parse_game_mode(words[0]).is_some(),
+            ArgumentParser::Dimension => Dimension::from_name(words[0]).is_some(),
+            ArgumentParser::EntityType => MobType::from_name(words[0]).is_some(),
+            // Checked against the registries by the commands
+            ArgumentParser::Sound | ArgumentParser::Particle => true,
+        }
+    }
+}
+
+/// Matches the words of a command against one of its usages, returning the value of each argument.
+///
+/// Every argument has to be present, and there can't be any words left over.
+pub fn match_usage(usage: &[Argument], words: &[&str]) -> Option<Vec<(String, String)>> {
+    let mut values = Vec::with_capacity(usage.len());
+    let mut rest = words;
+
+    for argument in usage {
+        let count = argument.parser.word_count().unwrap_or(rest.len());
+        if count > rest.len() {
+            return None;
+        }
+
+        let (taken, remaining) = rest.split_at(count);
+        if !argument.accepts(taken) {
+            return None;
+        }
+
+        values.push((argument.name.clone(), taken.join(" ")));
+        rest = remaining;
+    }
+
+    rest.is_empty().then_some(values)
+}
+
+/// Parses a single coordinate. `~` and `~<offset>` are relative to `base`.
+pub fn parse_coordinate(word: &str, base: f64) -> Option<f64> {
+    match word.strip_prefix('~') {
+        Some("") => Some(base),
+        Some(offset) => offset.parse::<f64>().ok().map(|offset| base + offset),
+        None => word.parse::<f64>().ok(),
+    }
+    .filter(|coordinate| coordinate.is_finite())
+}
+
+/// Parses the name of a game mode into its id.
+pub fn parse_game_mode(name: &str) -> Option<u8> {
+    match name {
+        "survival" => Some(0),
+        "creative" => Some(1),
+        "adventure" => Some(2),
+        "spectator" => Some(3),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_match_usage() {
+        let usage = vec![
+            Argument::new("target", ArgumentParser::Player),
+            Argument::new("location", ArgumentParser::Vec3),
+        ];
+
+        assert_eq!(
+            match_usage(&usage, &["Steve", "1", "~", "~-2.5"]),
+            Some(vec![
+                ("target".to_string(), "Steve".to_string()),
+                ("location".to_string(), "1 ~ ~-2.5".to_string()),
+            ])
+        );
+        assert_eq!(match_usage(&usage, &["Steve", "1", "2"]), None);
+        assert_eq!(match_usage(&usage, &["Steve", "1", "2", "3", "4"]), None);
+        assert_eq!(match_usage(&usage, &["Steve", "1", "two", "3"]), None);
+        assert_eq!(match_usage(&[], &[]), Some(vec![]));
+
+        let usage = vec![Argument::new("dimension", ArgumentParser::Dimension)];
+        assert!(match_usage(&usage, &["minecraft:the_end"]).is_some());
+        assert!(match_usage(&usage, &["minecraft:moon"]).is_none());
+
+        let usage = vec![Argument::new("entity", ArgumentParser::EntityType)];
+        assert!(match_usage(&usage, &["minecraft:zombie"]).is_some());
+        assert!(match_usage(&usage, &["minecraft:wither"]).is_none());
+    }
+
+    #[test]
+    fn test_match_literal_usage() {
+        let usage = vec![
+            Argument::literal("add"),
+            Argument::new("player", ArgumentParser::Word),
+        ];
+
+        assert_eq!(
+            match_usage(&usage, &["add", "Steve"]),
+            Some(vec![
+                ("add".to_string(), "add".to_string()),
+                ("player".to_string(), "Steve".to_string()),
+            ])
+        );
+        assert_eq!(match_usage(&usage, &["remove", "Steve"]), None);
+    }
+
+    #[test]
+    fn test_match_greedy_usage() {
+        let usage = vec![Argument::new("message", ArgumentParser::GreedyString)];
+
+        assert_eq!(
+            match_usage(&usage, &["hello", "there"]),
+            Some(vec![("message".to_string(), "hello there".to_string())])
+        );
+        assert_eq!(match_usage(&usage, &[]), None);
+    }
+
+    #[test]
+    fn test_parse_coordinate() {
+        assert_eq!(parse_coordinate("12.5", 3.0), Some(12.5));
+        assert_eq!(parse_coordinate("~", 3.0), Some(3.0));
+        assert_eq!(parse_coordinate("~-1", 3.0), Some(2.0));
+        assert_eq!(parse_coordinate("~~", 3.0), None);
+        assert_eq!(parse_coordinate("NaN", 3.0), None);
+    }
+}
@@ -0,0 +1,173 @@
+use std::net::IpAddr;
+
+use tracing::info;
+use uuid::Uuid;
+
+use crate::access::bans::{parse_duration, unix_now, BanDetails};
+use crate::access::ops::levels;
+use crate::commands::arguments::{Argument, ArgumentParser};
+use crate::commands::{Command, CommandContext, CommandRegistry};
+use crate::utils::components::player::Player;
+use crate::utils::prelude::*;
+use crate::utils::text_component::TextComponent;
+
+const DEFAULT_REASON: &str = "Banned by an operator.";
+
+pub(super) fn register(registry: &CommandRegistry) {
+    let player = Argument::new("player", ArgumentParser::Word);
+    let reason = Argument::new("reason", ArgumentParser::GreedyString);
+
+    registry.register_command(
+        Command::new("ban", ban)
+            .usage(vec![player.clone()])
+            .usage(vec![player.clone(), reason.clone()])
+            .permission(levels::ADMIN),
+    );
+
+    let duration = Argument::new("duration", ArgumentParser::Word);
+    registry.register_command(
+        Command::new("tempban", ban)
+            .usage(vec![player.clone(), duration.clone()])
+            .usage(vec![player.clone(), duration, reason.clone()])
+            .permission(levels::ADMIN),
+    );
+
+    let target = Argument::new("target", ArgumentParser::Word);
+    registry.register_command(
+        Command::new("ban-ip", ban_ip)
+            .usage(vec![target.clone()])
+            .usage(vec![target.clone(), reason])
+            .permission(levels::ADMIN),
+    );
+
+    registry.register_command(
+        Command::new("pardon", pardon)
+            .usage(vec![player])
+            .permission(levels::ADMIN),
+    );
+    registry.register_command(
+        Command::new("pardon-ip", pardon_ip)
+            .usage(vec![target])
+            .permission(levels::ADMIN),
+    );
+}
+
+/// `/ban` and `/tempban`, which only differ by the duration.
+async fn ban(ctx: CommandContext) -> Result<()> {
+    let name = ctx.argument("player").unwrap_or_default();
+    let duration = match ctx.argument("duration") {
+        Some(duration) => match parse_duration(duration) {
+            Some(duration) => Some(duration),
+            None => return ctx.reply(&format!("Invalid duration: {}", duration)).await,
+        },
+        None => None,
+    };
+    let source = source(&ctx).await;
+    let details = BanDetails::new(
+        &source,
+        duration,
+        ctx.argument("reason").unwrap_or(DEFAULT_REASON),
+    );
+
+    // Online players are banned by uuid, anyone else by name until they next try to join
+    let online = ctx.find_player(name).await;
+    let (uuid, name) = match online {
+        Some(entity) => {
+            let player = ctx.state.world.get_component::<Player>(entity).await?;
+            (Some(Uuid::from_u128(player.uuid)), player.username.clone())
+        }
+        None => (None, name.to_string()),
+    };
+
+    let message = details.kick_message(unix_now());
+    ctx.state.bans.ban_player(uuid, &name, details).await?;
+    info!("{} banned {}", source, name);
+
+    if let Some(entity) = online {
+        kick(&ctx, entity, &message).await?;
+    }
+    ctx.reply(&format!("Banned {}", name)).await
+}
+
+async fn ban_ip(ctx: CommandContext) -> Result<()> {
+    let target = ctx.argument("target").unwrap_or_default();
+
+    let ip = match target.parse::<IpAddr>() {
+        Ok(ip) => ip,
+        Err(_) => match player_address(&ctx, target).await {
+            Some(ip) => ip,
+            None => return ctx.reply("Invalid IP address or unknown player").await,
+        },
+    };
+    let source = source(&ctx).await;
+    let details = BanDetails::new(
+        &source,
+        None,
+        ctx.argument("reason").unwrap_or(DEFAULT_REASON),
+    );
+
+    let message = details.kick_message(unix_now());
+    ctx.state.bans.ban_ip(ip, details).await?;
+    info!("{} banned the IP {}", source, ip);
+
+    // Everyone connected from the address goes, not just the player that was named
+    let connections = ctx
+        .state
+        .connections
+        .connections
+        .iter()
+        .map(|conn| (*conn.key(), conn.value().clone()))
+        .collect::<Vec<_>>();
+    for (id, conn) in connections {
+        let address = conn.read().await.metadata.address;
+        if address.is_some_and(|address| address.ip() == ip) {
+            kick(&ctx, id, &message).await?;
+        }
+    }
+
+    ctx.reply(&format!("Banned IP {}", ip)).await
+}
+
+async fn pardon(ctx: CommandContext) -> Result<()> {
+    let name = ctx.argument("player").unwrap_or_default();
+
+    match ctx.state.bans.pardon_player(name).await? {
+        true => ctx.reply(&format!("Unbanned {}", name)).await,
+        false => ctx.reply("Nothing changed. The player isn't banned").await,
+    }
+}
+
+async fn pardon_ip(ctx: CommandContext) -> Result<()> {
+    let target = ctx.argument("target").unwrap_or_default();
+    let Ok(ip) = target.parse::<IpAddr>() else {
+        return ctx.reply(&format!("Invalid IP address: {}", target)).await;
+    };
+
+    match ctx.state.bans.pardon_ip(ip).await? {
+        true => ctx.reply(&format!("Unbanned IP {}", ip)).await,
+        false => ctx.reply("Nothing changed. That IP isn't banned").await,
+    }
+}
+
+/// Who the ban is recorded as coming from.
+async fn source(ctx: &CommandContext) -> String {
+    match ctx.state.world.get_component::<Player>(ctx.sender).await {
+        Ok(player) => player.username.clone(),
+        Err(_) => "Server".to_string(),
+    }
+}
+
+async fn player_address(ctx: &CommandContext, name: &str) -> Option<IpAddr> {
+    let entity = ctx.find_player(name).await?;
+    let conn = ctx.state.connections.connections.get(&entity)?.clone();
+    let address = conn.read().await.metadata.address;
+    address.map(|address| address.ip())
+}
+
+async fn kick(ctx: &CommandContext, entity: usize, message: &str) -> Result<()> {
+    let Some(conn) = ctx.state.connections.connections.get(&entity).map(|conn| conn.clone()) else {
+        return Ok(());
+    };
+
+    crate::net::kick(&conn, &TextComponent::text(message), ctx.state.clone()).await
+}
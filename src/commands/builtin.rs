@@ -0,0 +1,243 @@
+use crate::access::ops::levels;
+use crate::commands::arguments::{parse_coordinate, parse_game_mode, Argument, ArgumentParser};
+use crate::commands::{
+    bans, database, experience, game_rules, ops, plugins, profile, script, sounds, time, title,
+    weather, whitelist, Command, CommandContext, CommandRegistry,
+};
+use crate::events::config_events::reload_config;
+use crate::net::utils::movement::{change_dimension, teleport};
+use crate::shutdown;
+use crate::utils::components::dimension::dimension_of;
+use crate::utils::components::game_mode::set_game_mode;
+use crate::utils::components::player::Player;
+use crate::utils::components::rotation::Rotation;
+use crate::utils::encoding::position::Position;
+use crate::utils::prelude::*;
+use crate::world::blocks::surface_height;
+use crate::world::dimension::Dimension;
+use crate::world::mobs::{spawn_mob, MobType};
+
+pub(super) fn register_builtins(registry: &CommandRegistry) {
+    registry.register_command(Command::new("stop", stop).permission(levels::OWNER));
+    registry.register_command(Command::new("reload", reload).permission(levels::OWNER));
+
+    let location = Argument::new("location", ArgumentParser::Vec3);
+    let destination = Argument::new("destination", ArgumentParser::Player);
+    let targets = Argument::new("targets", ArgumentParser::Player);
+    registry.register_command(
+        Command::new("tp", tp)
+            .usage(vec![location.clone()])
+            .usage(vec![destination.clone()])
+            .usage(vec![targets.clone(), location])
+            .usage(vec![targets, destination])
+            .permission(levels::GAMEMASTER),
+    );
+
+    let gamemode = Argument::new("gamemode", ArgumentParser::GameMode);
+    registry.register_command(
+        Command::new("gamemode", game_mode)
+            .usage(vec![gamemode.clone()])
+            .usage(vec![gamemode, Argument::new("target", ArgumentParser::Player)])
+            .permission(levels::GAMEMASTER),
+    );
+
+    let dimension = Argument::new("dimension", ArgumentParser::Dimension);
+    registry.register_command(
+        Command::new("dimension", change_dimension_command)
+            .usage(vec![dimension.clone()])
+            .usage(vec![dimension, Argument::new("target", ArgumentParser::Player)])
+            .permission(levels::GAMEMASTER),
+    );
+
+    let entity = Argument::new("entity", ArgumentParser::EntityType);
+    registry.register_command(
+        Command::new("summon", summon)
+            .usage(vec![entity.clone()])
+            .usage(vec![entity, Argument::new("location", ArgumentParser::Vec3)])
+            .permission(levels::GAMEMASTER),
+    );
+
+    experience::register(registry);
+    game_rules::register(registry);
+    time::register(registry);
+    title::register(registry);
+    sounds::register(registry);
+    weather::register(registry);
+    whitelist::register(registry);
+    bans::register(registry);
+    ops::register(registry);
+    database::register(registry);
+    plugins::register(registry);
+    profile::register(registry);
+    script::register(registry);
+}
+
+async fn stop(ctx: CommandContext) -> Result<()> {
+    ctx.reply("Stopping the server").await?;
+    shutdown::request();
+    Ok(())
+}
+
+async fn reload(ctx: CommandContext) -> Result<()> {
+    match reload_config(&ctx.state).await {
+        Ok(()) => ctx.reply("Reloaded the config").await,
+        Err(e) => ctx.reply(&format!("Failed to reload the config: {}", e)).await,
+    }
+}
+
+async fn tp(ctx: CommandContext) -> Result<()> {
+    let Some(target) = player_argument(&ctx, "targets").await? else {
+        return Ok(());
+    };
+
+    let position = if ctx.argument("destination").is_some() {
+        let Some(destination) = player_argument(&ctx, "destination").await? else {
+            return Ok(());
+        };
+        let position = ctx.state.world.get_component::<Position>(destination).await?.clone();
+
+        // Going to a player in another dimension takes the target there too
+        let dimension = dimension_of(&ctx.state, destination).await;
+        if dimension != dimension_of(&ctx.state, target).await {
+            change_dimension(target, ctx.state.clone(), dimension, position.clone()).await?;
+            let name = username(&ctx, target).await?;
+            return ctx
+                .reply(&format!("Teleported {} to {} in the {}", name, position, dimension))
+                .await;
+        }
+        position
+    } else {
+        let location = ctx
+            .argument("location")
+            .ok_or_else(|| Error::Generic("Missing location".to_string()))?;
+        // Relative coordinates are from where the sender is, like vanilla
+        let base = ctx.state.world.get_component::<Position>(ctx.sender).await?.clone();
+        let Some(position) = parse_location(location, &base) else {
+            return ctx.reply(&format!("Invalid location: {}", location)).await;
+        };
+        position
+    };
+
+    teleport(target, ctx.state.clone(), position.clone()).await?;
+
+    let name = username(&ctx, target).await?;
+    ctx.reply(&format!("Teleported {} to {}", name, position)).await
+}
+
+async fn game_mode(ctx: CommandContext) -> Result<()> {
+    let name = ctx.argument("gamemode").unwrap_or_default();
+    let mode = parse_game_mode(name)
+        .ok_or_else(|| Error::Generic(format!("Unknown game mode: {}", name)))?;
+
+    let Some(target) = player_argument(&ctx, "target").await? else {
+        return Ok(());
+    };
+
+    set_game_mode(&ctx.state, target, mode).await?;
+
+    let player = username(&ctx, target).await?;
+    ctx.reply(&format!("Set {}'s game mode to {}", player, name)).await
+}
+
+/// Moves a player to the same x and z in another dimension, on top of the ground there.
+async fn change_dimension_command(ctx: CommandContext) -> Result<()> {
+    let name = ctx.argument("dimension").unwrap_or_default();
+    let dimension = Dimension::from_name(name)
+        .ok_or_else(|| Error::Generic(format!("Unknown dimension: {}", name)))?;
+
+    let Some(target) = player_argument(&ctx, "target").await? else {
+        return Ok(());
+    };
+    let player = username(&ctx, target).await?;
+    if dimension_of(&ctx.state, target).await == dimension {
+        return ctx.reply(&format!("{} is already in the {}", player, dimension)).await;
+    }
+
+    let Position { x, z, .. } = *ctx.state.world.get_component::<Position>(target).await?;
+    let y = surface_height(&ctx.state, x, z, dimension).await?;
+    change_dimension(target, ctx.state.clone(), dimension, Position::new(x, y as i16, z)).await?;
+
+    ctx.reply(&format!("Sent {} to the {}", player, dimension)).await
+}
+
+/// Spawns a mob where the sender is standing, or at the given location, facing the same way as
+/// them.
+async fn summon(ctx: CommandContext) -> Result<()> {
+    let name = ctx.argument("entity").unwrap_or_default();
+    let mob_type = MobType::from_name(name)
+        .ok_or_else(|| Error::Generic(format!("Unknown entity: {}", name)))?;
+
+    let base = ctx.state.world.get_component::<Position>(ctx.sender).await?.clone();
+    // Positions are whole blocks, so the sender is taken to be in the middle of theirs
+    let base = (base.x as f64 + 0.5, base.y as f64, base.z as f64 + 0.5);
+    let position = match ctx.argument("location") {
+        Some(location) => match parse_exact_location(location, base) {
+            Some(position) => position,
+            None => return ctx.reply(&format!("Invalid location: {}", location)).await,
+        },
+        None => base,
+    };
+    let yaw = ctx
+        .state
+        .world
+        .get_component::<Rotation>(ctx.sender)
+        .await
+        .map_or(0.0, |rotation| rotation.yaw);
+    let dimension = dimension_of(&ctx.state, ctx.sender).await;
+    spawn_mob(&ctx.state, mob_type, dimension, position, yaw).await;
+
+    ctx.reply(&format!("Summoned new {}", mob_type.name)).await
+}
+
+/// Resolves a player argument, defaulting to the sender if it wasn't given.
+///
+/// Tells the sender if nobody by that name is online, and returns `None`.
+pub(super) async fn player_argument(
+    ctx: &CommandContext,
+    argument: &str,
+) -> Result<Option<usize>> {
+    let Some(name) = ctx.argument(argument) else {
+        return Ok(Some(ctx.sender));
+    };
+
+    let player = ctx.find_player(name).await;
+    if player.is_none() {
+        ctx.reply(&format!("No player was found called {}", name)).await?;
+    }
+    Ok(player)
+}
+
+pub(super) async fn username(ctx: &CommandContext, entity_id: usize) -> Result<String> {
+    let player = ctx.state.world.get_component::<Player>(entity_id).await?;
+    Ok(player.get_username().to_string())
+}
+
+fn parse_location(location: &str, base: &Position) -> Option<Position> {
+    let base = (base.x as f64, base.y as f64, base.z as f64);
+    let (x, y, z) = parse_exact_location(location, base)?;
+    Some(Position::new(x.floor() as i32, y.floor() as i16, z.floor() as i32))
+}
+
+/// Like [parse_location], for things that don't have to be in the corner of a block.
+pub(super) fn parse_exact_location(location: &str, base: (f64, f64, f64)) -> Option<(f64, f64, f64)> {
+    let mut words = location.split_whitespace();
+    let mut next = |base: f64| parse_coordinate(words.next()?, base);
+    Some((next(base.0)?, next(base.1)?, next(base.2)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_location() {
+        let base = Position::new(10, 64, -10);
+
+        let position = parse_location("~ ~5 3.7", &base).unwrap();
+        assert_eq!((position.x, position.y, position.z), (10, 69, 3));
+        assert!(parse_location("~ ~", &base).is_none());
+
+        let position = parse_exact_location("~ ~1 -3.25", (10.5, 64.0, -9.5)).unwrap();
+        assert_eq!(position, (10.5, 65.0, -3.25));
+    }
+}
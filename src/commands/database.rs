@@ -0,0 +1,74 @@
+use crate::access::ops::levels;
+use crate::commands::arguments::Argument;
+use crate::commands::{Command, CommandContext, CommandRegistry};
+use crate::database::players::save_all_players;
+use crate::database::world_meta::save_world_meta;
+use crate::utils::prelude::*;
+
+pub(super) fn register(registry: &CommandRegistry) {
+    registry.register_command(
+        Command::new("db", database)
+            .usage(vec![Argument::literal("stats")])
+            .usage(vec![Argument::literal("compact")])
+            .permission(levels::OWNER),
+    );
+    registry.register_command(Command::new("save-all", save_all).permission(levels::OWNER));
+    registry.register_command(
+        Command::new("save-on", |ctx| set_autosave(ctx, true)).permission(levels::OWNER),
+    );
+    registry.register_command(
+        Command::new("save-off", |ctx| set_autosave(ctx, false)).permission(levels::OWNER),
+    );
+}
+
+async fn database(ctx: CommandContext) -> Result<()> {
+    let database = &ctx.state.database;
+    let subcommand = ctx.arguments.first().map(|(name, _)| name.as_str());
+
+    let message = match subcommand {
+        Some("stats") => {
+            let stats = database.stats().await?;
+            format!(
+                "{}\nChunk cache: {}\n{} chunks waiting to be saved",
+                stats,
+                database.cache_stats(),
+                database.dirty_chunks()
+            )
+        }
+        Some("compact") => {
+            ctx.reply("Compacting the database...").await?;
+            let (size, compacted) = database.compact().await?;
+            let stats = database.stats().await?;
+            format!(
+                "Compacted the database from {} KB to {} KB, which takes effect after a restart\n{}",
+                size / 1024,
+                compacted / 1024,
+                stats
+            )
+        }
+        _ => return Err(Error::Generic("Unknown db subcommand".to_string())),
+    };
+
+    ctx.reply(&message).await
+}
+
+/// Saves everything now, whether or not automatic saving is on.
+async fn save_all(ctx: CommandContext) -> Result<()> {
+    ctx.reply("Saving...").await?;
+    let chunks = ctx.state.database.flush().await?;
+    let players = save_all_players(&ctx.state).await?;
+    save_world_meta(&ctx.state).await?;
+    ctx.reply(&format!("Saved {} chunks and {} players", chunks, players))
+        .await
+}
+
+async fn set_autosave(ctx: CommandContext, enabled: bool) -> Result<()> {
+    let was_enabled = ctx.state.database.set_autosave(enabled);
+    let state = if enabled { "enabled" } else { "disabled" };
+    let message = if was_enabled == enabled {
+        format!("Automatic saving is already {}", state)
+    } else {
+        format!("Automatic saving is now {}", state)
+    };
+    ctx.reply(&message).await
+}
@@ -0,0 +1,95 @@
+use crate::access::ops::levels;
+use crate::commands::arguments::{Argument, ArgumentParser};
+use crate::commands::builtin::{player_argument, username};
+use crate::commands::{Command, CommandContext, CommandRegistry};
+use crate::utils::components::experience::{send_experience, Experience};
+use crate::utils::prelude::*;
+
+const TOO_MANY_POINTS: &str =
+    "Unable to set experience points above the maximum points for the player's current level";
+
+pub(super) fn register(registry: &CommandRegistry) {
+    let targets = Argument::new("targets", ArgumentParser::Player);
+    let amount = Argument::new("amount", ArgumentParser::Integer);
+
+    // Same as vanilla, where /xp is short for /experience
+    for name in ["experience", "xp"] {
+        let mut command = Command::new(name, experience);
+        for subcommand in ["add", "set"] {
+            let usage = vec![Argument::literal(subcommand), targets.clone(), amount.clone()];
+            command = command.usage(usage.clone());
+            for unit in ["points", "levels"] {
+                command = command.usage([usage.clone(), vec![Argument::literal(unit)]].concat());
+            }
+        }
+        for unit in ["points", "levels"] {
+            let usage = vec![Argument::literal("query"), targets.clone(), Argument::literal(unit)];
+            command = command.usage(usage);
+        }
+        registry.register_command(command.permission(levels::GAMEMASTER));
+    }
+}
+
+async fn experience(ctx: CommandContext) -> Result<()> {
+    let subcommand = ctx.arguments.first().map(|(name, _)| name.clone());
+    // Points are the default
+    let in_levels = ctx.argument("levels").is_some();
+    let unit = if in_levels { "levels" } else { "points" };
+    let Some(target) = player_argument(&ctx, "targets").await? else {
+        return Ok(());
+    };
+    let player = username(&ctx, target).await?;
+
+    if subcommand.as_deref() == Some("query") {
+        let experience = ctx
+            .state
+            .world
+            .get_component::<Experience>(target)
+            .await
+            .map(|experience| *experience)
+            .unwrap_or_default();
+        let amount = if in_levels {
+            experience.level
+        } else {
+            experience.points
+        };
+        return ctx
+            .reply(&format!("{} has {} experience {}", player, amount, unit))
+            .await;
+    }
+
+    let amount = ctx.argument("amount").unwrap_or_default();
+    let amount = amount
+        .parse::<i32>()
+        .map_err(|_| Error::Generic(format!("Invalid amount: {}", amount)))?;
+    let message = {
+        let mut experience = ctx
+            .state
+            .world
+            .get_component_storage()
+            .get_mut_or_insert_with::<Experience>(target, Default::default)
+            .await;
+        match (subcommand.as_deref(), in_levels) {
+            (Some("add"), false) => {
+                experience.add_points(amount);
+                format!("Gave {} experience points to {}", amount, player)
+            }
+            (Some("add"), true) => {
+                experience.add_levels(amount);
+                format!("Gave {} experience levels to {}", amount, player)
+            }
+            (Some("set"), false) => match experience.set_points(amount) {
+                true => format!("Set {} experience points on {}", amount, player),
+                false => TOO_MANY_POINTS.to_string(),
+            },
+            (Some("set"), true) => {
+                experience.set_levels(amount);
+                format!("Set {} experience levels on {}", amount, player)
+            }
+            _ => return Err(Error::Generic("Unknown experience subcommand".to_string())),
+        }
+    };
+
+    send_experience(&ctx.state, target).await?;
+    ctx.reply(&message).await
+}
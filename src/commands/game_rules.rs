@@ -0,0 +1,55 @@
+use crate::access::ops::levels;
+use crate::commands::arguments::{Argument, ArgumentParser};
+use crate::commands::{Command, CommandContext, CommandRegistry};
+use crate::database::world_meta::WorldMeta;
+use crate::net::packets::outgoing::update_time::UpdateTime;
+use crate::net::utils::broadcast::broadcast_packet;
+use crate::utils::prelude::*;
+use crate::world::game_rules::{self, GameRule, GAME_RULES};
+
+pub(super) fn register(registry: &CommandRegistry) {
+    let mut command = Command::new("gamerule", game_rule);
+    for rule in GAME_RULES {
+        let parser = match rule.is_bool() {
+            true => ArgumentParser::Bool,
+            false => ArgumentParser::Integer,
+        };
+        command = command
+            .usage(vec![Argument::literal(rule.name)])
+            .usage(vec![Argument::literal(rule.name), Argument::new("value", parser)]);
+    }
+    registry.register_command(command.permission(levels::GAMEMASTER));
+}
+
+async fn game_rule(ctx: CommandContext) -> Result<()> {
+    let name = ctx.arguments.first().map(|(name, _)| name.as_str());
+    let Some(rule) = name.and_then(GameRule::get) else {
+        return Err(Error::Generic("Unknown gamerule".to_string()));
+    };
+
+    let Some(value) = ctx.argument("value") else {
+        let value = ctx
+            .state
+            .world
+            .resource::<WorldMeta>()
+            .await?
+            .game_rule(rule);
+        let message = format!("Gamerule {} is currently set to: {}", rule.name, value);
+        return ctx.reply(&message).await;
+    };
+
+    let (value, time) = {
+        let mut world_meta = ctx.state.world.resource_mut::<WorldMeta>().await?;
+        let value = world_meta
+            .set_game_rule(rule, value)
+            .ok_or_else(|| Error::Generic(format!("Invalid value for {}: {}", rule.name, value)))?;
+        (value, UpdateTime::new(&world_meta))
+    };
+    // Clients stop or start their sun straight away, rather than at the next time update
+    if *rule == game_rules::DO_DAYLIGHT_CYCLE {
+        broadcast_packet(time, &ctx.state).await?;
+    }
+
+    ctx.reply(&format!("Gamerule {} is now set to: {}", rule.name, value))
+        .await
+}
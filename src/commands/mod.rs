@@ -0,0 +1,308 @@
+use std::any::Any;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use dashmap::DashMap;
+use ferrumc_codec::network_types::varint::VarInt;
+use tracing::warn;
+
+use crate::access::ops::levels;
+use crate::commands::arguments::{match_usage, Argument, ArgumentParser};
+use crate::net::packets::outgoing::commands::Commands;
+use crate::net::packets::outgoing::entity_event::EntityEvent;
+use crate::net::packets::outgoing::system_chat_message::SystemChatMessage;
+use crate::state::GlobalState;
+use crate::utils::components::player::Player;
+use crate::utils::prelude::*;
+
+pub mod arguments;
+mod bans;
+mod builtin;
+mod database;
+mod experience;
+mod game_rules;
+mod ops;
+mod plugins;
+mod profile;
+mod script;
+mod sounds;
+pub mod suggestions;
+mod time;
+mod title;
+pub mod tree;
+mod weather;
+mod whitelist;
+
+pub type CommandFuture = Pin<Box<dyn Future<Output = Result<()>> + Send>>;
+pub type CommandHandler = Arc<dyn Fn(CommandContext) -> CommandFuture + Send + Sync>;
+
+const NO_ARGUMENTS: &[Vec<Argument>] = &[Vec::new()];
+
+/// A command players can run, e.g. `/tp`.
+pub struct Command {
+    pub name: String,
+    /// The different sets of arguments the command can be run with.
+    /// Without any usages, the command doesn't take arguments.
+    pub usages: Vec<Vec<Argument>>,
+    /// The permission level needed to run the command. See [levels].
+    pub permission: u8,
+    handler: CommandHandler,
+    /// Kept until the command is dropped, which is only once it's finished running. Plugins keep
+    /// their library loaded with it, since the handler's code lives there.
+    _keep_alive: Option<Arc<dyn Any + Send + Sync>>,
+}
+
+impl Command {
+    pub fn new<F, Fut>(name: &str, handler: F) -> Self
+    where
+        F: Fn(CommandContext) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        Self {
+            name: name.to_string(),
+            usages: Vec::new(),
+            permission: levels::ALL,
+            handler: Arc::new(move |ctx| Box::pin(handler(ctx))),
+            _keep_alive: None,
+        }
+    }
+
+    /// Adds a set of arguments the command can be run with.
+    pub fn usage(mut self, arguments: Vec<Argument>) -> Self {
+        self.usages.push(arguments);
+        self
+    }
+
+    /// Only lets players with at least this permission level run the command.
+    pub fn permission(mut self, level: u8) -> Self {
+        self.permission = level;
+        self
+    }
+
+    /// Keeps `value` around for as long as the command is, e.g. the library its handler is from.
+    pub(crate) fn keep_alive(mut self, value: Arc<dyn Any + Send + Sync>) -> Self {
+        self._keep_alive = Some(value);
+        self
+    }
+
+    pub fn usages(&self) -> &[Vec<Argument>] {
+        if self.usages.is_empty() {
+            NO_ARGUMENTS
+        } else {
+            &self.usages
+        }
+    }
+
+    /// How the command is used, e.g. `/gamemode <gamemode> <target>`, one line per usage.
+    pub fn usage_text(&self) -> String {
+        self.usages()
+            .iter()
+            .map(|usage| {
+                std::iter::once(format!("/{}", self.name))
+                    .chain(usage.iter().map(|argument| match argument.is_literal() {
+                        true => argument.name.clone(),
+                        false => format!("<{}>", argument.name),
+                    }))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// Everything a command handler gets to work with.
+pub struct CommandContext {
+    /// The entity id of the player who ran the command.
+    pub sender: usize,
+    /// The value of each argument in the usage that matched, by name.
+    pub arguments: Vec<(String, String)>,
+    pub state: GlobalState,
+}
+
+impl CommandContext {
+    pub fn argument(&self, name: &str) -> Option<&str> {
+        self.arguments
+            .iter()
+            .find(|(argument, _)| argument == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Sends a message to the player who ran the command.
+    pub async fn reply(&self, message: &str) -> Result<()> {
+        send_message(self.sender, &self.state, message).await
+    }
+
+    /// Finds an online player by name. `@s` is the player who ran the command.
+    pub async fn find_player(&self, name: &str) -> Option<usize> {
+        if name == "@s" {
+            return Some(self.sender);
+        }
+
+        let query = self.state.world.query::<&Player>();
+        let found = query
+            .iter()
+            .await
+            .find(|(_, player)| player.username.eq_ignore_ascii_case(name))
+            .map(|(id, _)| id);
+        found
+    }
+}
+
+/// All the commands on the server. Dispatches incoming commands to their handlers, and builds the
+/// command tree the client uses for validation and completion.
+pub struct CommandRegistry {
+    commands: DashMap<String, Arc<Command>>,
+}
+
+impl CommandRegistry {
+    /// Creates a registry with the built-in commands already registered.
+    pub fn new() -> Self {
+        let registry = Self {
+            commands: DashMap::new(),
+        };
+        builtin::register_builtins(&registry);
+        registry
+    }
+
+    /// Registers a command that takes any arguments. The handler gets them as a single `args` argument.
+    ///
+    /// Use [CommandRegistry::register_command] to declare the arguments, so the client can check them.
+    pub fn register<F, Fut>(&self, name: &str, handler: F)
+    where
+        F: Fn(CommandContext) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        let command = Command::new(name, handler)
+            .usage(vec![])
+            .usage(vec![Argument::new("args", ArgumentParser::GreedyString)]);
+        self.register_command(command);
+    }
+
+    /// Registers a command, replacing any existing one with the same name.
+    pub fn register_command(&self, command: Command) {
+        self.commands.insert(command.name.clone(), Arc::new(command));
+    }
+
+    /// Removes a command, returning whether there was one.
+    pub fn unregister(&self, name: &str) -> bool {
+        self.commands.remove(name).is_some()
+    }
+
+    pub fn get(&self, name: &str) -> Option<Arc<Command>> {
+        self.commands.get(name).map(|command| Arc::clone(&command))
+    }
+
+    /// The Commands packet for the commands a player with the given permission level can run.
+    pub fn declare_commands(&self, permission_level: u8) -> Commands {
+        let mut commands = self
+            .commands
+            .iter()
+            .filter(|command| command.permission <= permission_level)
+            .map(|command| Arc::clone(&command))
+            .collect::<Vec<_>>();
+        commands.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let nodes = tree::build_tree(&commands);
+        Commands::new_auto(VarInt::new(nodes.len() as i32), nodes, VarInt::new(0))
+    }
+
+    /// Runs a command for a player. `input` is the command without the leading slash.
+    ///
+    /// Unknown commands and bad arguments are reported back to the player rather than returned as errors.
+    pub async fn dispatch(&self, input: &str, sender: usize, state: GlobalState) -> Result<()> {
+        let words = input.split_whitespace().collect::<Vec<_>>();
+        let Some((name, words)) = words.split_first() else {
+            return Ok(());
+        };
+
+        let Some(command) = self.get(name) else {
+            return send_message(sender, &state, &format!("Unknown command: /{}", name)).await;
+        };
+        if !state.has_permission(sender, command.permission).await {
+            let message = "You do not have permission to use this command";
+            return send_message(sender, &state, message).await;
+        }
+
+        let Some(arguments) = command
+            .usages()
+            .iter()
+            .find_map(|usage| match_usage(usage, words))
+        else {
+            let message = format!("Usage:\n{}", command.usage_text());
+            return send_message(sender, &state, &message).await;
+        };
+
+        let ctx = CommandContext {
+            sender,
+            arguments,
+            state: state.clone(),
+        };
+        if let Err(e) = (command.handler)(ctx).await {
+            warn!("Failed to run /{} for {}: {}", input, sender, e);
+            send_message(sender, &state, "An error occurred while running the command").await?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for CommandRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+async fn send_message(entity_id: usize, state: &GlobalState, message: &str) -> Result<()> {
+    let conn = state.connections.get_connection(entity_id)?;
+    let conn = conn.read().await;
+    conn.send_packet(SystemChatMessage::text(message)).await
+}
+
+/// Tells a player about a change to their permission level, and which commands they can run now.
+pub async fn send_permissions(entity_id: usize, state: &GlobalState) -> Result<()> {
+    let level = state.permission_level(entity_id).await;
+    let conn = state.connections.get_connection(entity_id)?;
+    let conn = conn.read().await;
+    conn.send_packet(EntityEvent::op_permission_level(entity_id as i32, level))
+        .await?;
+    conn.send_packet(state.commands.declare_commands(level)).await
+}
+
+/// Sends every player the commands they can run now, after some have been added or removed.
+pub async fn resend_commands(state: &GlobalState) -> Result<()> {
+    let query = state.world.query::<&Player>();
+    let players = query.iter().await.map(|(id, _)| id).collect::<Vec<_>>();
+    for entity_id in players {
+        let level = state.permission_level(entity_id).await;
+        let conn = state.connections.get_connection(entity_id)?;
+        let conn = conn.read().await;
+        conn.send_packet(state.commands.declare_commands(level)).await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn command_names(commands: &Commands) -> Vec<String> {
+        commands.nodes.iter().filter_map(|node| node.name.clone()).collect()
+    }
+
+    #[test]
+    fn test_declare_commands_by_permission() {
+        let registry = CommandRegistry::new();
+        registry.register("hello", |_| async { Ok(()) });
+
+        let everyone = command_names(&registry.declare_commands(levels::ALL));
+        assert!(everyone.contains(&"hello".to_string()));
+        assert!(!everyone.contains(&"tp".to_string()));
+
+        let owner = command_names(&registry.declare_commands(levels::OWNER));
+        assert!(owner.contains(&"tp".to_string()));
+        assert!(owner.contains(&"stop".to_string()));
+        assert!(owner.contains(&"hello".to_string()));
+    }
+}
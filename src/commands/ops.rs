@@ -0,0 +1,63 @@
+use tracing::info;
+use uuid::Uuid;
+
+use crate::access::ops::levels;
+use crate::commands::arguments::{Argument, ArgumentParser};
+use crate::commands::{send_permissions, Command, CommandContext, CommandRegistry};
+use crate::utils::components::player::Player;
+use crate::utils::config::get_global_config;
+use crate::utils::prelude::*;
+
+pub(super) fn register(registry: &CommandRegistry) {
+    let player = Argument::new("player", ArgumentParser::Word);
+    registry.register_command(
+        Command::new("op", op)
+            .usage(vec![player.clone()])
+            .permission(levels::ADMIN),
+    );
+    registry.register_command(
+        Command::new("deop", deop)
+            .usage(vec![player])
+            .permission(levels::ADMIN),
+    );
+}
+
+async fn op(ctx: CommandContext) -> Result<()> {
+    let name = ctx.argument("player").unwrap_or_default();
+    let level = get_global_config().op_permission_level;
+
+    // Online players are opped by uuid, anyone else by name
+    let online = ctx.find_player(name).await;
+    let (uuid, name) = match online {
+        Some(entity) => {
+            let player = ctx.state.world.get_component::<Player>(entity).await?;
+            (Some(Uuid::from_u128(player.uuid)), player.username.clone())
+        }
+        None => (None, name.to_string()),
+    };
+
+    if ctx.state.ops.names().iter().any(|op| op.eq_ignore_ascii_case(&name)) {
+        return ctx.reply("Nothing changed. The player already is an operator").await;
+    }
+    ctx.state.ops.op(&name, uuid, level).await?;
+    info!("Made {} a server operator", name);
+
+    if let Some(entity) = online {
+        send_permissions(entity, &ctx.state).await?;
+    }
+    ctx.reply(&format!("Made {} a server operator", name)).await
+}
+
+async fn deop(ctx: CommandContext) -> Result<()> {
+    let name = ctx.argument("player").unwrap_or_default();
+
+    if !ctx.state.ops.deop(name).await? {
+        return ctx.reply("Nothing changed. The player is not an operator").await;
+    }
+    info!("Made {} no longer a server operator", name);
+
+    if let Some(entity) = ctx.find_player(name).await {
+        send_permissions(entity, &ctx.state).await?;
+    }
+    ctx.reply(&format!("Made {} no longer a server operator", name)).await
+}
@@ -0,0 +1,74 @@
+use std::env::consts::DLL_EXTENSION;
+use std::path::Path;
+
+use crate::access::ops::levels;
+use crate::commands::arguments::{Argument, ArgumentParser};
+use crate::commands::{Command, CommandContext, CommandRegistry};
+use crate::utils::constants::PLUGINS_DIRECTORY;
+use crate::utils::prelude::*;
+
+pub(super) fn register(registry: &CommandRegistry) {
+    registry.register_command(Command::new("plugins", plugins));
+    registry.register_command(
+        Command::new("plugin", plugin)
+            .usage(vec![
+                Argument::literal("load"),
+                Argument::new("file", ArgumentParser::Word),
+            ])
+            .usage(vec![
+                Argument::literal("unload"),
+                Argument::new("name", ArgumentParser::Word),
+            ])
+            .usage(vec![
+                Argument::literal("reload"),
+                Argument::new("name", ArgumentParser::Word),
+            ])
+            .permission(levels::OWNER),
+    );
+}
+
+async fn plugins(ctx: CommandContext) -> Result<()> {
+    let plugins = ctx.state.plugins.plugins();
+    let names = plugins
+        .iter()
+        .map(|(name, version)| format!("{} {}", name, version))
+        .collect::<Vec<_>>();
+    ctx.reply(&format!("Plugins ({}): {}", names.len(), names.join(", ")))
+        .await
+}
+
+async fn plugin(ctx: CommandContext) -> Result<()> {
+    let state = &ctx.state;
+    let subcommand = ctx.arguments.first().map(|(name, _)| name.as_str());
+
+    let message = match subcommand {
+        Some("load") => {
+            let file = ctx.argument("file").unwrap_or_default();
+            let mut path = Path::new(PLUGINS_DIRECTORY).join(file);
+            if path.extension().is_none() {
+                path.set_extension(DLL_EXTENSION);
+            }
+            match state.plugins.load(state, &path).await {
+                Ok(name) => format!("Loaded {}", name),
+                Err(e) => format!("Failed to load {}: {}", path.display(), e),
+            }
+        }
+        Some("unload") => {
+            let name = ctx.argument("name").unwrap_or_default();
+            match state.plugins.unload(state, name).await {
+                Ok(()) => format!("Unloaded {}", name),
+                Err(e) => format!("Failed to unload {}: {}", name, e),
+            }
+        }
+        Some("reload") => {
+            let name = ctx.argument("name").unwrap_or_default();
+            match state.plugins.reload(state, name).await {
+                Ok(name) => format!("Reloaded {}", name),
+                Err(e) => format!("Failed to reload {}: {}", name, e),
+            }
+        }
+        _ => return Err(Error::Generic("Unknown plugin subcommand".to_string())),
+    };
+
+    ctx.reply(&message).await
+}
@@ -0,0 +1,73 @@
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::access::ops::levels;
+use crate::commands::arguments::Argument;
+use crate::commands::{Command, CommandContext, CommandRegistry};
+use crate::net::profiler::PROFILER;
+use crate::utils::constants::PROFILES_DIRECTORY;
+use crate::utils::prelude::*;
+
+/// How many of the slowest stacks are shown when the profiler's stopped.
+const SHOWN: usize = 5;
+
+pub(super) fn register(registry: &CommandRegistry) {
+    registry.register_command(
+        Command::new("profile", profile)
+            .usage(vec![Argument::literal("start")])
+            .usage(vec![Argument::literal("stop")])
+            .usage(vec![Argument::literal("stop"), Argument::literal("flame")])
+            .usage(vec![Argument::literal("stop"), Argument::literal("json")])
+            .usage(vec![Argument::literal("status")])
+            .permission(levels::ADMIN),
+    );
+}
+
+async fn profile(ctx: CommandContext) -> Result<()> {
+    let subcommand = ctx.arguments.first().map(|(name, _)| name.as_str());
+
+    let message = match subcommand {
+        Some("start") if PROFILER.start() => "Started profiling".to_string(),
+        Some("start") => "The profiler is already running".to_string(),
+        Some("stop") => {
+            let Some(report) = PROFILER.stop() else {
+                return ctx.reply("The profiler isn't running").await;
+            };
+            let (contents, extension) = if ctx.arguments.iter().any(|(name, _)| name == "json") {
+                let json = report.json().map_err(|e| Error::Generic(e.to_string()))?;
+                (json, "json")
+            } else {
+                // Folded stacks, for flame graphs
+                (report.folded(), "folded")
+            };
+            let seconds = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |time| time.as_secs());
+            let directory = Path::new(PROFILES_DIRECTORY);
+            tokio::fs::create_dir_all(directory).await?;
+            let path = directory.join(format!("profile-{}.{}", seconds, extension));
+            tokio::fs::write(&path, contents).await?;
+
+            let mut message = format!(
+                "Profiled for {:.1}s, saved to {}",
+                report.duration.as_secs_f64(),
+                path.display()
+            );
+            for (stack, sample) in report.slowest(SHOWN) {
+                message.push_str(&format!(
+                    "\n{}: {:.1}ms over {} calls, at most {:.2}ms",
+                    stack,
+                    sample.total.as_secs_f64() * 1000.0,
+                    sample.calls,
+                    sample.max.as_secs_f64() * 1000.0
+                ));
+            }
+            message
+        }
+        Some("status") if PROFILER.is_running() => "The profiler is running".to_string(),
+        Some("status") => "The profiler isn't running".to_string(),
+        _ => return Err(Error::Generic("Unknown profile subcommand".to_string())),
+    };
+
+    ctx.reply(&message).await
+}
@@ -0,0 +1,47 @@
+use crate::access::ops::levels;
+use crate::commands::arguments::{Argument, ArgumentParser};
+use crate::commands::{Command, CommandContext, CommandRegistry};
+use crate::scripting;
+use crate::utils::prelude::*;
+
+pub(super) fn register(registry: &CommandRegistry) {
+    registry.register_command(
+        Command::new("script", script)
+            .usage(vec![
+                Argument::literal("run"),
+                Argument::new("code", ArgumentParser::GreedyString),
+            ])
+            .usage(vec![
+                Argument::literal("file"),
+                Argument::new("name", ArgumentParser::Word),
+            ])
+            .permission(levels::OWNER),
+    );
+}
+
+async fn script(ctx: CommandContext) -> Result<()> {
+    let state = &ctx.state;
+    let subcommand = ctx.arguments.first().map(|(name, _)| name.as_str());
+
+    let result = match subcommand {
+        Some("run") => {
+            let code = ctx.argument("code").unwrap_or_default().to_string();
+            scripting::run(state, code, Some(ctx.sender)).await
+        }
+        Some("file") => {
+            let name = ctx.argument("name").unwrap_or_default();
+            scripting::run_file(state, name, Some(ctx.sender)).await
+        }
+        _ => return Err(Error::Generic("Unknown script subcommand".to_string())),
+    };
+
+    match result {
+        Ok(output) => {
+            for line in output {
+                ctx.reply(&line).await?;
+            }
+            Ok(())
+        }
+        Err(e) => ctx.reply(&format!("Script failed: {}", e)).await,
+    }
+}
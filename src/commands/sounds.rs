@@ -0,0 +1,134 @@
+use rand::random;
+
+use crate::access::ops::levels;
+use crate::commands::arguments::{Argument, ArgumentParser};
+use crate::commands::builtin::{parse_exact_location, player_argument, username};
+use crate::commands::{Command, CommandContext, CommandRegistry};
+use crate::net::packets::outgoing::particle::{Particle, ParticleData};
+use crate::net::packets::outgoing::sound_effect::SoundEffect;
+use crate::utils::components::dimension::dimension_of;
+use crate::utils::encoding::position::Position;
+use crate::utils::prelude::*;
+use crate::world::particles::{particle_id, spawn_particles};
+use crate::world::sounds::{range, sound_id, SoundCategory, CATEGORY_NAMES};
+
+pub(super) fn register(registry: &CommandRegistry) {
+    let sound = Argument::new("sound", ArgumentParser::Sound);
+    let targets = Argument::new("targets", ArgumentParser::Player);
+    let position = Argument::new("pos", ArgumentParser::Vec3);
+    let volume = Argument::new("volume", ArgumentParser::Double);
+    let pitch = Argument::new("pitch", ArgumentParser::Double);
+
+    let mut command = Command::new("playsound", play_sound);
+    for category in CATEGORY_NAMES {
+        let mut usage = vec![sound.clone(), Argument::literal(category), targets.clone()];
+        command = command.usage(usage.clone());
+        for optional in [&position, &volume, &pitch] {
+            usage.push(optional.clone());
+            command = command.usage(usage.clone());
+        }
+    }
+    registry.register_command(command.permission(levels::GAMEMASTER));
+
+    let particle = Argument::new("name", ArgumentParser::Particle);
+    let delta = Argument::new("delta", ArgumentParser::Vec3);
+    let speed = Argument::new("speed", ArgumentParser::Double);
+    let count = Argument::new("count", ArgumentParser::Integer);
+    let full = vec![particle.clone(), position.clone(), delta, speed, count];
+    let mut command = Command::new("particle", show_particle)
+        .usage(vec![particle.clone()])
+        .usage(vec![particle, position])
+        .usage(full.clone());
+    for mode in ["force", "normal"] {
+        command = command.usage([full.clone(), vec![Argument::literal(mode)]].concat());
+    }
+    registry.register_command(command.permission(levels::GAMEMASTER));
+}
+
+fn number<T: std::str::FromStr>(ctx: &CommandContext, name: &str, default: T) -> Result<T> {
+    match ctx.argument(name) {
+        Some(value) => value
+            .parse()
+            .map_err(|_| Error::Generic(format!("Invalid {}: {}", name, value))),
+        None => Ok(default),
+    }
+}
+
+/// Where the sender is, or where they said relative to that.
+async fn position_argument(ctx: &CommandContext, entity_id: usize) -> Result<(f64, f64, f64)> {
+    let base = ctx
+        .state
+        .world
+        .get_component::<Position>(entity_id)
+        .await?
+        .clone();
+    // Positions are whole blocks, so the player is taken to be in the middle of theirs
+    let base = (base.x as f64 + 0.5, base.y as f64, base.z as f64 + 0.5);
+    match ctx.argument("pos") {
+        Some(location) => parse_exact_location(location, base)
+            .ok_or_else(|| Error::Generic(format!("Invalid position: {}", location))),
+        None => Ok(base),
+    }
+}
+
+async fn play_sound(ctx: CommandContext) -> Result<()> {
+    let name = ctx.argument("sound").unwrap_or_default();
+    let category = ctx
+        .arguments
+        .get(1)
+        .and_then(|(category, _)| SoundCategory::from_name(category))
+        .ok_or_else(|| Error::Generic("Unknown sound category".to_string()))?;
+    let Some(target) = player_argument(&ctx, "targets").await? else {
+        return Ok(());
+    };
+    let player = username(&ctx, target).await?;
+    let position = position_argument(&ctx, ctx.sender).await?;
+    let volume = number(&ctx, "volume", 1.0f32)?;
+    let pitch = number(&ctx, "pitch", 1.0f32)?.clamp(0.0, 2.0);
+
+    // Like vanilla, players too far away to hear it don't, unless it's loud enough
+    let heard_from = ctx
+        .state
+        .world
+        .get_component::<Position>(target)
+        .await?
+        .clone();
+    let dx = heard_from.x as f64 + 0.5 - position.0;
+    let dy = heard_from.y as f64 - position.1;
+    let dz = heard_from.z as f64 + 0.5 - position.2;
+    if dx * dx + dy * dy + dz * dz > range(volume) * range(volume) {
+        return ctx
+            .reply("The player is too far away to hear the sound")
+            .await;
+    }
+
+    let sound = sound_id(&ctx.state, name);
+    let packet = SoundEffect::new(sound, category as i32, position, volume, pitch, random());
+    let conn = ctx.state.connections.get_connection(target)?;
+    let conn = conn.read().await;
+    conn.send_packet(packet).await?;
+    ctx.reply(&format!("Played sound {} to {}", name, player))
+        .await
+}
+
+async fn show_particle(ctx: CommandContext) -> Result<()> {
+    let name = ctx.argument("name").unwrap_or_default();
+    let id = particle_id(&ctx.state, name)?;
+    let data = ParticleData::default_for(name)
+        .ok_or_else(|| Error::Generic(format!("Particle {} can't be shown yet", name)))?;
+    let position = position_argument(&ctx, ctx.sender).await?;
+    let delta = match ctx.argument("delta") {
+        Some(delta) => parse_exact_location(delta, (0.0, 0.0, 0.0))
+            .ok_or_else(|| Error::Generic(format!("Invalid delta: {}", delta)))?,
+        None => (0.0, 0.0, 0.0),
+    };
+    let offset = (delta.0 as f32, delta.1 as f32, delta.2 as f32);
+    let speed = number(&ctx, "speed", 0.0f32)?;
+    let count = number(&ctx, "count", 0)?;
+
+    let mut particles = Particle::new(id, data, position, offset, speed, count);
+    particles.long_distance = ctx.argument("force").is_some();
+    let dimension = dimension_of(&ctx.state, ctx.sender).await;
+    spawn_particles(&ctx.state, dimension, particles).await?;
+    ctx.reply(&format!("Displaying particle {}", name)).await
+}
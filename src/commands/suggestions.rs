@@ -0,0 +1,156 @@
+use crate::commands::arguments::{Argument, ArgumentParser};
+use crate::commands::CommandRegistry;
+use crate::state::GlobalState;
+use crate::utils::components::player::Player;
+use crate::utils::encoding::position::Position;
+use crate::utils::prelude::*;
+use crate::world::mobs::MOB_TYPES;
+
+/// Completions for the word being typed, which starts at `start` in the text and is `length` long.
+#[derive(Debug, Default)]
+pub struct Suggestions {
+    pub start: usize,
+    pub length: usize,
+    pub matches: Vec<String>,
+}
+
+impl CommandRegistry {
+    /// Suggests completions for a partially typed command. `text` includes the leading slash.
+    pub async fn suggest(&self, text: &str, sender: usize, state: &GlobalState) -> Result<Suggestions> {
+        let input = text.strip_prefix('/').unwrap_or(text);
+        let words = input.split(' ').collect::<Vec<_>>();
+        let partial = words.last().copied().unwrap_or_default();
+
+        let mut suggestions = Suggestions {
+            start: text.len() - partial.len(),
+            length: partial.len(),
+            matches: Vec::new(),
+        };
+
+        // Command names are completed by the client from the command tree
+        let Some((name, words)) = words.split_first().filter(|(_, words)| !words.is_empty()) else {
+            return Ok(suggestions);
+        };
+        let Some(command) = self.get(name) else {
+            return Ok(suggestions);
+        };
+        if !state.has_permission(sender, command.permission).await {
+            return Ok(suggestions);
+        }
+
+        for usage in command.usages() {
+            let Some((argument, offset)) = completing(usage, words) else {
+                continue;
+            };
+
+            for candidate in candidates(argument, offset, sender, state).await? {
+                let matches = candidate
+                    .to_lowercase()
+                    .starts_with(&partial.to_lowercase());
+                if matches && !suggestions.matches.contains(&candidate) {
+                    suggestions.matches.push(candidate);
+                }
+            }
+        }
+
+        Ok(suggestions)
+    }
+}
+
+/// Works out which argument of the usage the last word belongs to, and which of its words it is.
+///
+/// Returns `None` if the words before it don't fit the usage.
+pub fn completing<'a>(usage: &'a [Argument], words: &[&str]) -> Option<(&'a Argument, usize)> {
+    let current = words.len().checked_sub(1)?;
+    let mut start = 0;
+
+    for argument in usage {
+        let Some(count) = argument.parser.word_count() else {
+            return Some((argument, current - start));
+        };
+
+        if current < start + count {
+            return Some((argument, current - start));
+        }
+        if !argument.accepts(&words[start..start + count]) {
+            return None;
+        }
+        start += count;
+    }
+
+    None
+}
+
+/// Everything that could go in the argument, starting at its `offset`th word.
+async fn candidates(
+    argument: &Argument,
+    offset: usize,
+    sender: usize,
+    state: &GlobalState,
+) -> Result<Vec<String>> {
+    let candidates = match argument.parser {
+        ArgumentParser::Player => {
+            let query = state.world.query::<&Player>();
+            let players = query
+                .iter()
+                .await
+                .map(|(_, player)| player.username.clone())
+                .collect::<Vec<_>>();
+            players
+        }
+        ArgumentParser::Vec3 => {
+            // Where the sender is standing, from the coordinate being typed onwards
+            let position = state.world.get_component::<Position>(sender).await?;
+            let coordinates = [position.x.to_string(), position.y.to_string(), position.z.to_string()];
+            vec![coordinates[offset.min(2)..].join(" ")]
+        }
+        ArgumentParser::EntityType => MOB_TYPES
+            .iter()
+            .map(|mob_type| format!("minecraft:{}", mob_type.name))
+            .collect(),
+        ArgumentParser::Sound => sorted_names(state.sounds.names()),
+        ArgumentParser::Particle => sorted_names(state.particles.names()),
+        _ => Vec::new(),
+    };
+
+    Ok(candidates)
+}
+
+fn sorted_names<'a>(names: impl Iterator<Item = &'a str>) -> Vec<String> {
+    let mut names = names.map(str::to_string).collect::<Vec<_>>();
+    names.sort();
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_completing() {
+        let usage = vec![
+            Argument::new("targets", ArgumentParser::Player),
+            Argument::new("location", ArgumentParser::Vec3),
+        ];
+
+        let (argument, offset) = completing(&usage, &["Ste"]).unwrap();
+        assert_eq!((argument.name.as_str(), offset), ("targets", 0));
+
+        let (argument, offset) = completing(&usage, &["Steve", "1", ""]).unwrap();
+        assert_eq!((argument.name.as_str(), offset), ("location", 1));
+
+        assert!(completing(&usage, &["Steve", "1", "2", "3", ""]).is_none());
+        assert!(completing(&usage, &[]).is_none());
+    }
+
+    #[test]
+    fn test_completing_rejects_bad_arguments() {
+        let usage = vec![
+            Argument::new("gamemode", ArgumentParser::GameMode),
+            Argument::new("target", ArgumentParser::Player),
+        ];
+
+        assert!(completing(&usage, &["creative", "St"]).is_some());
+        assert!(completing(&usage, &["flying", "St"]).is_none());
+    }
+}
@@ -0,0 +1,74 @@
+use crate::access::ops::levels;
+use crate::commands::arguments::{Argument, ArgumentParser};
+use crate::commands::{Command, CommandContext, CommandRegistry};
+use crate::database::world_meta::{WorldMeta, DAY_LENGTH};
+use crate::net::packets::outgoing::update_time::UpdateTime;
+use crate::net::utils::broadcast::broadcast_packet;
+use crate::utils::prelude::*;
+
+/// What `/time set` takes instead of a number, and the time of day each one is.
+const NAMED_TIMES: &[(&str, i64)] = &[
+    ("day", 1000),
+    ("noon", 6000),
+    ("night", 13000),
+    ("midnight", 18000),
+];
+
+pub(super) fn register(registry: &CommandRegistry) {
+    let time = Argument::new("time", ArgumentParser::Integer);
+
+    let mut command = Command::new("time", time_command)
+        .usage(vec![Argument::literal("set"), time.clone()])
+        .usage(vec![Argument::literal("add"), time]);
+    for (name, _) in NAMED_TIMES {
+        command = command.usage(vec![Argument::literal("set"), Argument::literal(name)]);
+    }
+    for query in ["daytime", "gametime", "day"] {
+        command = command.usage(vec![Argument::literal("query"), Argument::literal(query)]);
+    }
+    registry.register_command(command.permission(levels::GAMEMASTER));
+}
+
+async fn time_command(ctx: CommandContext) -> Result<()> {
+    let subcommand = ctx.arguments.first().map(|(name, _)| name.as_str());
+    let second = ctx.arguments.get(1).map(|(name, _)| name.as_str());
+
+    if subcommand == Some("query") {
+        let time = {
+            let world_meta = ctx.state.world.resource::<WorldMeta>().await?;
+            match second {
+                Some("gametime") => world_meta.time,
+                Some("day") => world_meta.day_time / DAY_LENGTH,
+                _ => world_meta.time_of_day(),
+            }
+        };
+        return ctx.reply(&format!("The time is {}", time)).await;
+    }
+
+    let amount = match ctx.argument("time") {
+        Some(time) => time
+            .parse::<i64>()
+            .map_err(|_| Error::Generic(format!("Invalid time: {}", time)))?,
+        None => NAMED_TIMES
+            .iter()
+            .find(|(name, _)| Some(*name) == second)
+            .map(|(_, time)| *time)
+            .ok_or_else(|| Error::Generic("Unknown time".to_string()))?,
+    };
+    if amount < 0 {
+        return ctx.reply("Tick count must be non-negative").await;
+    }
+
+    let (time_of_day, packet) = {
+        let mut world_meta = ctx.state.world.resource_mut::<WorldMeta>().await?;
+        match subcommand {
+            Some("set") => world_meta.day_time = amount,
+            Some("add") => world_meta.day_time += amount,
+            _ => return Err(Error::Generic("Unknown time subcommand".to_string())),
+        }
+        (world_meta.time_of_day(), UpdateTime::new(&world_meta))
+    };
+
+    broadcast_packet(packet, &ctx.state).await?;
+    ctx.reply(&format!("Set the time to {}", time_of_day)).await
+}
@@ -0,0 +1,82 @@
+use crate::access::ops::levels;
+use crate::commands::arguments::{Argument, ArgumentParser};
+use crate::commands::builtin::{player_argument, username};
+use crate::commands::{Command, CommandContext, CommandRegistry};
+use crate::net::packets::outgoing::set_subtitle_text::SetSubtitleText;
+use crate::net::packets::outgoing::set_title_text::SetTitleText;
+use crate::utils::components::player::Player;
+use crate::utils::prelude::*;
+use crate::utils::text_component::TextComponent;
+
+pub(super) fn register(registry: &CommandRegistry) {
+    let targets = Argument::new("targets", ArgumentParser::Player);
+    let text = Argument::new("text", ArgumentParser::GreedyString);
+    let time = |name: &str| Argument::new(name, ArgumentParser::Integer);
+
+    let mut command = Command::new("title", title_command);
+    for subcommand in ["clear", "reset"] {
+        command = command.usage(vec![targets.clone(), Argument::literal(subcommand)]);
+    }
+    for subcommand in ["title", "subtitle", "actionbar"] {
+        let usage = vec![targets.clone(), Argument::literal(subcommand), text.clone()];
+        command = command.usage(usage);
+    }
+    let times = vec![
+        targets,
+        Argument::literal("times"),
+        time("fadeIn"),
+        time("stay"),
+        time("fadeOut"),
+    ];
+    registry.register_command(command.usage(times).permission(levels::GAMEMASTER));
+}
+
+async fn title_command(ctx: CommandContext) -> Result<()> {
+    let Some(target) = player_argument(&ctx, "targets").await? else {
+        return Ok(());
+    };
+    let player = username(&ctx, target).await?;
+    // Either JSON or plain text
+    let text = TextComponent::parse(ctx.argument("text").unwrap_or_default());
+    let subcommand = ctx.arguments.get(1).map(|(name, _)| name.as_str());
+
+    let message = match subcommand {
+        Some("clear") => {
+            Player::clear_title(&ctx.state, target, false).await?;
+            format!("Cleared titles for {}", player)
+        }
+        Some("reset") => {
+            Player::clear_title(&ctx.state, target, true).await?;
+            format!("Reset title options for {}", player)
+        }
+        // Like vanilla, the subtitle is kept for the next title rather than shown on its own
+        Some("title") | Some("subtitle") => {
+            let conn = ctx.state.connections.get_connection(target)?;
+            let conn = conn.read().await;
+            match subcommand {
+                Some("title") => conn.send_packet(SetTitleText::new(&text)).await?,
+                _ => conn.send_packet(SetSubtitleText::new(&text)).await?,
+            }
+            format!("Showing new title for {}", player)
+        }
+        Some("actionbar") => {
+            Player::send_action_bar(&ctx.state, target, &text).await?;
+            format!("Showing new actionbar title for {}", player)
+        }
+        Some("times") => {
+            let time = |name: &str| -> Result<i32> {
+                let value = ctx.argument(name).unwrap_or_default();
+                value
+                    .parse::<i32>()
+                    .ok()
+                    .filter(|value| *value >= 0)
+                    .ok_or_else(|| Error::Generic(format!("Invalid time: {}", value)))
+            };
+            let (fade_in, stay, fade_out) = (time("fadeIn")?, time("stay")?, time("fadeOut")?);
+            Player::set_title_times(&ctx.state, target, fade_in, stay, fade_out).await?;
+            format!("Changed title display times for {}", player)
+        }
+        _ => return Err(Error::Generic("Unknown title subcommand".to_string())),
+    };
+    ctx.reply(&message).await
+}
@@ -0,0 +1,130 @@
+use std::sync::Arc;
+
+use ferrumc_codec::network_types::varint::VarInt;
+
+use crate::commands::arguments::ArgumentParser;
+use crate::commands::Command;
+use crate::net::packets::outgoing::commands::{flags, CommandNode};
+
+struct Node {
+    flags: u8,
+    children: Vec<usize>,
+    name: Option<String>,
+    parser: Option<ArgumentParser>,
+}
+
+impl Node {
+    fn new(flags: u8, name: Option<String>, parser: Option<ArgumentParser>) -> Self {
+        Self {
+            flags,
+            children: Vec::new(),
+            name,
+            parser,
+        }
+    }
+}
+
+/// Flattens the commands into the list of nodes the Commands packet expects, with the root at index 0.
+///
+/// Every command is a literal under the root, and each of its usages is a chain of argument nodes
+/// under that. Usages that start with the same arguments share those nodes.
+pub fn build_tree(commands: &[Arc<Command>]) -> Vec<CommandNode> {
+    let mut nodes = vec![Node::new(flags::ROOT, None, None)];
+
+    for command in commands {
+        let literal = push_child(&mut nodes, 0, Node::new(flags::LITERAL, Some(command.name.clone()), None));
+
+        for usage in command.usages() {
+            let mut parent = literal;
+            for argument in usage {
+                let existing = nodes[parent]
+                    .children
+                    .iter()
+                    .copied()
+                    .find(|&child| nodes[child].name.as_ref() == Some(&argument.name));
+
+                parent = match existing {
+                    Some(child) => child,
+                    None if argument.is_literal() => push_child(
+                        &mut nodes,
+                        parent,
+                        Node::new(flags::LITERAL, Some(argument.name.clone()), None),
+                    ),
+                    None => push_child(
+                        &mut nodes,
+                        parent,
+                        Node::new(flags::ARGUMENT, Some(argument.name.clone()), Some(argument.parser)),
+                    ),
+                };
+            }
+            nodes[parent].flags |= flags::EXECUTABLE;
+        }
+    }
+
+    nodes
+        .into_iter()
+        .map(|node| {
+            let suggestions_type = node.parser.and_then(|parser| parser.suggestions_type());
+            let flags = match suggestions_type {
+                Some(_) => node.flags | flags::HAS_SUGGESTIONS_TYPE,
+                None => node.flags,
+            };
+
+            CommandNode {
+                flags,
+                children_count: VarInt::new(node.children.len() as i32),
+                children: node
+                    .children
+                    .into_iter()
+                    .map(|child| VarInt::new(child as i32))
+                    .collect(),
+                redirect_node: None,
+                name: node.name,
+                parser_id: node.parser.and_then(|parser| parser.id()),
+                properties: node.parser.map(|parser| parser.properties()).unwrap_or_default(),
+                suggestions_type: suggestions_type.map(str::to_string),
+            }
+        })
+        .collect()
+}
+
+fn push_child(nodes: &mut Vec<Node>, parent: usize, node: Node) -> usize {
+    nodes.push(node);
+    let index = nodes.len() - 1;
+    nodes[parent].children.push(index);
+    index
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::arguments::Argument;
+
+    #[test]
+    fn test_build_tree() {
+        let gamemode = Argument::new("gamemode", ArgumentParser::GameMode);
+        let command = Command::new("gamemode", |_| async { Ok(()) })
+            .usage(vec![gamemode.clone()])
+            .usage(vec![gamemode, Argument::new("target", ArgumentParser::Player)]);
+        let stop = Command::new("stop", |_| async { Ok(()) });
+
+        let nodes = build_tree(&[Arc::new(command), Arc::new(stop)]);
+
+        // root, gamemode, <gamemode>, <target>, stop
+        assert_eq!(nodes.len(), 5);
+        assert_eq!(nodes[0].flags, flags::ROOT);
+        assert_eq!(nodes[0].children.len(), 2);
+        assert_eq!(nodes[1].name.as_deref(), Some("gamemode"));
+        assert_eq!(nodes[1].flags, flags::LITERAL);
+        assert_eq!(nodes[1].children.len(), 1);
+        assert_eq!(nodes[2].flags, flags::ARGUMENT | flags::EXECUTABLE);
+        assert_eq!(nodes[2].children.len(), 1);
+        assert_eq!(nodes[3].name.as_deref(), Some("target"));
+        assert_eq!(
+            nodes[3].flags,
+            flags::ARGUMENT | flags::EXECUTABLE | flags::HAS_SUGGESTIONS_TYPE
+        );
+        assert_eq!(nodes[3].suggestions_type.as_deref(), Some("minecraft:ask_server"));
+        assert_eq!(nodes[4].flags, flags::LITERAL | flags::EXECUTABLE);
+    }
+}
@@ -0,0 +1,54 @@
+use crate::access::ops::levels;
+use crate::commands::arguments::{Argument, ArgumentParser};
+use crate::commands::{Command, CommandContext, CommandRegistry};
+use crate::database::world_meta::WorldMeta;
+use crate::net::systems::game_loop::TICKS_PER_SECOND;
+use crate::utils::prelude::*;
+use crate::world::weather::{WeatherKind, DEFAULT_DURATION};
+
+/// What each kind of weather is typed as, and what it's called in the reply.
+const KINDS: &[(&str, WeatherKind, &str)] = &[
+    ("clear", WeatherKind::Clear, "clear"),
+    ("rain", WeatherKind::Rain, "rain"),
+    ("thunder", WeatherKind::Thunder, "rain & thunder"),
+];
+
+pub(super) fn register(registry: &CommandRegistry) {
+    // In seconds, like vanilla
+    let duration = Argument::new("duration", ArgumentParser::Integer);
+
+    let mut command = Command::new("weather", weather);
+    for (name, _, _) in KINDS {
+        command = command
+            .usage(vec![Argument::literal(name)])
+            .usage(vec![Argument::literal(name), duration.clone()]);
+    }
+    registry.register_command(command.permission(levels::GAMEMASTER));
+}
+
+async fn weather(ctx: CommandContext) -> Result<()> {
+    let kind = ctx.arguments.first().map(|(name, _)| name.as_str());
+    let Some((_, kind, description)) = KINDS.iter().find(|(name, _, _)| Some(*name) == kind) else {
+        return Err(Error::Generic("Unknown weather".to_string()));
+    };
+
+    let duration = match ctx.argument("duration") {
+        Some(duration) => {
+            let seconds = duration
+                .parse::<i32>()
+                .map_err(|_| Error::Generic(format!("Invalid duration: {}", duration)))?;
+            if seconds < 0 {
+                return ctx.reply("Duration must not be negative").await;
+            }
+            seconds.saturating_mul(TICKS_PER_SECOND as i32)
+        }
+        None => DEFAULT_DURATION,
+    };
+
+    // The weather fades over from here, and the weather ticker tells everyone as it does
+    {
+        let mut world_meta = ctx.state.world.resource_mut::<WorldMeta>().await?;
+        world_meta.weather.set(*kind, duration);
+    }
+    ctx.reply(&format!("Set the weather to {}", description)).await
+}
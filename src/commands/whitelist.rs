@@ -0,0 +1,68 @@
+use uuid::Uuid;
+
+use crate::access::ops::levels;
+use crate::commands::arguments::{Argument, ArgumentParser};
+use crate::commands::{Command, CommandContext, CommandRegistry};
+use crate::utils::components::player::Player;
+use crate::utils::prelude::*;
+
+pub(super) fn register(registry: &CommandRegistry) {
+    let player = Argument::new("player", ArgumentParser::Word);
+    registry.register_command(
+        Command::new("whitelist", whitelist)
+            .usage(vec![Argument::literal("on")])
+            .usage(vec![Argument::literal("off")])
+            .usage(vec![Argument::literal("list")])
+            .usage(vec![Argument::literal("reload")])
+            .usage(vec![Argument::literal("add"), player.clone()])
+            .usage(vec![Argument::literal("remove"), player])
+            .permission(levels::ADMIN),
+    );
+}
+
+async fn whitelist(ctx: CommandContext) -> Result<()> {
+    let whitelist = &ctx.state.whitelist;
+    let subcommand = ctx.arguments.first().map(|(name, _)| name.as_str());
+    let player = ctx.argument("player").unwrap_or_default();
+
+    let message = match subcommand {
+        Some("on") => {
+            whitelist.set_enabled(true);
+            "Whitelist is now turned on".to_string()
+        }
+        Some("off") => {
+            whitelist.set_enabled(false);
+            "Whitelist is now turned off".to_string()
+        }
+        Some("list") => {
+            let names = whitelist.names();
+            format!("There are {} whitelisted players: {}", names.len(), names.join(", "))
+        }
+        Some("reload") => {
+            whitelist.reload().await?;
+            "Reloaded the whitelist".to_string()
+        }
+        Some("add") => {
+            // Online players are added by uuid, anyone else by name until they join
+            let uuid = match ctx.find_player(player).await {
+                Some(entity) => {
+                    let online = ctx.state.world.get_component::<Player>(entity).await?;
+                    Some(Uuid::from_u128(online.uuid))
+                }
+                None => None,
+            };
+
+            match whitelist.add(player, uuid).await? {
+                true => format!("Added {} to the whitelist", player),
+                false => "Player is already whitelisted".to_string(),
+            }
+        }
+        Some("remove") => match whitelist.remove(player).await? {
+            true => format!("Removed {} from the whitelist", player),
+            false => "Player is not whitelisted".to_string(),
+        },
+        _ => return Err(Error::Generic("Unknown whitelist subcommand".to_string())),
+    };
+
+    ctx.reply(&message).await
+}
@@ -0,0 +1,224 @@
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use ferrumc_codec::network_types::varint::VarInt;
+use tokio::io::AsyncReadExt;
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::connection::{PacketReader, PacketWriter};
+use crate::error::{BotError, Result};
+use crate::packets::{self, clientbound, read_string};
+use crate::stats::Stats;
+use crate::Options;
+
+/// Same as the server's.
+const TICK: Duration = Duration::from_millis(50);
+/// How far bots walk from where they spawned, in blocks. They go round in a circle.
+const WALK_RADIUS: f64 = 3.0;
+/// In radians a tick, which is about a block a second.
+const WALK_SPEED: f64 = 0.016;
+
+/// What the reading half of a connection passes on to the bot.
+enum Event {
+    KeepAlive(i64),
+    Teleport { x: f64, y: f64, z: f64 },
+}
+
+/// Runs a bot until it's disconnected, counting it in `stats`.
+pub async fn run(name: String, options: Arc<Options>, stats: Arc<Stats>) {
+    let mut joined = false;
+    let result = connect(&name, &options, &stats, &mut joined).await;
+    if joined {
+        stats.online.fetch_sub(1, Ordering::Relaxed);
+    }
+    stats.failed.fetch_add(1, Ordering::Relaxed);
+    if let Err(e) = result {
+        warn!("{} stopped: {}", name, e);
+    }
+}
+
+async fn connect(
+    name: &str,
+    options: &Options,
+    stats: &Arc<Stats>,
+    joined: &mut bool,
+) -> Result<()> {
+    let connected = Instant::now();
+    let stream = TcpStream::connect(&options.address).await?;
+    stream.set_nodelay(true)?;
+    let (read, write) = stream.into_split();
+    let mut reader = PacketReader::new(read, Arc::clone(stats));
+    let mut writer = PacketWriter::new(write, Arc::clone(stats));
+
+    let (host, port) = match options.address.rsplit_once(':') {
+        Some((host, port)) => (host, port.parse().unwrap_or(25565)),
+        None => (options.address.as_str(), 25565),
+    };
+    writer
+        .send(packets::Handshake::new_auto(
+            VarInt::new(packets::PROTOCOL_VERSION),
+            host.to_string(),
+            port,
+            VarInt::new(2),
+        ))
+        .await?;
+    writer
+        .send(packets::LoginStart::new_auto(
+            name.to_string(),
+            offline_uuid(name).as_u128(),
+        ))
+        .await?;
+    login(&mut reader, &mut writer).await?;
+
+    writer
+        .send(packets::ClientInformation::new_auto(
+            "en_us".to_string(),
+            options.view_distance,
+        ))
+        .await?;
+    play(reader, writer, options, stats, connected, joined).await
+}
+
+/// The same as the server works out for players in offline mode.
+fn offline_uuid(name: &str) -> Uuid {
+    let namespace = Uuid::new_v5(&Uuid::NAMESPACE_URL, "OfflinePlayer".as_bytes());
+    Uuid::new_v3(&namespace, name.as_bytes())
+}
+
+/// Reads login packets until Login Success.
+async fn login(
+    reader: &mut PacketReader<OwnedReadHalf>,
+    writer: &mut PacketWriter<OwnedWriteHalf>,
+) -> Result<()> {
+    loop {
+        let mut packet = reader.read().await?;
+        match packet.id {
+            clientbound::login::DISCONNECT => {
+                return Err(BotError::Disconnected(read_string(&mut packet.data).await?));
+            }
+            clientbound::login::ENCRYPTION_REQUEST => return Err(BotError::OnlineMode),
+            clientbound::login::LOGIN_SUCCESS => return Ok(()),
+            clientbound::login::SET_COMPRESSION => {
+                let threshold = VarInt::read(&mut packet.data).await?.get_val();
+                reader.enable_compression();
+                writer.enable_compression(threshold.max(0) as usize);
+            }
+            clientbound::login::LOGIN_PLUGIN_REQUEST => {
+                let message_id = VarInt::read(&mut packet.data).await?;
+                writer
+                    .send(packets::LoginPluginResponse::new_auto(message_id))
+                    .await?;
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Walks round in a circle and chats every so often once it's spawned, until it's disconnected
+/// or the server stops answering.
+async fn play(
+    reader: PacketReader<OwnedReadHalf>,
+    mut writer: PacketWriter<OwnedWriteHalf>,
+    options: &Options,
+    stats: &Stats,
+    connected: Instant,
+    joined: &mut bool,
+) -> Result<()> {
+    // Reading isn't cancel safe, so it's done on its own task rather than raced with the ticks
+    let (events, mut received) = mpsc::unbounded_channel();
+    let mut reading = tokio::spawn(read_play(reader, events));
+
+    let mut ticks = tokio::time::interval(TICK);
+    let mut tick = 0u64;
+    let mut spawn = None;
+    loop {
+        tokio::select! {
+            event = received.recv() => match event {
+                Some(Event::KeepAlive(id)) => {
+                    writer.send(packets::KeepAlive::new_auto(id)).await?;
+                }
+                Some(Event::Teleport { x, y, z }) => {
+                    if !*joined {
+                        *joined = true;
+                        stats.joined(connected.elapsed());
+                    }
+                    spawn = Some((x, y, z));
+                }
+                // The reading task's finished, which it only does if something went wrong
+                None => {
+                    return match (&mut reading).await {
+                        Ok(result) => result,
+                        Err(e) => Err(BotError::Generic(e.to_string())),
+                    };
+                }
+            },
+            _ = ticks.tick() => {
+                let Some((x, y, z)) = spawn else {
+                    continue;
+                };
+                tick += 1;
+                let angle = tick as f64 * WALK_SPEED;
+                writer
+                    .send(packets::SetPlayerPosition::new_auto(
+                        x + angle.cos() * WALK_RADIUS - WALK_RADIUS,
+                        y,
+                        z + angle.sin() * WALK_RADIUS,
+                        true,
+                    ))
+                    .await?;
+
+                if options.chat_interval > 0 && tick.is_multiple_of(options.chat_interval) {
+                    let timestamp = SystemTime::now()
+                        .duration_since(UNIX_EPOCH)
+                        .map_or(0, |time| time.as_millis() as i64);
+                    let message = format!("Hello from tick {}", tick);
+                    writer
+                        .send(packets::ChatMessage::new_auto(message, timestamp))
+                        .await?;
+                }
+            }
+        }
+    }
+}
+
+async fn read_play(
+    mut reader: PacketReader<OwnedReadHalf>,
+    events: mpsc::UnboundedSender<Event>,
+) -> Result<()> {
+    loop {
+        let mut packet = reader.read().await?;
+        let event = match packet.id {
+            clientbound::play::DISCONNECT => {
+                return Err(BotError::Disconnected(read_string(&mut packet.data).await?));
+            }
+            clientbound::play::KEEP_ALIVE => Event::KeepAlive(packet.data.read_i64().await?),
+            clientbound::play::SYNCHRONIZE_PLAYER_POSITION => Event::Teleport {
+                x: packet.data.read_f64().await?,
+                y: packet.data.read_f64().await?,
+                z: packet.data.read_f64().await?,
+            },
+            _ => continue,
+        };
+        if events.send(event).is_err() {
+            // The bot's stopped
+            return Ok(());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_offline_uuid() {
+        assert_eq!(offline_uuid("Bot1"), offline_uuid("Bot1"));
+        assert_ne!(offline_uuid("Bot1"), offline_uuid("Bot2"));
+        assert_eq!(offline_uuid("Bot1").get_version_num(), 3);
+    }
+}
@@ -0,0 +1,128 @@
+use std::io::Cursor;
+use std::sync::Arc;
+
+use bytes::Bytes;
+use ferrumc_codec::dec::decompress_packet;
+use ferrumc_codec::enc::{NetEncode, NetEncodeOpts};
+use ferrumc_codec::network_types::varint::VarInt;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::error::{BotError, Result};
+use crate::stats::Stats;
+
+/// Same as the server's limit, the most a packet can be before it's decompressed.
+const MAX_PACKET_LENGTH: usize = 2097151;
+
+/// A packet from the server, decompressed if it needed to be.
+pub struct Packet {
+    pub id: i32,
+    /// Its data, after the id.
+    pub data: Cursor<Bytes>,
+}
+
+/// Reads packets off one half of a bot's connection.
+pub struct PacketReader<R> {
+    stream: R,
+    compressed: bool,
+    stats: Arc<Stats>,
+}
+
+impl<R: AsyncRead + Unpin> PacketReader<R> {
+    pub fn new(stream: R, stats: Arc<Stats>) -> Self {
+        Self {
+            stream,
+            compressed: false,
+            stats,
+        }
+    }
+
+    /// Called when the server sends Set Compression.
+    pub fn enable_compression(&mut self) {
+        self.compressed = true;
+    }
+
+    pub async fn read(&mut self) -> Result<Packet> {
+        let length = VarInt::read(&mut self.stream).await?;
+        let frame_length = usize::try_from(length.get_val())
+            .ok()
+            .filter(|&length| length <= MAX_PACKET_LENGTH)
+            .ok_or(BotError::PacketTooLong(length.get_val()))?;
+        let mut frame = vec![0; frame_length];
+        self.stream.read_exact(&mut frame).await?;
+        self.stats.received(length.get_len() + frame_length);
+
+        let mut data = Bytes::from(frame);
+        if self.compressed {
+            data = decompress_packet(data).await?;
+        }
+        let mut data = Cursor::new(data);
+        let id = VarInt::read(&mut data).await?.get_val();
+        Ok(Packet { id, data })
+    }
+}
+
+/// Writes packets to the other half of a bot's connection.
+pub struct PacketWriter<W> {
+    stream: W,
+    compression: NetEncodeOpts,
+    stats: Arc<Stats>,
+}
+
+impl<W: AsyncWrite + Unpin> PacketWriter<W> {
+    pub fn new(stream: W, stats: Arc<Stats>) -> Self {
+        Self {
+            stream,
+            compression: NetEncodeOpts::None,
+            stats,
+        }
+    }
+
+    pub fn enable_compression(&mut self, threshold: usize) {
+        self.compression = NetEncodeOpts::Compressed { threshold };
+    }
+
+    pub async fn send(&mut self, packet: impl NetEncode) -> Result<()> {
+        let mut frame = Vec::new();
+        packet
+            .net_encode_with_opts(&mut frame, &self.compression)
+            .await?;
+        self.stream.write_all(&frame).await?;
+        self.stats.sent(frame.len());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::packets::KeepAlive;
+
+    use super::*;
+
+    async fn round_trip(threshold: Option<usize>) {
+        let stats = Arc::new(Stats::default());
+        let (client, server) = tokio::io::duplex(1024);
+        let mut writer = PacketWriter::new(client, Arc::clone(&stats));
+        let mut reader = PacketReader::new(server, Arc::clone(&stats));
+        if let Some(threshold) = threshold {
+            writer.enable_compression(threshold);
+            reader.enable_compression();
+        }
+
+        writer.send(KeepAlive::new_auto(42)).await.unwrap();
+        let mut packet = reader.read().await.unwrap();
+        assert_eq!(packet.id, 0x12);
+        assert_eq!(packet.data.read_i64().await.unwrap(), 42);
+
+        let stats = stats.snapshot();
+        assert_eq!((stats.packets_sent, stats.packets_received), (1, 1));
+        assert_eq!(stats.bytes_sent, stats.bytes_received);
+    }
+
+    #[tokio::test]
+    async fn test_round_trip() {
+        round_trip(None).await;
+        // Below the threshold, then compressed
+        round_trip(Some(256)).await;
+        round_trip(Some(0)).await;
+    }
+}
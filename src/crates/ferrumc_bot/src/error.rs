@@ -0,0 +1,19 @@
+use ferrumc_codec::CodecError;
+
+pub type Result<T> = std::result::Result<T, BotError>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum BotError {
+    #[error("Codec error: {0}")]
+    Codec(#[from] CodecError),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Disconnected: {0}")]
+    Disconnected(String),
+    #[error("The server is in online mode, bots can only join servers in offline mode")]
+    OnlineMode,
+    #[error("Packet length {0} is out of bounds")]
+    PacketTooLong(i32),
+    #[error("{0}")]
+    Generic(String),
+}
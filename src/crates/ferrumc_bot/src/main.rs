@@ -0,0 +1,126 @@
+//! Headless bots for load testing a FerrumC server. Each bot logs in, walks round in a circle and
+//! chats every so often, like a very boring player, and the bots' stats are logged as they go.
+//!
+//! The server has to be in offline mode. Options are passed like the server's:
+//! - `--address=<host:port>`, `localhost:25565` by default
+//! - `--bots=<count>`, 10 by default
+//! - `--prefix=<name>`, what the bots' names start with, `Bot` by default
+//! - `--join-delay=<milliseconds>` between each bot connecting, 50 by default
+//! - `--duration=<seconds>` to run for, until ctrl+c by default
+//! - `--chat-interval=<ticks>` between each bot's chat messages, 200 by default, 0 for none
+//! - `--view-distance=<chunks>`, 8 by default
+
+use std::env;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use tracing::{error, info};
+
+use crate::stats::Stats;
+
+mod bot;
+mod connection;
+mod error;
+mod packets;
+mod stats;
+
+/// How often the stats are logged.
+const REPORT_INTERVAL: Duration = Duration::from_secs(5);
+
+pub struct Options {
+    pub address: String,
+    pub bots: usize,
+    pub prefix: String,
+    pub join_delay: Duration,
+    pub duration: Option<Duration>,
+    pub chat_interval: u64,
+    pub view_distance: i8,
+}
+
+impl Options {
+    fn from_args() -> Result<Self, String> {
+        let duration = option("duration", 0)?;
+        Ok(Self {
+            address: option("address", "localhost:25565".to_string())?,
+            bots: option("bots", 10)?,
+            prefix: option("prefix", "Bot".to_string())?,
+            join_delay: Duration::from_millis(option("join-delay", 50)?),
+            duration: (duration > 0).then(|| Duration::from_secs(duration)),
+            chat_interval: option("chat-interval", 200)?,
+            view_distance: option("view-distance", 8)?,
+        })
+    }
+}
+
+/// The value of `--<name>=<value>`, or `default` if it wasn't passed.
+fn option<T: FromStr>(name: &str, default: T) -> Result<T, String> {
+    let prefix = format!("--{}=", name);
+    match env::args().find_map(|arg| arg.strip_prefix(&prefix).map(String::from)) {
+        Some(value) => value
+            .parse()
+            .map_err(|_| format!("Invalid value for --{}: {}", name, value)),
+        None => Ok(default),
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    tracing_subscriber::fmt::init();
+
+    let options = match Options::from_args() {
+        Ok(options) => Arc::new(options),
+        Err(e) => {
+            error!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    let stats = Arc::new(Stats::default());
+
+    info!("Connecting {} bots to {}", options.bots, options.address);
+    let started = Instant::now();
+    let spawning = tokio::spawn(spawn_bots(Arc::clone(&options), Arc::clone(&stats)));
+
+    let stop = async {
+        match options.duration {
+            Some(duration) => tokio::time::sleep(duration).await,
+            None => {
+                let _ = tokio::signal::ctrl_c().await;
+            }
+        }
+    };
+    tokio::select! {
+        _ = stop => {}
+        _ = report(&stats) => {}
+    }
+    spawning.abort();
+
+    let snapshot = stats.snapshot();
+    let rates = snapshot.rates(&Default::default(), started.elapsed());
+    info!("Finished after {:.0?}: {}", started.elapsed(), snapshot);
+    info!("On average: {}", rates);
+    // The bots are stopped along with the runtime
+}
+
+async fn spawn_bots(options: Arc<Options>, stats: Arc<Stats>) {
+    let mut delay = tokio::time::interval(options.join_delay.max(Duration::from_millis(1)));
+    for i in 1..=options.bots {
+        delay.tick().await;
+        let name = format!("{}{}", options.prefix, i);
+        tokio::spawn(bot::run(name, Arc::clone(&options), Arc::clone(&stats)));
+    }
+}
+
+/// Logs the stats every [REPORT_INTERVAL], forever.
+async fn report(stats: &Stats) {
+    let mut interval = tokio::time::interval(REPORT_INTERVAL);
+    interval.tick().await;
+    let mut last = (stats.snapshot(), Instant::now());
+    loop {
+        interval.tick().await;
+        let now = (stats.snapshot(), Instant::now());
+        let rates = now.0.rates(&last.0, now.1 - last.1);
+        info!("{}, {}", now.0, rates);
+        last = now;
+    }
+}
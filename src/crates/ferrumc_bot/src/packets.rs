@@ -0,0 +1,120 @@
+//! The packets bots send, and the ids of the ones they read. Everything is 1.20.1 (protocol 763),
+//! the server's own version, so nothing gets remapped on the way.
+
+use ferrumc_codec::network_types::varint::VarInt;
+use ferrumc_macros::NetEncode;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use crate::error::{BotError, Result};
+
+pub const PROTOCOL_VERSION: i32 = 763;
+
+pub mod clientbound {
+    pub mod login {
+        pub const DISCONNECT: i32 = 0x00;
+        pub const ENCRYPTION_REQUEST: i32 = 0x01;
+        pub const LOGIN_SUCCESS: i32 = 0x02;
+        pub const SET_COMPRESSION: i32 = 0x03;
+        pub const LOGIN_PLUGIN_REQUEST: i32 = 0x04;
+    }
+
+    pub mod play {
+        pub const DISCONNECT: i32 = 0x1A;
+        pub const KEEP_ALIVE: i32 = 0x23;
+        pub const SYNCHRONIZE_PLAYER_POSITION: i32 = 0x3C;
+    }
+}
+
+#[derive(NetEncode)]
+pub struct Handshake {
+    #[encode(default = VarInt::from(0x00))]
+    pub packet_id: VarInt,
+    pub protocol_version: VarInt,
+    pub server_address: String,
+    pub server_port: u16,
+    pub next_state: VarInt,
+}
+
+#[derive(NetEncode)]
+pub struct LoginStart {
+    #[encode(default = VarInt::from(0x00))]
+    pub packet_id: VarInt,
+    pub username: String,
+    pub uuid: u128,
+}
+
+/// Tells the server the bot doesn't understand a login plugin request, e.g. a proxy's.
+#[derive(NetEncode)]
+pub struct LoginPluginResponse {
+    #[encode(default = VarInt::from(0x02))]
+    pub packet_id: VarInt,
+    pub message_id: VarInt,
+    #[encode(default = false)]
+    pub successful: bool,
+}
+
+#[derive(NetEncode)]
+pub struct ClientInformation {
+    #[encode(default = VarInt::from(0x08))]
+    pub packet_id: VarInt,
+    pub locale: String,
+    pub view_distance: i8,
+    /// Full chat.
+    #[encode(default = VarInt::from(0))]
+    pub chat_mode: VarInt,
+    #[encode(default = true)]
+    pub chat_colors: bool,
+    #[encode(default = 0x7F)]
+    pub displayed_skin_parts: u8,
+    /// Right handed.
+    #[encode(default = VarInt::from(1))]
+    pub main_hand: VarInt,
+    #[encode(default = false)]
+    pub text_filtering: bool,
+    #[encode(default = true)]
+    pub allow_server_listings: bool,
+}
+
+#[derive(NetEncode)]
+pub struct KeepAlive {
+    #[encode(default = VarInt::from(0x12))]
+    pub packet_id: VarInt,
+    pub keep_alive_id: i64,
+}
+
+#[derive(NetEncode)]
+pub struct SetPlayerPosition {
+    #[encode(default = VarInt::from(0x14))]
+    pub packet_id: VarInt,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    pub on_ground: bool,
+}
+
+/// An unsigned chat message.
+#[derive(NetEncode)]
+pub struct ChatMessage {
+    #[encode(default = VarInt::from(0x05))]
+    pub packet_id: VarInt,
+    pub message: String,
+    pub timestamp: i64,
+    #[encode(default = 0)]
+    pub salt: i64,
+    #[encode(default = false)]
+    pub has_signature: bool,
+    #[encode(default = VarInt::from(0))]
+    pub message_count: VarInt,
+    /// A fixed bitset of the 20 last seen messages, none of which were.
+    #[encode(default = vec![0; 3], raw_bytes(prepend_length = false))]
+    pub acknowledged: Vec<u8>,
+}
+
+pub async fn read_string<R: AsyncRead + Unpin>(reader: &mut R) -> Result<String> {
+    let length = VarInt::read(reader).await?.get_val();
+    let length = usize::try_from(length)
+        .map_err(|_| BotError::Generic(format!("Invalid string length {}", length)))?;
+    let mut bytes = vec![0; length];
+    reader.read_exact(&mut bytes).await?;
+    String::from_utf8(bytes).map_err(|e| BotError::Generic(e.to_string()))
+}
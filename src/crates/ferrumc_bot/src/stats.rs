@@ -0,0 +1,143 @@
+use std::fmt::{Display, Formatter};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Counted by every bot as it goes, and reported on by the main task.
+#[derive(Debug, Default)]
+pub struct Stats {
+    /// Bots that have spawned in the world and are still connected.
+    pub online: AtomicU64,
+    /// Bots that have spawned in the world, including ones that have left since.
+    pub joined: AtomicU64,
+    /// Bots that were disconnected or failed, whether or not they'd joined.
+    pub failed: AtomicU64,
+    /// In milliseconds, from connecting to spawning, for all the bots that joined.
+    pub join_time: AtomicU64,
+    pub packets_received: AtomicU64,
+    pub packets_sent: AtomicU64,
+    pub bytes_received: AtomicU64,
+    pub bytes_sent: AtomicU64,
+}
+
+impl Stats {
+    pub fn joined(&self, join_time: Duration) {
+        self.online.fetch_add(1, Ordering::Relaxed);
+        self.joined.fetch_add(1, Ordering::Relaxed);
+        self.join_time
+            .fetch_add(join_time.as_millis() as u64, Ordering::Relaxed);
+    }
+
+    pub fn received(&self, bytes: usize) {
+        self.packets_received.fetch_add(1, Ordering::Relaxed);
+        self.bytes_received
+            .fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    pub fn sent(&self, bytes: usize) {
+        self.packets_sent.fetch_add(1, Ordering::Relaxed);
+        self.bytes_sent.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            online: self.online.load(Ordering::Relaxed),
+            joined: self.joined.load(Ordering::Relaxed),
+            failed: self.failed.load(Ordering::Relaxed),
+            join_time: self.join_time.load(Ordering::Relaxed),
+            packets_received: self.packets_received.load(Ordering::Relaxed),
+            packets_sent: self.packets_sent.load(Ordering::Relaxed),
+            bytes_received: self.bytes_received.load(Ordering::Relaxed),
+            bytes_sent: self.bytes_sent.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// The stats at one moment, so the rates since the last one can be worked out.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Snapshot {
+    pub online: u64,
+    pub joined: u64,
+    pub failed: u64,
+    pub join_time: u64,
+    pub packets_received: u64,
+    pub packets_sent: u64,
+    pub bytes_received: u64,
+    pub bytes_sent: u64,
+}
+
+impl Snapshot {
+    /// In milliseconds.
+    pub fn average_join_time(&self) -> u64 {
+        self.join_time.checked_div(self.joined).unwrap_or(0)
+    }
+
+    /// What happened between `earlier` and this one, `elapsed` apart.
+    pub fn rates(&self, earlier: &Snapshot, elapsed: Duration) -> Rates {
+        let per_second = |now: u64, then: u64| (now - then) as f64 / elapsed.as_secs_f64();
+        Rates {
+            packets_received: per_second(self.packets_received, earlier.packets_received),
+            packets_sent: per_second(self.packets_sent, earlier.packets_sent),
+            bytes_received: per_second(self.bytes_received, earlier.bytes_received),
+            bytes_sent: per_second(self.bytes_sent, earlier.bytes_sent),
+        }
+    }
+}
+
+impl Display for Snapshot {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} online, {} joined, {} failed, {}ms average join time",
+            self.online,
+            self.joined,
+            self.failed,
+            self.average_join_time()
+        )
+    }
+}
+
+/// Per second.
+#[derive(Debug, Clone, Copy)]
+pub struct Rates {
+    pub packets_received: f64,
+    pub packets_sent: f64,
+    pub bytes_received: f64,
+    pub bytes_sent: f64,
+}
+
+impl Display for Rates {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "in {:.0} packets/s ({:.1} KiB/s), out {:.0} packets/s ({:.1} KiB/s)",
+            self.packets_received,
+            self.bytes_received / 1024.0,
+            self.packets_sent,
+            self.bytes_sent / 1024.0
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rates() {
+        let stats = Stats::default();
+        stats.joined(Duration::from_millis(100));
+        stats.joined(Duration::from_millis(300));
+        let earlier = stats.snapshot();
+        for _ in 0..10 {
+            stats.received(1024);
+        }
+        stats.sent(512);
+
+        let now = stats.snapshot();
+        assert_eq!(now.average_join_time(), 200);
+        let rates = now.rates(&earlier, Duration::from_secs(2));
+        assert_eq!(rates.packets_received, 5.0);
+        assert_eq!(rates.bytes_received, 5120.0);
+        assert_eq!(rates.bytes_sent, 256.0);
+    }
+}
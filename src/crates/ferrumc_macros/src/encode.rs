@@ -9,6 +9,8 @@ struct FieldAttribs {
     default_value: Option<syn::Expr>,
     raw_bytes: Option<RawBytes>,
     prepend_length: bool,
+    /// Options get a bool in front saying whether they're there, everything else its length.
+    prefixed: bool,
     lifetime: Option<Lifetime>,
 }
 struct RawBytes {
@@ -24,6 +26,7 @@ fn parse_field_attributes(field: &Field) -> FieldAttribs {
         default_value: None,
         raw_bytes: None,
         prepend_length: false,
+        prefixed: false,
         lifetime: None,
     };
 
@@ -52,6 +55,8 @@ fn parse_field_attributes(field: &Field) -> FieldAttribs {
                 field_attrib.default_value = Some(meta.value()?.parse()?);
             } else if meta.path.is_ident("prepend_length") {
                 field_attrib.prepend_length = meta.value()?.parse::<syn::LitBool>()?.value;
+            } else if meta.path.is_ident("prefixed") {
+                field_attrib.prefixed = true;
             }
             Ok(())
         })
@@ -61,7 +66,47 @@ fn parse_field_attributes(field: &Field) -> FieldAttribs {
     field_attrib
 }
 
+/// Whether the field's type is written as an `Option<..>`.
+fn is_option(field_type: &syn::Type) -> bool {
+    match field_type {
+        syn::Type::Path(type_path) => type_path
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "Option"),
+        _ => false,
+    }
+}
+
+/// `#[encode(prefixed)]`, the way the protocol usually sends optional fields and collections.
+fn generate_prefixed_encode_statement(field_attrib: &FieldAttribs) -> proc_macro2::TokenStream {
+    let field_name = &field_attrib.field_name;
+
+    if is_option(&field_attrib.field_type) {
+        quote! {
+            match &self.#field_name {
+                Some(value) => {
+                    true.net_encode(bytes).await?;
+                    value.net_encode(bytes).await?;
+                }
+                None => false.net_encode(bytes).await?,
+            }
+        }
+    } else {
+        quote! {
+            ferrumc_codec::network_types::varint::VarInt::new(self.#field_name.len() as i32)
+                .net_encode(bytes)
+                .await?;
+            self.#field_name.net_encode(bytes).await?;
+        }
+    }
+}
+
 fn generate_field_encode_statement(field_attrib: &FieldAttribs) -> proc_macro2::TokenStream {
+    if field_attrib.prefixed {
+        return generate_prefixed_encode_statement(field_attrib);
+    }
+
     let field_name = &field_attrib.field_name;
     let cursor = format_ident!("__cursor_{}", field_name);
     let bytes = format_ident!("__bytes_{}", field_name);
@@ -3,7 +3,9 @@ use std::ops::Add;
 use std::path::Path;
 
 use quote::quote;
-use syn::{parse_macro_input, LitInt, LitStr};
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{parse_macro_input, LitInt, LitStr, Token};
 
 use proc_macro::TokenStream;
 
@@ -29,6 +31,24 @@ pub fn attribute(args: TokenStream, input: TokenStream) -> TokenStream {
     TokenStream::from(input)
 }
 
+/// One entry of `ids(...)`, e.g. `764 = 0x16`: the packet's id for clients on that protocol version.
+struct VersionId {
+    protocol_version: i32,
+    packet_id: u8,
+}
+
+impl Parse for VersionId {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let protocol_version = input.parse::<LitInt>()?.base10_parse()?;
+        input.parse::<Token![=]>()?;
+        let packet_id = input.parse::<LitInt>()?.base10_parse()?;
+        Ok(Self {
+            protocol_version,
+            packet_id,
+        })
+    }
+}
+
 pub fn bake(input: TokenStream) -> TokenStream {
     // read all the files in /src/packets/incoming
     // for each file, read the packet_id attribute
@@ -50,6 +70,11 @@ pub fn bake(input: TokenStream) -> TokenStream {
     }
 
     let mut match_arms = Vec::new();
+    let mut version_arms = Vec::new();
+    let mut name_arms = Vec::new();
+    let mut version_name_arms = Vec::new();
+    let mut decode_arms = Vec::new();
+    let mut version_decode_arms = Vec::new();
 
     let start = std::time::Instant::now();
 
@@ -71,9 +96,12 @@ pub fn bake(input: TokenStream) -> TokenStream {
             };
 
             // format: #[packet(packet_id = 0x00, state = "handshake")]
+            // or, for a packet whose id isn't the same in every version:
+            // #[packet(packet_id = 0x12, state = "play", ids(764 = 0x14))]
 
             let mut packet_id = None;
             let mut state = None;
+            let mut version_ids = Vec::new();
 
             for attr in item_struct.attrs {
                 if !attr.path().is_ident("packet") {
@@ -98,6 +126,12 @@ pub fn bake(input: TokenStream) -> TokenStream {
                             let n = value.value();
                             state = Some(n);
                         }
+                        "ids" => {
+                            let content;
+                            syn::parenthesized!(content in meta.input);
+                            let ids = Punctuated::<VersionId, Token![,]>::parse_terminated(&content)?;
+                            version_ids.extend(ids);
+                        }
                         &_ => {
                             return Ok(());
                         }
@@ -120,6 +154,8 @@ pub fn bake(input: TokenStream) -> TokenStream {
             };
 
             let struct_name = &item_struct.ident;
+            // For the profiler, packet debugging and replays
+            let packet_name = struct_name.to_string();
 
             println!(
                 "[FERRUMC_MACROS] Found Packet (ID: 0x{:02X}, State: {}, Struct Name: {})",
@@ -135,10 +171,50 @@ pub fn bake(input: TokenStream) -> TokenStream {
 
             let struct_path = syn::parse_str::<syn::Path>(&struct_path).expect("parse_str failed");
 
+            for version_id in &version_ids {
+                let (protocol_version, packet_id) = (version_id.protocol_version, version_id.packet_id);
+                println!(
+                    "[FERRUMC_MACROS]   (ID: 0x{:02X} for protocol {})",
+                    packet_id, protocol_version
+                );
+                version_name_arms.push(quote! {
+                    (#protocol_version, #state, #packet_id) => Some(#packet_name),
+                });
+                version_decode_arms.push(quote! {
+                    (#protocol_version, #state, #packet_id) => {
+                        crate::net::protocol::decode_as(protocol_version, #struct_path::net_decode(cursor)).await?;
+                        #packet_name
+                    },
+                });
+                version_arms.push(quote! {
+                    (#protocol_version, #state, #packet_id) => {
+                        let packet = crate::net::protocol::decode_as(protocol_version, #struct_path::net_decode(cursor)).await?;
+                        crate::net::profiler::profile_packet(#state, #packet_name, packet.handle(conn_id, state)).await?;
+                    },
+                });
+            }
+
+            // Versions with their own id must not also match the default one, it can belong to
+            // another packet in those versions
+            let guard = if version_ids.is_empty() {
+                quote! {}
+            } else {
+                let other_versions = version_ids.iter().map(|version_id| version_id.protocol_version);
+                quote! { if !matches!(protocol_version, #(#other_versions)|*) }
+            };
+            name_arms.push(quote! {
+                (_, #state, #packet_id) #guard => Some(#packet_name),
+            });
+            decode_arms.push(quote! {
+                (_, #state, #packet_id) #guard => {
+                    crate::net::protocol::decode_as(protocol_version, #struct_path::net_decode(cursor)).await?;
+                    #packet_name
+                },
+            });
             match_arms.push(quote! {
-                (#packet_id, #state) => {
-                    let packet= #struct_path::net_decode(cursor).await?;
-                    packet.handle(conn_id, state).await?;
+                (_, #state, #packet_id) #guard => {
+                    let packet = crate::net::protocol::decode_as(protocol_version, #struct_path::net_decode(cursor)).await?;
+                    crate::net::profiler::profile_packet(#state, #packet_name, packet.handle(conn_id, state)).await?;
                 },
             });
 
@@ -159,16 +235,44 @@ pub fn bake(input: TokenStream) -> TokenStream {
     );
 
     let match_arms = match_arms.into_iter();
-
+    let version_arms = version_arms.into_iter();
+    let name_arms = name_arms.into_iter();
+    let version_name_arms = version_name_arms.into_iter();
+    let decode_arms = decode_arms.into_iter();
+    let version_decode_arms = version_decode_arms.into_iter();
+
+    // The arms for specific versions go first, everything else is matched on the packet's
+    // default id
     let output = quote! {
-        pub async fn handle_packet(packet_id: u8, conn_id: usize, conn_state: &crate::net::State, cursor: &mut std::io::Cursor<Vec<u8>>, state: crate::state::GlobalState) -> crate::utils::prelude::Result<()> {
-            match (packet_id, conn_state.as_str()) {
+        pub async fn handle_packet(packet_id: u8, protocol_version: i32, conn_id: usize, conn_state: &crate::net::State, cursor: &mut std::io::Cursor<bytes::Bytes>, state: crate::state::GlobalState) -> crate::utils::prelude::Result<()> {
+            match (protocol_version, conn_state.as_str(), packet_id) {
+                #(#version_arms)*
                 #(#match_arms)*
-                _ => tracing::warn!("No packet found for ID: 0x{:02X} in state: {}", packet_id, conn_state.as_str()),
+                _ => tracing::warn!("No packet found for ID: 0x{:02X} in state: {} (protocol {})", packet_id, conn_state.as_str(), protocol_version),
             }
 
             Ok(())
         }
+
+        /// The name of the packet `handle_packet` would decode, for logging.
+        pub fn packet_name(packet_id: u8, protocol_version: i32, conn_state: &crate::net::State) -> Option<&'static str> {
+            match (protocol_version, conn_state.as_str(), packet_id) {
+                #(#version_name_arms)*
+                #(#name_arms)*
+                _ => None,
+            }
+        }
+
+        /// Decodes a packet without handling it, returning its name, or `None` if there isn't one
+        /// with that id. For replaying [captures](crate::net::capture).
+        pub async fn decode_packet(packet_id: u8, protocol_version: i32, conn_state: &crate::net::State, cursor: &mut std::io::Cursor<bytes::Bytes>) -> crate::utils::prelude::Result<Option<&'static str>> {
+            let name = match (protocol_version, conn_state.as_str(), packet_id) {
+                #(#version_decode_arms)*
+                #(#decode_arms)*
+                _ => return Ok(None),
+            };
+            Ok(Some(name))
+        }
     };
 
     TokenStream::from(output)
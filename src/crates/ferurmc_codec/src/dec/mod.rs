@@ -0,0 +1,42 @@
+use std::io::{Cursor, Read};
+
+use bytes::Bytes;
+use flate2::read::ZlibDecoder;
+
+use crate::network_types::varint::VarInt;
+use crate::prelude::*;
+
+/// In bytes, the most a compressed packet can hold once it's decompressed. Same as vanilla.
+pub const MAX_DECOMPRESSED_LENGTH: usize = 8388608;
+
+/// Takes the body of a compressed packet frame (everything after the packet length) and returns
+/// the uncompressed packet id and data. Packets that weren't compressed are sliced out of `frame`
+/// without copying. Packets claiming to be bigger than [MAX_DECOMPRESSED_LENGTH] are refused
+/// before anything is allocated for them.
+pub async fn decompress_packet(frame: Bytes) -> Result<Bytes> {
+    let mut cursor = Cursor::new(&frame[..]);
+    let data_length = VarInt::read(&mut cursor).await?.get_val();
+    let data_length = usize::try_from(data_length)
+        .ok()
+        .filter(|&length| length <= MAX_DECOMPRESSED_LENGTH)
+        .ok_or(CodecError::DecompressedTooLong(data_length))?;
+    let start = cursor.position() as usize;
+
+    // A data length of 0 means the packet was below the threshold and sent as-is
+    if data_length == 0 {
+        return Ok(frame.slice(start..));
+    }
+
+    // Reading one byte past the length is enough to tell it was wrong, without inflating
+    // however much the packet really holds
+    let mut data = Vec::with_capacity(data_length);
+    ZlibDecoder::new(&frame[start..])
+        .take(data_length as u64 + 1)
+        .read_to_end(&mut data)?;
+
+    if data.len() != data_length {
+        return Err(CodecError::DecompressedLengthMismatch(data_length, data.len()));
+    }
+
+    Ok(Bytes::from(data))
+}
@@ -0,0 +1,112 @@
+use std::io::{Cursor, Write};
+
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+use crate::enc::NetEncode;
+use crate::network_types::varint::VarInt;
+use crate::prelude::*;
+
+/// Rewrites a buffer of one or more `[length][packet id][data]` frames into the compressed
+/// `[length][data length][zlib(packet id + data)]` layout.
+pub(crate) async fn compress_frames(frames: &[u8], threshold: usize) -> Result<Vec<u8>> {
+    let mut cursor = Cursor::new(frames);
+    let mut out = Vec::with_capacity(frames.len());
+
+    while (cursor.position() as usize) < frames.len() {
+        let length = VarInt::read(&mut cursor).await?;
+        let start = cursor.position() as usize;
+        let end = start + length.get_val() as usize;
+        let data = frames
+            .get(start..end)
+            .ok_or_else(|| CodecError::Other("Packet frame is shorter than its length".into()))?;
+        cursor.set_position(end as u64);
+
+        compress_packet(data, threshold, &mut out).await?;
+    }
+
+    Ok(out)
+}
+
+async fn compress_packet<W>(data: &[u8], threshold: usize, writer: &mut W) -> Result<()>
+where
+    W: AsyncWrite + Unpin,
+{
+    // A data length of 0 tells the client the packet isn't actually compressed
+    let (data_length, body) = if data.len() < threshold {
+        (VarInt::new(0), data.to_vec())
+    } else {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data)?;
+        (VarInt::new(data.len() as i32), encoder.finish()?)
+    };
+
+    let mut header = Vec::new();
+    data_length.net_encode(&mut header).await?;
+
+    VarInt::new((header.len() + body.len()) as i32)
+        .net_encode(writer)
+        .await?;
+    writer.write_all(&header).await?;
+    writer.write_all(&body).await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dec::{decompress_packet, MAX_DECOMPRESSED_LENGTH};
+    use crate::enc::{NetEncode, NetEncodeOpts};
+    use crate::network_types::varint::VarInt;
+
+    struct RawFrame(Vec<u8>);
+
+    impl NetEncode for RawFrame {
+        async fn net_encode<W>(&self, writer: &mut W) -> crate::Result<()>
+        where
+            W: tokio::io::AsyncWrite + Unpin,
+        {
+            VarInt::new(self.0.len() as i32).net_encode(writer).await?;
+            self.0.net_encode(writer).await
+        }
+    }
+
+    async fn round_trip(data: Vec<u8>, threshold: usize) -> Vec<u8> {
+        let mut buf = Vec::new();
+        RawFrame(data)
+            .net_encode_with_opts(&mut buf, &NetEncodeOpts::Compressed { threshold })
+            .await
+            .unwrap();
+
+        let mut cursor = std::io::Cursor::new(buf);
+        let length = VarInt::read(&mut cursor).await.unwrap();
+        let pos = cursor.position() as usize;
+        let frame = cursor.into_inner()[pos..].to_vec();
+        assert_eq!(frame.len(), length.get_val() as usize);
+
+        decompress_packet(frame.into()).await.unwrap().to_vec()
+    }
+
+    #[tokio::test]
+    async fn test_below_threshold_is_uncompressed() {
+        let data = vec![0x01, 0x02, 0x03];
+        assert_eq!(round_trip(data.clone(), 256).await, data);
+    }
+
+    #[tokio::test]
+    async fn test_above_threshold_is_compressed() {
+        let data = vec![0x42; 1024];
+        assert_eq!(round_trip(data.clone(), 256).await, data);
+    }
+
+    #[tokio::test]
+    async fn test_oversized_length_is_refused() {
+        for length in [MAX_DECOMPRESSED_LENGTH as i32 + 1, i32::MAX, -1] {
+            let mut frame = Vec::new();
+            VarInt::new(length).net_encode(&mut frame).await.unwrap();
+            frame.extend_from_slice(&[0x78, 0x9c]);
+            assert!(decompress_packet(frame.into()).await.is_err());
+        }
+    }
+}
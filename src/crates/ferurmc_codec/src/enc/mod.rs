@@ -1,13 +1,43 @@
-use tokio::io::AsyncWrite;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
 
 use crate::prelude::*;
 
+mod compression;
 mod non_primitives;
 mod primitives;
 
+/// Options that change how a packet is framed when it is written to the wire.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum NetEncodeOpts {
+    /// Plain `[length][packet id][data]` frames.
+    #[default]
+    None,
+    /// Zlib compressed frames, as enabled by the Set Compression packet.
+    /// Packets smaller than `threshold` bytes are sent uncompressed, but still in the compressed layout.
+    Compressed { threshold: usize },
+}
+
 pub trait NetEncode {
     #[allow(async_fn_in_trait)]
     async fn net_encode<W>(&self, writer: &mut W) -> Result<()>
     where
         W: AsyncWrite + Unpin;
+
+    /// Encodes one or more length-prefixed packets, reframing them according to `opts`.
+    #[allow(async_fn_in_trait)]
+    async fn net_encode_with_opts<W>(&self, writer: &mut W, opts: &NetEncodeOpts) -> Result<()>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        match opts {
+            NetEncodeOpts::None => self.net_encode(writer).await,
+            NetEncodeOpts::Compressed { threshold } => {
+                let mut frames = Vec::new();
+                self.net_encode(&mut frames).await?;
+                let compressed = compression::compress_frames(&frames, *threshold).await?;
+                writer.write_all(&compressed).await?;
+                Ok(())
+            }
+        }
+    }
 }
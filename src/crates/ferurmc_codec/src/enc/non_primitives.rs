@@ -1,3 +1,5 @@
+use std::collections::{BTreeMap, HashMap};
+
 use tokio::io::{AsyncWrite, AsyncWriteExt};
 
 use crate::enc::NetEncode;
@@ -65,4 +67,45 @@ impl<'a, E: NetEncode> NetEncode for &'a [E] {
 
         Ok(())
     }
-}
\ No newline at end of file
+}
+impl<K: NetEncode, V: NetEncode> NetEncode for HashMap<K, V> {
+    /// Each key followed by its value, in no particular order. Like a [Vec], the number of
+    /// entries isn't encoded.
+    async fn net_encode<W>(&self, writer: &mut W) -> Result<()>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        for (key, value) in self {
+            key.net_encode(writer).await?;
+            value.net_encode(writer).await?;
+        }
+
+        Ok(())
+    }
+}
+impl<K: NetEncode, V: NetEncode> NetEncode for BTreeMap<K, V> {
+    /// Each key followed by its value, sorted by key. Like a [Vec], the number of entries isn't
+    /// encoded.
+    async fn net_encode<W>(&self, writer: &mut W) -> Result<()>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        for (key, value) in self {
+            key.net_encode(writer).await?;
+            value.net_encode(writer).await?;
+        }
+
+        Ok(())
+    }
+}
+impl NetEncode for uuid::Uuid {
+    /// The 128 bits as a big-endian number, i.e. the same as the UUID's hex string read left to
+    /// right.
+    async fn net_encode<W>(&self, writer: &mut W) -> Result<()>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        writer.write_all(self.as_bytes()).await?;
+        Ok(())
+    }
+}
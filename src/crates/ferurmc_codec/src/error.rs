@@ -13,6 +13,10 @@ pub enum CodecError {
     VarIntTooBig,
     #[error("VarLong too big")]
     VarLongTooBig,
+    #[error("Decompressed packet length mismatch: expected {0}, got {1}")]
+    DecompressedLengthMismatch(usize, usize),
+    #[error("Decompressed packet length {0} is out of bounds")]
+    DecompressedTooLong(i32),
     #[error("Other error")]
     Other(String),
 }
@@ -0,0 +1,73 @@
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::enc::NetEncode;
+use crate::prelude::*;
+
+/// A rotation in steps of 1/256 of a full turn, which is how the protocol sends entities' yaw,
+/// pitch and head yaw.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Angle(pub u8);
+
+impl Angle {
+    pub fn new(steps: u8) -> Self {
+        Angle(steps)
+    }
+
+    /// The closest angle below `degrees`. Any number of degrees works, they're wrapped into a
+    /// single turn first.
+    pub fn from_degrees(degrees: f32) -> Self {
+        Angle((degrees.rem_euclid(360.0) / 360.0 * 256.0) as u8)
+    }
+
+    pub fn to_degrees(self) -> f32 {
+        self.0 as f32 * 360.0 / 256.0
+    }
+
+    pub async fn read<T>(cursor: &mut T) -> Result<Angle>
+    where
+        T: AsyncRead + Unpin,
+    {
+        Ok(Angle(cursor.read_u8().await?))
+    }
+}
+
+impl From<u8> for Angle {
+    fn from(steps: u8) -> Self {
+        Angle(steps)
+    }
+}
+
+impl NetEncode for Angle {
+    async fn net_encode<T>(&self, cursor: &mut T) -> Result<()>
+    where
+        T: AsyncWrite + Unpin,
+    {
+        cursor.write_u8(self.0).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn test_from_degrees() {
+        assert_eq!(Angle::from_degrees(0.0), Angle(0));
+        assert_eq!(Angle::from_degrees(90.0), Angle(64));
+        assert_eq!(Angle::from_degrees(-90.0), Angle(192));
+        assert_eq!(Angle::from_degrees(360.0), Angle(0));
+        assert_eq!(Angle(128).to_degrees(), 180.0);
+    }
+
+    #[tokio::test]
+    async fn test_angle_round_trip() {
+        let mut bytes = Vec::new();
+        Angle::from_degrees(270.0).net_encode(&mut bytes).await.unwrap();
+        assert_eq!(bytes, vec![0xC0]);
+        let angle = Angle::read(&mut Cursor::new(bytes)).await.unwrap();
+        assert_eq!(angle.to_degrees(), 270.0);
+    }
+}
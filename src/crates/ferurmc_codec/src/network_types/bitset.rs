@@ -1,55 +1,13 @@
-/*#![allow(dead_code)]
-
-use ferrumc_codec::network_types::varint::VarInt;
-use crate::utils::error::Error;
-use ferrumc_codec::enc::Encode;
-use tokio::io::{AsyncSeek, AsyncWrite};
-
-pub struct BitSet {
-    len: VarInt,
-    data: Vec<u64>,
-}
-
-impl BitSet {
-    pub(crate) fn new() -> Self {
-        BitSet {
-            data: vec![0; 1],
-            len: VarInt::from(0),
-        }
-    }
-
-    pub(crate) fn set(&mut self, index: u32) {
-        let word_index = (index / 64) as usize;
-        let bit_index = index % 64;
-        if word_index >= self.data.len() {
-            self.data.resize(word_index + 1, 0);
-        }
-        self.data[word_index] |= 1 << bit_index;
-    }
-
-    fn to_vec(&self) -> Vec<i64> {
-        self.data.iter().map(|&x| x as i64).collect()
-    }
-}
-
-impl Encode for BitSet {
-    async fn encode<T>(&self, bytes: &mut T) -> Result<(), Error>
-    where
-        T: AsyncWrite + Unpin,
-    {
-        self.len.encode(bytes).await?;
-        self.data.encode(bytes).await?;
-        Ok(())
-    }
-}
-*/
-
 use std::ops::Index;
 
-use ferrumc_codec::enc::NetEncode;
-use ferrumc_codec::network_types::varint::VarInt;
 use tokio::io::{AsyncWrite, AsyncWriteExt};
 
+use crate::enc::NetEncode;
+use crate::network_types::varint::VarInt;
+use crate::prelude::*;
+
+/// The protocol's BitSet, e.g. the light masks in chunk and light packets. Bit `n` is bit `n % 64`
+/// of long `n / 64`, the same as Java's `BitSet.toLongArray`.
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct BitSet {
     data: Vec<u64>,
@@ -58,7 +16,7 @@ pub struct BitSet {
 
 impl BitSet {
     pub fn new(size: usize) -> Self {
-        let num_blocks = (size + 63) / 64;
+        let num_blocks = size.div_ceil(64);
         BitSet {
             data: vec![0; num_blocks],
             size,
@@ -128,7 +86,7 @@ impl BitSet {
     pub fn set_all(&mut self) {
         self.data.fill(u64::MAX);
         // Clear any bits beyond the set size
-        if self.size % 64 != 0 {
+        if !self.size.is_multiple_of(64) {
             let last_block = self.data.last_mut().unwrap();
             *last_block &= (1 << (self.size % 64)) - 1;
         }
@@ -140,8 +98,9 @@ impl FromIterator<usize> for BitSet {
     where
         I: IntoIterator<Item = usize>,
     {
-        let mut bs = BitSet::empty();
-        for i in iter {
+        let indices: Vec<usize> = iter.into_iter().collect();
+        let mut bs = BitSet::new(indices.iter().max().map_or(0, |&max| max + 1));
+        for i in indices {
             bs.set(i);
         }
         bs
@@ -161,7 +120,7 @@ impl Index<usize> for BitSet {
 }
 
 impl NetEncode for BitSet {
-    async fn net_encode<T>(&self, bytes: &mut T) -> Result<(), ferrumc_codec::CodecError>
+    async fn net_encode<T>(&self, bytes: &mut T) -> Result<()>
     where
         T: AsyncWrite + Unpin,
     {
@@ -217,4 +176,15 @@ mod tests {
         assert!(bs[50]);
         assert!(!bs[51]);
     }
+
+    #[tokio::test]
+    async fn test_bitset_encode() {
+        // Bits 0 and 65, which Java writes as the longs 1 and 2
+        let bs = BitSet::from_iter([0, 65]);
+        let mut bytes = Vec::new();
+        bs.net_encode(&mut bytes).await.unwrap();
+        assert_eq!(bytes[0], 2);
+        assert_eq!(bytes[1..9], 1u64.to_be_bytes());
+        assert_eq!(bytes[9..], 2u64.to_be_bytes());
+    }
 }
@@ -1,2 +1,5 @@
+pub mod angle;
+pub mod bitset;
+pub mod packed_array;
 pub mod varint;
-pub mod varlong;
\ No newline at end of file
+pub mod varlong;
@@ -0,0 +1,135 @@
+use std::borrow::Cow;
+
+use tokio::io::AsyncWrite;
+
+use crate::enc::NetEncode;
+use crate::network_types::varint::VarInt;
+use crate::prelude::*;
+
+/// Values of a fixed number of bits packed into longs, as many as fit in each without spreading
+/// any over two longs. This is how block states, biomes and heightmaps are sent, and stored on
+/// disk. The first value is in the lowest bits of the first long.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackedArray<'a> {
+    bits: u8,
+    data: Cow<'a, [i64]>,
+}
+
+impl PackedArray<'static> {
+    /// Packs `values`, each of which must fit in `bits` bits.
+    pub fn pack(values: &[u16], bits: u8) -> Self {
+        PackedArray {
+            bits,
+            data: Cow::Owned(pack_values(values, bits)),
+        }
+    }
+}
+
+impl<'a> PackedArray<'a> {
+    /// Longs that are already packed, like the ones in a chunk on disk.
+    pub fn from_longs(data: &'a [i64], bits: u8) -> Self {
+        PackedArray {
+            bits,
+            data: Cow::Borrowed(data),
+        }
+    }
+
+    pub fn bits(&self) -> u8 {
+        self.bits
+    }
+
+    pub fn longs(&self) -> &[i64] {
+        &self.data
+    }
+
+    pub fn into_longs(self) -> Vec<i64> {
+        self.data.into_owned()
+    }
+
+    /// The first `count` values. Missing longs read as 0.
+    pub fn unpack(&self, count: usize) -> Vec<u16> {
+        unpack_values(&self.data, self.bits, count)
+    }
+}
+
+impl NetEncode for PackedArray<'_> {
+    /// The number of longs, then the longs. Bits per entry are up to the packet, since most of
+    /// them have a palette in between.
+    async fn net_encode<W>(&self, writer: &mut W) -> Result<()>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        VarInt::from(self.data.len() as i32).net_encode(writer).await?;
+        for long in self.data.iter() {
+            long.net_encode(writer).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Packs values into longs, see [PackedArray]. Nothing is packed with 0 bits, which is how single
+/// valued palettes are sent.
+pub fn pack_values(values: &[u16], bits: u8) -> Vec<i64> {
+    if bits == 0 {
+        return Vec::new();
+    }
+    let per_long = 64 / bits as usize;
+    values
+        .chunks(per_long)
+        .map(|values| {
+            values
+                .iter()
+                .enumerate()
+                .fold(0u64, |long, (index, &value)| {
+                    long | (value as u64) << (index * bits as usize)
+                }) as i64
+        })
+        .collect()
+}
+
+/// Unpacks `count` values packed by [pack_values]. Missing longs read as 0.
+pub fn unpack_values(data: &[i64], bits: u8, count: usize) -> Vec<u16> {
+    if bits == 0 {
+        return vec![0; count];
+    }
+    let per_long = 64 / bits as usize;
+    let mask = (1u64 << bits) - 1;
+    (0..count)
+        .map(|index| {
+            let long = data.get(index / per_long).copied().unwrap_or(0) as u64;
+            ((long >> ((index % per_long) * bits as usize)) & mask) as u16
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pack_values() {
+        let values = (0..20).collect::<Vec<u16>>();
+        let packed = pack_values(&values, 5);
+        // 12 values per long, with the last 4 bits of each unused
+        assert_eq!(packed.len(), 2);
+        assert_eq!(unpack_values(&packed, 5, values.len()), values);
+        assert!(pack_values(&values, 0).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_packed_array_encode() {
+        // The example from the protocol wiki's chunk format page, 5 bits per block
+        let values = [1, 2, 2, 3, 4, 4, 5, 6, 6, 4, 8, 0, 7, 4, 3, 13, 15, 16, 9, 14, 10, 12, 0, 2];
+        let packed = PackedArray::pack(&values, 5);
+        assert_eq!(
+            packed.longs(),
+            &[0x0020863148418841, 0x01018A7260F68C87u64 as i64]
+        );
+
+        let mut bytes = Vec::new();
+        packed.net_encode(&mut bytes).await.unwrap();
+        assert_eq!(bytes.len(), 1 + 16);
+        assert_eq!(bytes[..9], [2, 0x00, 0x20, 0x86, 0x31, 0x48, 0x41, 0x88, 0x41]);
+        assert_eq!(packed.unpack(values.len()), values);
+    }
+}
@@ -35,20 +35,9 @@ pub struct VarInt {
 
 impl VarInt {
     pub fn new(value: i32) -> Self {
-        let bytes_required = if value < 128 && value >= -128 {
-            1
-        } else if value < 16384 && value >= -16384 {
-            2
-        } else if value < 2097152 && value >= -2097152 {
-            3
-        } else if value < 268435456 && value >= -268435456 {
-            4
-        } else {
-            5
-        };
         VarInt {
             val: value,
-            len: bytes_required,
+            len: varint_len(value),
         }
     }
     pub fn get_val(&self) -> i32 {
@@ -75,8 +64,14 @@ impl VarInt {
         Err(CodecError::VarIntTooBig)
     }
 
+    /// Decodes a VarInt from the start of `bytes`, which is a lot quicker than [VarInt::read] when
+    /// the bytes are already in memory.
+    pub fn decode(bytes: &[u8]) -> Result<Self> {
+        let (val, len) = decode_varint(bytes)?;
+        Ok(VarInt { val, len })
+    }
+
     // Write a VarInt to the given cursor.
-    // Yoinked from valence: https://github.com/valence-rs/valence/blob/main/crates/valence_protocol/src/var_int.rs#L98
     pub async fn write<T>(&self, cursor: &mut T) -> Result<()>
     where
         T: AsyncWrite + Unpin,
@@ -131,39 +126,109 @@ mod adapters {
     }
 }
 
-// Write a VarInt to the given cursor.
-// Yoinked from valence: https://github.com/valence-rs/valence/blob/main/crates/valence_protocol/src/var_int.rs#L98
-pub async fn write_varint<T>(value: impl TryInto<i64>, cursor: &mut T) -> Result<()>
-where
-    T: AsyncWrite + Unpin,
-{
-    let val = value.try_into().map_err(|_| CodecError::DoubleConversion)?;
+pub const MAX_VARINT_LENGTH: usize = 5;
 
-    // We need to convert the value to a u64 to prevent overflow.
-    // The reason we don't just use TryInto<u64> is because negative values won't work.
-    let val = val as u64;
+/// How many bytes a VarInt takes, by how many leading zeros the value has as a u32. Negative
+/// values have none, so they always take the full 5.
+const VARINT_LENGTHS: [u8; 33] = {
+    let mut lengths = [1; 33];
+    let mut leading_zeros = 0;
+    while leading_zeros < 32 {
+        lengths[leading_zeros] = (32 - leading_zeros).div_ceil(7) as u8;
+        leading_zeros += 1;
+    }
+    lengths
+};
 
-    let stage1 = (val & 0x000000000000007f)
-        | ((val & 0x0000000000003f80) << 1)
-        | ((val & 0x00000000001fc000) << 2)
-        | ((val & 0x000000000fe00000) << 3)
-        | ((val & 0x00000000f0000000) << 4);
+/// How many bytes `value` takes as a VarInt.
+pub fn varint_len(value: i32) -> usize {
+    VARINT_LENGTHS[(value as u32).leading_zeros() as usize] as usize
+}
 
-    let leading = stage1.leading_zeros();
+/// Encodes a VarInt without any branching. It's in the first however many bytes of the array,
+/// the second value.
+pub fn encode_varint(value: i32) -> ([u8; MAX_VARINT_LENGTH], usize) {
+    let val = value as u32 as u64;
+
+    // Spread the 7 bit groups out to one a byte
+    let spread = (val & 0x7f)
+        | ((val & 0x3f80) << 1)
+        | ((val & 0x1fc000) << 2)
+        | ((val & 0xfe00000) << 3)
+        | ((val & 0xf0000000) << 4);
+
+    // Then set the continuation bit on every byte but the last
+    let len = varint_len(value);
+    let continuation = 0x80808080 & ((1 << ((len - 1) * 8)) - 1);
+
+    let mut bytes = [0; MAX_VARINT_LENGTH];
+    bytes.copy_from_slice(&(spread | continuation).to_le_bytes()[..MAX_VARINT_LENGTH]);
+    (bytes, len)
+}
 
-    let unused_bytes = (leading - 1) >> 3;
-    let bytes_needed = 8 - unused_bytes;
+/// Decodes a VarInt from the start of `bytes` without looping over them, returning it and how many
+/// bytes it took.
+pub fn decode_varint(bytes: &[u8]) -> Result<(i32, usize)> {
+    let available = bytes.len().min(MAX_VARINT_LENGTH);
+    let mut word = [0; 8];
+    word[..available].copy_from_slice(&bytes[..available]);
+    let word = u64::from_le_bytes(word);
+
+    // The first byte without its continuation bit is the last one. Missing bytes count as 0, so
+    // they'd end it too, which is caught below
+    let ends = !word & 0x8080808080;
+    if ends == 0 {
+        return Err(CodecError::VarIntTooBig);
+    }
+    let len = (ends.trailing_zeros() / 8 + 1) as usize;
+    if len > bytes.len() {
+        return Err(CodecError::Io(std::io::ErrorKind::UnexpectedEof.into()));
+    }
 
-    // set all but the last MSBs
-    let most_significant_bits = 0x8080808080808080;
-    let most_significant_bits_mask = 0xffffffffffffffff >> (((8 - bytes_needed + 1) << 3) - 1);
+    // Drop the bytes after it and squash the 7 bit groups back together
+    let word = word & (u64::MAX >> (64 - len * 8));
+    let val = (word & 0x7f)
+        | ((word >> 1) & 0x3f80)
+        | ((word >> 2) & 0x1fc000)
+        | ((word >> 3) & 0xfe00000)
+        | ((word >> 4) & 0xf0000000);
+    Ok((val as u32 as i32, len))
+}
 
-    let merged = stage1 | (most_significant_bits & most_significant_bits_mask);
-    let bytes = merged.to_le_bytes();
+/// Encodes all of `values` onto the end of `out`. Much quicker than writing them one at a time when
+/// there's a lot of them, e.g. a palette.
+pub fn encode_varints(values: impl IntoIterator<Item = i32>, out: &mut Vec<u8>) {
+    let values = values.into_iter();
+    out.reserve(values.size_hint().0);
+    for value in values {
+        let (bytes, len) = encode_varint(value);
+        out.extend_from_slice(&bytes[..len]);
+    }
+}
 
-    cursor
-        .write_all(unsafe { bytes.get_unchecked(..bytes_needed as usize) })
-        .await?;
+/// Decodes `count` VarInts one after the other from the start of `bytes`, returning them and how
+/// many bytes they took altogether.
+pub fn decode_varints(bytes: &[u8], count: usize) -> Result<(Vec<i32>, usize)> {
+    let mut values = Vec::with_capacity(count);
+    let mut position = 0;
+    for _ in 0..count {
+        let (value, len) = decode_varint(&bytes[position..])?;
+        values.push(value);
+        position += len;
+    }
+    Ok((values, position))
+}
+
+// Write a VarInt to the given cursor.
+pub async fn write_varint<T>(value: impl TryInto<i64>, cursor: &mut T) -> Result<()>
+where
+    T: AsyncWrite + Unpin,
+{
+    let val = value.try_into().map_err(|_| CodecError::DoubleConversion)?;
+
+    // Anything that doesn't fit is cut down to 32 bits, so negative values of any size work
+    let (bytes, len) = encode_varint(val as i32);
+    cursor.write_all(&bytes[..len]).await?;
 
     Ok(())
 }
@@ -225,4 +290,75 @@ mod tests {
         assert!(result.is_ok());
         assert_eq!(cursor.into_inner(), vec![0xff, 0xff, 0xff, 0xff, 0x0f]);
     }
+
+    #[test]
+    fn varint_lengths() {
+        for (value, len) in [
+            (0, 1),
+            (127, 1),
+            (128, 2),
+            (16383, 2),
+            (16384, 3),
+            (2097152, 4),
+        ] {
+            assert_eq!(VarInt::new(value).get_len(), len);
+        }
+        assert_eq!(VarInt::new(i32::MAX).get_len(), 5);
+        // Negative values always take all 5 bytes
+        assert_eq!(VarInt::new(-1).get_len(), 5);
+        assert_eq!(VarInt::new(i32::MIN).get_len(), 5);
+    }
+
+    #[tokio::test]
+    async fn encode_decode_matches_read_write() {
+        let values = [
+            0,
+            1,
+            127,
+            128,
+            255,
+            25565,
+            2097151,
+            2097152,
+            i32::MAX,
+            -1,
+            i32::MIN,
+        ];
+        for value in values {
+            let (bytes, len) = encode_varint(value);
+            let mut written = Vec::new();
+            write_varint(value, &mut written).await.unwrap();
+            assert_eq!(&bytes[..len], &written[..]);
+
+            assert_eq!(decode_varint(&written).unwrap(), (value, len));
+            let read = VarInt::read(&mut Cursor::new(&written)).await.unwrap();
+            assert_eq!(VarInt::decode(&written).unwrap(), read);
+            assert_eq!(read, VarInt::new(value));
+        }
+    }
+
+    #[test]
+    fn decode_varint_invalid_input() {
+        assert!(decode_varint(&[]).is_err());
+        assert!(decode_varint(&[0x80, 0x80]).is_err());
+        assert!(matches!(
+            decode_varint(&[0x80; 6]),
+            Err(CodecError::VarIntTooBig)
+        ));
+        // Whatever comes after it isn't touched
+        assert_eq!(decode_varint(&[0x01, 0x80, 0x80]).unwrap(), (1, 1));
+    }
+
+    #[test]
+    fn encode_decode_varints() {
+        let values = vec![1, 300, -1, 0, 2097151];
+        let mut bytes = Vec::new();
+        encode_varints(values.iter().copied(), &mut bytes);
+        bytes.push(0xff);
+
+        let (decoded, len) = decode_varints(&bytes, values.len()).unwrap();
+        assert_eq!(decoded, values);
+        assert_eq!(len, bytes.len() - 1);
+        assert!(decode_varints(&bytes[..len - 1], values.len()).is_err());
+    }
 }
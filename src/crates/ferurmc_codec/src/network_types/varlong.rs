@@ -173,6 +173,24 @@ mod tests {
         assert_eq!(result.unwrap(), Varlong::new(9223372036854775807));
     }
 
+    #[tokio::test]
+    async fn read_varlong_protocol_examples() {
+        // The examples on the protocol wiki
+        let examples: [(&[u8], i64); 4] = [
+            (&[0x7f], 127),
+            (&[0x80, 0x01], 128),
+            (&[0xff, 0xff, 0xff, 0xff, 0x07], 2147483647),
+            (
+                &[0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x01],
+                -9223372036854775808,
+            ),
+        ];
+        for (bytes, value) in examples {
+            let result = Varlong::read(&mut Cursor::new(bytes)).await;
+            assert_eq!(result.unwrap(), Varlong::new(value));
+        }
+    }
+
     #[tokio::test]
     async fn read_varlong_too_big() {
         let mut cursor = Cursor::new(vec![0xff; 9]);
@@ -1,3 +1,5 @@
+use crate::enc::NetEncode;
+
 #[tokio::test]
 async fn test_encode_bool() {
     let mut buf = Vec::new();
@@ -7,3 +9,17 @@ async fn test_encode_bool() {
     false.net_encode(&mut buf).await.unwrap();
     assert_eq!(buf, vec![0]);
 }
+
+#[tokio::test]
+async fn test_encode_uuid() {
+    let mut buf = Vec::new();
+    let uuid = uuid::Uuid::parse_str("069a79f4-44e9-4726-a5be-fca90e38aaf5").unwrap();
+    uuid.net_encode(&mut buf).await.unwrap();
+    assert_eq!(
+        buf,
+        vec![
+            0x06, 0x9a, 0x79, 0xf4, 0x44, 0xe9, 0x47, 0x26, 0xa5, 0xbe, 0xfc, 0xa9, 0x0e, 0x38,
+            0xaa, 0xf5
+        ]
+    );
+}
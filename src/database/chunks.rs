@@ -1,145 +1,35 @@
-use byteorder::LE;
-use heed::types::Bytes;
-use heed::{types::U64, Env};
-use moka::future::Cache;
+use dashmap::DashMap;
 use std::sync::Arc;
-use tokio::runtime::Handle;
-use tracing::{trace, warn};
+use std::time::Instant;
+use tokio::sync::{Mutex, OwnedMutexGuard};
+use tracing::trace;
 
-use super::spawn_blocking_db;
 use crate::database::encoding::ZstdCodec;
+use crate::database::storage::ChunkKey;
+use crate::net::metrics::METRICS;
 use crate::world::importing::SerializedChunk;
-use crate::{
-    database::Database, utils::error::Error, utils::hash::hash, world::chunk_format::Chunk,
-};
+use crate::{database::Database, utils::error::Error, world::chunk_format::Chunk};
 
 impl Database {
     // Close the database
     pub fn close(self) {
-        let token = self.db.prepare_for_closing();
-        token.wait();
+        self.storage.close();
     }
 
-    /// Fetch chunk from database
-    async fn get_chunk_from_database(db: &Env, key: &u64) -> Result<Option<Chunk>, heed::Error> {
-        let data = {
-            // Initialize read transaction and open chunks table
-            let ro_tx = db.read_txn()?;
-            let database = db
-                .open_database::<U64<LE>, Bytes>(&ro_tx, Some("chunks"))?
-                .expect("No table \"chunks\" found. The database should have been initialized");
-
-            // Attempt to fetch chunk from table
-            let data = database.get(&ro_tx, key)?;
-
-            data.map(|data| data.to_vec())
-        };
-
-        // Now, proceed with the async operation without holding `ro_tx`
-        if let Some(data) = data {
-            let chunk = ZstdCodec::decompress_data::<Chunk>(data.as_slice())
-                .await
-                .expect("Failed to decompress chunk");
-            Ok(Some(chunk))
-        } else {
-            Ok(None)
+    /// Fetch chunk from the storage
+    async fn get_chunk_from_storage(&self, key: &ChunkKey) -> Result<Option<Chunk>, Error> {
+        let start = Instant::now();
+        let data = self.storage.load(key).await?;
+        METRICS.database_reads.observe(start.elapsed());
+        match data {
+            Some(data) => Ok(Some(ZstdCodec::decompress_data::<Chunk>(&data).await?)),
+            None => Ok(None),
         }
     }
 
-    /// Insert a single chunk into database
-    fn insert_chunk_into_database(db: &Env, chunk: &Chunk) -> Result<(), heed::Error> {
-        // Initialize write transaction and open chunks table
-        let mut rw_tx = db.write_txn()?;
-        let database = db
-            .open_database::<U64<LE>, Bytes>(&rw_tx, Some("chunks"))?
-            .expect("No table \"chunks\" found. The database should have been initialized");
-
-        // Calculate key
-        let key = hash((chunk.dimension.as_ref().unwrap(), chunk.x_pos, chunk.z_pos));
-
-        let chunk = chunk.clone();
-        let chunk = Handle::current().block_on(async {
-            ZstdCodec::compress_data(chunk)
-                .await
-                .expect("Failed to compress chunk")
-        });
-
-        // Insert chunk
-        let res = database.put(&mut rw_tx, &key, chunk.as_slice());
-        rw_tx.commit()?;
-
-        res
-    }
-
-    /// Insert multiple chunks into database
-    /// TODO: Find better name/disambiguation
-    fn insert_chunks_into_database(
-        db: &Env,
-        chunks: &[SerializedChunk],
-    ) -> Result<(), heed::Error> {
-        // Initialize write transaction and open chunks table
-        let mut rw_tx = db.write_txn()?;
-        let database = db
-            .open_database::<U64<LE>, Bytes>(&rw_tx, Some("chunks"))?
-            .expect("No table \"chunks\" found. The database should have been initialized");
-
-        // Update page
-        for chunk in chunks {
-            // Calculate key
-            // let key = hash((chunk.dimension.as_ref().unwrap(), chunk.x_pos, chunk.z_pos));
-
-            // Insert chunk
-            database.put(&mut rw_tx, &chunk.hash(), chunk.data())?;
-        }
-        // Commit changes
-        rw_tx.commit()?;
-        Ok(())
-    }
-
-    #[allow(dead_code)]
-    async fn load_into_cache(&self, key: u64) -> Result<(), Error> {
-        Database::load_into_cache_standalone(self.db.clone(), self.cache.clone(), key).await
-    }
-
-    async fn load_into_cache_standalone(
-        db: Env,
-        cache: Arc<Cache<u64, Chunk>>,
-        key: u64,
-    ) -> Result<(), Error> {
-        // let tsk_db = db.clone();
-
-        let db = db.clone();
-        tokio::task::spawn(async move {
-            // Check cache
-            if cache.contains_key(&key) {
-                trace!("Chunk already exists in cache: {:X}", key);
-            }
-            // If not in cache then search in database
-            else if let Ok(chunk) = Self::get_chunk_from_database(&db, &key).await
-            /*spawn_blocking_db(tsk_db, move || Self::get_chunk_from_database(&db, &key))
-            .await
-            .unwrap()*/
-            {
-                if let Some(chunk) = chunk {
-                    cache.insert(key, chunk).await;
-                } else {
-                    warn!(
-                        "Chunk does not exist in db, can't load into cache: {:X}",
-                        key,
-                    );
-                }
-            }
-            // The chunk don't exist
-            else {
-                warn!("Error getting chunk: {:X}", key,);
-            }
-        })
-        .await?;
-        Ok(())
-    }
     /// Insert a chunk into the database <br>
     /// This will also insert the chunk into the cache <br>
-    /// If the chunk already exists, it will return an error
+    /// The chunk is written to disk on the next [flush](Database::flush)
     /// # Arguments
     /// * `value` - The chunk to insert
     /// # Returns
@@ -156,26 +46,11 @@ impl Database {
     ///
     /// ```
     pub async fn insert_chunk(&self, value: Chunk) -> Result<(), Error> {
-        // Calculate key of this chunk
-        // WARNING: This key wasn't supposed to include value.dimension in the tuple, but it was different from the key used in persistent database most likely a bug.
-        let key = hash((value.dimension.as_ref().unwrap(), value.x_pos, value.z_pos));
-
-        // Insert chunk into persistent database
-        let chunk = value.clone();
-        let db = self.db.clone();
-        let tsk_db = self.db.clone();
-        spawn_blocking_db(tsk_db, move || {
-            Self::insert_chunk_into_database(&db, &chunk)
-        })
-        .await
-        .unwrap()?;
-
-        // Insert into cache
-        self.cache.insert(key, value).await;
+        self.save_later(value).await;
         Ok(())
     }
 
-    /// Get a chunk from the database <br>
+    /// Get a chunk from the cache, or the database if it isn't cached <br>
     /// This will also insert the chunk into the cache <br>
     /// If the chunk does not exist, it will return None
     /// # Arguments
@@ -201,32 +76,28 @@ impl Database {
         z: i32,
         dimension: String,
     ) -> Result<Option<Chunk>, Error> {
-        // Calculate key of this chunk and clone database pointer
-        let key = hash((dimension, x, z));
-        let db = self.db.clone();
-
-        let res = Self::get_chunk_from_database(&db, &key).await?;
-
-        Ok(res)
+        // Calculate key of this chunk
+        let key = ChunkKey::new(&dimension, x, z);
+        let hash = key.hash();
 
-        /* // First check cache
-        if self.cache.contains_key(&key) {
-            Ok(self.cache.get(&key).await)
+        // First check cache, then the chunks that haven't been saved yet in case they got evicted
+        let cached = match self.cache.get(&hash).await {
+            Some(chunk) => Some(chunk),
+            None => self.dirty.get(&hash).map(|chunk| chunk.clone()),
+        };
+        if let Some(chunk) = cached {
+            self.record_cache_lookup(true);
+            return Ok(Some(chunk));
         }
-        // Attempt to get chunk from persistent database
-        else if let Some(chunk) = Self::get_chunk_from_database(&db, &key).await?
-            /*
-            spawn_blocking_db(tsk_db, move || Self::get_chunk_from_database(&db, &key))
-                .await
-                .unwrap()?*/
-        {
-            // self.cache.insert(key, chunk.clone()).await;
-            Ok(Some(chunk))
+        self.record_cache_lookup(false);
+
+        // Attempt to get chunk from persistent storage, keeping it around for next time
+        let res = self.get_chunk_from_storage(&key).await?;
+        if let Some(chunk) = &res {
+            self.cache.insert(hash, chunk.clone()).await;
         }
-        // Chunk do not exist
-        else {
-            Ok(None)
-        }*/
+
+        Ok(res)
     }
 
     /// Check if a chunk exists in the database
@@ -248,19 +119,16 @@ impl Database {
     ///
     /// ```
     pub async fn chunk_exists(&self, x: i32, z: i32, dimension: String) -> Result<bool, Error> {
-        // Calculate key and copy database pointer
-        let key = hash((dimension, x, z));
-        let db = self.db.clone();
+        // Calculate key
+        let key = ChunkKey::new(&dimension, x, z);
+        let hash = key.hash();
 
         // Check first cache
-        if self.cache.contains_key(&key) {
+        if self.cache.contains_key(&hash) || self.dirty.contains_key(&hash) {
             Ok(true)
         // Else check persistent database and load it into cache
         } else {
-            /*let res = spawn_blocking_db(tsk_db, move || Self::get_chunk_from_database(&db, &key))
-            .await
-            .unwrap();*/
-            let Some(res) = Self::get_chunk_from_database(&db, &key).await? else {
+            let Some(res) = self.get_chunk_from_storage(&key).await? else {
                 return Ok(false);
             };
 
@@ -268,7 +136,7 @@ impl Database {
             // This has been replaced by directly loading the queried chunk into cache
 
             // Load chunk into cache
-            self.cache.insert(key, res.clone()).await;
+            self.cache.insert(hash, res.clone()).await;
             Ok(true)
 
             /* match res {
@@ -286,7 +154,7 @@ impl Database {
 
     /// Update a chunk in the database <br>
     /// This will also update the chunk in the cache <br>
-    /// If the chunk does not exist, it will return an error
+    /// The chunk is written to disk on the next [flush](Database::flush)
     /// # Arguments
     /// * `value` - The chunk to update
     /// # Returns
@@ -303,23 +171,92 @@ impl Database {
     ///
     /// ```
     pub async fn update_chunk(&self, value: Chunk) -> Result<(), Error> {
+        self.save_later(value).await;
+        Ok(())
+    }
+
+    /// Puts the new state of a chunk in the cache and marks it as needing to be written to disk.
+    async fn save_later(&self, value: Chunk) {
         // Calculate key of this chunk
-        // WARNING: This key wasn't supposed to include value.dimension in the tuple, but it was different from the key used in persistent database most likely a bug.
-        let key = hash((value.dimension.as_ref().unwrap(), value.x_pos, value.z_pos));
-
-        // Insert new chunk state into persistent database
-        let chunk = value.clone();
-        let db = self.db.clone();
-        let tsk_db = self.db.clone();
-        spawn_blocking_db(tsk_db, move || {
-            Self::insert_chunk_into_database(&db, &chunk)
-        })
-        .await
-        .unwrap()?;
+        let key = key_of(&value).hash();
 
-        // Insert new chunk state into cache
+        self.dirty.insert(key, value.clone());
         self.cache.insert(key, value).await;
-        Ok(())
+    }
+
+    /// Writes every chunk that has changed since the last flush to disk, in a single transaction.
+    /// Returns how many chunks were written.
+    ///
+    /// If writing fails the chunks stay marked as changed, so the next flush tries them again.
+    pub async fn flush(&self) -> Result<usize, Error> {
+        let keys: Vec<u64> = self.dirty.iter().map(|entry| *entry.key()).collect();
+        if keys.is_empty() {
+            return Ok(0);
+        }
+
+        let mut chunks = Vec::with_capacity(keys.len());
+        for key in keys {
+            // Anything changed after this gets marked again and goes in the next flush
+            if let Some((key, chunk)) = self.dirty.remove(&key) {
+                chunks.push((key, chunk));
+            }
+        }
+
+        let mut serialized = Vec::with_capacity(chunks.len());
+        for (_, chunk) in &chunks {
+            match ZstdCodec::compress_data(chunk.clone()).await {
+                Ok(data) => serialized.push(SerializedChunk::new(key_of(chunk), data)),
+                Err(e) => {
+                    self.mark_dirty_again(chunks);
+                    return Err(e);
+                }
+            }
+        }
+
+        let count = serialized.len();
+        let start = Instant::now();
+        let result = self.storage.save(serialized).await;
+        METRICS.database_writes.observe(start.elapsed());
+        if let Err(e) = result {
+            self.mark_dirty_again(chunks);
+            return Err(e);
+        }
+
+        trace!("Flushed {} chunks to disk", count);
+        Ok(count)
+    }
+
+    /// How many chunks are waiting to be written to disk.
+    pub fn dirty_chunks(&self) -> usize {
+        self.dirty.len()
+    }
+
+    /// Puts chunks that failed to save back, unless they've changed again since.
+    fn mark_dirty_again(&self, chunks: Vec<(u64, Chunk)>) {
+        for (key, chunk) in chunks {
+            self.dirty.entry(key).or_insert(chunk);
+        }
+    }
+
+    /// Locks a chunk against other edits until the guard is dropped, so getting it, changing it
+    /// and [updating](Database::update_chunk) it can't be interleaved with another edit. Nothing
+    /// should hold more than one chunk's lock at a time.
+    pub async fn lock_chunk(&self, x: i32, z: i32, dimension: &str) -> OwnedMutexGuard<()> {
+        let key = ChunkKey::new(dimension, x, z).hash();
+        lock_key(&self.chunk_locks, key).await
+    }
+
+    /// Locks a chunk against being generated twice at once, see
+    /// [get_or_generate_chunk](crate::world::generation::get_or_generate_chunk). Separate from
+    /// [Database::lock_chunk] since chunks get generated while they're locked for editing.
+    pub async fn lock_chunk_generation(
+        &self,
+        x: i32,
+        z: i32,
+        dimension: &str,
+    ) -> OwnedMutexGuard<()> {
+        let key = ChunkKey::new(dimension, x, z).hash();
+        lock_key(&self.generation_locks, key).await
     }
 
     /// Batch insert chunks into the database <br>
@@ -341,48 +278,31 @@ impl Database {
     ///
     /// ```
     pub async fn batch_insert(&self, values: Vec<SerializedChunk>) -> Result<(), Error> {
-        // Clone database pointer
-        let db = self.db.clone();
-        let tsk_db = self.db.clone();
-
-        // Calculate all keys
-        /*      let keys = values
-                    .iter()
-                    .map(|v| hash((v.dimension.as_ref().unwrap_or_else(|| panic!("Invalid chunk @ ({},{})", v.x_pos, v.z_pos)), v.x_pos, v.z_pos)))
-                    .collect::<Vec<u64>>();
-        */
-        // let keys = values.iter().map(|v| v.hash()).collect::<Vec<u64>>();
-
-        // WARNING: The previous logic was to first insert in database and then insert in cache using load_into_cache fn.
-        // This has been modified to avoid having to query database while we already have the data available.
-        // First insert into cache
-
-        // TODO: Renable cache. Currently disabled because we only get serialized bytes with the hash.
-        // to save in the database
-        /*for (chunk) in values.iter() {
-            let cache = self.cache.clone();
-            let db = self.db.clone();
-            let key = chunk.hash();
-            let chunk = chunk.data().clone();
-            tokio::spawn(async move {
-                cache.insert(key, chunk).await;
-                if let Err(e) = Database::load_into_cache_standalone(db, cache, key).await {
-                    warn!("Error inserting chunk into database: {:?}", e);
-                }
-            });
-        }
-        */
-        // Then insert into persistent database
-        spawn_blocking_db(tsk_db, move || {
-            Self::insert_chunks_into_database(&db, &values)
-        })
-        .await
-        .unwrap()?;
-
-        Ok(())
+        // These only come as serialized bytes, so they skip the cache and go straight to storage
+        let start = Instant::now();
+        let result = self.storage.save(values).await;
+        METRICS.database_writes.observe(start.elapsed());
+        result
     }
 }
 
+async fn lock_key(locks: &DashMap<u64, Arc<Mutex<()>>>, key: u64) -> OwnedMutexGuard<()> {
+    // Forget the locks that nobody is holding or waiting on
+    locks.retain(|_, lock| Arc::strong_count(lock) > 1);
+
+    let lock = Arc::clone(&locks.entry(key).or_default());
+    lock.lock_owned().await
+}
+
+/// Where a chunk goes in the storage.
+fn key_of(chunk: &Chunk) -> ChunkKey {
+    let dimension = chunk
+        .dimension
+        .as_deref()
+        .unwrap_or_else(|| panic!("Invalid chunk @ ({},{})", chunk.x_pos, chunk.z_pos));
+    ChunkKey::new(dimension, chunk.x_pos, chunk.z_pos)
+}
+
 #[tokio::test]
 #[ignore]
 async fn dump_chunk() {
@@ -403,3 +323,58 @@ async fn dump_chunk() {
     let mut writer = std::io::BufWriter::new(outfile);
     chunk.nbt_serialize(&mut writer).unwrap();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::storage::MemoryStorage;
+    use crate::world::generation::flat::FlatGenerator;
+    use crate::world::generation::WorldGenerator;
+
+    #[tokio::test]
+    async fn test_write_behind() {
+        let database = Database::new(Box::new(MemoryStorage::new()), 64 * 1024 * 1024);
+        let chunk = FlatGenerator::default()
+            .generate_chunk(3, 4, "overworld")
+            .unwrap();
+        database.insert_chunk(chunk.clone()).await.unwrap();
+        assert_eq!(database.dirty_chunks(), 1);
+
+        // Served from memory before it's been written
+        let found = database.get_chunk(3, 4, "overworld".to_string()).await.unwrap();
+        assert_eq!(found, Some(chunk));
+        assert_eq!(database.cache_stats().hits, 1);
+        assert!(database.chunk_exists(3, 4, "overworld".to_string()).await.unwrap());
+
+        assert_eq!(database.flush().await.unwrap(), 1);
+        assert_eq!(database.dirty_chunks(), 0);
+        let stats = database.stats().await.unwrap();
+        assert_eq!(stats.dimensions["overworld"].chunks, 1);
+
+        assert_eq!(database.get_chunk(0, 0, "overworld".to_string()).await.unwrap(), None);
+        assert_eq!(database.cache_stats().misses, 1);
+    }
+
+    #[tokio::test]
+    async fn test_chunk_locks() {
+        let database = Database::new(Box::new(MemoryStorage::new()), 64 * 1024 * 1024);
+        let guard = database.lock_chunk(1, 2, "overworld").await;
+        // Other chunks aren't held up
+        drop(database.lock_chunk(1, 3, "nether").await);
+        drop(database.lock_chunk(1, 2, "nether").await);
+        let waiting = tokio::time::timeout(
+            std::time::Duration::from_millis(20),
+            database.lock_chunk(1, 2, "overworld"),
+        );
+        assert!(waiting.await.is_err());
+
+        // Only the one still held is kept once another chunk is locked
+        assert_eq!(database.chunk_locks.len(), 1);
+        drop(guard);
+        drop(database.lock_chunk(5, 5, "end").await);
+        assert_eq!(database.chunk_locks.len(), 1);
+        assert!(database
+            .chunk_locks
+            .contains_key(&ChunkKey::new("end", 5, 5).hash()));
+    }
+}
@@ -0,0 +1,370 @@
+use byteorder::LE;
+use heed::types::{Bytes, Str, U64};
+use heed::{CompactionOption, Env, EnvFlags, EnvOpenOptions, MdbError};
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::path::Path;
+use std::sync::{Arc, LazyLock, Mutex, OnceLock, RwLock};
+use tokio::fs;
+use tokio::sync::oneshot;
+use tracing::{debug, info, warn};
+
+use crate::database::maintenance::{dimension_of, DatabaseStats, DimensionStats};
+use crate::database::storage::{ChunkKey, WorldStorage};
+use crate::utils::error::Error;
+use crate::world::importing::SerializedChunk;
+
+const LMDB_MIN_PAGE_SIZE: usize = 1800 * 1024usize.pow(2); // 1800MB
+const LMDB_PAGE_SIZE_INCREMENT: usize = 250 * 1024usize.pow(2); // 250MB
+const LMDB_MAX_DBS: u32 = 10;
+
+/// The compacted copy of the database, swapped in the next time the server starts.
+const COMPACTED_FILE: &str = "data.mdb.compact";
+/// The transaction the compacted copy was made at, so it's only used if nothing changed since.
+const COMPACTED_TXN_FILE: &str = "data.mdb.compact-txn";
+const DATA_FILE: &str = "data.mdb";
+
+// Database threadpool
+static LMDB_THREADPOOL: OnceLock<ThreadPool> = OnceLock::new();
+
+// Global size
+static LMDB_PAGE_SIZE: LazyLock<Arc<Mutex<usize>>> =
+    LazyLock::new(|| Arc::new(Mutex::new(LMDB_MIN_PAGE_SIZE)));
+static LMDB_READER_SYNC: LazyLock<Arc<RwLock<()>>> = LazyLock::new(|| Arc::new(RwLock::new(())));
+
+/// Stores chunks in an LMDB database, keyed by a hash of where they are. This is the default.
+pub struct LmdbStorage {
+    db: Env,
+}
+
+impl LmdbStorage {
+    /// Opens the database in `world_path`, creating it if it doesn't exist yet.
+    pub async fn open(world_path: &Path) -> Result<Self, Error> {
+        debug!("Opening database at {}", world_path.display());
+
+        if !fs::try_exists(world_path).await? {
+            fs::create_dir_all(world_path).await?;
+        }
+
+        // Swap in the compacted copy from /db compact, if there is one
+        let lmdb = match apply_compaction(open_environment(world_path))? {
+            Some(lmdb) => lmdb,
+            None => open_environment(world_path),
+        };
+
+        // Start database threadpool
+        LMDB_THREADPOOL.get_or_init(|| {
+            ThreadPoolBuilder::new()
+                .num_threads(num_cpus::get() / 2)
+                .build()
+                .unwrap()
+        });
+
+        // Check if database is built. Otherwise, initialize it
+        let mut rw_tx = lmdb.write_txn()?;
+        if lmdb
+            // .open_database::<U64<LE>, Zstd<Chunk>>(&rw_tx, Some("chunks"))
+            .open_database::<U64<LE>, Bytes>(&rw_tx, Some("chunks"))?
+            .is_none()
+        {
+            lmdb.create_database::<U64<LE>, Bytes>(&mut rw_tx, Some("chunks"))
+                .expect("Unable to create database");
+        }
+        if lmdb
+            .open_database::<Str, Bytes>(&rw_tx, Some("meta"))?
+            .is_none()
+        {
+            lmdb.create_database::<Str, Bytes>(&mut rw_tx, Some("meta"))
+                .expect("Unable to create database");
+        }
+        // `entities` table to be added, but needs the type to do so
+
+        rw_tx.commit()?;
+
+        Ok(Self { db: lmdb })
+    }
+
+    /// Fetch chunk from database
+    fn get_chunk_from_database(db: &Env, key: &u64) -> Result<Option<Vec<u8>>, heed::Error> {
+        // Initialize read transaction and open chunks table
+        let ro_tx = db.read_txn()?;
+        let database = db
+            .open_database::<U64<LE>, Bytes>(&ro_tx, Some("chunks"))?
+            .expect("No table \"chunks\" found. The database should have been initialized");
+
+        // Attempt to fetch chunk from table
+        let data = database.get(&ro_tx, key)?;
+
+        Ok(data.map(|data| data.to_vec()))
+    }
+
+    /// Insert multiple chunks into database, in one transaction so a crash part way through leaves
+    /// none of them written rather than some
+    /// TODO: Find better name/disambiguation
+    fn insert_chunks_into_database(
+        db: &Env,
+        chunks: &[SerializedChunk],
+    ) -> Result<(), heed::Error> {
+        // Initialize write transaction and open chunks table
+        let mut rw_tx = db.write_txn()?;
+        let database = db
+            .open_database::<U64<LE>, Bytes>(&rw_tx, Some("chunks"))?
+            .expect("No table \"chunks\" found. The database should have been initialized");
+
+        // Update page
+        for chunk in chunks {
+            // Calculate key
+            // let key = hash((chunk.dimension.as_ref().unwrap(), chunk.x_pos, chunk.z_pos));
+
+            // Insert chunk
+            database.put(&mut rw_tx, &chunk.hash(), chunk.data())?;
+        }
+        // Commit changes
+        rw_tx.commit()?;
+        Ok(())
+    }
+
+    fn get_meta_from_database(db: &Env, key: &str) -> Result<Option<Vec<u8>>, heed::Error> {
+        let ro_tx = db.read_txn()?;
+        let database = db
+            .open_database::<Str, Bytes>(&ro_tx, Some("meta"))?
+            .expect("No table \"meta\" found. The database should have been initialized");
+        let data = database.get(&ro_tx, key)?;
+        Ok(data.map(|data| data.to_vec()))
+    }
+
+    fn insert_meta_into_database(db: &Env, key: &str, data: &[u8]) -> Result<(), heed::Error> {
+        let mut rw_tx = db.write_txn()?;
+        let database = db
+            .open_database::<Str, Bytes>(&rw_tx, Some("meta"))?
+            .expect("No table \"meta\" found. The database should have been initialized");
+        database.put(&mut rw_tx, key, data)?;
+        rw_tx.commit()?;
+        Ok(())
+    }
+
+    fn collect_stats(db: &Env) -> Result<DatabaseStats, heed::Error> {
+        let ro_tx = db.read_txn()?;
+        let database = db
+            .open_database::<U64<LE>, Bytes>(&ro_tx, Some("chunks"))?
+            .expect("No table \"chunks\" found. The database should have been initialized");
+
+        // The keys are hashes, so the dimension has to come from the chunks themselves
+        let mut dimensions: BTreeMap<String, DimensionStats> = BTreeMap::new();
+        for entry in database.iter(&ro_tx)? {
+            let (_, data) = entry?;
+            let stats = dimensions.entry(dimension_of(data)).or_default();
+            stats.chunks += 1;
+            stats.bytes += data.len() as u64;
+        }
+
+        Ok(DatabaseStats {
+            file_size: db.real_disk_size()?,
+            used_size: db.non_free_pages_size()?,
+            dimensions,
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl WorldStorage for LmdbStorage {
+    async fn load(&self, key: &ChunkKey) -> Result<Option<Vec<u8>>, Error> {
+        Ok(Self::get_chunk_from_database(&self.db, &key.hash())?)
+    }
+
+    async fn save(&self, chunks: Vec<SerializedChunk>) -> Result<(), Error> {
+        let db = self.db.clone();
+        let tsk_db = self.db.clone();
+        spawn_blocking_db(tsk_db, move || {
+            Self::insert_chunks_into_database(&db, &chunks)
+        })
+        .await
+        .unwrap()?;
+        Ok(())
+    }
+
+    async fn load_meta(&self, key: &str) -> Result<Option<Vec<u8>>, Error> {
+        Ok(Self::get_meta_from_database(&self.db, key)?)
+    }
+
+    async fn save_meta(&self, key: &str, data: Vec<u8>) -> Result<(), Error> {
+        let db = self.db.clone();
+        let tsk_db = self.db.clone();
+        let key = key.to_string();
+        spawn_blocking_db(tsk_db, move || {
+            Self::insert_meta_into_database(&db, &key, &data)
+        })
+        .await
+        .unwrap()?;
+        Ok(())
+    }
+
+    async fn stats(&self) -> Result<DatabaseStats, Error> {
+        let db = self.db.clone();
+        let tsk_db = self.db.clone();
+        let stats = spawn_blocking_db(tsk_db, move || Self::collect_stats(&db))
+            .await
+            .unwrap()?;
+        Ok(stats)
+    }
+
+    /// Makes a compacted copy of the database without the free pages. LMDB files never shrink
+    /// while they're open, so the copy replaces the database the next time the server starts, as
+    /// long as nothing has been saved since.
+    async fn compact(&self) -> Result<(u64, u64), Error> {
+        let directory = self.db.path().to_path_buf();
+        let compacted = directory.join(COMPACTED_FILE);
+        if compacted.exists() {
+            std::fs::remove_file(&compacted)?;
+        }
+
+        let txn = self.db.info().last_txn_id;
+        let db = self.db.clone();
+        let tsk_db = self.db.clone();
+        let path = compacted.clone();
+        let size = spawn_blocking_db(tsk_db, move || {
+            let file = db.copy_to_file(&path, CompactionOption::Enabled)?;
+            Ok(file.metadata()?.len())
+        })
+        .await
+        .unwrap()?;
+        std::fs::write(directory.join(COMPACTED_TXN_FILE), txn.to_string())?;
+
+        Ok((self.db.real_disk_size()?, size))
+    }
+
+    fn close(self: Box<Self>) {
+        let token = self.db.prepare_for_closing();
+        token.wait();
+    }
+
+    fn name(&self) -> &'static str {
+        "lmdb"
+    }
+}
+
+fn open_environment(world_path: &Path) -> Env {
+    // Database Options
+    let mut opts = EnvOpenOptions::new();
+    opts.max_readers(num_cpus::get() as u32)
+        .map_size(LMDB_MIN_PAGE_SIZE)
+        .max_dbs(LMDB_MAX_DBS);
+
+    // Open database (This operation is safe as we assume no other process touched the database)
+    unsafe {
+        opts.flags(EnvFlags::WRITE_MAP | EnvFlags::NO_SYNC)
+            .open(world_path)
+            .expect("Unable to open LMDB environment located at {world_path:?}")
+    }
+}
+
+/// Replaces the database with the copy made by [Database::compact], if there is one. Returns the
+/// environment back if there was nothing to do, or `None` if it was closed to swap the files and
+/// needs opening again.
+fn apply_compaction(env: Env) -> Result<Option<Env>, Error> {
+    let directory = env.path().to_path_buf();
+    let compacted = directory.join(COMPACTED_FILE);
+    let txn_file = directory.join(COMPACTED_TXN_FILE);
+    if !compacted.exists() {
+        return Ok(Some(env));
+    }
+
+    let compacted_at = std::fs::read_to_string(&txn_file)
+        .ok()
+        .and_then(|txn| txn.trim().parse::<usize>().ok());
+    if compacted_at != Some(env.info().last_txn_id) {
+        warn!("The database has changed since it was compacted, discarding the compacted copy");
+        remove_compacted(&directory)?;
+        return Ok(Some(env));
+    }
+
+    let before = env.real_disk_size()?;
+    env.prepare_for_closing().wait();
+    std::fs::rename(&compacted, directory.join(DATA_FILE))?;
+    std::fs::remove_file(&txn_file)?;
+    let after = std::fs::metadata(directory.join(DATA_FILE))?.len();
+    info!(
+        "Swapped in the compacted database, {} KB -> {} KB",
+        before / 1024,
+        after / 1024
+    );
+    Ok(None)
+}
+
+fn remove_compacted(directory: &Path) -> Result<(), Error> {
+    for file in [COMPACTED_FILE, COMPACTED_TXN_FILE] {
+        let path = directory.join(file);
+        if path.exists() {
+            std::fs::remove_file(path)?;
+        }
+    }
+    Ok(())
+}
+
+/// LMDB will follow a linear growth as opposed to MDBX which
+/// uses a geometric growth.
+pub(super) fn new_page_size(old_size: usize) -> usize {
+    old_size + LMDB_PAGE_SIZE_INCREMENT
+}
+
+/// Spawn a blocking task to interact with the database
+/// This is used to prevent the database from being blocked
+/// by a single thread
+///
+/// # Arguments
+///
+/// * `db` - The database environment
+/// * `f` - The function to execute
+///
+/// # Returns
+///
+/// A future that resolves to the result of the function
+pub(super) fn spawn_blocking_db<F, R>(
+    db: Env,
+    f: F,
+) -> impl Future<Output = Result<Result<R, heed::Error>, oneshot::error::RecvError>>
+where
+    F: Fn() -> Result<R, heed::Error> + Send + 'static,
+    R: Send + 'static + std::fmt::Debug,
+{
+    let (tx, res) = oneshot::channel::<Result<R, heed::Error>>();
+
+    let pool = LMDB_THREADPOOL.get().unwrap();
+    pool.spawn(move || {
+
+        let read_lock = LMDB_READER_SYNC.read()
+            .expect("Database RWLock has been poisoned. A thread should have crashed somewhere.");
+
+        let mut res = f();
+        if let Err(heed::Error::Mdb(MdbError::MapFull)) = res {
+
+            warn!("Database page is full. Resizing...");
+
+            drop(read_lock);
+
+            let _resize_guard = LMDB_READER_SYNC.write()
+                .expect("Database RWLock has been poisoned. A thread should have crashed somewhere.");
+
+            let mut global_size_lock = LMDB_PAGE_SIZE.lock().unwrap();
+            let old_size = *global_size_lock;
+            *global_size_lock = new_page_size(old_size);
+            unsafe { db.resize(*global_size_lock).expect("Unable to resize LMDB environment.") };
+
+            tracing::info!("Successfully resized LMDB page from {} MiB to {} MiB", old_size / 1024usize.pow(2), *global_size_lock / 1024usize.pow(2));
+
+            drop(global_size_lock);
+            drop(_resize_guard);
+
+            res = f();
+        } else {
+            drop(read_lock)
+        }
+
+        if tx.send(res).is_err() {
+            warn!("A database task has been unable to send its result because the receiver at other end have closed.")
+        }
+    });
+
+    res
+}
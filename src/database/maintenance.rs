@@ -0,0 +1,88 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use bincode::config::standard;
+
+use crate::database::Database;
+use crate::utils::error::Error;
+
+/// How much space the chunks of one dimension take up in the database.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct DimensionStats {
+    pub chunks: u64,
+    /// The size of the stored chunks, without any database overhead.
+    pub bytes: u64,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct DatabaseStats {
+    /// How much space the database takes up on disk.
+    pub file_size: u64,
+    /// How much of that is in use, the rest is space left behind by deleted or moved data.
+    pub used_size: u64,
+    pub dimensions: BTreeMap<String, DimensionStats>,
+}
+
+impl fmt::Display for DatabaseStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Database is {} KB, {} KB in use",
+            self.file_size / 1024,
+            self.used_size / 1024
+        )?;
+        for (dimension, stats) in &self.dimensions {
+            write!(
+                f,
+                "\n{}: {} chunks, {} KB",
+                dimension,
+                stats.chunks,
+                stats.bytes / 1024
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl Database {
+    /// Counts the chunks and their size in each dimension. This can mean reading every chunk, so
+    /// it can take a while on big worlds.
+    pub async fn stats(&self) -> Result<DatabaseStats, Error> {
+        self.storage.stats().await
+    }
+
+    /// Writes any changed chunks, then frees up the space old ones left behind, if the storage
+    /// supports it. Returns the size before and after.
+    pub async fn compact(&self) -> Result<(u64, u64), Error> {
+        self.flush().await?;
+        self.storage.compact().await
+    }
+}
+
+/// Reads just the dimension from a stored chunk. It's the first field of
+/// [Chunk](crate::world::chunk_format::Chunk), so the rest doesn't need decoding.
+pub(super) fn dimension_of(data: &[u8]) -> String {
+    match bincode::decode_from_slice::<Option<String>, _>(data, standard()) {
+        Ok((Some(dimension), _)) => dimension,
+        _ => "unknown".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::database::encoding::ZstdCodec;
+    use crate::world::generation::flat::FlatGenerator;
+    use crate::world::generation::WorldGenerator;
+
+    #[tokio::test]
+    async fn test_dimension_of() {
+        let chunk = FlatGenerator::default()
+            .generate_chunk(0, 0, "the_nether")
+            .unwrap();
+        let data = ZstdCodec::compress_data(chunk).await.unwrap();
+        assert_eq!(dimension_of(&data), "the_nether");
+        assert_eq!(dimension_of(&[]), "unknown");
+    }
+}
@@ -1,50 +1,150 @@
-use byteorder::LE;
+use dashmap::DashMap;
 use deepsize::DeepSizeOf;
 use futures::FutureExt;
-use heed::types::{Bytes, U64};
-use heed::{Env as LMDBDatabase, Env, EnvFlags, EnvOpenOptions, MdbError};
 use moka::notification::{ListenerFuture, RemovalCause};
-use rayon::{ThreadPool, ThreadPoolBuilder};
 use std::env;
-use std::future::Future;
+use std::fmt;
 use std::path::PathBuf;
-use std::sync::{Arc, LazyLock, Mutex, OnceLock, RwLock};
-use std::time::Duration;
-use tokio::fs;
-use tokio::sync::oneshot;
-use tracing::{debug, info, trace, warn};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use tracing::{info, trace};
 
+use crate::database::lmdb::LmdbStorage;
+use crate::database::region::RegionStorage;
+use crate::database::storage::{MemoryStorage, WorldStorage};
 use crate::utils::config::get_global_config;
 use crate::utils::error::Error;
 
 use crate::world::chunk_format::Chunk;
 pub mod chunks;
 pub(crate) mod encoding;
-
-const LMDB_MIN_PAGE_SIZE: usize = 1800 * 1024usize.pow(2); // 1800MB
-const LMDB_PAGE_SIZE_INCREMENT: usize = 250 * 1024usize.pow(2); // 250MB
-const LMDB_MAX_DBS: u32 = 10;
-
-// Database threadpool
-static LMDB_THREADPOOL: OnceLock<ThreadPool> = OnceLock::new();
-
-// Global size
-static LMDB_PAGE_SIZE: LazyLock<Arc<Mutex<usize>>> =
-    LazyLock::new(|| Arc::new(Mutex::new(LMDB_MIN_PAGE_SIZE)));
-static LMDB_READER_SYNC: LazyLock<Arc<RwLock<()>>> = LazyLock::new(|| Arc::new(RwLock::new(())));
+pub mod lmdb;
+pub mod maintenance;
+pub mod players;
+pub mod region;
+pub mod storage;
+pub mod world_meta;
 
 /// Global database structure
 ///
-/// Internally contain a handle to the persistent database and a
+/// Internally contain a handle to the persistent storage and a
 /// cache for all in-memory updates
 pub struct Database {
-    db: LMDBDatabase,
+    storage: Box<dyn WorldStorage>,
     cache: Arc<moka::future::Cache<u64, Chunk>>,
+    cache_hits: AtomicU64,
+    cache_misses: AtomicU64,
+    /// Chunks that have changed since they were last written to disk. Kept separately from the
+    /// cache so they can't be evicted before they're saved. See [Database::flush].
+    dirty: DashMap<u64, Chunk>,
+    /// Whether chunks and players get saved periodically, turned off with `/save-off`.
+    autosave: AtomicBool,
+    /// One lock for each chunk that's being edited, see [Database::lock_chunk].
+    chunk_locks: DashMap<u64, Arc<tokio::sync::Mutex<()>>>,
+    /// One lock for each chunk that's being generated, see [Database::lock_chunk_generation].
+    generation_locks: DashMap<u64, Arc<tokio::sync::Mutex<()>>>,
+}
+
+/// How well the chunk cache is doing, see [Database::cache_stats].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    /// Chunks in the cache right now.
+    pub entries: u64,
+    /// Roughly how much memory the cached chunks use, in bytes.
+    pub size: u64,
+}
+
+impl CacheStats {
+    /// The fraction of chunk lookups that didn't have to go to the database, from 0 to 1.
+    pub fn hit_rate(&self) -> f64 {
+        let total = self.hits + self.misses;
+        if total == 0 {
+            return 0.0;
+        }
+        self.hits as f64 / total as f64
+    }
+}
+
+impl fmt::Display for CacheStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} chunks ({} KB) cached, {:.1}% hit rate ({} hits, {} misses)",
+            self.entries,
+            self.size / 1024,
+            self.hit_rate() * 100.0,
+            self.hits,
+            self.misses
+        )
+    }
+}
+
+impl Database {
+    /// A database in front of the given storage, caching up to `cache_size` bytes of chunks.
+    pub fn new(storage: Box<dyn WorldStorage>, cache_size: u64) -> Self {
+        // Chunks are weighed by their size in memory, so the capacity is in bytes, and the least
+        // recently used ones go first once it's full.
+        let cache = moka::future::Cache::builder()
+            .async_eviction_listener(evict_chunk)
+            .weigher(|_, v: &Chunk| v.deep_size_of().try_into().unwrap_or(u32::MAX))
+            .eviction_policy(moka::policy::EvictionPolicy::lru())
+            .max_capacity(cache_size)
+            .build();
+
+        Self {
+            storage,
+            cache: Arc::new(cache),
+            cache_hits: AtomicU64::new(0),
+            cache_misses: AtomicU64::new(0),
+            dirty: DashMap::new(),
+            autosave: AtomicBool::new(true),
+            chunk_locks: DashMap::new(),
+            generation_locks: DashMap::new(),
+        }
+    }
+
+    /// Which kind of storage the chunks are kept in, e.g. `lmdb`.
+    pub fn storage_name(&self) -> &'static str {
+        self.storage.name()
+    }
+
+    /// Hit rate and size of the chunk cache since the server started.
+    pub fn cache_stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.cache_hits.load(Ordering::Relaxed),
+            misses: self.cache_misses.load(Ordering::Relaxed),
+            entries: self.cache.entry_count(),
+            size: self.cache.weighted_size(),
+        }
+    }
+
+    /// Whether the [ChunkFlusher](crate::net::systems::chunk_flusher::ChunkFlusher) and
+    /// [PlayerSaver](crate::net::systems::player_saver::PlayerSaver) save anything. Everything is
+    /// still saved on shutdown and when players leave.
+    pub fn autosave_enabled(&self) -> bool {
+        self.autosave.load(Ordering::Relaxed)
+    }
+
+    /// Turns periodic saving on or off, returning whether it was on before.
+    pub fn set_autosave(&self, enabled: bool) -> bool {
+        self.autosave.swap(enabled, Ordering::Relaxed)
+    }
+
+    fn record_cache_lookup(&self, hit: bool) {
+        let counter = if hit {
+            &self.cache_hits
+        } else {
+            &self.cache_misses
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
 }
 
 fn evict_chunk(_key: Arc<u64>, value: Chunk, cause: RemovalCause) -> ListenerFuture {
     async move {
-        if cause == RemovalCause::Expired {
+        if cause == RemovalCause::Size {
             trace!(
                 "Evicting chunk from cache: {}, {}",
                 value.x_pos,
@@ -55,8 +155,9 @@ fn evict_chunk(_key: Arc<u64>, value: Chunk, cause: RemovalCause) -> ListenerFut
     .boxed()
 }
 
-/// Start database
-pub async fn start_database() -> Result<Database, Error> {
+/// The directory the current world's database lives in, `data/<world>` under FERRUMC_ROOT or the
+/// exe's directory.
+pub fn world_directory() -> Result<PathBuf, Error> {
     // Parse root directory from environment variable
     let root = if env::var("FERRUMC_ROOT").is_ok() {
         PathBuf::from(env::var("FERRUMC_ROOT").unwrap())
@@ -71,132 +172,60 @@ pub async fn start_database() -> Result<Database, Error> {
 
     // Obtain global config to locate which world folder to load
     let world = get_global_config().world.clone();
-    let world_path = root.join("data").join(world);
-
-    debug!("Opening database at {}", world_path.display());
-
-    if !fs::try_exists(&world_path).await? {
-        fs::create_dir_all(&world_path).await?;
-    }
+    Ok(root.join("data").join(world))
+}
 
-    // Database Options
-    let mut opts = EnvOpenOptions::new();
-    opts.max_readers(num_cpus::get() as u32)
-        .map_size(LMDB_MIN_PAGE_SIZE)
-        .max_dbs(LMDB_MAX_DBS);
-
-    // Open database (This operation is safe as we assume no other process touched the database)
-    let lmdb = unsafe {
-        opts.flags(EnvFlags::WRITE_MAP | EnvFlags::NO_SYNC)
-            .open(&world_path)
-            .expect("Unable to open LMDB environment located at {world_path:?}")
+/// Start database
+pub async fn start_database() -> Result<Database, Error> {
+    let config = &get_global_config().database;
+    let world_path = world_directory()?;
+
+    let storage: Box<dyn WorldStorage> = match config.backend.as_str() {
+        "lmdb" => Box::new(LmdbStorage::open(&world_path).await?),
+        "region" => Box::new(RegionStorage::open(world_path.join("regions")).await?),
+        "memory" => Box::new(MemoryStorage::new()),
+        other => {
+            return Err(Error::Generic(format!(
+                "Unknown database backend \"{}\". Use \"lmdb\", \"region\" or \"memory\"",
+                other
+            )))
+        }
     };
 
-    // Start database threadpool
-    LMDB_THREADPOOL.get_or_init(|| {
-        ThreadPoolBuilder::new()
-            .num_threads(num_cpus::get() / 2)
-            .build()
-            .unwrap()
-    });
-
-    // Check if database is built. Otherwise, initialize it
-    let mut rw_tx = lmdb.write_txn()?;
-    if lmdb
-        // .open_database::<U64<LE>, Zstd<Chunk>>(&rw_tx, Some("chunks"))
-        .open_database::<U64<LE>, Bytes>(&rw_tx, Some("chunks"))?
-        .is_none()
-    {
-        lmdb.create_database::<U64<LE>, Bytes>(&mut rw_tx, Some("chunks"))
-            .expect("Unable to create database");
-    }
-    // `entities` table to be added, but needs the type to do so
-
-    rw_tx.commit()?;
-
-    info!("Database started");
+    info!("Database started using {} storage", storage.name());
 
     info!("Initializing cache");
 
-    // Initializing moka cache
-    let cache = moka::future::Cache::builder()
-        .async_eviction_listener(evict_chunk)
-        .weigher(|_, v| v.deep_size_of() as u32)
-        .eviction_policy(moka::policy::EvictionPolicy::tiny_lfu())
-        /*.max_capacity(get_global_config().database.cache_size as u64 * 1024)
-        .initial_capacity(1000)*/
-        .time_to_live(Duration::from_millis(1000))
-        .build();
-
-    Ok(Database {
-        db: lmdb,
-        cache: Arc::new(cache),
-    })
-}
-
-/// LMDB will follow a linear growth as opposed to MDBX which
-/// uses a geometric growth.
-pub(super) fn new_page_size(old_size: usize) -> usize {
-    old_size + LMDB_PAGE_SIZE_INCREMENT
+    Ok(Database::new(storage, config.cache_size as u64 * 1024))
 }
 
-/// Spawn a blocking task to interact with the database
-/// This is used to prevent the database from being blocked
-/// by a single thread
-///
-/// # Arguments
-///
-/// * `db` - The database environment
-/// * `f` - The function to execute
-///
-/// # Returns
-///
-/// A future that resolves to the result of the function
-pub(super) fn spawn_blocking_db<F, R>(
-    db: Env,
-    f: F,
-) -> impl Future<Output = Result<Result<R, heed::Error>, oneshot::error::RecvError>>
-where
-    F: Fn() -> Result<R, heed::Error> + Send + 'static,
-    R: Send + 'static + std::fmt::Debug,
-{
-    let (tx, res) = oneshot::channel::<Result<R, heed::Error>>();
-
-    let pool = LMDB_THREADPOOL.get().unwrap();
-    pool.spawn(move || {
-
-        let read_lock = LMDB_READER_SYNC.read()
-            .expect("Database RWLock has been poisoned. A thread should have crashed somewhere.");
-
-        let mut res = f();
-        if let Err(heed::Error::Mdb(MdbError::MapFull)) = res {
-
-            warn!("Database page is full. Resizing...");
-
-            drop(read_lock);
-
-            let _resize_guard = LMDB_READER_SYNC.write()
-                .expect("Database RWLock has been poisoned. A thread should have crashed somewhere.");
-
-            let mut global_size_lock = LMDB_PAGE_SIZE.lock().unwrap();
-            let old_size = *global_size_lock;
-            *global_size_lock = new_page_size(old_size);
-            unsafe { db.resize(*global_size_lock).expect("Unable to resize LMDB environment.") };
-
-            tracing::info!("Successfully resized LMDB page from {} MiB to {} MiB", old_size / 1024usize.pow(2), *global_size_lock / 1024usize.pow(2));
-
-            drop(global_size_lock);
-            drop(_resize_guard);
-
-            res = f();
-        } else {
-            drop(read_lock)
-        }
-
-        if tx.send(res).is_err() {
-            warn!("A database task has been unable to send its result because the receiver at other end have closed.")
-        }
-    });
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_hit_rate() {
+        let mut stats = CacheStats {
+            hits: 0,
+            misses: 0,
+            entries: 0,
+            size: 0,
+        };
+        assert_eq!(stats.hit_rate(), 0.0);
+        stats.hits = 3;
+        stats.misses = 1;
+        assert_eq!(stats.hit_rate(), 0.75);
+        assert!(stats.to_string().contains("75.0% hit rate"));
+    }
 
-    res
+    #[test]
+    fn test_autosave_toggle() {
+        let database = Database::new(Box::new(MemoryStorage::new()), 1024);
+        assert!(database.autosave_enabled());
+        assert!(database.set_autosave(false));
+        assert!(!database.autosave_enabled());
+        assert!(!database.set_autosave(false));
+        assert!(!database.set_autosave(true));
+        assert!(database.autosave_enabled());
+    }
 }
@@ -0,0 +1,420 @@
+//! What's kept about players between sessions: where they are, their game mode and their
+//! inventory.
+//!
+//! Player data is saved one file per player under `<world>/playerdata`, apart from the chunks, so
+//! it's the same whatever storage backend the chunks are in.
+
+use std::io::{Cursor, Read};
+use std::path::{Path, PathBuf};
+
+use bincode::{Decode, Encode};
+use nbt_lib::NBTDeserializeBytes;
+use tokio::sync::Mutex;
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+use crate::database::encoding::ZstdCodec;
+use crate::database::world_meta::WorldMeta;
+use crate::state::GlobalState;
+use crate::utils::components::dimension::dimension_of;
+use crate::utils::components::game_mode::GameMode;
+use crate::utils::components::inventory::Inventory;
+use crate::utils::components::player::Player;
+use crate::utils::components::rotation::Rotation;
+use crate::utils::constants::init;
+use crate::utils::encoding::position::Position;
+use crate::utils::encoding::slot::ItemStack;
+use crate::utils::prelude::*;
+use crate::world::dimension::Dimension;
+use crate::world::items::ItemRegistry;
+
+#[derive(Debug, Clone, PartialEq, Encode, Decode)]
+pub struct PlayerData {
+    pub x: i32,
+    pub y: i16,
+    pub z: i32,
+    pub yaw: f32,
+    pub pitch: f32,
+    /// The name the dimension's chunks are stored under, see [Dimension::name].
+    pub dimension: String,
+    pub game_mode: u8,
+    pub selected_slot: i16,
+    pub inventory: Vec<SavedItem>,
+}
+
+/// An item in a player's inventory, in the inventory window's slot numbers.
+#[derive(Debug, Clone, PartialEq, Encode, Decode)]
+pub struct SavedItem {
+    pub slot: i16,
+    pub id: i32,
+    pub count: i8,
+    pub nbt: Vec<u8>,
+}
+
+impl Default for PlayerData {
+    /// A player that has never joined before, at the spawn point with nothing in their inventory.
+    fn default() -> Self {
+        Self {
+            x: init::DEFAULT_SPAWN_X_POS,
+            y: init::DEFAULT_SPAWN_Y_POS,
+            z: init::DEFAULT_SPAWN_Z_POS,
+            yaw: init::DEFAULT_SPAWN_YAW,
+            pitch: init::DEFAULT_SPAWN_PITCH,
+            dimension: Dimension::Overworld.name().to_string(),
+            game_mode: init::DEFAULT_GAME_MODE,
+            selected_slot: 0,
+            inventory: Vec::new(),
+        }
+    }
+}
+
+impl PlayerData {
+    /// A player joining for the first time, at the world's spawn point.
+    pub fn new_at_spawn(world: &WorldMeta) -> Self {
+        Self {
+            x: world.spawn_x,
+            y: world.spawn_y,
+            z: world.spawn_z,
+            yaw: world.spawn_angle,
+            ..Default::default()
+        }
+    }
+
+    /// Takes everything that's saved from a player's components.
+    pub async fn capture(state: &GlobalState, entity: usize) -> Result<Self> {
+        let position = state.world.get_component::<Position>(entity).await?.clone();
+        let rotation = state
+            .world
+            .get_component::<Rotation>(entity)
+            .await
+            .map(|rotation| rotation.clone())
+            .unwrap_or_else(|_| Rotation::new(init::DEFAULT_SPAWN_YAW, init::DEFAULT_SPAWN_PITCH));
+        let game_mode = state
+            .world
+            .get_component::<GameMode>(entity)
+            .await
+            .map_or(init::DEFAULT_GAME_MODE, |game_mode| game_mode.mode);
+        let dimension = dimension_of(state, entity).await;
+
+        let (selected_slot, inventory) = match state.world.get_component::<Inventory>(entity).await
+        {
+            Ok(inventory) => {
+                let mut items: Vec<SavedItem> = inventory
+                    .slots
+                    .iter()
+                    .map(|(&slot, item)| SavedItem {
+                        slot,
+                        id: item.id,
+                        count: item.count,
+                        nbt: item.nbt.clone(),
+                    })
+                    .collect();
+                items.sort_by_key(|item| item.slot);
+                (inventory.selected_slot, items)
+            }
+            Err(_) => (0, Vec::new()),
+        };
+
+        Ok(Self {
+            x: position.x,
+            y: position.y,
+            z: position.z,
+            yaw: rotation.yaw,
+            pitch: rotation.pitch,
+            dimension: dimension.name().to_string(),
+            game_mode,
+            selected_slot,
+            inventory,
+        })
+    }
+
+    pub fn position(&self) -> Position {
+        Position::new(self.x, self.y, self.z)
+    }
+
+    pub fn rotation(&self) -> Rotation {
+        Rotation::new(self.yaw, self.pitch)
+    }
+
+    /// Dimensions that don't exist (any more) put the player back in the overworld.
+    pub fn dimension(&self) -> Dimension {
+        Dimension::from_name(&self.dimension).unwrap_or_default()
+    }
+
+    pub fn inventory(&self) -> Inventory {
+        let mut inventory = Inventory {
+            selected_slot: self.selected_slot,
+            ..Default::default()
+        };
+        for item in &self.inventory {
+            let stack = ItemStack {
+                id: item.id,
+                count: item.count,
+                nbt: item.nbt.clone(),
+            };
+            inventory.set_slot(item.slot, Some(stack));
+        }
+        inventory
+    }
+}
+
+/// Loads and saves [PlayerData], by the player's UUID.
+pub struct PlayerStore {
+    directory: PathBuf,
+    /// A player can be saved by the autosave and by leaving at the same time, which would
+    /// otherwise write the same temporary file at once.
+    write_lock: Mutex<()>,
+}
+
+impl PlayerStore {
+    pub fn new(directory: impl Into<PathBuf>) -> Self {
+        Self {
+            directory: directory.into(),
+            write_lock: Mutex::new(()),
+        }
+    }
+
+    fn path(&self, uuid: Uuid) -> PathBuf {
+        self.directory.join(format!("{}.bin", uuid))
+    }
+
+    /// The player's data, or `None` if they've never joined.
+    pub async fn load(&self, uuid: Uuid) -> Result<Option<PlayerData>> {
+        let data = match tokio::fs::read(self.path(uuid)).await {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        ZstdCodec::decompress_data(&data).await.map(Some)
+    }
+
+    /// Writes through a temporary file, so a crash can't leave the player's data half written.
+    pub async fn save(&self, uuid: Uuid, data: &PlayerData) -> Result<()> {
+        tokio::fs::create_dir_all(&self.directory).await?;
+        let bytes = ZstdCodec::compress_data(data.clone()).await?;
+        let path = self.path(uuid);
+        let temp = path.with_extension("tmp");
+        let _guard = self.write_lock.lock().await;
+        tokio::fs::write(&temp, bytes).await?;
+        tokio::fs::rename(temp, path).await?;
+        Ok(())
+    }
+
+    /// Converts a vanilla world's `playerdata/<uuid>.dat` files. Items the registry doesn't know
+    /// are left out, and so is item NBT, which is in a different format on disk than on the network.
+    ///
+    /// Returns how many players were imported.
+    pub async fn import_vanilla(&self, directory: &Path, items: &ItemRegistry) -> Result<usize> {
+        let mut files = match tokio::fs::read_dir(directory).await {
+            Ok(files) => files,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut imported = 0;
+        while let Some(file) = files.next_entry().await? {
+            let path = file.path();
+            if path.extension() != Some("dat".as_ref()) {
+                continue;
+            }
+            let Some(uuid) = path
+                .file_stem()
+                .and_then(|stem| Uuid::parse_str(&stem.to_string_lossy()).ok())
+            else {
+                continue;
+            };
+
+            match read_vanilla_player(&path).await {
+                Ok(player) => {
+                    self.save(uuid, &player.convert(items)).await?;
+                    imported += 1;
+                }
+                Err(e) => warn!("Couldn't import player data {}: {}", path.display(), e),
+            }
+        }
+        debug!("Imported {} players from {}", imported, directory.display());
+        Ok(imported)
+    }
+}
+
+/// Saves a player's data, e.g. when they leave.
+pub async fn save_player(state: &GlobalState, entity: usize) -> Result<()> {
+    let uuid = Uuid::from_u128(state.world.get_component::<Player>(entity).await?.uuid);
+    let data = PlayerData::capture(state, entity).await?;
+    state.players.save(uuid, &data).await
+}
+
+/// Saves every player that's online. Returns how many were saved.
+pub async fn save_all_players(state: &GlobalState) -> Result<usize> {
+    let players = {
+        let query = state.world.query::<&Player>();
+        query.iter().await.map(|(id, _)| id).collect::<Vec<_>>()
+    };
+
+    let mut saved = 0;
+    for entity in players {
+        match save_player(state, entity).await {
+            Ok(()) => saved += 1,
+            // They may have left while the others were being saved
+            Err(e) => debug!("Couldn't save player {}: {}", entity, e),
+        }
+    }
+    Ok(saved)
+}
+
+#[derive(nbt_lib::NBTDeserialize)]
+#[nbt(is_root)]
+#[nbt(rename = "")]
+struct VanillaPlayer {
+    #[nbt(rename = "Pos")]
+    pos: Vec<f64>,
+    #[nbt(rename = "Rotation")]
+    rotation: Vec<f32>,
+    #[nbt(rename = "Dimension")]
+    dimension: Option<String>,
+    #[nbt(rename = "playerGameType")]
+    game_type: Option<i32>,
+    #[nbt(rename = "SelectedItemSlot")]
+    selected_slot: Option<i32>,
+    #[nbt(rename = "Inventory")]
+    inventory: Option<Vec<VanillaItem>>,
+}
+
+#[derive(nbt_lib::NBTDeserialize)]
+struct VanillaItem {
+    #[nbt(rename = "Slot")]
+    slot: i8,
+    id: String,
+    #[nbt(rename = "Count")]
+    count: i8,
+}
+
+async fn read_vanilla_player(path: &Path) -> Result<VanillaPlayer> {
+    let compressed = tokio::fs::read(path).await?;
+    let mut data = Vec::new();
+    flate2::read::GzDecoder::new(compressed.as_slice()).read_to_end(&mut data)?;
+    Ok(VanillaPlayer::read_from_bytes(&mut Cursor::new(data))?)
+}
+
+impl VanillaPlayer {
+    fn convert(self, items: &ItemRegistry) -> PlayerData {
+        let defaults = PlayerData::default();
+        let coordinate = |index: usize| self.pos.get(index).copied().unwrap_or_default();
+        let dimension = self
+            .dimension
+            .as_deref()
+            .and_then(Dimension::from_name)
+            .unwrap_or_default();
+
+        let inventory = self
+            .inventory
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|item| {
+                Some(SavedItem {
+                    slot: window_slot(item.slot)?,
+                    id: items.id(&item.id)?,
+                    count: item.count,
+                    nbt: Vec::new(),
+                })
+            })
+            .collect();
+
+        PlayerData {
+            x: coordinate(0).floor() as i32,
+            y: coordinate(1).floor() as i16,
+            z: coordinate(2).floor() as i32,
+            yaw: self.rotation.first().copied().unwrap_or(defaults.yaw),
+            pitch: self.rotation.get(1).copied().unwrap_or(defaults.pitch),
+            dimension: dimension.name().to_string(),
+            game_mode: self.game_type.map_or(defaults.game_mode, |mode| mode as u8),
+            selected_slot: self.selected_slot.unwrap_or_default() as i16,
+            inventory,
+        }
+    }
+}
+
+/// Vanilla saves the hotbar as slots 0 to 8, the rest of the inventory as 9 to 35, armor from the
+/// feet up as 100 to 103 and the offhand as -106. The inventory window has the armor from the head
+/// down at 5 to 8, and the hotbar after the rest of the inventory.
+fn window_slot(slot: i8) -> Option<i16> {
+    let slot = slot as i16;
+    match slot {
+        0..=8 => Some(Inventory::HOTBAR_START + slot),
+        9..=35 => Some(slot),
+        100..=103 => Some(108 - slot),
+        -106 => Some(Inventory::OFFHAND),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_save_and_load() {
+        let directory = std::env::temp_dir().join(format!("ferrumc-players-{}", std::process::id()));
+        let store = PlayerStore::new(&directory);
+        let uuid = Uuid::from_u128(42);
+        assert_eq!(store.load(uuid).await.unwrap(), None);
+
+        let data = PlayerData {
+            dimension: Dimension::Nether.name().to_string(),
+            inventory: vec![SavedItem {
+                slot: 36,
+                id: 1,
+                count: 64,
+                nbt: vec![0],
+            }],
+            ..Default::default()
+        };
+        store.save(uuid, &data).await.unwrap();
+        let loaded = store.load(uuid).await.unwrap().unwrap();
+        assert_eq!(loaded, data);
+        assert_eq!(loaded.dimension(), Dimension::Nether);
+        assert_eq!(loaded.inventory().held_item(false).unwrap().count, 64);
+
+        tokio::fs::remove_dir_all(directory).await.unwrap();
+    }
+
+    #[test]
+    fn test_convert_vanilla_player() {
+        let items = ItemRegistry::parse(
+            r#"{"minecraft:item": {"entries": {"minecraft:stone": {"protocol_id": 1}}}}"#,
+        )
+        .unwrap();
+        let player = VanillaPlayer {
+            pos: vec![10.5, 70.0, -3.2],
+            rotation: vec![90.0, 10.0],
+            dimension: Some("minecraft:the_end".to_string()),
+            game_type: Some(0),
+            selected_slot: Some(2),
+            inventory: Some(vec![
+                VanillaItem {
+                    slot: 2,
+                    id: "minecraft:stone".to_string(),
+                    count: 5,
+                },
+                VanillaItem {
+                    slot: 103,
+                    id: "minecraft:stone".to_string(),
+                    count: 1,
+                },
+                VanillaItem {
+                    slot: 0,
+                    id: "minecraft:unknown".to_string(),
+                    count: 1,
+                },
+            ]),
+        };
+
+        let data = player.convert(&items);
+        assert_eq!(data.position(), Position::new(10, 70, -4));
+        assert_eq!(data.dimension(), Dimension::End);
+        assert_eq!(data.game_mode, 0);
+        let slots: Vec<i16> = data.inventory.iter().map(|item| item.slot).collect();
+        assert_eq!(slots, vec![38, 5]);
+        assert_eq!(data.inventory().held_item(false).unwrap().count, 5);
+    }
+}
@@ -0,0 +1,442 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use async_trait::async_trait;
+use tokio::sync::Mutex;
+use tracing::warn;
+
+use crate::database::maintenance::{DatabaseStats, DimensionStats};
+use crate::database::storage::{ChunkKey, WorldStorage};
+use crate::utils::error::Error;
+use crate::world::importing::SerializedChunk;
+
+/// Chunks per side of a region, the same as vanilla's region files.
+const REGION_SIZE: i32 = 32;
+const CHUNKS_PER_REGION: usize = (REGION_SIZE * REGION_SIZE) as usize;
+/// An offset and a length for each chunk, both u32s.
+const HEADER_SIZE: usize = CHUNKS_PER_REGION * 8;
+/// Where [WorldStorage::save_meta] puts things, next to the dimensions' folders.
+const META_DIRECTORY: &str = "meta";
+/// The batch of chunks being saved, kept until every region file in it has been rewritten.
+const JOURNAL_FILE: &str = "journal.bin";
+
+/// Stores chunks in one file per 32x32 chunk region, under `<directory>/<dimension>/r.<x>.<z>.bin`.
+/// Easy to back up or copy a part of the world around, and nothing to compact since each file is
+/// rewritten whole when it changes.
+///
+/// Each file starts with the offset and length of every chunk in it, 0 if the chunk isn't there,
+/// followed by the chunks themselves. Data about the world as a whole goes in
+/// `<directory>/meta/<key>.bin`.
+///
+/// Each region file is replaced in one go, but a batch can cover several of them, so the batch is
+/// written to `<directory>/journal.bin` first. If the server crashes part way through, the journal
+/// is still there when it starts again and [RegionStorage::open] finishes the batch.
+pub struct RegionStorage {
+    directory: PathBuf,
+    /// Only one batch gets written at a time, so two saves can't rewrite the same file at once.
+    write_lock: Mutex<()>,
+}
+
+impl RegionStorage {
+    pub fn new(directory: PathBuf) -> Self {
+        Self {
+            directory,
+            write_lock: Mutex::new(()),
+        }
+    }
+
+    /// Opens the storage in `directory`, finishing the last batch of chunks if the server stopped
+    /// while it was being saved.
+    pub async fn open(directory: PathBuf) -> Result<Self, Error> {
+        let storage = Self::new(directory);
+        let journal = storage.journal_path();
+        // Only renamed to the journal once it's complete, so this batch never got to the regions
+        match tokio::fs::remove_file(journal.with_extension("tmp")).await {
+            Ok(()) => warn!("Discarded a batch of chunks that was never saved"),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+
+        let data = match tokio::fs::read(&journal).await {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(storage),
+            Err(e) => return Err(e.into()),
+        };
+        match decode_journal(&data) {
+            Some(chunks) => {
+                warn!(
+                    "The last save didn't finish, saving its {} chunks again",
+                    chunks.len()
+                );
+                let regions = storage.group_by_region(chunks);
+                tokio::task::spawn_blocking(move || write_regions(regions)).await??;
+            }
+            None => warn!("Discarded a corrupted journal at {}", journal.display()),
+        }
+        tokio::fs::remove_file(journal).await?;
+        Ok(storage)
+    }
+
+    fn journal_path(&self) -> PathBuf {
+        self.directory.join(JOURNAL_FILE)
+    }
+
+    fn region_path(&self, dimension: &str, region_x: i32, region_z: i32) -> PathBuf {
+        // Namespaced dimensions like minecraft:the_nether can't have a colon in a folder name on
+        // every platform
+        self.directory
+            .join(dimension.replace(':', "_"))
+            .join(format!("r.{}.{}.bin", region_x, region_z))
+    }
+
+    fn meta_path(&self, key: &str) -> PathBuf {
+        self.directory.join(META_DIRECTORY).join(format!("{}.bin", key))
+    }
+
+    fn path_for(&self, key: &ChunkKey) -> PathBuf {
+        self.region_path(
+            &key.dimension,
+            key.x.div_euclid(REGION_SIZE),
+            key.z.div_euclid(REGION_SIZE),
+        )
+    }
+
+    /// The chunks' data by region file and where they go in it.
+    fn group_by_region(
+        &self,
+        chunks: Vec<SerializedChunk>,
+    ) -> HashMap<PathBuf, Vec<(usize, Vec<u8>)>> {
+        let mut regions: HashMap<PathBuf, Vec<(usize, Vec<u8>)>> = HashMap::new();
+        for chunk in chunks {
+            let path = self.path_for(chunk.key());
+            let (key, data) = chunk.into_parts();
+            regions
+                .entry(path)
+                .or_default()
+                .push((index_in_region(key.x, key.z), data));
+        }
+        regions
+    }
+}
+
+/// Where a chunk is in its region's header.
+fn index_in_region(x: i32, z: i32) -> usize {
+    (z.rem_euclid(REGION_SIZE) * REGION_SIZE + x.rem_euclid(REGION_SIZE)) as usize
+}
+
+fn read_chunk(path: &Path, index: usize) -> Result<Option<Vec<u8>>, Error> {
+    let mut file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    let mut entry = [0u8; 8];
+    file.seek(SeekFrom::Start(index as u64 * 8))?;
+    file.read_exact(&mut entry)?;
+    let offset = u32::from_le_bytes(entry[..4].try_into().unwrap());
+    let length = u32::from_le_bytes(entry[4..].try_into().unwrap());
+    if length == 0 {
+        return Ok(None);
+    }
+
+    let mut data = vec![0; length as usize];
+    file.seek(SeekFrom::Start(offset as u64))?;
+    file.read_exact(&mut data)?;
+    Ok(Some(data))
+}
+
+/// Every chunk in a region file, by index.
+fn read_region(path: &Path) -> Result<Vec<Option<Vec<u8>>>, Error> {
+    let mut chunks = vec![None; CHUNKS_PER_REGION];
+    let data = match fs::read(path) {
+        Ok(data) => data,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(chunks),
+        Err(e) => return Err(e.into()),
+    };
+    if data.len() < HEADER_SIZE {
+        return Err(Error::Generic(format!(
+            "Region file {} is corrupted",
+            path.display()
+        )));
+    }
+
+    for (index, chunk) in chunks.iter_mut().enumerate() {
+        let entry = &data[index * 8..index * 8 + 8];
+        let offset = u32::from_le_bytes(entry[..4].try_into().unwrap()) as usize;
+        let length = u32::from_le_bytes(entry[4..].try_into().unwrap()) as usize;
+        if length == 0 {
+            continue;
+        }
+        let bytes = data.get(offset..offset + length).ok_or_else(|| {
+            Error::Generic(format!("Region file {} is corrupted", path.display()))
+        })?;
+        *chunk = Some(bytes.to_vec());
+    }
+    Ok(chunks)
+}
+
+/// Writes a whole region file, through a temporary file so a crash can't leave it half written.
+fn write_region(path: &Path, chunks: &[Option<Vec<u8>>]) -> Result<(), Error> {
+    let mut header = Vec::with_capacity(HEADER_SIZE);
+    let mut body = Vec::new();
+    for chunk in chunks {
+        let (offset, length) = match chunk {
+            Some(data) => {
+                let offset = HEADER_SIZE + body.len();
+                body.extend_from_slice(data);
+                (offset as u32, data.len() as u32)
+            }
+            None => (0, 0),
+        };
+        header.extend_from_slice(&offset.to_le_bytes());
+        header.extend_from_slice(&length.to_le_bytes());
+    }
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let temp = path.with_extension("tmp");
+    let mut file = File::create(&temp)?;
+    file.write_all(&header)?;
+    file.write_all(&body)?;
+    file.sync_all()?;
+    fs::rename(temp, path)?;
+    Ok(())
+}
+
+/// Replaces the given chunks in each region file, keeping the rest of the chunks in it.
+fn write_regions(regions: HashMap<PathBuf, Vec<(usize, Vec<u8>)>>) -> Result<(), Error> {
+    for (path, changed) in regions {
+        let mut chunks = read_region(&path)?;
+        for (index, data) in changed {
+            chunks[index] = Some(data);
+        }
+        write_region(&path, &chunks)?;
+    }
+    Ok(())
+}
+
+/// Each chunk's dimension as a u16 length and the name, its x and z as i32s, then its data as a
+/// u32 length and the bytes, all little endian.
+fn encode_journal(chunks: &[SerializedChunk]) -> Vec<u8> {
+    let mut journal = Vec::new();
+    for chunk in chunks {
+        let key = chunk.key();
+        journal.extend_from_slice(&(key.dimension.len() as u16).to_le_bytes());
+        journal.extend_from_slice(key.dimension.as_bytes());
+        journal.extend_from_slice(&key.x.to_le_bytes());
+        journal.extend_from_slice(&key.z.to_le_bytes());
+        journal.extend_from_slice(&(chunk.data().len() as u32).to_le_bytes());
+        journal.extend_from_slice(chunk.data());
+    }
+    journal
+}
+
+/// The chunks in a journal, or `None` if it's been cut off or isn't one.
+fn decode_journal(mut journal: &[u8]) -> Option<Vec<SerializedChunk>> {
+    fn take<'a>(journal: &mut &'a [u8], length: usize) -> Option<&'a [u8]> {
+        if journal.len() < length {
+            return None;
+        }
+        let (taken, rest) = journal.split_at(length);
+        *journal = rest;
+        Some(taken)
+    }
+
+    let mut chunks = Vec::new();
+    while !journal.is_empty() {
+        let length = u16::from_le_bytes(take(&mut journal, 2)?.try_into().ok()?) as usize;
+        let dimension = std::str::from_utf8(take(&mut journal, length)?).ok()?;
+        let x = i32::from_le_bytes(take(&mut journal, 4)?.try_into().ok()?);
+        let z = i32::from_le_bytes(take(&mut journal, 4)?.try_into().ok()?);
+        let length = u32::from_le_bytes(take(&mut journal, 4)?.try_into().ok()?) as usize;
+        let data = take(&mut journal, length)?.to_vec();
+        chunks.push(SerializedChunk::new(ChunkKey::new(dimension, x, z), data));
+    }
+    Some(chunks)
+}
+
+/// Writes the journal through a temporary file, so it's either all there or not there at all.
+fn write_journal(path: &Path, journal: &[u8]) -> Result<(), Error> {
+    fs::create_dir_all(path.parent().unwrap_or(Path::new(".")))?;
+    let temp = path.with_extension("tmp");
+    let mut file = File::create(&temp)?;
+    file.write_all(journal)?;
+    file.sync_all()?;
+    fs::rename(temp, path)?;
+    Ok(())
+}
+
+#[async_trait]
+impl WorldStorage for RegionStorage {
+    async fn load(&self, key: &ChunkKey) -> Result<Option<Vec<u8>>, Error> {
+        let path = self.path_for(key);
+        let index = index_in_region(key.x, key.z);
+        tokio::task::spawn_blocking(move || read_chunk(&path, index)).await?
+    }
+
+    async fn save(&self, chunks: Vec<SerializedChunk>) -> Result<(), Error> {
+        let journal = encode_journal(&chunks);
+        let journal_path = self.journal_path();
+        let regions = self.group_by_region(chunks);
+
+        let _guard = self.write_lock.lock().await;
+        tokio::task::spawn_blocking(move || {
+            write_journal(&journal_path, &journal)?;
+            write_regions(regions)?;
+            fs::remove_file(journal_path)?;
+            Ok(())
+        })
+        .await?
+    }
+
+    async fn load_meta(&self, key: &str) -> Result<Option<Vec<u8>>, Error> {
+        match tokio::fs::read(self.meta_path(key)).await {
+            Ok(data) => Ok(Some(data)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn save_meta(&self, key: &str, data: Vec<u8>) -> Result<(), Error> {
+        let path = self.meta_path(key);
+        let _guard = self.write_lock.lock().await;
+        tokio::task::spawn_blocking(move || {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            let temp = path.with_extension("tmp");
+            fs::write(&temp, data)?;
+            fs::rename(temp, path)?;
+            Ok(())
+        })
+        .await?
+    }
+
+    async fn stats(&self) -> Result<DatabaseStats, Error> {
+        let directory = self.directory.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut stats = DatabaseStats::default();
+            let Ok(dimensions) = fs::read_dir(&directory) else {
+                return Ok(stats);
+            };
+            let mut per_dimension: BTreeMap<String, DimensionStats> = BTreeMap::new();
+            for dimension in dimensions {
+                let dimension = dimension?;
+                if !dimension.file_type()?.is_dir() || dimension.file_name() == META_DIRECTORY {
+                    continue;
+                }
+                let name = dimension.file_name().to_string_lossy().to_string();
+                for region in fs::read_dir(dimension.path())? {
+                    let path = region?.path();
+                    if path.extension().is_none_or(|extension| extension != "bin") {
+                        continue;
+                    }
+                    stats.file_size += fs::metadata(&path)?.len();
+                    let dimension_stats = per_dimension.entry(name.clone()).or_default();
+                    for chunk in read_region(&path)?.into_iter().flatten() {
+                        dimension_stats.chunks += 1;
+                        dimension_stats.bytes += chunk.len() as u64;
+                    }
+                }
+            }
+            stats.used_size = stats.file_size;
+            stats.dimensions = per_dimension;
+            Ok(stats)
+        })
+        .await?
+    }
+
+    fn name(&self) -> &'static str {
+        "region"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_region_storage() {
+        let directory = std::env::temp_dir().join(format!("ferrumc-regions-{}", std::process::id()));
+        let storage = RegionStorage::new(directory.clone());
+
+        // Chunks on both sides of a region border, and in another dimension
+        let first = ChunkKey::new("overworld", 31, -1);
+        let second = ChunkKey::new("overworld", 32, -1);
+        let nether = ChunkKey::new("minecraft:the_nether", 31, -1);
+        storage
+            .save(vec![
+                SerializedChunk::new(first.clone(), vec![1; 10]),
+                SerializedChunk::new(second.clone(), vec![2; 20]),
+                SerializedChunk::new(nether.clone(), vec![3; 30]),
+            ])
+            .await
+            .unwrap();
+        // Saving again only replaces the chunks that are in the batch
+        storage
+            .save(vec![SerializedChunk::new(first.clone(), vec![4; 5])])
+            .await
+            .unwrap();
+
+        assert_eq!(storage.load(&first).await.unwrap(), Some(vec![4; 5]));
+        assert_eq!(storage.load(&second).await.unwrap(), Some(vec![2; 20]));
+        assert_eq!(storage.load(&nether).await.unwrap(), Some(vec![3; 30]));
+        assert_eq!(
+            storage
+                .load(&ChunkKey::new("overworld", 30, -1))
+                .await
+                .unwrap(),
+            None
+        );
+        assert!(directory.join("overworld").join("r.0.-1.bin").exists());
+        assert!(directory.join("overworld").join("r.1.-1.bin").exists());
+
+        let stats = storage.stats().await.unwrap();
+        assert_eq!(stats.dimensions["overworld"].chunks, 2);
+        assert_eq!(stats.dimensions["overworld"].bytes, 25);
+        assert_eq!(stats.dimensions["minecraft_the_nether"].chunks, 1);
+        storage.save_meta("world", vec![7; 3]).await.unwrap();
+        assert_eq!(storage.load_meta("world").await.unwrap(), Some(vec![7; 3]));
+        assert!(!storage.stats().await.unwrap().dimensions.contains_key("meta"));
+        assert!(!directory.join(JOURNAL_FILE).exists());
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_journal_recovery() {
+        let directory = std::env::temp_dir().join(format!("ferrumc-journal-{}", std::process::id()));
+        let saved = ChunkKey::new("overworld", 0, 0);
+        let unsaved = ChunkKey::new("overworld", 100, -3);
+        RegionStorage::new(directory.clone())
+            .save(vec![SerializedChunk::new(saved.clone(), vec![1; 10])])
+            .await
+            .unwrap();
+
+        // A crash after the journal was written, but before the second region file was
+        let batch = vec![
+            SerializedChunk::new(saved.clone(), vec![2; 10]),
+            SerializedChunk::new(unsaved.clone(), vec![3; 20]),
+        ];
+        let journal = encode_journal(&batch);
+        assert_eq!(decode_journal(&journal).unwrap().len(), 2);
+        write_journal(&directory.join(JOURNAL_FILE), &journal).unwrap();
+
+        let storage = RegionStorage::open(directory.clone()).await.unwrap();
+        assert_eq!(storage.load(&saved).await.unwrap(), Some(vec![2; 10]));
+        assert_eq!(storage.load(&unsaved).await.unwrap(), Some(vec![3; 20]));
+        assert!(!directory.join(JOURNAL_FILE).exists());
+
+        // A journal that was cut off is thrown away, leaving the regions as they were
+        fs::write(directory.join(JOURNAL_FILE), &journal[..journal.len() - 1]).unwrap();
+        assert!(decode_journal(&journal[..journal.len() - 1]).is_none());
+        let storage = RegionStorage::open(directory.clone()).await.unwrap();
+        assert_eq!(storage.load(&saved).await.unwrap(), Some(vec![2; 10]));
+        assert!(!directory.join(JOURNAL_FILE).exists());
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+}
@@ -0,0 +1,148 @@
+use std::collections::BTreeMap;
+
+use async_trait::async_trait;
+use dashmap::DashMap;
+
+use crate::database::maintenance::{DatabaseStats, DimensionStats};
+use crate::utils::error::Error;
+use crate::utils::hash::hash;
+use crate::world::importing::SerializedChunk;
+
+/// Where a chunk is in the world.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ChunkKey {
+    pub dimension: String,
+    pub x: i32,
+    pub z: i32,
+}
+
+impl ChunkKey {
+    pub fn new(dimension: &str, x: i32, z: i32) -> Self {
+        Self {
+            dimension: dimension.to_string(),
+            x,
+            z,
+        }
+    }
+
+    /// The key chunks are stored under in LMDB and the chunk cache.
+    pub fn hash(&self) -> u64 {
+        hash((&self.dimension, self.x, self.z))
+    }
+}
+
+/// Somewhere to keep the world's chunks. The [Database](crate::database::Database) handles the
+/// cache and (de)serialization, so storages only ever see chunks as already encoded bytes.
+///
+/// Picked with `backend` in the `[database]` section of the config.
+#[async_trait]
+pub trait WorldStorage: Send + Sync {
+    /// Gets a chunk's bytes, or `None` if it hasn't been saved.
+    async fn load(&self, key: &ChunkKey) -> Result<Option<Vec<u8>>, Error>;
+    /// Saves a batch of chunks, replacing any that are already there.
+    async fn save(&self, chunks: Vec<SerializedChunk>) -> Result<(), Error>;
+    /// How many chunks there are in each dimension and how much space they take up.
+    async fn stats(&self) -> Result<DatabaseStats, Error>;
+    /// Gets a piece of data about the world as a whole that was saved under `key`, like
+    /// [WorldMeta](crate::database::world_meta::WorldMeta).
+    async fn load_meta(&self, key: &str) -> Result<Option<Vec<u8>>, Error>;
+    /// Saves a piece of data about the world under `key`, replacing what was there.
+    async fn save_meta(&self, key: &str, data: Vec<u8>) -> Result<(), Error>;
+    /// Frees up space left behind by old chunks. Returns the size before and after.
+    async fn compact(&self) -> Result<(u64, u64), Error> {
+        Err(Error::Generic(format!(
+            "The {} storage doesn't support compacting",
+            self.name()
+        )))
+    }
+    /// Called once when the server is done with the storage.
+    fn close(self: Box<Self>) {}
+    fn name(&self) -> &'static str;
+}
+
+/// Keeps chunks in memory only, so everything is gone once the server stops. Useful for tests and
+/// throwaway worlds.
+#[derive(Default)]
+pub struct MemoryStorage {
+    chunks: DashMap<ChunkKey, Vec<u8>>,
+    meta: DashMap<String, Vec<u8>>,
+}
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl WorldStorage for MemoryStorage {
+    async fn load(&self, key: &ChunkKey) -> Result<Option<Vec<u8>>, Error> {
+        Ok(self.chunks.get(key).map(|data| data.clone()))
+    }
+
+    async fn save(&self, chunks: Vec<SerializedChunk>) -> Result<(), Error> {
+        for chunk in chunks {
+            let (key, data) = chunk.into_parts();
+            self.chunks.insert(key, data);
+        }
+        Ok(())
+    }
+
+    async fn load_meta(&self, key: &str) -> Result<Option<Vec<u8>>, Error> {
+        Ok(self.meta.get(key).map(|data| data.clone()))
+    }
+
+    async fn save_meta(&self, key: &str, data: Vec<u8>) -> Result<(), Error> {
+        self.meta.insert(key.to_string(), data);
+        Ok(())
+    }
+
+    async fn stats(&self) -> Result<DatabaseStats, Error> {
+        let mut dimensions: BTreeMap<String, DimensionStats> = BTreeMap::new();
+        for entry in self.chunks.iter() {
+            let stats = dimensions.entry(entry.key().dimension.clone()).or_default();
+            stats.chunks += 1;
+            stats.bytes += entry.value().len() as u64;
+        }
+        let size = dimensions.values().map(|stats| stats.bytes).sum();
+        Ok(DatabaseStats {
+            file_size: size,
+            used_size: size,
+            dimensions,
+        })
+    }
+
+    fn name(&self) -> &'static str {
+        "memory"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_memory_storage() {
+        let storage = MemoryStorage::new();
+        let key = ChunkKey::new("overworld", 1, -2);
+        assert_eq!(storage.load(&key).await.unwrap(), None);
+
+        storage
+            .save(vec![
+                SerializedChunk::new(key.clone(), vec![1, 2, 3]),
+                SerializedChunk::new(ChunkKey::new("the_nether", 0, 0), vec![4]),
+            ])
+            .await
+            .unwrap();
+        assert_eq!(storage.load(&key).await.unwrap(), Some(vec![1, 2, 3]));
+
+        let stats = storage.stats().await.unwrap();
+        assert_eq!(stats.dimensions["overworld"].chunks, 1);
+        assert_eq!(stats.dimensions["the_nether"].bytes, 1);
+        assert!(storage.compact().await.is_err());
+
+        assert_eq!(storage.load_meta("world").await.unwrap(), None);
+        storage.save_meta("world", vec![5, 6]).await.unwrap();
+        assert_eq!(storage.load_meta("world").await.unwrap(), Some(vec![5, 6]));
+    }
+}
@@ -0,0 +1,328 @@
+//! Data about the world as a whole rather than any one chunk: where players spawn, the seed, the
+//! time, the weather and the gamerules.
+
+use std::collections::BTreeMap;
+use std::io::{Cursor, Read};
+use std::path::Path;
+
+use bincode::{Decode, Encode};
+use nbt_lib::NBTDeserializeBytes;
+use sha2::{Digest, Sha256};
+
+use crate::database::encoding::ZstdCodec;
+use crate::database::Database;
+use crate::state::GlobalState;
+use crate::utils::constants::init;
+use crate::utils::encoding::position::Position;
+use crate::utils::prelude::*;
+use crate::world::game_rules::{self, GameRule, GameRuleValue};
+use crate::world::weather::Weather;
+
+/// What [WorldMeta] is saved under in the storage.
+const WORLD_META_KEY: &str = "world";
+/// How many ticks a day lasts, from sunrise to sunrise.
+pub const DAY_LENGTH: i64 = 24000;
+
+#[derive(Debug, Clone, PartialEq, Encode, Decode)]
+pub struct WorldMeta {
+    pub spawn_x: i32,
+    pub spawn_y: i16,
+    pub spawn_z: i32,
+    /// The way players face when they spawn, in degrees.
+    pub spawn_angle: f32,
+    pub seed: i64,
+    /// Ticks since the world was made.
+    pub time: i64,
+    /// Ticks into the day, which doesn't always follow [WorldMeta::time] since it can be set.
+    pub day_time: i64,
+    pub weather: Weather,
+    /// By name, with the values as vanilla stores them, e.g. `doDaylightCycle = "true"`. See
+    /// [game_rules].
+    pub game_rules: BTreeMap<String, String>,
+}
+
+impl Default for WorldMeta {
+    fn default() -> Self {
+        Self {
+            spawn_x: init::DEFAULT_SPAWN_X_POS,
+            spawn_y: init::DEFAULT_SPAWN_Y_POS,
+            spawn_z: init::DEFAULT_SPAWN_Z_POS,
+            spawn_angle: init::DEFAULT_SPAWN_YAW,
+            seed: 0,
+            time: 0,
+            day_time: 0,
+            weather: Weather::default(),
+            game_rules: BTreeMap::new(),
+        }
+    }
+}
+
+impl WorldMeta {
+    pub fn spawn_position(&self) -> Position {
+        Position::new(self.spawn_x, self.spawn_y, self.spawn_z)
+    }
+
+    /// A gamerule's value, or its default if it isn't set or what's set isn't the right type.
+    pub fn game_rule(&self, rule: &GameRule) -> GameRuleValue {
+        self.game_rules
+            .get(rule.name)
+            .and_then(|value| rule.default.parse_same(value))
+            .unwrap_or(rule.default)
+    }
+
+    /// Whether a true or false gamerule is on.
+    pub fn game_rule_enabled(&self, rule: &GameRule) -> bool {
+        self.game_rule(rule) == GameRuleValue::Bool(true)
+    }
+
+    /// A number gamerule's value, or 0 if it isn't one.
+    pub fn game_rule_int(&self, rule: &GameRule) -> i32 {
+        match self.game_rule(rule) {
+            GameRuleValue::Int(value) => value,
+            GameRuleValue::Bool(_) => 0,
+        }
+    }
+
+    /// Sets a gamerule from how it's typed. Returns the new value, or `None` if it isn't the
+    /// rule's type.
+    pub fn set_game_rule(&mut self, rule: &GameRule, value: &str) -> Option<GameRuleValue> {
+        let value = rule.default.parse_same(value)?;
+        self.game_rules.insert(rule.name.to_string(), value.to_string());
+        Some(value)
+    }
+
+    /// Moves the clock on by a tick. The time of day stands still while `doDaylightCycle` is off.
+    pub fn tick_time(&mut self) {
+        self.time += 1;
+        if self.game_rule_enabled(&game_rules::DO_DAYLIGHT_CYCLE) {
+            self.day_time += 1;
+        }
+    }
+
+    /// How far into the current day it is, from 0 to [DAY_LENGTH].
+    pub fn time_of_day(&self) -> i64 {
+        self.day_time.rem_euclid(DAY_LENGTH)
+    }
+
+    /// What the client is told instead of the seed, which it uses for biome blending. The first 8
+    /// bytes of the SHA-256 of the seed, the same as vanilla.
+    pub fn seed_hash(&self) -> i64 {
+        let digest = Sha256::digest(self.seed.to_le_bytes());
+        i64::from_le_bytes(digest[..8].try_into().unwrap())
+    }
+
+    /// Reads the spawn point, seed, time, weather and gamerules from a vanilla world's `level.dat`.
+    pub async fn import_vanilla(path: &Path) -> Result<Self> {
+        let compressed = tokio::fs::read(path).await?;
+        let mut data = Vec::new();
+        flate2::read::GzDecoder::new(compressed.as_slice()).read_to_end(&mut data)?;
+        Ok(VanillaLevel::read_from_bytes(&mut Cursor::new(data))?.data.convert())
+    }
+}
+
+impl Database {
+    /// The world's [WorldMeta], or `None` if it hasn't been saved yet.
+    pub async fn load_world_meta(&self) -> Result<Option<WorldMeta>> {
+        match self.storage.load_meta(WORLD_META_KEY).await? {
+            Some(data) => ZstdCodec::decompress_data(&data).await.map(Some),
+            None => Ok(None),
+        }
+    }
+
+    pub async fn save_world_meta(&self, meta: &WorldMeta) -> Result<()> {
+        let data = ZstdCodec::compress_data(meta.clone()).await?;
+        self.storage.save_meta(WORLD_META_KEY, data).await
+    }
+}
+
+/// Saves the [WorldMeta] the server is running with, so e.g. the time carries on after a restart.
+pub async fn save_world_meta(state: &GlobalState) -> Result<()> {
+    let meta = state.world.resource::<WorldMeta>().await?.clone();
+    state.database.save_world_meta(&meta).await
+}
+
+#[derive(nbt_lib::NBTDeserialize)]
+#[nbt(is_root)]
+#[nbt(rename = "")]
+struct VanillaLevel {
+    #[nbt(rename = "Data")]
+    data: VanillaLevelData,
+}
+
+#[derive(nbt_lib::NBTDeserialize)]
+struct VanillaLevelData {
+    #[nbt(rename = "SpawnX")]
+    spawn_x: Option<i32>,
+    #[nbt(rename = "SpawnY")]
+    spawn_y: Option<i32>,
+    #[nbt(rename = "SpawnZ")]
+    spawn_z: Option<i32>,
+    #[nbt(rename = "SpawnAngle")]
+    spawn_angle: Option<f32>,
+    /// Where the seed is since 1.16.
+    #[nbt(rename = "WorldGenSettings")]
+    world_gen_settings: Option<VanillaWorldGenSettings>,
+    /// Where the seed was before 1.16.
+    #[nbt(rename = "RandomSeed")]
+    random_seed: Option<i64>,
+    #[nbt(rename = "Time")]
+    time: Option<i64>,
+    #[nbt(rename = "DayTime")]
+    day_time: Option<i64>,
+    #[nbt(rename = "clearWeatherTime")]
+    clear_weather_time: Option<i32>,
+    #[nbt(rename = "rainTime")]
+    rain_time: Option<i32>,
+    raining: Option<bool>,
+    #[nbt(rename = "thunderTime")]
+    thunder_time: Option<i32>,
+    thundering: Option<bool>,
+    #[nbt(rename = "GameRules")]
+    game_rules: Option<BTreeMap<String, String>>,
+}
+
+#[derive(nbt_lib::NBTDeserialize)]
+struct VanillaWorldGenSettings {
+    seed: i64,
+}
+
+impl VanillaLevelData {
+    fn convert(self) -> WorldMeta {
+        let defaults = WorldMeta::default();
+        let weather = self.weather();
+        WorldMeta {
+            spawn_x: self.spawn_x.unwrap_or(defaults.spawn_x),
+            spawn_y: self.spawn_y.map_or(defaults.spawn_y, |y| {
+                y.clamp(i16::MIN as i32, i16::MAX as i32) as i16
+            }),
+            spawn_z: self.spawn_z.unwrap_or(defaults.spawn_z),
+            spawn_angle: self.spawn_angle.unwrap_or(defaults.spawn_angle),
+            seed: self
+                .world_gen_settings
+                .map(|settings| settings.seed)
+                .or(self.random_seed)
+                .unwrap_or_default(),
+            time: self.time.unwrap_or_default(),
+            day_time: self.day_time.unwrap_or_default(),
+            weather,
+            game_rules: self.game_rules.unwrap_or_default(),
+        }
+    }
+
+    /// Vanilla doesn't save how heavy the rain is, it's full on if it's raining when it loads.
+    fn weather(&self) -> Weather {
+        let raining = self.raining.unwrap_or_default();
+        let thundering = self.thundering.unwrap_or_default();
+        Weather {
+            clear_time: self.clear_weather_time.unwrap_or_default(),
+            rain_time: self.rain_time.unwrap_or_default(),
+            raining,
+            thunder_time: self.thunder_time.unwrap_or_default(),
+            thundering,
+            rain_level: if raining { 1.0 } else { 0.0 },
+            thunder_level: if thundering { 1.0 } else { 0.0 },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::storage::MemoryStorage;
+
+    #[tokio::test]
+    async fn test_save_and_load() {
+        let database = Database::new(Box::new(MemoryStorage::new()), 1024);
+        assert_eq!(database.load_world_meta().await.unwrap(), None);
+
+        let meta = WorldMeta {
+            spawn_x: 100,
+            seed: -42,
+            game_rules: BTreeMap::from([("doDaylightCycle".to_string(), "false".to_string())]),
+            ..Default::default()
+        };
+        database.save_world_meta(&meta).await.unwrap();
+        assert_eq!(database.load_world_meta().await.unwrap(), Some(meta));
+    }
+
+    #[test]
+    fn test_game_rules() {
+        let mut meta = WorldMeta {
+            game_rules: BTreeMap::from([
+                (game_rules::KEEP_INVENTORY.name.to_string(), "true".to_string()),
+                (game_rules::SHOW_DEATH_MESSAGES.name.to_string(), "maybe".to_string()),
+            ]),
+            ..Default::default()
+        };
+        assert!(meta.game_rule_enabled(&game_rules::KEEP_INVENTORY));
+        // Not a bool, so it's the default
+        assert!(meta.game_rule_enabled(&game_rules::SHOW_DEATH_MESSAGES));
+        assert!(meta.game_rule_enabled(&game_rules::DO_DAYLIGHT_CYCLE));
+        assert_eq!(meta.game_rule_int(&game_rules::SPAWN_RADIUS), 10);
+
+        assert_eq!(meta.set_game_rule(&game_rules::SPAWN_RADIUS, "true"), None);
+        assert_eq!(
+            meta.set_game_rule(&game_rules::SPAWN_RADIUS, "0"),
+            Some(GameRuleValue::Int(0))
+        );
+        assert_eq!(meta.game_rule_int(&game_rules::SPAWN_RADIUS), 0);
+        assert_eq!(meta.game_rules["spawnRadius"], "0");
+    }
+
+    #[test]
+    fn test_tick_time() {
+        let mut meta = WorldMeta {
+            day_time: DAY_LENGTH - 1,
+            ..Default::default()
+        };
+        meta.tick_time();
+        assert_eq!(meta.time, 1);
+        assert_eq!(meta.day_time, DAY_LENGTH);
+        assert_eq!(meta.time_of_day(), 0);
+
+        meta.set_game_rule(&game_rules::DO_DAYLIGHT_CYCLE, "false");
+        meta.tick_time();
+        assert_eq!(meta.time, 2);
+        assert_eq!(meta.day_time, DAY_LENGTH);
+    }
+
+    #[test]
+    fn test_convert_vanilla_level() {
+        let level = VanillaLevelData {
+            spawn_x: Some(-8),
+            spawn_y: Some(70),
+            spawn_z: Some(16),
+            spawn_angle: None,
+            world_gen_settings: Some(VanillaWorldGenSettings { seed: 123 }),
+            random_seed: Some(456),
+            time: Some(24000),
+            day_time: Some(6000),
+            clear_weather_time: None,
+            rain_time: Some(200),
+            raining: Some(true),
+            thunder_time: None,
+            thundering: None,
+            game_rules: None,
+        };
+
+        let meta = level.convert();
+        assert_eq!(meta.spawn_position(), Position::new(-8, 70, 16));
+        assert_eq!(meta.spawn_angle, init::DEFAULT_SPAWN_YAW);
+        // The newer location wins
+        assert_eq!(meta.seed, 123);
+        assert_eq!(meta.day_time, 6000);
+        assert!(meta.weather.raining && meta.weather.is_raining());
+        assert!(!meta.weather.thundering);
+        assert!(meta.game_rules.is_empty());
+    }
+
+    #[test]
+    fn test_seed_hash() {
+        // Worked out with another SHA-256 implementation
+        let meta = WorldMeta {
+            seed: 1,
+            ..Default::default()
+        };
+        assert_eq!(meta.seed_hash(), -6467378160175308932);
+    }
+}
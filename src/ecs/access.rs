@@ -0,0 +1,136 @@
+use std::any::TypeId;
+
+/// What a system reads and writes, so the ones that don't get in each other's way can run at the
+/// same time. Usually these are [Components](crate::ecs::component::Component) or
+/// [Resources](crate::ecs::resource::Resource), but anything shared works.
+///
+/// # Examples
+/// ```ignore
+/// let access = Access::new().read::<Player>().write::<KeepAlive>();
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Access {
+    reads: Vec<TypeId>,
+    writes: Vec<TypeId>,
+    exclusive: bool,
+}
+
+impl Access {
+    /// Nothing at all.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Anything and everything, so nothing runs alongside it. For systems that haven't said what
+    /// they use.
+    pub fn exclusive() -> Self {
+        Self {
+            exclusive: true,
+            ..Self::default()
+        }
+    }
+
+    pub fn read<T: 'static>(mut self) -> Self {
+        self.reads.push(TypeId::of::<T>());
+        self
+    }
+
+    pub fn write<T: 'static>(mut self) -> Self {
+        self.writes.push(TypeId::of::<T>());
+        self
+    }
+
+    pub fn is_exclusive(&self) -> bool {
+        self.exclusive
+    }
+
+    /// Whether running both at once could go wrong: one writes something the other uses, or either
+    /// is exclusive. Reading the same things is fine.
+    pub fn conflicts_with(&self, other: &Access) -> bool {
+        if self.exclusive || other.exclusive {
+            return true;
+        }
+        let writes_used_by = |writes: &[TypeId], access: &Access| {
+            writes
+                .iter()
+                .any(|type_id| access.reads.contains(type_id) || access.writes.contains(type_id))
+        };
+        writes_used_by(&self.writes, other) || writes_used_by(&other.writes, self)
+    }
+}
+
+/// Splits systems, in the order they're meant to run, into stages that run one after the other.
+/// Everything in a stage can run at the same time. Returns the indices of the systems in each.
+///
+/// A system goes in the stage after the last one with a system it conflicts with, so conflicting
+/// systems still run in their original order, and the others as early as they can.
+pub fn stages(accesses: &[Access]) -> Vec<Vec<usize>> {
+    let mut stage_of: Vec<usize> = Vec::with_capacity(accesses.len());
+    let mut stages: Vec<Vec<usize>> = Vec::new();
+    for (index, access) in accesses.iter().enumerate() {
+        let stage = (0..index)
+            .filter(|&earlier| accesses[earlier].conflicts_with(access))
+            .map(|earlier| stage_of[earlier] + 1)
+            .max()
+            .unwrap_or(0);
+        stage_of.push(stage);
+        if stage == stages.len() {
+            stages.push(Vec::new());
+        }
+        stages[stage].push(index);
+    }
+    stages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct A;
+    struct B;
+
+    #[test]
+    fn test_conflicts() {
+        let read_a = Access::new().read::<A>();
+        let write_a = Access::new().write::<A>();
+        let read_a_write_b = Access::new().read::<A>().write::<B>();
+
+        assert!(!read_a.conflicts_with(&read_a));
+        assert!(read_a.conflicts_with(&write_a));
+        assert!(write_a.conflicts_with(&read_a));
+        assert!(write_a.conflicts_with(&write_a));
+        assert!(!read_a.conflicts_with(&read_a_write_b));
+        assert!(write_a.conflicts_with(&read_a_write_b));
+        assert!(Access::exclusive().conflicts_with(&Access::new()));
+        assert!(!Access::new().conflicts_with(&Access::new()));
+    }
+
+    #[test]
+    fn test_stages() {
+        let accesses = [
+            Access::new().write::<A>(),
+            Access::new().write::<B>(),
+            Access::new().read::<A>(),
+            Access::new().read::<B>(),
+            Access::exclusive(),
+            Access::new().read::<A>(),
+            Access::new().read::<B>(),
+        ];
+        assert_eq!(
+            stages(&accesses),
+            vec![vec![0, 1], vec![2, 3], vec![4], vec![5, 6]]
+        );
+        assert!(stages(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_stages_run_early() {
+        // The last one doesn't touch A, so it doesn't wait for the second
+        let accesses = [
+            Access::new().write::<A>(),
+            Access::new().write::<A>(),
+            Access::new().read::<B>(),
+        ];
+        assert_eq!(stages(&accesses), vec![vec![0, 2], vec![1]]);
+    }
+}
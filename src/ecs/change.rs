@@ -0,0 +1,147 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::ecs::component::ComponentStorage;
+
+/// When a component was added and last changed, as [change ticks](ComponentStorage::change_tick).
+#[derive(Debug)]
+pub struct ComponentTicks {
+    added: u64,
+    changed: AtomicU64,
+}
+
+impl ComponentTicks {
+    pub(crate) fn new(tick: u64) -> Self {
+        Self {
+            added: tick,
+            changed: AtomicU64::new(tick),
+        }
+    }
+
+    pub fn added(&self) -> u64 {
+        self.added
+    }
+
+    /// Adding it counts as a change too.
+    pub fn changed(&self) -> u64 {
+        self.changed.load(Ordering::Acquire)
+    }
+
+    pub fn is_added(&self, since: u64) -> bool {
+        self.added > since
+    }
+
+    pub fn is_changed(&self, since: u64) -> bool {
+        self.changed() > since
+    }
+
+    pub(crate) fn set_changed(&self, tick: u64) {
+        self.changed.fetch_max(tick, Ordering::AcqRel);
+    }
+}
+
+/// Remembers when a system last ran, so it can only look at what's been added or changed since.
+/// Systems are unit structs, so keep it in a static.
+///
+/// # Examples
+/// ```ignore
+/// static LAST_RUN: LastRun = LastRun::new();
+///
+/// let since = LAST_RUN.start(state.world.get_component_storage());
+/// let query = state.world.query::<(Changed<DisplayName>, &Player)>().since(since);
+/// for (entity_id, (display_name, player)) in query.iter().await {
+///     // Only the players whose display name changed
+/// }
+/// ```
+#[derive(Debug, Default)]
+pub struct LastRun(AtomicU64);
+
+impl LastRun {
+    pub const fn new() -> Self {
+        Self(AtomicU64::new(0))
+    }
+
+    /// Starts a run, returning the change tick the last one started at. Everything counts as added
+    /// on the first run.
+    ///
+    /// Changes the system makes itself are seen on its next run, like everyone else's.
+    pub fn start(&self, storage: &ComponentStorage) -> u64 {
+        let now = storage.advance_change_tick();
+        self.0.swap(now, Ordering::AcqRel)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ecs::query::{Added, Changed};
+    use crate::ecs::world::World;
+    use crate::utils::encoding::position::Position;
+    use crate::utils::encoding::velocity::Velocity;
+
+    use super::*;
+
+    async fn changed_positions(world: &World, since: u64) -> Vec<usize> {
+        let query = world.query::<Changed<Position>>().since(since);
+        let changed = query.iter().await.map(|(entity_id, _)| entity_id);
+        changed.collect()
+    }
+
+    #[tokio::test]
+    async fn test_changed() {
+        let world = World::new();
+        let last_run = LastRun::new();
+        let first = world
+            .create_entity()
+            .await
+            .with(Position::new(0, 0, 0))
+            .build();
+        let second = world
+            .create_entity()
+            .await
+            .with(Position::new(0, 0, 0))
+            .build();
+
+        let since = last_run.start(world.get_component_storage());
+        assert_eq!(changed_positions(&world, since).await, vec![first, second]);
+
+        // Nothing's happened since
+        let since = last_run.start(world.get_component_storage());
+        assert!(changed_positions(&world, since).await.is_empty());
+
+        // Only mutating it counts, not just borrowing it mutably
+        world.get_component_mut::<Position>(second).await.unwrap().x = 1;
+        drop(world.get_component_mut::<Position>(first).await.unwrap());
+        let since = last_run.start(world.get_component_storage());
+        assert_eq!(changed_positions(&world, since).await, vec![second]);
+    }
+
+    #[tokio::test]
+    async fn test_added() {
+        let world = World::new();
+        let last_run = LastRun::new();
+        let first = world
+            .create_entity()
+            .await
+            .with(Position::new(0, 0, 0))
+            .build();
+        last_run.start(world.get_component_storage());
+
+        let second = world
+            .create_entity()
+            .await
+            .with(Position::new(0, 0, 0))
+            .build();
+        world.get_component_mut::<Position>(first).await.unwrap().x = 1;
+        world
+            .get_component_storage()
+            .insert(first, Velocity::new(0, 0, 0));
+
+        let since = last_run.start(world.get_component_storage());
+        let query = world.query::<Added<Position>>().since(since);
+        let added: Vec<usize> = query.iter().await.map(|(entity_id, _)| entity_id).collect();
+        assert_eq!(added, vec![second]);
+
+        let query = world.query::<(&Position, Added<Velocity>)>().since(since);
+        let added: Vec<usize> = query.iter().await.map(|(entity_id, _)| entity_id).collect();
+        assert_eq!(added, vec![first]);
+    }
+}
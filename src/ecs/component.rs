@@ -1,16 +1,31 @@
 use crate::utils::prelude::*;
-use std::any::TypeId;
+use std::any::{Any, TypeId};
 use std::fmt::Debug;
 use std::marker::PhantomData;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 
+use crate::ecs::change::ComponentTicks;
 use crate::ecs::error::Error;
 use crate::ecs::helpers::sparse_set::SparseSet;
 use dashmap::DashMap;
-use tokio::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+use tokio::sync::{OwnedRwLockReadGuard, OwnedRwLockWriteGuard, RwLock};
 
 /// A trait for components in the ECS.
 pub trait Component: 'static + Send + Sync + Debug {}
 
+/// A component that's been boxed up without its type, e.g. while an entity's being built. It
+/// still knows which storage it goes in.
+pub trait ErasedComponent: Send + Sync + Debug {
+    fn insert_into(self: Box<Self>, storage: &ComponentStorage, entity_id: usize);
+}
+
+impl<T: Component> ErasedComponent for T {
+    fn insert_into(self: Box<Self>, storage: &ComponentStorage, entity_id: usize) {
+        storage.insert(entity_id, *self);
+    }
+}
+
 /// An immutable reference to a component.
 ///
 /// # Examples
@@ -20,11 +35,12 @@ pub trait Component: 'static + Send + Sync + Debug {}
 /// ```
 #[derive(Debug)]
 pub struct ComponentRef<'a, T: Component + 'a> {
-    read_guard: RwLockReadGuard<'a, Box<dyn Component>>,
-    _phantom: PhantomData<T>,
+    read_guard: OwnedRwLockReadGuard<T>,
+    _phantom: PhantomData<&'a ()>,
 }
 
-/// A mutable reference to a component.
+/// A mutable reference to a component. The component only counts as
+/// [changed](crate::ecs::query::Changed) if it's actually mutated through it.
 ///
 /// # Examples
 /// ```ignore
@@ -33,34 +49,93 @@ pub struct ComponentRef<'a, T: Component + 'a> {
 /// ```
 #[derive(Debug)]
 pub struct ComponentRefMut<'a, T: Component> {
-    write_guard: RwLockWriteGuard<'a, Box<dyn Component>>,
-    _phantom: PhantomData<T>,
+    write_guard: OwnedRwLockWriteGuard<T>,
+    ticks: Arc<ComponentTicks>,
+    change_tick: &'a AtomicU64,
+    changed: bool,
 }
 
 impl<'a, T: Component> std::ops::Deref for ComponentRef<'a, T> {
     type Target = T;
 
     fn deref(&self) -> &Self::Target {
-        unsafe { &*(&**self.read_guard as *const dyn Component as *const T) }
+        &self.read_guard
     }
 }
 impl<'id, T: Component> std::ops::Deref for ComponentRefMut<'id, T> {
     type Target = T;
 
     fn deref(&self) -> &Self::Target {
-        unsafe { &*(&**self.write_guard as *const dyn Component as *const T) }
+        &self.write_guard
     }
 }
 
 impl<'id, T: Component> std::ops::DerefMut for ComponentRefMut<'id, T> {
     fn deref_mut(&mut self) -> &mut Self::Target {
-        unsafe { &mut *(&mut **self.write_guard as *mut dyn Component as *mut T) }
+        self.changed = true;
+        &mut self.write_guard
+    }
+}
+
+impl<'id, T: Component> Drop for ComponentRefMut<'id, T> {
+    fn drop(&mut self) {
+        // Marked once it's done with rather than when it's first mutated, so a system that starts
+        // in the meantime can't miss it
+        if self.changed {
+            let tick = self.change_tick.load(Ordering::Acquire);
+            self.ticks.set_changed(tick);
+        }
+    }
+}
+
+/// A component and when it was added and changed. Each is behind its own [Arc] so a
+/// [ComponentRef] can keep hold of it without borrowing the set, which moves its components about as
+/// it grows.
+struct Slot<T> {
+    value: Arc<RwLock<T>>,
+    ticks: Arc<ComponentTicks>,
+}
+
+/// Every component of one type.
+type Components<T> = SparseSet<Slot<T>>;
+
+/// What the storage map holds: a [Components] of some type, downcast back with [Storage::as_any].
+trait Storage: Send + Sync {
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+    fn remove_entity(&mut self, entity_id: usize);
+}
+
+impl<T: Component> Storage for Components<T> {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn remove_entity(&mut self, entity_id: usize) {
+        self.remove(entity_id);
     }
 }
 
+fn downcast<T: Component>(storage: &dyn Storage) -> Result<&Components<T>> {
+    let components = storage.as_any().downcast_ref::<Components<T>>();
+    Ok(components.ok_or(Error::StorageTypeMismatch)?)
+}
+
+fn downcast_mut<T: Component>(storage: &mut dyn Storage) -> Result<&mut Components<T>> {
+    let components = storage.as_any_mut().downcast_mut::<Components<T>>();
+    Ok(components.ok_or(Error::StorageTypeMismatch)?)
+}
+
 /// A storage structure for components in the ECS.
 pub struct ComponentStorage {
-    storages: DashMap<TypeId, SparseSet<RwLock<Box<dyn Component>>>>,
+    storages: DashMap<TypeId, Box<dyn Storage>>,
+    /// Goes up every time a system [starts a run](crate::ecs::change::LastRun::start). Components
+    /// are stamped with it when they're added or changed.
+    change_tick: AtomicU64,
 }
 
 // New + Insert
@@ -69,6 +144,7 @@ impl ComponentStorage {
     pub fn new() -> Self {
         Self {
             storages: DashMap::new(),
+            change_tick: AtomicU64::new(1),
         }
     }
 
@@ -85,10 +161,50 @@ impl ComponentStorage {
             .map_err(|_| Error::ConversionError)
             .unwrap();
         let type_id = TypeId::of::<T>();
-        let mut storage = self.storages.entry(type_id).or_insert_with(SparseSet::new);
-        storage.insert(entity_id, RwLock::new(Box::new(component)));
+        let mut storage = self
+            .storages
+            .entry(type_id)
+            .or_insert_with(|| Box::new(Components::<T>::new()));
+        let slot = Slot {
+            value: Arc::new(RwLock::new(component)),
+            ticks: Arc::new(ComponentTicks::new(self.change_tick())),
+        };
+        downcast_mut::<T>(&mut **storage)
+            .expect("Components are stored by their own type. Please report this as a bug.")
+            .insert(entity_id, slot);
         self
     }
+
+    /// Inserts already boxed components for a given entity, each in the storage for its own type.
+    ///
+    /// Used by the [EntityBuilder](crate::ecs::helpers::entity_builder::EntityBuilder) to add all of
+    /// an entity's components in one go.
+    pub fn insert_all(
+        &self,
+        entity_id: usize,
+        components: impl IntoIterator<Item = Box<dyn ErasedComponent>>,
+    ) {
+        for component in components {
+            component.insert_into(self, entity_id);
+        }
+    }
+
+    /// The lock around a component and its ticks, without locking it, so the storage isn't
+    /// borrowed while waiting for it.
+    fn slot<T: Component>(
+        &self,
+        entity_id: usize,
+    ) -> Result<(Arc<RwLock<T>>, Arc<ComponentTicks>)> {
+        let type_id = TypeId::of::<T>();
+        let storage = self
+            .storages
+            .get(&type_id)
+            .ok_or(Error::ComponentNotFound)?;
+        let slot = downcast::<T>(&**storage)?
+            .get(entity_id)
+            .ok_or(Error::ComponentNotFound)?;
+        Ok((Arc::clone(&slot.value), Arc::clone(&slot.ticks)))
+    }
 }
 
 impl Default for ComponentStorage {
@@ -110,20 +226,9 @@ impl ComponentStorage {
         &self,
         entity_id: impl TryInto<usize>,
     ) -> Result<ComponentRef<'a, T>> {
-        let type_id = TypeId::of::<T>();
         let entity_id = entity_id.try_into().map_err(|_| Error::ConversionError)?;
-        let storage = self
-            .storages
-            .get(&type_id)
-            .ok_or(Error::ComponentNotFound)?;
-        let component = storage.get(entity_id).ok_or(Error::ComponentNotFound)?;
-
-        let read_guard = unsafe {
-            std::mem::transmute::<
-                RwLockReadGuard<'_, Box<dyn Component>>,
-                RwLockReadGuard<'_, Box<dyn Component>>,
-            >(component.read().await)
-        };
+        let (component, _) = self.slot::<T>(entity_id)?;
+        let read_guard = component.read_owned().await;
 
         Ok(ComponentRef {
             read_guard,
@@ -142,30 +247,57 @@ impl ComponentStorage {
         &self,
         entity_id: impl TryInto<usize>,
     ) -> Result<ComponentRefMut<T>> {
-        let type_id = TypeId::of::<T>();
         let entity_id = entity_id.try_into().map_err(|_| Error::ConversionError)?;
-        let storage = self
-            .storages
-            .get(&type_id)
-            .ok_or(Error::ComponentNotFound)?;
-        let component = storage.get(entity_id).ok_or(Error::ComponentNotFound)?;
-
-        let write = component.write().await;
-
-        let write_guard = unsafe {
-            std::mem::transmute::<
-                RwLockWriteGuard<'_, Box<dyn Component>>,
-                RwLockWriteGuard<Box<dyn Component>>,
-            >(write)
-        };
+        let (component, ticks) = self.slot::<T>(entity_id)?;
+        let write_guard = component.write_owned().await;
 
         Ok(ComponentRefMut {
             write_guard,
-            _phantom: PhantomData,
+            ticks,
+            change_tick: &self.change_tick,
+            changed: false,
         })
     }
 }
 
+// Change detection
+impl ComponentStorage {
+    /// The current change tick. See [LastRun](crate::ecs::change::LastRun) for how systems use it.
+    pub fn change_tick(&self) -> u64 {
+        self.change_tick.load(Ordering::Acquire)
+    }
+
+    /// Moves the change tick on, returning what it was.
+    pub fn advance_change_tick(&self) -> u64 {
+        self.change_tick.fetch_add(1, Ordering::AcqRel)
+    }
+
+    /// Whether the entity's `T` was added after the `since` change tick. False if it hasn't got one.
+    pub fn is_added<T: Component>(&self, entity_id: usize, since: u64) -> bool {
+        self.with_ticks::<T, _>(entity_id, |ticks| ticks.is_added(since))
+    }
+
+    /// Whether the entity's `T` was added or changed after the `since` change tick. False if it
+    /// hasn't got one.
+    pub fn is_changed<T: Component>(&self, entity_id: usize, since: u64) -> bool {
+        self.with_ticks::<T, _>(entity_id, |ticks| ticks.is_changed(since))
+    }
+
+    fn with_ticks<T: Component, R: Default>(
+        &self,
+        entity_id: usize,
+        f: impl FnOnce(&ComponentTicks) -> R,
+    ) -> R {
+        let Some(storage) = self.storages.get(&TypeId::of::<T>()) else {
+            return R::default();
+        };
+        let slot = downcast::<T>(&**storage)
+            .ok()
+            .and_then(|components| components.get(entity_id));
+        slot.map_or_else(R::default, |slot| f(&slot.ticks))
+    }
+}
+
 // GetOrInsertWith + GetMutOrInsertWith
 impl ComponentStorage {
     pub async fn get_or_insert_with<'a, T: Component + 'a>(
@@ -221,11 +353,12 @@ impl ComponentStorage {
         let type_id = TypeId::of::<T>();
         let entity_id = entity_id.into();
         if let Some(mut storage) = self.storages.get_mut(&type_id) {
+            let storage = downcast_mut::<T>(&mut **storage)?;
             let component = storage.get(entity_id);
             let Some(component) = component else {
                 return Err(Error::ComponentNotFound)?;
             };
-            if component.try_write().is_err() {
+            if component.value.try_write().is_err() {
                 return Err(Error::ComponentLocked)?;
             }
             storage.remove(entity_id);
@@ -236,7 +369,7 @@ impl ComponentStorage {
     pub fn remove_all(&self, entity_id: impl Into<usize>) {
         let entity_id = entity_id.into();
         for mut storage in self.storages.iter_mut() {
-            storage.remove(entity_id);
+            storage.remove_entity(entity_id);
         }
     }
 }
@@ -277,4 +410,30 @@ mod tests {
         assert!(component.is_ok());
         assert_eq!(component.unwrap().x, 0);
     }
+
+    #[tokio::test]
+    async fn test_ref_outlives_growth() {
+        let storage = ComponentStorage::new();
+        storage.insert(0usize, Position { x: 1, y: 2, z: 3 });
+        let position = storage.get::<Position>(0usize).await.unwrap();
+
+        // Enough to move everything in the set about
+        for entity in 1..1000usize {
+            storage.insert(entity, Position { x: 0, y: 0, z: 0 });
+        }
+        assert_eq!((position.x, position.y, position.z), (1, 2, 3));
+    }
+
+    #[tokio::test]
+    async fn test_remove() {
+        let storage = ComponentStorage::new();
+        storage.insert(0usize, Position { x: 0, y: 0, z: 0 });
+
+        let position = storage.get_mut::<Position>(0usize).await.unwrap();
+        assert!(storage.remove::<Position>(0usize).is_err());
+        drop(position);
+
+        assert!(storage.remove::<Position>(0usize).is_ok());
+        assert!(storage.get::<Position>(0usize).await.is_err());
+    }
 }
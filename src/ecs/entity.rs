@@ -96,6 +96,13 @@ impl EntityManager {
             && inner.generations[entity.id as usize] == entity.generation
     }
 
+    /// Checks if an entity id is in use, whatever its generation.
+    pub async fn is_alive(&self, entity: impl Into<usize>) -> bool {
+        let entity = entity.into();
+        let inner = self.inner.read().await;
+        entity < inner.generations.len() && !inner.free_ids.contains(&(entity as u32))
+    }
+
     /// Returns the number of active entities.
     pub async fn entity_count(&self) -> usize {
         let inner = self.inner.read().await;
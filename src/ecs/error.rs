@@ -6,6 +6,10 @@ pub enum Error {
     ComponentNotFound,
     #[error("Couldn't remove component since it's locked")]
     ComponentLocked,
+    #[error("Component storage holds a different type to the one asked for")]
+    StorageTypeMismatch,
+    #[error("Resource {0} not found")]
+    ResourceNotFound(&'static str),
     #[error("Conversion error from usize to entity id")]
     ConversionError,
 }
@@ -1,9 +1,16 @@
-use crate::ecs::component::{Component, ComponentStorage};
+use std::any::TypeId;
+
+use crate::ecs::component::{Component, ComponentStorage, ErasedComponent};
 
 /// A builder for creating and configuring entities in an Entity-Component-System architecture.
+///
+/// Components are held by the builder until [EntityBuilder::build] is called, so queries never see
+/// an entity that only has some of its components.
+#[must_use = "the entity's components are only added when it's built"]
 pub struct EntityBuilder<'a> {
     entity_id: usize,
     component_storage: &'a ComponentStorage,
+    components: Vec<(TypeId, Box<dyn ErasedComponent>)>,
 }
 impl<'a> EntityBuilder<'a> {
     /// Creates a new `EntityBuilder` instance.
@@ -17,6 +24,7 @@ impl<'a> EntityBuilder<'a> {
         EntityBuilder {
             entity_id,
             component_storage,
+            components: Vec::new(),
         }
     }
 
@@ -29,17 +37,62 @@ impl<'a> EntityBuilder<'a> {
     /// # Returns
     ///
     /// The `EntityBuilder` instance, allowing for method chaining.
-    pub fn with<T: Component>(self, component: T) -> Self {
-        self.component_storage.insert(self.entity_id, component);
+    pub fn with<T: Component>(mut self, component: T) -> Self {
+        let type_id = TypeId::of::<T>();
+        // Adding the same component twice replaces it, like inserting it would
+        self.components.retain(|(existing, _)| *existing != type_id);
+        self.components.push((type_id, Box::new(component)));
         self
     }
 
-    /// Finalizes the entity creation process.
+    /// Finalizes the entity creation process, adding all of its components to the world.
     ///
     /// # Returns
     ///
     /// The `entity_id` of the built entity.
     pub fn build(self) -> usize {
+        let components = self.components.into_iter().map(|(_, component)| component);
+        self.component_storage
+            .insert_all(self.entity_id, components);
         self.entity_id
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::ecs::world::World;
+    use crate::utils::encoding::position::Position;
+    use crate::utils::encoding::velocity::Velocity;
+
+    #[tokio::test]
+    async fn test_components_added_on_build() {
+        let world = World::new();
+
+        let builder = world
+            .create_entity()
+            .await
+            .with(Position::new(0, 1, 0))
+            .with(Velocity::new(1, 1, 1));
+        assert_eq!(world.query::<&Position>().iter().await.count(), 0);
+
+        let entity = builder.build();
+        let position = world.get_component::<Position>(entity).await.unwrap();
+        assert_eq!(position.y, 1);
+        assert!(world.get_component::<Velocity>(entity).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_with_replaces_component() {
+        let world = World::new();
+
+        let entity = world
+            .create_entity()
+            .await
+            .with(Position::new(0, 1, 0))
+            .with(Position::new(0, 2, 0))
+            .build();
+
+        let position = world.get_component::<Position>(entity).await.unwrap();
+        assert_eq!(position.y, 2);
+    }
+}
@@ -1,11 +1,14 @@
 #[cfg(test)]
 use std::sync::OnceLock;
 
+pub mod access;
+pub mod change;
 pub mod component;
 pub mod entity;
 pub mod error;
 pub mod helpers;
 pub mod query;
+pub mod resource;
 #[cfg(test)]
 pub mod test;
 #[cfg(test)]
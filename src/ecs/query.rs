@@ -12,6 +12,13 @@ pub trait QueryItem {
         entity_id: impl Into<usize>,
         storage: &'a ComponentStorage,
     ) -> Result<Self::Item<'a>>;
+
+    /// Whether the entity's components were added or changed the way the query wants since the
+    /// `since` [change tick](ComponentStorage::change_tick). Only [Added] and [Changed] care,
+    /// everything else matches.
+    fn matches(_entity_id: usize, _storage: &ComponentStorage, _since: u64) -> bool {
+        true
+    }
 }
 
 // Implement QueryItem for immutable references
@@ -38,12 +45,51 @@ impl<T: Component> QueryItem for &mut T {
     }
 }
 
+/// Fetches a `T` like `&T` does, but only if it was added since the query's
+/// [since](Query::since) tick.
+pub struct Added<T>(PhantomData<T>);
+
+impl<T: Component> QueryItem for Added<T> {
+    type Item<'a> = ComponentRef<'a, T>;
+
+    async fn fetch<'a>(
+        entity_id: impl Into<usize>,
+        storage: &'a ComponentStorage,
+    ) -> Result<Self::Item<'a>> {
+        storage.get::<T>(entity_id).await
+    }
+
+    fn matches(entity_id: usize, storage: &ComponentStorage, since: u64) -> bool {
+        storage.is_added::<T>(entity_id, since)
+    }
+}
+
+/// Fetches a `T` like `&T` does, but only if it was added or mutated since the query's
+/// [since](Query::since) tick.
+pub struct Changed<T>(PhantomData<T>);
+
+impl<T: Component> QueryItem for Changed<T> {
+    type Item<'a> = ComponentRef<'a, T>;
+
+    async fn fetch<'a>(
+        entity_id: impl Into<usize>,
+        storage: &'a ComponentStorage,
+    ) -> Result<Self::Item<'a>> {
+        storage.get::<T>(entity_id).await
+    }
+
+    fn matches(entity_id: usize, storage: &ComponentStorage, since: u64) -> bool {
+        storage.is_changed::<T>(entity_id, since)
+    }
+}
+
 /// Struct for querying components in the ECS.
 #[derive(Clone, Copy)]
 pub struct Query<'a, Q: QueryItem> {
     entity_manager: &'a EntityManager,
     component_storage: &'a ComponentStorage,
     current_id: usize,
+    since: u64,
     _marker: PhantomData<Q>,
 }
 
@@ -60,10 +106,19 @@ impl<'a, Q: QueryItem> Query<'a, Q> {
             entity_manager,
             component_storage,
             current_id: 0,
+            since: 0,
             _marker: PhantomData,
         }
     }
 
+    /// Only matches [Added] and [Changed] components that were added or changed after this
+    /// [change tick](ComponentStorage::change_tick), usually from
+    /// [LastRun::start](crate::ecs::change::LastRun::start). Without it everything counts.
+    pub fn since(mut self, tick: u64) -> Self {
+        self.since = tick;
+        self
+    }
+
     /// Returns an iterator over the query results.
     ///
     /// # Examples
@@ -87,6 +142,9 @@ impl<'a, Q: QueryItem> Query<'a, Q> {
         let mut results = vec![];
 
         for entity_id in 0..=max_entity_id {
+            if !Q::matches(entity_id, self.component_storage, self.since) {
+                continue;
+            }
             if let Ok(item) = Q::fetch(entity_id, self.component_storage).await {
                 results.push((entity_id, item));
             }
@@ -116,6 +174,10 @@ impl<'a, Q: QueryItem> Query<'a, Q> {
     {
         let max_entity_id = self.entity_manager.len().await;
         while self.current_id <= max_entity_id {
+            if !Q::matches(self.current_id, self.component_storage, self.since) {
+                self.current_id += 1;
+                continue;
+            }
             if let Ok(item) = Q::fetch(self.current_id, self.component_storage).await {
                 let result = Some((self.current_id, item));
                 self.current_id += 1;
@@ -145,6 +207,10 @@ macro_rules! impl_query_item_tuple {
                     )*
                 ))
             }
+
+            fn matches(entity_id: usize, storage: &ComponentStorage, since: u64) -> bool {
+                $($T::matches(entity_id, storage, since) &&)* true
+            }
         }
     };
 }
@@ -0,0 +1,162 @@
+use std::any::{type_name, Any, TypeId};
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use dashmap::DashMap;
+use tokio::sync::{OwnedRwLockReadGuard, OwnedRwLockWriteGuard, RwLock};
+
+use crate::ecs::error::Error;
+use crate::utils::prelude::*;
+
+/// Anything there's only one of for the whole world, e.g. the
+/// [WorldMeta](crate::database::world_meta::WorldMeta). Unlike components they don't belong to an
+/// entity.
+pub trait Resource: 'static + Send + Sync {}
+
+impl<T: 'static + Send + Sync> Resource for T {}
+
+/// An immutable reference to a resource, like a [ComponentRef](crate::ecs::component::ComponentRef).
+#[derive(Debug)]
+pub struct ResourceRef<'a, T: Resource> {
+    read_guard: OwnedRwLockReadGuard<T>,
+    _phantom: PhantomData<&'a ()>,
+}
+
+/// A mutable reference to a resource, like a
+/// [ComponentRefMut](crate::ecs::component::ComponentRefMut).
+#[derive(Debug)]
+pub struct ResourceRefMut<'a, T: Resource> {
+    write_guard: OwnedRwLockWriteGuard<T>,
+    _phantom: PhantomData<&'a ()>,
+}
+
+impl<'a, T: Resource> std::ops::Deref for ResourceRef<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.read_guard
+    }
+}
+
+impl<'a, T: Resource> std::ops::Deref for ResourceRefMut<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.write_guard
+    }
+}
+
+impl<'a, T: Resource> std::ops::DerefMut for ResourceRefMut<'a, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.write_guard
+    }
+}
+
+/// One of each [Resource], keyed by its type. Each is an `Arc<RwLock<T>>` behind the [Any].
+#[derive(Default)]
+pub struct Resources {
+    resources: DashMap<TypeId, Box<dyn Any + Send + Sync>>,
+}
+
+impl Resources {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a resource, replacing the one of the same type if there is one.
+    ///
+    /// # Examples
+    /// ```ignore
+    /// resources.insert(WorldMeta::default());
+    /// ```
+    pub fn insert<T: Resource>(&self, resource: T) {
+        let resource = Arc::new(RwLock::new(resource));
+        self.resources.insert(TypeId::of::<T>(), Box::new(resource));
+    }
+
+    pub fn contains<T: Resource>(&self) -> bool {
+        self.resources.contains_key(&TypeId::of::<T>())
+    }
+
+    /// Removes a resource. Anyone still holding a reference to it keeps it until they're done.
+    pub fn remove<T: Resource>(&self) -> Result<()> {
+        match self.resources.remove(&TypeId::of::<T>()) {
+            Some(_) => Ok(()),
+            None => Err(Error::ResourceNotFound(type_name::<T>()))?,
+        }
+    }
+
+    /// # Examples
+    /// ```ignore
+    /// let world_meta = resources.get::<WorldMeta>().await?;
+    /// let time = world_meta.time;
+    /// ```
+    pub async fn get<T: Resource>(&self) -> Result<ResourceRef<'_, T>> {
+        let read_guard = self.lock::<T>()?.read_owned().await;
+        Ok(ResourceRef {
+            read_guard,
+            _phantom: PhantomData,
+        })
+    }
+
+    /// # Examples
+    /// ```ignore
+    /// let mut world_meta = resources.get_mut::<WorldMeta>().await?;
+    /// world_meta.tick_time();
+    /// ```
+    pub async fn get_mut<T: Resource>(&self) -> Result<ResourceRefMut<'_, T>> {
+        let write_guard = self.lock::<T>()?.write_owned().await;
+        Ok(ResourceRefMut {
+            write_guard,
+            _phantom: PhantomData,
+        })
+    }
+
+    /// The lock around a resource, without locking it, so the map isn't borrowed while waiting
+    /// for it.
+    fn lock<T: Resource>(&self) -> Result<Arc<RwLock<T>>> {
+        let not_found = || Error::ResourceNotFound(type_name::<T>());
+        let resource = self
+            .resources
+            .get(&TypeId::of::<T>())
+            .ok_or_else(not_found)?;
+        let resource = resource
+            .downcast_ref::<Arc<RwLock<T>>>()
+            .ok_or(Error::StorageTypeMismatch)?;
+        Ok(Arc::clone(resource))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Time(u64);
+
+    #[tokio::test]
+    async fn test_insert_and_get() {
+        let resources = Resources::new();
+        assert!(resources.get::<Time>().await.is_err());
+
+        resources.insert(Time(0));
+        resources.get_mut::<Time>().await.unwrap().0 += 1;
+        assert_eq!(*resources.get::<Time>().await.unwrap(), Time(1));
+
+        resources.insert(Time(5));
+        assert_eq!(*resources.get::<Time>().await.unwrap(), Time(5));
+    }
+
+    #[tokio::test]
+    async fn test_remove() {
+        let resources = Resources::new();
+        resources.insert(Time(0));
+        let time = resources.get::<Time>().await.unwrap();
+
+        resources.remove::<Time>().unwrap();
+        assert!(!resources.contains::<Time>());
+        assert!(resources.remove::<Time>().is_err());
+        // Still there for whoever had it
+        assert_eq!(*time, Time(0));
+    }
+}
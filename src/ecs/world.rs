@@ -3,6 +3,7 @@ use crate::ecs::entity::EntityManager;
 use crate::ecs::error::Error;
 use crate::ecs::helpers::entity_builder::EntityBuilder;
 use crate::ecs::query::Query;
+use crate::ecs::resource::{Resource, ResourceRef, ResourceRefMut, Resources};
 
 use crate::utils::prelude::*;
 
@@ -58,6 +59,7 @@ use crate::utils::prelude::*;
 pub struct World {
     entity_manager: EntityManager,
     component_storage: ComponentStorage,
+    resources: Resources,
 }
 
 impl World {
@@ -73,6 +75,7 @@ impl World {
         Self {
             entity_manager: EntityManager::new(),
             component_storage: ComponentStorage::new(),
+            resources: Resources::new(),
         }
     }
 
@@ -100,14 +103,18 @@ impl World {
         EntityBuilder::new(entity, &self.component_storage)
     }
 
+    /// Deletes an entity along with all of its components, whatever their types.
     pub async fn delete_entity(&self, entity_id: impl TryInto<usize>) -> Result<()> {
         let entity_id = entity_id.try_into().map_err(|_| Error::ConversionError)?;
 
-        if !self.entity_manager.delete_entity(entity_id).await {
+        if !self.entity_manager.is_alive(entity_id).await {
             return Err(Error::EntityNotFound(entity_id))?;
         }
 
+        // The components have to go before the id is freed, otherwise a new entity could be
+        // given the id and lose its components
         self.component_storage.remove_all(entity_id);
+        self.entity_manager.delete_entity(entity_id).await;
 
         Ok(())
     }
@@ -183,6 +190,37 @@ impl World {
     pub fn get_component_storage(&self) -> &ComponentStorage {
         &self.component_storage
     }
+
+    /// Adds a [Resource], replacing the one of the same type if there is one.
+    pub fn insert_resource<T: Resource>(&self, resource: T) {
+        self.resources.insert(resource);
+    }
+
+    pub fn remove_resource<T: Resource>(&self) -> Result<()> {
+        self.resources.remove::<T>()
+    }
+
+    /// # Example
+    ///
+    /// ```ignore
+    /// let day_time = world.resource::<WorldMeta>().await?.day_time;
+    /// ```
+    pub async fn resource<T: Resource>(&self) -> Result<ResourceRef<'_, T>> {
+        self.resources.get::<T>().await
+    }
+
+    /// # Example
+    ///
+    /// ```ignore
+    /// world.resource_mut::<WorldMeta>().await?.tick_time();
+    /// ```
+    pub async fn resource_mut<T: Resource>(&self) -> Result<ResourceRefMut<'_, T>> {
+        self.resources.get_mut::<T>().await
+    }
+
+    pub fn get_resources(&self) -> &Resources {
+        &self.resources
+    }
 }
 
 impl Default for World {
@@ -254,5 +292,26 @@ mod multiple_components {
             assert_eq!(pos.x, 0);
             assert_eq!(rot.pitch, 0.0);
         }
+
+        #[tokio::test]
+        async fn test_delete_entity() {
+            let world = World::new();
+            let entity = world
+                .create_entity()
+                .await
+                .with(Position::new(0, 0, 0))
+                .with(Rotation::new(0f32, 0f32))
+                .build();
+
+            world.delete_entity(entity).await.unwrap();
+            assert!(world.get_component::<Position>(entity).await.is_err());
+            assert!(world.get_component::<Rotation>(entity).await.is_err());
+            assert!(world.delete_entity(entity).await.is_err());
+
+            // The id gets reused, without any of the old components
+            let reused = world.create_entity().await.build();
+            assert_eq!(reused, entity);
+            assert!(world.get_component::<Position>(reused).await.is_err());
+        }
     }
 }
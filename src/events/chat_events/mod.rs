@@ -0,0 +1,72 @@
+use crate::events::creation::event::{Cancellation, Event};
+use crate::net::packets::outgoing::system_chat_message::SystemChatMessage;
+use crate::net::utils::broadcast::broadcast_packet;
+use crate::state::GlobalState;
+use crate::utils::components::player::Player;
+use crate::utils::config::get_global_config;
+use ferrumc_macros::{event_handler, Constructor};
+use std::sync::Arc;
+use tracing::{error, info};
+
+/// Dispatched when a player sends a chat message (not a command).
+///
+/// Cancelling it stops the message from being broadcast.
+#[derive(Constructor)]
+pub struct ChatMessageEvent {
+    pub entity_id: usize,
+    pub message: String,
+    pub cancellation: Cancellation,
+}
+
+impl Event for ChatMessageEvent {
+    fn is_cancelled(&self) -> bool {
+        self.cancellation.is_cancelled()
+    }
+}
+
+#[event_handler(priority = "slow")]
+async fn on_chat_message(event: Arc<ChatMessageEvent>, state: GlobalState) {
+    if let Err(e) = broadcast_chat_message(&event, state).await {
+        error!("Failed to broadcast chat message: {:?}", e);
+    }
+}
+
+async fn broadcast_chat_message(event: &ChatMessageEvent, state: GlobalState) -> crate::Result<()> {
+    let username = state
+        .world
+        .get_component::<Player>(event.entity_id)
+        .await?
+        .get_username()
+        .to_string();
+
+    let message = format_chat_message(&get_global_config().chat_format, &username, &event.message);
+    info!("{}", message);
+
+    // Sent as system chat, so the client doesn't expect the message to be signed
+    broadcast_packet(SystemChatMessage::text(&message), &state).await
+}
+
+/// Fills in the `{username}` and `{message}` placeholders of the chat format.
+pub fn format_chat_message(format: &str, username: &str, message: &str) -> String {
+    // The message goes in last, so players can't inject a `{username}` of their own
+    format
+        .replace("{username}", username)
+        .replace("{message}", message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_chat_message() {
+        assert_eq!(
+            format_chat_message("<{username}> {message}", "Steve", "hello"),
+            "<Steve> hello"
+        );
+        assert_eq!(
+            format_chat_message("{username}: {message}", "Steve", "I am {username}"),
+            "Steve: I am {username}"
+        );
+    }
+}
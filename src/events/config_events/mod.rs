@@ -0,0 +1,38 @@
+use crate::events::creation::dispatcher::EventDispatcherExt;
+use crate::events::creation::event::Event;
+use crate::state::GlobalState;
+use crate::utils::config::{reload_global_config, ServerConfig};
+use crate::utils::prelude::*;
+use ferrumc_macros::Constructor;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+/// Dispatched after the config file has been reloaded, so anything that copied a setting when the
+/// server started can pick up the new value.
+///
+/// Anything that reads [get_global_config](crate::utils::config::get_global_config) when it needs a
+/// setting sees the new values without handling this.
+#[derive(Constructor)]
+pub struct ConfigReloadEvent {
+    pub old: Arc<ServerConfig>,
+    pub new: Arc<ServerConfig>,
+}
+
+impl Event for ConfigReloadEvent {}
+
+/// Reloads the config file and dispatches a [ConfigReloadEvent]. Run by /reload and on SIGHUP.
+pub async fn reload_config(state: &GlobalState) -> Result<()> {
+    let (old, new) = reload_global_config()?;
+
+    let restart_required = new.restart_required(&old);
+    if !restart_required.is_empty() {
+        warn!(
+            "Changes to {} won't take effect until the server is restarted",
+            restart_required.join(", ")
+        );
+    }
+
+    info!("Reloaded the config");
+    state.dispatch_event(ConfigReloadEvent::new(old, new)).await;
+    Ok(())
+}
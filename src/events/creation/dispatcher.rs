@@ -1,28 +1,75 @@
-use std::any::Any;
 use std::sync::Arc;
-use crate::events::creation::registry::{dispatch_event};
+use parking_lot::RwLock;
+use crate::events::creation::event::Event;
+use crate::events::creation::registry::{dispatch_event_with, EventHandlerWrapper};
 use crate::state::GlobalState;
 
-pub struct EventDispatcher;
+pub struct EventDispatcher {
+    /// Handlers registered at runtime rather than with `#[event_handler]`, with who registered
+    /// them so they can be removed again.
+    handlers: RwLock<Vec<RuntimeHandler>>,
+}
+
+struct RuntimeHandler {
+    owner: String,
+    priority: u8,
+    handler: Arc<dyn EventHandlerWrapper>,
+}
 
 
 impl EventDispatcher {
     pub fn new() -> Self {
-        Self
+        Self {
+            handlers: RwLock::new(Vec::new()),
+        }
     }
-    pub async fn dispatch_event<T: 'static + Any + Send + Sync>(&self, event: T, state: GlobalState) {
+    /// Runs the handlers for the event, and hands it back so the caller can check if it was cancelled.
+    pub async fn dispatch_event<T: Event>(&self, event: T, state: GlobalState) -> Arc<T> {
         let event = Arc::new(event);
-        dispatch_event::<T>(event, state).await;
+        dispatch_event_with::<T>(Arc::clone(&event), state, self.handlers_for::<T>()).await;
+        event
+    }
+
+    /// Adds a handler on behalf of `owner`, e.g. a [plugin](crate::plugins). See
+    /// [EventPriority](crate::events::creation::registry::EventPriority) for the priority.
+    pub fn add_handler(&self, owner: &str, priority: u8, handler: Arc<dyn EventHandlerWrapper>) {
+        self.handlers.write().push(RuntimeHandler {
+            owner: owner.to_string(),
+            priority,
+            handler,
+        });
+    }
+
+    /// Removes every handler `owner` added.
+    pub fn remove_handlers(&self, owner: &str) {
+        self.handlers
+            .write()
+            .retain(|handler| handler.owner != owner);
+    }
+
+    fn handlers_for<T: Event>(&self) -> Vec<(u8, Arc<dyn EventHandlerWrapper>)> {
+        self.handlers
+            .read()
+            .iter()
+            .filter(|handler| handler.handler.event_type_id() == std::any::TypeId::of::<T>())
+            .map(|handler| (handler.priority, Arc::clone(&handler.handler)))
+            .collect()
+    }
+}
+
+impl Default for EventDispatcher {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
 pub trait EventDispatcherExt {
     #[allow(async_fn_in_trait)]
-    async fn dispatch_event<T: 'static + Any + Send + Sync>(&self, event: T);
+    async fn dispatch_event<T: Event>(&self, event: T) -> Arc<T>;
 }
 
 impl EventDispatcherExt for GlobalState {
-    async fn dispatch_event<T: 'static + Any + Send + Sync>(&self, event: T) {
-        self.event_dispatcher.dispatch_event(event, self.clone()).await;
+    async fn dispatch_event<T: Event>(&self, event: T) -> Arc<T> {
+        self.event_dispatcher.dispatch_event(event, self.clone()).await
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,66 @@
+use std::any::Any;
+use std::sync::Mutex;
+
+/// Something that can be dispatched to event handlers.
+///
+/// Events that can be cancelled hold a [Cancellation] and return whether it's been cancelled from
+/// [Event::is_cancelled]. Once an event is cancelled, handlers after the current one aren't run, and
+/// whatever dispatched it should skip the action the event was about.
+pub trait Event: Any + Send + Sync {
+    fn is_cancelled(&self) -> bool {
+        false
+    }
+}
+
+/// Whether a cancellable event has been cancelled, and optionally why.
+#[derive(Debug, Default)]
+pub struct Cancellation {
+    reason: Mutex<Option<Option<String>>>,
+}
+
+impl Cancellation {
+    pub fn cancel(&self) {
+        self.set(None);
+    }
+
+    /// Cancels the event, with a reason that may be shown to the player.
+    pub fn cancel_with_reason(&self, reason: impl Into<String>) {
+        self.set(Some(reason.into()));
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.lock().is_some()
+    }
+
+    /// The reason given when the event was cancelled, if any.
+    pub fn reason(&self) -> Option<String> {
+        self.lock().clone().flatten()
+    }
+
+    fn set(&self, reason: Option<String>) {
+        *self.lock() = Some(reason);
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Option<Option<String>>> {
+        // Nothing can panic while the lock is held, so it can't be poisoned
+        self.reason.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cancellation() {
+        let cancellation = Cancellation::default();
+        assert!(!cancellation.is_cancelled());
+
+        cancellation.cancel();
+        assert!(cancellation.is_cancelled());
+        assert_eq!(cancellation.reason(), None);
+
+        cancellation.cancel_with_reason("Banned");
+        assert_eq!(cancellation.reason().as_deref(), Some("Banned"));
+    }
+}
@@ -1,3 +1,4 @@
+pub mod event;
 pub mod registry;
 #[cfg(test)]
 mod tests;
@@ -2,6 +2,7 @@ use std::any::Any;
 use std::future::Future;
 use std::pin::Pin;
 use std::sync::Arc;
+use crate::events::creation::event::Event;
 use crate::state::GlobalState;
 
 pub trait EventHandlerWrapper: Send + Sync + 'static {
@@ -60,14 +61,33 @@ pub fn get_event_handlers_for<T: 'static>() -> Vec<&'static EventContainer> {
     handlers
 }
 
-pub async fn dispatch_event<T: 'static + Any + Send + Sync>(event: Arc<T>, state: GlobalState) {
-    let handlers = get_event_handlers_for::<T>();
+/// Runs the handlers for the event in order of priority, stopping early if one of them cancels it.
+pub async fn dispatch_event<T: Event>(event: Arc<T>, state: GlobalState) {
+    dispatch_event_with(event, state, Vec::new()).await;
+}
 
-    let event = event as Arc<dyn Any + Send + Sync>;
+/// Like [dispatch_event], but with some extra handlers that were registered at runtime, e.g. by
+/// [plugins](crate::plugins), as `(priority, handler)`. They run after the built-in handlers with the
+/// same priority.
+pub async fn dispatch_event_with<T: Event>(
+    event: Arc<T>,
+    state: GlobalState,
+    extra: Vec<(u8, Arc<dyn EventHandlerWrapper>)>,
+) {
+    let mut handlers = get_event_handlers_for::<T>()
+        .into_iter()
+        .map(|h| (h.priority.0, h.handler))
+        .collect::<Vec<(u8, &dyn EventHandlerWrapper)>>();
+    handlers.extend(extra.iter().map(|(priority, handler)| (*priority, handler.as_ref())));
+    handlers.sort_by_key(|(priority, _)| *priority);
 
+    for (_, handler) in handlers {
+        let erased = Arc::clone(&event) as Arc<dyn Any + Send + Sync>;
+        handler.handle(erased, state.clone()).await;
 
-    for handler in handlers.iter() {
-        handler.handler.handle(Arc::clone(&event), state.clone()).await;
+        if event.is_cancelled() {
+            break;
+        }
     }
 }
 
@@ -91,5 +111,36 @@ impl<E: 'static + Any + Send + Sync> EventHandlerWrapper for FunctionEventHandle
     }
 }
 
+type BoxedHandler<E> =
+    Box<dyn Fn(Arc<E>, GlobalState) -> Pin<Box<dyn Future<Output = ()> + Send>> + Send + Sync>;
+
+/// An event handler made from a closure, for handlers that are registered at runtime rather than
+/// with `#[event_handler]`.
+pub struct ClosureEventHandler<E: Send + Sync> {
+    handler: BoxedHandler<E>,
+}
+
+impl<E: Send + Sync> ClosureEventHandler<E> {
+    pub fn new<F, Fut>(handler: F) -> Self
+    where
+        F: Fn(Arc<E>, GlobalState) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        Self {
+            handler: Box::new(move |event, state| Box::pin(handler(event, state))),
+        }
+    }
+}
+
+impl<E: 'static + Any + Send + Sync> EventHandlerWrapper for ClosureEventHandler<E> {
+    fn handle(&self, event: Arc<dyn Any + Send + Sync>, state: GlobalState) -> Pin<Box<dyn Future<Output=()> + Send + '_>> {
+        let event = Arc::downcast::<E>(event).expect("wrong type for event");
+        (self.handler)(event, state)
+    }
+
+    fn event_type_id(&self) -> std::any::TypeId {
+        std::any::TypeId::of::<E>()
+    }
+}
 
 inventory::collect!(EventContainer);
\ No newline at end of file
@@ -28,8 +28,11 @@ impl EventHandler for Handler2 {
 }
 
 */
-use crate::events::creation::registry::dispatch_event;
+use crate::events::creation::event::{Cancellation, Event};
+use crate::events::creation::dispatcher::EventDispatcherExt;
+use crate::events::creation::registry::{dispatch_event, ClosureEventHandler};
 use ferrumc_macros::event_handler;
+use std::sync::atomic::{AtomicU32, Ordering};
 use std::sync::Arc;
 use tokio::net::TcpListener;
 use crate::create_state;
@@ -39,6 +42,31 @@ struct TestEvent {
     value: i32,
 }
 
+impl Event for parking_lot::RwLock<TestEvent> {}
+
+#[derive(Default)]
+struct CancellableTestEvent {
+    handled: AtomicU32,
+    cancellation: Cancellation,
+}
+
+impl Event for CancellableTestEvent {
+    fn is_cancelled(&self) -> bool {
+        self.cancellation.is_cancelled()
+    }
+}
+
+#[event_handler(priority = "fastest")]
+async fn cancelling_handler(event: Arc<CancellableTestEvent>, _state: GlobalState) {
+    event.handled.fetch_add(1, Ordering::SeqCst);
+    event.cancellation.cancel();
+}
+
+#[event_handler(priority = "slowest")]
+async fn cancelled_handler(event: Arc<CancellableTestEvent>, _state: GlobalState) {
+    event.handled.fetch_add(1, Ordering::SeqCst);
+}
+
 #[event_handler(priority = "fastest")]
 async fn handler(event: Arc<parking_lot::RwLock<TestEvent>>, _state: GlobalState) {
     let mut event = event.write();
@@ -69,11 +97,47 @@ async fn test_if_this_even_compiles() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_cancelled_event_stops_propagating() -> anyhow::Result<()> {
+    let state = create_state(TcpListener::bind("127.0.0.1:0").await?).await?;
 
+    let event = Arc::new(CancellableTestEvent::default());
+    dispatch_event(Arc::clone(&event), state).await;
 
+    assert!(event.is_cancelled());
+    assert_eq!(event.handled.load(Ordering::SeqCst), 1);
 
+    Ok(())
+}
 
+#[derive(Default)]
+struct RuntimeTestEvent {
+    handled: AtomicU32,
+}
 
+impl Event for RuntimeTestEvent {}
 
+#[tokio::test]
+async fn test_runtime_handlers() -> anyhow::Result<()> {
+    let state = create_state(TcpListener::bind("127.0.0.1:0").await?).await?;
+    let handler = ClosureEventHandler::new(|event: Arc<RuntimeTestEvent>, _| async move {
+        event.handled.fetch_add(1, Ordering::SeqCst);
+    });
+    state.event_dispatcher.add_handler("test", 128, Arc::new(handler));
+    // Runs after the built-in handler with the same priority, which cancels the event
+    let handler = ClosureEventHandler::new(|event: Arc<CancellableTestEvent>, _| async move {
+        event.handled.fetch_add(1, Ordering::SeqCst);
+    });
+    state.event_dispatcher.add_handler("test", 0, Arc::new(handler));
+
+    let event = state.dispatch_event(RuntimeTestEvent::default()).await;
+    assert_eq!(event.handled.load(Ordering::SeqCst), 1);
+    let event = state.dispatch_event(CancellableTestEvent::default()).await;
+    assert_eq!(event.handled.load(Ordering::SeqCst), 1);
+
+    state.event_dispatcher.remove_handlers("test");
+    let event = state.dispatch_event(RuntimeTestEvent::default()).await;
+    assert_eq!(event.handled.load(Ordering::SeqCst), 0);
 
-
+    Ok(())
+}
@@ -0,0 +1,204 @@
+use crate::events::creation::dispatcher::EventDispatcherExt;
+use crate::events::creation::event::{Cancellation, Event};
+use crate::net::packets::outgoing::damage_event::DamageEventOut;
+use crate::net::packets::outgoing::entity_animation::{animations, EntityAnimation};
+use crate::net::utils::broadcast::broadcast_to_viewers;
+use crate::net::utils::combat::knock_back;
+use crate::net::utils::death::die;
+use crate::state::GlobalState;
+use crate::utils::components::abilities::Abilities;
+use crate::utils::components::food::{exhaust, exhaustion};
+use crate::utils::components::health::{send_health, Health};
+use crate::utils::components::mob::Mob;
+use crate::utils::components::player::Player;
+use crate::utils::prelude::*;
+use crate::world::mobs::kill_mob;
+use crate::world::projectiles::ProjectileKind;
+use ferrumc_macros::{event_handler, Constructor};
+use std::sync::Arc;
+use std::time::Instant;
+use tracing::error;
+
+/// Dispatched when something hurts an entity, before its health goes down. Falling, attacks and
+/// starving all go through it, see [damage].
+///
+/// Cancelling it leaves the entity unhurt.
+#[derive(Constructor)]
+pub struct DamageEvent {
+    pub entity_id: usize,
+    /// In half hearts.
+    pub amount: f32,
+    pub cause: DamageCause,
+    pub cancellation: Cancellation,
+}
+
+impl Event for DamageEvent {
+    fn is_cancelled(&self) -> bool {
+        self.cancellation.is_cancelled()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DamageCause {
+    Fall,
+    /// A player attacking.
+    Attack { attacker: usize },
+    /// Being hit by a projectile, like an arrow.
+    Projectile {
+        kind: ProjectileKind,
+        projectile: usize,
+        shooter: Option<usize>,
+    },
+    Starvation,
+    /// Falling out of the world.
+    Void,
+    Generic,
+}
+
+impl DamageCause {
+    /// Whether it hurts players that can't be hurt otherwise, like in creative.
+    pub fn bypasses_invulnerability(&self) -> bool {
+        matches!(self, DamageCause::Void)
+    }
+
+    /// Its id in the `minecraft:damage_type` registry, which decides the sound the client plays.
+    pub fn damage_type(&self) -> i32 {
+        match self {
+            DamageCause::Projectile {
+                kind: ProjectileKind::Arrow,
+                ..
+            } => 0,
+            DamageCause::Fall => 8,
+            DamageCause::Generic => 16,
+            DamageCause::Void => 29,
+            DamageCause::Attack { .. } => 31,
+            DamageCause::Starvation => 35,
+            DamageCause::Projectile {
+                kind: ProjectileKind::Snowball,
+                ..
+            } => 39,
+        }
+    }
+
+    /// The entity responsible, if there is one.
+    pub fn source(&self) -> Option<usize> {
+        match self {
+            DamageCause::Attack { attacker } => Some(*attacker),
+            DamageCause::Projectile { shooter, .. } => *shooter,
+            _ => None,
+        }
+    }
+
+    /// The entity that actually did the damage, like an arrow rather than whoever shot it.
+    pub fn direct_source(&self) -> Option<usize> {
+        match self {
+            DamageCause::Projectile { projectile, .. } => Some(*projectile),
+            _ => self.source(),
+        }
+    }
+}
+
+/// Dispatched when a player attacks an entity, before it's hurt. The damage is then dealt through
+/// a [DamageEvent].
+///
+/// Cancelling it stops the attack, so the entity isn't hurt or knocked back.
+#[derive(Constructor)]
+pub struct EntityDamageByEntityEvent {
+    pub attacker: usize,
+    pub target: usize,
+    /// In half hearts.
+    pub amount: f32,
+    pub critical: bool,
+    /// How fast the target is knocked back, in blocks per tick.
+    pub knockback: f64,
+    pub cancellation: Cancellation,
+}
+
+impl Event for EntityDamageByEntityEvent {
+    fn is_cancelled(&self) -> bool {
+        self.cancellation.is_cancelled()
+    }
+}
+
+/// Hurts an entity, unless it can't be hurt by `cause`. Returns whether it was hurt, which it
+/// isn't if the [DamageEvent] was cancelled.
+pub async fn damage(
+    state: &GlobalState,
+    entity_id: usize,
+    amount: f32,
+    cause: DamageCause,
+) -> Result<bool> {
+    let invulnerable = state
+        .world
+        .get_component::<Abilities>(entity_id)
+        .await
+        .is_ok_and(|abilities| abilities.invulnerable);
+    if amount <= 0.0 || (invulnerable && !cause.bypasses_invulnerability()) {
+        return Ok(false);
+    }
+    let event = DamageEvent::new(entity_id, amount, cause, Cancellation::default());
+    let event = state.dispatch_event(event).await;
+    Ok(!event.is_cancelled())
+}
+
+#[event_handler(priority = "slow")]
+async fn on_damage(event: Arc<DamageEvent>, state: GlobalState) {
+    if let Err(e) = apply_damage(&event, &state).await {
+        error!("Failed to damage entity {}: {:?}", event.entity_id, e);
+    }
+}
+
+async fn apply_damage(event: &DamageEvent, state: &GlobalState) -> Result<()> {
+    let died = {
+        let mut health = state
+            .world
+            .get_component_storage()
+            .get_mut::<Health>(event.entity_id)
+            .await?;
+        if health.is_dead() {
+            return Ok(());
+        }
+        health.damage(event.amount, Instant::now());
+        health.is_dead()
+    };
+
+    let cause = event.cause;
+    let packet = DamageEventOut::new(
+        event.entity_id,
+        cause.damage_type(),
+        cause.source(),
+        cause.direct_source(),
+    );
+    broadcast_to_viewers(packet, event.entity_id, state).await?;
+    if state.world.get_component::<Player>(event.entity_id).await.is_ok() {
+        exhaust(state, event.entity_id, exhaustion::DAMAGE).await;
+        send_health(state, event.entity_id).await?;
+        if died {
+            die(state, event.entity_id, cause).await?;
+        }
+    } else if died && state.world.get_component::<Mob>(event.entity_id).await.is_ok() {
+        kill_mob(state, event.entity_id).await?;
+    }
+    Ok(())
+}
+
+#[event_handler(priority = "slow")]
+async fn on_entity_damage_by_entity(event: Arc<EntityDamageByEntityEvent>, state: GlobalState) {
+    if let Err(e) = apply_attack(&event, &state).await {
+        error!("Failed for {} to attack {}: {:?}", event.attacker, event.target, e);
+    }
+}
+
+async fn apply_attack(event: &EntityDamageByEntityEvent, state: &GlobalState) -> Result<()> {
+    let cause = DamageCause::Attack {
+        attacker: event.attacker,
+    };
+    if !damage(state, event.target, event.amount, cause).await? {
+        return Ok(());
+    }
+    if event.critical {
+        let animation = EntityAnimation::new(event.target, animations::CRITICAL_EFFECT);
+        broadcast_to_viewers(animation, event.target, state).await?;
+    }
+    knock_back(state, event.target, event.attacker, event.knockback).await
+}
@@ -0,0 +1,29 @@
+use std::net::IpAddr;
+
+use crate::events::creation::event::{Cancellation, Event};
+use ferrumc_macros::Constructor;
+
+/// Dispatched when a player has started logging in, before they're let into the world.
+///
+/// Cancelling it denies the login. The cancellation reason is shown on the disconnect screen.
+#[derive(Constructor)]
+pub struct LoginStartEvent {
+    pub conn_id: usize,
+    pub username: String,
+    pub uuid: u128,
+    pub address: Option<IpAddr>,
+    pub cancellation: Cancellation,
+}
+
+impl LoginStartEvent {
+    /// Denies the login, kicking the player with the reason.
+    pub fn deny(&self, reason: impl Into<String>) {
+        self.cancellation.cancel_with_reason(reason);
+    }
+}
+
+impl Event for LoginStartEvent {
+    fn is_cancelled(&self) -> bool {
+        self.cancellation.is_cancelled()
+    }
+}
@@ -1,2 +1,7 @@
+pub mod chat_events;
+pub mod config_events;
 pub mod creation;
+pub mod health_events;
+pub mod login_events;
+pub mod projectile_events;
 pub mod world_events;
\ No newline at end of file
@@ -0,0 +1,78 @@
+use crate::events::creation::event::{Cancellation, Event};
+use crate::events::health_events::{damage, DamageCause};
+use crate::net::utils::combat::{knock_back_towards, KNOCKBACK};
+use crate::state::GlobalState;
+use crate::utils::components::abilities::Abilities;
+use crate::utils::components::health::Health;
+use crate::utils::components::projectile::Projectile;
+use crate::utils::prelude::*;
+use crate::world::projectiles::{arrow_damage, heading, ProjectileKind};
+use ferrumc_macros::{event_handler, Constructor};
+use std::sync::Arc;
+use std::time::Instant;
+use tracing::error;
+
+/// Dispatched when a projectile hits an entity, before the entity's hurt. The damage is then dealt
+/// through a [DamageEvent](crate::events::health_events::DamageEvent). Hitting a block doesn't
+/// dispatch it.
+///
+/// Cancelling it lets the projectile fly on through the entity.
+#[derive(Constructor)]
+pub struct ProjectileHitEvent {
+    /// The projectile's entity, which is removed once the event's done.
+    pub entity_id: usize,
+    pub projectile: Projectile,
+    /// How fast it was flying, in blocks per tick.
+    pub velocity: (f64, f64, f64),
+    pub target: usize,
+    pub cancellation: Cancellation,
+}
+
+impl Event for ProjectileHitEvent {
+    fn is_cancelled(&self) -> bool {
+        self.cancellation.is_cancelled()
+    }
+}
+
+#[event_handler(priority = "slow")]
+async fn on_projectile_hit(event: Arc<ProjectileHitEvent>, state: GlobalState) {
+    if let Err(e) = apply_hit(&event, &state).await {
+        error!("Failed for {} to hit {}: {:?}", event.entity_id, event.target, e);
+    }
+}
+
+/// Arrows hurt what they hit, the faster the more. Snowballs don't hurt, but they still push
+/// things back.
+async fn apply_hit(event: &ProjectileHitEvent, state: &GlobalState) -> Result<()> {
+    let now = Instant::now();
+    let hurtable = state
+        .world
+        .get_component::<Health>(event.target)
+        .await
+        .is_ok_and(|health| !health.is_dead() && !health.recently_hurt(now));
+    let invulnerable = state
+        .world
+        .get_component::<Abilities>(event.target)
+        .await
+        .is_ok_and(|abilities| abilities.invulnerable);
+    if !hurtable || invulnerable {
+        return Ok(());
+    }
+
+    let projectile = event.projectile;
+    let amount = match projectile.kind {
+        ProjectileKind::Arrow => {
+            arrow_damage(event.velocity, projectile.critical, rand::random::<f64>())
+        }
+        ProjectileKind::Snowball => 0.0,
+    };
+    let cause = DamageCause::Projectile {
+        kind: projectile.kind,
+        projectile: event.entity_id,
+        shooter: projectile.shooter,
+    };
+    if amount > 0.0 && !damage(state, event.target, amount, cause).await? {
+        return Ok(());
+    }
+    knock_back_towards(state, event.target, heading(event.velocity), KNOCKBACK).await
+}
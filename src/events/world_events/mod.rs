@@ -1,14 +1,22 @@
+use crate::events::creation::event::{Cancellation, Event};
 use crate::state::GlobalState;
+use crate::utils::components::dimension::dimension_of;
 use crate::utils::components::player::{Player};
+use crate::utils::encoding::position::Position;
+use crate::world::blocks::set_block;
+use crate::world::chunk_format::Palette;
+use crate::world::signs::{block_entity_id, open_sign_editor};
 use ferrumc_macros::{event_handler, Constructor};
 use std::sync::Arc;
 use tracing::{error, info};
 
 #[derive(Constructor)]
 pub struct PlayerJoinWorldEvent {
-    entity_id: usize,
+    pub entity_id: usize,
 }
 
+impl Event for PlayerJoinWorldEvent {}
+
 #[event_handler(priority = "slow")]
 async fn on_player_join_world(event: Arc<PlayerJoinWorldEvent>, state: GlobalState) {
     if let Err(e) = send_join_message(event.entity_id, state).await {
@@ -22,4 +30,68 @@ async fn send_join_message(entity_id: usize, state: GlobalState) -> crate::Resul
     info!("{} joined the world!", player.get_username());
     
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Dispatched when a player breaks a block, before it's removed.
+///
+/// Cancelling it leaves the block where it is.
+#[derive(Constructor)]
+pub struct BlockBreakEvent {
+    pub entity_id: usize,
+    pub position: Position,
+    /// The block that's being broken.
+    pub block: Palette,
+    pub cancellation: Cancellation,
+}
+
+impl Event for BlockBreakEvent {
+    fn is_cancelled(&self) -> bool {
+        self.cancellation.is_cancelled()
+    }
+}
+
+#[event_handler(priority = "slow")]
+async fn on_block_break(event: Arc<BlockBreakEvent>, state: GlobalState) {
+    let Position { x, y, z } = event.position;
+    let dimension = dimension_of(&state, event.entity_id).await;
+    let air = Palette::new("minecraft:air");
+    if let Err(e) = set_block(state, x, y as i32, z, air, dimension.name()).await {
+        error!("Failed to break block at {}: {:?}", event.position, e);
+    }
+}
+
+/// Dispatched when a player places a block, before it's put in the world.
+///
+/// Cancelling it stops the block from being placed.
+#[derive(Constructor)]
+pub struct BlockPlaceEvent {
+    pub entity_id: usize,
+    pub position: Position,
+    pub block: Palette,
+    pub cancellation: Cancellation,
+}
+
+impl Event for BlockPlaceEvent {
+    fn is_cancelled(&self) -> bool {
+        self.cancellation.is_cancelled()
+    }
+}
+
+#[event_handler(priority = "slow")]
+async fn on_block_place(event: Arc<BlockPlaceEvent>, state: GlobalState) {
+    let Position { x, y, z } = event.position;
+    let dimension = dimension_of(&state, event.entity_id).await;
+    let block = event.block.clone();
+    if let Err(e) = set_block(state.clone(), x, y as i32, z, block, dimension.name()).await {
+        error!("Failed to place block at {}: {:?}", event.position, e);
+        return;
+    }
+    // Whoever places a sign gets to write on it
+    if block_entity_id(&event.block.name).is_some() {
+        let opened =
+            open_sign_editor(&state, event.entity_id, &event.position, dimension.name()).await;
+        if let Err(e) = opened {
+            error!("Failed to open the sign editor at {}: {:?}", event.position, e);
+        }
+    }
+}
@@ -5,18 +5,45 @@ use std::sync::{atomic::AtomicU32, Arc};
 use dashmap::DashMap;
 use ecs::world::World;
 use net::ConnectionList;
+use net::throttle::ConnectionThrottle;
+use net::scheduler::Scheduler;
+use net::scoreboard::ScoreboardManager;
+use net::tab_list::TabList;
+use net::utils::skins::SkinCache;
 use state::{GlobalState, ServerState};
 use tokio::net::TcpListener;
 use utils::prelude::*;
 use crate::events::creation::dispatcher::EventDispatcher;
+use crate::access::bans::BanManager;
+use crate::access::ops::Operators;
+use crate::access::whitelist::Whitelist;
+use crate::database::players::PlayerStore;
+use crate::database::world_directory;
+use crate::commands::CommandRegistry;
+use crate::plugins::PluginManager;
+use crate::utils::config::get_global_config;
+use crate::world::generation::create_generator;
+use crate::utils::constants::{
+    BANNED_IPS_FILE, BANNED_PLAYERS_FILE, ITEM_REGISTRY_FILE, OPS_FILE, PLAYER_DATA_DIRECTORY,
+    RECIPE_DATA_DIRECTORY, REGISTRY_DATA_DIRECTORY, WHITELIST_FILE,
+};
+use crate::world::id_registry::IdRegistry;
+use crate::world::items::ItemRegistry;
+use crate::world::recipes::RecipeBook;
+use crate::world::registry_data::init_registry_data;
 
 extern crate core;
 #[macro_use]
 extern crate macro_rules_attribute;
 
+pub mod access;
+pub mod commands;
 pub mod ecs;
 pub mod net;
+pub mod plugins;
+pub mod scripting;
 pub mod setup;
+pub mod shutdown;
 #[cfg(test)]
 mod tests;
 pub mod utils;
@@ -27,14 +54,38 @@ pub mod world;
 pub mod events;
 
 pub async fn create_state(tcp_listener: TcpListener) -> Result<GlobalState> {
+    init_registry_data(REGISTRY_DATA_DIRECTORY)?;
+    let database = database::start_database().await?;
+    let world = World::new();
+    world.insert_resource(database.load_world_meta().await?.unwrap_or_default());
+    let items = ItemRegistry::load(ITEM_REGISTRY_FILE).await?;
+    let recipes = RecipeBook::load(RECIPE_DATA_DIRECTORY, &items)?;
+    let sounds = IdRegistry::load(ITEM_REGISTRY_FILE, "minecraft:sound_event").await?;
+    let particles = IdRegistry::load(ITEM_REGISTRY_FILE, "minecraft:particle_type").await?;
     Ok(Arc::new(ServerState {
-        world: Arc::new(World::new()),
+        world: Arc::new(world),
         connections: ConnectionList {
             connections: DashMap::new(),
             connection_count: AtomicU32::new(0),
         },
-        database: database::start_database().await?,
+        database,
+        players: PlayerStore::new(world_directory()?.join(PLAYER_DATA_DIRECTORY)),
         server_stream: tcp_listener,
         event_dispatcher: Arc::new(EventDispatcher::new()),
+        commands: Arc::new(CommandRegistry::new()),
+        whitelist: Whitelist::load(WHITELIST_FILE, get_global_config().whitelist).await?,
+        bans: BanManager::load(BANNED_PLAYERS_FILE, BANNED_IPS_FILE).await?,
+        ops: Operators::load(OPS_FILE).await?,
+        throttle: ConnectionThrottle::default(),
+        skins: SkinCache::default(),
+        tab_list: TabList::default(),
+        scoreboard: ScoreboardManager::default(),
+        world_generator: create_generator(&get_global_config().generation)?,
+        items,
+        recipes,
+        sounds,
+        particles,
+        plugins: PluginManager::default(),
+        scheduler: Scheduler::default(),
     }))
 }
@@ -1,14 +1,18 @@
 use std::env;
 use std::process::exit;
 
-use ferrumc::{create_state, setup, utils, world};
+use ferrumc::state::GlobalState;
+use ferrumc::{create_state, setup, shutdown, utils, world};
 use tokio::net::TcpListener;
 use tokio::select;
 use tokio::task::JoinHandle;
-use tracing::{error, info, trace};
+use tokio::time::timeout;
+use tracing::{error, info, trace, warn};
 
 use ferrumc::{
-    net::systems::{kill_all_systems, start_all_systems},
+    net::capture::{self, Capture},
+    net::systems::start_all_systems,
+    net::utils::encryption::get_server_key,
     utils::{config::get_global_config, prelude::*},
 };
 use ferrumc::utils::config::ServerConfig;
@@ -29,6 +33,11 @@ async fn entry() -> Result<()> {
         return Ok(());
     }
 
+    let replay_path = env::args().find_map(|arg| arg.strip_prefix("--replay=").map(String::from));
+    if let Some(path) = replay_path {
+        return replay(&path).await;
+    }
+
     info!("Initializing server...");
 
     {
@@ -36,10 +45,10 @@ async fn entry() -> Result<()> {
         let _ = ServerConfig::new()?;
     }
 
-    let server_handle = start_server().await?;
+    let (mut server_handle, state) = start_server().await?;
 
-    let need_to_kill = select! {
-        server_result = server_handle => {
+    let server_exited = select! {
+        server_result = &mut server_handle => {
             match server_result.expect("join_error") {
                 Ok(_) => {
                     info!("Server exited successfully!");
@@ -49,27 +58,54 @@ async fn entry() -> Result<()> {
                     error!("{}", e);
                 }
             }
-            false
+            true
         },
         _ = tokio::signal::ctrl_c() => {
             info!("Received ctrl+c.. Shutting down..");
-            true
+            false
         }
+        _ = shutdown::requested() => false,
     };
 
-    if need_to_kill {
-        kill_all_systems().await?;
+    if !server_exited {
+        shutdown::request();
+        if timeout(shutdown::SYSTEMS_TIMEOUT, server_handle).await.is_err() {
+            warn!("The systems took too long to stop, shutting down anyway");
+        }
     }
+    shutdown::shutdown(&state).await?;
 
     info!("Exiting server;");
 
     Ok(())
 }
 
+/// Decodes every packet a client sent in a [capture](ferrumc::net::capture), then exits with 1 if
+/// any failed to.
+async fn replay(path: &str) -> Result<()> {
+    let capture = Capture::read(path).await?;
+    let report = capture::replay(&capture).await;
+    for (index, e) in &report.failures {
+        error!("Packet {} failed to decode: {}", index, e);
+    }
+    info!(
+        "Replayed {}: {} decoded, {} failed, {} without a decoder, {} sent by the server",
+        path,
+        report.decoded,
+        report.failures.len(),
+        report.unknown,
+        report.skipped
+    );
+    if !report.failures.is_empty() {
+        exit(1);
+    }
+    Ok(())
+}
+
 /// Starts the server. Sets up the sockets and listens for incoming connections
 ///
 /// The actual management of connections tx/rx is handled by [net::systems::connection_handler]
-async fn start_server() -> Result<JoinHandle<Result<()>>> {
+async fn start_server() -> Result<(JoinHandle<Result<()>>, GlobalState)> {
     let config = get_global_config();
     trace!("Starting server on {}:{}", config.host, config.port);
 
@@ -96,19 +132,24 @@ async fn start_server() -> Result<JoinHandle<Result<()>>> {
         exit(0);
     }
 
-    info!("Server started on {}", addr);
+    if config.online_mode {
+        // Generating the keypair takes a moment, so get it out of the way before anyone joins
+        get_server_key();
+    }
 
-    // Start all systems (separate task)
-    let handle = tokio::task::spawn(async {
-        let all_systems = tokio::task::spawn(start_all_systems(state));
+    let plugins = state.plugins.load_all(&state).await?;
+    if plugins > 0 {
+        info!("Loaded {} plugins", plugins);
+    }
+    let scripts = ferrumc::scripting::run_all(&state).await?;
+    if scripts > 0 {
+        info!("Ran {} scripts", scripts);
+    }
 
-        // Wait for all systems to finish
-        all_systems.await??;
+    info!("Server started on {}", addr);
 
-        // Kill all systems since we're done.
-        kill_all_systems().await?;
-        Ok(())
-    });
+    // Start all systems (separate task). They run until a shutdown is requested.
+    let handle = tokio::task::spawn(start_all_systems(state.clone()));
 
-    Ok(handle)
+    Ok((handle, state))
 }
@@ -0,0 +1,165 @@
+//! Buffers for packets that are handed back once they've been sent or handled, so the next packet
+//! can reuse their memory instead of allocating its own. With a lot of players online that's most
+//! of what the server would otherwise be asking the allocator for.
+//!
+//! How often a buffer was there to be reused is counted in [METRICS], as
+//! `ferrumc_buffer_pool_hits_total` and `ferrumc_buffer_pool_misses_total`.
+
+use std::ops::{Deref, DerefMut};
+
+use bytes::Bytes;
+use parking_lot::Mutex;
+
+use crate::net::metrics::METRICS;
+
+/// Shared by every connection, for reading and writing.
+pub static BUFFER_POOL: BufferPool = BufferPool::new();
+
+/// Big enough for most packets without growing.
+const INITIAL_CAPACITY: usize = 512;
+/// Buffers that grew bigger than this are freed instead, so a few chunk batches don't leave
+/// megabytes sat in the pool.
+const MAX_POOLED_CAPACITY: usize = 64 * 1024;
+/// More than are in use at once by a full server, the rest are freed.
+const MAX_POOLED_BUFFERS: usize = 4096;
+
+pub struct BufferPool {
+    buffers: Mutex<Vec<Vec<u8>>>,
+}
+
+impl BufferPool {
+    pub const fn new() -> Self {
+        Self {
+            buffers: parking_lot::const_mutex(Vec::new()),
+        }
+    }
+
+    /// An empty buffer, which goes back in the pool when it's dropped.
+    pub fn take(&'static self) -> PooledBuffer {
+        let buffer = self.buffers.lock().pop();
+        let buffer = match buffer {
+            Some(buffer) => {
+                METRICS.buffer_pool_hits.add(1);
+                buffer
+            }
+            None => {
+                METRICS.buffer_pool_misses.add(1);
+                Vec::with_capacity(INITIAL_CAPACITY)
+            }
+        };
+        PooledBuffer { buffer, pool: self }
+    }
+
+    /// How many buffers are waiting to be reused.
+    pub fn len(&self) -> usize {
+        self.buffers.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn put(&self, mut buffer: Vec<u8>) {
+        if buffer.capacity() == 0 || buffer.capacity() > MAX_POOLED_CAPACITY {
+            return;
+        }
+        buffer.clear();
+        let mut buffers = self.buffers.lock();
+        if buffers.len() < MAX_POOLED_BUFFERS {
+            buffers.push(buffer);
+        }
+    }
+}
+
+impl Default for BufferPool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A buffer from a [BufferPool]. Use it like a `Vec<u8>`.
+pub struct PooledBuffer {
+    buffer: Vec<u8>,
+    pool: &'static BufferPool,
+}
+
+impl PooledBuffer {
+    /// Freezes it for packet handlers, which can hold on to slices of it. It goes back in the pool
+    /// once the last of them is dropped.
+    pub fn into_bytes(self) -> Bytes {
+        Bytes::from_owner(self)
+    }
+}
+
+impl Deref for PooledBuffer {
+    type Target = Vec<u8>;
+
+    fn deref(&self) -> &Vec<u8> {
+        &self.buffer
+    }
+}
+
+impl DerefMut for PooledBuffer {
+    fn deref_mut(&mut self) -> &mut Vec<u8> {
+        &mut self.buffer
+    }
+}
+
+impl AsRef<[u8]> for PooledBuffer {
+    fn as_ref(&self) -> &[u8] {
+        &self.buffer
+    }
+}
+
+impl Drop for PooledBuffer {
+    fn drop(&mut self) {
+        self.pool.put(std::mem::take(&mut self.buffer));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pool() -> &'static BufferPool {
+        Box::leak(Box::new(BufferPool::new()))
+    }
+
+    #[test]
+    fn test_buffers_are_reused() {
+        let pool = pool();
+        let mut buffer = pool.take();
+        buffer.extend_from_slice(&[1, 2, 3]);
+        let pointer = buffer.as_ptr();
+        drop(buffer);
+        assert_eq!(pool.len(), 1);
+
+        let buffer = pool.take();
+        assert!(buffer.is_empty());
+        assert_eq!(buffer.as_ptr(), pointer);
+        assert!(pool.is_empty());
+    }
+
+    #[test]
+    fn test_big_buffers_are_freed() {
+        let pool = pool();
+        let mut buffer = pool.take();
+        buffer.resize(MAX_POOLED_CAPACITY + 1, 0);
+        drop(buffer);
+        assert!(pool.is_empty());
+    }
+
+    #[test]
+    fn test_bytes_return_when_dropped() {
+        let pool = pool();
+        let mut buffer = pool.take();
+        buffer.extend_from_slice(&[1, 2, 3, 4]);
+        let bytes = buffer.into_bytes();
+        let slice = bytes.slice(1..3);
+        drop(bytes);
+        assert!(pool.is_empty());
+        assert_eq!(&slice[..], &[2, 3]);
+        drop(slice);
+        assert_eq!(pool.len(), 1);
+    }
+}
@@ -0,0 +1,385 @@
+//! Records the packets going in and out of each connection, so whatever a client ran into can be
+//! replayed through the decoders later, e.g. in a test.
+//!
+//! With `--capture`, every connection is recorded to `captures/<time>-<connection id>.fcap`. A
+//! capture is `FCAP` and a format version byte, followed by a record for each packet:
+//! `[direction][state][protocol version][milliseconds since the first packet][length][id and data]`,
+//! as a byte, a byte, an i32, a u32, a u32 and the packet, all big endian. Packets are recorded
+//! before they're compressed or encrypted, and outgoing ones have the server's own ids.
+//!
+//! `--replay=<capture>` decodes every incoming packet in a capture and reports the ones that don't
+//! decode any more, then exits. [replay] does the same for tests.
+
+use std::fs::File;
+use std::io::{BufWriter, Cursor, Read, Write};
+use std::path::Path;
+use std::sync::{LazyLock, OnceLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use byteorder::{ReadBytesExt, WriteBytesExt, BE};
+use bytes::Bytes;
+use dashmap::DashMap;
+use ferrumc_codec::network_types::varint::VarInt;
+use parking_lot::Mutex;
+use tracing::{error, info};
+
+use crate::net::packet_debug::split_frames;
+use crate::net::packets::decode_packet;
+use crate::net::{Connection, State};
+use crate::utils::constants::CAPTURES_DIRECTORY;
+use crate::utils::prelude::*;
+
+const MAGIC: &[u8; 4] = b"FCAP";
+const FORMAT_VERSION: u8 = 1;
+
+/// The connections being recorded. `None` if their capture couldn't be created, so it isn't tried
+/// again for every packet.
+static RECORDERS: LazyLock<DashMap<usize, Option<Mutex<Recorder>>>> = LazyLock::new(DashMap::new);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Incoming,
+    Outgoing,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CapturedPacket {
+    pub direction: Direction,
+    pub state: State,
+    pub protocol_version: i32,
+    /// Since the first packet in the capture.
+    pub time: Duration,
+    /// The packet's id, followed by its data.
+    pub data: Bytes,
+}
+
+/// Every packet recorded on a connection, in the order they were sent.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Capture {
+    pub packets: Vec<CapturedPacket>,
+}
+
+impl Capture {
+    pub fn parse(bytes: &[u8]) -> Result<Self> {
+        let mut cursor = Cursor::new(bytes);
+        let mut magic = [0; 4];
+        cursor.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(Error::Generic("Not a packet capture".to_string()));
+        }
+        let version = cursor.read_u8()?;
+        if version != FORMAT_VERSION {
+            return Err(Error::Generic(format!(
+                "Unsupported capture format version {}",
+                version
+            )));
+        }
+
+        let mut packets = Vec::new();
+        while (cursor.position() as usize) < bytes.len() {
+            let direction = match cursor.read_u8()? {
+                0 => Direction::Incoming,
+                1 => Direction::Outgoing,
+                direction => {
+                    return Err(Error::Generic(format!("Invalid direction {}", direction)));
+                }
+            };
+            let state = cursor.read_u8()?;
+            let state = state_from_id(state)
+                .ok_or_else(|| Error::Generic(format!("Invalid state {}", state)))?;
+            let protocol_version = cursor.read_i32::<BE>()?;
+            let time = Duration::from_millis(cursor.read_u32::<BE>()? as u64);
+            let length = cursor.read_u32::<BE>()? as usize;
+            let mut data = vec![0; length];
+            cursor.read_exact(&mut data)?;
+            packets.push(CapturedPacket {
+                direction,
+                state,
+                protocol_version,
+                time,
+                data: Bytes::from(data),
+            });
+        }
+        Ok(Self { packets })
+    }
+
+    pub async fn read(path: impl AsRef<Path>) -> Result<Self> {
+        Self::parse(&tokio::fs::read(path).await?)
+    }
+
+    pub fn write_to(&self, out: &mut impl Write) -> Result<()> {
+        out.write_all(MAGIC)?;
+        out.write_u8(FORMAT_VERSION)?;
+        for packet in &self.packets {
+            write_packet(out, packet)?;
+        }
+        Ok(())
+    }
+}
+
+fn write_packet(out: &mut impl Write, packet: &CapturedPacket) -> Result<()> {
+    out.write_u8(match packet.direction {
+        Direction::Incoming => 0,
+        Direction::Outgoing => 1,
+    })?;
+    out.write_u8(state_id(&packet.state))?;
+    out.write_i32::<BE>(packet.protocol_version)?;
+    out.write_u32::<BE>(packet.time.as_millis().min(u32::MAX as u128) as u32)?;
+    out.write_u32::<BE>(packet.data.len() as u32)?;
+    out.write_all(&packet.data)?;
+    Ok(())
+}
+
+fn state_id(state: &State) -> u8 {
+    match state {
+        State::Unknown => 0,
+        State::Handshake => 1,
+        State::Status => 2,
+        State::Login => 3,
+        State::Configuration => 4,
+        State::Play => 5,
+    }
+}
+
+fn state_from_id(id: u8) -> Option<State> {
+    Some(match id {
+        0 => State::Unknown,
+        1 => State::Handshake,
+        2 => State::Status,
+        3 => State::Login,
+        4 => State::Configuration,
+        5 => State::Play,
+        _ => return None,
+    })
+}
+
+struct Recorder {
+    file: BufWriter<File>,
+    started: Instant,
+}
+
+impl Recorder {
+    fn create(conn_id: usize) -> Result<Self> {
+        let directory = Path::new(CAPTURES_DIRECTORY);
+        std::fs::create_dir_all(directory)?;
+        let seconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_secs());
+        let path = directory.join(format!("{}-{}.fcap", seconds, conn_id));
+        let mut file = BufWriter::new(File::create(&path)?);
+        file.write_all(MAGIC)?;
+        file.write_u8(FORMAT_VERSION)?;
+        info!("Recording connection {} to {}", conn_id, path.display());
+        Ok(Self {
+            file,
+            started: Instant::now(),
+        })
+    }
+}
+
+/// Whether `--capture` was passed.
+pub fn enabled() -> bool {
+    static ENABLED: OnceLock<bool> = OnceLock::new();
+    *ENABLED.get_or_init(|| std::env::args().any(|arg| arg == "--capture"))
+}
+
+/// Records a packet a client sent. `packet` is its id followed by its data.
+pub fn record_incoming(conn_id: usize, conn_state: &State, protocol_version: i32, packet: &[u8]) {
+    record(
+        conn_id,
+        Direction::Incoming,
+        conn_state,
+        protocol_version,
+        packet,
+    );
+}
+
+/// Records each of the `[length][packet id][data]` frames in `frames`.
+pub fn record_outgoing(conn: &Connection, frames: &[u8]) {
+    for packet in split_frames(frames) {
+        record(
+            conn.id,
+            Direction::Outgoing,
+            &conn.state,
+            conn.metadata.protocol_version,
+            packet,
+        );
+    }
+}
+
+fn record(
+    conn_id: usize,
+    direction: Direction,
+    conn_state: &State,
+    protocol_version: i32,
+    packet: &[u8],
+) {
+    if !enabled() {
+        return;
+    }
+    // Connections start with the client's handshake, so packets sent after one's been finished
+    // don't start another capture
+    let recorder = match direction {
+        Direction::Incoming => RECORDERS
+            .entry(conn_id)
+            .or_insert_with(|| {
+                Recorder::create(conn_id)
+                    .inspect_err(|e| error!("Failed to record connection {}: {}", conn_id, e))
+                    .ok()
+                    .map(Mutex::new)
+            })
+            .downgrade(),
+        Direction::Outgoing => match RECORDERS.get(&conn_id) {
+            Some(recorder) => recorder,
+            None => return,
+        },
+    };
+    let Some(recorder) = recorder.as_ref() else {
+        return;
+    };
+
+    let mut recorder = recorder.lock();
+    let packet = CapturedPacket {
+        direction,
+        state: conn_state.clone(),
+        protocol_version,
+        time: recorder.started.elapsed(),
+        data: Bytes::copy_from_slice(packet),
+    };
+    if let Err(e) = write_packet(&mut recorder.file, &packet) {
+        error!("Failed to record a packet on connection {}: {}", conn_id, e);
+    }
+}
+
+/// Writes out the rest of a connection's capture, once it's been dropped.
+pub fn finish(conn_id: usize) {
+    let Some((_, Some(recorder))) = RECORDERS.remove(&conn_id) else {
+        return;
+    };
+    if let Err(e) = recorder.into_inner().file.flush() {
+        error!("Failed to finish recording connection {}: {}", conn_id, e);
+    }
+}
+
+/// How replaying a capture went.
+#[derive(Debug, Default)]
+pub struct Report {
+    /// Incoming packets that decoded without anything left over.
+    pub decoded: usize,
+    /// Incoming packets the server doesn't have a decoder for.
+    pub unknown: usize,
+    /// Outgoing packets, which are only decoded by the client.
+    pub skipped: usize,
+    /// The index of each packet that failed to decode, and why.
+    pub failures: Vec<(usize, String)>,
+}
+
+/// Decodes every incoming packet in `capture` with the state and version it was sent in. Packets
+/// aren't handled, so nothing needs to be running.
+pub async fn replay(capture: &Capture) -> Report {
+    let mut report = Report::default();
+    for (index, packet) in capture.packets.iter().enumerate() {
+        if packet.direction == Direction::Outgoing {
+            report.skipped += 1;
+            continue;
+        }
+        match replay_packet(packet).await {
+            Ok(true) => report.decoded += 1,
+            Ok(false) => report.unknown += 1,
+            Err(e) => report.failures.push((index, e.to_string())),
+        }
+    }
+    report
+}
+
+/// Whether the packet has a decoder, failing if it didn't decode or had bytes left over.
+async fn replay_packet(packet: &CapturedPacket) -> Result<bool> {
+    let mut cursor = Cursor::new(packet.data.clone());
+    let packet_id = VarInt::read(&mut cursor).await?.get_val() as u8;
+    let decoded = decode_packet(
+        packet_id,
+        packet.protocol_version,
+        &packet.state,
+        &mut cursor,
+    )
+    .await?;
+    let Some(name) = decoded else {
+        return Ok(false);
+    };
+
+    let left = packet.data.len() - cursor.position() as usize;
+    if left > 0 {
+        return Err(Error::Generic(format!(
+            "{} (0x{:02X}) had {} bytes left over",
+            name, packet_id, left
+        )));
+    }
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn incoming(state: State, data: &[u8]) -> CapturedPacket {
+        CapturedPacket {
+            direction: Direction::Incoming,
+            state,
+            protocol_version: 767,
+            time: Duration::from_millis(5),
+            data: Bytes::copy_from_slice(data),
+        }
+    }
+
+    fn handshake() -> Vec<u8> {
+        // Protocol 767, localhost:25565, going to status
+        let mut data = vec![0x00, 0xFF, 0x05, 9];
+        data.extend_from_slice(b"localhost");
+        data.extend_from_slice(&[0x63, 0xDD, 0x01]);
+        data
+    }
+
+    #[test]
+    fn test_write_and_parse() {
+        let capture = Capture {
+            packets: vec![
+                incoming(State::Handshake, &handshake()),
+                CapturedPacket {
+                    direction: Direction::Outgoing,
+                    ..incoming(State::Status, &[0x00, 0x02, b'{', b'}'])
+                },
+            ],
+        };
+        let mut bytes = Vec::new();
+        capture.write_to(&mut bytes).unwrap();
+        assert_eq!(Capture::parse(&bytes).unwrap(), capture);
+
+        assert!(Capture::parse(b"nope").is_err());
+        // Cut off in the middle of a packet
+        assert!(Capture::parse(&bytes[..bytes.len() - 1]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_replay() {
+        let mut left_over = handshake();
+        left_over.push(0xFF);
+        let capture = Capture {
+            packets: vec![
+                incoming(State::Handshake, &handshake()),
+                incoming(State::Handshake, &[0x7F]),
+                incoming(State::Handshake, &left_over),
+                incoming(State::Handshake, &[0x00, 0x80]),
+            ],
+        };
+
+        let report = replay(&capture).await;
+        assert_eq!(report.decoded, 1);
+        assert_eq!(report.unknown, 1);
+        let failed = report
+            .failures
+            .iter()
+            .map(|(index, _)| *index)
+            .collect::<Vec<_>>();
+        assert_eq!(failed, [2, 3]);
+    }
+}
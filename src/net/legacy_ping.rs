@@ -0,0 +1,113 @@
+//! The server list ping from before 1.7, which old clients and some query tools and proxies
+//! still send. It starts with `0xFE` instead of a handshake, so it has to be caught before
+//! anything tries to read a packet length.
+
+use rand::prelude::IndexedRandom;
+use tokio::io::AsyncWriteExt;
+use tracing::debug;
+
+use crate::net::packets::incoming::status::motd_component;
+use crate::net::protocol::supported_versions;
+use crate::net::Connection;
+use crate::state::GlobalState;
+use crate::utils::components::player::Player;
+use crate::utils::config::get_global_config;
+use crate::utils::prelude::*;
+
+const LEGACY_PING: u8 = 0xFE;
+/// Sent after [LEGACY_PING] from 1.4 on, which wants the version in the response too.
+const LEGACY_PING_PAYLOAD: u8 = 0x01;
+/// What a legacy kick packet starts with, which is what the response is.
+const LEGACY_KICK: u8 = 0xFF;
+/// Tells old clients the version is incompatible, since none of them can join.
+const LEGACY_PROTOCOL_VERSION: i32 = 127;
+
+/// Answers the connection if it opened with a legacy ping, returning whether it did. The
+/// connection has nothing else to say after that, so it can be dropped.
+pub async fn respond_if_legacy(conn: &Connection, state: &GlobalState) -> Result<bool> {
+    let mut first = [0u8; 2];
+    let read = {
+        let mut in_stream = conn.get_in_stream().await;
+        in_stream.get_mut().peek(&mut first).await?
+    };
+    if read == 0 || first[0] != LEGACY_PING {
+        return Ok(false);
+    }
+
+    let config = get_global_config();
+    let motd = config
+        .motd
+        .choose(&mut rand::rng())
+        .map(|motd| legacy_motd(motd))
+        .unwrap_or_default();
+    let online = state.world.query::<&Player>().iter().await.count();
+    let with_version = read > 1 && first[1] == LEGACY_PING_PAYLOAD;
+    debug!("Answering a legacy ping (from 1.4 or newer: {})", with_version);
+
+    let response = legacy_response(&motd, online, config.max_players, with_version);
+    let mut out_stream = conn.get_out_stream().await;
+    out_stream.write_all(&response).await?;
+    out_stream.flush().await?;
+    Ok(true)
+}
+
+/// Old clients only show plain text, so a JSON MOTD is cut down to its text.
+fn legacy_motd(motd: &str) -> String {
+    motd_component(motd).plain_text()
+}
+
+/// A kick packet with the server's details in its reason. Clients from 1.4 on get the version as
+/// well, separated by nulls, older ones only the MOTD and player counts separated by `§`.
+fn legacy_response(motd: &str, online: usize, max_players: i32, with_version: bool) -> Vec<u8> {
+    let reason = if with_version {
+        format!(
+            "§1\0{}\0{}\0{}\0{}\0{}",
+            LEGACY_PROTOCOL_VERSION,
+            supported_versions(),
+            motd,
+            online,
+            max_players
+        )
+    } else {
+        // `§` is the separator, so it can't be in the MOTD
+        format!("{}§{}§{}", motd.replace('§', ""), online, max_players)
+    };
+
+    let reason: Vec<u16> = reason.encode_utf16().collect();
+    let mut response = vec![LEGACY_KICK];
+    response.extend_from_slice(&(reason.len() as u16).to_be_bytes());
+    for unit in reason {
+        response.extend_from_slice(&unit.to_be_bytes());
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_legacy_response() {
+        let response = legacy_response("Hi", 1, 20, false);
+        // "Hi§1§20" is 7 characters
+        assert_eq!(&response[..3], &[LEGACY_KICK, 0, 7]);
+        assert_eq!(&response[3..7], &[0, b'H', 0, b'i']);
+        assert_eq!(&response[7..9], &0xA7u16.to_be_bytes());
+
+        let response = legacy_response("Hi", 1, 20, true);
+        let reason: Vec<u16> = response[3..]
+            .chunks(2)
+            .map(|unit| u16::from_be_bytes([unit[0], unit[1]]))
+            .collect();
+        assert_eq!(
+            String::from_utf16(&reason).unwrap(),
+            format!("§1\0127\0{}\0Hi\01\020", supported_versions())
+        );
+    }
+
+    #[test]
+    fn test_legacy_motd() {
+        assert_eq!(legacy_motd("A FerrumC Server"), "A FerrumC Server");
+        assert_eq!(legacy_motd(r#"{"text": "Hi", "color": "gold"}"#), "Hi");
+    }
+}
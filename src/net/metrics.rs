@@ -0,0 +1,296 @@
+//! Metrics for Prometheus, served over HTTP by the
+//! [MetricsServer](crate::net::systems::metrics_server::MetricsServer) if `enable_metrics` is on.
+//!
+//! Counters and histograms are kept in [METRICS] as things happen, from the game loop, the
+//! connections and the database. Gauges that can be read off the server, like how many players are
+//! online, are read when the metrics are asked for instead.
+
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use crate::net::buffer_pool::BUFFER_POOL;
+use crate::net::packet_debug::split_frames;
+use crate::state::GlobalState;
+use crate::utils::components::player::Player;
+
+/// Everything that's counted and timed as the server runs.
+pub static METRICS: Metrics = Metrics::new();
+
+/// In seconds. Ticks should take well under 50ms, and the database's reads and writes a few.
+const BUCKETS: [f64; 10] = [0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0];
+
+pub struct Metrics {
+    /// In hundredths of a tick per second, since there isn't an atomic float.
+    tps: AtomicU64,
+    pub tick_duration: Histogram,
+    pub packets_received: Counter,
+    pub packets_sent: Counter,
+    pub bytes_received: Counter,
+    pub bytes_sent: Counter,
+    pub database_reads: Histogram,
+    pub database_writes: Histogram,
+    pub buffer_pool_hits: Counter,
+    pub buffer_pool_misses: Counter,
+}
+
+impl Metrics {
+    const fn new() -> Self {
+        Self {
+            tps: AtomicU64::new(0),
+            tick_duration: Histogram::new(),
+            packets_received: Counter::new(),
+            packets_sent: Counter::new(),
+            bytes_received: Counter::new(),
+            bytes_sent: Counter::new(),
+            database_reads: Histogram::new(),
+            database_writes: Histogram::new(),
+            buffer_pool_hits: Counter::new(),
+            buffer_pool_misses: Counter::new(),
+        }
+    }
+
+    pub fn tps(&self) -> f64 {
+        self.tps.load(Ordering::Relaxed) as f64 / 100.0
+    }
+
+    pub fn set_tps(&self, tps: f64) {
+        self.tps
+            .store((tps * 100.0).round() as u64, Ordering::Relaxed);
+    }
+
+    /// Everything in Prometheus' text format, along with the gauges read off the server.
+    pub async fn render(&self, state: &GlobalState) -> String {
+        let players = {
+            let query = state.world.query::<&Player>();
+            let players = query.iter().await.count();
+            players
+        };
+        let chunks = state.database.cache_stats().entries;
+        self.render_with(players, chunks, BUFFER_POOL.len())
+    }
+
+    /// The share of packet buffers that were reused rather than allocated.
+    pub fn buffer_pool_hit_rate(&self) -> f64 {
+        let hits = self.buffer_pool_hits.get();
+        let total = hits + self.buffer_pool_misses.get();
+        if total == 0 {
+            return 0.0;
+        }
+        hits as f64 / total as f64
+    }
+
+    fn render_with(&self, players: usize, chunks: u64, pooled_buffers: usize) -> String {
+        let mut out = String::new();
+        gauge(
+            &mut out,
+            "ferrumc_tps",
+            "Ticks run in the last second.",
+            self.tps(),
+        );
+        self.tick_duration.render(
+            &mut out,
+            "ferrumc_tick_duration_seconds",
+            "How long each tick took.",
+        );
+        gauge(
+            &mut out,
+            "ferrumc_players_online",
+            "Players in the game.",
+            players as f64,
+        );
+        gauge(
+            &mut out,
+            "ferrumc_chunks_loaded",
+            "Chunks in the database's cache.",
+            chunks as f64,
+        );
+        self.packets_received.render(
+            &mut out,
+            "ferrumc_packets_received_total",
+            "Packets read from clients.",
+        );
+        self.packets_sent.render(
+            &mut out,
+            "ferrumc_packets_sent_total",
+            "Packets written to clients.",
+        );
+        self.bytes_received.render(
+            &mut out,
+            "ferrumc_bytes_received_total",
+            "Bytes of packets read from clients, before they're decompressed.",
+        );
+        self.bytes_sent.render(
+            &mut out,
+            "ferrumc_bytes_sent_total",
+            "Bytes of packets written to clients, after they're compressed.",
+        );
+        self.database_reads.render(
+            &mut out,
+            "ferrumc_database_read_seconds",
+            "How long reading a chunk from storage took.",
+        );
+        self.database_writes.render(
+            &mut out,
+            "ferrumc_database_write_seconds",
+            "How long writing a batch of chunks to storage took.",
+        );
+        self.buffer_pool_hits.render(
+            &mut out,
+            "ferrumc_buffer_pool_hits_total",
+            "Packet buffers reused from the pool.",
+        );
+        self.buffer_pool_misses.render(
+            &mut out,
+            "ferrumc_buffer_pool_misses_total",
+            "Packet buffers that had to be allocated, since the pool was empty.",
+        );
+        gauge(
+            &mut out,
+            "ferrumc_buffer_pool_hit_rate",
+            "The share of packet buffers that were reused rather than allocated.",
+            self.buffer_pool_hit_rate(),
+        );
+        gauge(
+            &mut out,
+            "ferrumc_buffer_pool_buffers",
+            "Packet buffers waiting in the pool.",
+            pooled_buffers as f64,
+        );
+        out
+    }
+}
+
+pub struct Counter(AtomicU64);
+
+impl Counter {
+    const fn new() -> Self {
+        Self(AtomicU64::new(0))
+    }
+
+    pub fn add(&self, amount: u64) {
+        self.0.fetch_add(amount, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    fn render(&self, out: &mut String, name: &str, help: &str) {
+        let _ = writeln!(out, "# HELP {} {}", name, help);
+        let _ = writeln!(out, "# TYPE {} counter", name);
+        let _ = writeln!(out, "{} {}", name, self.get());
+    }
+}
+
+/// Durations, counted in the [BUCKETS] they fit in.
+pub struct Histogram {
+    /// How many fit in each bucket, not counting the smaller ones. The last is for the ones that
+    /// don't fit in any.
+    buckets: [AtomicU64; BUCKETS.len() + 1],
+    /// In microseconds.
+    sum: AtomicU64,
+}
+
+impl Histogram {
+    const fn new() -> Self {
+        Self {
+            buckets: [const { AtomicU64::new(0) }; BUCKETS.len() + 1],
+            sum: AtomicU64::new(0),
+        }
+    }
+
+    pub fn observe(&self, duration: Duration) {
+        let seconds = duration.as_secs_f64();
+        let bucket = BUCKETS
+            .iter()
+            .position(|&bound| seconds <= bound)
+            .unwrap_or(BUCKETS.len());
+        self.buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        self.sum
+            .fetch_add(duration.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    pub fn count(&self) -> u64 {
+        self.buckets
+            .iter()
+            .map(|bucket| bucket.load(Ordering::Relaxed))
+            .sum()
+    }
+
+    fn render(&self, out: &mut String, name: &str, help: &str) {
+        let _ = writeln!(out, "# HELP {} {}", name, help);
+        let _ = writeln!(out, "# TYPE {} histogram", name);
+        // Prometheus' buckets include everything smaller too
+        let mut cumulative = 0;
+        for (bound, bucket) in BUCKETS.iter().zip(&self.buckets) {
+            cumulative += bucket.load(Ordering::Relaxed);
+            let _ = writeln!(out, "{}_bucket{{le=\"{}\"}} {}", name, bound, cumulative);
+        }
+        let count = self.count();
+        let _ = writeln!(out, "{}_bucket{{le=\"+Inf\"}} {}", name, count);
+        let sum = self.sum.load(Ordering::Relaxed) as f64 / 1_000_000.0;
+        let _ = writeln!(out, "{}_sum {}", name, sum);
+        let _ = writeln!(out, "{}_count {}", name, count);
+    }
+}
+
+fn gauge(out: &mut String, name: &str, help: &str, value: f64) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} gauge", name);
+    let _ = writeln!(out, "{} {}", name, value);
+}
+
+/// How many packets are in a buffer of frames, each of which starts with its length as a VarInt.
+pub fn count_frames(frames: &[u8]) -> u64 {
+    split_frames(frames).count() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram() {
+        let histogram = Histogram::new();
+        histogram.observe(Duration::from_millis(3));
+        histogram.observe(Duration::from_millis(40));
+        histogram.observe(Duration::from_secs(2));
+        assert_eq!(histogram.count(), 3);
+
+        let mut out = String::new();
+        histogram.render(&mut out, "test", "A test.");
+        assert!(out.contains("test_bucket{le=\"0.0025\"} 0\n"));
+        assert!(out.contains("test_bucket{le=\"0.005\"} 1\n"));
+        assert!(out.contains("test_bucket{le=\"0.05\"} 2\n"));
+        assert!(out.contains("test_bucket{le=\"1\"} 2\n"));
+        assert!(out.contains("test_bucket{le=\"+Inf\"} 3\n"));
+        assert!(out.contains("test_sum 2.043\n"));
+        assert!(out.contains("test_count 3\n"));
+    }
+
+    #[test]
+    fn test_render() {
+        let metrics = Metrics::new();
+        metrics.set_tps(19.5);
+        metrics.packets_sent.add(4);
+        metrics.buffer_pool_hits.add(3);
+        metrics.buffer_pool_misses.add(1);
+        let out = metrics.render_with(2, 100, 7);
+        assert!(out.contains("# TYPE ferrumc_tps gauge\nferrumc_tps 19.5\n"));
+        assert!(out.contains("ferrumc_players_online 2\n"));
+        assert!(out.contains("ferrumc_chunks_loaded 100\n"));
+        assert!(out.contains("ferrumc_packets_sent_total 4\n"));
+        assert!(out.contains("ferrumc_buffer_pool_hit_rate 0.75\n"));
+        assert!(out.contains("ferrumc_buffer_pool_buffers 7\n"));
+    }
+
+    #[test]
+    fn test_count_frames() {
+        // Two short frames, then one with a two byte length
+        let mut frames = vec![2, 0x01, 0x02, 1, 0x03, 0x80, 0x01];
+        frames.extend([0; 128]);
+        assert_eq!(count_frames(&frames), 3);
+        assert_eq!(count_frames(&[]), 0);
+    }
+}
@@ -1,21 +1,40 @@
 use std::cmp::PartialEq;
 use std::fmt::{Debug, Display};
 use std::io::Cursor;
+use std::net::SocketAddr;
 use std::sync::atomic::AtomicU32;
 use std::sync::{atomic, Arc};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
+use bytes::Bytes;
 use dashmap::DashMap;
-use ferrumc_codec::enc::NetEncode;
+use ferrumc_codec::dec::decompress_packet;
+use ferrumc_codec::enc::{NetEncode, NetEncodeOpts};
 use ferrumc_codec::network_types::varint::VarInt;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::sync::{Mutex, MutexGuard, RwLock, RwLockReadGuard};
-use tracing::{debug, error, trace};
+use tracing::{debug, error, trace, warn};
 
 use ferrumc_macros::Component;
 
+use crate::net::packets::incoming::login_start::LoginStart;
+use crate::net::packets::outgoing::disconnect::{ConfigurationDisconnect, Disconnect};
+use crate::net::packets::outgoing::login_disconnect::LoginDisconnect;
+use crate::net::packets::outgoing::login_success::Property;
+use crate::database::players::save_player;
+use crate::net::buffer_pool::{PooledBuffer, BUFFER_POOL};
+use crate::net::metrics::{count_frames, METRICS};
 use crate::net::packets::{handle_packet, ConnectionId};
+use crate::net::protocol::{Protocol, NATIVE_PROTOCOL};
+use crate::net::utils::encoded_packet::EncodedPacket;
+use crate::net::utils::encrypted_stream::{EncryptedReader, EncryptedWriter};
+use crate::net::utils::encryption::create_ciphers;
+use crate::net::utils::forwarding::ForwardedPlayer;
 use crate::state::GlobalState;
+use crate::utils::constants::limits::{MAX_DECOMPRESSED_LENGTH, MAX_PACKET_LENGTH};
+use crate::utils::error::NetDecodeError;
+use crate::utils::components::player::Player;
+use crate::utils::text_component::TextComponent;
 
 use super::utils::config::get_global_config;
 use super::utils::prelude::*;
@@ -35,10 +54,23 @@ impl Debug for ConnectionWrapper {
 unsafe impl Send for ConnectionWrapper {}
 unsafe impl Sync for ConnectionWrapper {}
 
+pub mod buffer_pool;
+pub mod capture;
+pub mod legacy_ping;
+pub mod metrics;
+pub mod packet_debug;
+pub mod profiler;
 pub mod packets;
+pub mod protocol;
+pub mod proxy_protocol;
+pub mod query;
+pub mod scheduler;
+pub mod scoreboard;
 pub mod systems;
+pub mod tab_list;
 mod test_ecs;
 pub mod the_dimension_codec;
+pub mod throttle;
 
 #[derive(PartialEq, Debug, Clone)]
 pub enum State {
@@ -46,6 +78,8 @@ pub enum State {
     Handshake,
     Status,
     Login,
+    /// Between login and play, from 1.20.2 on. See [ConnectionMetadata::uses_configuration].
+    Configuration,
     Play,
 }
 
@@ -62,6 +96,7 @@ impl State {
             State::Handshake => "handshake",
             State::Status => "status",
             State::Login => "login",
+            State::Configuration => "configuration",
             State::Play => "play",
         }
     }
@@ -107,15 +142,49 @@ pub struct Connection {
     pub drop: bool,
 }
 
+/// The two halves of the socket. Both are wrapped in an encryption layer, which does nothing
+/// until [Connection::enable_encryption] is called.
 pub struct NetStream {
-    pub in_stream: Mutex<tokio::net::tcp::OwnedReadHalf>,
-    pub out_stream: Mutex<tokio::net::tcp::OwnedWriteHalf>,
+    pub in_stream: Mutex<EncryptedReader<tokio::net::tcp::OwnedReadHalf>>,
+    pub out_stream: Mutex<EncryptedWriter<tokio::net::tcp::OwnedWriteHalf>>,
+    /// Framed packets waiting to be written with the next send or flush, see
+    /// [Connection::queue_packet].
+    pub staged: Mutex<PooledBuffer>,
 }
 
 #[derive(Debug, Default)]
 pub struct ConnectionMetadata {
     pub protocol_version: i32,
     pub entity: usize,
+    /// Where the client is connecting from.
+    pub address: Option<SocketAddr>,
+    /// How packets are framed. Switched to [NetEncodeOpts::Compressed] once Set Compression has been sent.
+    pub compression: NetEncodeOpts,
+    /// The login that's waiting on an Encryption Response in online mode, or on the configuration
+    /// state to finish before the player joins.
+    pub pending_login: Option<LoginStart>,
+    /// The token sent in the Encryption Request, which the client has to send back encrypted.
+    pub verify_token: Vec<u8>,
+    /// Who a BungeeCord proxy said is connecting in the handshake, see
+    /// [forwarding](crate::net::utils::forwarding).
+    pub forwarded: Option<ForwardedPlayer>,
+    /// The id of the login plugin request Velocity has to answer with the player's details.
+    pub forwarding_message_id: Option<i32>,
+    /// The player's skin and cape, kept from the login until the player joins.
+    pub properties: Vec<Property>,
+}
+
+impl ConnectionMetadata {
+    /// The client's version, or the server's own if it isn't one that's supported (e.g. for status
+    /// pings, which work with any version).
+    pub fn protocol(&self) -> &'static Protocol {
+        Protocol::get(self.protocol_version).unwrap_or(&NATIVE_PROTOCOL)
+    }
+
+    /// Whether the client goes through [State::Configuration] after logging in.
+    pub fn uses_configuration(&self) -> bool {
+        self.protocol_version >= protocol::V1_20_2
+    }
 }
 
 pub fn setup_tracer() {
@@ -130,17 +199,24 @@ pub fn setup_tracer() {
 pub async fn init_connection(socket: tokio::net::TcpStream, state: GlobalState) -> Result<()> {
     let entity_id = state.world.create_entity().await.build();
 
+    let address = socket.peer_addr().ok();
+    // Packets are already batched by the game loop, waiting for more only adds latency
+    socket.set_nodelay(true)?;
     let (in_stream, out_stream) = socket.into_split();
 
     let conn = Connection {
         id: entity_id,
         stream: NetStream {
-            in_stream: Mutex::new(in_stream),
-            out_stream: Mutex::new(out_stream),
+            in_stream: Mutex::new(EncryptedReader::new(in_stream)),
+            out_stream: Mutex::new(EncryptedWriter::new(out_stream)),
+            staged: Mutex::new(BUFFER_POOL.take()),
         },
         player_uuid: None,
         state: State::Handshake,
-        metadata: ConnectionMetadata::default(),
+        metadata: ConnectionMetadata {
+            address,
+            ..Default::default()
+        },
         drop: false,
     };
 
@@ -178,7 +254,8 @@ pub async fn init_connection(socket: tokio::net::TcpStream, state: GlobalState)
             "Error occurred in {:?}: {:?}, dropping connection",
             entity_id, e
         );
-        drop_conn(entity_id, state).await?;
+        let reason = TextComponent::text("An internal error occurred in your connection");
+        kick(&conn, &reason, state).await?;
     }
 
     Ok(())
@@ -199,23 +276,69 @@ pub async fn manage_conn(conn: Arc<RwLock<Connection>>, state: GlobalState) -> R
             .in_stream
             .lock()
             .await
+            .get_ref()
             .peer_addr()?;
         debug!("Starting receiver for the addr: {:?}", local_addr);
     }
 
+    if get_global_config().proxy.proxy_protocol {
+        let conn_id = conn.read().await.id;
+        if let Err(e) = read_proxy_header(&conn).await {
+            debug!("Dropping connection {}: {}", conn_id, e);
+            return drop_conn(conn_id, state).await;
+        }
+    }
+
+    let (conn_id, address) = {
+        let conn = conn.read().await;
+        (conn.id, conn.metadata.address)
+    };
+    if let Some(address) = address {
+        if !state.throttle.allow_connection(address.ip()) {
+            debug!("Dropping connection {}, {} is connecting too often", conn_id, address.ip());
+            return drop_conn(conn_id, state).await;
+        }
+    }
+
+    // Legacy pings aren't framed like anything else, and are the only thing sent on the connection
+    let (conn_id, legacy) = {
+        let conn = conn.read().await;
+        (conn.id, legacy_ping::respond_if_legacy(&conn, &state).await?)
+    };
+    if legacy {
+        return drop_conn(conn_id, state).await;
+    }
+
+    let mut packet_limiter = throttle::packet_limiter();
     loop {
         // Get the length of the packet
         let conn_read = conn.read().await;
 
         trace!("Reading length buffer");
 
-        let (packet_length, buffer) = get_packet_length_and_buffer(&conn_read).await?;
         let (conn_id, conn_state) = (conn_read.id, conn_read.state.clone());
+        let (packet_length, buffer) = match get_packet_length_and_buffer(&conn_read).await {
+            Ok(packet) => packet,
+            Err(Error::NetDecode(e)) => {
+                drop(conn_read);
+                return kick_for_invalid_packet(&conn, conn_id, e, state).await;
+            }
+            Err(e) => return Err(e),
+        };
+        let protocol_version = conn_read.metadata.protocol_version;
         // drop the handle to the write lock. to allow other tasks to write/read
         // mainly cuz the packet tries to access ECS component. And some system tries to access connection turns into a deadlock!!
         drop(conn_read);
 
+        if !packet_limiter.hit(Instant::now()) {
+            warn!("Connection {} is sending too many packets, disconnecting it", conn_id);
+            let reason = TextComponent::text("You are sending too many packets!");
+            return kick(&conn, &reason, state).await;
+        }
+
         trace!("Packet Length: {}", packet_length.get_val());
+        METRICS.packets_received.add(1);
+        METRICS.bytes_received.add(packet_length.get_val() as u64);
 
         let mut cursor = Cursor::new(buffer);
 
@@ -224,12 +347,67 @@ pub async fn manage_conn(conn: Arc<RwLock<Connection>>, state: GlobalState) -> R
         trace!("Packet ID: {}", packet_id);
 
         let packet_id = packet_id.get_val() as u8;
+        if packet_debug::enabled() {
+            let packet = cursor.get_ref();
+            packet_debug::log_incoming(conn_id, &conn_state, protocol_version, packet_id, packet);
+        }
+        if capture::enabled() {
+            let packet = cursor.get_ref();
+            capture::record_incoming(conn_id, &conn_state, protocol_version, packet);
+        }
 
-        let state_clone = state.clone();
-        tokio::spawn(async move {
-            handle_packet(packet_id, conn_id, &conn_state, &mut cursor, state_clone).await
-        });
-        // handle_packet(packet_id, conn_id, &conn_state, &mut cursor, state.clone()).await?;
+        if conn_state == State::Play {
+            let state_clone = state.clone();
+            let conn_clone = conn.clone();
+            tokio::spawn(async move {
+                let result = handle_packet(
+                    packet_id,
+                    protocol_version,
+                    conn_id,
+                    &conn_state,
+                    &mut cursor,
+                    state_clone.clone(),
+                )
+                .await;
+                match result {
+                    Err(Error::NetDecode(e)) => {
+                        kick_for_invalid_packet(&conn_clone, conn_id, e, state_clone).await
+                    }
+                    Err(Error::ConnectionNotFound(_)) => Ok(()),
+                    Err(e) => {
+                        error!("Failed to handle packet 0x{:02X} from {}: {}", packet_id, conn_id, e);
+                        let reason =
+                            TextComponent::text("An internal error occurred in your connection");
+                        kick(&conn_clone, &reason, state_clone).await
+                    }
+                    result => result,
+                }
+            });
+        } else {
+            // Packets before play can change the state, which decides how the next packet is
+            // read, so they're handled before reading it
+            let result = handle_packet(
+                packet_id,
+                protocol_version,
+                conn_id,
+                &conn_state,
+                &mut cursor,
+                state.clone(),
+            )
+            .await;
+            match result {
+                Err(Error::NetDecode(e)) => {
+                    return kick_for_invalid_packet(&conn, conn_id, e, state).await;
+                }
+                Err(Error::ConnectionNotFound(_)) => {}
+                Err(e) => {
+                    error!("Failed to handle packet 0x{:02X} from {}: {}", packet_id, conn_id, e);
+                    let reason = TextComponent::text("An internal error occurred in your connection");
+                    return kick(&conn, &reason, state).await;
+                }
+                Ok(()) => {}
+            }
+        }
 
         drop_conn_if_flagged(conn.clone(), state.clone()).await?;
 
@@ -244,15 +422,54 @@ pub async fn manage_conn(conn: Arc<RwLock<Connection>>, state: GlobalState) -> R
     #[allow(unreachable_code)]
     Ok(())
 }
+
+/// Reads the next packet, returning its length and the packet id and data, decompressed. It's read
+/// into a [pooled](buffer_pool) buffer, which goes back once the packet's been handled.
 async fn get_packet_length_and_buffer(
     conn: &RwLockReadGuard<'_, Connection>,
-) -> Result<(VarInt, Vec<u8>)> {
+) -> Result<(VarInt, Bytes)> {
+    let compression = conn.metadata.compression;
     let mut conn = conn.get_in_stream().await;
     let packet_length = VarInt::read(&mut *conn).await?;
-    let mut buffer = vec![0u8; packet_length.get_val() as usize];
-    conn.read_exact(&mut buffer).await?;
+    let length = NetDecodeError::check_length("Packet", packet_length.get_val(), MAX_PACKET_LENGTH)?;
+    let mut read_buffer = BUFFER_POOL.take();
+    read_buffer.resize(length, 0);
+    conn.read_exact(&mut read_buffer[..]).await?;
+    let mut buffer = read_buffer.into_bytes();
+    if let NetEncodeOpts::Compressed { .. } = compression {
+        let data_length = VarInt::read(&mut Cursor::new(&buffer)).await?.get_val();
+        NetDecodeError::check_length("Decompressed packet", data_length, MAX_DECOMPRESSED_LENGTH)?;
+        buffer = decompress_packet(buffer).await?;
+    }
     Ok((packet_length, buffer))
 }
+
+/// Disconnects a client that sent a packet breaking the [limits](crate::utils::constants::limits).
+async fn kick_for_invalid_packet(
+    conn: &RwLock<Connection>,
+    conn_id: usize,
+    error: NetDecodeError,
+    state: GlobalState,
+) -> Result<()> {
+    warn!("Connection {} sent an invalid packet: {}", conn_id, error);
+    kick(conn, &TextComponent::text("Invalid packet"), state).await
+}
+
+/// Replaces the connection's address with the one in its PROXY protocol header, which a load
+/// balancer in front of the server sends before anything else.
+async fn read_proxy_header(conn: &RwLock<Connection>) -> Result<()> {
+    let address = {
+        let conn = conn.read().await;
+        let mut in_stream = conn.get_in_stream().await;
+        proxy_protocol::read_header(&mut *in_stream).await?
+    };
+    if let Some(address) = address {
+        trace!("Proxied connection from {}", address);
+        conn.write().await.metadata.address = Some(address);
+    }
+    Ok(())
+}
+
 async fn drop_conn_if_flagged(conn: Arc<RwLock<Connection>>, state: GlobalState) -> Result<()> {
     let read = conn.read().await;
     let do_drop = read.drop;
@@ -265,6 +482,31 @@ async fn drop_conn_if_flagged(conn: Arc<RwLock<Connection>>, state: GlobalState)
 
     Ok(())
 }
+
+/// Disconnects the client, showing it `reason`, and drops the connection and its entity.
+/// Whatever was queued for the client goes out before the reason.
+///
+/// Takes the lock rather than a guard, since dropping the connection locks it again. Callers
+/// mustn't be holding a guard on it.
+pub async fn kick(
+    conn: &RwLock<Connection>,
+    reason: &TextComponent,
+    state: GlobalState,
+) -> Result<()> {
+    let conn_id = {
+        let conn = conn.read().await;
+        // The client may already be gone
+        if let Err(e) = conn.send_disconnect(reason).await {
+            debug!("Couldn't send connection {} its disconnect reason: {}", conn.id, e);
+        }
+        conn.id
+    };
+    match drop_conn(conn_id, state).await {
+        Ok(()) | Err(Error::ConnectionNotFound(_)) => Ok(()),
+        Err(e) => Err(e),
+    }
+}
+
 pub async fn drop_conn(connection_id: usize, state: GlobalState) -> Result<()> {
     debug!("Dropping connection with id: {}", connection_id);
     let connection = state.connections.connections.remove(&connection_id);
@@ -275,11 +517,27 @@ pub async fn drop_conn(connection_id: usize, state: GlobalState) -> Result<()> {
         .connections
         .connection_count
         .fetch_sub(1, atomic::Ordering::Relaxed);
+    capture::finish(connection_id);
 
     {
         let read_lock = conn_arc.read().await;
         let entity_id = read_lock.id;
+        // Only players that made it into the world have anything to save
+        let uuid = match state.world.get_component::<Player>(entity_id).await {
+            Ok(player) => Some(player.uuid),
+            Err(_) => None,
+        };
+        if uuid.is_some() {
+            if let Err(e) = save_player(&state, entity_id).await {
+                error!("Failed to save player {}: {}", entity_id, e);
+            }
+        }
         state.world.delete_entity(entity_id).await?;
+        if let Some(uuid) = uuid {
+            if let Err(e) = tab_list::remove_player(&state, uuid).await {
+                error!("Failed to take player {} out of the tab list: {}", entity_id, e);
+            }
+        }
     }
 
     // drop the connection in the end, just in case it errors out
@@ -290,9 +548,116 @@ pub async fn drop_conn(connection_id: usize, state: GlobalState) -> Result<()> {
 }
 
 impl Connection {
+    /// Sends one or more packets straight away, along with anything queued before them. Their ids
+    /// are changed to the client's version's if it isn't the server's own, see
+    /// [Protocol::remap_frames].
     pub async fn send_packet(&self, packet: impl NetEncode) -> Result<()> {
+        let frames = self.frame(packet).await?;
         let mut out_stream = self.get_out_stream().await;
-        packet.net_encode(&mut *out_stream).await?;
+        let mut staged = self.take_staged().await;
+        staged.extend_from_slice(&frames);
+        Self::write_frames(&mut out_stream, &staged).await
+    }
+
+    /// Queues one or more packets to go out with the next [Connection::send_packet] or
+    /// [Connection::flush_packets], which the game loop calls at the end of every tick. Lets
+    /// everything a tick sends a player go out in one write, instead of one per packet.
+    pub async fn queue_packet(&self, packet: impl NetEncode) -> Result<()> {
+        let frames = self.frame(packet).await?;
+        self.stream.staged.lock().await.extend_from_slice(&frames);
+        Ok(())
+    }
+
+    /// Writes out everything that's been queued.
+    pub async fn flush_packets(&self) -> Result<()> {
+        let mut out_stream = self.get_out_stream().await;
+        let staged = {
+            let mut staged = self.stream.staged.lock().await;
+            if staged.is_empty() {
+                return Ok(());
+            }
+            std::mem::replace(&mut *staged, BUFFER_POOL.take())
+        };
+        Self::write_frames(&mut out_stream, &staged).await
+    }
+
+    /// Sends an [EncodedPacket] straight away, along with anything queued before it. It's only
+    /// framed again if nobody on the same version with the same compression has had it yet.
+    pub async fn send_encoded(&self, packet: &EncodedPacket) -> Result<()> {
+        let frames = packet.frames_for(self).await?;
+        let mut out_stream = self.get_out_stream().await;
+        let mut staged = self.take_staged().await;
+        staged.extend_from_slice(&frames);
+        Self::write_frames(&mut out_stream, &staged).await
+    }
+
+    /// Queues an [EncodedPacket] for the end of the tick, see [Connection::queue_packet].
+    pub async fn queue_encoded(&self, packet: &EncodedPacket) -> Result<()> {
+        let frames = packet.frames_for(self).await?;
+        self.stream.staged.lock().await.extend_from_slice(&frames);
+        Ok(())
+    }
+
+    /// Tells the client why it's being disconnected, with the Disconnect packet for its state.
+    /// Nothing is sent in states that don't have one, e.g. during status pings.
+    pub async fn send_disconnect(&self, reason: &TextComponent) -> Result<()> {
+        match self.state {
+            State::Login => self.send_packet(LoginDisconnect::component(reason)).await,
+            State::Configuration => {
+                self.send_packet(ConfigurationDisconnect::component(reason))
+                    .await
+            }
+            State::Play => self.send_packet(Disconnect::component(reason)).await,
+            _ => Ok(()),
+        }
+    }
+
+    /// Everything that's been queued, leaving an empty buffer from the pool in its place.
+    async fn take_staged(&self) -> PooledBuffer {
+        std::mem::replace(&mut *self.stream.staged.lock().await, BUFFER_POOL.take())
+    }
+
+    /// Encodes packets the way they go out on the wire, compressed and for the client's version.
+    pub(crate) async fn frame(&self, packet: impl NetEncode) -> Result<PooledBuffer> {
+        if packet_debug::enabled() || capture::enabled() {
+            // Encoded again just to be logged, it's only for debugging
+            let mut native = Vec::new();
+            packet.net_encode(&mut native).await?;
+            packet_debug::log_outgoing(self, packet_debug::type_name(&packet), &native);
+            capture::record_outgoing(self, &native);
+        }
+        self.frame_unlogged(packet).await
+    }
+
+    /// [Connection::frame], without logging the packet for `--packet-debug` or recording it for
+    /// `--capture`.
+    pub(crate) async fn frame_unlogged(&self, packet: impl NetEncode) -> Result<PooledBuffer> {
+        let protocol = self.metadata.protocol();
+        let mut frames = BUFFER_POOL.take();
+        if protocol.is_native() {
+            packet
+                .net_encode_with_opts(&mut *frames, &self.metadata.compression)
+                .await?;
+        } else {
+            let mut native = Vec::new();
+            packet.net_encode(&mut native).await?;
+            let native = protocol.remap_frames(&self.state, native).await?;
+            native
+                .net_encode_with_opts(&mut *frames, &self.metadata.compression)
+                .await?;
+        }
+        Ok(frames)
+    }
+
+    async fn write_frames(
+        out_stream: &mut EncryptedWriter<tokio::net::tcp::OwnedWriteHalf>,
+        frames: &[u8],
+    ) -> Result<()> {
+        out_stream.write_all(frames).await?;
+        // The encryption layer may be holding on to bytes the socket didn't take yet
+        out_stream.flush().await?;
+        METRICS.packets_sent.add(count_frames(frames));
+        METRICS.bytes_sent.add(frames.len() as u64);
         Ok(())
     }
 
@@ -301,15 +666,30 @@ impl Connection {
         self.send_packet(packets).await
     }
 
-    pub async fn get_in_stream(&self) -> MutexGuard<'_, tokio::net::tcp::OwnedReadHalf> {
-        self.stream.in_stream.lock().await
+    /// Queues a [PacketQueue](utils::packet_queue::PacketQueue) for the end of the tick, see
+    /// [Connection::queue_packet].
+    pub async fn queue_packets(&self, packets: impl NetEncode) -> Result<()> {
+        self.queue_packet(packets).await
     }
 
-    pub async fn get_out_stream(&self) -> MutexGuard<'_, tokio::net::tcp::OwnedWriteHalf> {
-        self.stream.out_stream.lock().await
+    /// Derives the stream ciphers from the shared secret the client sent in its Encryption Response.
+    /// Every byte sent or received after this is encrypted.
+    pub async fn enable_encryption(&self, shared_secret: &[u8]) -> Result<()> {
+        let (encryptor, decryptor) = create_ciphers(shared_secret)?;
+        self.get_in_stream().await.enable_encryption(decryptor);
+        self.get_out_stream().await.enable_encryption(encryptor);
+        Ok(())
     }
 
-    pub async fn drop_connection(&self, state: GlobalState) -> Result<()> {
-        drop_conn(self.id, state).await
+    pub async fn get_in_stream(
+        &self,
+    ) -> MutexGuard<'_, EncryptedReader<tokio::net::tcp::OwnedReadHalf>> {
+        self.stream.in_stream.lock().await
+    }
+
+    pub async fn get_out_stream(
+        &self,
+    ) -> MutexGuard<'_, EncryptedWriter<tokio::net::tcp::OwnedWriteHalf>> {
+        self.stream.out_stream.lock().await
     }
 }
@@ -0,0 +1,280 @@
+//! Logs every packet that goes in or out, for working out what a client and the server are saying
+//! to each other. Turned on with these flags:
+//! - `--packet-debug`, which logs each packet's direction, connection, state, id, name and size
+//! - `--packet-hex`, which adds a hex dump of each packet
+//! - `--packet-filter=<filters>`, a comma separated list of packet names or ids, e.g.
+//!   `--packet-filter=ChatMessage,0x1A`. Only packets matching one are logged. Filters starting
+//!   with `!` leave packets out instead, e.g. `--packet-filter=!KeepAlive`.
+//!
+//! Packets are logged under the `ferrumc::packets` target, before they're compressed or encrypted.
+//! Outgoing ones are logged with the server's own ids, even for clients on other versions.
+
+use std::fmt::Write;
+use std::sync::OnceLock;
+
+use tracing::info;
+
+use crate::net::{Connection, State};
+
+/// Hex dumps stop after this many bytes, chunks and the like can be hundreds of kilobytes.
+const MAX_HEX_DUMP_LENGTH: usize = 1024;
+
+static SETTINGS: OnceLock<Settings> = OnceLock::new();
+
+#[derive(Debug, Default, PartialEq)]
+struct Settings {
+    enabled: bool,
+    hex: bool,
+    include: Vec<Filter>,
+    exclude: Vec<Filter>,
+}
+
+#[derive(Debug, PartialEq)]
+enum Filter {
+    Id(u8),
+    Name(String),
+}
+
+impl Filter {
+    fn parse(filter: &str) -> Self {
+        let id = filter
+            .strip_prefix("0x")
+            .or_else(|| filter.strip_prefix("0X"))
+            .and_then(|id| u8::from_str_radix(id, 16).ok());
+        match id {
+            Some(id) => Filter::Id(id),
+            None => Filter::Name(filter.to_string()),
+        }
+    }
+
+    fn matches(&self, id: u8, name: &str) -> bool {
+        match self {
+            Filter::Id(filter) => *filter == id,
+            Filter::Name(filter) => filter.eq_ignore_ascii_case(name),
+        }
+    }
+}
+
+impl Settings {
+    fn from_args(args: impl Iterator<Item = String>) -> Self {
+        let mut settings = Settings::default();
+        for arg in args {
+            match arg.as_str() {
+                "--packet-debug" => settings.enabled = true,
+                "--packet-hex" => settings.hex = true,
+                _ => {
+                    let Some(filters) = arg.strip_prefix("--packet-filter=") else {
+                        continue;
+                    };
+                    for filter in filters.split(',').map(str::trim) {
+                        if let Some(filter) = filter.strip_prefix('!') {
+                            settings.exclude.push(Filter::parse(filter));
+                        } else if !filter.is_empty() {
+                            settings.include.push(Filter::parse(filter));
+                        }
+                    }
+                }
+            }
+        }
+        settings
+    }
+
+    fn should_log(&self, id: u8, name: &str) -> bool {
+        let included =
+            self.include.is_empty() || self.include.iter().any(|filter| filter.matches(id, name));
+        included && !self.exclude.iter().any(|filter| filter.matches(id, name))
+    }
+}
+
+fn settings() -> &'static Settings {
+    SETTINGS.get_or_init(|| Settings::from_args(std::env::args()))
+}
+
+/// Whether `--packet-debug` was passed, so the work of logging packets can be skipped if not.
+pub fn enabled() -> bool {
+    settings().enabled
+}
+
+/// Logs a packet a client sent. `packet` is its id followed by its data.
+pub fn log_incoming(
+    conn_id: usize,
+    conn_state: &State,
+    protocol_version: i32,
+    packet_id: u8,
+    packet: &[u8],
+) {
+    let name = crate::net::packets::packet_name(packet_id, protocol_version, conn_state);
+    log(
+        "in",
+        conn_id,
+        conn_state,
+        packet_id,
+        name.unwrap_or("Unknown"),
+        packet,
+    );
+}
+
+/// Logs each of the `[length][packet id][data]` frames in `frames`, all of which are `name`.
+pub fn log_outgoing(conn: &Connection, name: &str, frames: &[u8]) {
+    for packet in split_frames(frames) {
+        let Some(&packet_id) = packet.first() else {
+            continue;
+        };
+        log("out", conn.id, &conn.state, packet_id, name, packet);
+    }
+}
+
+fn log(
+    direction: &str,
+    conn_id: usize,
+    conn_state: &State,
+    packet_id: u8,
+    name: &str,
+    packet: &[u8],
+) {
+    let settings = settings();
+    if !settings.enabled || !settings.should_log(packet_id, name) {
+        return;
+    }
+
+    let id = format!("0x{:02X}", packet_id);
+    if settings.hex {
+        info!(
+            target: "ferrumc::packets",
+            direction,
+            conn = conn_id,
+            state = conn_state.as_str(),
+            id = %id,
+            name,
+            size = packet.len(),
+            "\n{}",
+            hex_dump(packet)
+        );
+    } else {
+        info!(
+            target: "ferrumc::packets",
+            direction,
+            conn = conn_id,
+            state = conn_state.as_str(),
+            id = %id,
+            name,
+            size = packet.len()
+        );
+    }
+}
+
+/// The name of a packet's type, without its path or generics, e.g. `KeepAlive`.
+pub fn type_name<P: ?Sized>(packet: &P) -> &'static str {
+    let name = std::any::type_name_of_val(packet);
+    let name = name.split('<').next().unwrap_or(name);
+    name.rsplit("::").next().unwrap_or(name)
+}
+
+/// The id and data of each frame in a buffer of frames, each of which starts with its length as a
+/// VarInt.
+pub fn split_frames(mut frames: &[u8]) -> impl Iterator<Item = &[u8]> {
+    std::iter::from_fn(move || {
+        if frames.is_empty() {
+            return None;
+        }
+        let mut length = 0usize;
+        let mut read = 0;
+        for (i, byte) in frames.iter().take(5).enumerate() {
+            length |= ((byte & 0x7F) as usize) << (7 * i);
+            read = i + 1;
+            if byte & 0x80 == 0 {
+                break;
+            }
+        }
+        // Cut off, which shouldn't happen with frames the server made itself
+        let frame = frames.get(read..read + length)?;
+        frames = &frames[read + length..];
+        Some(frame)
+    })
+}
+
+/// 16 bytes a line, with their offset and any printable ones alongside.
+fn hex_dump(bytes: &[u8]) -> String {
+    let mut out = String::new();
+    for (line, chunk) in bytes.chunks(16).take(MAX_HEX_DUMP_LENGTH / 16).enumerate() {
+        let _ = write!(out, "{:08X} ", line * 16);
+        for byte in chunk {
+            let _ = write!(out, " {:02X}", byte);
+        }
+        let padding = (16 - chunk.len()) * 3;
+        let text = chunk
+            .iter()
+            .map(|&byte| {
+                if byte.is_ascii_graphic() || byte == b' ' {
+                    byte as char
+                } else {
+                    '.'
+                }
+            })
+            .collect::<String>();
+        let _ = writeln!(out, "{:padding$}  |{}|", "", text, padding = padding);
+    }
+    if bytes.len() > MAX_HEX_DUMP_LENGTH {
+        let _ = writeln!(out, "... {} more bytes", bytes.len() - MAX_HEX_DUMP_LENGTH);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filters() {
+        let args = [
+            "ferrumc",
+            "--packet-debug",
+            "--packet-filter=KeepAlive,0x1a,!chatmessage",
+        ];
+        let settings = Settings::from_args(args.into_iter().map(String::from));
+        assert!(settings.enabled);
+        assert!(!settings.hex);
+        assert_eq!(settings.include.len(), 2);
+
+        assert!(settings.should_log(0x00, "KeepAlive"));
+        assert!(settings.should_log(0x1A, "PlayerAction"));
+        assert!(!settings.should_log(0x02, "PlayerAction"));
+        assert!(!settings.should_log(0x1A, "ChatMessage"));
+
+        // Everything but what's left out, without any packets to include
+        let args = ["ferrumc", "--packet-filter=!KeepAlive"];
+        let settings = Settings::from_args(args.into_iter().map(String::from));
+        assert!(settings.should_log(0x02, "PlayerAction"));
+        assert!(!settings.should_log(0x00, "KeepAlive"));
+    }
+
+    #[test]
+    fn test_split_frames() {
+        let mut frames = vec![2, 0x01, 0x02, 1, 0x03, 0x80, 0x01];
+        frames.extend([0; 128]);
+        let split = split_frames(&frames).collect::<Vec<_>>();
+        assert_eq!(split.len(), 3);
+        assert_eq!(split[0], [0x01, 0x02]);
+        assert_eq!(split[1], [0x03]);
+        assert_eq!(split[2].len(), 128);
+        assert_eq!(split_frames(&[]).count(), 0);
+    }
+
+    #[test]
+    fn test_hex_dump() {
+        let dump = hex_dump(b"\x00Hello");
+        assert_eq!(
+            dump,
+            format!("00000000  00 48 65 6C 6C 6F{}  |.Hello|\n", " ".repeat(30))
+        );
+
+        let dump = hex_dump(&[0; MAX_HEX_DUMP_LENGTH + 10]);
+        assert!(dump.ends_with("... 10 more bytes\n"));
+    }
+
+    #[test]
+    fn test_type_name() {
+        assert_eq!(type_name(&State::Play), "State");
+        assert_eq!(type_name(&Vec::<u8>::new()), "Vec");
+    }
+}
@@ -0,0 +1,32 @@
+use ferrumc_macros::{packet, NetDecode};
+
+use crate::net::packets::{ConnectionId, IncomingPacket};
+use crate::net::utils::packet_queue::PacketQueue;
+use crate::net::State;
+use crate::state::GlobalState;
+use crate::utils::prelude::*;
+
+/// The client's answer to [FinishConfiguration](crate::net::packets::outgoing::finish_configuration::FinishConfiguration).
+/// It's in play from here on, so the player joins.
+#[derive(NetDecode)]
+#[packet(packet_id = 0x02, state = "configuration")]
+pub struct AcknowledgeFinishConfiguration;
+
+impl IncomingPacket for AcknowledgeFinishConfiguration {
+    async fn handle(self, conn_id: ConnectionId, state: GlobalState) -> Result<()> {
+        let conn = state.connections.get_connection(conn_id)?;
+        let login = {
+            let mut conn = conn.write().await;
+            let Some(login) = conn.metadata.pending_login.take() else {
+                return Err(Error::InvalidConnectionMetadata(
+                    "Finished configuring without a pending login".to_string(),
+                ));
+            };
+            // Anything the client sends from now on is a play packet
+            conn.state = State::Play;
+            login
+        };
+
+        login.join(conn_id, state, PacketQueue::new()).await
+    }
+}
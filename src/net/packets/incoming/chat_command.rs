@@ -0,0 +1,28 @@
+use tracing::debug;
+
+use ferrumc_macros::{packet, NetDecode};
+
+use crate::net::packets::{ConnectionId, IncomingPacket};
+use crate::state::GlobalState;
+use crate::utils::prelude::*;
+
+/// Sent when the player runs a command. The argument signatures that follow the timestamp are
+/// only needed for signed chat, so they aren't read.
+#[derive(NetDecode)]
+#[packet(packet_id = 0x04, state = "play")]
+pub struct ChatCommand {
+    /// The command, without the leading slash.
+    pub command: String,
+    pub timestamp: i64,
+}
+
+impl IncomingPacket for ChatCommand {
+    async fn handle(self, conn_id: ConnectionId, state: GlobalState) -> Result<()> {
+        debug!("{} ran command: /{}", conn_id, self.command);
+
+        state
+            .commands
+            .dispatch(&self.command, conn_id, state.clone())
+            .await
+    }
+}
@@ -2,6 +2,8 @@ use tracing::debug;
 
 use ferrumc_macros::{packet, NetDecode};
 
+use crate::events::chat_events::ChatMessageEvent;
+use crate::events::creation::dispatcher::EventDispatcherExt;
 use crate::net::packets::{ConnectionId, IncomingPacket};
 use crate::state::GlobalState;
 use crate::utils::components::player::Player;
@@ -24,6 +26,11 @@ impl IncomingPacket for PacketChatMessage {
         let my_player = state.world.get_component::<Player>(my_id).await?;
 
         debug!("[{}]: {}", my_player.username, self.message);
+        drop(my_player);
+
+        state
+            .dispatch_event(ChatMessageEvent::new(my_id, self.message, Default::default()))
+            .await;
 
         Ok(())
     }
@@ -0,0 +1,556 @@
+use std::collections::{HashMap, HashSet};
+
+use ferrumc_codec::network_types::varint::VarInt;
+
+use ferrumc_macros::{packet, NetDecode};
+
+use crate::net::packets::outgoing::set_container_content::SetContainerContent;
+use crate::net::packets::outgoing::set_container_slot::SetContainerSlot;
+use crate::net::packets::{ConnectionId, IncomingPacket};
+use crate::state::GlobalState;
+use crate::utils::components::game_mode::GameMode;
+use crate::utils::components::inventory::Inventory;
+use crate::utils::components::open_container::OpenContainer;
+use crate::utils::encoding::position::Position;
+use crate::utils::encoding::slot::{ItemStack, Slot};
+use crate::utils::prelude::*;
+use crate::world::chunk_format::BlockEntity;
+use crate::world::containers::ContainerType;
+use crate::world::generation::get_or_generate_chunk;
+use crate::world::item_entities::throw_item;
+use crate::world::items::ItemRegistry;
+use crate::world::recipes::RecipeBook;
+
+/// Sent when the player clicks in a window. The client works out what the click does itself and
+/// sends the slots it changed, along with what it thinks is on the cursor now.
+#[derive(NetDecode)]
+#[packet(packet_id = 0x0B, state = "play", ids(764 = 0x0D))]
+pub struct ClickContainer {
+    /// 0 is the player's inventory.
+    pub window_id: u8,
+    /// The last [state id](Inventory::state_id) the client was sent.
+    pub state_id: VarInt,
+    /// [OUTSIDE] for outside the window.
+    pub slot: i16,
+    pub button: i8,
+    /// Which kind of click it was, e.g. 1 for a shift click or 4 for dropping an item.
+    pub mode: VarInt,
+    pub changed_slots: Vec<ChangedSlot>,
+    pub carried_item: Slot,
+}
+
+/// The slot that's clicked when clicking outside the window.
+pub const OUTSIDE: i16 = -999;
+
+pub struct ChangedSlot {
+    pub slot: i16,
+    pub item: Slot,
+}
+
+// NetDecode and AsyncRead come in with the derive above
+impl NetDecode for ChangedSlot {
+    async fn net_decode<T>(bytes: &mut T) -> Result<Box<Self>>
+    where
+        T: AsyncRead + Unpin,
+    {
+        Ok(Box::new(Self {
+            slot: Box::into_inner(i16::net_decode(bytes).await?),
+            item: Box::into_inner(Slot::net_decode(bytes).await?),
+        }))
+    }
+}
+
+impl IncomingPacket for ClickContainer {
+    async fn handle(self, conn_id: ConnectionId, state: GlobalState) -> Result<()> {
+        let creative = state
+            .world
+            .get_component::<GameMode>(conn_id)
+            .await
+            .is_ok_and(|game_mode| game_mode.mode == GameMode::CREATIVE);
+        if self.window_id != 0 {
+            return self.click_in_container(conn_id, &state, creative).await;
+        }
+
+        let mut dropped = Vec::new();
+        let (content, result) = {
+            let mut inventory = state
+                .world
+                .get_component_storage()
+                .get_mut_or_insert_with::<Inventory>(conn_id, Default::default)
+                .await;
+            if self.takes_result() {
+                self.craft(&mut inventory, &state.recipes, &state.items);
+                // Whatever the client thinks happened, it's told what really did
+                inventory.next_state_id();
+                (Some(SetContainerContent::player_inventory(&inventory)), None)
+            } else if let Some(items) = self.apply(&mut inventory, &state.items, creative) {
+                dropped = items;
+                if self.changes_grid() && state.recipes.update_crafting_result(&mut inventory) {
+                    inventory.next_state_id();
+                    let slot =
+                        SetContainerSlot::player_inventory(&inventory, Inventory::CRAFTING_RESULT);
+                    (None, Some(slot))
+                } else {
+                    (None, None)
+                }
+            } else {
+                inventory.next_state_id();
+                (Some(SetContainerContent::player_inventory(&inventory)), None)
+            }
+        };
+
+        {
+            let conn = state.connections.get_connection(conn_id)?;
+            let conn = conn.read().await;
+            if let Some(packet) = content {
+                conn.send_packet(packet).await?;
+            }
+            if let Some(packet) = result {
+                conn.send_packet(packet).await?;
+            }
+        }
+        for item in dropped {
+            throw_item(&state, conn_id, item).await?;
+        }
+        Ok(())
+    }
+}
+
+impl ClickContainer {
+    /// A click in a container's window, which can change what's in the container as well as the
+    /// player's inventory.
+    async fn click_in_container(
+        &self,
+        conn_id: ConnectionId,
+        state: &GlobalState,
+        creative: bool,
+    ) -> Result<()> {
+        let (position, dimension, container) = {
+            let Ok(open) = state.world.get_component::<OpenContainer>(conn_id).await else {
+                return Ok(());
+            };
+            if open.window_id != self.window_id {
+                return Ok(());
+            }
+            (open.position.clone(), open.dimension.clone(), open.container)
+        };
+
+        let Position { x, y, z } = position;
+        let (chunk_x, chunk_z) = (x >> 4, z >> 4);
+        // Held until the container is saved, so two clicks on it at once can't both take from
+        // what was in it before either of them
+        let lock = state.database.lock_chunk(chunk_x, chunk_z, &dimension).await;
+        let mut chunk = get_or_generate_chunk(state, chunk_x, chunk_z, &dimension)
+            .await?
+            .ok_or(Error::ChunkNotFound(chunk_x, chunk_z))?;
+        let block = chunk.get_block(
+            x.rem_euclid(16) as usize,
+            y as i32,
+            z.rem_euclid(16) as usize,
+        )?;
+        // It's been replaced since it was opened
+        if ContainerType::get(&block.name) != Some(container) {
+            return Ok(());
+        }
+        let mut block_entity = chunk
+            .block_entity(x, y as i32, z)
+            .cloned()
+            .unwrap_or_else(|| BlockEntity::new(container.block, x, y as i32, z));
+        let slots = block_entity.container_slots(container.slots, &state.items);
+
+        let mut dropped = Vec::new();
+        let (changed, resync) = {
+            let mut inventory = state
+                .world
+                .get_component_storage()
+                .get_mut_or_insert_with::<Inventory>(conn_id, Default::default)
+                .await;
+            let mut window = container.window(&slots, &inventory);
+            let mut carried = inventory.carried.take();
+            let applied = if self.state_id.get_val() == inventory.state_id {
+                self.apply_to(
+                    &mut window,
+                    &mut carried,
+                    container.window_slots(),
+                    &state.items,
+                    creative,
+                )
+            } else {
+                None
+            };
+            inventory.carried = carried;
+            if let Some(items) = applied {
+                dropped = items;
+                // The client only finds out about the new state id with a slot it's sent
+                let slot = self.changed_slots.first().map_or(0, |changed| changed.slot);
+                let synced = SetContainerSlot::new_auto(
+                    self.window_id as i8,
+                    VarInt::new(inventory.next_state_id()),
+                    slot,
+                    Slot {
+                        item: window.get(&slot).cloned(),
+                    },
+                );
+                let slots = container.split_window(window, &mut inventory);
+                (Some((slots, synced)), None)
+            } else {
+                inventory.next_state_id();
+                let content =
+                    SetContainerContent::container(self.window_id, container, &slots, &inventory);
+                (None, Some(content))
+            }
+        };
+
+        let synced = match changed {
+            Some((slots, synced)) => {
+                block_entity.set_container_slots(&slots, &state.items);
+                chunk.set_block_entity(block_entity);
+                state.database.update_chunk(chunk).await?;
+                Some(synced)
+            }
+            None => None,
+        };
+        drop(lock);
+
+        let conn = state.connections.get_connection(conn_id)?;
+        let conn = conn.read().await;
+        if let Some(packet) = synced {
+            conn.send_packet(packet).await?;
+        }
+        if let Some(packet) = resync {
+            conn.send_packet(packet).await?;
+        }
+        drop(conn);
+        for item in dropped {
+            throw_item(state, conn_id, item).await?;
+        }
+        Ok(())
+    }
+
+    fn takes_result(&self) -> bool {
+        self.slot == Inventory::CRAFTING_RESULT
+            || self
+                .changed_slots
+                .iter()
+                .any(|changed| changed.slot == Inventory::CRAFTING_RESULT)
+    }
+
+    /// Whether the click throws items out of the window: dropping them from a slot, or clicking
+    /// outside the window with something on the cursor.
+    fn drops(&self) -> bool {
+        match self.mode.get_val() {
+            0 => self.slot == OUTSIDE,
+            4 => self.slot != OUTSIDE,
+            _ => false,
+        }
+    }
+
+    fn changes_grid(&self) -> bool {
+        self.changed_slots
+            .iter()
+            .any(|changed| Inventory::CRAFTING_GRID.contains(&changed.slot))
+    }
+
+    /// Takes what the crafting grid makes, using up one of each item in it per craft. Shift
+    /// clicking crafts as many as fit in the inventory, clicking puts one lot on the cursor.
+    /// Nothing else does anything to the result.
+    fn craft(&self, inventory: &mut Inventory, recipes: &RecipeBook, items: &ItemRegistry) {
+        match self.mode.get_val() {
+            0 => {
+                let Some(result) = inventory.slots.get(&Inventory::CRAFTING_RESULT).cloned() else {
+                    return;
+                };
+                let max_stack_size = items.max_stack_size(result.id) as i32;
+                inventory.carried = match inventory.carried.take() {
+                    None => Some(result),
+                    Some(carried)
+                        if carried.stacks_with(&result)
+                            && carried.count as i32 + result.count as i32 <= max_stack_size =>
+                    {
+                        Some(ItemStack {
+                            count: carried.count + result.count,
+                            ..carried
+                        })
+                    }
+                    carried => {
+                        inventory.carried = carried;
+                        return;
+                    }
+                };
+                use_ingredients(inventory);
+                recipes.update_crafting_result(inventory);
+            }
+            1 => {
+                // Each craft uses items up, so this ends once the grid runs out at the latest
+                while let Some(result) = inventory.slots.get(&Inventory::CRAFTING_RESULT).cloned() {
+                    if !inventory.add_item(&result, items.max_stack_size(result.id)) {
+                        break;
+                    }
+                    use_ingredients(inventory);
+                    recipes.update_crafting_result(inventory);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Makes the changes the client made, if they could have come from a click, and returns the
+    /// items it dropped. If they couldn't, nothing changes and the client needs to be sent what's
+    /// really there.
+    ///
+    /// Clicks only move items around, so the changed slots and the cursor have to hold the same
+    /// items before and after, apart from what a drop throws out. Players in creative mode can do
+    /// anything.
+    fn apply(
+        &self,
+        inventory: &mut Inventory,
+        items: &ItemRegistry,
+        creative: bool,
+    ) -> Option<Vec<ItemStack>> {
+        // Clicked on something that's changed since
+        if self.state_id.get_val() != inventory.state_id {
+            return None;
+        }
+        let Inventory { slots, carried, .. } = inventory;
+        self.apply_to(slots, carried, Inventory::SLOTS, items, creative)
+    }
+
+    /// [ClickContainer::apply] for any window, with its `window_slots` slots keyed by their
+    /// number in the window.
+    fn apply_to(
+        &self,
+        slots: &mut HashMap<i16, ItemStack>,
+        carried: &mut Option<ItemStack>,
+        window_slots: i16,
+        items: &ItemRegistry,
+        creative: bool,
+    ) -> Option<Vec<ItemStack>> {
+        // A slot that's in there twice would be counted twice below
+        let mut seen = HashSet::new();
+        let valid = self.changed_slots.iter().all(|changed| {
+            (0..window_slots).contains(&changed.slot)
+                && seen.insert(changed.slot)
+                && changed.item.item.as_ref().is_none_or(|item| items.is_valid(item))
+        });
+        if !valid {
+            return None;
+        }
+
+        let mut dropped = Vec::new();
+        if !creative || self.drops() {
+            let before_items = self
+                .changed_slots
+                .iter()
+                .filter_map(|changed| slots.get(&changed.slot))
+                .chain(carried.iter())
+                .collect::<Vec<_>>();
+            let before = item_counts(before_items.iter().copied());
+            let after = item_counts(
+                self.changed_slots
+                    .iter()
+                    .filter_map(|changed| changed.item.item.as_ref())
+                    .chain(&self.carried_item.item),
+            );
+            for (&(id, tag), &count) in &before {
+                let left = after.get(&(id, tag)).copied().unwrap_or(0);
+                let item = before_items
+                    .iter()
+                    .find(|item| item.id == id && item.tag() == tag);
+                if let (true, Some(item)) = (left < count, item) {
+                    dropped.push(ItemStack {
+                        count: (count - left) as i8,
+                        ..(*item).clone()
+                    });
+                }
+            }
+            let gained = after
+                .iter()
+                .any(|(key, &count)| count > before.get(key).copied().unwrap_or(0));
+            // A drop only throws out one kind of item
+            let drops = if self.drops() { 1 } else { 0 };
+            if !creative && (gained || dropped.len() > drops) {
+                return None;
+            }
+        }
+
+        for changed in &self.changed_slots {
+            match &changed.item.item {
+                Some(item) => slots.insert(changed.slot, item.clone()),
+                None => slots.remove(&changed.slot),
+            };
+        }
+        *carried = self.carried_item.item.clone();
+        Some(dropped)
+    }
+}
+
+/// Takes one of each item in the crafting grid.
+fn use_ingredients(inventory: &mut Inventory) {
+    for slot in Inventory::CRAFTING_GRID {
+        let item = inventory.slots.get(&slot).map(|item| ItemStack {
+            count: item.count - 1,
+            ..item.clone()
+        });
+        if item.is_some() {
+            inventory.set_slot(slot, item);
+        }
+    }
+}
+
+/// How many of each item there are, telling items apart by their id and NBT.
+fn item_counts<'a>(
+    items: impl Iterator<Item = &'a ItemStack>,
+) -> HashMap<(i32, &'a [u8]), i32> {
+    let mut counts = HashMap::new();
+    for item in items {
+        *counts.entry((item.id, item.tag())).or_default() += item.count as i32;
+    }
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::recipes::{Ingredient, Recipe, RecipeKind};
+
+    fn stone(count: i8) -> Option<ItemStack> {
+        Some(ItemStack::new(1, count))
+    }
+
+    fn click(
+        changed_slots: Vec<(i16, Option<ItemStack>)>,
+        carried: Option<ItemStack>,
+    ) -> ClickContainer {
+        ClickContainer {
+            window_id: 0,
+            state_id: VarInt::new(0),
+            slot: 0,
+            button: 0,
+            mode: VarInt::new(0),
+            changed_slots: changed_slots
+                .into_iter()
+                .map(|(slot, item)| ChangedSlot {
+                    slot,
+                    item: Slot { item },
+                })
+                .collect(),
+            carried_item: Slot { item: carried },
+        }
+    }
+
+    #[test]
+    fn test_click_moves_items() {
+        let items = ItemRegistry::default();
+        let mut inventory = Inventory::default();
+        inventory.set_slot(36, stone(10));
+
+        // Picking up half the stack, then putting it down somewhere else
+        let take_half = click(vec![(36, stone(5))], stone(5));
+        assert_eq!(take_half.apply(&mut inventory, &items, false), Some(Vec::new()));
+        assert_eq!(inventory.carried, stone(5));
+        let put_down = click(vec![(20, stone(5))], None);
+        assert_eq!(put_down.apply(&mut inventory, &items, false), Some(Vec::new()));
+        assert_eq!(inventory.slots.get(&20).cloned(), stone(5));
+        assert_eq!(inventory.carried, None);
+    }
+
+    #[test]
+    fn test_click_rejects_new_items() {
+        let items = ItemRegistry::default();
+        let mut inventory = Inventory::default();
+        inventory.set_slot(36, stone(10));
+        inventory.set_slot(37, stone(60));
+        let mut survival = |changed_slots, carried| {
+            click(changed_slots, carried)
+                .apply(&mut inventory, &items, false)
+                .is_some()
+        };
+
+        assert!(!survival(vec![(36, stone(10)), (38, stone(10))], None));
+        // Dropping the stack outside the window
+        assert!(!survival(vec![(36, None)], None));
+        // Hiding extra items behind a negative count
+        assert!(!survival(vec![(36, stone(15)), (38, stone(-5))], None));
+        // Or behind the same slot twice
+        assert!(!survival(vec![(36, stone(1)), (36, stone(19))], None));
+        // Bigger stacks than the item can have
+        assert!(!survival(vec![(36, None), (37, stone(70))], None));
+        assert_eq!(inventory.slots.get(&36).cloned(), stone(10));
+        assert_eq!(inventory.slots.get(&37).cloned(), stone(60));
+        assert_eq!(inventory.slots.len(), 2);
+
+        // Anything else goes in creative mode
+        let too_many = click(vec![(38, stone(100))], None);
+        assert_eq!(too_many.apply(&mut inventory, &items, true), None);
+        let creative = click(vec![(38, stone(64))], None);
+        assert_eq!(creative.apply(&mut inventory, &items, true), Some(Vec::new()));
+
+        // Clicks on an old state of the inventory are turned away
+        inventory.next_state_id();
+        let old = click(vec![(36, None)], stone(10));
+        assert_eq!(old.apply(&mut inventory, &items, false), None);
+    }
+
+    #[test]
+    fn test_click_drops() {
+        let items = ItemRegistry::default();
+        let mut inventory = Inventory::default();
+        inventory.set_slot(36, stone(10));
+
+        // Dropping one from a slot
+        let mut drop_one = click(vec![(36, stone(9))], None);
+        drop_one.slot = 36;
+        drop_one.mode = VarInt::new(4);
+        assert_eq!(drop_one.apply(&mut inventory, &items, false), Some(vec![stone(1).unwrap()]));
+        assert_eq!(inventory.slots.get(&36).cloned(), stone(9));
+
+        // Picking the rest up, then dropping it outside the window
+        assert!(click(vec![(36, None)], stone(9))
+            .apply(&mut inventory, &items, false)
+            .is_some());
+        let mut outside = click(Vec::new(), None);
+        outside.slot = OUTSIDE;
+        assert_eq!(outside.apply(&mut inventory, &items, false), Some(vec![stone(9).unwrap()]));
+        assert_eq!(inventory.carried, None);
+
+        // Drops can't make items either
+        inventory.set_slot(37, stone(5));
+        let mut more = click(vec![(37, stone(6))], None);
+        more.slot = OUTSIDE;
+        assert_eq!(more.apply(&mut inventory, &items, false), None);
+    }
+
+    #[test]
+    fn test_craft() {
+        let items = ItemRegistry::default();
+        // Two stone make four sticks
+        let recipes = RecipeBook::new(vec![Recipe {
+            id: "test:sticks".to_string(),
+            group: String::new(),
+            category: "misc".to_string(),
+            kind: RecipeKind::Shapeless {
+                ingredients: vec![Ingredient(vec![1]), Ingredient(vec![1])],
+            },
+            result: ItemStack::new(2, 4),
+        }]);
+        let mut inventory = Inventory::default();
+        inventory.set_slot(1, stone(3));
+        inventory.set_slot(2, stone(2));
+        assert!(recipes.update_crafting_result(&mut inventory));
+
+        let mut take = click(vec![(0, None)], Some(ItemStack::new(2, 4)));
+        assert!(take.takes_result());
+        take.craft(&mut inventory, &recipes, &items);
+        assert_eq!(inventory.carried, Some(ItemStack::new(2, 4)));
+        assert_eq!(inventory.slots.get(&1).cloned(), stone(2));
+        assert_eq!(inventory.slots.get(&2).cloned(), stone(1));
+
+        // Shift clicking crafts everything that's left, and the grid's empty after
+        take.mode = VarInt::new(1);
+        take.craft(&mut inventory, &recipes, &items);
+        assert_eq!(inventory.slots.get(&36).cloned(), Some(ItemStack::new(2, 4)));
+        assert_eq!(inventory.slots.get(&2), None);
+        assert_eq!(inventory.slots.get(&Inventory::CRAFTING_RESULT), None);
+        assert_eq!(inventory.slots.get(&1).cloned(), stone(1));
+    }
+}
@@ -0,0 +1,40 @@
+use ferrumc_codec::network_types::varint::VarInt;
+use ferrumc_macros::{packet, NetDecode};
+
+use crate::net::packets::{ConnectionId, IncomingPacket};
+use crate::net::utils::death::respawn;
+use crate::state::GlobalState;
+use crate::utils::components::health::Health;
+use crate::utils::prelude::*;
+
+/// Sent when the player clicks respawn on the death screen, or opens their statistics.
+#[derive(NetDecode)]
+#[packet(packet_id = 0x07, state = "play", ids(764 = 0x08))]
+pub struct ClientCommand {
+    /// One of [actions].
+    pub action: VarInt,
+}
+
+pub mod actions {
+    pub const PERFORM_RESPAWN: i32 = 0;
+    /// Statistics aren't kept, so this is ignored.
+    pub const REQUEST_STATS: i32 = 1;
+}
+
+impl IncomingPacket for ClientCommand {
+    async fn handle(self, conn_id: ConnectionId, state: GlobalState) -> Result<()> {
+        if self.action.get_val() != actions::PERFORM_RESPAWN {
+            return Ok(());
+        }
+        // The client also sends it after the death screen is skipped, but only dead players respawn
+        let dead = state
+            .world
+            .get_component::<Health>(conn_id)
+            .await
+            .is_ok_and(|health| health.is_dead());
+        if dead {
+            respawn(&state, conn_id).await?;
+        }
+        Ok(())
+    }
+}
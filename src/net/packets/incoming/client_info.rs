@@ -7,7 +7,7 @@ use crate::net::systems::chunk_sender::ChunkSender;
 use crate::state::GlobalState;
 
 #[derive(NetDecode, Component, Clone, Debug)]
-#[packet(packet_id = 0x08, state = "play")]
+#[packet(packet_id = 0x08, state = "play", ids(764 = 0x09))]
 pub struct ClientInfo {
     pub locale: String,
     pub view_distance: i8,
@@ -0,0 +1,113 @@
+use ferrumc_macros::{packet, NetDecode};
+
+use crate::net::packets::outgoing::set_container_content::SetContainerContent;
+use crate::net::packets::{ConnectionId, IncomingPacket};
+use crate::state::GlobalState;
+use crate::utils::components::inventory::Inventory;
+use crate::utils::components::open_container::OpenContainer;
+use crate::utils::prelude::*;
+use crate::utils::encoding::slot::ItemStack;
+use crate::world::item_entities::throw_item;
+use crate::world::items::ItemRegistry;
+
+/// Sent when the player closes a window, including their own inventory.
+#[derive(NetDecode)]
+#[packet(packet_id = 0x0C, state = "play", ids(764 = 0x0E))]
+pub struct CloseContainer {
+    pub window_id: u8,
+}
+
+impl IncomingPacket for CloseContainer {
+    async fn handle(self, conn_id: ConnectionId, state: GlobalState) -> Result<()> {
+        let closes_container = state
+            .world
+            .get_component::<OpenContainer>(conn_id)
+            .await
+            .is_ok_and(|open| open.window_id == self.window_id);
+        if closes_container {
+            state
+                .world
+                .get_component_storage()
+                .remove::<OpenContainer>(conn_id)?;
+        }
+
+        let (update, dropped) = {
+            let mut inventory = state
+                .world
+                .get_component_storage()
+                .get_mut_or_insert_with::<Inventory>(conn_id, Default::default)
+                .await;
+            let (changed, dropped) = put_away(&mut inventory, &state.items);
+            if changed {
+                state.recipes.update_crafting_result(&mut inventory);
+                inventory.next_state_id();
+                (Some(SetContainerContent::player_inventory(&inventory)), dropped)
+            } else {
+                (None, dropped)
+            }
+        };
+
+        if let Some(packet) = update {
+            let conn = state.connections.get_connection(conn_id)?;
+            conn.read().await.send_packet(packet).await?;
+        }
+        for item in dropped {
+            throw_item(&state, conn_id, item).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Whatever is on the cursor gets dropped when a window closes, like in vanilla. What's in the
+/// crafting grid goes back in the inventory, and is dropped if there's no room. Returns whether
+/// the grid changed, which the client has to be told about, and what's dropped.
+fn put_away(inventory: &mut Inventory, items: &ItemRegistry) -> (bool, Vec<ItemStack>) {
+    let mut changed = false;
+    let mut dropped = inventory.carried.take().into_iter().collect::<Vec<_>>();
+    for slot in Inventory::CRAFTING_GRID {
+        let Some(item) = inventory.slots.remove(&slot) else {
+            continue;
+        };
+        changed = true;
+        if !inventory.add_item(&item, items.max_stack_size(item.id)) {
+            dropped.push(item);
+        }
+    }
+    (changed, dropped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_put_away() {
+        let items = ItemRegistry::default();
+        let mut inventory = Inventory {
+            carried: Some(ItemStack::new(1, 10)),
+            ..Default::default()
+        };
+        inventory.set_slot(3, Some(ItemStack::new(1, 5)));
+        inventory.set_slot(4, Some(ItemStack::new(2, 1)));
+        assert_eq!(
+            put_away(&mut inventory, &items),
+            (true, vec![ItemStack::new(1, 10)])
+        );
+        assert_eq!(inventory.carried, None);
+        assert_eq!(inventory.slots.get(&36), Some(&ItemStack::new(1, 5)));
+        assert_eq!(inventory.slots.get(&37), Some(&ItemStack::new(2, 1)));
+        assert_eq!(inventory.slots.len(), 2);
+        assert_eq!(put_away(&mut inventory, &items), (false, Vec::new()));
+
+        // No room for what's in the grid
+        for slot in Inventory::MAIN_START..Inventory::OFFHAND {
+            inventory.set_slot(slot, Some(ItemStack::new(3, 64)));
+        }
+        inventory.set_slot(1, Some(ItemStack::new(2, 1)));
+        assert_eq!(
+            put_away(&mut inventory, &items),
+            (true, vec![ItemStack::new(2, 1)])
+        );
+        assert_eq!(inventory.slots.get(&1), None);
+    }
+}
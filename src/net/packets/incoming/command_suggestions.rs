@@ -0,0 +1,43 @@
+use ferrumc_codec::network_types::varint::VarInt;
+use tracing::trace;
+
+use ferrumc_macros::{packet, NetDecode};
+
+use crate::net::packets::outgoing::command_suggestions_response::{
+    CommandSuggestionsResponse, SuggestionMatch,
+};
+use crate::net::packets::{ConnectionId, IncomingPacket};
+use crate::state::GlobalState;
+use crate::utils::prelude::*;
+
+/// Sent while the player types an argument that the command tree says the server completes.
+#[derive(NetDecode)]
+#[packet(packet_id = 0x09, state = "play", ids(764 = 0x0A))]
+pub struct CommandSuggestionsRequest {
+    pub transaction_id: VarInt,
+    /// Everything typed so far, including the leading slash.
+    pub text: String,
+}
+
+impl IncomingPacket for CommandSuggestionsRequest {
+    async fn handle(self, conn_id: ConnectionId, state: GlobalState) -> Result<()> {
+        let suggestions = state.commands.suggest(&self.text, conn_id, &state).await?;
+        trace!("Suggestions for {:?}: {:?}", self.text, suggestions.matches);
+
+        let response = CommandSuggestionsResponse::new_auto(
+            self.transaction_id,
+            VarInt::new(suggestions.start as i32),
+            VarInt::new(suggestions.length as i32),
+            VarInt::new(suggestions.matches.len() as i32),
+            suggestions
+                .matches
+                .into_iter()
+                .map(SuggestionMatch::new)
+                .collect(),
+        );
+
+        let conn = state.connections.get_connection(conn_id)?;
+        let conn = conn.read().await;
+        conn.send_packet(response).await
+    }
+}
@@ -0,0 +1,37 @@
+use tracing::trace;
+
+use ferrumc_macros::{packet, NetDecode};
+
+use crate::net::packets::incoming::client_info::ClientInfo;
+use crate::net::packets::{ConnectionId, IncomingPacket};
+use crate::state::GlobalState;
+use crate::utils::prelude::*;
+
+/// The same as [ClientInfo], during configuration. The player isn't in the world yet, so it's only
+/// kept for when they join.
+#[derive(NetDecode)]
+#[packet(packet_id = 0x00, state = "configuration")]
+pub struct ConfigurationClientInfo {
+    pub locale: String,
+    pub view_distance: i8,
+    pub chat_mode: i8,
+    pub chat_colors: bool,
+    pub displayed_skin_parts: u8,
+    pub main_hand: i8,
+}
+
+impl IncomingPacket for ConfigurationClientInfo {
+    async fn handle(self, conn_id: ConnectionId, state: GlobalState) -> Result<()> {
+        trace!("Connection {} has a view distance of {}", conn_id, self.view_distance);
+        let client_info = ClientInfo {
+            locale: self.locale,
+            view_distance: self.view_distance,
+            chat_mode: self.chat_mode,
+            chat_colors: self.chat_colors,
+            displayed_skin_parts: self.displayed_skin_parts,
+            main_hand: self.main_hand,
+        };
+        state.world.get_component_storage().insert(conn_id, client_info);
+        Ok(())
+    }
+}
@@ -0,0 +1,22 @@
+use tracing::debug;
+
+use ferrumc_macros::{packet, NetDecode};
+
+use crate::net::packets::{ConnectionId, IncomingPacket};
+use crate::state::GlobalState;
+use crate::utils::prelude::*;
+
+/// Data on a mod or plugin channel, like the client's brand on `minecraft:brand`. No channels are
+/// handled, so the data after the channel isn't read.
+#[derive(NetDecode)]
+#[packet(packet_id = 0x01, state = "configuration")]
+pub struct ConfigurationPluginMessage {
+    pub channel: String,
+}
+
+impl IncomingPacket for ConfigurationPluginMessage {
+    async fn handle(self, conn_id: ConnectionId, _state: GlobalState) -> Result<()> {
+        debug!("Connection {} sent a plugin message on {}", conn_id, self.channel);
+        Ok(())
+    }
+}
@@ -0,0 +1,70 @@
+use bytes::Bytes;
+use tracing::{debug, warn};
+
+use ferrumc_macros::{packet, NetDecode};
+
+use crate::net::packets::incoming::login_start::disconnect_login;
+use crate::net::packets::{ConnectionId, IncomingPacket};
+use crate::net::utils::authentication::has_joined;
+use crate::net::utils::encryption::{get_server_key, minecraft_digest};
+use crate::state::GlobalState;
+use crate::utils::prelude::*;
+
+/// Sent by the client in reply to [crate::net::packets::outgoing::encryption_request::EncryptionRequest].
+///
+/// Both fields are encrypted with the server's public key. Once the verify token checks out,
+/// the server enables encryption, checks the player with the session server and finishes the login.
+#[derive(NetDecode)]
+#[packet(packet_id = 0x01, state = "login")]
+pub struct EncryptionResponse {
+    pub shared_secret: Bytes,
+    pub verify_token: Bytes,
+}
+
+impl IncomingPacket for EncryptionResponse {
+    async fn handle(self, conn_id: ConnectionId, state: GlobalState) -> Result<()> {
+        let conn_arc = state.connections.get_connection(conn_id)?;
+        let (mut login, server_hash) = {
+            let mut conn = conn_arc.write().await;
+
+            let Some(login) = conn.metadata.pending_login.take() else {
+                return Err(Error::InvalidConnectionMetadata(
+                    "Got an encryption response without a pending login".to_string(),
+                ));
+            };
+
+            let server_key = get_server_key();
+            let verify_token = server_key.decrypt(&self.verify_token)?;
+            if verify_token != conn.metadata.verify_token {
+                disconnect_login(&mut conn, "Invalid verify token").await?;
+                return Err(Error::EncryptionError("Verify token mismatch".to_string()));
+            }
+
+            let shared_secret = server_key.decrypt(&self.shared_secret)?;
+            conn.enable_encryption(&shared_secret).await?;
+
+            let server_hash = minecraft_digest("", &shared_secret, server_key.public_key_der());
+            (login, server_hash)
+        };
+
+        // Nothing else can use the connection while it's locked, so the session server is asked
+        // without holding it
+        let profile = match has_joined(&login.username, &server_hash).await {
+            Ok(profile) => profile,
+            Err(e) => {
+                warn!("Failed to authenticate {}: {}", login.username, e);
+                disconnect_login(&mut *conn_arc.write().await, "Failed to verify username!")
+                    .await?;
+                return Err(e);
+            }
+        };
+
+        // Use the names and ids mojang knows the player by
+        debug!("{} authenticated successfully", profile.name);
+        login.username = profile.name;
+        login.uuid = profile.id.as_u128();
+
+        let properties = profile.properties.into_iter().map(Into::into).collect();
+        login.login(conn_id, state, properties).await
+    }
+}
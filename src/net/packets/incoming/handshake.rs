@@ -1,8 +1,13 @@
+use std::net::SocketAddr;
+
 use ferrumc_codec::network_types::varint::VarInt;
+use tracing::debug;
 
 use ferrumc_macros::{packet, NetDecode};
 
+use crate::net::packets::incoming::login_start::disconnect_login;
 use crate::net::packets::{ConnectionId, IncomingPacket};
+use crate::net::utils::forwarding::{get_forwarding, parse_bungeecord, Forwarding};
 use crate::net::State;
 use crate::state::GlobalState;
 use crate::utils::prelude::*;
@@ -34,6 +39,27 @@ impl IncomingPacket for Handshake {
             s => return Err(Error::InvalidState(s)),
         };
 
+        // A missing player is only a problem once they try to log in, see LoginStart
+        if conn.state == State::Login && get_forwarding() == Forwarding::BungeeCord {
+            match parse_bungeecord(&self.server_address) {
+                Ok(forwarded) => {
+                    let port = conn.metadata.address.map_or(0, |address| address.port());
+                    conn.metadata.address = Some(SocketAddr::new(forwarded.address, port));
+                    conn.metadata.forwarded = Some(forwarded);
+                }
+                Err(e) => debug!("Connection {} wasn't forwarded: {}", conn_id, e),
+            }
+        }
+
+        if conn.state == State::Login {
+            let ip = conn.metadata.address.map(|address| address.ip());
+            if ip.is_some_and(|ip| !state.throttle.allow_login(ip)) {
+                debug!("Connection {} is logging in too often", conn_id);
+                let reason = "Connection throttled! Please wait before reconnecting.";
+                return disconnect_login(&mut conn, reason).await;
+            }
+        }
+
         Ok(())
     }
 }
@@ -0,0 +1,74 @@
+use tracing::trace;
+
+use ferrumc_codec::network_types::varint::VarInt;
+
+use ferrumc_macros::{packet, NetDecode};
+
+use crate::net::packets::{ConnectionId, IncomingPacket};
+use crate::net::utils::combat::attack;
+use crate::state::GlobalState;
+use crate::utils::prelude::*;
+
+/// Sent when the player attacks or right clicks an entity.
+#[derive(NetDecode)]
+#[packet(packet_id = 0x10, state = "play", ids(764 = 0x12))]
+pub struct Interact {
+    pub entity_id: VarInt,
+    pub interaction: Interaction,
+    pub sneaking: bool,
+}
+
+pub enum Interaction {
+    Interact {
+        /// 0 for the main hand, 1 for the offhand.
+        hand: VarInt,
+    },
+    Attack,
+    /// Sent along with [Interaction::Interact] when the player right clicks an entity, with where
+    /// on the entity they clicked, relative to it.
+    InteractAt {
+        x: f32,
+        y: f32,
+        z: f32,
+        hand: VarInt,
+    },
+}
+
+// NetDecode and AsyncRead come in with the derive above
+impl NetDecode for Interaction {
+    async fn net_decode<T>(bytes: &mut T) -> Result<Box<Self>>
+    where
+        T: AsyncRead + Unpin,
+    {
+        let interaction = match VarInt::net_decode(bytes).await?.get_val() {
+            0 => Interaction::Interact {
+                hand: Box::into_inner(VarInt::net_decode(bytes).await?),
+            },
+            1 => Interaction::Attack,
+            2 => Interaction::InteractAt {
+                x: *f32::net_decode(bytes).await?,
+                y: *f32::net_decode(bytes).await?,
+                z: *f32::net_decode(bytes).await?,
+                hand: Box::into_inner(VarInt::net_decode(bytes).await?),
+            },
+            other => return Err(Error::Generic(format!("Unknown interaction: {}", other))),
+        };
+        Ok(Box::new(interaction))
+    }
+}
+
+impl IncomingPacket for Interact {
+    async fn handle(self, conn_id: ConnectionId, state: GlobalState) -> Result<()> {
+        let Ok(target) = usize::try_from(self.entity_id.get_val()) else {
+            return Ok(());
+        };
+        match self.interaction {
+            Interaction::Attack => attack(&state, conn_id, target).await,
+            // Nothing can be right clicked yet
+            _ => {
+                trace!("{} interacted with {}", conn_id, target);
+                Ok(())
+            }
+        }
+    }
+}
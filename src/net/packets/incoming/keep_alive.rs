@@ -5,9 +5,10 @@ use ferrumc_macros::{packet, NetDecode};
 use crate::net::packets::{ConnectionId, IncomingPacket};
 use crate::state::GlobalState;
 use crate::utils::components::keep_alive::KeepAlive;
+use crate::utils::components::latency::Latency;
 
 #[derive(NetDecode, Debug)]
-#[packet(packet_id = 0x12, state = "play")]
+#[packet(packet_id = 0x12, state = "play", ids(764 = 0x14))]
 pub struct KeepAlivePacketIn {
     pub keep_alive_id: i64,
 }
@@ -29,6 +30,19 @@ impl IncomingPacket for KeepAlivePacketIn {
         debug!("KeepAlive for player: {:?}", *keep_alive);
 
         keep_alive.last_received = std::time::Instant::now();
+        // Answers to older keep alives don't say how long this one took
+        let round_trip =
+            (self.keep_alive_id == keep_alive.data).then(|| keep_alive.last_sent.elapsed());
+        drop(keep_alive);
+
+        if let Some(round_trip) = round_trip {
+            state
+                .world
+                .get_component_storage()
+                .get_mut_or_insert_with::<Latency>(player, Default::default)
+                .await
+                .record(round_trip);
+        }
 
         Ok(())
     }
@@ -0,0 +1,45 @@
+use tracing::debug;
+
+use ferrumc_macros::{packet, NetDecode};
+
+use crate::net::packets::outgoing::feature_flags::FeatureFlags;
+use crate::net::packets::outgoing::finish_configuration::FinishConfiguration;
+use crate::net::packets::outgoing::registry_data::RegistryCodecPacket;
+use crate::net::packets::{ConnectionId, IncomingPacket};
+use crate::net::utils::packet_queue::PacketQueue;
+use crate::net::State;
+use crate::state::GlobalState;
+use crate::utils::prelude::*;
+
+/// Sent by clients that go through configuration once they've got Login Success. They're sent the
+/// feature flags and the registries, then told configuration is done.
+///
+/// Known Packs isn't sent, since only 1.20.5 and newer clients have it, so the registries always
+/// go in full.
+#[derive(NetDecode)]
+#[packet(packet_id = 0x03, state = "login")]
+pub struct LoginAcknowledged;
+
+impl IncomingPacket for LoginAcknowledged {
+    async fn handle(self, conn_id: ConnectionId, state: GlobalState) -> Result<()> {
+        let conn = state.connections.get_connection(conn_id)?;
+        let mut conn = conn.write().await;
+        if conn.metadata.pending_login.is_none() {
+            return Err(Error::InvalidConnectionMetadata(
+                "Got a login acknowledgement without a pending login".to_string(),
+            ));
+        }
+
+        conn.state = State::Configuration;
+        debug!("Connection {} is configuring", conn_id);
+
+        let mut packet_queue = PacketQueue::new();
+        packet_queue.queue(FeatureFlags::vanilla()).await?;
+        packet_queue
+            .queue(RegistryCodecPacket::new(conn.metadata.protocol()))
+            .await?;
+        packet_queue.queue(FinishConfiguration::new_auto()).await?;
+        conn.send_packets(packet_queue).await?;
+        Ok(())
+    }
+}
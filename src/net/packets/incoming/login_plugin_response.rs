@@ -0,0 +1,80 @@
+use std::net::SocketAddr;
+
+use ferrumc_codec::network_types::varint::VarInt;
+use tracing::{debug, error, warn};
+
+use ferrumc_macros::{packet, NetDecode};
+
+use crate::net::packets::incoming::login_start::disconnect_login;
+use crate::net::packets::{ConnectionId, IncomingPacket};
+use crate::net::utils::forwarding::verify_velocity;
+use crate::state::GlobalState;
+use crate::utils::config::get_global_config;
+use crate::utils::impls::packet_impls::RemainingBytes;
+use crate::utils::prelude::*;
+
+/// Sent by the client in reply to [crate::net::packets::outgoing::login_plugin_query::LoginPluginQuery].
+///
+/// Only used for Velocity's modern forwarding, where the proxy answers with the player's signed
+/// details. Once they've been verified, the login carries on as that player.
+#[derive(NetDecode)]
+#[packet(packet_id = 0x02, state = "login")]
+pub struct LoginPluginResponse {
+    pub message_id: VarInt,
+    /// Whether the client understood the request. Vanilla clients never do.
+    pub successful: bool,
+    pub data: RemainingBytes,
+}
+
+impl IncomingPacket for LoginPluginResponse {
+    async fn handle(self, conn_id: ConnectionId, state: GlobalState) -> Result<()> {
+        let conn = state.connections.get_connection(conn_id)?;
+        let mut conn = conn.write().await;
+
+        if conn.metadata.forwarding_message_id != Some(self.message_id.get_val()) {
+            debug!("Ignoring login plugin response {}", self.message_id.get_val());
+            return Ok(());
+        }
+        let Some(mut login) = conn.metadata.pending_login.take() else {
+            return Err(Error::InvalidConnectionMetadata(
+                "Got a login plugin response without a pending login".to_string(),
+            ));
+        };
+        conn.metadata.forwarding_message_id = None;
+
+        if !self.successful {
+            debug!("{} connected without going through Velocity", login.username);
+            return disconnect_login(&mut conn, "This server requires you to connect with Velocity.")
+                .await;
+        }
+
+        let secret = &get_global_config().proxy.secret;
+        // Anyone could sign the details with an empty secret
+        if secret.is_empty() {
+            error!("Velocity forwarding is enabled, but no secret is set in the config");
+            disconnect_login(&mut conn, "Unable to verify player details").await?;
+            return Err(Error::AuthenticationFailed("No Velocity secret is set".to_string()));
+        }
+        let forwarded = match verify_velocity(secret, &self.data.0).await {
+            Ok(forwarded) => forwarded,
+            Err(e) => {
+                warn!("Failed to verify {}'s forwarded details: {}", login.username, e);
+                disconnect_login(&mut conn, "Unable to verify player details").await?;
+                return Err(e);
+            }
+        };
+
+        debug!("Velocity forwarded {} from {}", login.username, forwarded.address);
+        login.uuid = forwarded.uuid.as_u128();
+        if let Some(username) = forwarded.username {
+            login.username = username;
+        }
+        let port = conn.metadata.address.map_or(0, |address| address.port());
+        conn.metadata.address = Some(SocketAddr::new(forwarded.address, port));
+
+        // Release the lock, the rest of the login needs it
+        drop(conn);
+
+        login.login(conn_id, state, forwarded.properties).await
+    }
+}
@@ -1,73 +1,241 @@
 use std::time::Instant;
 
+use ferrumc_codec::enc::NetEncodeOpts;
 use ferrumc_codec::network_types::varint::VarInt;
 use rand::random;
-use tracing::{debug};
+use tracing::{debug, warn};
 use uuid::Uuid;
 
 use ferrumc_macros::{packet, NetDecode};
+use crate::database::players::PlayerData;
+use crate::database::world_meta::WorldMeta;
 use crate::events::creation::dispatcher::EventDispatcherExt;
+use crate::events::creation::event::Event;
+use crate::events::login_events::LoginStartEvent;
 use crate::events::world_events::PlayerJoinWorldEvent;
 use crate::net::packets::outgoing::default_spawn_position::DefaultSpawnPosition;
+use crate::net::packets::outgoing::encryption_request::EncryptionRequest;
+use crate::net::packets::outgoing::entity_event::EntityEvent;
 use crate::net::packets::outgoing::keep_alive::KeepAlivePacketOut;
+use crate::net::packets::outgoing::login_play::{ConfiguredLoginPlay, LoginPlay};
+use crate::net::packets::outgoing::login_plugin_query::LoginPluginQuery;
 use crate::net::packets::outgoing::login_plugin_request::LoginPluginRequest;
-use crate::net::packets::outgoing::login_success::LoginSuccess;
+use crate::net::packets::outgoing::login_success::{LoginSuccess, Property};
+use crate::net::packets::outgoing::player_abilities::PlayerAbilitiesOut;
+use crate::net::packets::outgoing::player_info_update::PlayerListEntry;
+use crate::net::packets::incoming::status::{get_favicon, motd_component};
+use crate::net::packets::outgoing::server_data::ServerData;
+use crate::net::packets::outgoing::set_compression::SetCompression;
+use crate::net::packets::outgoing::set_container_content::SetContainerContent;
+use crate::net::packets::outgoing::set_experience::SetExperience;
+use crate::net::packets::outgoing::set_health::SetHealth;
+use crate::net::packets::outgoing::set_held_item::SetHeldItemOut;
 use crate::net::packets::outgoing::synchronize_player_position::SynchronizePlayerPosition;
+use crate::net::packets::outgoing::update_recipes::UpdateRecipes;
+use crate::net::packets::outgoing::update_time::UpdateTime;
 use crate::net::packets::{ConnectionId, IncomingPacket};
+use crate::net::protocol::{supported_versions, Protocol};
 use crate::net::systems::chunk_sender::ChunkSender;
+use crate::net::tab_list;
+use crate::net::utils::encryption::get_server_key;
+use crate::net::utils::forwarding::{
+    get_forwarding, Forwarding, VELOCITY_CHANNEL, VELOCITY_FORWARDING_VERSION,
+};
 use crate::net::utils::packet_queue::PacketQueue;
 use crate::net::Connection;
 use crate::net::State::Play;
 use crate::state::GlobalState;
+use crate::utils::components::dimension::CurrentDimension;
+use crate::utils::components::abilities::Abilities;
+use crate::utils::components::experience::Experience;
+use crate::utils::components::food::Food;
+use crate::utils::components::game_mode::GameMode;
+use crate::utils::components::health::Health;
 use crate::utils::components::keep_alive::KeepAlive;
 use crate::utils::components::player::Player;
 use crate::utils::components::rotation::Rotation;
-use crate::utils::constants::init;
+use crate::utils::config::get_global_config;
 use crate::utils::encoding::position::Position;
 use crate::utils::prelude::*;
+use crate::utils::text_component::TextComponent;
+use crate::world::dimension::Dimension;
+use crate::world::registry_data::get_registry_data;
 
 /// The login start packet is sent by the client to the server to start the login process.
 ///
-/// Server responds with [crate::net::packets::outgoing::login_success::LoginSuccess],
+/// If compression is enabled, [crate::net::packets::outgoing::set_compression::SetCompression] is sent first.
+/// Server then responds with [crate::net::packets::outgoing::login_success::LoginSuccess],
 /// [crate::net::packets::outgoing::login_play::LoginPlay], and
 /// [crate::net::packets::outgoing::default_spawn_position::DefaultSpawnPosition] packets in that order.
 /// No response is required from the client while these are being sent.
 ///
+/// In online mode, the server first sends an [crate::net::packets::outgoing::encryption_request::EncryptionRequest]
+/// and the rest of the login continues once the client's
+/// [crate::net::packets::incoming::encryption_response::EncryptionResponse] has been verified.
+/// Behind a proxy, the player's details come from the proxy instead, see
+/// [crate::net::utils::forwarding].
+///
 /// This is the final stage in the login process. The client is now in the play state.
-#[derive(NetDecode)]
+#[derive(NetDecode, Debug)]
 #[packet(packet_id = 0x00, state = "login")]
 pub struct LoginStart {
     pub username: String,
     pub uuid: u128,
 }
 
-// MAKE SURE YOU RUN THE TEST IN THE login_play.rs FILE TO GENERATE THE NBT FILE
-// The NBT encoded data for the dimension codec. Using flate_include cos the codec file is like 40kb
-#[cfg(not(test))]
-// flate!(pub static NBT_CODEC: [u8] from "./.etc/nbt_codec.nbt");
-const NBT_CODEC: &[u8] = include_bytes!("../../../../.etc/nbt_codec.nbt");
-
-#[cfg(test)]
-const NBT_CODEC: &[u8] = &[0u8; 1];
-
 impl IncomingPacket for LoginStart {
     async fn handle(mut self, conn_id: ConnectionId, state: GlobalState) -> Result<()> {
         self.username = self.username.trim().to_string();
 
+        let conn = state.connections.get_connection(conn_id)?;
+        let protocol_version = conn.read().await.metadata.protocol_version;
+        if Protocol::get(protocol_version).is_none() {
+            debug!("{} tried to join on protocol {}", self.username, protocol_version);
+            let reason = format!("Unsupported version! Please use {}", supported_versions());
+            return disconnect_login(&mut *conn.write().await, &reason).await;
+        }
+
+        // The proxy has already authenticated the player
+        match get_forwarding() {
+            Forwarding::BungeeCord => return self.login_bungeecord(conn_id, state).await,
+            Forwarding::Velocity => return self.request_velocity_forwarding(conn_id, state).await,
+            Forwarding::None => {}
+        }
+
+        if get_global_config().online_mode {
+            return self.request_encryption(conn_id, state).await;
+        }
+
+        let properties = state.skins.properties(&self.username).await;
+        self.login(conn_id, state, properties).await
+    }
+}
+
+impl LoginStart {
+    /// Finishes the login. Clients that go through the configuration state are only sent Login
+    /// Success here and join once it's finished, the rest join right away. See [LoginStart::join].
+    ///
+    /// `properties` are the player's profile properties (skin, cape), from the session server, the
+    /// proxy, or looked up by name in offline mode.
+    pub async fn login(
+        self,
+        conn_id: ConnectionId,
+        state: GlobalState,
+        properties: Vec<Property>,
+    ) -> Result<()> {
         let conn = state.connections.get_connection(conn_id)?;
         // let conn = conn.read().await;
 
+        let address = conn.read().await.metadata.address.map(|address| address.ip());
+        let event = LoginStartEvent::new(
+            conn_id,
+            self.username.clone(),
+            self.uuid,
+            address,
+            Default::default(),
+        );
+        let event = state.dispatch_event(event).await;
+        if event.is_cancelled() {
+            let reason = event.cancellation.reason();
+            debug!("Login for {} was denied: {:?}", self.username, reason);
+            let reason = reason.unwrap_or_else(|| "You are not allowed to join this server".to_string());
+            return disconnect_login(&mut *conn.write().await, &reason).await;
+        }
+
         let mut packet_queue = PacketQueue::new();
+        let uses_configuration = {
+            let metadata = &mut conn.write().await.metadata;
+            metadata.properties = properties.clone();
+            metadata.uses_configuration()
+        };
+        self.send_login_success(&mut packet_queue, properties).await?;
+
+        if uses_configuration {
+            // The client acknowledges the login and goes through configuration first, it joins
+            // once that's finished
+            let mut conn = conn.write().await;
+            self.enable_compression(&mut conn).await?;
+            conn.send_packets(packet_queue).await?;
+            conn.metadata.pending_login = Some(self);
+            return Ok(());
+        }
+
+        self.join(conn_id, state, packet_queue).await
+    }
 
-        self.send_login_success(&mut packet_queue).await?;
-        self.send_login_play(&mut packet_queue).await?;
-        self.send_spawn_position(&mut packet_queue).await?;
+    /// Spawns the player into the world and moves the client into the play state. `packet_queue`
+    /// has anything that has to be sent before Login (play).
+    pub async fn join(
+        self,
+        conn_id: ConnectionId,
+        state: GlobalState,
+        mut packet_queue: PacketQueue,
+    ) -> Result<()> {
+        let conn = state.connections.get_connection(conn_id)?;
+        let world_meta = state.world.resource::<WorldMeta>().await?.clone();
+        let player_data = self.load_player_data(&state, &world_meta).await;
+
+        let uses_configuration = conn.read().await.metadata.uses_configuration();
+        self.send_login_play(
+            &mut packet_queue,
+            conn_id,
+            &player_data,
+            &world_meta,
+            uses_configuration,
+        )
+        .await?;
+        self.send_spawn_position(&mut packet_queue, &world_meta).await?;
+        packet_queue.queue(UpdateTime::new(&world_meta)).await?;
+        for event in world_meta.weather.current_events() {
+            packet_queue.queue(event).await?;
+        }
+        packet_queue.queue(server_data().await).await?;
+        // The player entity doesn't exist yet, so the level can't come from has_permission
+        let permission_level = state.ops.level(Uuid::from_u128(self.uuid), &self.username);
+        packet_queue
+            .queue(EntityEvent::op_permission_level(conn_id as i32, permission_level))
+            .await?;
+        packet_queue
+            .queue(state.commands.declare_commands(permission_level))
+            .await?;
 
         let data: i64 = random();
         let mut keep_alive = KeepAlive::new(Instant::now(), Instant::now(), data);
         self.send_keep_alive(&mut packet_queue, &mut keep_alive)
             .await?;
-        self.update_world_state(&*conn.read().await, keep_alive, state.clone())
+        // Before the player is in the world, see tab_list::add_player
+        let entry = PlayerListEntry {
+            uuid: self.uuid,
+            username: self.username.clone(),
+            properties: conn.read().await.metadata.properties.clone(),
+            game_mode: player_data.game_mode,
+            latency: 0,
+            display_name: None,
+        };
+        tab_list::add_player(&state, entry, &mut packet_queue).await?;
+        state.scoreboard.queue_all(&mut packet_queue).await?;
+        self.update_world_state(&*conn.read().await, keep_alive, &player_data, state.clone())
+            .await?;
+        if !player_data.inventory.is_empty() {
+            let inventory = player_data.inventory();
+            packet_queue
+                .queue(SetContainerContent::player_inventory(&inventory))
+                .await?;
+        }
+        packet_queue
+            .queue(SetHeldItemOut::new_auto(player_data.selected_slot as i8))
+            .await?;
+        packet_queue
+            .queue(PlayerAbilitiesOut::new(&Abilities::configured(player_data.game_mode)))
+            .await?;
+        packet_queue
+            .queue(SetHealth::new(&Health::default(), &Food::default()))
+            .await?;
+        packet_queue
+            .queue(SetExperience::new(&Experience::default()))
+            .await?;
+        packet_queue
+            .queue(UpdateRecipes::new(&state.recipes))
             .await?;
 
         self.synchronize_player_position(state.clone(), &*conn.read().await, &mut packet_queue)
@@ -81,6 +249,8 @@ impl IncomingPacket for LoginStart {
         state.dispatch_event(event).await;
 
         let mut conn = conn.write().await;
+        // Has to go out before Login Success, everything after it is compressed
+        self.enable_compression(&mut conn).await?;
         // Send all the queued packets
         conn.send_packets(packet_queue).await?;
 
@@ -95,24 +265,106 @@ impl IncomingPacket for LoginStart {
 
         Ok(())
     }
-}
 
-impl LoginStart {
-    async fn send_login_success(&self, packet_queue: &mut PacketQueue) -> Result<()> {
+    async fn request_encryption(self, conn_id: ConnectionId, state: GlobalState) -> Result<()> {
+        let conn = state.connections.get_connection(conn_id)?;
+        let mut conn = conn.write().await;
+
+        let verify_token = random::<[u8; 4]>().to_vec();
+        let packet = EncryptionRequest::new_auto(
+            String::new(),
+            get_server_key().public_key_der().to_vec(),
+            verify_token.clone(),
+        );
+        conn.send_packet(packet).await?;
+
+        debug!("Requested encryption from {}", self.username);
+        conn.metadata.verify_token = verify_token;
+        conn.metadata.pending_login = Some(self);
+        Ok(())
+    }
+
+    /// BungeeCord sent who the player is in the handshake, see
+    /// [Handshake](crate::net::packets::incoming::handshake::Handshake).
+    async fn login_bungeecord(mut self, conn_id: ConnectionId, state: GlobalState) -> Result<()> {
+        let conn = state.connections.get_connection(conn_id)?;
+        let Some(forwarded) = conn.write().await.metadata.forwarded.take() else {
+            debug!("{} connected without going through BungeeCord", self.username);
+            let reason = "If you wish to use IP forwarding, please enable it in your BungeeCord config as well!";
+            return disconnect_login(&mut *conn.write().await, reason).await;
+        };
+
+        self.uuid = forwarded.uuid.as_u128();
+        self.login(conn_id, state, forwarded.properties).await
+    }
+
+    /// Asks Velocity who the player is, the login carries on once the
+    /// [LoginPluginResponse](crate::net::packets::incoming::login_plugin_response::LoginPluginResponse) is verified.
+    async fn request_velocity_forwarding(
+        self,
+        conn_id: ConnectionId,
+        state: GlobalState,
+    ) -> Result<()> {
+        let conn = state.connections.get_connection(conn_id)?;
+        let mut conn = conn.write().await;
+
+        let message_id = random::<u16>() as i32;
+        let packet = LoginPluginQuery::new_auto(
+            VarInt::new(message_id),
+            VELOCITY_CHANNEL.to_string(),
+            vec![VELOCITY_FORWARDING_VERSION],
+        );
+        conn.send_packet(packet).await?;
+
+        debug!("Requested {}'s details from Velocity", self.username);
+        conn.metadata.forwarding_message_id = Some(message_id);
+        conn.metadata.pending_login = Some(self);
+        Ok(())
+    }
+
+    async fn enable_compression(&self, conn: &mut Connection) -> Result<()> {
+        let threshold = get_global_config().network_compression_threshold;
+        // Already enabled if the client went through configuration
+        if threshold < 0 || conn.metadata.compression != NetEncodeOpts::None {
+            return Ok(());
+        }
+
+        conn.send_packet(SetCompression::new_auto(VarInt::new(threshold)))
+            .await?;
+        conn.metadata.compression = NetEncodeOpts::Compressed {
+            threshold: threshold as usize,
+        };
+        debug!("Enabled compression with a threshold of {threshold} bytes");
+        Ok(())
+    }
+
+    async fn send_login_success(
+        &self,
+        packet_queue: &mut PacketQueue,
+        properties: Vec<Property>,
+    ) -> Result<()> {
         debug!("LoginStart packet received");
         debug!("Username: {}", self.username);
         let uuid = Uuid::from_u128(self.uuid);
         debug!("UUID: {uuid}");
 
-        let namespace_uuid = Uuid::new_v5(&Uuid::NAMESPACE_URL, "OfflinePlayer".as_bytes());
-        let uuid = Uuid::new_v3(&namespace_uuid, self.username.as_bytes());
-
-        let response = LoginSuccess::new_auto(
-            uuid.as_bytes().into(),
-            "OfflinePlayer".to_string(),
-            VarInt::new(0),
-            vec![],
-        );
+        let response = if get_global_config().online_mode || get_forwarding().is_enabled() {
+            // The session server or the proxy has already verified the uuid and username
+            LoginSuccess::new_auto(
+                uuid.as_bytes().into(),
+                self.username.clone(),
+                properties,
+            )
+        } else {
+            let namespace_uuid = Uuid::new_v5(&Uuid::NAMESPACE_URL, "OfflinePlayer".as_bytes());
+            let uuid = Uuid::new_v3(&namespace_uuid, self.username.as_bytes());
+
+            LoginSuccess::new_auto(
+                uuid.as_bytes().into(),
+                "OfflinePlayer".to_string(),
+                properties,
+            )
+        };
 
         packet_queue.queue(response).await?;
 
@@ -123,22 +375,64 @@ impl LoginStart {
         Ok(())
     }
 
-    async fn send_login_play(&self, packet_queue: &mut PacketQueue) -> Result<()> {
-        let play_packet = crate::net::packets::outgoing::login_play::LoginPlay {
+    async fn send_login_play(
+        &self,
+        packet_queue: &mut PacketQueue,
+        conn_id: ConnectionId,
+        player_data: &PlayerData,
+        world_meta: &WorldMeta,
+        uses_configuration: bool,
+    ) -> Result<()> {
+        let dimension = player_data.dimension();
+        let dimension_names: Vec<String> =
+            Dimension::ALL.iter().map(|d| d.id().to_string()).collect();
+        let view_distance = VarInt::new(get_global_config().view_distance as i32);
+
+        if uses_configuration {
+            // The registries were sent during configuration
+            packet_queue
+                .queue(ConfiguredLoginPlay {
+                    packet_id: VarInt::from(0x28),
+                    entity_id: conn_id as i32,
+                    hardcore: false,
+                    dimension_length: VarInt::new(dimension_names.len() as i32),
+                    dimension_names,
+                    max_players: VarInt::new(20),
+                    view_distance,
+                    simulation_distance: view_distance,
+                    reduced_debug_info: false,
+                    enable_respawn_screen: true,
+                    do_limited_crafting: false,
+                    dimension_type: dimension.id().to_string(),
+                    dimension_name: dimension.id().to_string(),
+                    seed_hash: world_meta.seed_hash(),
+                    gamemode: player_data.game_mode,
+                    previous_gamemode: -1,
+                    is_debug: false,
+                    is_flat: false,
+                    has_death_location: false,
+                    portal_cooldown: VarInt::new(0),
+                })
+                .await?;
+            return Ok(());
+        }
+
+        let play_packet = LoginPlay {
             packet_id: VarInt::from(0x28),
-            entity_id: 0,
+            // The player's entity id is the id of its connection
+            entity_id: conn_id as i32,
             hardcore: false,
-            gamemode: 1,
+            gamemode: player_data.game_mode,
             previous_gamemode: -1,
-            dimension_length: VarInt::new(1),
-            dimension_names: vec!["minecraft:overworld".to_string()],
-            registry_codec: NBT_CODEC,
-            dimension_type: "minecraft:overworld".to_string(),
-            dimension_name: "minecraft:overworld".to_string(),
-            seed_hash: 0,
+            dimension_length: VarInt::new(dimension_names.len() as i32),
+            dimension_names,
+            registry_codec: get_registry_data().codec(),
+            dimension_type: dimension.id().to_string(),
+            dimension_name: dimension.id().to_string(),
+            seed_hash: world_meta.seed_hash(),
             max_players: VarInt::new(20),
-            view_distance: VarInt::new(10),
-            simulation_distance: VarInt::new(10),
+            view_distance,
+            simulation_distance: view_distance,
             reduced_debug_info: false,
             enable_respawn_screen: true,
             is_debug: false,
@@ -156,13 +450,25 @@ impl LoginStart {
         Ok(())
     }
 
-    async fn send_spawn_position(&self, packet_queue: &mut PacketQueue) -> Result<()> {
-        let player_position = Position {
-            x: init::DEFAULT_SPAWN_X_POS,
-            y: init::DEFAULT_SPAWN_Y_POS,
-            z: init::DEFAULT_SPAWN_Z_POS,
-        };
-        let spawn_position = DefaultSpawnPosition::new_auto(player_position.clone(), 0.0);
+    /// Where the player was when they last left, or the spawn point if they haven't joined before.
+    async fn load_player_data(&self, state: &GlobalState, world_meta: &WorldMeta) -> PlayerData {
+        match state.players.load(Uuid::from_u128(self.uuid)).await {
+            Ok(Some(data)) => data,
+            Ok(None) => PlayerData::new_at_spawn(world_meta),
+            Err(e) => {
+                warn!("Failed to load {}'s player data: {}", self.username, e);
+                PlayerData::new_at_spawn(world_meta)
+            }
+        }
+    }
+
+    async fn send_spawn_position(
+        &self,
+        packet_queue: &mut PacketQueue,
+        world_meta: &WorldMeta,
+    ) -> Result<()> {
+        let spawn_position =
+            DefaultSpawnPosition::new_auto(world_meta.spawn_position(), world_meta.spawn_angle);
         packet_queue.queue(spawn_position).await?;
         Ok(())
     }
@@ -182,6 +488,7 @@ impl LoginStart {
         &self,
         conn: &Connection,
         keep_alive: KeepAlive,
+        player_data: &PlayerData,
         state: GlobalState,
     ) -> Result<()> {
         let entity = conn.id;
@@ -189,20 +496,20 @@ impl LoginStart {
         let component_storage = state.world.get_component_storage();
 
         component_storage
+            .insert(entity, player_data.position())
+            .insert(entity, player_data.rotation())
+            .insert(entity, keep_alive)
+            .insert(entity, GameMode::new(player_data.game_mode))
+            .insert(entity, Abilities::configured(player_data.game_mode))
+            .insert(entity, Health::default())
+            .insert(entity, Food::default())
+            .insert(entity, Experience::default())
+            .insert(entity, CurrentDimension::new(player_data.dimension()))
+            .insert(entity, player_data.inventory())
             .insert(
                 entity,
-                Position::new(
-                    init::DEFAULT_SPAWN_X_POS,
-                    init::DEFAULT_SPAWN_Y_POS,
-                    init::DEFAULT_SPAWN_Z_POS,
-                ),
-            )
-            .insert(
-                entity,
-                Rotation::new(init::DEFAULT_SPAWN_YAW, init::DEFAULT_SPAWN_PITCH),
-            )
-            .insert(entity, keep_alive)
-            .insert(entity, Player::new(self.uuid, self.username.clone()));
+                Player::new(self.uuid, self.username.clone(), conn.metadata.properties.clone()),
+            );
 
         Ok(())
     }
@@ -225,3 +532,22 @@ impl LoginStart {
         Ok(())
     }
 }
+
+/// Kicks a player that's still logging in, showing them the reason.
+pub async fn disconnect_login(conn: &mut Connection, reason: &str) -> Result<()> {
+    conn.send_disconnect(&TextComponent::text(reason)).await?;
+    conn.drop = true;
+    Ok(())
+}
+
+/// What the server list showed about the server, for a client that's joined: the first MOTD and
+/// the icon.
+async fn server_data() -> ServerData {
+    let config = get_global_config();
+    let motd = config
+        .motd
+        .first()
+        .map_or_else(|| TextComponent::text(""), |motd| motd_component(motd));
+    let icon = get_favicon().await.cloned();
+    ServerData::new(&motd, icon, config.server_info.enforce_secure_chat)
+}
@@ -1,11 +1,30 @@
+pub mod acknowledge_finish_configuration;
+pub mod chat_command;
 pub mod chat_message;
+pub mod client_command;
+pub mod click_container;
 pub mod client_info;
+pub mod close_container;
+pub mod command_suggestions;
+pub mod configuration_client_info;
+pub mod configuration_plugin_message;
+pub mod encryption_response;
 pub mod handshake;
+pub mod interact;
 pub mod keep_alive;
+pub mod login_acknowledged;
+pub mod login_plugin_response;
 pub mod login_start;
 pub mod ping;
 pub mod player_abilities;
+pub mod player_action;
+pub mod player_command;
+pub mod set_creative_mode_slot;
+pub mod set_held_item;
 pub mod set_player_pos_and_rotate;
 pub mod set_player_position;
 pub mod set_player_rotation;
 pub mod status;
+pub mod update_sign;
+pub mod use_item;
+pub mod use_item_on;
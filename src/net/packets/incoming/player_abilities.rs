@@ -1,24 +1,44 @@
-use tracing::trace;
+use tracing::debug;
 
 use ferrumc_macros::{packet, NetDecode};
 
+use crate::net::packets::outgoing::player_abilities::PlayerAbilitiesOut;
 use crate::net::packets::{ConnectionId, IncomingPacket};
 use crate::state::GlobalState;
+use crate::utils::components::abilities::Abilities;
+use crate::utils::prelude::*;
 
+/// The client only sends whether the player is flying, all other flags are the server's.
+const FLYING: u8 = 0x02;
+
+/// Sent when the player starts or stops flying.
 #[derive(NetDecode)]
-#[packet(packet_id = 0x1C, state = "play")]
+#[packet(packet_id = 0x1C, state = "play", ids(764 = 0x1F))]
 pub struct PlayerAbilities {
     pub flags: u8,
 }
 
 impl IncomingPacket for PlayerAbilities {
-    async fn handle(
-        self,
-        _: ConnectionId,
-        _state: GlobalState,
-    ) -> crate::utils::prelude::Result<()> {
-        trace!("PlayerAbilities packet received");
-        trace!("Flags: {}", self.flags);
-        Ok(())
+    async fn handle(self, conn_id: ConnectionId, state: GlobalState) -> Result<()> {
+        let flying = self.flags & FLYING != 0;
+        let abilities = {
+            let mut abilities = state
+                .world
+                .get_component_storage()
+                .get_mut_or_insert_with::<Abilities>(conn_id, Default::default)
+                .await;
+            if !flying || abilities.allow_flying {
+                abilities.flying = flying;
+                return Ok(());
+            }
+            *abilities
+        };
+
+        // Put them back on the ground
+        debug!("Player {} tried to fly without being allowed to", conn_id);
+        let conn = state.connections.get_connection(conn_id)?;
+        let conn = conn.read().await;
+        conn.send_packet(PlayerAbilitiesOut::new(&abilities))
+            .await
     }
 }
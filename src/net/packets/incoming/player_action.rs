@@ -0,0 +1,171 @@
+use ferrumc_codec::network_types::varint::VarInt;
+use tracing::debug;
+
+use ferrumc_macros::{packet, NetDecode};
+
+use crate::events::creation::dispatcher::EventDispatcherExt;
+use crate::events::creation::event::Event;
+use crate::events::world_events::BlockBreakEvent;
+use crate::net::packets::outgoing::acknowledge_block_change::AcknowledgeBlockChange;
+use crate::net::packets::outgoing::block_update::BlockUpdate;
+use crate::net::packets::{ConnectionId, IncomingPacket};
+use crate::state::GlobalState;
+use crate::utils::components::dimension::dimension_of;
+use crate::utils::components::food::{exhaust, exhaustion};
+use crate::utils::components::game_mode::GameMode;
+use crate::utils::components::inventory::Inventory;
+use crate::utils::constants::init;
+use crate::utils::constants::limits::MAX_REACH_SQUARED;
+use crate::utils::encoding::position::Position;
+use crate::utils::encoding::slot::ItemStack;
+use crate::utils::prelude::*;
+use crate::world::blocks::get_block;
+use crate::world::chunk_format::Palette;
+use crate::world::conversions::BlockId;
+use crate::world::item_entities::throw_item;
+use crate::world::projectiles::release_item;
+
+/// Sent when the player digs a block, and for a few other actions like dropping items.
+#[derive(NetDecode)]
+#[packet(packet_id = 0x1D, state = "play", ids(764 = 0x20))]
+pub struct PlayerAction {
+    /// One of [statuses].
+    pub status: VarInt,
+    pub location: Position,
+    pub face: i8,
+    /// Echoed back in [AcknowledgeBlockChange].
+    pub sequence: VarInt,
+}
+
+pub mod statuses {
+    pub const STARTED_DIGGING: i32 = 0;
+    pub const CANCELLED_DIGGING: i32 = 1;
+    pub const FINISHED_DIGGING: i32 = 2;
+    pub const DROP_ITEM_STACK: i32 = 3;
+    pub const DROP_ITEM: i32 = 4;
+    /// Letting go of right click while using an item, like shooting a bow.
+    pub const RELEASE_USE_ITEM: i32 = 5;
+}
+
+impl IncomingPacket for PlayerAction {
+    async fn handle(self, conn_id: ConnectionId, state: GlobalState) -> Result<()> {
+        let status = self.status.get_val();
+        let breaks = match status {
+            statuses::STARTED_DIGGING => state
+                .world
+                .get_component::<GameMode>(conn_id)
+                .await
+                .is_ok_and(|game_mode| game_mode.breaks_instantly()),
+            statuses::FINISHED_DIGGING => true,
+            statuses::CANCELLED_DIGGING => false,
+            statuses::DROP_ITEM_STACK | statuses::DROP_ITEM => {
+                return drop_held_item(conn_id, &state, status == statuses::DROP_ITEM_STACK).await;
+            }
+            statuses::RELEASE_USE_ITEM => return release_item(&state, conn_id).await,
+            // Swapping items between hands isn't handled yet
+            _ => return Ok(()),
+        };
+
+        if breaks {
+            self.break_block(conn_id, &state).await?;
+        }
+
+        let conn = state.connections.get_connection(conn_id)?;
+        conn.read()
+            .await
+            .send_packet(AcknowledgeBlockChange::new_auto(self.sequence))
+            .await?;
+        Ok(())
+    }
+}
+
+/// Throws the item in the player's main hand, or the whole stack. The client has already taken it
+/// out of the slot.
+async fn drop_held_item(
+    conn_id: ConnectionId,
+    state: &GlobalState,
+    whole_stack: bool,
+) -> Result<()> {
+    let spectator = state
+        .world
+        .get_component::<GameMode>(conn_id)
+        .await
+        .is_ok_and(|game_mode| game_mode.mode == GameMode::SPECTATOR);
+    if spectator {
+        return Ok(());
+    }
+    let dropped = {
+        let mut inventory = state
+            .world
+            .get_component_storage()
+            .get_mut_or_insert_with::<Inventory>(conn_id, Default::default)
+            .await;
+        let slot = Inventory::HOTBAR_START + inventory.selected_slot;
+        let Some(mut held) = inventory.slots.remove(&slot) else {
+            return Ok(());
+        };
+        let count = if whole_stack { held.count } else { 1 };
+        held.count -= count;
+        inventory.set_slot(slot, Some(held.clone()));
+        ItemStack { count, ..held }
+    };
+    throw_item(state, conn_id, dropped).await
+}
+
+impl PlayerAction {
+    async fn break_block(&self, conn_id: ConnectionId, state: &GlobalState) -> Result<()> {
+        let Position { x, y, z } = self.location;
+        let dimension = dimension_of(state, conn_id).await;
+        let block = get_block(state, x, y as i32, z, dimension.name()).await?;
+        if block.is_air() {
+            return Ok(());
+        }
+
+        let game_mode = state
+            .world
+            .get_component::<GameMode>(conn_id)
+            .await
+            .map_or(GameMode::new(init::DEFAULT_GAME_MODE), |game_mode| {
+                *game_mode
+            });
+        let position = state
+            .world
+            .get_component::<Position>(conn_id)
+            .await?
+            .clone();
+        if !game_mode.can_build() || position.distance_squared(&self.location) > MAX_REACH_SQUARED {
+            debug!("{} can't break the block at {}", conn_id, self.location);
+            return self.resend_block(conn_id, state, &block).await;
+        }
+
+        let event = state
+            .dispatch_event(BlockBreakEvent::new(
+                conn_id,
+                self.location.clone(),
+                block.clone(),
+                Default::default(),
+            ))
+            .await;
+
+        if event.is_cancelled() {
+            debug!("Breaking the block at {} was cancelled", self.location);
+            return self.resend_block(conn_id, state, &block).await;
+        }
+        exhaust(state, conn_id, exhaustion::BREAK_BLOCK).await;
+        Ok(())
+    }
+
+    /// The client already removed the block, so it has to be told it's still there.
+    async fn resend_block(
+        &self,
+        conn_id: ConnectionId,
+        state: &GlobalState,
+        block: &Palette,
+    ) -> Result<()> {
+        let id = BlockId::from_palette(block).unwrap_or(BlockId::AIR);
+        let conn = state.connections.get_connection(conn_id)?;
+        let conn = conn.read().await;
+        conn.send_packet(BlockUpdate::new_auto(self.location.clone(), id.into()))
+            .await
+    }
+}
@@ -0,0 +1,54 @@
+use ferrumc_codec::network_types::varint::VarInt;
+
+use ferrumc_macros::{packet, NetDecode};
+
+use crate::net::packets::outgoing::set_entity_metadata::SetEntityMetadata;
+use crate::net::packets::{ConnectionId, IncomingPacket};
+use crate::net::utils::broadcast::broadcast_to_viewers;
+use crate::state::GlobalState;
+use crate::utils::components::entity_state::EntityState;
+use crate::utils::prelude::*;
+
+/// Sent when the player starts or stops sneaking or sprinting, and for a few things to do with
+/// beds, horses and elytras.
+#[derive(NetDecode)]
+#[packet(packet_id = 0x1E, state = "play", ids(764 = 0x21))]
+pub struct PlayerCommand {
+    /// Always the player's own.
+    pub entity_id: VarInt,
+    /// One of [actions].
+    pub action: VarInt,
+    /// How hard a horse jumps, from 0 to 100. Only for [actions::START_JUMP_WITH_HORSE].
+    pub jump_boost: VarInt,
+}
+
+pub mod actions {
+    pub const START_SNEAKING: i32 = 0;
+    pub const STOP_SNEAKING: i32 = 1;
+    pub const LEAVE_BED: i32 = 2;
+    pub const START_SPRINTING: i32 = 3;
+    pub const STOP_SPRINTING: i32 = 4;
+    pub const START_JUMP_WITH_HORSE: i32 = 5;
+}
+
+impl IncomingPacket for PlayerCommand {
+    async fn handle(self, conn_id: ConnectionId, state: GlobalState) -> Result<()> {
+        let metadata = {
+            let mut entity_state = state
+                .world
+                .get_component_storage()
+                .get_mut_or_insert_with::<EntityState>(conn_id, Default::default)
+                .await;
+            match self.action.get_val() {
+                actions::START_SNEAKING => entity_state.sneaking = true,
+                actions::STOP_SNEAKING => entity_state.sneaking = false,
+                actions::START_SPRINTING => entity_state.sprinting = true,
+                actions::STOP_SPRINTING => entity_state.sprinting = false,
+                // Beds, horses and elytras aren't handled yet
+                _ => return Ok(()),
+            }
+            entity_state.metadata()
+        };
+        broadcast_to_viewers(SetEntityMetadata::new(conn_id, metadata), conn_id, &state).await
+    }
+}
@@ -0,0 +1,73 @@
+use ferrumc_macros::{packet, NetDecode};
+
+use crate::net::packets::outgoing::set_container_slot::SetContainerSlot;
+use crate::net::packets::{ConnectionId, IncomingPacket};
+use crate::state::GlobalState;
+use crate::utils::components::game_mode::GameMode;
+use crate::utils::components::inventory::Inventory;
+use crate::utils::encoding::slot::Slot;
+use crate::utils::prelude::*;
+use crate::world::item_entities::throw_item;
+
+/// Sent when a player in creative mode puts an item in a slot of their inventory, or clears one,
+/// or drops an item from the creative menu.
+#[derive(NetDecode)]
+#[packet(packet_id = 0x2B, state = "play", ids(764 = 0x2E))]
+pub struct SetCreativeModeSlot {
+    pub slot: i16,
+    pub clicked_item: Slot,
+}
+
+/// The slot for dropping the item out of the creative menu.
+const DROP: i16 = -1;
+
+impl IncomingPacket for SetCreativeModeSlot {
+    async fn handle(self, conn_id: ConnectionId, state: GlobalState) -> Result<()> {
+        let creative = state
+            .world
+            .get_component::<GameMode>(conn_id)
+            .await
+            .is_ok_and(|game_mode| game_mode.mode == GameMode::CREATIVE);
+        let valid = self
+            .clicked_item
+            .item
+            .as_ref()
+            .is_none_or(|item| state.items.is_valid(item));
+        if !creative || !valid {
+            return Ok(());
+        }
+        if self.slot == DROP {
+            return match self.clicked_item.item {
+                Some(item) => throw_item(&state, conn_id, item).await,
+                None => Ok(()),
+            };
+        }
+        // Slot 0 is the crafting result
+        if !(1..Inventory::SLOTS).contains(&self.slot) {
+            return Ok(());
+        }
+
+        let update = {
+            let mut inventory = state
+                .world
+                .get_component_storage()
+                .get_mut_or_insert_with::<Inventory>(conn_id, Default::default)
+                .await;
+            inventory.set_slot(self.slot, self.clicked_item.item);
+            if Inventory::CRAFTING_GRID.contains(&self.slot)
+                && state.recipes.update_crafting_result(&mut inventory)
+            {
+                inventory.next_state_id();
+                Some(SetContainerSlot::player_inventory(&inventory, Inventory::CRAFTING_RESULT))
+            } else {
+                None
+            }
+        };
+
+        if let Some(packet) = update {
+            let conn = state.connections.get_connection(conn_id)?;
+            conn.read().await.send_packet(packet).await?;
+        }
+        Ok(())
+    }
+}
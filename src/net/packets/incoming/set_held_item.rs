@@ -0,0 +1,35 @@
+use std::time::Instant;
+
+use ferrumc_macros::{packet, NetDecode};
+
+use crate::net::packets::{ConnectionId, IncomingPacket};
+use crate::state::GlobalState;
+use crate::utils::components::attack_cooldown::AttackCooldown;
+use crate::utils::components::inventory::Inventory;
+use crate::utils::prelude::*;
+
+/// Sent when the player changes which hotbar slot they have selected.
+#[derive(NetDecode)]
+#[packet(packet_id = 0x28, state = "play", ids(764 = 0x2B))]
+pub struct SetHeldItem {
+    pub slot: i16,
+}
+
+impl IncomingPacket for SetHeldItem {
+    async fn handle(self, conn_id: ConnectionId, state: GlobalState) -> Result<()> {
+        if !(0..9).contains(&self.slot) {
+            return Ok(());
+        }
+        let component_storage = state.world.get_component_storage();
+        component_storage
+            .get_mut_or_insert_with::<Inventory>(conn_id, Default::default)
+            .await
+            .selected_slot = self.slot;
+        // Switching items starts the attack recharging again
+        component_storage
+            .get_mut_or_insert_with::<AttackCooldown>(conn_id, Default::default)
+            .await
+            .reset(Instant::now());
+        Ok(())
+    }
+}
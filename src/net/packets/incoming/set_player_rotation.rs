@@ -1,11 +1,11 @@
 use ferrumc_macros::{packet, NetDecode};
 
 use crate::net::packets::{ConnectionId, IncomingPacket};
+use crate::net::utils::movement::handle_movement;
 use crate::state::GlobalState;
-use crate::utils::components::rotation::Rotation;
 
 #[derive(NetDecode)]
-#[packet(packet_id = 0x16, state = "play")]
+#[packet(packet_id = 0x16, state = "play", ids(764 = 0x18))]
 pub struct SetPlayerRotation {
     pub yaw: f32,
     pub pitch: f32,
@@ -18,17 +18,13 @@ impl IncomingPacket for SetPlayerRotation {
         conn_id: ConnectionId,
         state: GlobalState,
     ) -> crate::utils::prelude::Result<()> {
-        let my_entity_id = conn_id;
-
-        let component_storage = state.world.get_component_storage();
-
-        let mut rotation = component_storage
-            .get_mut_or_insert_with(my_entity_id, || Rotation::new(0.0, 0.0))
-            .await;
-
-        rotation.yaw = self.yaw;
-        rotation.pitch = self.pitch;
-
-        Ok(())
+        handle_movement(
+            conn_id,
+            state,
+            None,
+            Some((self.yaw, self.pitch)),
+            self.on_ground,
+        )
+        .await
     }
 }
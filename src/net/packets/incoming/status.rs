@@ -11,10 +11,12 @@ use uuid::Uuid;
 
 use crate::net::packets::outgoing::status::OutgoingStatusResponse;
 use crate::net::packets::{ConnectionId, IncomingPacket};
+use crate::net::protocol::{supported_versions, Protocol, NATIVE_PROTOCOL};
 use crate::state::GlobalState;
 use crate::utils::components::player::Player;
 use crate::utils::config;
 use crate::utils::prelude::*;
+use crate::utils::text_component::TextComponent;
 
 /// The status packet is sent by the client to the server to request the server's status.
 ///
@@ -29,8 +31,9 @@ pub struct Status;
 struct JsonResponse {
     version: Version,
     players: Players,
-    description: Description,
-    favicon: &'static String,
+    description: TextComponent,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    favicon: Option<&'static String>,
 }
 
 #[derive(Serialize)]
@@ -52,10 +55,11 @@ struct Sample {
     id: String,
 }
 
-#[derive(Serialize)]
-struct Description {
-    text: String,
-}
+/// Vanilla only ever shows this many players when hovering over the player count.
+const MAX_PLAYER_SAMPLE: usize = 12;
+
+/// Files the favicon is read from, in order. The icon has to be a 64x64 PNG.
+const FAVICON_FILES: [&str; 2] = ["server-icon.png", "icon-64.png"];
 
 impl IncomingPacket for Status {
     async fn handle(self, conn_id: ConnectionId, state: GlobalState) -> Result<()> {
@@ -65,30 +69,34 @@ impl IncomingPacket for Status {
         let conn = state.connections.get_connection(conn_id)?;
         let conn = conn.read().await;
 
-        let random_motd = config.motd.choose(&mut rand::thread_rng()).unwrap().clone();
+        let random_motd = config
+            .motd
+            .choose(&mut rand::thread_rng())
+            .cloned()
+            .unwrap_or_default();
 
         //Queries all players and makes a Sample struct from them
         let player_query = state.world.query::<&Player>();
         let players = player_query.iter().await.collect::<Vec<_>>();
-        let player_samples: Vec<Sample> = players.iter().map(|(_, player)| Sample{
-            name: player.username.to_string(),
-            id: Uuid::from_u128(player.uuid).to_string(),
-        }).collect();
+        let player_samples: Vec<Sample> = players
+            .iter()
+            .take(MAX_PLAYER_SAMPLE)
+            .map(|(_, player)| Sample {
+                name: player.username.to_string(),
+                id: Uuid::from_u128(player.uuid).to_string(),
+            })
+            .collect();
 
         let response = OutgoingStatusResponse {
             packet_id: VarInt::new(0x00),
             json_response: serde_json::ser::to_string(&JsonResponse {
-                version: Version {
-                    name: "1.20.6".to_string(),
-                    // Allow any protocol version for now. To check the ping and stuff
-                    protocol: conn.metadata.protocol_version as u32,
-                },
+                version: version(conn.metadata.protocol_version),
                 players: Players {
                     max: config.max_players,
-                    online: player_samples.len() as i32,
+                    online: players.len() as i32,
                     sample: player_samples,
                 },
-                description: Description { text: random_motd },
+                description: motd_component(&random_motd),
                 favicon: get_encoded_favicon().await,
             })
             .unwrap(),
@@ -107,20 +115,91 @@ impl IncomingPacket for Status {
     }
 }
 
-/// Get the favicon as a base64 encoded string.
+/// The client's own version if it can join, otherwise the server's, which the client shows as
+/// incompatible.
+fn version(protocol_version: i32) -> Version {
+    match Protocol::get(protocol_version) {
+        Some(protocol) => Version {
+            name: protocol.name.to_string(),
+            protocol: protocol.version as u32,
+        },
+        None => Version {
+            name: supported_versions(),
+            protocol: NATIVE_PROTOCOL.version as u32,
+        },
+    }
+}
+
+/// MOTD entries can either be plain text or a JSON text component, e.g.
+/// `{"text": "Hello", "color": "gold"}`.
+pub(crate) fn motd_component(motd: &str) -> TextComponent {
+    TextComponent::parse(motd)
+}
+
+/// Get the favicon as a base64 encoded string, or `None` if there's no icon.
+///
+/// This is cached in a `OnceCell` to avoid encoding it every time.
+async fn get_encoded_favicon() -> Option<&'static String> {
+    static ENCODED: OnceCell<Option<String>> = OnceCell::const_new();
+    ENCODED
+        .get_or_init(|| async {
+            let data = base64::engine::general_purpose::STANDARD.encode(get_favicon().await?);
+            Some(format!("data:image/png;base64,{}", data))
+        })
+        .await
+        .as_ref()
+}
+
+/// Get the favicon's PNG, or `None` if there's no icon. Also sent to joined players in
+/// [ServerData](crate::net::packets::outgoing::server_data::ServerData).
 ///
 /// This is cached in a `OnceCell` to avoid reading the file every time.
-async fn get_encoded_favicon() -> &'static String {
-    static FAVICON: OnceCell<String> = OnceCell::const_new();
+pub(crate) async fn get_favicon() -> Option<&'static Vec<u8>> {
+    static FAVICON: OnceCell<Option<Vec<u8>>> = OnceCell::const_new();
     FAVICON
         .get_or_init(|| async {
-            let mut data = Vec::new();
-            let Ok(mut image) = tokio::fs::File::open("icon-64.png").await else {
-                return String::new();
-            };
-            image.read_to_end(&mut data).await.unwrap_or_default();
-            let data = base64::engine::general_purpose::STANDARD.encode(&data);
-            format!("data:image/png;base64,{}", data)
+            for file in FAVICON_FILES {
+                let Ok(mut image) = tokio::fs::File::open(file).await else {
+                    continue;
+                };
+                let mut data = Vec::new();
+                if image.read_to_end(&mut data).await.is_err() {
+                    continue;
+                }
+                return Some(data);
+            }
+            debug!("No server icon found");
+            None
         })
         .await
+        .as_ref()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{motd_component, version};
+    use crate::utils::text_component::TextComponent;
+
+    #[test]
+    fn test_motd_component() {
+        assert_eq!(
+            motd_component("A FerrumC Server"),
+            TextComponent::text("A FerrumC Server")
+        );
+        assert_eq!(
+            motd_component(r#"{"text": "Hi", "color": "gold"}"#),
+            TextComponent::text("Hi").color("gold")
+        );
+        // Valid JSON, but not a text component
+        assert_eq!(motd_component("42"), TextComponent::text("42"));
+    }
+
+    #[test]
+    fn test_version() {
+        assert_eq!(version(764).name, "1.20.2");
+        // Too new, so it's told what it could join with
+        let unsupported = version(767);
+        assert_eq!(unsupported.name, "1.20.1, 1.20.2");
+        assert_eq!(unsupported.protocol, 763);
+    }
 }
@@ -0,0 +1,57 @@
+use tracing::debug;
+
+use ferrumc_macros::{packet, NetDecode};
+
+use crate::net::packets::{ConnectionId, IncomingPacket};
+use crate::state::GlobalState;
+use crate::utils::components::sign_editor::SignEditor;
+use crate::utils::constants::limits::MAX_SIGN_LINE_LENGTH;
+use crate::utils::encoding::position::Position;
+use crate::utils::prelude::*;
+use crate::world::signs::write_on_sign;
+
+/// Sent when the player is done with the sign editor, with what they wrote.
+#[derive(NetDecode)]
+#[packet(packet_id = 0x2E, state = "play", ids(764 = 0x31))]
+pub struct UpdateSign {
+    pub location: Position,
+    pub is_front_text: bool,
+    pub line_1: String,
+    pub line_2: String,
+    pub line_3: String,
+    pub line_4: String,
+}
+
+impl IncomingPacket for UpdateSign {
+    async fn handle(self, conn_id: ConnectionId, state: GlobalState) -> Result<()> {
+        // Only the sign the player was sent the editor for can be written on, and only once
+        let dimension = match state.world.get_component::<SignEditor>(conn_id).await {
+            Ok(editor) if editor.position == self.location => editor.dimension.clone(),
+            _ => return Ok(()),
+        };
+        state
+            .world
+            .get_component_storage()
+            .remove::<SignEditor>(conn_id)?;
+
+        let lines = [self.line_1, self.line_2, self.line_3, self.line_4];
+        if lines
+            .iter()
+            .any(|line| line.chars().count() > MAX_SIGN_LINE_LENGTH)
+        {
+            return Ok(());
+        }
+        let written = write_on_sign(
+            &state,
+            &self.location,
+            &dimension,
+            self.is_front_text,
+            &lines,
+        )
+        .await?;
+        if !written {
+            debug!("Couldn't write on the sign at {}", self.location);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,33 @@
+use ferrumc_codec::network_types::varint::VarInt;
+
+use ferrumc_macros::{packet, NetDecode};
+
+use crate::net::packets::outgoing::acknowledge_block_change::AcknowledgeBlockChange;
+use crate::net::packets::{ConnectionId, IncomingPacket};
+use crate::state::GlobalState;
+use crate::utils::prelude::*;
+use crate::world::projectiles::use_item;
+
+/// Sent when the player right clicks with an item without aiming at a block, like throwing a
+/// snowball or starting to draw a bow.
+#[derive(NetDecode)]
+#[packet(packet_id = 0x32, state = "play", ids(764 = 0x35))]
+pub struct UseItem {
+    /// 0 for the main hand, 1 for the offhand.
+    pub hand: VarInt,
+    /// Echoed back in [AcknowledgeBlockChange].
+    pub sequence: VarInt,
+}
+
+impl IncomingPacket for UseItem {
+    async fn handle(self, conn_id: ConnectionId, state: GlobalState) -> Result<()> {
+        use_item(&state, conn_id, self.hand.get_val() == 1).await?;
+
+        let conn = state.connections.get_connection(conn_id)?;
+        conn.read()
+            .await
+            .send_packet(AcknowledgeBlockChange::new_auto(self.sequence))
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,230 @@
+use ferrumc_codec::network_types::varint::VarInt;
+use tracing::debug;
+
+use ferrumc_macros::{packet, NetDecode};
+
+use crate::events::creation::dispatcher::EventDispatcherExt;
+use crate::events::creation::event::Event;
+use crate::events::world_events::BlockPlaceEvent;
+use crate::net::packets::outgoing::acknowledge_block_change::AcknowledgeBlockChange;
+use crate::net::packets::outgoing::block_update::BlockUpdate;
+use crate::net::packets::{ConnectionId, IncomingPacket};
+use crate::state::GlobalState;
+use crate::utils::components::dimension::dimension_of;
+use crate::utils::components::game_mode::GameMode;
+use crate::utils::components::inventory::Inventory;
+use crate::utils::components::player::Player;
+use crate::utils::constants::init;
+use crate::utils::constants::limits::MAX_REACH_SQUARED;
+use crate::utils::encoding::position::Position;
+use crate::utils::prelude::*;
+use crate::world::blocks::{get_block, is_replaceable};
+use crate::world::chunk_format::Palette;
+use crate::world::containers::open_container;
+use crate::world::conversions::BlockId;
+
+/// Sent when the player right clicks a block, which opens it if it's a container and otherwise
+/// places the block they're holding.
+#[derive(NetDecode)]
+#[packet(packet_id = 0x31, state = "play", ids(764 = 0x34))]
+pub struct UseItemOn {
+    /// 0 for the main hand, 1 for the offhand.
+    pub hand: VarInt,
+    pub location: Position,
+    /// One of [faces].
+    pub face: VarInt,
+    pub cursor_x: f32,
+    pub cursor_y: f32,
+    pub cursor_z: f32,
+    pub inside_block: bool,
+    /// Echoed back in [AcknowledgeBlockChange].
+    pub sequence: VarInt,
+}
+
+pub mod faces {
+    pub const BOTTOM: i32 = 0;
+    pub const TOP: i32 = 1;
+    pub const NORTH: i32 = 2;
+    pub const SOUTH: i32 = 3;
+    pub const WEST: i32 = 4;
+    pub const EAST: i32 = 5;
+}
+
+impl IncomingPacket for UseItemOn {
+    async fn handle(self, conn_id: ConnectionId, state: GlobalState) -> Result<()> {
+        // Sneaking to place against a container instead isn't tracked yet
+        let dimension = dimension_of(&state, conn_id).await.name();
+        let opened = open_container(&state, conn_id, &self.location, dimension)
+            .await
+            .unwrap_or_else(|e| {
+                debug!("Couldn't open a container at {}: {}", self.location, e);
+                false
+            });
+        if !opened {
+            if let Err(e) = self.place_block(conn_id, &state).await {
+                debug!("Couldn't place a block at {}: {}", self.location, e);
+            }
+        }
+
+        let conn = state.connections.get_connection(conn_id)?;
+        conn.read()
+            .await
+            .send_packet(AcknowledgeBlockChange::new_auto(self.sequence))
+            .await?;
+        Ok(())
+    }
+}
+
+impl UseItemOn {
+    async fn place_block(&self, conn_id: ConnectionId, state: &GlobalState) -> Result<()> {
+        let Some((slot, item_id, block)) = self.held_block(conn_id, state).await else {
+            return Ok(());
+        };
+
+        // Clicking grass, water etc. replaces it, anything else places against the face
+        let Position { x, y, z } = self.location;
+        let dimension = dimension_of(state, conn_id).await.name();
+        let clicked = get_block(state, x, y as i32, z, dimension).await?;
+        let position = if is_replaceable(&clicked) {
+            self.location.clone()
+        } else {
+            offset(&self.location, self.face.get_val())
+        };
+
+        let existing = if position == self.location {
+            clicked
+        } else {
+            get_block(state, position.x, position.y as i32, position.z, dimension).await?
+        };
+        if !is_replaceable(&existing) || player_in_the_way(state, &position).await {
+            return self.revert(conn_id, state, position, &existing).await;
+        }
+
+        let game_mode = state
+            .world
+            .get_component::<GameMode>(conn_id)
+            .await
+            .map_or(GameMode::new(init::DEFAULT_GAME_MODE), |game_mode| {
+                *game_mode
+            });
+        let player = state
+            .world
+            .get_component::<Position>(conn_id)
+            .await?
+            .clone();
+        if !game_mode.can_build() || player.distance_squared(&position) > MAX_REACH_SQUARED {
+            debug!("{} can't place a block at {}", conn_id, position);
+            return self.revert(conn_id, state, position, &existing).await;
+        }
+
+        let event = state
+            .dispatch_event(BlockPlaceEvent::new(
+                conn_id,
+                position.clone(),
+                block,
+                Default::default(),
+            ))
+            .await;
+        if event.is_cancelled() {
+            return self.revert(conn_id, state, position, &existing).await;
+        }
+        if game_mode.mode != GameMode::CREATIVE {
+            use_up(state, conn_id, slot, item_id).await;
+        }
+        Ok(())
+    }
+
+    /// The block the player is holding in the hand they used, if they're holding a block item,
+    /// along with the slot it's in and the item's id.
+    async fn held_block(
+        &self,
+        conn_id: ConnectionId,
+        state: &GlobalState,
+    ) -> Option<(i16, i32, Palette)> {
+        let inventory = state.world.get_component::<Inventory>(conn_id).await.ok()?;
+        let offhand = self.hand.get_val() == 1;
+        let slot = if offhand {
+            Inventory::OFFHAND
+        } else {
+            Inventory::HOTBAR_START + inventory.selected_slot
+        };
+        let item = inventory.held_item(offhand)?;
+        Some((slot, item.id, state.items.block(item.id)?))
+    }
+
+    /// The client shows the block as placed straight away, so it has to be told what's really there.
+    async fn revert(
+        &self,
+        conn_id: ConnectionId,
+        state: &GlobalState,
+        position: Position,
+        block: &Palette,
+    ) -> Result<()> {
+        let id = BlockId::from_palette(block).unwrap_or(BlockId::AIR);
+        let conn = state.connections.get_connection(conn_id)?;
+        conn.read()
+            .await
+            .send_packet(BlockUpdate::new_auto(position, id.into()))
+            .await?;
+        Ok(())
+    }
+}
+
+/// The block next to `position` on one of its [faces].
+fn offset(position: &Position, face: i32) -> Position {
+    let Position { x, y, z } = *position;
+    match face {
+        faces::BOTTOM => Position::new(x, y - 1, z),
+        faces::TOP => Position::new(x, y + 1, z),
+        faces::NORTH => Position::new(x, y, z - 1),
+        faces::SOUTH => Position::new(x, y, z + 1),
+        faces::WEST => Position::new(x - 1, y, z),
+        faces::EAST => Position::new(x + 1, y, z),
+        _ => position.clone(),
+    }
+}
+
+/// Takes one of the placed block's item out of the slot it was in. The client already took it out
+/// of its own inventory. Nothing is taken if the slot has something else in it by now.
+async fn use_up(state: &GlobalState, conn_id: ConnectionId, slot: i16, item_id: i32) {
+    let mut inventory = state
+        .world
+        .get_component_storage()
+        .get_mut_or_insert_with::<Inventory>(conn_id, Default::default)
+        .await;
+    let still_held = inventory
+        .slots
+        .get(&slot)
+        .is_some_and(|item| item.id == item_id);
+    if still_held {
+        inventory.take_one(slot);
+    }
+}
+
+/// Whether a player is standing in the block, so placing it would trap them.
+async fn player_in_the_way(state: &GlobalState, block: &Position) -> bool {
+    let query = state.world.query::<(&Player, &Position)>();
+    let in_the_way = query.iter().await.any(|(_, (_, player))| {
+        // Players are 2 blocks tall, and their position is where their feet are
+        player.x == block.x
+            && player.z == block.z
+            && (player.y == block.y || player.y + 1 == block.y)
+    });
+    in_the_way
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_offset() {
+        let position = Position::new(10, 64, -3);
+        assert_eq!(offset(&position, faces::TOP), Position::new(10, 65, -3));
+        assert_eq!(offset(&position, faces::BOTTOM), Position::new(10, 63, -3));
+        assert_eq!(offset(&position, faces::NORTH), Position::new(10, 64, -4));
+        assert_eq!(offset(&position, faces::SOUTH), Position::new(10, 64, -2));
+        assert_eq!(offset(&position, faces::WEST), Position::new(9, 64, -3));
+        assert_eq!(offset(&position, faces::EAST), Position::new(11, 64, -3));
+    }
+}
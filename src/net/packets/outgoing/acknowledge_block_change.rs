@@ -0,0 +1,11 @@
+use ferrumc_codec::network_types::varint::VarInt;
+use ferrumc_macros::NetEncode;
+
+/// Tells the client the server has handled its block changes up to `sequence`, so it can stop
+/// predicting them and show what the server sent instead.
+#[derive(NetEncode)]
+pub struct AcknowledgeBlockChange {
+    #[encode(default = VarInt::from(0x06))]
+    pub packet_id: VarInt,
+    pub sequence: VarInt,
+}
@@ -0,0 +1,17 @@
+use ferrumc_codec::network_types::varint::VarInt;
+use ferrumc_macros::NetEncode;
+
+use crate::utils::encoding::position::Position;
+
+/// Tells the client a block entity has changed, e.g. the text on a sign.
+#[derive(NetEncode)]
+pub struct BlockEntityData {
+    #[encode(default = VarInt::from(0x08))]
+    pub packet_id: VarInt,
+    pub location: Position,
+    /// From the `minecraft:block_entity_type` registry.
+    pub block_entity_type: VarInt,
+    /// Network NBT for the client's version.
+    #[encode(raw_bytes(prepend_length = false))]
+    pub data: Vec<u8>,
+}
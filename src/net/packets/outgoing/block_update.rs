@@ -0,0 +1,14 @@
+use ferrumc_codec::network_types::varint::VarInt;
+use ferrumc_macros::NetEncode;
+
+use crate::utils::encoding::position::Position;
+
+/// Tells the client a block has changed.
+#[derive(NetEncode)]
+pub struct BlockUpdate {
+    #[encode(default = VarInt::from(0x0A))]
+    pub packet_id: VarInt,
+    pub location: Position,
+    /// The block state's network ID.
+    pub block_id: VarInt,
+}
@@ -1,12 +1,14 @@
+use crate::net::protocol::Protocol;
 use crate::state::GlobalState;
-use crate::utils::encoding::bitset::BitSet;
 use crate::utils::error::Error;
-use crate::world::chunk_format::Heightmaps;
+use crate::world::chunk_format::{Biomes, Chunk, Heightmaps};
+use crate::world::dimension::Dimension;
+use crate::world::generation::get_or_generate_chunk;
 use crate::Result;
 use ferrumc_codec::enc::NetEncode;
+use ferrumc_codec::network_types::bitset::BitSet;
 use ferrumc_codec::network_types::varint::VarInt;
 use ferrumc_macros::NetEncode;
-use nbt_lib::NBTTag;
 use std::io::Cursor;
 use tracing::warn;
 
@@ -29,7 +31,9 @@ pub struct ChunkDataAndUpdateLight {
     pub packet_id: VarInt,
     pub chunk_x: i32,
     pub chunk_z: i32,
-    pub heightmaps: Heightmaps,
+    /// The encoded [Heightmaps], as network NBT for the client's version.
+    #[encode(raw_bytes(prepend_length = false))]
+    pub heightmaps: Vec<u8>,
     #[encode(raw_bytes(prepend_length = true))]
     pub data: Vec<u8>,
     pub block_entities_count: VarInt,
@@ -49,12 +53,16 @@ pub struct LightData {
     pub block_light_arrays: Vec<LightArray>,
 }
 
+/// A block entity the client should know about, e.g. so a chest can be drawn.
 #[derive(NetEncode)]
 pub struct BlockEntity {
+    /// The x and z in the chunk, as `x << 4 | z`.
     pub packed_xz: u8,
     pub y: i16,
     pub type_id: VarInt,
-    pub data: NBTTag,
+    /// Network NBT for the client's version.
+    #[encode(raw_bytes(prepend_length = false))]
+    pub data: Vec<u8>,
 }
 
 #[derive(NetEncode, Clone)]
@@ -64,20 +72,42 @@ pub struct LightArray {
 }
 
 impl ChunkDataAndUpdateLight {
-    pub async fn new(state: GlobalState, chunk_x: i32, chunk_z: i32) -> Result<Self> {
-        let chunk = state
-            .database
-            .get_chunk(chunk_x, chunk_z, "overworld".to_string())
+    pub async fn new(
+        state: GlobalState,
+        chunk_x: i32,
+        chunk_z: i32,
+        dimension: Dimension,
+        protocol: &Protocol,
+    ) -> Result<Self> {
+        let chunk = get_or_generate_chunk(&state, chunk_x, chunk_z, dimension.name())
             .await?
             .ok_or(Error::ChunkNotFound(chunk_x, chunk_z))?;
+        Self::from_chunk(&chunk, chunk_x, chunk_z, dimension, protocol).await
+    }
 
+    /// Builds the packet for a chunk that's already been loaded, which has to be in the network
+    /// format.
+    pub async fn from_chunk(
+        chunk: &Chunk,
+        chunk_x: i32,
+        chunk_z: i32,
+        dimension: Dimension,
+        protocol: &Protocol,
+    ) -> Result<Self> {
         // Serialize the chunk data
         let mut data = Cursor::new(Vec::new());
 
         if let Some(sections) = &chunk.sections {
             for section in sections {
                 section.net_encode(&mut data).await?;
-                serialize_biomes().await?.net_encode(&mut data).await?;
+                match &section.biomes {
+                    Some(biomes) => biomes.net_encode(&mut data).await?,
+                    None => {
+                        Biomes::single(dimension.default_biome())
+                            .net_encode(&mut data)
+                            .await?
+                    }
+                }
             }
         } else {
             return Err(Error::InvalidChunk(
@@ -87,22 +117,20 @@ impl ChunkDataAndUpdateLight {
             ));
         }
 
-        // 24 is the number of sections in a chunk
-
-        // -4 to 20
-        const SECTIONS: usize = 24;
+        // 24 sections in the overworld, from -4 to 20, and 16 in the nether and the end
+        let section_count = chunk.sections.as_ref().map_or(0, Vec::len);
 
         // let sky_light_mask = BitSet::from_iter((0..SECTIONS + 2).map(|_| 1));
         // let block_light_mask = BitSet::from_iter((0..SECTIONS + 2).map(|_| 1));
         // let empty_sky_light_mask = BitSet::from_iter((0..SECTIONS + 2).map(|_| 0));
         // let empty_block_light_mask = BitSet::from_iter((0..SECTIONS + 2).map(|_| 0));
 
-        let mut sky_light_mask = BitSet::new(SECTIONS + 2);
+        let mut sky_light_mask = BitSet::new(section_count + 2);
         sky_light_mask.set_all();
-        let mut block_light_mask = BitSet::new(SECTIONS + 2);
+        let mut block_light_mask = BitSet::new(section_count + 2);
         block_light_mask.set_all();
-        let empty_sky_light_mask = BitSet::new(SECTIONS + 2);
-        let empty_block_light_mask = BitSet::new(SECTIONS + 2);
+        let empty_sky_light_mask = BitSet::new(section_count + 2);
+        let empty_block_light_mask = BitSet::new(section_count + 2);
 
         // Create light arrays
         let mut sky_light_arrays = Vec::new();
@@ -141,24 +169,39 @@ impl ChunkDataAndUpdateLight {
             data: vec![0; 2048],
         });
 
-        let heightmaps = chunk.heightmaps.unwrap_or_else(|| {
-            warn!("Chunk is missing heightmaps, creating default heightmaps");
-            Heightmaps {
-                //motion_blocking_no_leaves: None,
-                motion_blocking: Some(vec![i64::MAX; 37]),
-                //ocean_floor: None,
-                world_surface: Some(vec![i64::MAX; 37]),
+        let mut heightmap_bytes = Vec::new();
+        match &chunk.heightmaps {
+            Some(heightmaps) => heightmaps.net_encode(&mut heightmap_bytes).await?,
+            None => {
+                warn!("Chunk is missing heightmaps, recalculating them");
+                let min_y = chunk.y_pos * 16;
+                Heightmaps::compute(chunk.sections.as_deref().unwrap_or_default(), min_y)
+                    .net_encode(&mut heightmap_bytes)
+                    .await?
             }
-        });
+        }
+
+        let mut block_entities = Vec::new();
+        for block_entity in chunk.block_entities.iter().flatten() {
+            let Some(type_id) = block_entity.type_id() else {
+                continue;
+            };
+            block_entities.push(BlockEntity {
+                packed_xz: ((block_entity.x & 15) << 4 | (block_entity.z & 15)) as u8,
+                y: block_entity.y as i16,
+                type_id: VarInt::new(type_id),
+                data: protocol.network_nbt(block_entity.client_data()?),
+            });
+        }
 
         let res = ChunkDataAndUpdateLight {
             packet_id: VarInt::from(0x24),
             chunk_x,
             chunk_z,
-            heightmaps,
+            heightmaps: protocol.network_nbt(heightmap_bytes),
             data: data.into_inner(),
-            block_entities_count: VarInt::from(0),
-            block_entities: Vec::new(),
+            block_entities_count: VarInt::from(block_entities.len() as i32),
+            block_entities,
             light_data: LightData {
                 sky_light_mask,
                 block_light_mask,
@@ -193,23 +236,6 @@ async fn serialize_block_states(block_states: &BlockStates) -> Result<Vec<u8>> {
 
     Ok(data)
 }*/
-async fn serialize_biomes() -> Result<Vec<u8>> {
-    let mut data: Vec<u8> = Vec::new();
-    let bits_per_biome = 6;
-    data.push(bits_per_biome);
-
-    // Direct biome encoding, no palette
-    let biome_data = vec![0u64; 64 * (bits_per_biome as usize) / 64]; // 64 biomes per section
-    VarInt::from(biome_data.len() as i32)
-        .net_encode(&mut data)
-        .await?;
-
-    for long in biome_data {
-        long.net_encode(&mut data).await?;
-    }
-
-    Ok(data)
-}
 /*
 fn create_basic_chunk(chunk_x: i32, chunk_z: i32) -> Chunk {
     let _rng = rand::thread_rng();
@@ -0,0 +1,11 @@
+use ferrumc_codec::network_types::varint::VarInt;
+use ferrumc_macros::NetEncode;
+
+/// Takes down the title that's showing. With `reset`, the subtitle and times go back to their
+/// defaults too.
+#[derive(NetEncode)]
+pub struct ClearTitles {
+    #[encode(default = VarInt::from(0x0E))]
+    pub packet_id: VarInt,
+    pub reset: bool,
+}
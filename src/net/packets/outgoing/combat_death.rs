@@ -0,0 +1,20 @@
+use ferrumc_codec::network_types::varint::VarInt;
+use ferrumc_macros::NetEncode;
+
+use crate::utils::text_component::TextComponent;
+
+/// Shows a player the death screen, with `message` above the respawn button.
+#[derive(NetEncode)]
+pub struct CombatDeath {
+    #[encode(default = VarInt::from(0x38))]
+    pub packet_id: VarInt,
+    pub player_id: VarInt,
+    /// A JSON text component.
+    pub message: String,
+}
+
+impl CombatDeath {
+    pub fn new(player_id: usize, message: &TextComponent) -> Self {
+        Self::new_auto(VarInt::new(player_id as i32), message.to_json())
+    }
+}
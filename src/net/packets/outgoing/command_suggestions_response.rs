@@ -0,0 +1,35 @@
+use ferrumc_codec::network_types::varint::VarInt;
+use ferrumc_macros::NetEncode;
+
+/// The answer to a [CommandSuggestionsRequest](crate::net::packets::incoming::command_suggestions::CommandSuggestionsRequest).
+///
+/// `start` and `length` are the part of the typed text the matches replace.
+#[derive(NetEncode)]
+pub struct CommandSuggestionsResponse {
+    #[encode(default = VarInt::from(0x0F))]
+    pub packet_id: VarInt,
+    /// The id of the request this answers.
+    pub transaction_id: VarInt,
+    pub start: VarInt,
+    pub length: VarInt,
+    pub count: VarInt,
+    pub matches: Vec<SuggestionMatch>,
+}
+
+#[derive(NetEncode)]
+pub struct SuggestionMatch {
+    pub text: String,
+    pub has_tooltip: bool,
+    /// JSON text component shown when hovering over the match.
+    pub tooltip: Option<String>,
+}
+
+impl SuggestionMatch {
+    pub fn new(text: String) -> Self {
+        Self {
+            text,
+            has_tooltip: false,
+            tooltip: None,
+        }
+    }
+}
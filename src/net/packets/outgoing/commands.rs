@@ -0,0 +1,38 @@
+use ferrumc_codec::network_types::varint::VarInt;
+use ferrumc_macros::NetEncode;
+
+/// Declares the server's command tree, which the client uses to validate and complete commands.
+///
+/// See [CommandRegistry::declare_commands](crate::commands::CommandRegistry::declare_commands).
+#[derive(NetEncode)]
+pub struct Commands {
+    #[encode(default = VarInt::from(0x10))]
+    pub packet_id: VarInt,
+    pub count: VarInt,
+    pub nodes: Vec<CommandNode>,
+    pub root_index: VarInt,
+}
+
+/// A node in the command tree. Which of the optional fields are present depends on the `flags`.
+#[derive(NetEncode, Debug)]
+pub struct CommandNode {
+    pub flags: u8,
+    pub children_count: VarInt,
+    pub children: Vec<VarInt>,
+    pub redirect_node: Option<VarInt>,
+    pub name: Option<String>,
+    pub parser_id: Option<VarInt>,
+    /// Parser specific properties, already encoded.
+    #[encode(raw_bytes(prepend_length = false))]
+    pub properties: Vec<u8>,
+    pub suggestions_type: Option<String>,
+}
+
+pub mod flags {
+    pub const ROOT: u8 = 0x00;
+    pub const LITERAL: u8 = 0x01;
+    pub const ARGUMENT: u8 = 0x02;
+    pub const EXECUTABLE: u8 = 0x04;
+    pub const HAS_REDIRECT: u8 = 0x08;
+    pub const HAS_SUGGESTIONS_TYPE: u8 = 0x10;
+}
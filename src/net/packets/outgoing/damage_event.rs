@@ -0,0 +1,36 @@
+use ferrumc_codec::network_types::varint::VarInt;
+use ferrumc_macros::NetEncode;
+
+/// Shows an entity getting hurt: it flashes red and makes its hurt sound.
+#[derive(NetEncode)]
+pub struct DamageEventOut {
+    #[encode(default = VarInt::from(0x18))]
+    pub packet_id: VarInt,
+    pub entity_id: VarInt,
+    /// From the `minecraft:damage_type` registry.
+    pub source_type: VarInt,
+    /// The entity responsible, plus one, or 0 for none.
+    pub source_cause: VarInt,
+    /// The entity that did the damage, e.g. an arrow, plus one, or 0 for none.
+    pub source_direct: VarInt,
+    /// The source's position is never sent.
+    pub has_source_position: bool,
+}
+
+impl DamageEventOut {
+    pub fn new(
+        entity_id: usize,
+        source_type: i32,
+        source: Option<usize>,
+        direct: Option<usize>,
+    ) -> Self {
+        let id = |entity: Option<usize>| VarInt::new(entity.map_or(0, |entity| entity as i32 + 1));
+        Self::new_auto(
+            VarInt::new(entity_id as i32),
+            VarInt::new(source_type),
+            id(source),
+            id(direct),
+            false,
+        )
+    }
+}
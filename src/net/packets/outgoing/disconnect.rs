@@ -0,0 +1,40 @@
+use ferrumc_codec::network_types::varint::VarInt;
+
+use ferrumc_macros::NetEncode;
+
+use crate::utils::text_component::TextComponent;
+
+/// Kicks a player that's in the play state. See [LoginDisconnect](super::login_disconnect::LoginDisconnect)
+/// for players that are still logging in.
+#[derive(NetEncode)]
+pub struct Disconnect {
+    #[encode(default = VarInt::from(0x1A))]
+    pub packet_id: VarInt,
+    /// JSON text component shown on the disconnect screen.
+    pub reason: String,
+}
+
+/// Kicks a player that's in the configuration state.
+#[derive(NetEncode)]
+pub struct ConfigurationDisconnect {
+    #[encode(default = VarInt::from(0x01))]
+    pub packet_id: VarInt,
+    /// JSON text component shown on the disconnect screen.
+    pub reason: String,
+}
+
+impl ConfigurationDisconnect {
+    pub fn component(reason: &TextComponent) -> Self {
+        Self::new_auto(reason.to_json())
+    }
+}
+
+impl Disconnect {
+    pub fn text(reason: &str) -> Self {
+        Self::component(&TextComponent::text(reason))
+    }
+
+    pub fn component(reason: &TextComponent) -> Self {
+        Self::new_auto(reason.to_json())
+    }
+}
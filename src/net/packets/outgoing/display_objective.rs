@@ -0,0 +1,13 @@
+use ferrumc_codec::network_types::varint::VarInt;
+use ferrumc_macros::NetEncode;
+
+/// Shows an objective in one of the scoreboard's slots, e.g. the sidebar. An empty name clears the
+/// slot.
+#[derive(NetEncode)]
+pub struct DisplayObjective {
+    #[encode(default = VarInt::from(0x51))]
+    pub packet_id: VarInt,
+    /// See [DisplaySlot::id](crate::net::scoreboard::DisplaySlot::id).
+    pub position: u8,
+    pub objective_name: String,
+}
@@ -0,0 +1,18 @@
+use ferrumc_codec::network_types::varint::VarInt;
+
+use ferrumc_macros::NetEncode;
+
+/// Sent by the server in online mode to start the encryption handshake.
+/// The client answers with [crate::net::packets::incoming::encryption_response::EncryptionResponse].
+#[derive(NetEncode)]
+pub struct EncryptionRequest {
+    #[encode(default = VarInt::from(0x01))]
+    pub packet_id: VarInt,
+    // Always empty on modern versions
+    pub server_id: String,
+    /// The server's public key, DER encoded.
+    #[encode(raw_bytes(prepend_length = true))]
+    pub public_key: Vec<u8>,
+    #[encode(raw_bytes(prepend_length = true))]
+    pub verify_token: Vec<u8>,
+}
@@ -0,0 +1,25 @@
+use ferrumc_codec::network_types::varint::VarInt;
+use ferrumc_macros::NetEncode;
+
+/// Plays an animation on an entity for the players that can see it.
+#[derive(NetEncode)]
+pub struct EntityAnimation {
+    #[encode(default = VarInt::from(0x04))]
+    pub packet_id: VarInt,
+    pub entity_id: VarInt,
+    /// One of [animations].
+    pub animation: u8,
+}
+
+impl EntityAnimation {
+    pub fn new(entity_id: usize, animation: u8) -> Self {
+        Self::new_auto(VarInt::new(entity_id as i32), animation)
+    }
+}
+
+pub mod animations {
+    pub const SWING_MAIN_ARM: u8 = 0;
+    pub const SWING_OFFHAND: u8 = 3;
+    /// The particles of a critical hit.
+    pub const CRITICAL_EFFECT: u8 = 4;
+}
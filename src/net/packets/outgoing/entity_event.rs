@@ -0,0 +1,29 @@
+use ferrumc_codec::network_types::varint::VarInt;
+use ferrumc_macros::NetEncode;
+
+/// Triggers something client-side for an entity, e.g. an animation. What happens depends on the
+/// entity's type.
+#[derive(NetEncode)]
+pub struct EntityEvent {
+    #[encode(default = VarInt::from(0x1C))]
+    pub packet_id: VarInt,
+    pub entity_id: i32,
+    /// One of [statuses].
+    pub status: i8,
+}
+
+impl EntityEvent {
+    /// Tells a player their own permission level, which unlocks e.g. the F3 + F4 game mode switcher.
+    pub fn op_permission_level(entity_id: i32, level: u8) -> Self {
+        Self::new_auto(entity_id, statuses::OP_PERMISSION_LEVEL_0 + level.min(4) as i8)
+    }
+}
+
+pub mod statuses {
+    /// Plays a living entity's death animation and sound.
+    pub const DEATH: i8 = 3;
+    /// Snowballs burst into particles.
+    pub const PROJECTILE_BREAK: i8 = 3;
+    /// Levels 1 to 4 follow on from this one.
+    pub const OP_PERMISSION_LEVEL_0: i8 = 24;
+}
@@ -0,0 +1,18 @@
+use ferrumc_codec::network_types::varint::VarInt;
+use ferrumc_macros::NetEncode;
+
+use crate::net::packets::outgoing::sound_effect::SoundId;
+
+/// Plays a sound that follows an entity around.
+#[derive(NetEncode)]
+pub struct EntitySoundEffect {
+    #[encode(default = VarInt::from(0x61))]
+    pub packet_id: VarInt,
+    pub sound: SoundId,
+    /// See [SoundCategory](crate::world::sounds::SoundCategory).
+    pub category: VarInt,
+    pub entity_id: VarInt,
+    pub volume: f32,
+    pub pitch: f32,
+    pub seed: i64,
+}
@@ -0,0 +1,18 @@
+use ferrumc_codec::network_types::varint::VarInt;
+use ferrumc_macros::NetEncode;
+
+/// Which experimental features are turned on, sent during configuration.
+#[derive(NetEncode)]
+pub struct FeatureFlags {
+    #[encode(default = VarInt::from(0x07))]
+    pub packet_id: VarInt,
+    pub count: VarInt,
+    pub flags: Vec<String>,
+}
+
+impl FeatureFlags {
+    /// Just the features every world has.
+    pub fn vanilla() -> Self {
+        Self::new_auto(VarInt::new(1), vec!["minecraft:vanilla".to_string()])
+    }
+}
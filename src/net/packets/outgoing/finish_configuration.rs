@@ -0,0 +1,11 @@
+use ferrumc_codec::network_types::varint::VarInt;
+use ferrumc_macros::NetEncode;
+
+/// Tells the client configuration is done. It answers with
+/// [AcknowledgeFinishConfiguration](crate::net::packets::incoming::acknowledge_finish_configuration::AcknowledgeFinishConfiguration)
+/// and switches to play.
+#[derive(NetEncode)]
+pub struct FinishConfiguration {
+    #[encode(default = VarInt::from(0x02))]
+    pub packet_id: VarInt,
+}
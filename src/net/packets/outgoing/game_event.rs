@@ -0,0 +1,22 @@
+use ferrumc_codec::network_types::varint::VarInt;
+use ferrumc_macros::NetEncode;
+
+/// Tells the client about a change in game state, e.g. weather or its game mode.
+#[derive(NetEncode)]
+pub struct GameEvent {
+    #[encode(default = VarInt::from(0x1F))]
+    pub packet_id: VarInt,
+    /// One of [events].
+    pub event: u8,
+    pub value: f32,
+}
+
+pub mod events {
+    pub const END_RAINING: u8 = 1;
+    pub const BEGIN_RAINING: u8 = 2;
+    pub const CHANGE_GAME_MODE: u8 = 3;
+    /// The value is how heavy the rain is, from 0 to 1.
+    pub const RAIN_LEVEL_CHANGE: u8 = 7;
+    /// The value is how heavy the thunder is, from 0 to 1.
+    pub const THUNDER_LEVEL_CHANGE: u8 = 8;
+}
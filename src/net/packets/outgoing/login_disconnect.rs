@@ -2,11 +2,24 @@ use ferrumc_codec::network_types::varint::VarInt;
 
 use ferrumc_macros::NetEncode;
 
+use crate::utils::text_component::TextComponent;
+
 /// The login disconnect packet is sent by the server to the client to disconnect the client.
 /// Used to cancel the login process.
 #[derive(NetEncode)]
 pub struct LoginDisconnect {
     #[encode(default = VarInt::from(0x00))]
     pub packet_id: VarInt,
+    /// JSON text component shown on the disconnect screen.
     pub reason: String,
 }
+
+impl LoginDisconnect {
+    pub fn text(reason: &str) -> Self {
+        Self::component(&TextComponent::text(reason))
+    }
+
+    pub fn component(reason: &TextComponent) -> Self {
+        Self::new_auto(reason.to_json())
+    }
+}
@@ -14,7 +14,7 @@ pub struct LoginPlay<'a> {
     pub previous_gamemode: i8,
     pub dimension_length: VarInt,
     pub dimension_names: Vec<String>,
-    /// The codec for the dimension. Baked into the binary, see [crate::net::packets::incoming::login_start::LoginStart::decode].
+    /// The registries the client needs, see [registry_data](crate::world::registry_data).
     // #[encode(raw_bytes(prepend_length = false))]
     pub registry_codec: &'a [u8],
     pub dimension_type: String,
@@ -33,6 +33,33 @@ pub struct LoginPlay<'a> {
     pub portal_cooldown: VarInt,
 }
 
+/// [LoginPlay] for clients that go through configuration, which got the registries there instead
+/// and have the fields in another order.
+#[derive(NetEncode)]
+pub struct ConfiguredLoginPlay {
+    #[encode(default = VarInt::from(0x28))]
+    pub packet_id: VarInt,
+    pub entity_id: i32,
+    pub hardcore: bool,
+    pub dimension_length: VarInt,
+    pub dimension_names: Vec<String>,
+    pub max_players: VarInt,
+    pub view_distance: VarInt,
+    pub simulation_distance: VarInt,
+    pub reduced_debug_info: bool,
+    pub enable_respawn_screen: bool,
+    pub do_limited_crafting: bool,
+    pub dimension_type: String,
+    pub dimension_name: String,
+    pub seed_hash: i64,
+    pub gamemode: u8,
+    pub previous_gamemode: i8,
+    pub is_debug: bool,
+    pub is_flat: bool,
+    pub has_death_location: bool,
+    pub portal_cooldown: VarInt,
+}
+
 #[ignore]
 #[test]
 fn generate_codec() {
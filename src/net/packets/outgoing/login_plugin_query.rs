@@ -0,0 +1,19 @@
+use ferrumc_codec::network_types::varint::VarInt;
+use ferrumc_macros::NetEncode;
+
+/// Asks the client something on a plugin channel during login, which it has to answer with a
+/// [LoginPluginResponse](crate::net::packets::incoming::login_plugin_response::LoginPluginResponse).
+/// Vanilla clients always say they don't understand, it's meant for proxies.
+///
+/// Called Login Plugin Request in the protocol, not to be confused with
+/// [LoginPluginRequest](crate::net::packets::outgoing::login_plugin_request::LoginPluginRequest).
+#[derive(NetEncode)]
+pub struct LoginPluginQuery {
+    #[encode(default = VarInt::from(0x04))]
+    pub packet_id: VarInt,
+    /// Echoed back in the response.
+    pub message_id: VarInt,
+    pub channel: String,
+    #[encode(raw_bytes(prepend_length = false))]
+    pub data: Vec<u8>,
+}
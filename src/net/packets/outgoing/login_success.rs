@@ -9,17 +9,49 @@ pub struct LoginSuccess {
     pub packet_id: VarInt,
     pub uuid: Vec<u8>,
     pub username: String,
-    // Just set this to 0
-    pub property_count: VarInt,
-    // TODO: Figure out how what in the everloving fuck this is
+    /// The player's skin and cape, when they're known.
+    #[encode(prefixed)]
     pub properties: Vec<Property>,
 }
 
-#[derive(NetEncode)]
+#[derive(NetEncode, Debug, Clone, PartialEq)]
 pub struct Property {
     pub name: String,
     pub value: String,
-    pub is_signed: bool,
-    // Only if is_signed is true
-    pub signature: String,
+    #[encode(prefixed)]
+    pub signature: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use ferrumc_codec::enc::NetEncode;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_prefixed_encode() {
+        let property = Property {
+            name: "a".to_string(),
+            value: "b".to_string(),
+            signature: None,
+        };
+        let mut data = Vec::new();
+        property.net_encode(&mut data).await.unwrap();
+        assert_eq!(data, vec![1, b'a', 1, b'b', 0]);
+
+        let signed = Property {
+            signature: Some("c".to_string()),
+            ..property.clone()
+        };
+        let mut data = Vec::new();
+        LoginSuccess::new_auto(vec![], "d".to_string(), vec![property, signed])
+            .net_encode(&mut data)
+            .await
+            .unwrap();
+        // Length, packet id, name, then the 2 properties
+        assert_eq!(
+            data,
+            vec![16, 0x02, 1, b'd', 2, 1, b'a', 1, b'b', 0, 1, b'a', 1, b'b', 1, 1, b'c']
+        );
+    }
 }
@@ -0,0 +1,27 @@
+use ferrumc_codec::network_types::varint::VarInt;
+use ferrumc_macros::NetEncode;
+
+use crate::utils::text_component::TextComponent;
+
+/// Opens a window on the client, e.g. a chest's. What's in it is sent after with
+/// [SetContainerContent](crate::net::packets::outgoing::set_container_content::SetContainerContent).
+#[derive(NetEncode)]
+pub struct OpenScreen {
+    #[encode(default = VarInt::from(0x30))]
+    pub packet_id: VarInt,
+    pub window_id: VarInt,
+    /// From the `minecraft:menu` registry.
+    pub window_type: VarInt,
+    /// A JSON text component.
+    pub title: String,
+}
+
+impl OpenScreen {
+    pub fn new(window_id: u8, window_type: i32, title: &TextComponent) -> Self {
+        Self::new_auto(
+            VarInt::new(window_id as i32),
+            VarInt::new(window_type),
+            title.to_json(),
+        )
+    }
+}
@@ -0,0 +1,14 @@
+use ferrumc_codec::network_types::varint::VarInt;
+use ferrumc_macros::NetEncode;
+
+use crate::utils::encoding::position::Position;
+
+/// Opens the sign editor, e.g. after the player places a sign. The text is sent back with
+/// [UpdateSign](crate::net::packets::incoming::update_sign::UpdateSign).
+#[derive(NetEncode)]
+pub struct OpenSignEditor {
+    #[encode(default = VarInt::from(0x31))]
+    pub packet_id: VarInt,
+    pub location: Position,
+    pub is_front_text: bool,
+}
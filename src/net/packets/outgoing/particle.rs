@@ -0,0 +1,69 @@
+use ferrumc_codec::network_types::varint::VarInt;
+use ferrumc_macros::NetEncode;
+
+/// Shows some particles, scattered around a position.
+#[derive(NetEncode)]
+pub struct Particle {
+    #[encode(default = VarInt::from(0x26))]
+    pub packet_id: VarInt,
+    /// Its ID in the particle type registry.
+    pub particle_id: VarInt,
+    /// Shows them from up to 512 blocks away instead of 32, and even with particles turned down.
+    pub long_distance: bool,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    /// How far they're scattered on each axis, times a random number from a normal distribution.
+    /// With a count of 0 it's their direction instead, times `max_speed`.
+    pub offset_x: f32,
+    pub offset_y: f32,
+    pub offset_z: f32,
+    pub max_speed: f32,
+    pub count: i32,
+    pub data: ParticleData,
+}
+
+/// What particles like `minecraft:dust` or `minecraft:block` need to know besides where they are.
+#[derive(NetEncode, Clone, Copy, Debug, PartialEq)]
+pub enum ParticleData {
+    /// Most particles.
+    Empty,
+    /// A block state, for `minecraft:block`, `minecraft:block_marker` and `minecraft:falling_dust`.
+    Block(VarInt),
+    /// For `minecraft:dust`. The color's channels are from 0 to 1.
+    Dust {
+        red: f32,
+        green: f32,
+        blue: f32,
+        scale: f32,
+    },
+    /// The angle it's turned by in radians, for `minecraft:sculk_charge`.
+    SculkCharge(f32),
+    /// The ticks before it appears, for `minecraft:shriek`.
+    Shriek(VarInt),
+}
+
+impl Particle {
+    pub fn new(
+        particle_id: i32,
+        data: ParticleData,
+        position: (f64, f64, f64),
+        offset: (f32, f32, f32),
+        max_speed: f32,
+        count: i32,
+    ) -> Self {
+        Self::new_auto(
+            VarInt::new(particle_id),
+            false,
+            position.0,
+            position.1,
+            position.2,
+            offset.0,
+            offset.1,
+            offset.2,
+            max_speed,
+            count,
+            data,
+        )
+    }
+}
@@ -0,0 +1,25 @@
+use ferrumc_codec::network_types::varint::VarInt;
+use ferrumc_macros::NetEncode;
+
+use crate::utils::components::abilities::Abilities;
+
+/// Tells the client what the player can do, e.g. fly, and whether they're flying.
+#[derive(NetEncode)]
+pub struct PlayerAbilitiesOut {
+    #[encode(default = VarInt::from(0x34))]
+    pub packet_id: VarInt,
+    /// See [Abilities::flags].
+    pub flags: u8,
+    pub flying_speed: f32,
+    pub fov_modifier: f32,
+}
+
+impl PlayerAbilitiesOut {
+    pub fn new(abilities: &Abilities) -> Self {
+        Self::new_auto(
+            abilities.flags(),
+            abilities.flying_speed,
+            abilities.walking_speed,
+        )
+    }
+}
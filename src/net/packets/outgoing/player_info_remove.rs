@@ -0,0 +1,18 @@
+use ferrumc_codec::network_types::varint::VarInt;
+
+use ferrumc_macros::NetEncode;
+
+/// Takes players out of the client's player list, and so the tab list.
+#[derive(NetEncode)]
+pub struct PlayerInfoRemove {
+    #[encode(default = VarInt::from(0x39))]
+    pub packet_id: VarInt,
+    pub count: VarInt,
+    pub uuids: Vec<u128>,
+}
+
+impl PlayerInfoRemove {
+    pub fn new(uuids: Vec<u128>) -> Self {
+        Self::new_auto(VarInt::new(uuids.len() as i32), uuids)
+    }
+}
@@ -1,42 +1,161 @@
-#![allow(dead_code)]
 use ferrumc_codec::network_types::varint::VarInt;
 use ferrumc_macros::NetEncode;
 
+use crate::net::packets::outgoing::login_success::Property;
+use crate::utils::text_component::TextComponent;
+
+/// Adds players to the client's player list, or updates them.
+///
+/// `actions` is a bitmask of [actions], and every entry in `players` has to carry
+/// the data for each of them, in the order of the bits.
 #[derive(NetEncode)]
 pub struct PlayerInfoUpdatePacket {
-    actions: u8,
-    number_of_players: VarInt,
-    players: Vec<PlayerInfo>
+    #[encode(default = VarInt::from(0x3A))]
+    pub packet_id: VarInt,
+    pub actions: u8,
+    pub number_of_players: VarInt,
+    pub players: Vec<PlayerInfo>,
+}
+
+pub mod actions {
+    pub const ADD_PLAYER: u8 = 0x01;
+    pub const INITIALIZE_CHAT: u8 = 0x02;
+    pub const UPDATE_GAME_MODE: u8 = 0x04;
+    pub const UPDATE_LISTED: u8 = 0x08;
+    pub const UPDATE_LATENCY: u8 = 0x10;
+    pub const UPDATE_DISPLAY_NAME: u8 = 0x20;
 }
 
 #[derive(NetEncode)]
-struct PlayerInfo {
-    uuid: u128,
-    actions: Vec<Action>
+pub struct PlayerInfo {
+    pub uuid: u128,
+    pub actions: Vec<Action>,
 }
 
 #[derive(NetEncode)]
-enum Action {
+pub enum Action {
     AddPlayer(AddPlayer),
-    InitializeChat(InitializeChat),
+    UpdateGameMode(VarInt),
+    UpdateListed(bool),
+    /// In milliseconds.
+    UpdateLatency(VarInt),
+    /// Whether there's a name, then the name as JSON. Without one the username is shown.
+    UpdateDisplayName(bool, Option<String>),
 }
 
 #[derive(NetEncode)]
-struct AddPlayer {
-    name: String,
-    number_of_properties: VarInt,
-    properties: Vec<Property>,
+pub struct AddPlayer {
+    pub name: String,
+    #[encode(prefixed)]
+    pub properties: Vec<Property>,
 }
 
-#[derive(NetEncode)]
-struct Property {
-    name: String,
-    value: String,
-    is_signed: bool,
-    signature: Option<String>,
+/// Everything the client is told about a player in the tab list.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlayerListEntry {
+    pub uuid: u128,
+    pub username: String,
+    pub properties: Vec<Property>,
+    pub game_mode: u8,
+    /// In milliseconds.
+    pub latency: i32,
+    pub display_name: Option<TextComponent>,
 }
 
-#[derive(NetEncode)]
-struct InitializeChat {
-    message: String,
-}
\ No newline at end of file
+impl PlayerInfoUpdatePacket {
+    fn with_actions(actions: u8, players: Vec<PlayerInfo>) -> Self {
+        Self::new_auto(actions, VarInt::new(players.len() as i32), players)
+    }
+
+    /// Adds players to the client's player list, and shows them in the tab list.
+    pub fn add_players(entries: &[PlayerListEntry]) -> Self {
+        let players = entries
+            .iter()
+            .map(|entry| PlayerInfo {
+                uuid: entry.uuid,
+                actions: vec![
+                    Action::AddPlayer(AddPlayer {
+                        name: entry.username.clone(),
+                        properties: entry.properties.clone(),
+                    }),
+                    Action::UpdateGameMode(VarInt::new(entry.game_mode as i32)),
+                    Action::UpdateListed(true),
+                    Action::UpdateLatency(VarInt::new(entry.latency)),
+                    display_name(&entry.display_name),
+                ],
+            })
+            .collect();
+
+        Self::with_actions(
+            actions::ADD_PLAYER
+                | actions::UPDATE_GAME_MODE
+                | actions::UPDATE_LISTED
+                | actions::UPDATE_LATENCY
+                | actions::UPDATE_DISPLAY_NAME,
+            players,
+        )
+    }
+
+    pub fn update_game_mode(uuid: u128, game_mode: u8) -> Self {
+        let player = PlayerInfo {
+            uuid,
+            actions: vec![Action::UpdateGameMode(VarInt::new(game_mode as i32))],
+        };
+        Self::with_actions(actions::UPDATE_GAME_MODE, vec![player])
+    }
+
+    /// Every player's latency, in milliseconds.
+    pub fn update_latency(latencies: &[(u128, i32)]) -> Self {
+        let players = latencies
+            .iter()
+            .map(|&(uuid, latency)| PlayerInfo {
+                uuid,
+                actions: vec![Action::UpdateLatency(VarInt::new(latency))],
+            })
+            .collect();
+        Self::with_actions(actions::UPDATE_LATENCY, players)
+    }
+
+    pub fn update_display_name(uuid: u128, name: &Option<TextComponent>) -> Self {
+        let player = PlayerInfo {
+            uuid,
+            actions: vec![display_name(name)],
+        };
+        Self::with_actions(actions::UPDATE_DISPLAY_NAME, vec![player])
+    }
+}
+
+fn display_name(name: &Option<TextComponent>) -> Action {
+    Action::UpdateDisplayName(name.is_some(), name.as_ref().map(TextComponent::to_json))
+}
+
+#[cfg(test)]
+mod tests {
+    use ferrumc_codec::enc::NetEncode;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_add_players() {
+        let entry = PlayerListEntry {
+            uuid: 1,
+            username: "a".to_string(),
+            properties: vec![],
+            game_mode: 1,
+            latency: 200,
+            display_name: None,
+        };
+        let mut data = Vec::new();
+        PlayerInfoUpdatePacket::add_players(&[entry])
+            .net_encode(&mut data)
+            .await
+            .unwrap();
+        let mut expected = vec![0x3A, 0x3D, 1];
+        expected.extend_from_slice(&1u128.to_be_bytes());
+        // Name, no properties, creative, listed, 200ms and no display name
+        expected.extend_from_slice(&[1, b'a', 0, 1, 1, 0xC8, 0x01, 0]);
+        // Starts with the length
+        assert_eq!(data[0] as usize, data.len() - 1);
+        assert_eq!(data[1..], expected);
+    }
+}
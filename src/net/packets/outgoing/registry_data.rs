@@ -0,0 +1,22 @@
+use ferrumc_codec::network_types::varint::VarInt;
+use ferrumc_macros::NetEncode;
+
+use crate::net::protocol::Protocol;
+use crate::world::registry_data::get_registry_data;
+
+/// Sends every registry during configuration, the same codec as in
+/// [LoginPlay](crate::net::packets::outgoing::login_play::LoginPlay). See
+/// [registry_data](crate::world::registry_data).
+#[derive(NetEncode)]
+pub struct RegistryCodecPacket {
+    #[encode(default = VarInt::from(0x05))]
+    pub packet_id: VarInt,
+    #[encode(raw_bytes(prepend_length = false))]
+    pub codec: Vec<u8>,
+}
+
+impl RegistryCodecPacket {
+    pub fn new(protocol: &Protocol) -> Self {
+        Self::new_auto(protocol.network_nbt(get_registry_data().codec().to_vec()))
+    }
+}
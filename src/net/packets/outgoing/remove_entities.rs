@@ -0,0 +1,18 @@
+use ferrumc_codec::network_types::varint::VarInt;
+
+use ferrumc_macros::NetEncode;
+
+/// Despawns entities on the client.
+#[derive(NetEncode)]
+pub struct RemoveEntities {
+    #[encode(default = VarInt::from(0x3E))]
+    pub packet_id: VarInt,
+    pub count: VarInt,
+    pub entity_ids: Vec<VarInt>,
+}
+
+impl RemoveEntities {
+    pub fn new(entity_ids: Vec<VarInt>) -> Self {
+        Self::new_auto(VarInt::new(entity_ids.len() as i32), entity_ids)
+    }
+}
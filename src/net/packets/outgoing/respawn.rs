@@ -0,0 +1,132 @@
+use ferrumc_codec::network_types::varint::VarInt;
+use ferrumc_macros::NetEncode;
+
+use crate::net::protocol::{Protocol, V1_20_2};
+use crate::world::dimension::Dimension;
+
+/// Moves the client to another dimension, or brings it back after dying. It drops every chunk and
+/// entity it has, so the new dimension's chunks have to be sent again after this.
+#[derive(NetEncode)]
+pub struct Respawn {
+    #[encode(default = VarInt::from(0x41))]
+    pub packet_id: VarInt,
+    pub dimension_type: String,
+    pub dimension_name: String,
+    pub hashed_seed: i64,
+    pub game_mode: u8,
+    /// -1 if there wasn't one.
+    pub previous_game_mode: i8,
+    pub is_debug: bool,
+    pub is_flat: bool,
+    /// Bit 0 keeps the player's attributes and bit 1 its metadata, see [data_kept]. Only set
+    /// before [1.20.2](V1_20_2), which moved it to [Respawn::data_kept_last].
+    pub data_kept: Option<u8>,
+    pub has_death_location: bool,
+    pub portal_cooldown: VarInt,
+    /// [Respawn::data_kept], from 1.20.2 on.
+    pub data_kept_last: Option<u8>,
+}
+
+pub mod data_kept {
+    pub const ATTRIBUTES: u8 = 0x01;
+    pub const METADATA: u8 = 0x02;
+    pub const ALL: u8 = ATTRIBUTES | METADATA;
+}
+
+impl Respawn {
+    /// Changing dimension without dying, so everything about the player is kept. The seed hash
+    /// is the same as in [LoginPlay](super::login_play::LoginPlay).
+    pub fn change_dimension(
+        dimension: Dimension,
+        hashed_seed: i64,
+        game_mode: u8,
+        protocol: &Protocol,
+    ) -> Self {
+        Self::in_dimension(dimension, hashed_seed, game_mode, data_kept::ALL, protocol)
+    }
+
+    /// Coming back after dying, as a new player.
+    pub fn after_death(
+        dimension: Dimension,
+        hashed_seed: i64,
+        game_mode: u8,
+        protocol: &Protocol,
+    ) -> Self {
+        Self::in_dimension(dimension, hashed_seed, game_mode, 0, protocol)
+    }
+
+    fn in_dimension(
+        dimension: Dimension,
+        hashed_seed: i64,
+        game_mode: u8,
+        kept: u8,
+        protocol: &Protocol,
+    ) -> Self {
+        let (data_kept, data_kept_last) = if protocol.at_least(V1_20_2) {
+            (None, Some(kept))
+        } else {
+            (Some(kept), None)
+        };
+        Self::new_auto(
+            dimension.id().to_string(),
+            dimension.id().to_string(),
+            hashed_seed,
+            game_mode,
+            -1,
+            false,
+            false,
+            data_kept,
+            false,
+            VarInt::new(0),
+            data_kept_last,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ferrumc_codec::enc::NetEncode;
+
+    use super::*;
+    use crate::net::protocol::{NATIVE_PROTOCOL, PROTOCOL_1_20_2};
+
+    #[tokio::test]
+    async fn test_encode() {
+        let mut data = Vec::new();
+        Respawn::change_dimension(Dimension::Nether, 2, 1, &NATIVE_PROTOCOL)
+            .net_encode(&mut data)
+            .await
+            .unwrap();
+        // Hashed seed, game mode, previous game mode, is debug, is flat, data kept, has death
+        // location and portal cooldown
+        assert_eq!(data[data.len() - 15..data.len() - 7], 2i64.to_be_bytes());
+        assert_eq!(
+            data[data.len() - 7..],
+            [1, 0xFF, 0, 0, data_kept::ALL, 0, 0]
+        );
+
+        // Data kept went last in 1.20.2
+        let mut data = Vec::new();
+        Respawn::change_dimension(Dimension::Nether, 2, 1, &PROTOCOL_1_20_2)
+            .net_encode(&mut data)
+            .await
+            .unwrap();
+        assert_eq!(data[data.len() - 15..data.len() - 7], 2i64.to_be_bytes());
+        assert_eq!(
+            data[data.len() - 7..],
+            [1, 0xFF, 0, 0, 0, 0, data_kept::ALL]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_encode_after_death() {
+        let mut data = Vec::new();
+        Respawn::after_death(Dimension::Overworld, 2, 0, &NATIVE_PROTOCOL)
+            .net_encode(&mut data)
+            .await
+            .unwrap();
+        // Nothing is kept after dying
+        assert_eq!(data[data.len() - 15..data.len() - 7], 2i64.to_be_bytes());
+        assert_eq!(data[data.len() - 7..], [0, 0xFF, 0, 0, 0, 0, 0]);
+    }
+}
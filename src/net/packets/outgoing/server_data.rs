@@ -0,0 +1,64 @@
+use ferrumc_codec::network_types::varint::VarInt;
+use ferrumc_macros::NetEncode;
+
+use crate::utils::text_component::TextComponent;
+
+/// The MOTD and icon clients show for the server once they've joined, and whether it enforces
+/// secure chat, which hides the warning that chat messages can't be verified.
+#[derive(NetEncode)]
+pub struct ServerData {
+    #[encode(default = VarInt::from(0x45))]
+    pub packet_id: VarInt,
+    /// A JSON text component.
+    pub motd: String,
+    /// The icon as a PNG.
+    #[encode(prefixed)]
+    pub icon: Option<Icon>,
+    pub enforces_secure_chat: bool,
+}
+
+#[derive(NetEncode)]
+pub struct Icon {
+    #[encode(prefixed)]
+    pub png: Vec<u8>,
+}
+
+impl ServerData {
+    pub fn new(motd: &TextComponent, icon: Option<Vec<u8>>, enforces_secure_chat: bool) -> Self {
+        Self::new_auto(
+            motd.to_json(),
+            icon.map(|png| Icon { png }),
+            enforces_secure_chat,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ferrumc_codec::enc::NetEncode;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_encode() {
+        let motd = TextComponent::text("Hi");
+        let mut data = Vec::new();
+        ServerData::new(&motd, Some(vec![1, 2]), true)
+            .net_encode(&mut data)
+            .await
+            .unwrap();
+        let json = motd.to_json();
+        // Length, packet id, then the MOTD's length
+        assert_eq!(data[..3], [json.len() as u8 + 7, 0x45, json.len() as u8]);
+        // Has icon, its length, the icon and enforces secure chat
+        assert_eq!(data[3 + json.len()..], [1, 2, 1, 2, 1]);
+
+        let mut data = Vec::new();
+        ServerData::new(&motd, None, false)
+            .net_encode(&mut data)
+            .await
+            .unwrap();
+        assert_eq!(data[..3], [json.len() as u8 + 4, 0x45, json.len() as u8]);
+        assert_eq!(data[3 + json.len()..], [0, 0]);
+    }
+}
@@ -0,0 +1,19 @@
+use ferrumc_codec::network_types::varint::VarInt;
+use ferrumc_macros::NetEncode;
+
+use crate::utils::text_component::TextComponent;
+
+/// Shows text above the hotbar for a few seconds.
+#[derive(NetEncode)]
+pub struct SetActionBarText {
+    #[encode(default = VarInt::from(0x46))]
+    pub packet_id: VarInt,
+    /// A JSON text component.
+    pub text: String,
+}
+
+impl SetActionBarText {
+    pub fn new(text: &TextComponent) -> Self {
+        Self::new_auto(text.to_json())
+    }
+}
@@ -0,0 +1,13 @@
+use ferrumc_codec::network_types::varint::VarInt;
+
+use ferrumc_macros::NetEncode;
+
+/// Sent by the server during login to enable compression. Every packet after this one,
+/// in both directions, uses the compressed packet format.
+#[derive(NetEncode)]
+pub struct SetCompression {
+    #[encode(default = VarInt::from(0x03))]
+    pub packet_id: VarInt,
+    /// Packets at least this many bytes long are compressed.
+    pub threshold: VarInt,
+}
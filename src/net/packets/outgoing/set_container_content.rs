@@ -0,0 +1,64 @@
+use ferrumc_codec::network_types::varint::VarInt;
+use ferrumc_macros::NetEncode;
+
+use crate::utils::components::inventory::Inventory;
+use crate::utils::encoding::slot::{ItemStack, Slot};
+use crate::world::containers::ContainerType;
+
+/// Replaces every slot of a window, e.g. the player's inventory when they join.
+#[derive(NetEncode)]
+pub struct SetContainerContent {
+    #[encode(default = VarInt::from(0x12))]
+    pub packet_id: VarInt,
+    /// 0 is the player's inventory.
+    pub window_id: u8,
+    pub state_id: VarInt,
+    pub count: VarInt,
+    pub slots: Vec<Slot>,
+    /// The item on the cursor.
+    pub carried_item: Slot,
+}
+
+impl SetContainerContent {
+    /// Everything in the player's inventory, at its current [state id](Inventory::state_id).
+    pub fn player_inventory(inventory: &Inventory) -> Self {
+        let slots: Vec<Slot> = (0..Inventory::SLOTS)
+            .map(|slot| Slot {
+                item: inventory.slots.get(&slot).cloned(),
+            })
+            .collect();
+        Self::new_auto(
+            0,
+            VarInt::new(inventory.state_id),
+            VarInt::new(slots.len() as i32),
+            slots,
+            Slot {
+                item: inventory.carried.clone(),
+            },
+        )
+    }
+
+    /// Everything in an open container's window, the container's slots and the player's.
+    pub fn container(
+        window_id: u8,
+        container: &ContainerType,
+        slots: &[Option<ItemStack>],
+        inventory: &Inventory,
+    ) -> Self {
+        let window = container.window(slots, inventory);
+        let slots: Vec<Slot> = (0..container.window_slots())
+            .map(|slot| Slot {
+                item: window.get(&slot).cloned(),
+            })
+            .collect();
+        Self::new_auto(
+            window_id,
+            VarInt::new(inventory.state_id),
+            VarInt::new(slots.len() as i32),
+            slots,
+            Slot {
+                item: inventory.carried.clone(),
+            },
+        )
+    }
+}
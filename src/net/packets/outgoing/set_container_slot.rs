@@ -0,0 +1,32 @@
+use ferrumc_codec::network_types::varint::VarInt;
+use ferrumc_macros::NetEncode;
+
+use crate::utils::components::inventory::Inventory;
+use crate::utils::encoding::slot::Slot;
+
+/// Changes one slot of a window.
+#[derive(NetEncode)]
+pub struct SetContainerSlot {
+    #[encode(default = VarInt::from(0x14))]
+    pub packet_id: VarInt,
+    /// 0 is the player's inventory. -1 with slot -1 sets the item on the cursor.
+    pub window_id: i8,
+    pub state_id: VarInt,
+    pub slot: i16,
+    pub slot_data: Slot,
+}
+
+impl SetContainerSlot {
+    /// One slot of the player's inventory as the server has it, at its current
+    /// [state id](Inventory::state_id).
+    pub fn player_inventory(inventory: &Inventory, slot: i16) -> Self {
+        Self::new_auto(
+            0,
+            VarInt::new(inventory.state_id),
+            slot,
+            Slot {
+                item: inventory.slots.get(&slot).cloned(),
+            },
+        )
+    }
+}
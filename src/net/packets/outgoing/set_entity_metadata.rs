@@ -0,0 +1,21 @@
+use ferrumc_codec::network_types::varint::VarInt;
+
+use ferrumc_macros::NetEncode;
+
+use crate::utils::encoding::metadata::EntityMetadata;
+
+/// Changes some of an entity's [metadata](EntityMetadata). Entries that aren't sent are left as
+/// they were.
+#[derive(NetEncode)]
+pub struct SetEntityMetadata {
+    #[encode(default = VarInt::from(0x52))]
+    pub packet_id: VarInt,
+    pub entity_id: VarInt,
+    pub metadata: EntityMetadata,
+}
+
+impl SetEntityMetadata {
+    pub fn new(entity_id: usize, metadata: EntityMetadata) -> Self {
+        Self::new_auto(VarInt::new(entity_id as i32), metadata)
+    }
+}
@@ -0,0 +1,24 @@
+use ferrumc_codec::network_types::varint::VarInt;
+use ferrumc_macros::NetEncode;
+
+use crate::net::packets::outgoing::spawn_entity::encode_velocity;
+
+/// Pushes an entity, e.g. when it's knocked back.
+#[derive(NetEncode)]
+pub struct SetEntityVelocity {
+    #[encode(default = VarInt::from(0x54))]
+    pub packet_id: VarInt,
+    pub entity_id: VarInt,
+    /// In 1/8000 of a block per tick.
+    pub x: i16,
+    pub y: i16,
+    pub z: i16,
+}
+
+impl SetEntityVelocity {
+    /// `velocity` is in blocks per tick.
+    pub fn new(entity_id: usize, velocity: (f64, f64, f64)) -> Self {
+        let (x, y, z) = encode_velocity(velocity);
+        Self::new_auto(VarInt::new(entity_id as i32), x, y, z)
+    }
+}
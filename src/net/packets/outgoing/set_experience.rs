@@ -0,0 +1,25 @@
+use ferrumc_codec::network_types::varint::VarInt;
+use ferrumc_macros::NetEncode;
+
+use crate::utils::components::experience::Experience;
+
+/// The player's experience bar and the level shown above it.
+#[derive(NetEncode)]
+pub struct SetExperience {
+    #[encode(default = VarInt::from(0x56))]
+    pub packet_id: VarInt,
+    /// How full the bar is, from 0 to 1.
+    pub experience_bar: f32,
+    pub level: VarInt,
+    pub total_experience: VarInt,
+}
+
+impl SetExperience {
+    pub fn new(experience: &Experience) -> Self {
+        Self::new_auto(
+            experience.progress(),
+            VarInt::new(experience.level),
+            VarInt::new(experience.total),
+        )
+    }
+}
@@ -0,0 +1,21 @@
+use ferrumc_codec::network_types::varint::VarInt;
+use ferrumc_macros::NetEncode;
+
+use crate::utils::components::food::Food;
+use crate::utils::components::health::Health;
+
+/// The player's health and hunger bars. The client shows the death screen at 0 health.
+#[derive(NetEncode)]
+pub struct SetHealth {
+    #[encode(default = VarInt::from(0x57))]
+    pub packet_id: VarInt,
+    pub health: f32,
+    pub food: VarInt,
+    pub saturation: f32,
+}
+
+impl SetHealth {
+    pub fn new(health: &Health, food: &Food) -> Self {
+        Self::new_auto(health.health, VarInt::new(food.level), food.saturation)
+    }
+}
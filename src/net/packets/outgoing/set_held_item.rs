@@ -0,0 +1,11 @@
+use ferrumc_codec::network_types::varint::VarInt;
+use ferrumc_macros::NetEncode;
+
+/// Selects a hotbar slot for the player, e.g. the one they had selected when they last left.
+#[derive(NetEncode)]
+pub struct SetHeldItemOut {
+    #[encode(default = VarInt::from(0x4D))]
+    pub packet_id: VarInt,
+    /// 0 to 8.
+    pub slot: i8,
+}
@@ -0,0 +1,19 @@
+use ferrumc_codec::network_types::varint::VarInt;
+use ferrumc_macros::NetEncode;
+
+use crate::utils::text_component::TextComponent;
+
+/// Sets the text shown below the next title. It isn't shown on its own.
+#[derive(NetEncode)]
+pub struct SetSubtitleText {
+    #[encode(default = VarInt::from(0x5D))]
+    pub packet_id: VarInt,
+    /// A JSON text component.
+    pub text: String,
+}
+
+impl SetSubtitleText {
+    pub fn new(text: &TextComponent) -> Self {
+        Self::new_auto(text.to_json())
+    }
+}
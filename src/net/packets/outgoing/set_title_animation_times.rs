@@ -0,0 +1,13 @@
+use ferrumc_codec::network_types::varint::VarInt;
+use ferrumc_macros::NetEncode;
+
+/// How long titles take to fade in, stay up and fade out, in ticks. Clients keep these until
+/// they're changed or reset.
+#[derive(NetEncode)]
+pub struct SetTitleAnimationTimes {
+    #[encode(default = VarInt::from(0x60))]
+    pub packet_id: VarInt,
+    pub fade_in: i32,
+    pub stay: i32,
+    pub fade_out: i32,
+}
@@ -0,0 +1,19 @@
+use ferrumc_codec::network_types::varint::VarInt;
+use ferrumc_macros::NetEncode;
+
+use crate::utils::text_component::TextComponent;
+
+/// Shows a title in the middle of the screen, along with the last subtitle sent.
+#[derive(NetEncode)]
+pub struct SetTitleText {
+    #[encode(default = VarInt::from(0x5F))]
+    pub packet_id: VarInt,
+    /// A JSON text component.
+    pub text: String,
+}
+
+impl SetTitleText {
+    pub fn new(text: &TextComponent) -> Self {
+        Self::new_auto(text.to_json())
+    }
+}
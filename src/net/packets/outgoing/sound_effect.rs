@@ -0,0 +1,75 @@
+use ferrumc_codec::network_types::varint::VarInt;
+use ferrumc_macros::NetEncode;
+
+/// Plays a sound at a position.
+#[derive(NetEncode)]
+pub struct SoundEffect {
+    #[encode(default = VarInt::from(0x62))]
+    pub packet_id: VarInt,
+    pub sound: SoundId,
+    /// See [SoundCategory](crate::world::sounds::SoundCategory).
+    pub category: VarInt,
+    /// In eighths of a block.
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+    pub volume: f32,
+    pub pitch: f32,
+    /// Picks between a sound's variants, so everyone hears the same one.
+    pub seed: i64,
+}
+
+/// Which sound to play, either by its ID in the sound registry, or by name for ones that aren't
+/// in it, like sounds from resource packs.
+#[derive(NetEncode, Clone, Debug, PartialEq)]
+pub enum SoundId {
+    /// The ID plus one, since 0 means it's named.
+    Registered(VarInt),
+    Named(NamedSound),
+}
+
+#[derive(NetEncode, Clone, Debug, PartialEq)]
+pub struct NamedSound {
+    /// Always 0.
+    pub id: VarInt,
+    pub name: String,
+    /// How far away it can be heard, if not by its volume.
+    #[encode(prefixed)]
+    pub fixed_range: Option<f32>,
+}
+
+impl SoundId {
+    pub fn registered(id: i32) -> Self {
+        SoundId::Registered(VarInt::new(id + 1))
+    }
+
+    pub fn named(name: &str) -> Self {
+        SoundId::Named(NamedSound {
+            id: VarInt::new(0),
+            name: name.to_string(),
+            fixed_range: None,
+        })
+    }
+}
+
+impl SoundEffect {
+    pub fn new(
+        sound: SoundId,
+        category: i32,
+        position: (f64, f64, f64),
+        volume: f32,
+        pitch: f32,
+        seed: i64,
+    ) -> Self {
+        Self::new_auto(
+            sound,
+            VarInt::new(category),
+            (position.0 * 8.0) as i32,
+            (position.1 * 8.0) as i32,
+            (position.2 * 8.0) as i32,
+            volume,
+            pitch,
+            seed,
+        )
+    }
+}
@@ -0,0 +1,97 @@
+use ferrumc_codec::network_types::angle::Angle;
+use ferrumc_codec::network_types::varint::VarInt;
+
+use ferrumc_macros::NetEncode;
+
+/// Spawns any non-player entity for the client, and players too since 1.20.2.
+#[derive(NetEncode)]
+pub struct SpawnEntity {
+    #[encode(default = VarInt::from(0x01))]
+    pub packet_id: VarInt,
+    pub entity_id: VarInt,
+    pub uuid: u128,
+    /// The id from the `minecraft:entity_type` registry.
+    pub entity_type: VarInt,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    pub pitch: Angle,
+    pub yaw: Angle,
+    pub head_yaw: Angle,
+    /// Meaning depends on the entity type, e.g. the block state of a falling block.
+    pub data: VarInt,
+    pub velocity_x: i16,
+    pub velocity_y: i16,
+    pub velocity_z: i16,
+}
+
+/// Ids in the `minecraft:entity_type` registry.
+pub mod entity_types {
+    pub const ARROW: i32 = 3;
+    pub const EXPERIENCE_ORB: i32 = 34;
+    pub const ITEM: i32 = 54;
+    pub const LIGHTNING_BOLT: i32 = 59;
+    pub const SNOWBALL: i32 = 92;
+    pub const PLAYER: i32 = 122;
+}
+
+/// Velocity is sent in 1/8000ths of a block per tick, and clients cap it at this many blocks.
+const MAX_VELOCITY: f64 = 3.9;
+
+impl SpawnEntity {
+    /// A player, for clients that don't have
+    /// [SpawnPlayer](crate::net::packets::outgoing::spawn_player::SpawnPlayer). The player has to
+    /// be in the client's player info list first, same as with that.
+    pub fn player(entity_id: VarInt, uuid: u128, x: f64, y: f64, z: f64, yaw: Angle, pitch: Angle) -> Self {
+        Self::new_auto(
+            entity_id,
+            uuid,
+            VarInt::new(entity_types::PLAYER),
+            x,
+            y,
+            z,
+            pitch,
+            yaw,
+            yaw,
+            VarInt::new(0),
+            0,
+            0,
+            0,
+        )
+    }
+
+    /// Any other entity, with what it's spawned with depending on its type.
+    pub fn object(
+        entity_id: usize,
+        uuid: u128,
+        entity_type: i32,
+        (x, y, z): (f64, f64, f64),
+        (yaw, pitch): (f32, f32),
+        data: i32,
+        velocity: (f64, f64, f64),
+    ) -> Self {
+        let (velocity_x, velocity_y, velocity_z) = encode_velocity(velocity);
+        let (yaw, pitch) = (Angle::from_degrees(yaw), Angle::from_degrees(pitch));
+        Self::new_auto(
+            VarInt::new(entity_id as i32),
+            uuid,
+            VarInt::new(entity_type),
+            x,
+            y,
+            z,
+            pitch,
+            yaw,
+            yaw,
+            VarInt::new(data),
+            velocity_x,
+            velocity_y,
+            velocity_z,
+        )
+    }
+}
+
+/// A velocity in blocks per tick, as it's sent.
+pub fn encode_velocity((x, y, z): (f64, f64, f64)) -> (i16, i16, i16) {
+    let encode = |v: f64| (v.clamp(-MAX_VELOCITY, MAX_VELOCITY) * 8000.0) as i16;
+    (encode(x), encode(y), encode(z))
+}
@@ -0,0 +1,23 @@
+use ferrumc_codec::network_types::varint::VarInt;
+use ferrumc_macros::NetEncode;
+
+/// Spawns an experience orb for the client. Orbs don't have a uuid, so they can't be spawned with
+/// [SpawnEntity](crate::net::packets::outgoing::spawn_entity::SpawnEntity).
+#[derive(NetEncode)]
+pub struct SpawnExperienceOrb {
+    #[encode(default = VarInt::from(0x02))]
+    pub packet_id: VarInt,
+    pub entity_id: VarInt,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    /// How many points it's worth, which only changes how big it looks.
+    pub count: i16,
+}
+
+impl SpawnExperienceOrb {
+    pub fn new(entity_id: usize, (x, y, z): (f64, f64, f64), count: i32) -> Self {
+        let count = count.clamp(0, i16::MAX as i32) as i16;
+        Self::new_auto(VarInt::new(entity_id as i32), x, y, z, count)
+    }
+}
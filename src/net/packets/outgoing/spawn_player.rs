@@ -0,0 +1,19 @@
+use ferrumc_codec::network_types::angle::Angle;
+use ferrumc_codec::network_types::varint::VarInt;
+
+use ferrumc_macros::NetEncode;
+
+/// Spawns another player for the client. The player has to be in the client's player info list
+/// ([crate::net::packets::outgoing::player_info_update::PlayerInfoUpdatePacket]) first, or it's ignored.
+#[derive(NetEncode)]
+pub struct SpawnPlayer {
+    #[encode(default = VarInt::from(0x03))]
+    pub packet_id: VarInt,
+    pub entity_id: VarInt,
+    pub uuid: u128,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    pub yaw: Angle,
+    pub pitch: Angle,
+}
@@ -0,0 +1,27 @@
+use ferrumc_codec::network_types::varint::VarInt;
+use ferrumc_macros::NetEncode;
+
+use crate::utils::text_component::TextComponent;
+
+/// A chat message that isn't from a player, so the client doesn't try to verify a signature.
+///
+/// `content` is a JSON text component. With `overlay` set, it's shown above the hotbar instead of in chat.
+#[derive(NetEncode)]
+pub struct SystemChatMessage {
+    #[encode(default = VarInt::from(0x64))]
+    pub packet_id: VarInt,
+    pub content: String,
+    pub overlay: bool,
+}
+
+impl SystemChatMessage {
+    /// A plain text message in the chat box.
+    pub fn text(text: &str) -> Self {
+        Self::component(&TextComponent::text(text))
+    }
+
+    /// A formatted message in the chat box.
+    pub fn component(component: &TextComponent) -> Self {
+        Self::new_auto(component.to_json(), false)
+    }
+}
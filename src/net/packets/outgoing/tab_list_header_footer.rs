@@ -0,0 +1,21 @@
+use ferrumc_codec::network_types::varint::VarInt;
+
+use ferrumc_macros::NetEncode;
+
+use crate::utils::text_component::TextComponent;
+
+/// The text above and below the players in the tab list. Empty text hides it.
+#[derive(NetEncode)]
+pub struct TabListHeaderFooter {
+    #[encode(default = VarInt::from(0x65))]
+    pub packet_id: VarInt,
+    /// JSON text components.
+    pub header: String,
+    pub footer: String,
+}
+
+impl TabListHeaderFooter {
+    pub fn new(header: &TextComponent, footer: &TextComponent) -> Self {
+        Self::new_auto(header.to_json(), footer.to_json())
+    }
+}
@@ -0,0 +1,23 @@
+use ferrumc_codec::network_types::varint::VarInt;
+use ferrumc_macros::NetEncode;
+
+/// Shows an item flying into whoever picked it up. The client removes the item entity itself once
+/// it's all been taken.
+#[derive(NetEncode)]
+pub struct TakeItemEntity {
+    #[encode(default = VarInt::from(0x67))]
+    pub packet_id: VarInt,
+    pub collected_entity_id: VarInt,
+    pub collector_entity_id: VarInt,
+    pub pickup_item_count: VarInt,
+}
+
+impl TakeItemEntity {
+    pub fn new(collected: usize, collector: usize, count: i8) -> Self {
+        Self::new_auto(
+            VarInt::new(collected as i32),
+            VarInt::new(collector as i32),
+            VarInt::new(count as i32),
+        )
+    }
+}
@@ -0,0 +1,19 @@
+use ferrumc_codec::network_types::angle::Angle;
+use ferrumc_codec::network_types::varint::VarInt;
+
+use ferrumc_macros::NetEncode;
+
+/// Moves an entity to an absolute position. Used when it moved too far for
+/// [crate::net::packets::outgoing::update_entity_position::UpdateEntityPosition].
+#[derive(NetEncode)]
+pub struct TeleportEntity {
+    #[encode(default = VarInt::from(0x68))]
+    pub packet_id: VarInt,
+    pub entity_id: VarInt,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    pub yaw: Angle,
+    pub pitch: Angle,
+    pub on_ground: bool,
+}
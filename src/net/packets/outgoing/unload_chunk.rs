@@ -0,0 +1,53 @@
+use ferrumc_macros::NetEncode;
+use ferrumc_codec::network_types::varint::VarInt;
+
+use crate::net::protocol::{Protocol, V1_20_2};
+
+/// Tells the client to forget about a chunk, usually because it's now out of view distance.
+#[derive(NetEncode)]
+pub struct UnloadChunk {
+    #[encode(default = VarInt::from(0x1E))]
+    pub packet_id: VarInt,
+    /// The chunk's X, or its Z from [1.20.2](V1_20_2) on, which reads the two as one long with Z
+    /// first.
+    pub first: i32,
+    pub second: i32,
+}
+
+impl UnloadChunk {
+    pub fn new(chunk_x: i32, chunk_z: i32, protocol: &Protocol) -> Self {
+        if protocol.at_least(V1_20_2) {
+            Self::new_auto(chunk_z, chunk_x)
+        } else {
+            Self::new_auto(chunk_x, chunk_z)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ferrumc_codec::enc::NetEncode;
+
+    use super::*;
+    use crate::net::protocol::{NATIVE_PROTOCOL, PROTOCOL_1_20_2};
+
+    #[tokio::test]
+    async fn test_encode_native() {
+        let mut data = Vec::new();
+        UnloadChunk::new(1, 2, &NATIVE_PROTOCOL)
+            .net_encode(&mut data)
+            .await
+            .unwrap();
+        assert_eq!(data, [9, 0x1E, 0, 0, 0, 1, 0, 0, 0, 2]);
+    }
+
+    #[tokio::test]
+    async fn test_encode_1_20_2() {
+        let mut data = Vec::new();
+        UnloadChunk::new(1, 2, &PROTOCOL_1_20_2)
+            .net_encode(&mut data)
+            .await
+            .unwrap();
+        assert_eq!(data, [9, 0x1E, 0, 0, 0, 2, 0, 0, 0, 1]);
+    }
+}
@@ -0,0 +1,43 @@
+use ferrumc_codec::network_types::angle::Angle;
+use ferrumc_codec::network_types::varint::VarInt;
+
+use ferrumc_macros::NetEncode;
+
+/// Moves an entity by less than 8 blocks in each direction.
+///
+/// Deltas are `(current * 32 - previous * 32) * 128`, so in 1/4096ths of a block.
+#[derive(NetEncode)]
+pub struct UpdateEntityPosition {
+    #[encode(default = VarInt::from(0x2B))]
+    pub packet_id: VarInt,
+    pub entity_id: VarInt,
+    pub delta_x: i16,
+    pub delta_y: i16,
+    pub delta_z: i16,
+    pub on_ground: bool,
+}
+
+/// Same as [UpdateEntityPosition], but also sets the rotation.
+#[derive(NetEncode)]
+pub struct UpdateEntityPositionAndRotation {
+    #[encode(default = VarInt::from(0x2C))]
+    pub packet_id: VarInt,
+    pub entity_id: VarInt,
+    pub delta_x: i16,
+    pub delta_y: i16,
+    pub delta_z: i16,
+    pub yaw: Angle,
+    pub pitch: Angle,
+    pub on_ground: bool,
+}
+
+/// Only rotates an entity's body. The head is rotated with [crate::net::packets::outgoing::set_head_rotation::SetHeadRotation].
+#[derive(NetEncode)]
+pub struct UpdateEntityRotation {
+    #[encode(default = VarInt::from(0x2D))]
+    pub packet_id: VarInt,
+    pub entity_id: VarInt,
+    pub yaw: Angle,
+    pub pitch: Angle,
+    pub on_ground: bool,
+}
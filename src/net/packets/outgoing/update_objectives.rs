@@ -0,0 +1,59 @@
+use ferrumc_codec::network_types::varint::VarInt;
+use ferrumc_macros::NetEncode;
+
+use crate::net::scoreboard::Objective;
+
+/// Creates, removes or renames a scoreboard objective.
+#[derive(NetEncode)]
+pub struct UpdateObjectives {
+    #[encode(default = VarInt::from(0x58))]
+    pub packet_id: VarInt,
+    pub objective_name: String,
+    /// One of [modes].
+    pub mode: u8,
+    /// Left out when it's being removed.
+    pub display: Option<ObjectiveDisplay>,
+}
+
+#[derive(NetEncode)]
+pub struct ObjectiveDisplay {
+    /// A JSON text component.
+    pub display_name: String,
+    /// See [RenderType](crate::net::scoreboard::RenderType).
+    pub render_type: VarInt,
+}
+
+pub mod modes {
+    pub const CREATE: u8 = 0;
+    pub const REMOVE: u8 = 1;
+    pub const UPDATE: u8 = 2;
+}
+
+impl UpdateObjectives {
+    pub fn create(objective: &Objective) -> Self {
+        Self::new_auto(
+            objective.name.clone(),
+            modes::CREATE,
+            Some(display(objective)),
+        )
+    }
+
+    pub fn update(objective: &Objective) -> Self {
+        Self::new_auto(
+            objective.name.clone(),
+            modes::UPDATE,
+            Some(display(objective)),
+        )
+    }
+
+    pub fn remove(name: &str) -> Self {
+        Self::new_auto(name.to_string(), modes::REMOVE, None)
+    }
+}
+
+fn display(objective: &Objective) -> ObjectiveDisplay {
+    ObjectiveDisplay {
+        display_name: objective.display_name.to_json(),
+        render_type: VarInt::new(objective.render_type as i32),
+    }
+}
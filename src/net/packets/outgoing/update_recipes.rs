@@ -0,0 +1,135 @@
+use ferrumc_codec::enc::NetEncode;
+use ferrumc_codec::network_types::varint::VarInt;
+use ferrumc_macros::NetEncode;
+use tokio::io::AsyncWrite;
+
+use crate::utils::encoding::slot::{ItemStack, Slot};
+use crate::world::recipes::{Ingredient, Recipe, RecipeBook, RecipeKind};
+
+/// Every recipe the client can show in the recipe book and craft. Sent when the player joins.
+#[derive(NetEncode)]
+pub struct UpdateRecipes {
+    #[encode(default = VarInt::from(0x6D))]
+    pub packet_id: VarInt,
+    pub count: VarInt,
+    pub recipes: Vec<Recipe>,
+}
+
+impl UpdateRecipes {
+    pub fn new(recipes: &RecipeBook) -> Self {
+        Self::new_auto(
+            VarInt::new(recipes.len() as i32),
+            recipes.recipes().to_vec(),
+        )
+    }
+}
+
+impl NetEncode for Recipe {
+    /// The recipe's type and id, then what's in it, which depends on the type.
+    async fn net_encode<T>(&self, bytes: &mut T) -> Result<(), ferrumc_codec::CodecError>
+    where
+        T: AsyncWrite + Unpin,
+    {
+        self.type_name().to_string().net_encode(bytes).await?;
+        self.id.net_encode(bytes).await?;
+        match &self.kind {
+            RecipeKind::Shaped {
+                width,
+                height,
+                ingredients,
+                show_notification,
+            } => {
+                VarInt::new(*width as i32).net_encode(bytes).await?;
+                VarInt::new(*height as i32).net_encode(bytes).await?;
+                self.group.net_encode(bytes).await?;
+                VarInt::new(self.category_id()).net_encode(bytes).await?;
+                for ingredient in ingredients {
+                    encode_ingredient(ingredient, bytes).await?;
+                }
+                self.result().net_encode(bytes).await?;
+                show_notification.net_encode(bytes).await
+            }
+            RecipeKind::Shapeless { ingredients } => {
+                self.group.net_encode(bytes).await?;
+                VarInt::new(self.category_id()).net_encode(bytes).await?;
+                VarInt::new(ingredients.len() as i32)
+                    .net_encode(bytes)
+                    .await?;
+                for ingredient in ingredients {
+                    encode_ingredient(ingredient, bytes).await?;
+                }
+                self.result().net_encode(bytes).await
+            }
+            RecipeKind::Smelting {
+                ingredient,
+                experience,
+                cooking_time,
+            } => {
+                self.group.net_encode(bytes).await?;
+                VarInt::new(self.category_id()).net_encode(bytes).await?;
+                encode_ingredient(ingredient, bytes).await?;
+                self.result().net_encode(bytes).await?;
+                experience.net_encode(bytes).await?;
+                VarInt::new(*cooking_time).net_encode(bytes).await
+            }
+        }
+    }
+}
+
+impl Recipe {
+    fn result(&self) -> Slot {
+        Slot {
+            item: Some(self.result.clone()),
+        }
+    }
+}
+
+/// Each item the ingredient can be, as a slot holding one of it.
+async fn encode_ingredient<T>(
+    ingredient: &Ingredient,
+    bytes: &mut T,
+) -> Result<(), ferrumc_codec::CodecError>
+where
+    T: AsyncWrite + Unpin,
+{
+    VarInt::new(ingredient.0.len() as i32)
+        .net_encode(bytes)
+        .await?;
+    for &id in &ingredient.0 {
+        Slot {
+            item: Some(ItemStack::new(id, 1)),
+        }
+        .net_encode(bytes)
+        .await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_encode_shapeless() {
+        let recipe = Recipe {
+            id: "a:b".to_string(),
+            group: String::new(),
+            category: "redstone".to_string(),
+            kind: RecipeKind::Shapeless {
+                ingredients: vec![Ingredient(vec![1, 2])],
+            },
+            result: ItemStack::new(3, 4),
+        };
+        let mut bytes = Vec::new();
+        recipe.net_encode(&mut bytes).await.unwrap();
+
+        let mut expected = vec![28];
+        expected.extend_from_slice(b"minecraft:crafting_shapeless");
+        expected.extend_from_slice(&[3, b'a', b':', b'b']);
+        // No group, the redstone category and one ingredient of either item
+        expected.extend_from_slice(&[0, 1, 1, 2]);
+        expected.extend_from_slice(&[1, 1, 1, 0, 1, 2, 1, 0]);
+        expected.extend_from_slice(&[1, 3, 4, 0]);
+        assert_eq!(bytes, expected);
+    }
+}
@@ -0,0 +1,36 @@
+use ferrumc_codec::network_types::varint::VarInt;
+use ferrumc_macros::NetEncode;
+
+/// Sets or clears the score an entry has in an objective. Entries are players' usernames, or any
+/// other text for lines that aren't about a player.
+#[derive(NetEncode)]
+pub struct UpdateScore {
+    #[encode(default = VarInt::from(0x5B))]
+    pub packet_id: VarInt,
+    pub entry: String,
+    /// 0 to set the score, 1 to clear it.
+    pub action: VarInt,
+    pub objective_name: String,
+    /// Left out when it's being cleared.
+    pub value: Option<VarInt>,
+}
+
+impl UpdateScore {
+    pub fn set(objective: &str, entry: &str, value: i32) -> Self {
+        Self::new_auto(
+            entry.to_string(),
+            VarInt::new(0),
+            objective.to_string(),
+            Some(VarInt::new(value)),
+        )
+    }
+
+    pub fn reset(objective: &str, entry: &str) -> Self {
+        Self::new_auto(
+            entry.to_string(),
+            VarInt::new(1),
+            objective.to_string(),
+            None,
+        )
+    }
+}
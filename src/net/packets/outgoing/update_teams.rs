@@ -0,0 +1,104 @@
+use ferrumc_codec::network_types::varint::VarInt;
+use ferrumc_macros::NetEncode;
+
+use crate::net::scoreboard::Team;
+
+/// Creates, removes or changes a team, or who's on it.
+#[derive(NetEncode)]
+pub struct UpdateTeams {
+    #[encode(default = VarInt::from(0x5A))]
+    pub packet_id: VarInt,
+    pub team_name: String,
+    /// One of [modes].
+    pub mode: u8,
+    /// Only when it's being created or changed.
+    pub info: Option<TeamInfo>,
+    /// Only when it's being created, or members are joining or leaving.
+    pub members: Option<TeamMembers>,
+}
+
+#[derive(NetEncode)]
+pub struct TeamInfo {
+    /// A JSON text component.
+    pub display_name: String,
+    /// 0x01 for friendly fire, 0x02 to see invisible teammates.
+    pub friendly_flags: u8,
+    pub name_tag_visibility: String,
+    pub collision_rule: String,
+    pub color: VarInt,
+    /// JSON text components, shown before and after members' names.
+    pub prefix: String,
+    pub suffix: String,
+}
+
+#[derive(NetEncode)]
+pub struct TeamMembers {
+    /// Usernames, or whatever other entries are on the team.
+    #[encode(prefixed)]
+    pub members: Vec<String>,
+}
+
+pub mod modes {
+    pub const CREATE: u8 = 0;
+    pub const REMOVE: u8 = 1;
+    pub const UPDATE: u8 = 2;
+    pub const ADD_MEMBERS: u8 = 3;
+    pub const REMOVE_MEMBERS: u8 = 4;
+}
+
+impl UpdateTeams {
+    pub fn create(team: &Team) -> Self {
+        let members = team.members.iter().cloned().collect();
+        Self::new_auto(
+            team.name.clone(),
+            modes::CREATE,
+            Some(info(team)),
+            Some(TeamMembers { members }),
+        )
+    }
+
+    pub fn update(team: &Team) -> Self {
+        Self::new_auto(team.name.clone(), modes::UPDATE, Some(info(team)), None)
+    }
+
+    pub fn remove(name: &str) -> Self {
+        Self::new_auto(name.to_string(), modes::REMOVE, None, None)
+    }
+
+    pub fn add_members(name: &str, members: Vec<String>) -> Self {
+        Self::new_auto(
+            name.to_string(),
+            modes::ADD_MEMBERS,
+            None,
+            Some(TeamMembers { members }),
+        )
+    }
+
+    pub fn remove_members(name: &str, members: Vec<String>) -> Self {
+        Self::new_auto(
+            name.to_string(),
+            modes::REMOVE_MEMBERS,
+            None,
+            Some(TeamMembers { members }),
+        )
+    }
+}
+
+fn info(team: &Team) -> TeamInfo {
+    let mut friendly_flags = 0;
+    if team.friendly_fire {
+        friendly_flags |= 0x01;
+    }
+    if team.see_friendly_invisibles {
+        friendly_flags |= 0x02;
+    }
+    TeamInfo {
+        display_name: team.display_name.to_json(),
+        friendly_flags,
+        name_tag_visibility: team.name_tag_visibility.name_tag_visibility().to_string(),
+        collision_rule: team.collision_rule.collision_rule().to_string(),
+        color: VarInt::new(team.color as i32),
+        prefix: team.prefix.to_json(),
+        suffix: team.suffix.to_json(),
+    }
+}
@@ -0,0 +1,53 @@
+use ferrumc_codec::network_types::varint::VarInt;
+use ferrumc_macros::NetEncode;
+
+use crate::database::world_meta::WorldMeta;
+use crate::world::game_rules;
+
+/// The world's age and the time of day. The client moves its own clock on in between, this just
+/// keeps it in step.
+#[derive(NetEncode)]
+pub struct UpdateTime {
+    #[encode(default = VarInt::from(0x5E))]
+    pub packet_id: VarInt,
+    pub world_age: i64,
+    /// Negative to stop the client's sun moving, while `doDaylightCycle` is off.
+    pub time_of_day: i64,
+}
+
+impl UpdateTime {
+    pub fn new(world_meta: &WorldMeta) -> Self {
+        let mut time_of_day = world_meta.day_time;
+        if !world_meta.game_rule_enabled(&game_rules::DO_DAYLIGHT_CYCLE) {
+            // 0 can't be made negative, so it's sent as -1 like vanilla does
+            time_of_day = match time_of_day {
+                0 => -1,
+                time => -time.abs(),
+            };
+        }
+        Self::new_auto(world_meta.time, time_of_day)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frozen_time() {
+        let mut meta = WorldMeta {
+            time: 100,
+            day_time: 6000,
+            ..Default::default()
+        };
+        assert_eq!(UpdateTime::new(&meta).time_of_day, 6000);
+
+        meta.set_game_rule(&game_rules::DO_DAYLIGHT_CYCLE, "false");
+        let packet = UpdateTime::new(&meta);
+        assert_eq!(packet.world_age, 100);
+        assert_eq!(packet.time_of_day, -6000);
+
+        meta.day_time = 0;
+        assert_eq!(UpdateTime::new(&meta).time_of_day, -1);
+    }
+}
@@ -0,0 +1,191 @@
+//! A profiler for finding out what's making ticks lag, started and stopped with `/profile`.
+//!
+//! While it's running, the [GameLoop](crate::net::systems::game_loop::GameLoop) times each ticked
+//! system and the [scheduled tasks](crate::net::scheduler), and every incoming packet's handler is
+//! timed too. Timings are kept under stacks of names separated by `;`, like `tick;ChunkSender` or
+//! `packets;play;PlayerAction`, so a [Report] can be written as folded stacks for
+//! [flamegraph.pl](https://github.com/brendangregg/FlameGraph) and
+//! [inferno](https://github.com/jonhoo/inferno), or as JSON.
+//!
+//! Nothing is timed while it's stopped, other than checking whether it's running.
+
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use parking_lot::{const_mutex, Mutex};
+use serde::Serialize;
+
+/// The server's profiler.
+pub static PROFILER: Profiler = Profiler::new();
+
+pub struct Profiler {
+    running: AtomicBool,
+    started: Mutex<Option<Instant>>,
+    samples: Mutex<BTreeMap<String, Sample>>,
+}
+
+/// How long everything under one stack took, all together.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct Sample {
+    pub calls: u64,
+    #[serde(rename = "total_us", serialize_with = "micros")]
+    pub total: Duration,
+    #[serde(rename = "max_us", serialize_with = "micros")]
+    pub max: Duration,
+}
+
+impl Profiler {
+    const fn new() -> Self {
+        Self {
+            running: AtomicBool::new(false),
+            started: const_mutex(None),
+            samples: const_mutex(BTreeMap::new()),
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::Relaxed)
+    }
+
+    /// Starts collecting timings, from nothing. Returns false if it was already running.
+    pub fn start(&self) -> bool {
+        let mut started = self.started.lock();
+        if started.is_some() {
+            return false;
+        }
+        self.samples.lock().clear();
+        *started = Some(Instant::now());
+        self.running.store(true, Ordering::Relaxed);
+        true
+    }
+
+    /// Stops collecting timings and returns what was collected, or `None` if it wasn't running.
+    pub fn stop(&self) -> Option<Report> {
+        let started = self.started.lock().take()?;
+        self.running.store(false, Ordering::Relaxed);
+        let samples = std::mem::take(&mut *self.samples.lock());
+        Some(Report {
+            duration: started.elapsed(),
+            samples,
+        })
+    }
+
+    /// Adds a timing under `stack`, if it's running.
+    pub fn record(&self, stack: &str, duration: Duration) {
+        if !self.is_running() {
+            return;
+        }
+        let mut samples = self.samples.lock();
+        let sample = match samples.get_mut(stack) {
+            Some(sample) => sample,
+            None => samples.entry(stack.to_string()).or_default(),
+        };
+        sample.calls += 1;
+        sample.total += duration;
+        sample.max = sample.max.max(duration);
+    }
+
+    /// Runs `future`, timing it under `stack` if the profiler's running.
+    pub async fn profile<F: Future>(&self, stack: &str, future: F) -> F::Output {
+        if !self.is_running() {
+            return future.await;
+        }
+        let start = Instant::now();
+        let output = future.await;
+        self.record(stack, start.elapsed());
+        output
+    }
+}
+
+/// What the profiler collected between starting and stopping.
+#[derive(Debug, Serialize)]
+pub struct Report {
+    #[serde(rename = "duration_ms", serialize_with = "millis")]
+    pub duration: Duration,
+    pub samples: BTreeMap<String, Sample>,
+}
+
+impl Report {
+    /// One line for each stack with how many microseconds were spent in it, which flame graph
+    /// tools read as folded stacks.
+    pub fn folded(&self) -> String {
+        self.samples
+            .iter()
+            .map(|(stack, sample)| format!("{} {}\n", stack, sample.total.as_micros()))
+            .collect()
+    }
+
+    pub fn json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+
+    /// The stacks that took the longest all together, longest first.
+    pub fn slowest(&self, count: usize) -> Vec<(&str, &Sample)> {
+        let mut samples = self
+            .samples
+            .iter()
+            .map(|(stack, sample)| (stack.as_str(), sample))
+            .collect::<Vec<_>>();
+        samples.sort_by_key(|(_, sample)| Reverse(sample.total));
+        samples.truncate(count);
+        samples
+    }
+}
+
+fn micros<S: serde::Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_u128(duration.as_micros())
+}
+
+fn millis<S: serde::Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_u128(duration.as_millis())
+}
+
+/// Times a packet's handler under `packets;<state>;<packet>`. Used by the handlers
+/// [ferrumc_macros::bake_packet_registry] generates.
+pub async fn profile_packet<F: Future>(state: &str, packet: &str, handler: F) -> F::Output {
+    if !PROFILER.is_running() {
+        return handler.await;
+    }
+    let stack = format!("packets;{};{}", state, packet);
+    PROFILER.profile(&stack, handler).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profiler() {
+        let profiler = Profiler::new();
+        profiler.record("tick;Ignored", Duration::from_millis(1));
+        assert!(profiler.stop().is_none());
+
+        assert!(profiler.start());
+        assert!(!profiler.start());
+        profiler.record("tick;ChunkSender", Duration::from_micros(300));
+        profiler.record("tick;ChunkSender", Duration::from_micros(500));
+        profiler.record("packets;play;ChatMessage", Duration::from_micros(50));
+
+        let report = profiler.stop().unwrap();
+        assert!(!profiler.is_running());
+        assert_eq!(
+            report.folded(),
+            "packets;play;ChatMessage 50\ntick;ChunkSender 800\n"
+        );
+        assert_eq!(
+            report.samples["tick;ChunkSender"],
+            Sample {
+                calls: 2,
+                total: Duration::from_micros(800),
+                max: Duration::from_micros(500),
+            }
+        );
+        assert_eq!(report.slowest(1)[0].0, "tick;ChunkSender");
+
+        let json = serde_json::from_str::<serde_json::Value>(&report.json().unwrap()).unwrap();
+        assert_eq!(json["samples"]["tick;ChunkSender"]["total_us"], 800);
+    }
+}
@@ -0,0 +1,256 @@
+//! The protocol versions clients can connect with.
+//!
+//! Packets are written against the server's own version, [NATIVE_PROTOCOL]. For the other
+//! versions:
+//! - Incoming packets are matched on that version's ids by the packet registry, see the `ids` in
+//!   `#[packet(...)]`.
+//! - Outgoing packets get their ids swapped right before they're sent, see [Protocol::remap_frames].
+//!   The NBT in their slots is rewritten there too, since slots are in packets that are encoded once
+//!   for everyone, like entity metadata.
+//! - Packets that changed shape are built for the client's version where they're made, using
+//!   [Protocol::at_least].
+//! - Types that are read differently by version, but are only part of a packet, look up the
+//!   version being read with [decoding_protocol].
+//!
+//! Configuration packets don't exist in 1.20.1, so they're written against 1.20.2's ids instead.
+
+use std::future::Future;
+use std::io::Cursor;
+
+use ferrumc_codec::enc::NetEncode;
+use ferrumc_codec::network_types::varint::VarInt;
+use tracing::trace;
+
+use crate::net::State;
+use crate::utils::encoding::slot::network_slots;
+use crate::utils::prelude::*;
+
+/// 1.20 and 1.20.1.
+pub const V1_20_1: i32 = 763;
+/// 1.20.2, which added the configuration state.
+pub const V1_20_2: i32 = 764;
+
+pub struct Protocol {
+    pub version: i32,
+    pub name: &'static str,
+    /// Turns the id a clientbound packet has in [NATIVE_PROTOCOL] into this version's, or `None`
+    /// if the packet doesn't exist in this version.
+    clientbound_ids: fn(&State, u8) -> Option<u8>,
+}
+
+/// The version the packets are written against.
+pub static NATIVE_PROTOCOL: Protocol = Protocol {
+    version: V1_20_1,
+    name: "1.20.1",
+    clientbound_ids: |_, id| Some(id),
+};
+
+pub static PROTOCOL_1_20_2: Protocol = Protocol {
+    version: V1_20_2,
+    name: "1.20.2",
+    clientbound_ids: clientbound_ids_1_20_2,
+};
+
+/// Every version that can join, oldest first.
+pub static SUPPORTED_PROTOCOLS: [&Protocol; 2] = [&NATIVE_PROTOCOL, &PROTOCOL_1_20_2];
+
+impl Protocol {
+    /// The supported version with this protocol number.
+    pub fn get(version: i32) -> Option<&'static Protocol> {
+        SUPPORTED_PROTOCOLS
+            .iter()
+            .find(|protocol| protocol.version == version)
+            .copied()
+    }
+
+    pub fn is_native(&self) -> bool {
+        self.version == NATIVE_PROTOCOL.version
+    }
+
+    /// Whether this is `version` or newer, for packets that changed in it.
+    pub fn at_least(&self, version: i32) -> bool {
+        self.version >= version
+    }
+
+    pub fn clientbound_id(&self, state: &State, packet_id: u8) -> Option<u8> {
+        (self.clientbound_ids)(state, packet_id)
+    }
+
+    /// Rewrites a buffer of `[length][packet id][data]` frames from [NATIVE_PROTOCOL]'s ids to this
+    /// version's. Packets this version doesn't have are left out.
+    pub async fn remap_frames(&self, state: &State, frames: Vec<u8>) -> Result<Vec<u8>> {
+        if self.is_native() {
+            return Ok(frames);
+        }
+
+        let mut cursor = Cursor::new(frames.as_slice());
+        let mut out = Vec::with_capacity(frames.len());
+
+        while (cursor.position() as usize) < frames.len() {
+            let length = VarInt::read(&mut cursor).await?;
+            let end = cursor.position() as usize + length.get_val() as usize;
+            let packet_id = VarInt::read(&mut cursor).await?.get_val() as u8;
+            let data = frames
+                .get(cursor.position() as usize..end)
+                .ok_or_else(|| Error::Generic("Packet frame is shorter than its length".into()))?;
+            cursor.set_position(end as u64);
+
+            let slots = match state {
+                State::Play if self.nameless_nbt() => network_slots(self, packet_id, data).await?,
+                _ => None,
+            };
+            let data = slots.as_deref().unwrap_or(data);
+
+            let Some(packet_id) = self.clientbound_id(state, packet_id) else {
+                trace!(
+                    "Not sending packet 0x{:02X} in {}, {} doesn't have it",
+                    packet_id,
+                    state,
+                    self.name
+                );
+                continue;
+            };
+
+            let mut header = Vec::new();
+            VarInt::new(packet_id as i32)
+                .net_encode(&mut header)
+                .await?;
+            VarInt::new((header.len() + data.len()) as i32)
+                .net_encode(&mut out)
+                .await?;
+            out.extend_from_slice(&header);
+            out.extend_from_slice(data);
+        }
+
+        Ok(out)
+    }
+
+    /// Since 1.20.2, NBT sent over the network has no name on its root compound.
+    pub fn nameless_nbt(&self) -> bool {
+        self.at_least(V1_20_2)
+    }
+
+    /// See [Protocol::nameless_nbt]. `nbt` is a named root compound, as the encoders write it.
+    pub fn network_nbt(&self, nbt: Vec<u8>) -> Vec<u8> {
+        if !self.nameless_nbt() || nbt.len() < 3 || nbt[0] != 10 {
+            return nbt;
+        }
+        let name_length = u16::from_be_bytes([nbt[1], nbt[2]]) as usize;
+        let mut nameless = vec![10];
+        nameless.extend_from_slice(nbt.get(3 + name_length..).unwrap_or_default());
+        nameless
+    }
+}
+
+tokio::task_local! {
+    static DECODING: &'static Protocol;
+}
+
+/// Runs `decode` knowing it's reading a packet from `protocol_version`, see [decoding_protocol].
+pub async fn decode_as<F: Future>(protocol_version: i32, decode: F) -> F::Output {
+    let protocol = Protocol::get(protocol_version).unwrap_or(&NATIVE_PROTOCOL);
+    DECODING.scope(protocol, decode).await
+}
+
+/// The version of the packet being decoded, or [NATIVE_PROTOCOL] outside of [decode_as].
+pub fn decoding_protocol() -> &'static Protocol {
+    DECODING
+        .try_with(|protocol| *protocol)
+        .unwrap_or(&NATIVE_PROTOCOL)
+}
+
+/// The names of every supported version, e.g. to tell clients on another one what they need.
+pub fn supported_versions() -> String {
+    SUPPORTED_PROTOCOLS
+        .iter()
+        .map(|protocol| protocol.name)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// 1.20.2 added Chunk Batch Start/Finished, Ping Response and Start Configuration to play, which
+/// moved most ids up, and folded Spawn Player into Spawn Entity.
+fn clientbound_ids_1_20_2(state: &State, packet_id: u8) -> Option<u8> {
+    match state {
+        State::Play => match packet_id {
+            0x00..=0x02 => Some(packet_id),
+            // Spawn Player
+            0x03 => None,
+            0x04..=0x0C => Some(packet_id - 1),
+            0x0D..=0x32 => Some(packet_id + 1),
+            0x33..=0x62 => Some(packet_id + 2),
+            0x63..=0x6A => Some(packet_id + 3),
+            // Feature Flags, which is sent during configuration instead
+            0x6B => None,
+            0x6C..=0x6E => Some(packet_id + 2),
+            _ => None,
+        },
+        _ => Some(packet_id),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clientbound_ids_1_20_2() {
+        let ids = |packet_id| PROTOCOL_1_20_2.clientbound_id(&State::Play, packet_id);
+        // Keep Alive
+        assert_eq!(ids(0x23), Some(0x24));
+        // Login (play)
+        assert_eq!(ids(0x28), Some(0x29));
+        // Synchronize Player Position
+        assert_eq!(ids(0x3C), Some(0x3E));
+        // System Chat Message
+        assert_eq!(ids(0x64), Some(0x67));
+        // Update Tags
+        assert_eq!(ids(0x6E), Some(0x70));
+        assert_eq!(ids(0x03), None);
+        // Configuration is already written against 1.20.2
+        assert_eq!(
+            PROTOCOL_1_20_2.clientbound_id(&State::Configuration, 0x02),
+            Some(0x02)
+        );
+        // Nothing changed before configuration
+        assert_eq!(
+            PROTOCOL_1_20_2.clientbound_id(&State::Login, 0x02),
+            Some(0x02)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_remap_frames() {
+        // Keep Alive, then Spawn Player, which 1.20.2 doesn't have
+        let frames = vec![3, 0x23, 1, 2, 2, 0x03, 9];
+        let remapped = PROTOCOL_1_20_2
+            .remap_frames(&State::Play, frames.clone())
+            .await
+            .unwrap();
+        assert_eq!(remapped, vec![3, 0x24, 1, 2]);
+
+        let native = NATIVE_PROTOCOL
+            .remap_frames(&State::Play, frames.clone())
+            .await
+            .unwrap();
+        assert_eq!(native, frames);
+    }
+
+    #[test]
+    fn test_network_nbt() {
+        // A root compound named "a" holding nothing
+        let named = vec![10, 0, 1, b'a', 0];
+        assert_eq!(PROTOCOL_1_20_2.network_nbt(named.clone()), vec![10, 0]);
+        assert_eq!(NATIVE_PROTOCOL.network_nbt(named.clone()), named);
+    }
+
+    #[test]
+    fn test_get() {
+        assert_eq!(
+            Protocol::get(V1_20_2).map(|protocol| protocol.name),
+            Some("1.20.2")
+        );
+        assert!(Protocol::get(766).is_none());
+        assert_eq!(supported_versions(), "1.20.1, 1.20.2");
+    }
+}
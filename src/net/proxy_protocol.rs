@@ -0,0 +1,152 @@
+//! HAProxy's PROXY protocol, which load balancers use to pass on the address a TCP connection
+//! really came from. The header is the first thing on the connection, before the handshake, in
+//! either the text (v1) or binary (v2) format.
+//!
+//! See <https://www.haproxy.org/download/2.9/doc/proxy-protocol.txt>.
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use crate::utils::prelude::*;
+
+/// What a v1 header starts with.
+const V1_PREFIX: &str = "PROXY ";
+/// The longest a v1 header can be, including the `\r\n`.
+const V1_MAX_LENGTH: usize = 107;
+/// What a v2 header starts with.
+const V2_SIGNATURE: [u8; 12] = *b"\r\n\r\n\0\r\nQUIT\n";
+/// The v2 command for connections the proxy made itself, e.g. health checks.
+const V2_LOCAL: u8 = 0x0;
+const V2_PROXY: u8 = 0x1;
+const V2_TCP4: u8 = 0x11;
+const V2_TCP6: u8 = 0x21;
+
+/// Reads the header off the start of the connection, returning the client's address. That's
+/// `None` if the proxy doesn't know it or connected on its own behalf, in which case the
+/// connection's own address stands.
+pub async fn read_header<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Option<SocketAddr>> {
+    let first = reader.read_u8().await?;
+    match first {
+        b'P' => {
+            let mut line = vec![first];
+            while !line.ends_with(b"\r\n") {
+                if line.len() >= V1_MAX_LENGTH {
+                    return Err(invalid("the v1 header is too long"));
+                }
+                line.push(reader.read_u8().await?);
+            }
+            parse_v1(&line)
+        }
+        b'\r' => {
+            let mut signature = [0u8; 12];
+            signature[0] = first;
+            reader.read_exact(&mut signature[1..]).await?;
+            if signature != V2_SIGNATURE {
+                return Err(invalid("the v2 signature is wrong"));
+            }
+
+            let version_command = reader.read_u8().await?;
+            let family = reader.read_u8().await?;
+            let length = reader.read_u16().await? as usize;
+            let mut addresses = vec![0u8; length];
+            reader.read_exact(&mut addresses).await?;
+            parse_v2(version_command, family, &addresses)
+        }
+        _ => Err(invalid("the connection didn't start with one")),
+    }
+}
+
+/// `PROXY TCP4 <source> <destination> <source port> <destination port>\r\n`, or
+/// `PROXY UNKNOWN ...\r\n`.
+fn parse_v1(line: &[u8]) -> Result<Option<SocketAddr>> {
+    let line = std::str::from_utf8(line)
+        .map_err(|_| invalid("the v1 header isn't text"))?
+        .strip_suffix("\r\n")
+        .and_then(|line| line.strip_prefix(V1_PREFIX))
+        .ok_or_else(|| invalid("the v1 header isn't framed properly"))?;
+
+    let parts: Vec<&str> = line.split(' ').collect();
+    match parts.as_slice() {
+        ["UNKNOWN", ..] => Ok(None),
+        ["TCP4" | "TCP6", source, _, source_port, _] => {
+            let ip: IpAddr = source
+                .parse()
+                .map_err(|_| invalid(&format!("invalid source address {}", source)))?;
+            let port: u16 = source_port
+                .parse()
+                .map_err(|_| invalid(&format!("invalid source port {}", source_port)))?;
+            Ok(Some(SocketAddr::new(ip, port)))
+        }
+        _ => Err(invalid(&format!("unknown v1 header \"{}\"", line))),
+    }
+}
+
+fn parse_v2(version_command: u8, family: u8, addresses: &[u8]) -> Result<Option<SocketAddr>> {
+    if version_command >> 4 != 2 {
+        return Err(invalid(&format!("unknown version {}", version_command >> 4)));
+    }
+    match version_command & 0x0F {
+        V2_LOCAL => return Ok(None),
+        V2_PROXY => {}
+        command => return Err(invalid(&format!("unknown command {}", command))),
+    }
+
+    // Source address, destination address, source port, destination port. Anything after that
+    // is extensions, which aren't needed.
+    let address = match family {
+        V2_TCP4 if addresses.len() >= 12 => {
+            let ip: [u8; 4] = addresses[0..4].try_into().unwrap();
+            let port = u16::from_be_bytes([addresses[8], addresses[9]]);
+            SocketAddr::new(IpAddr::V4(Ipv4Addr::from(ip)), port)
+        }
+        V2_TCP6 if addresses.len() >= 36 => {
+            let ip: [u8; 16] = addresses[0..16].try_into().unwrap();
+            let port = u16::from_be_bytes([addresses[32], addresses[33]]);
+            SocketAddr::new(IpAddr::V6(Ipv6Addr::from(ip)), port)
+        }
+        // UDP and unix sockets, which can't be a Minecraft client
+        _ => return Ok(None),
+    };
+    Ok(Some(address))
+}
+
+fn invalid(reason: &str) -> Error {
+    Error::InvalidProxyHeader(reason.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_read_v1() {
+        let mut stream = Cursor::new(b"PROXY TCP4 192.168.0.1 192.168.0.11 56324 25565\r\n\x10".to_vec());
+        let address = read_header(&mut stream).await.unwrap();
+        assert_eq!(address, Some("192.168.0.1:56324".parse().unwrap()));
+        // The handshake is left where it was
+        assert_eq!(stream.read_u8().await.unwrap(), 0x10);
+
+        let mut stream = Cursor::new(b"PROXY UNKNOWN\r\n".to_vec());
+        assert_eq!(read_header(&mut stream).await.unwrap(), None);
+
+        let mut stream = Cursor::new(vec![0x10, 0x00]);
+        assert!(read_header(&mut stream).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_read_v2() {
+        let mut header = V2_SIGNATURE.to_vec();
+        header.extend_from_slice(&[0x21, V2_TCP4, 0, 12]);
+        header.extend_from_slice(&[10, 0, 0, 2, 10, 0, 0, 1]);
+        header.extend_from_slice(&[0xDC, 0x04, 0x63, 0xDD]);
+        let address = read_header(&mut Cursor::new(header)).await.unwrap();
+        assert_eq!(address, Some("10.0.0.2:56324".parse().unwrap()));
+
+        let mut header = V2_SIGNATURE.to_vec();
+        header.extend_from_slice(&[0x20, 0x00, 0, 0]);
+        assert_eq!(read_header(&mut Cursor::new(header)).await.unwrap(), None);
+    }
+}
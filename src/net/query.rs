@@ -0,0 +1,234 @@
+//! The GameSpy4 Query protocol, which server lists use over UDP to get more than the status ping
+//! gives them: the map, the plugins and every player's name.
+//!
+//! Clients first ask for a challenge token, then send it back with each stat request. Tokens are
+//! tied to the client's address and expire after [TOKEN_LIFETIME], like vanilla.
+
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+
+use dashmap::DashMap;
+use rand::random;
+
+const MAGIC: [u8; 2] = [0xFE, 0xFD];
+const TYPE_HANDSHAKE: u8 = 9;
+const TYPE_STAT: u8 = 0;
+
+/// Only the lower 4 bits of each byte of the session id are used.
+const SESSION_ID_MASK: i32 = 0x0F0F0F0F;
+
+pub const TOKEN_LIFETIME: Duration = Duration::from_secs(30);
+
+pub const GAME_TYPE: &str = "SMP";
+pub const GAME_ID: &str = "MINECRAFT";
+
+#[derive(Debug, PartialEq)]
+pub enum QueryRequest {
+    Handshake { session_id: i32 },
+    BasicStat { session_id: i32, token: i32 },
+    FullStat { session_id: i32, token: i32 },
+}
+
+impl QueryRequest {
+    /// Parses a request datagram, or `None` if it isn't one.
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        let rest = data.strip_prefix(&MAGIC)?;
+        let (&kind, rest) = rest.split_first()?;
+        let session_id = i32::from_be_bytes(rest.get(..4)?.try_into().ok()?) & SESSION_ID_MASK;
+        let rest = &rest[4..];
+
+        match kind {
+            TYPE_HANDSHAKE => Some(Self::Handshake { session_id }),
+            TYPE_STAT => {
+                let token = i32::from_be_bytes(rest.get(..4)?.try_into().ok()?);
+                // Full stat requests are padded with 4 more bytes
+                match rest.len() {
+                    4 => Some(Self::BasicStat { session_id, token }),
+                    8 => Some(Self::FullStat { session_id, token }),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Everything a stat response reports about the server.
+pub struct ServerInfo {
+    pub motd: String,
+    pub version: String,
+    pub plugins: String,
+    pub map: String,
+    pub max_players: i32,
+    pub players: Vec<String>,
+    pub host_ip: String,
+    pub host_port: u16,
+}
+
+pub fn handshake_response(session_id: i32, token: i32) -> Vec<u8> {
+    let mut response = header(TYPE_HANDSHAKE, session_id);
+    push_string(&mut response, &token.to_string());
+    response
+}
+
+pub fn basic_stat_response(session_id: i32, info: &ServerInfo) -> Vec<u8> {
+    let mut response = header(TYPE_STAT, session_id);
+    push_string(&mut response, &info.motd);
+    push_string(&mut response, GAME_TYPE);
+    push_string(&mut response, &info.map);
+    push_string(&mut response, &info.players.len().to_string());
+    push_string(&mut response, &info.max_players.to_string());
+    // The only little endian value in the protocol
+    response.extend_from_slice(&info.host_port.to_le_bytes());
+    push_string(&mut response, &info.host_ip);
+    response
+}
+
+pub fn full_stat_response(session_id: i32, info: &ServerInfo) -> Vec<u8> {
+    let mut response = header(TYPE_STAT, session_id);
+    response.extend_from_slice(b"splitnum\0\x80\0");
+
+    let values = [
+        ("hostname", info.motd.clone()),
+        ("gametype", GAME_TYPE.to_string()),
+        ("game_id", GAME_ID.to_string()),
+        ("version", info.version.clone()),
+        ("plugins", info.plugins.clone()),
+        ("map", info.map.clone()),
+        ("numplayers", info.players.len().to_string()),
+        ("maxplayers", info.max_players.to_string()),
+        ("hostport", info.host_port.to_string()),
+        ("hostip", info.host_ip.clone()),
+    ];
+    for (key, value) in values {
+        push_string(&mut response, key);
+        push_string(&mut response, &value);
+    }
+    response.push(0);
+
+    response.extend_from_slice(b"\x01player_\0\0");
+    for player in &info.players {
+        push_string(&mut response, player);
+    }
+    response.push(0);
+    response
+}
+
+fn header(kind: u8, session_id: i32) -> Vec<u8> {
+    let mut header = vec![kind];
+    header.extend_from_slice(&session_id.to_be_bytes());
+    header
+}
+
+/// Strings are null terminated, so any nulls in them would cut them short.
+fn push_string(buf: &mut Vec<u8>, value: &str) {
+    buf.extend(value.bytes().filter(|&byte| byte != 0));
+    buf.push(0);
+}
+
+/// The challenge tokens handed out to each address.
+#[derive(Default)]
+pub struct ChallengeTokens {
+    tokens: DashMap<SocketAddr, (i32, Instant)>,
+}
+
+impl ChallengeTokens {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Hands out a new token to an address, replacing any it had.
+    pub fn issue(&self, address: SocketAddr) -> i32 {
+        // Kept positive, since it's sent as a decimal string and some clients choke on the sign
+        let token = random::<i32>() & i32::MAX;
+        self.tokens.insert(address, (token, Instant::now()));
+        token
+    }
+
+    pub fn is_valid(&self, address: SocketAddr, token: i32) -> bool {
+        self.tokens
+            .get(&address)
+            .is_some_and(|entry| entry.0 == token && entry.1.elapsed() < TOKEN_LIFETIME)
+    }
+
+    /// Forgets the expired tokens, so addresses that never come back don't pile up.
+    pub fn prune(&self) {
+        self.tokens.retain(|_, (_, issued)| issued.elapsed() < TOKEN_LIFETIME);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info() -> ServerInfo {
+        ServerInfo {
+            motd: "A FerrumC Server".to_string(),
+            version: "1.20.1".to_string(),
+            plugins: "FerrumC".to_string(),
+            map: "world".to_string(),
+            max_players: 20,
+            players: vec!["Steve".to_string(), "Alex".to_string()],
+            host_ip: "127.0.0.1".to_string(),
+            host_port: 25565,
+        }
+    }
+
+    #[test]
+    fn test_parse_request() {
+        let handshake = [0xFE, 0xFD, 9, 0x00, 0x00, 0x00, 0x01];
+        assert_eq!(QueryRequest::parse(&handshake), Some(QueryRequest::Handshake { session_id: 1 }));
+
+        let basic = [0xFE, 0xFD, 0, 0xFF, 0x00, 0x00, 0x01, 0x00, 0x91, 0x29, 0x5B];
+        assert_eq!(
+            QueryRequest::parse(&basic),
+            Some(QueryRequest::BasicStat {
+                session_id: 0x0F000001,
+                token: 9513307
+            })
+        );
+
+        let full = [&basic[..], &[0, 0, 0, 0]].concat();
+        assert!(matches!(QueryRequest::parse(&full), Some(QueryRequest::FullStat { .. })));
+
+        assert_eq!(QueryRequest::parse(&[0xFE, 0xFD, 9, 0]), None);
+        assert_eq!(QueryRequest::parse(&[0x00, 0xFD, 9, 0, 0, 0, 1]), None);
+    }
+
+    #[test]
+    fn test_handshake_response() {
+        assert_eq!(handshake_response(1, 9513307), b"\x09\0\0\0\x019513307\0");
+    }
+
+    #[test]
+    fn test_basic_stat_response() {
+        let response = basic_stat_response(1, &info());
+        let expected = [
+            &b"\0\0\0\0\x01A FerrumC Server\0SMP\0world\x002\x0020\0"[..],
+            &25565u16.to_le_bytes(),
+            b"127.0.0.1\0",
+        ]
+        .concat();
+        assert_eq!(response, expected);
+    }
+
+    #[test]
+    fn test_full_stat_response() {
+        let response = full_stat_response(1, &info());
+        assert!(response.starts_with(b"\0\0\0\0\x01splitnum\0\x80\0hostname\0A FerrumC Server\0"));
+        assert!(response.ends_with(b"hostip\x00127.0.0.1\0\0\x01player_\0\0Steve\0Alex\0\0"));
+    }
+
+    #[test]
+    fn test_challenge_tokens() {
+        let tokens = ChallengeTokens::new();
+        let address = "127.0.0.1:5000".parse().unwrap();
+        let other = "127.0.0.2:5000".parse().unwrap();
+
+        let token = tokens.issue(address);
+        assert!(token >= 0);
+        assert!(tokens.is_valid(address, token));
+        assert!(!tokens.is_valid(address, token.wrapping_add(1)));
+        assert!(!tokens.is_valid(other, token));
+    }
+}
@@ -0,0 +1,189 @@
+//! Tasks that run on a later tick, once or over and over, for work that has to line up with the
+//! game rather than the clock.
+//!
+//! The [GameLoop](crate::net::systems::game_loop::GameLoop) runs the tasks that are due after its
+//! systems, one after the other, so their packets go out with the rest of the tick's. A task holds
+//! the tick up for as long as it runs, so anything slow should be spawned from it instead.
+//!
+//! Every task gets a [TaskHandle] to cancel it with. [Plugins](crate::plugins) have to cancel theirs
+//! when they're unloaded.
+
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+
+type TaskFuture = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// Cancels a scheduled task. Cancelling one that's already run does nothing.
+#[derive(Debug, Clone, Default)]
+pub struct TaskHandle {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl TaskHandle {
+    /// Stops the task from running again. A task that's already running finishes first.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+enum Task {
+    Once(TaskFuture),
+    Repeating {
+        interval: u64,
+        task: Arc<dyn Fn() -> TaskFuture + Send + Sync>,
+    },
+}
+
+struct ScheduledTask {
+    task: Task,
+    handle: TaskHandle,
+}
+
+#[derive(Default)]
+pub struct Scheduler {
+    /// By the tick they're due on, then the order they were scheduled in.
+    tasks: Mutex<BTreeMap<(u64, u64), ScheduledTask>>,
+    next_id: AtomicU64,
+    /// The tick that's running, or last ran. Delays count from here.
+    tick: AtomicU64,
+}
+
+impl Scheduler {
+    /// Runs `task` after `delay` ticks. A delay of 0 is the same as 1, the next tick.
+    pub fn run_later<Fut>(&self, delay: u64, task: Fut) -> TaskHandle
+    where
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.schedule(delay, Task::Once(Box::pin(task)))
+    }
+
+    /// Runs the future `task` makes every `interval` ticks, starting `interval` ticks from now. An
+    /// interval of 0 is the same as 1, every tick.
+    pub fn run_repeating<F, Fut>(&self, interval: u64, task: F) -> TaskHandle
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let interval = interval.max(1);
+        let task = Task::Repeating {
+            interval,
+            task: Arc::new(move || Box::pin(task()) as TaskFuture),
+        };
+        self.schedule(interval, task)
+    }
+
+    /// How many tasks are waiting to run, not counting cancelled ones.
+    pub fn pending(&self) -> usize {
+        self.tasks
+            .lock()
+            .values()
+            .filter(|scheduled| !scheduled.handle.is_cancelled())
+            .count()
+    }
+
+    fn schedule(&self, delay: u64, task: Task) -> TaskHandle {
+        let due = self.tick.load(Ordering::Relaxed) + delay.max(1);
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let handle = TaskHandle::default();
+        let scheduled = ScheduledTask {
+            task,
+            handle: handle.clone(),
+        };
+        self.tasks.lock().insert((due, id), scheduled);
+        handle
+    }
+
+    /// Runs every task that's due on `tick`, in the order they were due. Repeating tasks are
+    /// scheduled again before any of them run, so they keep to their interval.
+    pub async fn run_tick(&self, tick: u64) {
+        self.tick.store(tick, Ordering::Relaxed);
+        let due = {
+            let mut tasks = self.tasks.lock();
+            let later = tasks.split_off(&(tick + 1, 0));
+            std::mem::replace(&mut *tasks, later)
+        };
+
+        let mut futures = Vec::with_capacity(due.len());
+        for ((_, id), scheduled) in due {
+            let ScheduledTask { task, handle } = scheduled;
+            if handle.is_cancelled() {
+                continue;
+            }
+            match task {
+                Task::Once(future) => futures.push((future, handle)),
+                Task::Repeating { interval, task } => {
+                    futures.push((task(), handle.clone()));
+                    let task = Task::Repeating { interval, task };
+                    let scheduled = ScheduledTask { task, handle };
+                    self.tasks.lock().insert((tick + interval, id), scheduled);
+                }
+            }
+        }
+
+        for (future, handle) in futures {
+            // An earlier task this tick may have cancelled it
+            if !handle.is_cancelled() {
+                future.await;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicUsize;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_run_later() {
+        let scheduler = Scheduler::default();
+        let runs = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&runs);
+        scheduler.run_later(2, async move {
+            counter.fetch_add(1, Ordering::Relaxed);
+        });
+
+        scheduler.run_tick(1).await;
+        assert_eq!(runs.load(Ordering::Relaxed), 0);
+        scheduler.run_tick(2).await;
+        assert_eq!(runs.load(Ordering::Relaxed), 1);
+        scheduler.run_tick(3).await;
+        assert_eq!(runs.load(Ordering::Relaxed), 1);
+        assert_eq!(scheduler.pending(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_run_repeating_and_cancel() {
+        let scheduler = Scheduler::default();
+        let runs = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&runs);
+        let handle = scheduler.run_repeating(2, move || {
+            let counter = Arc::clone(&counter);
+            async move {
+                counter.fetch_add(1, Ordering::Relaxed);
+            }
+        });
+
+        for tick in 1..=6 {
+            scheduler.run_tick(tick).await;
+        }
+        assert_eq!(runs.load(Ordering::Relaxed), 3);
+
+        handle.cancel();
+        for tick in 7..=10 {
+            scheduler.run_tick(tick).await;
+        }
+        assert_eq!(runs.load(Ordering::Relaxed), 3);
+        assert_eq!(scheduler.pending(), 0);
+    }
+}
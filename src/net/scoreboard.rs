@@ -0,0 +1,500 @@
+//! Scoreboards: objectives, which keep a score for players (or any other text) and can be shown
+//! in the sidebar, the tab list or below players' names, and teams, which color and prefix their
+//! members' names.
+//!
+//! Everything lives in the [ScoreboardManager], which sends changes to everyone as they happen and
+//! the whole scoreboard to players as they join. Everyone sees the same scoreboard, and nothing's
+//! saved yet, so it's empty again after a restart.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use parking_lot::RwLock;
+
+use crate::net::packets::outgoing::display_objective::DisplayObjective;
+use crate::net::packets::outgoing::update_objectives::UpdateObjectives;
+use crate::net::packets::outgoing::update_score::UpdateScore;
+use crate::net::packets::outgoing::update_teams::UpdateTeams;
+use crate::net::utils::broadcast::broadcast_packet;
+use crate::net::utils::packet_queue::PacketQueue;
+use crate::state::GlobalState;
+use crate::utils::prelude::*;
+use crate::utils::text_component::TextComponent;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Objective {
+    pub name: String,
+    pub display_name: TextComponent,
+    pub render_type: RenderType,
+    /// By entry, which is a player's username or any other text.
+    pub scores: BTreeMap<String, i32>,
+}
+
+/// How scores are shown in the tab list. The sidebar and below names always show numbers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenderType {
+    Integer = 0,
+    Hearts = 1,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DisplaySlot {
+    List,
+    Sidebar,
+    BelowName,
+    /// The sidebar as players on a team of this color see it, instead of the usual one.
+    TeamSidebar(TeamColor),
+}
+
+impl DisplaySlot {
+    pub fn id(&self) -> u8 {
+        match self {
+            DisplaySlot::List => 0,
+            DisplaySlot::Sidebar => 1,
+            DisplaySlot::BelowName => 2,
+            // There's no reset sidebar
+            DisplaySlot::TeamSidebar(color) => 3 + (*color as u8).min(15),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TeamColor {
+    Black = 0,
+    DarkBlue = 1,
+    DarkGreen = 2,
+    DarkAqua = 3,
+    DarkRed = 4,
+    DarkPurple = 5,
+    Gold = 6,
+    Gray = 7,
+    DarkGray = 8,
+    Blue = 9,
+    Green = 10,
+    Aqua = 11,
+    Red = 12,
+    LightPurple = 13,
+    Yellow = 14,
+    White = 15,
+    /// Names keep their usual color.
+    Reset = 21,
+}
+
+/// Whose name tags players on the team show, or who they push around.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TeamRule {
+    Always,
+    Never,
+    /// Only to, or only, players on other teams.
+    OtherTeams,
+    /// Only to, or only, players on the same team.
+    OwnTeam,
+}
+
+impl TeamRule {
+    pub fn name_tag_visibility(&self) -> &'static str {
+        match self {
+            TeamRule::Always => "always",
+            TeamRule::Never => "never",
+            TeamRule::OtherTeams => "hideForOwnTeam",
+            TeamRule::OwnTeam => "hideForOtherTeams",
+        }
+    }
+
+    pub fn collision_rule(&self) -> &'static str {
+        match self {
+            TeamRule::Always => "always",
+            TeamRule::Never => "never",
+            TeamRule::OtherTeams => "pushOtherTeams",
+            TeamRule::OwnTeam => "pushOwnTeam",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Team {
+    pub name: String,
+    pub display_name: TextComponent,
+    /// Shown before and after members' names.
+    pub prefix: TextComponent,
+    pub suffix: TextComponent,
+    pub color: TeamColor,
+    pub friendly_fire: bool,
+    pub see_friendly_invisibles: bool,
+    pub name_tag_visibility: TeamRule,
+    pub collision_rule: TeamRule,
+    /// Usernames, or any other entries. An entry can only be on one team.
+    pub members: BTreeSet<String>,
+}
+
+impl Team {
+    /// A team with vanilla's defaults and no one on it.
+    pub fn new(name: impl Into<String>) -> Self {
+        let name = name.into();
+        Self {
+            display_name: TextComponent::text(name.clone()),
+            name,
+            prefix: TextComponent::text(""),
+            suffix: TextComponent::text(""),
+            color: TeamColor::Reset,
+            friendly_fire: true,
+            see_friendly_invisibles: true,
+            name_tag_visibility: TeamRule::Always,
+            collision_rule: TeamRule::Always,
+            members: BTreeSet::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct Scoreboard {
+    objectives: BTreeMap<String, Objective>,
+    /// The name of the objective in each slot.
+    displayed: BTreeMap<DisplaySlot, String>,
+    teams: BTreeMap<String, Team>,
+}
+
+impl Scoreboard {
+    fn objective(&mut self, name: &str) -> Result<&mut Objective> {
+        self.objectives
+            .get_mut(name)
+            .ok_or_else(|| Error::Generic(format!("Unknown objective {}", name)))
+    }
+
+    fn team(&mut self, name: &str) -> Result<&mut Team> {
+        self.teams
+            .get_mut(name)
+            .ok_or_else(|| Error::Generic(format!("Unknown team {}", name)))
+    }
+
+    fn add_objective(&mut self, objective: Objective) -> Result<()> {
+        if self.objectives.contains_key(&objective.name) {
+            return Err(Error::Generic(format!(
+                "An objective already exists by the name {}",
+                objective.name
+            )));
+        }
+        self.objectives.insert(objective.name.clone(), objective);
+        Ok(())
+    }
+
+    fn remove_objective(&mut self, name: &str) -> Result<()> {
+        self.objectives
+            .remove(name)
+            .ok_or_else(|| Error::Generic(format!("Unknown objective {}", name)))?;
+        // Clients take it out of its slots themselves
+        self.displayed.retain(|_, displayed| displayed != name);
+        Ok(())
+    }
+
+    fn add_team(&mut self, mut team: Team) -> Result<()> {
+        if self.teams.contains_key(&team.name) {
+            return Err(Error::Generic(format!(
+                "A team already exists by the name {}",
+                team.name
+            )));
+        }
+        let members = std::mem::take(&mut team.members);
+        let name = team.name.clone();
+        self.teams.insert(name.clone(), team);
+        self.join_team(&name, members.into_iter().collect())
+    }
+
+    /// Puts entries on a team, taking them off whichever team they were on. Clients do the same
+    /// when they're told.
+    fn join_team(&mut self, name: &str, members: Vec<String>) -> Result<()> {
+        self.team(name)?;
+        for team in self.teams.values_mut() {
+            for member in &members {
+                team.members.remove(member);
+            }
+        }
+        self.team(name)?.members.extend(members);
+        Ok(())
+    }
+}
+
+/// The scoreboard everyone sees.
+#[derive(Default)]
+pub struct ScoreboardManager {
+    scoreboard: RwLock<Scoreboard>,
+}
+
+impl ScoreboardManager {
+    pub fn objective(&self, name: &str) -> Option<Objective> {
+        self.scoreboard.read().objectives.get(name).cloned()
+    }
+
+    pub fn team(&self, name: &str) -> Option<Team> {
+        self.scoreboard.read().teams.get(name).cloned()
+    }
+
+    /// The team an entry is on, if any.
+    pub fn team_of(&self, entry: &str) -> Option<Team> {
+        let scoreboard = self.scoreboard.read();
+        scoreboard
+            .teams
+            .values()
+            .find(|team| team.members.contains(entry))
+            .cloned()
+    }
+
+    pub fn score(&self, objective: &str, entry: &str) -> Option<i32> {
+        let scoreboard = self.scoreboard.read();
+        scoreboard
+            .objectives
+            .get(objective)?
+            .scores
+            .get(entry)
+            .copied()
+    }
+
+    /// Adds an objective with no scores. It isn't shown until it's put in a [DisplaySlot].
+    pub async fn add_objective(
+        &self,
+        state: &GlobalState,
+        name: &str,
+        display_name: TextComponent,
+        render_type: RenderType,
+    ) -> Result<()> {
+        let objective = Objective {
+            name: name.to_string(),
+            display_name,
+            render_type,
+            scores: BTreeMap::new(),
+        };
+        let packet = UpdateObjectives::create(&objective);
+        self.scoreboard.write().add_objective(objective)?;
+        broadcast_packet(packet, state).await
+    }
+
+    /// Changes how an objective's shown, keeping its scores.
+    pub async fn update_objective(
+        &self,
+        state: &GlobalState,
+        name: &str,
+        display_name: TextComponent,
+        render_type: RenderType,
+    ) -> Result<()> {
+        let packet = {
+            let mut scoreboard = self.scoreboard.write();
+            let objective = scoreboard.objective(name)?;
+            objective.display_name = display_name;
+            objective.render_type = render_type;
+            UpdateObjectives::update(objective)
+        };
+        broadcast_packet(packet, state).await
+    }
+
+    pub async fn remove_objective(&self, state: &GlobalState, name: &str) -> Result<()> {
+        self.scoreboard.write().remove_objective(name)?;
+        broadcast_packet(UpdateObjectives::remove(name), state).await
+    }
+
+    /// Shows an objective in a slot, or clears it with `None`.
+    pub async fn set_display(
+        &self,
+        state: &GlobalState,
+        slot: DisplaySlot,
+        objective: Option<&str>,
+    ) -> Result<()> {
+        {
+            let mut scoreboard = self.scoreboard.write();
+            match objective {
+                Some(name) => {
+                    scoreboard.objective(name)?;
+                    scoreboard.displayed.insert(slot, name.to_string());
+                }
+                None => {
+                    scoreboard.displayed.remove(&slot);
+                }
+            }
+        }
+        let name = objective.unwrap_or_default().to_string();
+        broadcast_packet(DisplayObjective::new_auto(slot.id(), name), state).await
+    }
+
+    pub async fn set_score(
+        &self,
+        state: &GlobalState,
+        objective: &str,
+        entry: &str,
+        value: i32,
+    ) -> Result<()> {
+        self.scoreboard
+            .write()
+            .objective(objective)?
+            .scores
+            .insert(entry.to_string(), value);
+        broadcast_packet(UpdateScore::set(objective, entry, value), state).await
+    }
+
+    /// Adds to an entry's score, counting from 0 if it hasn't got one. Returns the new score.
+    pub async fn add_score(
+        &self,
+        state: &GlobalState,
+        objective: &str,
+        entry: &str,
+        amount: i32,
+    ) -> Result<i32> {
+        let value = {
+            let mut scoreboard = self.scoreboard.write();
+            let score = scoreboard
+                .objective(objective)?
+                .scores
+                .entry(entry.to_string())
+                .or_default();
+            *score = score.saturating_add(amount);
+            *score
+        };
+        broadcast_packet(UpdateScore::set(objective, entry, value), state).await?;
+        Ok(value)
+    }
+
+    /// Takes an entry's score out of an objective, so it isn't listed anymore.
+    pub async fn reset_score(
+        &self,
+        state: &GlobalState,
+        objective: &str,
+        entry: &str,
+    ) -> Result<()> {
+        self.scoreboard
+            .write()
+            .objective(objective)?
+            .scores
+            .remove(entry);
+        broadcast_packet(UpdateScore::reset(objective, entry), state).await
+    }
+
+    /// Adds a team, along with whoever's on it.
+    pub async fn add_team(&self, state: &GlobalState, team: Team) -> Result<()> {
+        let packet = UpdateTeams::create(&team);
+        self.scoreboard.write().add_team(team)?;
+        broadcast_packet(packet, state).await
+    }
+
+    /// Changes everything about a team but who's on it.
+    pub async fn update_team(&self, state: &GlobalState, team: Team) -> Result<()> {
+        let packet = UpdateTeams::update(&team);
+        {
+            let mut scoreboard = self.scoreboard.write();
+            let existing = scoreboard.team(&team.name)?;
+            let members = std::mem::take(&mut existing.members);
+            *existing = Team { members, ..team };
+        }
+        broadcast_packet(packet, state).await
+    }
+
+    pub async fn remove_team(&self, state: &GlobalState, name: &str) -> Result<()> {
+        self.scoreboard
+            .write()
+            .teams
+            .remove(name)
+            .ok_or_else(|| Error::Generic(format!("Unknown team {}", name)))?;
+        broadcast_packet(UpdateTeams::remove(name), state).await
+    }
+
+    /// Puts entries on a team, taking them off any other team they were on.
+    pub async fn join_team(
+        &self,
+        state: &GlobalState,
+        name: &str,
+        members: Vec<String>,
+    ) -> Result<()> {
+        self.scoreboard.write().join_team(name, members.clone())?;
+        broadcast_packet(UpdateTeams::add_members(name, members), state).await
+    }
+
+    pub async fn leave_team(
+        &self,
+        state: &GlobalState,
+        name: &str,
+        members: Vec<String>,
+    ) -> Result<()> {
+        {
+            let mut scoreboard = self.scoreboard.write();
+            let team = scoreboard.team(name)?;
+            for member in &members {
+                team.members.remove(member);
+            }
+        }
+        broadcast_packet(UpdateTeams::remove_members(name, members), state).await
+    }
+
+    /// Queues the whole scoreboard for a joining player.
+    pub async fn queue_all(&self, packet_queue: &mut PacketQueue) -> Result<()> {
+        // Not holding the lock while queueing
+        let scoreboard = self.scoreboard.read().clone();
+        for objective in scoreboard.objectives.values() {
+            packet_queue
+                .queue(UpdateObjectives::create(objective))
+                .await?;
+            for (entry, value) in &objective.scores {
+                packet_queue
+                    .queue(UpdateScore::set(&objective.name, entry, *value))
+                    .await?;
+            }
+        }
+        for (slot, name) in &scoreboard.displayed {
+            packet_queue
+                .queue(DisplayObjective::new_auto(slot.id(), name.clone()))
+                .await?;
+        }
+        for team in scoreboard.teams.values() {
+            packet_queue.queue(UpdateTeams::create(team)).await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn objective(name: &str) -> Objective {
+        Objective {
+            name: name.to_string(),
+            display_name: TextComponent::text(name),
+            render_type: RenderType::Integer,
+            scores: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_remove_objective_clears_slots() {
+        let mut scoreboard = Scoreboard::default();
+        scoreboard.add_objective(objective("kills")).unwrap();
+        assert!(scoreboard.add_objective(objective("kills")).is_err());
+        scoreboard
+            .displayed
+            .insert(DisplaySlot::Sidebar, "kills".to_string());
+
+        scoreboard.remove_objective("kills").unwrap();
+        assert!(scoreboard.displayed.is_empty());
+        assert!(scoreboard.remove_objective("kills").is_err());
+    }
+
+    #[test]
+    fn test_one_team_per_entry() {
+        let mut scoreboard = Scoreboard::default();
+        let mut red = Team::new("red");
+        red.members.insert("Steve".to_string());
+        scoreboard.add_team(red).unwrap();
+        scoreboard.add_team(Team::new("blue")).unwrap();
+
+        scoreboard
+            .join_team("blue", vec!["Steve".to_string(), "Alex".to_string()])
+            .unwrap();
+        assert!(scoreboard.teams["red"].members.is_empty());
+        assert_eq!(scoreboard.teams["blue"].members.len(), 2);
+        assert!(scoreboard
+            .join_team("green", vec!["Steve".to_string()])
+            .is_err());
+    }
+
+    #[test]
+    fn test_display_slot_ids() {
+        assert_eq!(DisplaySlot::BelowName.id(), 2);
+        assert_eq!(DisplaySlot::TeamSidebar(TeamColor::Black).id(), 3);
+        assert_eq!(DisplaySlot::TeamSidebar(TeamColor::White).id(), 18);
+    }
+}
@@ -0,0 +1,57 @@
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use ferrumc_macros::AutoGenName;
+use tracing::{error, info};
+
+use crate::database::world_meta::save_world_meta;
+use crate::net::systems::System;
+use crate::state::GlobalState;
+use crate::utils::config::get_global_config;
+
+/// Set when the system starts, so the last changes can still be written when it's killed.
+static STATE: OnceLock<GlobalState> = OnceLock::new();
+
+/// Writes changed chunks and the [WorldMeta](crate::database::world_meta::WorldMeta) to the
+/// database every `flush_interval` seconds, and once more when the server shuts down. Skipped
+/// while `/save-off` is in effect, apart from the last one.
+#[derive(AutoGenName)]
+pub struct ChunkFlusher;
+
+#[async_trait]
+impl System for ChunkFlusher {
+    async fn run(&self, state: GlobalState) {
+        let _ = STATE.set(state.clone());
+        loop {
+            let interval = get_global_config().database.flush_interval.max(1);
+            tokio::time::sleep(Duration::from_secs(interval)).await;
+            if !state.database.autosave_enabled() {
+                continue;
+            }
+            if let Err(e) = state.database.flush().await {
+                error!("Failed to save chunks: {}", e);
+            }
+            if let Err(e) = save_world_meta(&state).await {
+                error!("Failed to save the world meta: {}", e);
+            }
+        }
+    }
+
+    async fn kill(&self) {
+        let Some(state) = STATE.get() else {
+            return;
+        };
+        match state.database.flush().await {
+            Ok(count) => info!("Saved {} chunks", count),
+            Err(e) => error!("Failed to save chunks: {}", e),
+        }
+        if let Err(e) = save_world_meta(state).await {
+            error!("Failed to save the world meta: {}", e);
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        Self::type_name()
+    }
+}
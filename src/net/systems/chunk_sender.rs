@@ -1,181 +1,253 @@
 use std::sync::Arc;
 
 use async_trait::async_trait;
-use ferrumc_codec::enc::NetEncode;
+use futures::future::join_all;
 use tokio::sync::RwLock;
 use tracing::{debug, error, warn};
 
+use crate::ecs::access::Access;
 use crate::net::packets::incoming::client_info::ClientInfo;
 use crate::net::packets::outgoing::chunk_and_light_data::ChunkDataAndUpdateLight;
 use crate::net::packets::outgoing::set_center_chunk::SetCenterChunk;
-use crate::net::systems::System;
+use crate::net::packets::outgoing::unload_chunk::UnloadChunk;
+use crate::net::systems::TickedSystem;
+use crate::net::utils::packet_queue::PacketQueue;
 use crate::net::{Connection, ConnectionWrapper};
 use crate::state::GlobalState;
-use crate::utils::components::last_chunk_tx_pos::LastChunkTxPos;
+use crate::utils::components::chunk_tracker::ChunkTracker;
+use crate::utils::components::dimension::dimension_of;
 use crate::utils::components::player::Player;
+use crate::utils::config::get_global_config;
 use crate::utils::encoding::position::Position;
 use crate::utils::prelude::*;
+use crate::world::dimension::Dimension;
 use ferrumc_macros::AutoGenName;
 
 pub const DEFAULT_CHUNK_RADIUS: i8 = 16;
-const CHUNK_TX_INTERVAL_MS: u64 = 50000;
+/// How many chunks are loaded from the database and sent to the client at once.
+const CHUNK_BATCH_SIZE: usize = 16;
 
+/// Keeps every player's loaded chunks in line with their position and view distance.
+///
+/// Each tick, the chunks in range of the player are diffed against their [ChunkTracker].
+/// New chunks are sent, and chunks that fell out of range are unloaded.
 #[derive(AutoGenName)]
 pub struct ChunkSender;
 
 #[async_trait]
-impl System for ChunkSender {
-    async fn run(&self, state: GlobalState) {
-        let mut interval =
-            tokio::time::interval(std::time::Duration::from_millis(CHUNK_TX_INTERVAL_MS));
-        loop {
-            interval.tick().await;
-
-            // Get all the Players, instead of all the *entities*. The player is just a filter.
-            let query = state.world.query::<&Player>();
-            let send_to = query.iter().await.collect::<Vec<_>>();
-
-            send_to.into_iter().for_each(|(entity_id, player)| {
-                debug!("Sending chunk to player: {}", player.get_username());
-                drop(player);
-                let state = state.clone();
-                tokio::spawn(async move {
-                    if let Err(e) = ChunkSender::send_chunks_to_player(state, entity_id).await {
-                        error!("Failed to send chunk to player: {}", e);
-                    }
-                });
+impl TickedSystem for ChunkSender {
+    async fn tick(&self, state: GlobalState, _tick: u64) -> Result<()> {
+        // Get all the Players, instead of all the *entities*. The player is just a filter.
+        let query = state.world.query::<&Player>();
+        let send_to = query.iter().await.collect::<Vec<_>>();
+
+        // Loading chunks can take a while, so it's done outside the tick
+        send_to.into_iter().for_each(|(entity_id, player)| {
+            drop(player);
+            let state = state.clone();
+            tokio::spawn(async move {
+                if let Err(e) = ChunkSender::send_chunks_to_player(state, entity_id).await {
+                    error!("Failed to send chunk to player: {}", e);
+                }
             });
-        }
+        });
+
+        Ok(())
     }
 
     fn name(&self) -> &'static str {
         Self::type_name()
     }
+
+    /// The chunks are sent on their own tasks, all the tick itself does is find the players.
+    fn access(&self) -> Access {
+        Access::new().read::<Player>()
+    }
 }
 
 impl ChunkSender {
-    pub async fn send_chunks_to_player_if_needed(
+    /// Sends the chunks that came into range of the player since the last call,
+    /// and unloads the ones that went out of range.
+    pub async fn send_chunks_to_player(
         state: GlobalState,
         entity_id: impl TryInto<usize>,
-        current_pos: (i32, i32),
     ) -> Result<()> {
         let entity_id = entity_id.try_into().map_err(|_| Error::ConversionError)?;
 
-        let mut last_chunk_tx_pos = state
+        let (c_pos, c_conn) = state
             .world
-            .get_component_storage()
-            .get_mut_or_insert_with::<LastChunkTxPos>(entity_id, Default::default)
-            .await;
+            .get_components::<(Position, ConnectionWrapper)>(entity_id)
+            .await?;
 
-        let client_info = state.world.get_component::<ClientInfo>(entity_id).await?;
+        let center = (c_pos.x >> 4, c_pos.z >> 4);
+        let conn = c_conn.0.clone();
 
-        let distance = last_chunk_tx_pos.distance_to(current_pos.0, current_pos.1);
+        drop(c_pos);
+        drop(c_conn);
 
-        if distance < (client_info.view_distance as f64 / 5f64) {
-            return Ok(());
-        }
+        let radius = Self::view_distance(&state, entity_id).await;
+        let dimension = dimension_of(&state, entity_id).await;
+
+        let (to_load, to_unload, center_changed) = {
+            let mut tracker = state
+                .world
+                .get_component_storage()
+                .get_mut_or_insert_with::<ChunkTracker>(entity_id, Default::default)
+                .await;
+
+            tracker.switch_dimension(dimension);
+            let (to_load, to_unload) = tracker.diff(center, radius);
+            let center_changed = tracker.center != Some(center);
+            if to_load.is_empty() && to_unload.is_empty() && !center_changed {
+                return Ok(());
+            }
 
-        last_chunk_tx_pos.set_last_chunk_tx_pos(current_pos.0, current_pos.1);
+            // Marked right away, so the next tick doesn't send them again while they're being sent
+            tracker.start_sending(center, &to_load, &to_unload);
+            (to_load, to_unload, center_changed)
+        };
 
-        let state_clone = state.clone();
-        tokio::spawn(async move {
-            if let Err(e) = ChunkSender::send_chunks_to_player(state_clone, entity_id).await {
-                error!("Failed to send chunk to player: {}", e);
+        let result = async {
+            if center_changed {
+                ChunkSender::send_set_center_chunk(center, conn.clone()).await?;
             }
-        });
+            ChunkSender::send_unload_chunks(&to_unload, conn.clone()).await?;
+            ChunkSender::send_chunk_data_to_player(&state, entity_id, &to_load, dimension, conn)
+                .await
+        }
+        .await;
 
-        Ok(())
+        // Whatever didn't get sent is tried again next tick, the ones that did are already loaded
+        Self::update_tracker(&state, entity_id, |tracker| {
+            for &chunk in &to_load {
+                tracker.failed(dimension, chunk);
+            }
+        })
+        .await;
+        result
     }
-    pub async fn send_chunks_to_player(
-        state: GlobalState,
-        entity_id: impl TryInto<usize>,
-    ) -> Result<()> {
-        let entity_id = entity_id.try_into().map_err(|_| Error::ConversionError)?;
 
-        let (player, c_pos, c_conn) = state
-            .world
-            .get_components::<(Player, Position, ConnectionWrapper)>(entity_id)
-            .await?;
+    async fn update_tracker(
+        state: &GlobalState,
+        entity_id: usize,
+        update: impl FnOnce(&mut ChunkTracker),
+    ) {
+        let tracker = state.world.get_component_storage().get_mut::<ChunkTracker>(entity_id);
+        if let Ok(mut tracker) = tracker.await {
+            update(&mut tracker);
+        }
+    }
 
-        let c_info = state
+    /// The smaller of the server's and the client's view distance.
+    async fn view_distance(state: &GlobalState, entity_id: usize) -> i32 {
+        let client_view_distance = state
             .world
             .get_component::<ClientInfo>(entity_id)
             .await
-            .ok();
-
-        let pos = c_pos.clone();
-        let view_distance: i8 = c_info
-            .as_ref()
             .map_or(DEFAULT_CHUNK_RADIUS, |c| c.view_distance);
-        let conn = c_conn.0.clone();
-
-        drop(c_pos);
-        drop(c_conn);
 
-        debug!(
-            "Sending chunks to player: {} @ {:?}",
-            player.get_username(),
-            pos
-        );
-
-        drop(player);
-
-        ChunkSender::send_set_center_chunk(&pos, conn.clone()).await?;
-        ChunkSender::send_chunk_data_to_player(state.clone(), &pos, view_distance, conn.clone())
-            .await?;
-
-        Ok(())
+        (client_view_distance.max(0) as u32).min(get_global_config().view_distance) as i32
     }
 
+    /// Sends chunks in batches, marking each batch as loaded once it's been sent.
     async fn send_chunk_data_to_player(
-        state: GlobalState,
-        pos: &Position,
-        player_view_distance: i8,
+        state: &GlobalState,
+        entity_id: usize,
+        chunks: &[(i32, i32)],
+        dimension: Dimension,
         conn: Arc<RwLock<Connection>>,
     ) -> Result<()> {
+        if chunks.is_empty() {
+            return Ok(());
+        }
+
         let start = std::time::Instant::now();
 
-        let pos_x = pos.x;
-        let pos_z = pos.z;
-
-        let chunk_radius = player_view_distance as i32;
-
-        'x: for x in -chunk_radius..=chunk_radius {
-            for z in -chunk_radius..=chunk_radius {
-                let Ok(packet) =
-                    ChunkDataAndUpdateLight::new(state.clone(), (pos_x >> 4) + x, (pos_z >> 4) + z)
-                        .await
-                else {
-                    continue;
-                };
-                let conn_read = conn.read().await;
-                if let Err(e) = conn_read.send_packet(packet).await {
-                    warn!("Failed to send chunk to player: {} ; Cancelling.", e);
-                    break 'x;
+        let mut chunks_sent = 0;
+        let mut bytes_sent = 0;
+        let protocol = conn.read().await.metadata.protocol();
+
+        for batch in chunks.chunks(CHUNK_BATCH_SIZE) {
+            let packets = join_all(
+                batch
+                    .iter()
+                    .map(|&(x, z)| {
+                        ChunkDataAndUpdateLight::new(state.clone(), x, z, dimension, protocol)
+                    }),
+            )
+            .await;
+
+            let mut queue = PacketQueue::new();
+            let mut queued = Vec::with_capacity(batch.len());
+            // Chunks that couldn't be loaded are left out, and tried again next tick
+            for (&chunk, packet) in batch.iter().zip(packets) {
+                match packet {
+                    Ok(packet) => {
+                        queue.queue(packet).await?;
+                        queued.push(chunk);
+                    }
+                    Err(e) => debug!("Couldn't load chunk {:?}: {}", chunk, e),
                 }
             }
+
+            if queue.is_empty() {
+                continue;
+            }
+            let bytes = queue.len();
+
+            let conn_read = conn.read().await;
+            if let Err(e) = conn_read.send_packets(queue).await {
+                warn!("Failed to send chunk to player: {} ; Cancelling.", e);
+                break;
+            }
+            drop(conn_read);
+
+            chunks_sent += queued.len();
+            bytes_sent += bytes;
+            Self::update_tracker(state, entity_id, |tracker| {
+                for chunk in queued {
+                    tracker.sent(dimension, chunk);
+                }
+            })
+            .await;
         }
 
-        // check the size of a single chunk and multiply it by the number of chunks sent
-        let sample_chunk =
-            ChunkDataAndUpdateLight::new(state.clone(), pos_x >> 4, pos_z >> 4).await?;
-        let mut vec = vec![];
-        sample_chunk.net_encode(&mut vec).await?;
-        let chunk_rad_axis = chunk_radius * 2 + 1;
         debug!(
-                "Send {}({}x{}) chunks to player in {:?}. Approximately {} kb of data (~{} kb per chunk)",
-                chunk_rad_axis * chunk_rad_axis,
-                chunk_rad_axis,
-                chunk_rad_axis,
-                start.elapsed(),
-                vec.len() as i32 * chunk_rad_axis * chunk_rad_axis / 1024,
-                vec.len() as i32 / 1024
-            );
+            "Sent {} chunks to player in {:?}. {} kb of data (~{} kb per chunk)",
+            chunks_sent,
+            start.elapsed(),
+            bytes_sent / 1024,
+            bytes_sent / 1024 / chunks_sent.max(1)
+        );
 
         Ok(())
     }
-    async fn send_set_center_chunk(pos: &Position, conn: Arc<RwLock<Connection>>) -> Result<()> {
-        let packet = SetCenterChunk::new(pos.x >> 4, pos.z >> 4);
+
+    async fn send_unload_chunks(
+        chunks: &[(i32, i32)],
+        conn: Arc<RwLock<Connection>>,
+    ) -> Result<()> {
+        if chunks.is_empty() {
+            return Ok(());
+        }
+
+        let conn = conn.read().await;
+        let protocol = conn.metadata.protocol();
+        let mut queue = PacketQueue::new();
+        for &(chunk_x, chunk_z) in chunks {
+            queue
+                .queue(UnloadChunk::new(chunk_x, chunk_z, protocol))
+                .await?;
+        }
+
+        conn.send_packets(queue).await
+    }
+
+    async fn send_set_center_chunk(
+        center: (i32, i32),
+        conn: Arc<RwLock<Connection>>,
+    ) -> Result<()> {
+        let packet = SetCenterChunk::new(center.0, center.1);
 
         let read_guard = conn.read().await;
 
@@ -0,0 +1,528 @@
+use std::collections::{HashMap, HashSet};
+
+use async_trait::async_trait;
+use ferrumc_codec::network_types::angle::Angle;
+use ferrumc_codec::network_types::varint::VarInt;
+use tracing::{trace, warn};
+
+use ferrumc_macros::AutoGenName;
+
+use crate::net::packets::outgoing::remove_entities::RemoveEntities;
+use crate::net::packets::outgoing::set_entity_metadata::SetEntityMetadata;
+use crate::net::packets::outgoing::set_head_rotation::SetHeadRotation;
+use crate::net::packets::outgoing::spawn_entity::{entity_types, SpawnEntity};
+use crate::net::packets::outgoing::spawn_experience_orb::SpawnExperienceOrb;
+use crate::net::packets::outgoing::spawn_player::SpawnPlayer;
+use crate::net::packets::outgoing::teleport_entity::TeleportEntity;
+use crate::net::packets::outgoing::update_entity_position::{
+    UpdateEntityPosition, UpdateEntityPositionAndRotation, UpdateEntityRotation,
+};
+use crate::net::protocol::{Protocol, V1_20_2};
+use crate::net::systems::TickedSystem;
+use crate::net::utils::encoded_packet::EncodedPacket;
+use crate::net::utils::packet_queue::PacketQueue;
+use crate::net::ConnectionWrapper;
+use crate::state::GlobalState;
+use crate::utils::components::dimension::CurrentDimension;
+use crate::utils::components::entity_state::EntityState;
+use crate::utils::components::grounded::Grounded;
+use crate::utils::components::last_broadcast_position::LastBroadcastPosition;
+use crate::utils::components::motion::Motion;
+use crate::utils::components::object_entity::ObjectEntity;
+use crate::utils::components::player::Player;
+use crate::utils::components::rotation::Rotation;
+use crate::utils::components::visible_entities::VisibleEntities;
+use crate::utils::config::get_global_config;
+use crate::utils::encoding::metadata::EntityMetadata;
+use crate::utils::encoding::position::Position;
+use crate::utils::prelude::*;
+use crate::world::dimension::Dimension;
+
+/// Relative moves are in 1/4096ths of a block and have to fit in an i16, so anything further is a teleport.
+const MAX_RELATIVE_MOVE: i32 = 7;
+
+/// Keeps every player's view of the other entities up to date.
+///
+/// Each tick, entities that came into a player's view distance are spawned, ones that left it
+/// (or were removed from the world) are despawned, and movement is sent for everything in between.
+#[derive(AutoGenName)]
+pub struct EntityBroadcaster;
+
+/// A snapshot of an entity that gets broadcast, so no component locks are held while sending.
+struct TrackedEntity {
+    kind: TrackedKind,
+    dimension: Dimension,
+    /// Where clients see it.
+    position: (f64, f64, f64),
+    rotation: Rotation,
+    /// Sent when it's spawned, changes after that are sent as they happen.
+    metadata: EntityMetadata,
+}
+
+enum TrackedKind {
+    Player {
+        uuid: u128,
+        username: String,
+    },
+    /// An [ObjectEntity].
+    Object {
+        uuid: u128,
+        entity_type: i32,
+        data: i32,
+        velocity: (f64, f64, f64),
+    },
+}
+
+#[async_trait]
+impl TickedSystem for EntityBroadcaster {
+    async fn tick(&self, state: GlobalState, _tick: u64) -> Result<()> {
+        EntityBroadcaster::broadcast(&state).await
+    }
+
+    fn name(&self) -> &'static str {
+        Self::type_name()
+    }
+}
+
+impl EntityBroadcaster {
+    /// Despawns an entity for everyone that can see it, so it's spawned again fresh next tick.
+    /// Players that died stay dead on other clients otherwise.
+    pub async fn respawn_for_viewers(state: &GlobalState, entity_id: usize) -> Result<()> {
+        let viewers = {
+            let mut viewers = Vec::new();
+            let mut query = state
+                .world
+                .query::<(&mut VisibleEntities, &ConnectionWrapper)>();
+            while let Some((_, (mut visible, conn))) = query.next().await {
+                if visible.entities.remove(&entity_id) {
+                    viewers.push(conn.0.clone());
+                }
+            }
+            viewers
+        };
+
+        let packet = RemoveEntities::new(vec![VarInt::new(entity_id as i32)]);
+        let packet = EncodedPacket::new(packet).await?;
+        for conn in viewers {
+            let conn = conn.read().await;
+            if let Err(e) = conn.send_encoded(&packet).await {
+                warn!("Failed to despawn {} for {}: {}", entity_id, conn.id, e);
+            }
+        }
+        Ok(())
+    }
+
+    async fn broadcast(state: &GlobalState) -> Result<()> {
+        let (tracked, moves) = Self::collect_movement(state).await?;
+
+        let observers = {
+            let query = state
+                .world
+                .query::<(&Position, &ConnectionWrapper, Option<&CurrentDimension>)>();
+            query
+                .iter()
+                .await
+                .map(|(id, (position, conn, dimension))| {
+                    let dimension = dimension.map_or(Dimension::Overworld, |d| d.dimension);
+                    (id, (position.x >> 4, position.z >> 4), dimension, conn.0.clone())
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let view_distance = get_global_config().view_distance as i32;
+
+        for (observer, center, dimension, conn) in observers {
+            let in_range: HashSet<usize> = tracked
+                .iter()
+                .filter(|(&id, entity)| {
+                    let (x, _, z) = entity.position;
+                    id != observer
+                        && entity.dimension == dimension
+                        && ((x.floor() as i32 >> 4) - center.0).abs() <= view_distance
+                        && ((z.floor() as i32 >> 4) - center.1).abs() <= view_distance
+                })
+                .map(|(&id, _)| id)
+                .collect();
+
+            let protocol = conn.read().await.metadata.protocol();
+            let mut queue = PacketQueue::new();
+            {
+                let mut visible = state
+                    .world
+                    .get_component_storage()
+                    .get_mut_or_insert_with::<VisibleEntities>(observer, Default::default)
+                    .await;
+
+                let removed: Vec<VarInt> = visible
+                    .entities
+                    .difference(&in_range)
+                    .map(|&id| VarInt::new(id as i32))
+                    .collect();
+                if !removed.is_empty() {
+                    queue.queue(RemoveEntities::new(removed)).await?;
+                }
+
+                for &id in &in_range {
+                    if visible.entities.contains(&id) {
+                        if let Some(packets) = moves.get(&id) {
+                            queue.append(packets);
+                        }
+                    } else {
+                        Self::queue_spawn(&mut queue, id, &tracked[&id], protocol).await?;
+                    }
+                }
+
+                visible.entities = in_range;
+            }
+
+            if queue.is_empty() {
+                continue;
+            }
+
+            let conn = conn.read().await;
+            if let Err(e) = conn.queue_packets(queue).await {
+                warn!("Failed to send entity updates to {}: {}", observer, e);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Snapshots every broadcast entity and builds the movement packets for the ones that moved
+    /// since the last tick.
+    async fn collect_movement(
+        state: &GlobalState,
+    ) -> Result<(HashMap<usize, TrackedEntity>, HashMap<usize, PacketQueue>)> {
+        let snapshots = {
+            let query = state
+                .world
+                .query::<(
+                    &Player,
+                    &Position,
+                    Option<&Rotation>,
+                    Option<&Grounded>,
+                    Option<&CurrentDimension>,
+                    Option<&EntityState>,
+                )>();
+            query
+                .iter()
+                .await
+                .map(|(id, (player, position, rotation, grounded, dimension, entity_state))| {
+                    let rotation = rotation
+                        .map(|rotation| rotation.clone())
+                        .unwrap_or_else(|| Rotation::new(0.0, 0.0));
+                    let on_ground = grounded.is_some_and(|grounded| grounded.is_grounded);
+                    let dimension = dimension.map_or(Dimension::Overworld, |d| d.dimension);
+                    let metadata = entity_state.map_or_else(
+                        || EntityState::default().metadata(),
+                        |entity_state| entity_state.metadata(),
+                    );
+                    (
+                        id,
+                        player.uuid,
+                        player.username.clone(),
+                        dimension,
+                        position.clone(),
+                        rotation,
+                        on_ground,
+                        metadata,
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let mut tracked = HashMap::new();
+        let mut moves = HashMap::new();
+
+        for (id, uuid, username, dimension, position, rotation, on_ground, metadata) in snapshots {
+            let mut last = state
+                .world
+                .get_component_storage()
+                .get_mut_or_insert_with(id, || {
+                    LastBroadcastPosition::new(position.clone(), rotation.clone())
+                })
+                .await;
+
+            let mut packets = PacketQueue::new();
+            Self::queue_movement(&mut packets, id, &last, &position, &rotation, on_ground).await?;
+            if !packets.is_empty() {
+                moves.insert(id, packets);
+                *last = LastBroadcastPosition::new(position, rotation);
+            }
+
+            let position = &last.position;
+            tracked.insert(
+                id,
+                TrackedEntity {
+                    kind: TrackedKind::Player { uuid, username },
+                    dimension,
+                    position: (position.x as f64, position.y as f64, position.z as f64),
+                    rotation: last.rotation.clone(),
+                    metadata,
+                },
+            );
+        }
+
+        Self::collect_object_movement(state, &mut tracked, &mut moves).await?;
+        Ok((tracked, moves))
+    }
+
+    /// [EntityBroadcaster::collect_movement] for [ObjectEntity]s, which move by fractions of a
+    /// block.
+    async fn collect_object_movement(
+        state: &GlobalState,
+        tracked: &mut HashMap<usize, TrackedEntity>,
+        moves: &mut HashMap<usize, PacketQueue>,
+    ) -> Result<()> {
+        let mut query = state.world.query::<(
+            &mut ObjectEntity,
+            &Motion,
+            Option<&Rotation>,
+            Option<&CurrentDimension>,
+        )>();
+        while let Some((id, (mut object, motion, rotation, dimension))) = query.next().await {
+            let entity = VarInt::new(id as i32);
+            let to = motion.position;
+            let from = object.last_broadcast.unwrap_or(to);
+            let rotation = rotation.map_or((0.0, 0.0), |rotation| (rotation.yaw, rotation.pitch));
+            let rotated = rotation != object.last_rotation;
+            let (yaw, pitch) = (Angle::from_degrees(rotation.0), Angle::from_degrees(rotation.1));
+            let on_ground = motion.on_ground;
+            let mut packets = PacketQueue::new();
+            let sent = match object_delta(from, to) {
+                Some((0, 0, 0)) => {
+                    if rotated {
+                        packets
+                            .queue(UpdateEntityRotation::new_auto(entity, yaw, pitch, on_ground))
+                            .await?;
+                    }
+                    from
+                }
+                Some((dx, dy, dz)) => {
+                    if rotated {
+                        packets
+                            .queue(UpdateEntityPositionAndRotation::new_auto(
+                                entity, dx, dy, dz, yaw, pitch, on_ground,
+                            ))
+                            .await?;
+                    } else {
+                        packets
+                            .queue(UpdateEntityPosition::new_auto(entity, dx, dy, dz, on_ground))
+                            .await?;
+                    }
+                    // Where the client ends up, rounding and all
+                    let moved = |from: f64, delta: i16| from + delta as f64 / 4096.0;
+                    (moved(from.0, dx), moved(from.1, dy), moved(from.2, dz))
+                }
+                None => {
+                    packets
+                        .queue(TeleportEntity::new_auto(
+                            entity, to.0, to.1, to.2, yaw, pitch, on_ground,
+                        ))
+                        .await?;
+                    to
+                }
+            };
+            if rotated {
+                packets.queue(SetHeadRotation::new_auto(entity, yaw)).await?;
+            }
+            object.last_broadcast = Some(sent);
+            object.last_rotation = rotation;
+            if !packets.is_empty() {
+                moves.insert(id, packets);
+            }
+
+            tracked.insert(
+                id,
+                TrackedEntity {
+                    kind: TrackedKind::Object {
+                        uuid: object.uuid,
+                        entity_type: object.entity_type,
+                        data: object.data,
+                        velocity: motion.velocity,
+                    },
+                    dimension: dimension.map_or(Dimension::Overworld, |d| d.dimension),
+                    position: sent,
+                    rotation: Rotation::new(rotation.0, rotation.1),
+                    metadata: object.metadata.clone(),
+                },
+            );
+        }
+        Ok(())
+    }
+
+    async fn queue_movement(
+        queue: &mut PacketQueue,
+        entity_id: usize,
+        last: &LastBroadcastPosition,
+        position: &Position,
+        rotation: &Rotation,
+        on_ground: bool,
+    ) -> Result<()> {
+        let entity = VarInt::new(entity_id as i32);
+        let (dx, dy, dz) = (
+            position.x - last.position.x,
+            (position.y - last.position.y) as i32,
+            position.z - last.position.z,
+        );
+        let moved = dx != 0 || dy != 0 || dz != 0;
+        let rotated = rotation.yaw != last.rotation.yaw || rotation.pitch != last.rotation.pitch;
+
+        if !moved && !rotated {
+            return Ok(());
+        }
+
+        let (yaw, pitch) = (Angle::from_degrees(rotation.yaw), Angle::from_degrees(rotation.pitch));
+
+        if [dx, dy, dz].iter().any(|d| d.abs() > MAX_RELATIVE_MOVE) {
+            queue
+                .queue(TeleportEntity::new_auto(
+                    entity,
+                    position.x as f64,
+                    position.y as f64,
+                    position.z as f64,
+                    yaw,
+                    pitch,
+                    on_ground,
+                ))
+                .await?;
+        } else if moved && rotated {
+            queue
+                .queue(UpdateEntityPositionAndRotation::new_auto(
+                    entity,
+                    to_delta(dx),
+                    to_delta(dy),
+                    to_delta(dz),
+                    yaw,
+                    pitch,
+                    on_ground,
+                ))
+                .await?;
+        } else if moved {
+            queue
+                .queue(UpdateEntityPosition::new_auto(
+                    entity,
+                    to_delta(dx),
+                    to_delta(dy),
+                    to_delta(dz),
+                    on_ground,
+                ))
+                .await?;
+        } else {
+            queue
+                .queue(UpdateEntityRotation::new_auto(
+                    entity, yaw, pitch, on_ground,
+                ))
+                .await?;
+        }
+
+        if rotated {
+            queue.queue(SetHeadRotation::new_auto(entity, yaw)).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn queue_spawn(
+        queue: &mut PacketQueue,
+        entity_id: usize,
+        entity: &TrackedEntity,
+        protocol: &Protocol,
+    ) -> Result<()> {
+        let (uuid, username) = match &entity.kind {
+            TrackedKind::Player { uuid, username } => (*uuid, username),
+            TrackedKind::Object {
+                entity_type, data, ..
+            } if *entity_type == entity_types::EXPERIENCE_ORB => {
+                let orb = SpawnExperienceOrb::new(entity_id, entity.position, *data);
+                return queue.queue(orb).await;
+            }
+            TrackedKind::Object {
+                uuid,
+                entity_type,
+                data,
+                velocity,
+            } => {
+                trace!("Spawning entity {} of type {}", entity_id, entity_type);
+                let rotation = &entity.rotation;
+                queue
+                    .queue(SpawnEntity::object(
+                        entity_id,
+                        *uuid,
+                        *entity_type,
+                        entity.position,
+                        (rotation.yaw, rotation.pitch),
+                        *data,
+                        *velocity,
+                    ))
+                    .await?;
+                if !entity.metadata.is_empty() {
+                    queue
+                        .queue(SetEntityMetadata::new(entity_id, entity.metadata.clone()))
+                        .await?;
+                }
+                return Ok(());
+            }
+        };
+        trace!("Spawning entity {} ({})", entity_id, username);
+
+        let rotation = &entity.rotation;
+        let entity_id = VarInt::new(entity_id as i32);
+
+        // The client ignores players it doesn't have player info for, which the tab list has
+        // already sent
+        let (x, y, z) = entity.position;
+        let (yaw, pitch) = (Angle::from_degrees(rotation.yaw), Angle::from_degrees(rotation.pitch));
+        // Spawn Player was folded into Spawn Entity in 1.20.2
+        if protocol.at_least(V1_20_2) {
+            queue
+                .queue(SpawnEntity::player(entity_id, uuid, x, y, z, yaw, pitch))
+                .await?;
+        } else {
+            queue
+                .queue(SpawnPlayer::new_auto(entity_id, uuid, x, y, z, yaw, pitch))
+                .await?;
+        }
+        queue
+            .queue(SetHeadRotation::new_auto(entity_id, yaw))
+            .await?;
+        queue
+            .queue(SetEntityMetadata::new_auto(entity_id, entity.metadata.clone()))
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// Converts a move in whole blocks to 1/4096ths of a block.
+fn to_delta(blocks: i32) -> i16 {
+    (blocks * 4096) as i16
+}
+
+/// A move by a fraction of a block in 1/4096ths of a block, or None if it's too far to send
+/// that way.
+fn object_delta(from: (f64, f64, f64), to: (f64, f64, f64)) -> Option<(i16, i16, i16)> {
+    let delta = |from: f64, to: f64| {
+        let delta = ((to - from) * 4096.0).round();
+        (delta.abs() <= i16::MAX as f64).then_some(delta as i16)
+    };
+    Some((delta(from.0, to.0)?, delta(from.1, to.1)?, delta(from.2, to.2)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_delta() {
+        assert_eq!(to_delta(1), 4096);
+        assert_eq!(to_delta(-MAX_RELATIVE_MOVE), -28672);
+    }
+
+    #[test]
+    fn test_object_delta() {
+        let from = (0.5, 64.0, 0.5);
+        assert_eq!(object_delta(from, (0.75, 63.5, 0.5)), Some((1024, -2048, 0)));
+        assert_eq!(object_delta(from, (8.75, 64.0, 0.5)), None);
+    }
+}
@@ -0,0 +1,58 @@
+use async_trait::async_trait;
+
+use ferrumc_macros::AutoGenName;
+
+use crate::net::systems::TickedSystem;
+use crate::state::GlobalState;
+use crate::utils::components::body::Body;
+use crate::utils::components::dimension::CurrentDimension;
+use crate::utils::components::motion::Motion;
+use crate::utils::prelude::*;
+use crate::world::dimension::Dimension;
+use crate::world::physics::{step, BlockShapes};
+
+/// Entities that fall this far below the bottom of the world are gone.
+pub const VOID_DEPTH: i32 = 64;
+
+/// Moves every entity with a [Body] by a tick of [physics](crate::world::physics), and removes the
+/// ones that have fallen out of the world.
+#[derive(AutoGenName)]
+pub struct EntityPhysics;
+
+#[async_trait]
+impl TickedSystem for EntityPhysics {
+    async fn tick(&self, state: GlobalState, _tick: u64) -> Result<()> {
+        let entities = {
+            let query = state
+                .world
+                .query::<(&Motion, &Body, Option<&CurrentDimension>)>();
+            query
+                .iter()
+                .await
+                .map(|(id, (motion, body, dimension))| {
+                    let dimension = dimension.map_or(Dimension::Overworld, |d| d.dimension);
+                    (id, *motion, *body, dimension)
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let mut blocks = BlockShapes::new(&state);
+        for (id, mut motion, body, dimension) in entities {
+            let shapes = blocks.in_reach(dimension, &motion, &body).await;
+            step(&mut motion, &body, |x, y, z| {
+                shapes.get(&(x, y, z)).copied().unwrap_or_default()
+            });
+            // The entity broadcaster despawns them for everyone once they're gone
+            if motion.position.1 < (dimension.min_y() - VOID_DEPTH) as f64 {
+                state.world.delete_entity(id).await?;
+                continue;
+            }
+            *state.world.get_component_storage().get_mut::<Motion>(id).await? = motion;
+        }
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        Self::type_name()
+    }
+}
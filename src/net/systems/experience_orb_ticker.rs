@@ -0,0 +1,86 @@
+use std::collections::HashSet;
+
+use async_trait::async_trait;
+use tracing::debug;
+
+use ferrumc_macros::AutoGenName;
+
+use crate::net::packets::outgoing::take_item_entity::TakeItemEntity;
+use crate::net::systems::TickedSystem;
+use crate::net::utils::broadcast::broadcast_to_viewers;
+use crate::state::GlobalState;
+use crate::utils::components::dimension::CurrentDimension;
+use crate::utils::components::experience::give_experience;
+use crate::utils::components::experience_orb::ExperienceOrb;
+use crate::utils::components::motion::Motion;
+use crate::utils::prelude::*;
+use crate::world::dimension::Dimension;
+use crate::world::experience_orbs::attract;
+use crate::world::item_entities::{collectors, in_pickup_reach, nearest};
+
+/// Pulls experience orbs towards players, gives the points to whoever touches them and despawns
+/// ones that have been lying around too long, every tick. They're moved by
+/// [EntityPhysics](super::entity_physics::EntityPhysics).
+#[derive(AutoGenName)]
+pub struct ExperienceOrbTicker;
+
+#[async_trait]
+impl TickedSystem for ExperienceOrbTicker {
+    async fn tick(&self, state: GlobalState, _tick: u64) -> Result<()> {
+        let orbs = {
+            let query = state
+                .world
+                .query::<(&ExperienceOrb, &Motion, Option<&CurrentDimension>)>();
+            query
+                .iter()
+                .await
+                .map(|(id, (orb, motion, dimension))| {
+                    let dimension = dimension.map_or(Dimension::Overworld, |d| d.dimension);
+                    (id, *orb, *motion, dimension)
+                })
+                .collect::<Vec<_>>()
+        };
+        if orbs.is_empty() {
+            return Ok(());
+        }
+
+        let players = collectors(&state).await;
+        // Like vanilla, a player only collects one orb a tick
+        let mut collected = HashSet::new();
+        for (id, mut orb, mut motion, dimension) in orbs {
+            if let Some(player) = nearest(&players, dimension, motion.position) {
+                attract(&mut motion, player);
+            }
+            orb.age += 1;
+            if orb.age >= ExperienceOrb::DESPAWN_AGE {
+                state.world.delete_entity(id).await?;
+                continue;
+            }
+
+            let collector = players.iter().find(|(player, position, player_dimension)| {
+                *player_dimension == dimension
+                    && !collected.contains(player)
+                    && in_pickup_reach(*position, motion.position)
+            });
+            if let Some(&(player, _, _)) = collector {
+                collected.insert(player);
+                broadcast_to_viewers(TakeItemEntity::new(id, player, 1), id, &state).await?;
+                state.world.delete_entity(id).await?;
+                if let Err(e) = give_experience(&state, player, orb.value).await {
+                    // They've just left, and the points went with them
+                    debug!("Couldn't give {} experience: {}", player, e);
+                }
+                continue;
+            }
+
+            let component_storage = state.world.get_component_storage();
+            *component_storage.get_mut::<Motion>(id).await? = motion;
+            *component_storage.get_mut::<ExperienceOrb>(id).await? = orb;
+        }
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        Self::type_name()
+    }
+}
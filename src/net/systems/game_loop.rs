@@ -0,0 +1,256 @@
+use std::ops::Deref;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio::time::Instant;
+use tracing::{debug, trace, warn};
+
+use ferrumc_macros::AutoGenName;
+
+use crate::ecs::access::{stages, Access};
+use crate::net::metrics::METRICS;
+use crate::net::profiler::PROFILER;
+use crate::net::systems::{System, TickedSystem, TICKED_SYSTEMS};
+use crate::state::GlobalState;
+
+pub const TICKS_PER_SECOND: u64 = 20;
+pub const TICK_DURATION: Duration = Duration::from_millis(1000 / TICKS_PER_SECOND);
+/// How far behind the loop can fall before it gives up on catching up and skips the missed ticks.
+/// Same as vanilla's 2 seconds.
+const MAX_CATCH_UP_TICKS: u32 = 40;
+
+/// Drives every [TickedSystem](crate::net::systems::TickedSystem) at a fixed 20 ticks per second.
+///
+/// Systems run in the order of [TICKED_SYSTEMS], then any that [plugins](crate::plugins) have added,
+/// then the [scheduled tasks](crate::net::scheduler) that are due. Systems whose
+/// [access](TickedSystem::access) doesn't conflict run at the same time, each on its own task. If a
+/// tick runs long, the following ones run back to back until the loop has caught up.
+#[derive(AutoGenName)]
+pub struct GameLoop;
+
+#[async_trait]
+impl System for GameLoop {
+    async fn run(&self, state: GlobalState) {
+        let mut scheduler = TickScheduler::new(Instant::now());
+        // When the last second's worth of ticks started, for the TPS
+        let mut second_start = Instant::now();
+
+        loop {
+            tokio::time::sleep_until(scheduler.next_tick).await;
+
+            let tick = scheduler.tick;
+            let start = Instant::now();
+            // Plugins' systems run after the built-in ones
+            let plugin_systems = state.plugins.ticked_systems();
+            let systems: Vec<SystemRef> = TICKED_SYSTEMS
+                .iter()
+                .map(|&system| SystemRef::BuiltIn(system))
+                .chain(plugin_systems.into_iter().map(SystemRef::Plugin))
+                .collect();
+            let mut timings = Vec::with_capacity(systems.len());
+
+            let accesses: Vec<Access> = systems.iter().map(|system| system.access()).collect();
+            for stage in stages(&accesses) {
+                let stage = stage
+                    .into_iter()
+                    .map(|index| systems[index].clone())
+                    .collect();
+                run_stage(&state, tick, stage, &mut timings).await;
+            }
+            let scheduler_start = Instant::now();
+            state.scheduler.run_tick(tick).await;
+            timings.push(("Scheduler", scheduler_start.elapsed()));
+            let flush_start = Instant::now();
+            flush_connections(&state).await;
+            timings.push(("flush", flush_start.elapsed()));
+
+            let elapsed = start.elapsed();
+            METRICS.tick_duration.observe(elapsed);
+            if PROFILER.is_running() {
+                profile_tick(elapsed, &timings);
+            }
+            if (tick + 1).is_multiple_of(TICKS_PER_SECOND) {
+                let now = Instant::now();
+                let tps = TICKS_PER_SECOND as f64 / (now - second_start).as_secs_f64();
+                // Ticks that are caught up on run back to back, which isn't really going faster
+                METRICS.set_tps(tps.min(TICKS_PER_SECOND as f64));
+                second_start = now;
+            }
+            if elapsed > TICK_DURATION {
+                warn!("Tick {} took {:?}: {:?}", tick, elapsed, timings);
+            } else {
+                trace!("Tick {} took {:?}: {:?}", tick, elapsed, timings);
+            }
+
+            let skipped = scheduler.schedule_next(Instant::now());
+            if skipped > 0 {
+                warn!("Can't keep up! Skipping {} ticks", skipped);
+            }
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        Self::type_name()
+    }
+}
+
+/// A system for the tick, whether it's built in or from a plugin.
+#[derive(Clone)]
+enum SystemRef {
+    BuiltIn(&'static dyn TickedSystem),
+    Plugin(Arc<dyn TickedSystem>),
+}
+
+impl Deref for SystemRef {
+    type Target = dyn TickedSystem;
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            SystemRef::BuiltIn(system) => *system,
+            SystemRef::Plugin(system) => system.as_ref(),
+        }
+    }
+}
+
+/// Runs systems that don't conflict, at the same time if there's more than one.
+async fn run_stage(
+    state: &GlobalState,
+    tick: u64,
+    stage: Vec<SystemRef>,
+    timings: &mut Vec<(&'static str, Duration)>,
+) {
+    if let [system] = &stage[..] {
+        timings.push(run_system(&**system, state.clone(), tick).await);
+        return;
+    }
+
+    let handles: Vec<_> = stage
+        .into_iter()
+        .map(|system| {
+            let state = state.clone();
+            tokio::spawn(async move { run_system(&*system, state, tick).await })
+        })
+        .collect();
+    for handle in handles {
+        match handle.await {
+            Ok(timing) => timings.push(timing),
+            Err(e) => warn!("A system panicked on tick {}: {}", tick, e),
+        }
+    }
+}
+
+/// Runs a system for the tick, returning its name and how long it took.
+async fn run_system(
+    system: &dyn TickedSystem,
+    state: GlobalState,
+    tick: u64,
+) -> (&'static str, Duration) {
+    let start = Instant::now();
+    if let Err(e) = system.tick(state, tick).await {
+        warn!("{} failed on tick {}: {}", system.name(), tick, e);
+    }
+    (system.name(), start.elapsed())
+}
+
+/// Records a tick's timings with the [profiler](crate::net::profiler), under `tick`. Whatever
+/// wasn't spent in a system goes under `tick` itself.
+fn profile_tick(elapsed: Duration, timings: &[(&str, Duration)]) {
+    let mut accounted = Duration::ZERO;
+    for (name, duration) in timings {
+        PROFILER.record(&format!("tick;{}", name), *duration);
+        accounted += *duration;
+    }
+    PROFILER.record("tick", elapsed.saturating_sub(accounted));
+}
+
+/// Sends everything the systems queued this tick, see
+/// [Connection::queue_packet](crate::net::Connection::queue_packet).
+async fn flush_connections(state: &GlobalState) {
+    let connections: Vec<_> = state
+        .connections
+        .connections
+        .iter()
+        .map(|entry| (*entry.key(), entry.value().clone()))
+        .collect();
+    for (conn_id, conn) in connections {
+        if let Err(e) = conn.read().await.flush_packets().await {
+            trace!("Failed to flush packets to {}: {}", conn_id, e);
+        }
+    }
+}
+
+/// Keeps track of when the next tick is due.
+#[derive(Debug)]
+pub struct TickScheduler {
+    pub next_tick: Instant,
+    /// How many ticks have run.
+    pub tick: u64,
+}
+
+impl TickScheduler {
+    pub fn new(now: Instant) -> Self {
+        Self {
+            next_tick: now,
+            tick: 0,
+        }
+    }
+
+    /// Moves on to the next tick once the current one has run, returning how many ticks were skipped.
+    ///
+    /// A tick that's already overdue is due straight away, so the loop catches up. If it's more than
+    /// [MAX_CATCH_UP_TICKS] behind, the missed ticks are skipped instead.
+    pub fn schedule_next(&mut self, now: Instant) -> u32 {
+        self.tick += 1;
+        self.next_tick += TICK_DURATION;
+
+        let behind = now.saturating_duration_since(self.next_tick);
+        if behind <= TICK_DURATION * MAX_CATCH_UP_TICKS {
+            return 0;
+        }
+
+        let skipped = (behind.as_millis() / TICK_DURATION.as_millis()) as u32;
+        self.next_tick += TICK_DURATION * skipped;
+        debug!("Skipped to {:?}", self.next_tick);
+        skipped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schedule_on_time() {
+        let start = Instant::now();
+        let mut scheduler = TickScheduler::new(start);
+
+        assert_eq!(scheduler.schedule_next(start + Duration::from_millis(10)), 0);
+        assert_eq!(scheduler.next_tick, start + TICK_DURATION);
+        assert_eq!(scheduler.tick, 1);
+    }
+
+    #[test]
+    fn test_schedule_catches_up() {
+        let start = Instant::now();
+        let mut scheduler = TickScheduler::new(start);
+
+        // A slow tick doesn't push the schedule back, the next one is just due right away
+        assert_eq!(scheduler.schedule_next(start + Duration::from_millis(500)), 0);
+        assert_eq!(scheduler.next_tick, start + TICK_DURATION);
+    }
+
+    #[test]
+    fn test_schedule_skips_when_too_far_behind() {
+        let start = Instant::now();
+        let mut scheduler = TickScheduler::new(start);
+
+        let now = start + Duration::from_secs(5);
+        let skipped = scheduler.schedule_next(now);
+
+        assert_eq!(skipped, 99);
+        assert!(scheduler.next_tick <= now);
+        assert!(now - scheduler.next_tick < TICK_DURATION);
+        assert_eq!(scheduler.tick, 1);
+    }
+}
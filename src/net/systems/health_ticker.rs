@@ -0,0 +1,68 @@
+use async_trait::async_trait;
+use tracing::debug;
+
+use ferrumc_macros::AutoGenName;
+
+use crate::events::health_events::{damage, DamageCause};
+use crate::net::systems::TickedSystem;
+use crate::state::GlobalState;
+use crate::utils::components::food::{Food, FoodEffect};
+use crate::utils::components::health::{send_health, Health};
+use crate::utils::prelude::*;
+
+/// Half a heart.
+const STARVATION_DAMAGE: f32 = 1.0;
+
+/// Heals players that have eaten enough and hurts starving ones, every tick.
+#[derive(AutoGenName)]
+pub struct HealthTicker;
+
+#[async_trait]
+impl TickedSystem for HealthTicker {
+    async fn tick(&self, state: GlobalState, _tick: u64) -> Result<()> {
+        let mut changed = Vec::new();
+        {
+            let mut query = state.world.query::<(&mut Food, &Health)>();
+            while let Some((entity, (mut food, health))) = query.next().await {
+                if health.is_dead() {
+                    continue;
+                }
+                let before = (food.level, food.saturation);
+                let effect = food.tick(&health);
+                if effect.is_some() || before != (food.level, food.saturation) {
+                    changed.push((entity, effect));
+                }
+            }
+        }
+
+        for (entity, effect) in changed {
+            match effect {
+                Some(FoodEffect::Heal(amount)) => {
+                    if let Ok(mut health) = state
+                        .world
+                        .get_component_storage()
+                        .get_mut::<Health>(entity)
+                        .await
+                    {
+                        health.heal(amount);
+                    }
+                }
+                Some(FoodEffect::Starve) => {
+                    // Sends the new health itself
+                    damage(&state, entity, STARVATION_DAMAGE, DamageCause::Starvation).await?;
+                    continue;
+                }
+                None => {}
+            }
+            if let Err(e) = send_health(&state, entity).await {
+                // They may have just left
+                debug!("Couldn't send {} their health: {}", entity, e);
+            }
+        }
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        Self::type_name()
+    }
+}
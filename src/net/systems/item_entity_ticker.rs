@@ -0,0 +1,186 @@
+use async_trait::async_trait;
+use tracing::debug;
+
+use ferrumc_macros::AutoGenName;
+
+use crate::net::packets::outgoing::set_container_content::SetContainerContent;
+use crate::net::packets::outgoing::take_item_entity::TakeItemEntity;
+use crate::net::systems::TickedSystem;
+use crate::net::utils::broadcast::broadcast_to_viewers;
+use crate::state::GlobalState;
+use crate::utils::components::dimension::CurrentDimension;
+use crate::utils::components::inventory::Inventory;
+use crate::utils::components::item_entity::ItemEntity;
+use crate::utils::components::motion::Motion;
+use crate::utils::prelude::*;
+use crate::world::dimension::Dimension;
+use crate::world::item_entities::{collectors, in_merge_reach, in_pickup_reach, update_item};
+
+/// How often stacks close together are merged, in ticks.
+const MERGE_INTERVAL: u64 = 10;
+
+/// Ages dropped items, merges stacks that end up together, lets players pick them up and despawns
+/// ones that have been lying around too long, every tick. They're moved by
+/// [EntityPhysics](super::entity_physics::EntityPhysics).
+#[derive(AutoGenName)]
+pub struct ItemEntityTicker;
+
+/// A snapshot of an item entity, so no component locks are held while changing the world.
+struct Item {
+    id: usize,
+    item: ItemEntity,
+    motion: Motion,
+    dimension: Dimension,
+}
+
+#[async_trait]
+impl TickedSystem for ItemEntityTicker {
+    async fn tick(&self, state: GlobalState, tick: u64) -> Result<()> {
+        ItemEntityTicker::age_items(&state).await?;
+        if tick.is_multiple_of(MERGE_INTERVAL) {
+            ItemEntityTicker::merge_items(&state).await?;
+        }
+        ItemEntityTicker::pick_up_items(&state).await
+    }
+
+    fn name(&self) -> &'static str {
+        Self::type_name()
+    }
+}
+
+impl ItemEntityTicker {
+    async fn items(state: &GlobalState) -> Vec<Item> {
+        let query = state
+            .world
+            .query::<(&ItemEntity, &Motion, Option<&CurrentDimension>)>();
+        query
+            .iter()
+            .await
+            .map(|(id, (item, motion, dimension))| Item {
+                id,
+                item: item.clone(),
+                motion: *motion,
+                dimension: dimension.map_or(Dimension::Overworld, |d| d.dimension),
+            })
+            .collect()
+    }
+
+    async fn age_items(state: &GlobalState) -> Result<()> {
+        let mut despawned = Vec::new();
+        for Item { id, mut item, .. } in Self::items(state).await {
+            item.age += 1;
+            item.pickup_delay = item.pickup_delay.saturating_sub(1);
+            if item.age >= ItemEntity::DESPAWN_AGE {
+                despawned.push(id);
+                continue;
+            }
+
+            *state.world.get_component_storage().get_mut::<ItemEntity>(id).await? = item;
+        }
+
+        // The entity broadcaster despawns them for everyone once they're gone
+        for id in despawned {
+            state.world.delete_entity(id).await?;
+        }
+        Ok(())
+    }
+
+    async fn merge_items(state: &GlobalState) -> Result<()> {
+        let mut items = Self::items(state).await;
+        let mut changed = vec![false; items.len()];
+        for i in 0..items.len() {
+            for j in i + 1..items.len() {
+                let (left, right) = items.split_at_mut(j);
+                let (a, b) = (&mut left[i], &mut right[0]);
+                if a.item.item.count <= 0
+                    || b.item.item.count <= 0
+                    || a.dimension != b.dimension
+                    || !in_merge_reach(a.motion.position, b.motion.position)
+                {
+                    continue;
+                }
+                let max_stack_size = state.items.max_stack_size(a.item.item.id);
+                if a.item.merge(&mut b.item, max_stack_size)
+                    || b.item.merge(&mut a.item, max_stack_size)
+                {
+                    changed[i] = true;
+                    changed[j] = true;
+                }
+            }
+        }
+
+        for (item, _) in items.into_iter().zip(changed).filter(|(_, changed)| *changed) {
+            if item.item.item.count <= 0 {
+                state.world.delete_entity(item.id).await?;
+            } else {
+                update_item(state, item.id, item.item).await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn pick_up_items(state: &GlobalState) -> Result<()> {
+        let players = collectors(state).await;
+        if players.is_empty() {
+            return Ok(());
+        }
+
+        for mut item in Self::items(state).await {
+            if item.item.pickup_delay > 0 {
+                continue;
+            }
+            let picked_up_by = players.iter().filter(|(_, position, dimension)| {
+                *dimension == item.dimension && in_pickup_reach(*position, item.motion.position)
+            });
+            let mut taken = 0;
+            for &(player, _, _) in picked_up_by {
+                let added = Self::pick_up(state, player, &item).await?;
+                if added == 0 {
+                    continue;
+                }
+                taken += added;
+                item.item.item.count -= added;
+                let packet = TakeItemEntity::new(item.id, player, added);
+                broadcast_to_viewers(packet, item.id, state).await?;
+                if item.item.item.count <= 0 {
+                    break;
+                }
+            }
+
+            if taken == 0 {
+                continue;
+            }
+            if item.item.item.count <= 0 {
+                state.world.delete_entity(item.id).await?;
+            } else {
+                update_item(state, item.id, item.item).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Puts as much of an item in a player's inventory as fits. Returns how many went in.
+    async fn pick_up(state: &GlobalState, player: usize, item: &Item) -> Result<i8> {
+        let max_stack_size = state.items.max_stack_size(item.item.item.id);
+        let (added, content) = {
+            let mut inventory = state
+                .world
+                .get_component_storage()
+                .get_mut_or_insert_with::<Inventory>(player, Default::default)
+                .await;
+            let added = inventory.add_as_much(&item.item.item, max_stack_size);
+            if added == 0 {
+                return Ok(0);
+            }
+            inventory.next_state_id();
+            (added, SetContainerContent::player_inventory(&inventory))
+        };
+
+        match state.connections.get_connection(player) {
+            Ok(conn) => conn.read().await.send_packet(content).await?,
+            // They've just left, and still got the item
+            Err(e) => debug!("Couldn't send {} their inventory: {}", player, e),
+        }
+        Ok(added)
+    }
+}
@@ -1,106 +1,111 @@
+use std::sync::Arc;
+
 use async_trait::async_trait;
-use tokio::sync::RwLockReadGuard;
+use tokio::sync::RwLock;
 use tracing::{trace, warn};
 
 use ferrumc_macros::AutoGenName;
 
+use crate::ecs::access::Access;
 use crate::net::packets::outgoing::keep_alive::KeepAlivePacketOut;
-use crate::net::systems::System;
-use crate::net::{Connection, ConnectionWrapper};
+use crate::net::systems::game_loop::TICKS_PER_SECOND;
+use crate::net::systems::TickedSystem;
+use crate::net::{kick, Connection, ConnectionWrapper};
 use crate::state::GlobalState;
 use crate::utils::components::keep_alive::KeepAlive;
 use crate::utils::components::player::Player;
+use crate::utils::prelude::*;
+use crate::utils::text_component::TextComponent;
+
+const SEND_INTERVAL_TICKS: u64 = 15 * TICKS_PER_SECOND;
+const TIMEOUT_CHECK_INTERVAL_TICKS: u64 = 5 * TICKS_PER_SECOND;
 
 #[derive(AutoGenName)]
 pub struct KeepAliveSystem;
 
 #[async_trait]
-impl System for KeepAliveSystem {
-    async fn run(&self, state: GlobalState) {
-        let sender = KeepAliveSystem::sender(state.clone());
-        let receiver = KeepAliveSystem::receiver(state.clone());
-        tokio::join!(sender, receiver);
+impl TickedSystem for KeepAliveSystem {
+    async fn tick(&self, state: GlobalState, tick: u64) -> Result<()> {
+        if tick.is_multiple_of(SEND_INTERVAL_TICKS) {
+            KeepAliveSystem::sender(state.clone()).await;
+        }
+        if tick.is_multiple_of(TIMEOUT_CHECK_INTERVAL_TICKS) {
+            KeepAliveSystem::receiver(state).await;
+        }
+        Ok(())
     }
 
     fn name(&self) -> &'static str {
         Self::type_name()
     }
+
+    fn access(&self) -> Access {
+        Access::new()
+            .read::<Player>()
+            .read::<ConnectionWrapper>()
+            .write::<KeepAlive>()
+    }
 }
 impl KeepAliveSystem {
     async fn sender(state: GlobalState) {
-        let mut interval = tokio::time::interval(std::time::Duration::from_secs(15));
+        let mut timed_out = Vec::new();
         let mut query = state
             .world
             .query::<(&Player, &mut KeepAlive, &ConnectionWrapper)>();
 
-        loop {
-            interval.tick().await;
-
-            while let Some((_, (player, mut keep_alive, conn))) = query.next().await {
-                if keep_alive.last_sent.elapsed().as_secs() > 30 {
-                    let conn = conn.0.read().await;
-                    warn!("Dropping connection {} due to inactivity", conn.id);
-                    if let Err(err) = conn.drop_connection(state.clone()).await {
-                        warn!(
-                            "Error dropping connection {:?}: {:?}",
-                            conn.player_uuid, err
-                        );
-                    }
-                    continue;
-                }
-
-                keep_alive.data += 1;
-                keep_alive.last_sent = std::time::Instant::now();
-
-                let keep_alive_out = KeepAlivePacketOut::new_auto(keep_alive.data);
-                let conn = conn.0.write().await;
-
-                trace!("Sending keep alive packet to player: {:?}", player);
-                if let Err(e) = conn.send_packet(keep_alive_out).await {
-                    warn!("Error sending keep alive packet: {:?}", e);
-                }
+        while let Some((_, (player, mut keep_alive, conn))) = query.next().await {
+            if keep_alive.last_sent.elapsed().as_secs() > 30 {
+                timed_out.push((Arc::clone(&conn.0), player.username.clone()));
+                continue;
             }
+
+            keep_alive.data += 1;
+            keep_alive.last_sent = std::time::Instant::now();
+
+            let keep_alive_out = KeepAlivePacketOut::new_auto(keep_alive.data);
+            let conn = conn.0.read().await;
+
+            trace!("Sending keep alive packet to player: {:?}", player);
+            if let Err(e) = conn.queue_packet(keep_alive_out).await {
+                warn!("Error sending keep alive packet: {:?}", e);
+            }
+        }
+
+        for (conn, username) in timed_out {
+            Self::drop_connection(&conn, &username, state.clone()).await;
         }
     }
     async fn receiver(state: GlobalState) {
-        let mut interval = tokio::time::interval(std::time::Duration::from_secs(5));
+        let mut timed_out = Vec::new();
         let mut query = state.world.query::<(&KeepAlive, &ConnectionWrapper)>();
 
-        loop {
-            interval.tick().await;
-
-            while let Some((_, (keep_alive, conn_wrapper))) = query.next().await {
-                if keep_alive.last_sent.elapsed().as_secs() <= 30 {
-                    continue;
-                }
-
-                let conn = conn_wrapper.0.read().await;
-                let player = state.world.get_component::<Player>(conn.id).await;
+        while let Some((id, (keep_alive, conn_wrapper))) = query.next().await {
+            if keep_alive.last_sent.elapsed().as_secs() <= 30 {
+                continue;
+            }
 
-                let username = player
-                    .as_ref()
-                    .map(|p| p.username.as_str())
-                    .unwrap_or("Unknown<!>Player");
+            let player = state.world.get_component::<Player>(id).await;
+            let username = player
+                .as_ref()
+                .map(|p| p.username.clone())
+                .unwrap_or_else(|_| "Unknown<!>Player".to_string());
+            timed_out.push((Arc::clone(&conn_wrapper.0), username));
+        }
 
-                Self::drop_connection(conn, username, state.clone()).await;
-            }
+        for (conn, username) in timed_out {
+            Self::drop_connection(&conn, &username, state.clone()).await;
         }
     }
 
-    async fn drop_connection(
-        conn: RwLockReadGuard<'_, Connection>,
-        username: &str,
-        state: GlobalState,
-    ) {
+    /// Kicks a player that timed out. Done after the query, since kicking them deletes their
+    /// entity.
+    async fn drop_connection(conn: &RwLock<Connection>, username: &str, state: GlobalState) {
         warn!(
             "Dropping player `{}`'s connection due to inactivity",
             username
         );
-        if let Err(err) = conn.drop_connection(state).await {
-            warn!(
-                "Error dropping connection {:?}: {:?}",
-                conn.player_uuid, err
-            );
+        if let Err(err) = kick(conn, &TextComponent::text("Timed out"), state).await {
+            warn!("Error dropping {}'s connection: {:?}", username, err);
         }
     }
 }
@@ -0,0 +1,117 @@
+use async_trait::async_trait;
+use ferrumc_macros::AutoGenName;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tracing::{error, info, trace};
+
+use crate::net::metrics::METRICS;
+use crate::net::systems::System;
+use crate::state::GlobalState;
+use crate::utils::config::get_global_config;
+use crate::utils::prelude::*;
+
+/// Requests are a single line and a few headers, so anything bigger isn't one.
+const MAX_REQUEST_LENGTH: usize = 8192;
+
+/// Serves [metrics](crate::net::metrics) for Prometheus at `/metrics`, if `enable_metrics` is on.
+#[derive(AutoGenName)]
+pub struct MetricsServer;
+
+#[async_trait]
+impl System for MetricsServer {
+    async fn run(&self, state: GlobalState) {
+        let config = get_global_config();
+        if !config.enable_metrics {
+            return;
+        }
+
+        let address = format!("{}:{}", config.host, config.metrics_port);
+        if let Err(e) = Self::serve(state, &address).await {
+            error!("Metrics server on {} stopped: {}", address, e);
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        Self::type_name()
+    }
+}
+
+impl MetricsServer {
+    async fn serve(state: GlobalState, address: &str) -> Result<()> {
+        let listener = TcpListener::bind(address).await?;
+        info!("Metrics server listening on {}", listener.local_addr()?);
+
+        loop {
+            let (stream, address) = listener.accept().await?;
+            let state = state.clone();
+            tokio::spawn(async move {
+                if let Err(e) = Self::respond(stream, &state).await {
+                    trace!("Failed to answer a metrics request from {}: {}", address, e);
+                }
+            });
+        }
+    }
+
+    async fn respond(mut stream: TcpStream, state: &GlobalState) -> Result<()> {
+        let mut request = Vec::new();
+        let mut buf = [0u8; 1024];
+        while !request.windows(4).any(|end| end == b"\r\n\r\n") {
+            let read = stream.read(&mut buf).await?;
+            if read == 0 || request.len() + read > MAX_REQUEST_LENGTH {
+                return Ok(());
+            }
+            request.extend_from_slice(&buf[..read]);
+        }
+
+        let line = request
+            .split(|&byte| byte == b'\r')
+            .next()
+            .unwrap_or_default();
+        let (status, body) = match parse_request_line(line) {
+            Some(("GET", "/metrics")) => ("200 OK", METRICS.render(state).await),
+            Some(("GET", _)) => ("404 Not Found", "Not found\n".to_string()),
+            _ => (
+                "405 Method Not Allowed",
+                "Only GET is allowed\n".to_string(),
+            ),
+        };
+
+        let response = format!(
+            "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status,
+            body.len(),
+            body
+        );
+        stream.write_all(response.as_bytes()).await?;
+        stream.shutdown().await?;
+        Ok(())
+    }
+}
+
+/// The method and path, ignoring any query string.
+fn parse_request_line(line: &[u8]) -> Option<(&str, &str)> {
+    let line = std::str::from_utf8(line).ok()?;
+    let mut parts = line.split(' ');
+    let method = parts.next()?;
+    let target = parts.next()?;
+    let path = target.split('?').next()?;
+    Some((method, path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_request_line() {
+        assert_eq!(
+            parse_request_line(b"GET /metrics HTTP/1.1"),
+            Some(("GET", "/metrics"))
+        );
+        assert_eq!(
+            parse_request_line(b"GET /metrics?format=text HTTP/1.1"),
+            Some(("GET", "/metrics"))
+        );
+        assert_eq!(parse_request_line(b"GET"), None);
+    }
+}
@@ -0,0 +1,48 @@
+use async_trait::async_trait;
+
+use ferrumc_macros::AutoGenName;
+
+use crate::database::world_meta::WorldMeta;
+use crate::net::systems::TickedSystem;
+use crate::state::GlobalState;
+use crate::utils::config::get_global_config;
+use crate::utils::prelude::*;
+use crate::world::game_rules;
+use crate::world::mob_spawning::{
+    despawn_far_monsters, spawn_mobs, CREATURE_INTERVAL, MONSTER_INTERVAL,
+};
+use crate::world::mobs::MobCategory;
+
+/// Spawns mobs around players and despawns monsters nobody's near, see
+/// [mob_spawning](crate::world::mob_spawning). Spawning needs both `natural_spawning` in the config
+/// and the `doMobSpawning` gamerule.
+#[derive(AutoGenName)]
+pub struct MobSpawner;
+
+#[async_trait]
+impl TickedSystem for MobSpawner {
+    async fn tick(&self, state: GlobalState, tick: u64) -> Result<()> {
+        if !tick.is_multiple_of(MONSTER_INTERVAL) {
+            return Ok(());
+        }
+        despawn_far_monsters(&state).await?;
+        let do_mob_spawning = state
+            .world
+            .resource::<WorldMeta>()
+            .await?
+            .game_rule_enabled(&game_rules::DO_MOB_SPAWNING);
+        if !get_global_config().mobs.natural_spawning || !do_mob_spawning {
+            return Ok(());
+        }
+
+        spawn_mobs(&state, MobCategory::Monster).await?;
+        if tick.is_multiple_of(CREATURE_INTERVAL) {
+            spawn_mobs(&state, MobCategory::Creature).await?;
+        }
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        Self::type_name()
+    }
+}
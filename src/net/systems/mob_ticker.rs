@@ -0,0 +1,79 @@
+use async_trait::async_trait;
+
+use ferrumc_macros::AutoGenName;
+
+use crate::net::systems::TickedSystem;
+use crate::state::GlobalState;
+use crate::utils::components::dimension::CurrentDimension;
+use crate::utils::components::mob::Mob;
+use crate::utils::components::motion::Motion;
+use crate::utils::components::rotation::Rotation;
+use crate::utils::prelude::*;
+use crate::world::dimension::Dimension;
+use crate::world::experience_orbs::drop_experience;
+use crate::world::item_entities::{collectors, nearest};
+use crate::world::mobs::{tick, DEATH_TICKS};
+
+/// Runs every mob's AI, and removes dead ones once they've finished dying, every tick. They're
+/// moved by [EntityPhysics](super::entity_physics::EntityPhysics).
+#[derive(AutoGenName)]
+pub struct MobTicker;
+
+#[async_trait]
+impl TickedSystem for MobTicker {
+    async fn tick(&self, state: GlobalState, _tick: u64) -> Result<()> {
+        let mobs = {
+            let query = state.world.query::<(
+                &Mob,
+                &Motion,
+                Option<&Rotation>,
+                Option<&CurrentDimension>,
+            )>();
+            query
+                .iter()
+                .await
+                .map(|(id, (mob, motion, rotation, dimension))| {
+                    let rotation = rotation
+                        .map(|rotation| rotation.clone())
+                        .unwrap_or_else(|| Rotation::new(0.0, 0.0));
+                    let dimension = dimension.map_or(Dimension::Overworld, |d| d.dimension);
+                    (id, mob.clone(), *motion, rotation, dimension)
+                })
+                .collect::<Vec<_>>()
+        };
+        if mobs.is_empty() {
+            return Ok(());
+        }
+
+        let players = collectors(&state).await;
+        for (id, mut mob, mut motion, mut rotation, dimension) in mobs {
+            let player = nearest(&players, dimension, motion.position);
+            tick(&mut mob, &mut motion, &mut rotation, player, rand::random::<f64>);
+
+            if let Some(dead_for) = &mut mob.dead_for {
+                *dead_for += 1;
+                if *dead_for >= DEATH_TICKS {
+                    state.world.delete_entity(id).await?;
+                    let experience = mob.mob_type.experience;
+                    drop_experience(&state, dimension, motion.position, experience).await;
+                    continue;
+                }
+            }
+
+            let component_storage = state.world.get_component_storage();
+            {
+                let mut stored = component_storage.get_mut::<Mob>(id).await?;
+                // It might have been killed since the snapshot was taken
+                mob.dead_for = mob.dead_for.or(stored.dead_for);
+                *stored = mob;
+            }
+            *component_storage.get_mut::<Motion>(id).await? = motion;
+            component_storage.insert(id, rotation);
+        }
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        Self::type_name()
+    }
+}
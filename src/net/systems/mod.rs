@@ -1,14 +1,34 @@
 use async_trait::async_trait;
 use futures::stream::FuturesUnordered;
+use tokio::task::AbortHandle;
 use tracing::{debug_span, info, Instrument};
 
+use crate::ecs::access::Access;
+use crate::shutdown;
 use crate::state::GlobalState;
 use crate::utils::prelude::*;
 
+pub mod chunk_flusher;
 pub mod chunk_sender;
 pub mod connection_handler;
+pub mod entity_broadcaster;
+pub mod entity_physics;
+pub mod experience_orb_ticker;
+pub mod game_loop;
+pub mod health_ticker;
+pub mod item_entity_ticker;
 pub mod keep_alive_system;
+pub mod metrics_server;
+pub mod mob_spawner;
+pub mod mob_ticker;
+pub mod player_saver;
+pub mod projectile_ticker;
+pub mod query_server;
+pub mod reload_signal;
+pub mod tab_list_updater;
 pub mod tick_system;
+pub mod time_ticker;
+pub mod weather_ticker;
 
 #[async_trait]
 pub trait System: Send + Sync {
@@ -17,13 +37,50 @@ pub trait System: Send + Sync {
     async fn kill(&self) {}
 }
 
+/// A system that's run once per tick by the [GameLoop](game_loop::GameLoop), rather than in its own task.
+#[async_trait]
+pub trait TickedSystem: Send + Sync {
+    /// `tick` is the number of ticks that have run so far, for systems that only need to run every so often.
+    async fn tick(&self, state: GlobalState, tick: u64) -> Result<()>;
+    fn name(&self) -> &'static str;
+    /// What the system reads and writes. Systems whose access doesn't conflict run at the same
+    /// time, the rest in order. Systems that don't say run on their own.
+    fn access(&self) -> Access {
+        Access::exclusive()
+    }
+}
+
 pub static ALL_SYSTEMS: &[&dyn System] = &[
     &tick_system::TickSystem,
+    &game_loop::GameLoop,
+    &connection_handler::ConnectionHandler,
+    &reload_signal::ReloadSignal,
+    &query_server::QueryServer,
+    &metrics_server::MetricsServer,
+    &chunk_flusher::ChunkFlusher,
+    &player_saver::PlayerSaver,
+];
+
+/// Run in this order every tick, apart from ones that can run alongside each other, see
+/// [TickedSystem::access].
+pub static TICKED_SYSTEMS: &[&dyn TickedSystem] = &[
     &keep_alive_system::KeepAliveSystem,
+    &time_ticker::TimeTicker,
+    &weather_ticker::WeatherTicker,
     &chunk_sender::ChunkSender,
-    &connection_handler::ConnectionHandler,
+    &item_entity_ticker::ItemEntityTicker,
+    &experience_orb_ticker::ExperienceOrbTicker,
+    &mob_spawner::MobSpawner,
+    &mob_ticker::MobTicker,
+    &entity_physics::EntityPhysics,
+    &projectile_ticker::ProjectileTicker,
+    &entity_broadcaster::EntityBroadcaster,
+    &tab_list_updater::TabListUpdater,
+    &health_ticker::HealthTicker,
 ];
 
+/// Runs every system until they all finish, or until a [shutdown](crate::shutdown) is requested,
+/// which stops them.
 pub async fn start_all_systems(state: GlobalState) -> Result<()> {
     let handles = FuturesUnordered::new();
     for system in ALL_SYSTEMS {
@@ -37,7 +94,17 @@ pub async fn start_all_systems(state: GlobalState) -> Result<()> {
         handles.push(handle);
     }
 
-    futures::future::join_all(handles).await;
+    let aborts = handles.iter().map(|handle| handle.abort_handle()).collect::<Vec<_>>();
+    let all_finished = futures::future::join_all(handles);
+    tokio::pin!(all_finished);
+    tokio::select! {
+        _ = &mut all_finished => {}
+        _ = shutdown::requested() => {
+            aborts.iter().for_each(AbortHandle::abort);
+            all_finished.await;
+            info!("Stopped all systems");
+        }
+    }
 
     Ok(())
 }
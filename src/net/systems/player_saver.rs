@@ -0,0 +1,51 @@
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use ferrumc_macros::AutoGenName;
+use tracing::{debug, error, info};
+
+use crate::database::players::save_all_players;
+use crate::net::systems::System;
+use crate::state::GlobalState;
+use crate::utils::config::get_global_config;
+
+/// Set when the system starts, so the players can still be saved when it's killed.
+static STATE: OnceLock<GlobalState> = OnceLock::new();
+
+/// Saves every online player's data every `player_save_interval` seconds, and once more when the
+/// server shuts down. Players are also saved when they leave, even while `/save-off` is in effect.
+#[derive(AutoGenName)]
+pub struct PlayerSaver;
+
+#[async_trait]
+impl System for PlayerSaver {
+    async fn run(&self, state: GlobalState) {
+        let _ = STATE.set(state.clone());
+        loop {
+            let interval = get_global_config().database.player_save_interval.max(1);
+            tokio::time::sleep(Duration::from_secs(interval)).await;
+            if !state.database.autosave_enabled() {
+                continue;
+            }
+            match save_all_players(&state).await {
+                Ok(count) => debug!("Saved {} players", count),
+                Err(e) => error!("Failed to save players: {}", e),
+            }
+        }
+    }
+
+    async fn kill(&self) {
+        let Some(state) = STATE.get() else {
+            return;
+        };
+        match save_all_players(state).await {
+            Ok(count) => info!("Saved {} players", count),
+            Err(e) => error!("Failed to save players: {}", e),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        Self::type_name()
+    }
+}
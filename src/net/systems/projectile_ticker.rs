@@ -0,0 +1,255 @@
+use async_trait::async_trait;
+use tracing::debug;
+
+use ferrumc_macros::AutoGenName;
+
+use crate::events::creation::dispatcher::EventDispatcherExt;
+use crate::events::creation::event::{Cancellation, Event};
+use crate::events::projectile_events::ProjectileHitEvent;
+use crate::net::packets::outgoing::entity_event::{statuses, EntityEvent};
+use crate::net::packets::outgoing::set_container_content::SetContainerContent;
+use crate::net::packets::outgoing::take_item_entity::TakeItemEntity;
+use crate::net::systems::entity_physics::VOID_DEPTH;
+use crate::net::systems::TickedSystem;
+use crate::net::utils::broadcast::broadcast_to_viewers;
+use crate::state::GlobalState;
+use crate::utils::components::body::Body;
+use crate::utils::components::dimension::CurrentDimension;
+use crate::utils::components::health::Health;
+use crate::utils::components::inventory::Inventory;
+use crate::utils::components::motion::Motion;
+use crate::utils::components::projectile::Projectile;
+use crate::utils::encoding::slot::ItemStack;
+use crate::utils::prelude::*;
+use crate::world::collision::Aabb;
+use crate::world::dimension::Dimension;
+use crate::world::item_entities::{collectors, in_pickup_reach};
+use crate::world::physics::{step, BlockShapes};
+use crate::world::projectiles::{facing, first_hit, ProjectileKind};
+
+/// Projectiles can't hit whoever shot them for this many ticks, so they don't hit them on the way
+/// out.
+const SHOOTER_GRACE_TICKS: u32 = 5;
+/// Arrows stuck in a block despawn after this many ticks, a minute.
+const STUCK_DESPAWN_TICKS: u32 = 1200;
+/// How tall a player's hitbox is. Positions are whole blocks, so it's as wide as a block, since
+/// the player could be anywhere in theirs.
+const PLAYER_HEIGHT: f64 = 1.8;
+
+/// Moves projectiles by a tick of [physics](crate::world::physics), hits whatever's in their way,
+/// lets players pick up arrows stuck in blocks and despawns ones that have been there too long,
+/// every tick. See [projectiles](crate::world::projectiles).
+#[derive(AutoGenName)]
+pub struct ProjectileTicker;
+
+/// A snapshot of a projectile, so no component locks are held while changing the world.
+struct Flying {
+    id: usize,
+    projectile: Projectile,
+    motion: Motion,
+    dimension: Dimension,
+}
+
+#[async_trait]
+impl TickedSystem for ProjectileTicker {
+    async fn tick(&self, state: GlobalState, _tick: u64) -> Result<()> {
+        let projectiles = {
+            let query = state
+                .world
+                .query::<(&Projectile, &Motion, Option<&CurrentDimension>)>();
+            query
+                .iter()
+                .await
+                .map(|(id, (projectile, motion, dimension))| Flying {
+                    id,
+                    projectile: *projectile,
+                    motion: *motion,
+                    dimension: dimension.map_or(Dimension::Overworld, |d| d.dimension),
+                })
+                .collect::<Vec<_>>()
+        };
+        if projectiles.is_empty() {
+            return Ok(());
+        }
+
+        let targets = Self::targets(&state).await;
+        let mut blocks = BlockShapes::new(&state);
+        for flying in projectiles {
+            if flying.projectile.stuck_for.is_some() {
+                Self::lie(&state, flying).await?;
+            } else {
+                Self::fly(&state, &mut blocks, flying, &targets).await?;
+            }
+        }
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        Self::type_name()
+    }
+}
+
+impl ProjectileTicker {
+    /// Everything projectiles can hit, which is any player or mob that's alive.
+    async fn targets(state: &GlobalState) -> Vec<(usize, Aabb, Dimension)> {
+        let mut targets = collectors(state)
+            .await
+            .into_iter()
+            .map(|(id, position, dimension)| {
+                (id, Aabb::standing_at(position, 1.0, PLAYER_HEIGHT), dimension)
+            })
+            .collect::<Vec<_>>();
+        let query = state
+            .world
+            .query::<(&Health, &Motion, &Body, Option<&CurrentDimension>)>();
+        let mobs = query
+            .iter()
+            .await
+            .filter(|(_, (health, _, _, _))| !health.is_dead())
+            .map(|(id, (_, motion, body, dimension))| {
+                let dimension = dimension.map_or(Dimension::Overworld, |d| d.dimension);
+                (id, body.aabb(motion.position), dimension)
+            });
+        targets.extend(mobs);
+        targets
+    }
+
+    /// Moves a projectile on, and hits the first entity in its way, or the block that stopped it.
+    async fn fly(
+        state: &GlobalState,
+        blocks: &mut BlockShapes<'_>,
+        Flying {
+            id,
+            mut projectile,
+            mut motion,
+            dimension,
+        }: Flying,
+        targets: &[(usize, Aabb, Dimension)],
+    ) -> Result<()> {
+        projectile.age += 1;
+        let body = projectile.kind.body();
+        let before = motion;
+        let shapes = blocks.in_reach(dimension, &motion, &body).await;
+        let stopped = step(&mut motion, &body, |x, y, z| {
+            shapes.get(&(x, y, z)).copied().unwrap_or_default()
+        });
+        if motion.position.1 < (dimension.min_y() - VOID_DEPTH) as f64 {
+            state.world.delete_entity(id).await?;
+            return Ok(());
+        }
+
+        let in_the_way = targets
+            .iter()
+            .filter(|(target, _, target_dimension)| {
+                *target_dimension == dimension
+                    && (projectile.shooter != Some(*target) || projectile.age > SHOOTER_GRACE_TICKS)
+            })
+            .map(|(target, aabb, _)| (*target, *aabb))
+            .collect::<Vec<_>>();
+        let middle = |(x, y, z): (f64, f64, f64)| (x, y + body.height / 2.0, z);
+        let hit = first_hit(
+            middle(before.position),
+            middle(motion.position),
+            body.width / 2.0,
+            &in_the_way,
+        );
+        if let Some((target, _)) = hit {
+            let event = ProjectileHitEvent::new(
+                id,
+                projectile,
+                before.velocity,
+                target,
+                Cancellation::default(),
+            );
+            let event = state.dispatch_event(event).await;
+            if !event.is_cancelled() {
+                return Self::break_projectile(state, id, projectile.kind).await;
+            }
+            debug!("{} hitting {} was cancelled", id, target);
+        } else if stopped.contains(&true) {
+            if projectile.kind != ProjectileKind::Arrow {
+                return Self::break_projectile(state, id, projectile.kind).await;
+            }
+            motion.velocity = (0.0, 0.0, 0.0);
+            projectile.stuck_for = Some(0);
+        }
+
+        let component_storage = state.world.get_component_storage();
+        *component_storage.get_mut::<Motion>(id).await? = motion;
+        *component_storage.get_mut::<Projectile>(id).await? = projectile;
+        if projectile.stuck_for.is_none() {
+            component_storage.insert(id, facing(motion.velocity));
+        }
+        Ok(())
+    }
+
+    /// Removes a projectile that's hit something. Snowballs burst into particles.
+    async fn break_projectile(state: &GlobalState, id: usize, kind: ProjectileKind) -> Result<()> {
+        if kind == ProjectileKind::Snowball {
+            let event = EntityEvent::new_auto(id as i32, statuses::PROJECTILE_BREAK);
+            broadcast_to_viewers(event, id, state).await?;
+        }
+        // The entity broadcaster despawns it for everyone once it's gone
+        state.world.delete_entity(id).await
+    }
+
+    /// Ages an arrow stuck in a block, and lets a player walking over it pick it up.
+    async fn lie(
+        state: &GlobalState,
+        Flying {
+            id,
+            mut projectile,
+            motion,
+            dimension,
+        }: Flying,
+    ) -> Result<()> {
+        let stuck_for = projectile.stuck_for.unwrap_or_default() + 1;
+        if stuck_for >= STUCK_DESPAWN_TICKS {
+            return state.world.delete_entity(id).await;
+        }
+        projectile.stuck_for = Some(stuck_for);
+
+        if projectile.pickup {
+            let players = collectors(state).await;
+            let picked_up_by = players.iter().filter(|(_, position, player_dimension)| {
+                *player_dimension == dimension && in_pickup_reach(*position, motion.position)
+            });
+            for &(player, _, _) in picked_up_by {
+                if Self::pick_up(state, player).await? {
+                    let packet = TakeItemEntity::new(id, player, 1);
+                    broadcast_to_viewers(packet, id, state).await?;
+                    return state.world.delete_entity(id).await;
+                }
+            }
+        }
+        *state.world.get_component_storage().get_mut::<Projectile>(id).await? = projectile;
+        Ok(())
+    }
+
+    /// Gives a player an arrow, if they've got room for it. Returns whether they had.
+    async fn pick_up(state: &GlobalState, player: usize) -> Result<bool> {
+        let Some(arrow) = state.items.id("minecraft:arrow") else {
+            return Ok(false);
+        };
+        let max_stack_size = state.items.max_stack_size(arrow);
+        let content = {
+            let mut inventory = state
+                .world
+                .get_component_storage()
+                .get_mut_or_insert_with::<Inventory>(player, Default::default)
+                .await;
+            if !inventory.add_item(&ItemStack::new(arrow, 1), max_stack_size) {
+                return Ok(false);
+            }
+            inventory.next_state_id();
+            SetContainerContent::player_inventory(&inventory)
+        };
+
+        match state.connections.get_connection(player) {
+            Ok(conn) => conn.read().await.send_packet(content).await?,
+            // They've just left, and still got the arrow
+            Err(e) => debug!("Couldn't send {} their inventory: {}", player, e),
+        }
+        Ok(true)
+    }
+}
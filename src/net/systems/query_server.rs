@@ -0,0 +1,135 @@
+use std::net::SocketAddr;
+use std::time::Instant;
+
+use async_trait::async_trait;
+use ferrumc_macros::AutoGenName;
+use tokio::net::UdpSocket;
+use tracing::{debug, error, info, trace, warn};
+
+use crate::net::query::{
+    basic_stat_response, full_stat_response, handshake_response, ChallengeTokens, QueryRequest,
+    ServerInfo, TOKEN_LIFETIME,
+};
+use crate::net::systems::System;
+use crate::state::GlobalState;
+use crate::utils::components::player::Player;
+use crate::utils::config::get_global_config;
+use crate::utils::prelude::*;
+
+/// Shown as the server's version to query clients.
+const QUERY_VERSION: &str = "1.20.1";
+/// There's no plugin system yet, so this is just the server software.
+const QUERY_PLUGINS: &str = "FerrumC";
+
+/// Answers GS4 Query requests over UDP, if `enable_query` is on. See [crate::net::query].
+#[derive(AutoGenName)]
+pub struct QueryServer;
+
+#[async_trait]
+impl System for QueryServer {
+    async fn run(&self, state: GlobalState) {
+        let config = get_global_config();
+        if !config.enable_query {
+            return;
+        }
+
+        let address = format!("{}:{}", config.host, config.query_port);
+        if let Err(e) = Self::serve(state, &address).await {
+            error!("Query server on {} stopped: {}", address, e);
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        Self::type_name()
+    }
+}
+
+impl QueryServer {
+    async fn serve(state: GlobalState, address: &str) -> Result<()> {
+        let socket = UdpSocket::bind(address).await?;
+        info!("Query server listening on {}", socket.local_addr()?);
+
+        let tokens = ChallengeTokens::new();
+        let mut last_prune = Instant::now();
+        let mut buf = [0u8; 1460];
+
+        loop {
+            let (len, sender) = match socket.recv_from(&mut buf).await {
+                Ok(received) => received,
+                Err(e) => {
+                    warn!("Failed to receive a query packet: {}", e);
+                    continue;
+                }
+            };
+
+            if last_prune.elapsed() > TOKEN_LIFETIME {
+                tokens.prune();
+                last_prune = Instant::now();
+            }
+
+            let Some(request) = QueryRequest::parse(&buf[..len]) else {
+                trace!("Ignoring an invalid query packet from {}", sender);
+                continue;
+            };
+
+            let response = match Self::respond(&state, &tokens, request, sender).await {
+                Ok(Some(response)) => response,
+                Ok(None) => continue,
+                Err(e) => {
+                    warn!("Failed to answer a query from {}: {}", sender, e);
+                    continue;
+                }
+            };
+            if let Err(e) = socket.send_to(&response, sender).await {
+                warn!("Failed to answer a query from {}: {}", sender, e);
+            }
+        }
+    }
+
+    async fn respond(
+        state: &GlobalState,
+        tokens: &ChallengeTokens,
+        request: QueryRequest,
+        sender: SocketAddr,
+    ) -> Result<Option<Vec<u8>>> {
+        let response = match request {
+            QueryRequest::Handshake { session_id } => {
+                handshake_response(session_id, tokens.issue(sender))
+            }
+            QueryRequest::BasicStat { session_id, token } if tokens.is_valid(sender, token) => {
+                basic_stat_response(session_id, &Self::server_info(state).await?)
+            }
+            QueryRequest::FullStat { session_id, token } if tokens.is_valid(sender, token) => {
+                full_stat_response(session_id, &Self::server_info(state).await?)
+            }
+            _ => {
+                debug!("Ignoring a query from {} with an invalid token", sender);
+                return Ok(None);
+            }
+        };
+        Ok(Some(response))
+    }
+
+    async fn server_info(state: &GlobalState) -> Result<ServerInfo> {
+        let config = get_global_config();
+        let address = state.server_stream.local_addr()?;
+
+        let query = state.world.query::<&Player>();
+        let players = query
+            .iter()
+            .await
+            .map(|(_, player)| player.username.clone())
+            .collect();
+
+        Ok(ServerInfo {
+            motd: config.motd.first().cloned().unwrap_or_default(),
+            version: QUERY_VERSION.to_string(),
+            plugins: QUERY_PLUGINS.to_string(),
+            map: config.world.clone(),
+            max_players: config.max_players,
+            players,
+            host_ip: address.ip().to_string(),
+            host_port: address.port(),
+        })
+    }
+}
@@ -0,0 +1,42 @@
+use async_trait::async_trait;
+use ferrumc_macros::AutoGenName;
+use tracing::{error, info};
+
+use crate::events::config_events::reload_config;
+use crate::net::systems::System;
+use crate::state::GlobalState;
+
+/// Reloads the config whenever the process gets a SIGHUP, e.g. from `kill -HUP`. Does nothing on
+/// platforms without signals.
+#[derive(AutoGenName)]
+pub struct ReloadSignal;
+
+#[async_trait]
+impl System for ReloadSignal {
+    #[cfg(unix)]
+    async fn run(&self, state: GlobalState) {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let mut hangups = match signal(SignalKind::hangup()) {
+            Ok(hangups) => hangups,
+            Err(e) => {
+                error!("Failed to listen for SIGHUP: {}", e);
+                return;
+            }
+        };
+
+        while hangups.recv().await.is_some() {
+            info!("Got SIGHUP, reloading the config");
+            if let Err(e) = reload_config(&state).await {
+                error!("Failed to reload the config: {}", e);
+            }
+        }
+    }
+
+    #[cfg(not(unix))]
+    async fn run(&self, _state: GlobalState) {}
+
+    fn name(&self) -> &'static str {
+        Self::type_name()
+    }
+}
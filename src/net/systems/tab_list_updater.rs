@@ -0,0 +1,45 @@
+use async_trait::async_trait;
+
+use ferrumc_macros::AutoGenName;
+
+use crate::ecs::access::Access;
+use crate::net::systems::game_loop::TICKS_PER_SECOND;
+use crate::net::systems::TickedSystem;
+use crate::net::tab_list::update_latency;
+use crate::net::ConnectionWrapper;
+use crate::state::GlobalState;
+use crate::utils::components::display_name::DisplayName;
+use crate::utils::components::game_mode::GameMode;
+use crate::utils::components::latency::Latency;
+use crate::utils::components::player::Player;
+use crate::utils::prelude::*;
+
+/// Same as vanilla.
+const LATENCY_INTERVAL_TICKS: u64 = 30 * TICKS_PER_SECOND;
+
+/// Sends everyone's latency to the [tab list](crate::net::tab_list) every so often.
+#[derive(AutoGenName)]
+pub struct TabListUpdater;
+
+#[async_trait]
+impl TickedSystem for TabListUpdater {
+    async fn tick(&self, state: GlobalState, tick: u64) -> Result<()> {
+        if tick.is_multiple_of(LATENCY_INTERVAL_TICKS) {
+            update_latency(&state).await?;
+        }
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        Self::type_name()
+    }
+
+    fn access(&self) -> Access {
+        Access::new()
+            .read::<Player>()
+            .read::<GameMode>()
+            .read::<Latency>()
+            .read::<DisplayName>()
+            .read::<ConnectionWrapper>()
+    }
+}
@@ -0,0 +1,46 @@
+use async_trait::async_trait;
+
+use ferrumc_macros::AutoGenName;
+
+use crate::database::world_meta::WorldMeta;
+use crate::ecs::access::Access;
+use crate::net::packets::outgoing::update_time::UpdateTime;
+use crate::net::systems::TickedSystem;
+use crate::net::utils::broadcast::broadcast_packet;
+use crate::net::ConnectionWrapper;
+use crate::state::GlobalState;
+use crate::utils::prelude::*;
+
+/// How often everyone is told the time, in ticks. Same as vanilla's once a second.
+const BROADCAST_INTERVAL: u64 = 20;
+
+/// Moves the world's clock on every tick, and keeps the clients' clocks in step with it.
+#[derive(AutoGenName)]
+pub struct TimeTicker;
+
+#[async_trait]
+impl TickedSystem for TimeTicker {
+    async fn tick(&self, state: GlobalState, tick: u64) -> Result<()> {
+        let packet = {
+            let mut world_meta = state.world.resource_mut::<WorldMeta>().await?;
+            world_meta.tick_time();
+            tick.is_multiple_of(BROADCAST_INTERVAL)
+                .then(|| UpdateTime::new(&world_meta))
+        };
+
+        if let Some(packet) = packet {
+            broadcast_packet(packet, &state).await?;
+        }
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        Self::type_name()
+    }
+
+    fn access(&self) -> Access {
+        Access::new()
+            .write::<WorldMeta>()
+            .read::<ConnectionWrapper>()
+    }
+}
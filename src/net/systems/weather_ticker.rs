@@ -0,0 +1,107 @@
+use async_trait::async_trait;
+use tracing::debug;
+
+use ferrumc_macros::AutoGenName;
+
+use crate::database::world_meta::WorldMeta;
+use crate::ecs::access::Access;
+use crate::net::systems::TickedSystem;
+use crate::net::utils::broadcast::broadcast_packet;
+use crate::net::ConnectionWrapper;
+use crate::state::GlobalState;
+use crate::utils::components::dimension::CurrentDimension;
+use crate::utils::components::lightning_bolt::LightningBolt;
+use crate::utils::components::motion::Motion;
+use crate::utils::components::object_entity::ObjectEntity;
+use crate::utils::components::player::Player;
+use crate::utils::config::get_global_config;
+use crate::utils::encoding::position::Position;
+use crate::utils::prelude::*;
+use crate::world::dimension::Dimension;
+use crate::world::game_rules;
+use crate::world::weather::{lightning_strikes, strike_near};
+
+/// Moves the weather on every tick, telling everyone as it changes, and strikes lightning around
+/// players during thunderstorms. See [weather](crate::world::weather).
+#[derive(AutoGenName)]
+pub struct WeatherTicker;
+
+#[async_trait]
+impl TickedSystem for WeatherTicker {
+    async fn tick(&self, state: GlobalState, _tick: u64) -> Result<()> {
+        let (changes, thundering) = {
+            let mut world_meta = state.world.resource_mut::<WorldMeta>().await?;
+            let cycle = world_meta.game_rule_enabled(&game_rules::DO_WEATHER_CYCLE);
+            let changes = world_meta.weather.tick(cycle);
+            (changes, world_meta.weather.is_thundering())
+        };
+        for change in changes {
+            broadcast_packet(change, &state).await?;
+        }
+
+        remove_old_lightning(&state).await?;
+        if thundering && get_global_config().weather.lightning {
+            for position in overworld_players(&state).await {
+                if !lightning_strikes() {
+                    continue;
+                }
+                if let Err(e) = strike_near(&state, position).await {
+                    debug!("Couldn't strike lightning near {:?}: {}", position, e);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        Self::type_name()
+    }
+
+    /// Lightning bolts are entities like any other, so it writes everything one is spawned with.
+    fn access(&self) -> Access {
+        Access::new()
+            .write::<WorldMeta>()
+            .write::<LightningBolt>()
+            .write::<ObjectEntity>()
+            .write::<Motion>()
+            .write::<CurrentDimension>()
+            .read::<Player>()
+            .read::<Position>()
+            .read::<ConnectionWrapper>()
+    }
+}
+
+/// Ages every lightning bolt, removing the ones that have been around long enough.
+async fn remove_old_lightning(state: &GlobalState) -> Result<()> {
+    let expired = {
+        let mut expired = Vec::new();
+        let mut query = state.world.query::<&mut LightningBolt>();
+        while let Some((id, mut bolt)) = query.next().await {
+            bolt.age += 1;
+            if bolt.age >= LightningBolt::LIFETIME {
+                expired.push(id);
+            }
+        }
+        expired
+    };
+    for id in expired {
+        state.world.delete_entity(id).await?;
+    }
+    Ok(())
+}
+
+/// Where every player in the overworld is, the only dimension with weather.
+async fn overworld_players(state: &GlobalState) -> Vec<(f64, f64, f64)> {
+    let query = state
+        .world
+        .query::<(&Player, &Position, Option<&CurrentDimension>)>();
+    query
+        .iter()
+        .await
+        .filter_map(|(_, (_, position, dimension))| {
+            let dimension = dimension.map_or(Dimension::Overworld, |d| d.dimension);
+            (dimension == Dimension::Overworld)
+                .then(|| (position.x as f64, position.y as f64, position.z as f64))
+        })
+        .collect()
+}
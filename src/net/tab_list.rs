@@ -0,0 +1,135 @@
+//! The tab list, every online player plus a header and footer.
+//!
+//! Players are added to everyone's list when they join and taken out when they leave, so the list
+//! doesn't depend on who's in view. The client also needs a player's entry to spawn them, which
+//! is why joining players are added before they can be seen. Latency is kept up to date by the
+//! [TabListUpdater](crate::net::systems::tab_list_updater::TabListUpdater).
+
+use parking_lot::RwLock;
+
+use crate::net::packets::outgoing::player_info_remove::PlayerInfoRemove;
+use crate::net::packets::outgoing::player_info_update::{PlayerInfoUpdatePacket, PlayerListEntry};
+use crate::net::packets::outgoing::tab_list_header_footer::TabListHeaderFooter;
+use crate::net::packets::ConnectionId;
+use crate::net::utils::broadcast::broadcast_packet;
+use crate::net::utils::packet_queue::PacketQueue;
+use crate::state::GlobalState;
+use crate::utils::components::display_name::DisplayName;
+use crate::utils::components::game_mode::GameMode;
+use crate::utils::components::latency::Latency;
+use crate::utils::components::player::Player;
+use crate::utils::prelude::*;
+use crate::utils::text_component::TextComponent;
+
+/// The header and footer, which everyone sees the same.
+pub struct TabList {
+    header_footer: RwLock<(TextComponent, TextComponent)>,
+}
+
+impl Default for TabList {
+    fn default() -> Self {
+        Self {
+            header_footer: RwLock::new((TextComponent::text(""), TextComponent::text(""))),
+        }
+    }
+}
+
+impl TabList {
+    pub fn header_footer(&self) -> TabListHeaderFooter {
+        let (header, footer) = &*self.header_footer.read();
+        TabListHeaderFooter::new(header, footer)
+    }
+
+    /// Changes the text above and below the players for everyone. Empty text hides it.
+    pub async fn set_header_footer(
+        &self,
+        state: &GlobalState,
+        header: TextComponent,
+        footer: TextComponent,
+    ) -> Result<()> {
+        *self.header_footer.write() = (header, footer);
+        broadcast_packet(self.header_footer(), state).await
+    }
+}
+
+/// Every player that's in the world.
+pub async fn entries(state: &GlobalState) -> Vec<PlayerListEntry> {
+    let query = state
+        .world
+        .query::<(&Player, Option<&GameMode>, Option<&Latency>, Option<&DisplayName>)>();
+    query
+        .iter()
+        .await
+        .map(|(_, (player, game_mode, latency, display_name))| PlayerListEntry {
+            uuid: player.uuid,
+            username: player.username.clone(),
+            properties: player.properties.clone(),
+            game_mode: game_mode.map_or(GameMode::SURVIVAL, |game_mode| game_mode.mode),
+            latency: latency.map_or(0, |latency| latency.millis),
+            display_name: display_name.map(|display_name| display_name.name.clone()),
+        })
+        .collect()
+}
+
+/// Adds a joining player to everyone's tab list, and queues the whole list for them. Done before
+/// they're in the world, so no one can be sent their entity before their entry.
+pub async fn add_player(
+    state: &GlobalState,
+    entry: PlayerListEntry,
+    packet_queue: &mut PacketQueue,
+) -> Result<()> {
+    let packet = PlayerInfoUpdatePacket::add_players(std::slice::from_ref(&entry));
+    broadcast_packet(packet, state).await?;
+
+    let mut entries = entries(state).await;
+    entries.push(entry);
+    packet_queue
+        .queue(PlayerInfoUpdatePacket::add_players(&entries))
+        .await?;
+    packet_queue.queue(state.tab_list.header_footer()).await?;
+    Ok(())
+}
+
+/// Takes a player that's left out of everyone's tab list.
+pub async fn remove_player(state: &GlobalState, uuid: u128) -> Result<()> {
+    broadcast_packet(PlayerInfoRemove::new(vec![uuid]), state).await
+}
+
+pub async fn update_game_mode(state: &GlobalState, conn_id: ConnectionId, mode: u8) -> Result<()> {
+    let uuid = state.world.get_component::<Player>(conn_id).await?.uuid;
+    broadcast_packet(PlayerInfoUpdatePacket::update_game_mode(uuid, mode), state).await
+}
+
+/// Shows a player as something other than their username in the tab list, or as their username
+/// again with `None`.
+pub async fn set_display_name(
+    state: &GlobalState,
+    conn_id: ConnectionId,
+    name: Option<TextComponent>,
+) -> Result<()> {
+    let uuid = state.world.get_component::<Player>(conn_id).await?.uuid;
+    let storage = state.world.get_component_storage();
+    match &name {
+        Some(name) => {
+            storage.insert(conn_id, DisplayName::new(name.clone()));
+        }
+        None => {
+            // Fine if there wasn't one
+            let _ = storage.remove::<DisplayName>(conn_id);
+        }
+    }
+    broadcast_packet(PlayerInfoUpdatePacket::update_display_name(uuid, &name), state).await
+}
+
+/// Sends everyone's latency to everyone.
+pub async fn update_latency(state: &GlobalState) -> Result<()> {
+    let latencies = entries(state)
+        .await
+        .iter()
+        .map(|entry| (entry.uuid, entry.latency))
+        .collect::<Vec<_>>();
+    if latencies.is_empty() {
+        return Ok(());
+    }
+    broadcast_packet(PlayerInfoUpdatePacket::update_latency(&latencies), state).await
+}
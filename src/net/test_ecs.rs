@@ -19,9 +19,10 @@ mod tests {
             .create_entity()
             .await
             .with(Position::new(1, 2, 1))
-            .with(Velocity::new(2, 2, 2));
+            .with(Velocity::new(2, 2, 2))
+            .build();
 
-        world.create_entity().await.with(Position::new(2, 3, 2));
+        world.create_entity().await.with(Position::new(2, 3, 2)).build();
 
         // Query example
         for (entity_id, (mut position, velocity)) in
@@ -0,0 +1,143 @@
+//! Limits on how often one address can connect or log in, and how many packets a connection can
+//! send, against ping floods and bots joining over and over.
+//!
+//! Behind a proxy with [forwarding](crate::net::utils::forwarding) every connection comes from
+//! the proxy, so the per-address limits are left to it.
+
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+
+use dashmap::DashMap;
+
+use crate::net::utils::forwarding::get_forwarding;
+use crate::utils::config::get_global_config;
+
+/// How long the connection limit counts over.
+const CONNECTION_WINDOW: Duration = Duration::from_secs(60);
+/// Addresses that haven't been seen for a while are forgotten once this many are tracked.
+const MAX_TRACKED_ADDRESSES: usize = 1024;
+
+/// Lets through `limit` hits per `window`.
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    window: Duration,
+    limit: u32,
+    start: Instant,
+    count: u32,
+}
+
+impl RateLimiter {
+    pub fn new(window: Duration, limit: u32, now: Instant) -> Self {
+        Self {
+            window,
+            limit,
+            start: now,
+            count: 0,
+        }
+    }
+
+    /// Counts a hit, returning whether it's still within the limit. A limit of 0 lets everything
+    /// through.
+    pub fn hit(&mut self, now: Instant) -> bool {
+        if now.duration_since(self.start) >= self.window {
+            self.start = now;
+            self.count = 0;
+        }
+        self.count = self.count.saturating_add(1);
+        self.limit == 0 || self.count <= self.limit
+    }
+
+    fn is_expired(&self, now: Instant) -> bool {
+        now.duration_since(self.start) >= self.window
+    }
+}
+
+/// The per-address limits, see [Throttle](crate::utils::config::Throttle) for what they're set to.
+#[derive(Default)]
+pub struct ConnectionThrottle {
+    connections: DashMap<IpAddr, RateLimiter>,
+    logins: DashMap<IpAddr, Instant>,
+}
+
+impl ConnectionThrottle {
+    /// Counts a new connection from `ip`, returning whether it's allowed.
+    pub fn allow_connection(&self, ip: IpAddr) -> bool {
+        let limit = get_global_config().throttle.max_connections_per_minute;
+        if limit == 0 || get_forwarding().is_enabled() {
+            return true;
+        }
+        self.connection_at(ip, limit, Instant::now())
+    }
+
+    /// Counts a login from `ip`, returning whether it's been long enough since the last one.
+    pub fn allow_login(&self, ip: IpAddr) -> bool {
+        let interval = get_global_config().throttle.login_interval;
+        if interval == 0 || get_forwarding().is_enabled() {
+            return true;
+        }
+        self.login_at(ip, Duration::from_millis(interval), Instant::now())
+    }
+
+    fn connection_at(&self, ip: IpAddr, limit: u32, now: Instant) -> bool {
+        if self.connections.len() > MAX_TRACKED_ADDRESSES {
+            self.connections.retain(|_, limiter| !limiter.is_expired(now));
+        }
+        self.connections
+            .entry(ip)
+            .or_insert_with(|| RateLimiter::new(CONNECTION_WINDOW, limit, now))
+            .hit(now)
+    }
+
+    fn login_at(&self, ip: IpAddr, interval: Duration, now: Instant) -> bool {
+        if self.logins.len() > MAX_TRACKED_ADDRESSES {
+            self.logins.retain(|_, last| now.duration_since(*last) < interval);
+        }
+        // Trying again too early starts the wait over
+        let last = self.logins.insert(ip, now);
+        last.is_none_or(|last| now.duration_since(last) >= interval)
+    }
+}
+
+/// The limit on the packets one connection can send, see
+/// [Throttle](crate::utils::config::Throttle).
+pub fn packet_limiter() -> RateLimiter {
+    let limit = get_global_config().throttle.max_packets_per_second;
+    RateLimiter::new(Duration::from_secs(1), limit, Instant::now())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rate_limiter() {
+        let now = Instant::now();
+        let mut limiter = RateLimiter::new(Duration::from_secs(1), 2, now);
+        assert!(limiter.hit(now));
+        assert!(limiter.hit(now));
+        assert!(!limiter.hit(now));
+        // A new window starts the count over
+        assert!(limiter.hit(now + Duration::from_secs(1)));
+
+        let mut unlimited = RateLimiter::new(Duration::from_secs(1), 0, now);
+        assert!((0..1000).all(|_| unlimited.hit(now)));
+    }
+
+    #[test]
+    fn test_throttle() {
+        let throttle = ConnectionThrottle::default();
+        let ip: IpAddr = "127.0.0.1".parse().unwrap();
+        let other: IpAddr = "127.0.0.2".parse().unwrap();
+        let now = Instant::now();
+        let interval = Duration::from_secs(4);
+
+        assert!(throttle.login_at(ip, interval, now));
+        assert!(!throttle.login_at(ip, interval, now + Duration::from_secs(1)));
+        assert!(throttle.login_at(other, interval, now + Duration::from_secs(1)));
+        assert!(throttle.login_at(ip, interval, now + Duration::from_secs(6)));
+
+        assert!(throttle.connection_at(ip, 1, now));
+        assert!(!throttle.connection_at(ip, 1, now));
+        assert!(throttle.connection_at(other, 1, now));
+    }
+}
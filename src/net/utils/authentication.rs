@@ -0,0 +1,57 @@
+use serde::Deserialize;
+use tracing::debug;
+use uuid::Uuid;
+
+use crate::net::packets::outgoing::login_success::Property;
+use crate::utils::prelude::*;
+
+const SESSION_SERVER: &str = "https://sessionserver.mojang.com/session/minecraft/hasJoined";
+
+/// A player's profile, as returned by the session server.
+#[derive(Debug, Deserialize)]
+pub struct GameProfile {
+    pub id: Uuid,
+    pub name: String,
+    #[serde(default)]
+    pub properties: Vec<ProfileProperty>,
+}
+
+/// Mostly just the player's skin and cape ("textures").
+#[derive(Debug, Deserialize)]
+pub struct ProfileProperty {
+    pub name: String,
+    pub value: String,
+    pub signature: Option<String>,
+}
+
+impl From<ProfileProperty> for Property {
+    fn from(property: ProfileProperty) -> Self {
+        Property {
+            name: property.name,
+            value: property.value,
+            signature: property.signature,
+        }
+    }
+}
+
+/// Asks the session server whether `username` has joined the server identified by `server_hash`.
+/// The client does its half of this before sending the Encryption Response.
+pub async fn has_joined(username: &str, server_hash: &str) -> Result<GameProfile> {
+    let response = reqwest::Client::new()
+        .get(SESSION_SERVER)
+        .query(&[("username", username), ("serverId", server_hash)])
+        .send()
+        .await?
+        .error_for_status()?;
+
+    // The session server answers with 204 No Content if the player isn't authenticated
+    if response.status() == reqwest::StatusCode::NO_CONTENT {
+        return Err(Error::AuthenticationFailed(format!(
+            "{username} has not joined through the session server"
+        )));
+    }
+
+    let profile: GameProfile = response.json().await?;
+    debug!("Authenticated {} as {}", profile.name, profile.id);
+    Ok(profile)
+}
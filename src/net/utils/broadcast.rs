@@ -0,0 +1,113 @@
+use tracing::warn;
+
+use ferrumc_codec::enc::NetEncode;
+
+use crate::net::utils::encoded_packet::EncodedPacket;
+use crate::net::ConnectionWrapper;
+use crate::state::GlobalState;
+use crate::utils::components::dimension::CurrentDimension;
+use crate::utils::components::player::Player;
+use crate::utils::components::visible_entities::VisibleEntities;
+use crate::utils::encoding::position::Position;
+use crate::utils::prelude::*;
+use crate::world::dimension::Dimension;
+
+/// Sends a packet to every player that's in the world.
+///
+/// The packet is only encoded once, and only framed once for each version and compression players
+/// are using, see [EncodedPacket]. Failing to send to one player doesn't stop it going to the rest.
+pub async fn broadcast_packet(packet: impl NetEncode, state: &GlobalState) -> Result<()> {
+    let packet = EncodedPacket::new(packet).await?;
+
+    let connections = {
+        let query = state.world.query::<(&Player, &ConnectionWrapper)>();
+        query
+            .iter()
+            .await
+            .map(|(_, (_, conn))| conn.0.clone())
+            .collect::<Vec<_>>()
+    };
+
+    for conn in connections {
+        let conn = conn.read().await;
+        if let Err(e) = conn.send_encoded(&packet).await {
+            warn!("Failed to broadcast packet to {}: {}", conn.id, e);
+        }
+    }
+
+    Ok(())
+}
+
+/// Sends a packet about an entity to every player whose client has it spawned, and to the entity
+/// itself if it's a player. Works like [broadcast_packet] otherwise.
+pub async fn broadcast_to_viewers(
+    packet: impl NetEncode,
+    entity_id: usize,
+    state: &GlobalState,
+) -> Result<()> {
+    let packet = EncodedPacket::new(packet).await?;
+
+    let connections = {
+        let query = state.world.query::<(&VisibleEntities, &ConnectionWrapper)>();
+        query
+            .iter()
+            .await
+            .filter(|(id, (visible, _))| *id == entity_id || visible.entities.contains(&entity_id))
+            .map(|(_, (_, conn))| conn.0.clone())
+            .collect::<Vec<_>>()
+    };
+
+    for conn in connections {
+        let conn = conn.read().await;
+        if let Err(e) = conn.send_encoded(&packet).await {
+            warn!("Failed to send entity update to {}: {}", conn.id, e);
+        }
+    }
+
+    Ok(())
+}
+
+/// Sends a packet to every player in a dimension within `range` blocks of a position, like sounds
+/// and particles that are too far away to notice otherwise. Works like [broadcast_packet]
+/// otherwise.
+pub async fn broadcast_near(
+    packet: impl NetEncode,
+    state: &GlobalState,
+    dimension: Dimension,
+    position: (f64, f64, f64),
+    range: f64,
+) -> Result<()> {
+    let packet = EncodedPacket::new(packet).await?;
+
+    let connections = {
+        let query = state.world.query::<(
+            &Player,
+            &Position,
+            Option<&CurrentDimension>,
+            &ConnectionWrapper,
+        )>();
+        query
+            .iter()
+            .await
+            .filter(|(_, (_, player_position, current, _))| {
+                let player_dimension = current
+                    .as_ref()
+                    .map_or(Dimension::Overworld, |current| current.dimension);
+                let dx = player_position.x as f64 + 0.5 - position.0;
+                let dy = player_position.y as f64 - position.1;
+                let dz = player_position.z as f64 + 0.5 - position.2;
+                player_dimension == dimension && dx * dx + dy * dy + dz * dz <= range * range
+            })
+            .map(|(_, (_, _, _, conn))| conn.0.clone())
+            .collect::<Vec<_>>()
+    };
+
+    for conn in connections {
+        let conn = conn.read().await;
+        if let Err(e) = conn.send_encoded(&packet).await {
+            warn!("Failed to send nearby packet to {}: {}", conn.id, e);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,269 @@
+//! Players attacking other entities, both other players and mobs.
+//!
+//! Damage works like vanilla's: it depends on the held weapon and how far the attack has
+//! recharged, and attacking while falling is a critical hit. Whatever's hit is knocked away from
+//! the attacker.
+
+use std::time::Instant;
+
+use tracing::debug;
+
+use crate::events::creation::dispatcher::EventDispatcherExt;
+use crate::events::creation::event::{Cancellation, Event};
+use crate::events::health_events::EntityDamageByEntityEvent;
+use crate::net::packets::outgoing::set_entity_velocity::SetEntityVelocity;
+use crate::net::packets::ConnectionId;
+use crate::net::utils::broadcast::broadcast_to_viewers;
+use crate::state::GlobalState;
+use crate::utils::components::attack_cooldown::AttackCooldown;
+use crate::utils::components::dimension::dimension_of;
+use crate::utils::components::entity_state::EntityState;
+use crate::utils::components::food::{exhaust, exhaustion};
+use crate::utils::components::game_mode::GameMode;
+use crate::utils::components::grounded::Grounded;
+use crate::utils::components::health::Health;
+use crate::utils::components::inventory::Inventory;
+use crate::utils::components::motion::Motion;
+use crate::utils::components::rotation::Rotation;
+use crate::utils::encoding::position::Position;
+use crate::utils::prelude::*;
+
+/// How far away an entity can be attacked from, squared. A bit further than vanilla's 6 blocks,
+/// since positions are rounded to blocks.
+const MAX_REACH_SQUARED: i64 = 49;
+pub const KNOCKBACK: f64 = 0.4;
+/// Vanilla knocks back a second time for sprinting, which adds up to about this.
+const SPRINTING_KNOCKBACK: f64 = 0.7;
+const CRITICAL_MULTIPLIER: f32 = 1.5;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Weapon {
+    damage: f32,
+    /// Attacks per second at full strength.
+    speed: f32,
+}
+
+const FIST: Weapon = Weapon {
+    damage: 1.0,
+    speed: 4.0,
+};
+
+/// The damage and speed of an item, which is a fist's for anything that isn't a weapon or tool.
+fn weapon(item: &str) -> Weapon {
+    let (damage, speed) = match item.strip_prefix("minecraft:").unwrap_or(item) {
+        "wooden_sword" | "golden_sword" => (4.0, 1.6),
+        "stone_sword" => (5.0, 1.6),
+        "iron_sword" => (6.0, 1.6),
+        "diamond_sword" => (7.0, 1.6),
+        "netherite_sword" => (8.0, 1.6),
+        "wooden_axe" => (7.0, 0.8),
+        "golden_axe" => (7.0, 1.0),
+        "stone_axe" => (9.0, 0.8),
+        "iron_axe" => (9.0, 0.9),
+        "diamond_axe" => (9.0, 1.0),
+        "netherite_axe" => (10.0, 1.0),
+        "wooden_pickaxe" | "golden_pickaxe" => (2.0, 1.2),
+        "stone_pickaxe" => (3.0, 1.2),
+        "iron_pickaxe" => (4.0, 1.2),
+        "diamond_pickaxe" => (5.0, 1.2),
+        "netherite_pickaxe" => (6.0, 1.2),
+        "wooden_shovel" | "golden_shovel" => (2.5, 1.0),
+        "stone_shovel" => (3.5, 1.0),
+        "iron_shovel" => (4.5, 1.0),
+        "diamond_shovel" => (5.5, 1.0),
+        "netherite_shovel" => (6.5, 1.0),
+        "trident" => (9.0, 1.1),
+        _ => return FIST,
+    };
+    Weapon { damage, speed }
+}
+
+/// How much an attack with `strength` from [AttackCooldown::strength] does, and whether it's a
+/// critical hit.
+fn attack_damage(weapon: Weapon, strength: f32, falling: bool) -> (f32, bool) {
+    let damage = weapon.damage * (0.2 + strength * strength * 0.8);
+    let critical = falling && strength > 0.9;
+    if critical {
+        (damage * CRITICAL_MULTIPLIER, true)
+    } else {
+        (damage, false)
+    }
+}
+
+/// The velocity an entity is knocked back with, in blocks per tick, by an attacker facing `yaw`.
+fn knockback_velocity(strength: f64, yaw: f32, on_ground: bool) -> (f64, f64, f64) {
+    let yaw = (yaw as f64).to_radians();
+    let y = if on_ground { strength.min(0.4) } else { 0.0 };
+    (-yaw.sin() * strength, y, yaw.cos() * strength)
+}
+
+/// A player attacks an entity. Nothing happens if it's too far away, in another dimension, can't
+/// be hurt, or was hurt too recently, see [Health::recently_hurt].
+pub async fn attack(state: &GlobalState, attacker: ConnectionId, target: usize) -> Result<()> {
+    if attacker == target {
+        return Ok(());
+    }
+    let spectator = state
+        .world
+        .get_component::<GameMode>(attacker)
+        .await
+        .is_ok_and(|game_mode| game_mode.mode == GameMode::SPECTATOR);
+    let now = Instant::now();
+    let attackable = state
+        .world
+        .get_component::<Health>(target)
+        .await
+        .is_ok_and(|health| !health.is_dead() && !health.recently_hurt(now));
+    if spectator || !attackable {
+        return Ok(());
+    }
+    if dimension_of(state, attacker).await != dimension_of(state, target).await {
+        return Ok(());
+    }
+    let from = state.world.get_component::<Position>(attacker).await?.clone();
+    // Players have a Position, anything else a Motion
+    let to = match state.world.get_component::<Motion>(target).await {
+        Ok(motion) => motion.block(),
+        Err(_) => {
+            let to = state.world.get_component::<Position>(target).await?;
+            (to.x, to.y as i32, to.z)
+        }
+    };
+    let (dx, dy, dz) = (
+        (to.0 - from.x) as i64,
+        (to.1 - from.y as i32) as i64,
+        (to.2 - from.z) as i64,
+    );
+    if dx * dx + dy * dy + dz * dz > MAX_REACH_SQUARED {
+        debug!("{} tried to attack {}, which is too far away", attacker, target);
+        return Ok(());
+    }
+
+    let held = state
+        .world
+        .get_component::<Inventory>(attacker)
+        .await
+        .ok()
+        .and_then(|inventory| inventory.held_item(false).map(|item| item.id));
+    let weapon = held
+        .and_then(|id| state.items.name(id))
+        .map_or(FIST, weapon);
+    let strength = {
+        let mut cooldown = state
+            .world
+            .get_component_storage()
+            .get_mut_or_insert_with::<AttackCooldown>(attacker, Default::default)
+            .await;
+        let strength = cooldown.strength(weapon.speed, now);
+        cooldown.reset(now);
+        strength
+    };
+    let sprinting = state
+        .world
+        .get_component::<EntityState>(attacker)
+        .await
+        .is_ok_and(|entity_state| entity_state.sprinting);
+    let falling = state
+        .world
+        .get_component::<Grounded>(attacker)
+        .await
+        .is_ok_and(|grounded| !grounded.is_grounded && grounded.fall_distance > 0.0);
+    let (amount, critical) = attack_damage(weapon, strength, falling && !sprinting);
+    let knockback = if sprinting && strength > 0.9 {
+        SPRINTING_KNOCKBACK
+    } else {
+        KNOCKBACK
+    };
+    exhaust(state, attacker, exhaustion::ATTACK).await;
+
+    let event = state
+        .dispatch_event(EntityDamageByEntityEvent::new(
+            attacker,
+            target,
+            amount,
+            critical,
+            knockback,
+            Cancellation::default(),
+        ))
+        .await;
+    if event.is_cancelled() {
+        debug!("{} attacking {} was cancelled", attacker, target);
+    }
+    Ok(())
+}
+
+/// Knocks an entity away from whatever hit it, with its velocity sent to everyone who can see it.
+pub async fn knock_back(
+    state: &GlobalState,
+    target: usize,
+    attacker: usize,
+    strength: f64,
+) -> Result<()> {
+    let yaw = state
+        .world
+        .get_component::<Rotation>(attacker)
+        .await
+        .map_or(0.0, |rotation| rotation.yaw);
+    knock_back_towards(state, target, yaw, strength).await
+}
+
+/// Knocks an entity the way something facing `yaw` would push it, like a projectile flying that
+/// way.
+pub async fn knock_back_towards(
+    state: &GlobalState,
+    target: usize,
+    yaw: f32,
+    strength: f64,
+) -> Result<()> {
+    let on_ground = state
+        .world
+        .get_component::<Grounded>(target)
+        .await
+        .is_ok_and(|grounded| grounded.is_grounded);
+    let velocity = match state.world.get_component_storage().get_mut::<Motion>(target).await {
+        // Moved by the server, so it's knocked back here as well as on clients
+        Ok(mut motion) => {
+            let velocity = knockback_velocity(strength, yaw, motion.on_ground);
+            motion.velocity = velocity;
+            motion.on_ground = false;
+            velocity
+        }
+        Err(_) => knockback_velocity(strength, yaw, on_ground),
+    };
+    broadcast_to_viewers(SetEntityVelocity::new(target, velocity), target, state).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_weapon() {
+        assert_eq!(weapon("minecraft:diamond_sword").damage, 7.0);
+        assert_eq!(weapon("minecraft:stone_axe").speed, 0.8);
+        assert_eq!(weapon("minecraft:dirt"), FIST);
+    }
+
+    #[test]
+    fn test_attack_damage() {
+        let sword = weapon("minecraft:iron_sword");
+        assert_eq!(attack_damage(sword, 1.0, false), (6.0, false));
+        assert_eq!(attack_damage(sword, 1.0, true), (9.0, true));
+        // Spamming barely does anything
+        let (damage, critical) = attack_damage(sword, 0.1, true);
+        assert!(damage < 1.5);
+        assert!(!critical);
+    }
+
+    #[test]
+    fn test_knockback_velocity() {
+        // Facing south, towards +z
+        let (x, y, z) = knockback_velocity(KNOCKBACK, 0.0, true);
+        assert!(x.abs() < 1e-9);
+        assert_eq!((y, z), (0.4, 0.4));
+        // Facing west, towards -x, in the air
+        let (x, y, z) = knockback_velocity(KNOCKBACK, 90.0, false);
+        assert!((x + 0.4).abs() < 1e-9 && z.abs() < 1e-9);
+        assert_eq!(y, 0.0);
+    }
+}
@@ -0,0 +1,204 @@
+//! Players dying, and coming back.
+//!
+//! A player whose health runs out is shown the death screen, and everyone else is told in chat
+//! unless the `showDeathMessages` gamerule is off. They drop everything in their inventory, and
+//! lose their experience with some of it dropped as orbs, unless `keepInventory` is on. Clicking
+//! respawn brings them back at the world spawn with full health.
+
+use tracing::info;
+
+use crate::database::world_meta::WorldMeta;
+use crate::events::health_events::DamageCause;
+use crate::net::packets::outgoing::combat_death::CombatDeath;
+use crate::net::packets::outgoing::entity_event::{statuses, EntityEvent};
+use crate::net::packets::outgoing::set_container_content::SetContainerContent;
+use crate::net::packets::outgoing::system_chat_message::SystemChatMessage;
+use crate::net::packets::ConnectionId;
+use crate::net::systems::entity_broadcaster::EntityBroadcaster;
+use crate::net::utils::broadcast::{broadcast_packet, broadcast_to_viewers};
+use crate::net::utils::movement::respawn_after_death;
+use crate::state::GlobalState;
+use crate::utils::components::dimension::dimension_of;
+use crate::utils::components::entity_state::EntityState;
+use crate::utils::components::experience::{send_experience, Experience};
+use crate::utils::components::food::Food;
+use crate::utils::components::health::Health;
+use crate::utils::components::inventory::Inventory;
+use crate::utils::components::open_container::OpenContainer;
+use crate::utils::components::player::Player;
+use crate::utils::encoding::position::Position;
+use crate::utils::prelude::*;
+use crate::utils::text_component::TextComponent;
+use crate::world::dimension::Dimension;
+use crate::world::experience_orbs::drop_experience;
+use crate::world::game_rules;
+use crate::world::item_entities::scatter_items;
+use crate::world::projectiles::ProjectileKind;
+
+/// What everyone's told when `victim` dies, translated by their client. `attacker` is the name of
+/// whoever killed them, if anyone did.
+pub fn death_message(cause: DamageCause, victim: &str, attacker: Option<&str>) -> TextComponent {
+    let victim = TextComponent::text(victim);
+    let (key, with) = match (cause, attacker) {
+        (DamageCause::Attack { .. }, Some(attacker)) => {
+            ("death.attack.player", vec![victim, TextComponent::text(attacker)])
+        }
+        (DamageCause::Projectile { kind, .. }, Some(attacker)) => {
+            let key = match kind {
+                ProjectileKind::Arrow => "death.attack.arrow",
+                ProjectileKind::Snowball => "death.attack.thrown",
+            };
+            (key, vec![victim, TextComponent::text(attacker)])
+        }
+        (DamageCause::Fall, _) => ("death.attack.fall", vec![victim]),
+        (DamageCause::Starvation, _) => ("death.attack.starve", vec![victim]),
+        (DamageCause::Void, _) => ("death.attack.outOfWorld", vec![victim]),
+        _ => ("death.attack.generic", vec![victim]),
+    };
+    TextComponent::translate(key, with)
+}
+
+/// Kills a player whose health has run out: shows them the death screen and tells everyone else.
+pub async fn die(state: &GlobalState, conn_id: ConnectionId, cause: DamageCause) -> Result<()> {
+    let victim = state.world.get_component::<Player>(conn_id).await?.username.clone();
+    let attacker = match cause.source() {
+        Some(source) => state
+            .world
+            .get_component::<Player>(source)
+            .await
+            .ok()
+            .map(|attacker| attacker.username.clone()),
+        None => None,
+    };
+    let message = death_message(cause, &victim, attacker.as_deref());
+
+    let event = EntityEvent::new_auto(conn_id as i32, statuses::DEATH);
+    broadcast_to_viewers(event, conn_id, state).await?;
+    {
+        let conn = state.connections.get_connection(conn_id)?;
+        let conn = conn.read().await;
+        conn.send_packet(CombatDeath::new(conn_id, &message)).await?;
+    }
+
+    let (show_message, keep_inventory) = {
+        let world_meta = state.world.resource::<WorldMeta>().await?;
+        (
+            world_meta.game_rule_enabled(&game_rules::SHOW_DEATH_MESSAGES),
+            world_meta.game_rule_enabled(&game_rules::KEEP_INVENTORY),
+        )
+    };
+    info!("{}", message.plain_text());
+    if show_message {
+        broadcast_packet(SystemChatMessage::component(&message), state).await?;
+    }
+    if !keep_inventory {
+        drop_inventory(state, conn_id).await?;
+        drop_experience_points(state, conn_id).await?;
+    }
+    Ok(())
+}
+
+/// Empties a dead player's inventory onto the ground around them.
+async fn drop_inventory(state: &GlobalState, conn_id: ConnectionId) -> Result<()> {
+    let component_storage = state.world.get_component_storage();
+    // Fine if they didn't have anything open
+    let _ = component_storage.remove::<OpenContainer>(conn_id);
+    let (content, dropped) = {
+        let mut inventory = component_storage
+            .get_mut_or_insert_with::<Inventory>(conn_id, Default::default)
+            .await;
+        let mut slots = inventory.slots.drain().collect::<Vec<_>>();
+        // The crafting result isn't really there
+        slots.retain(|(slot, _)| *slot != Inventory::CRAFTING_RESULT);
+        slots.sort_by_key(|(slot, _)| *slot);
+        let mut dropped = slots.into_iter().map(|(_, item)| item).collect::<Vec<_>>();
+        dropped.extend(inventory.carried.take());
+        inventory.next_state_id();
+        (SetContainerContent::player_inventory(&inventory), dropped)
+    };
+    {
+        let conn = state.connections.get_connection(conn_id)?;
+        let conn = conn.read().await;
+        conn.send_packet(content).await?;
+    }
+    scatter_items(state, conn_id, dropped).await
+}
+
+/// Takes away all of a dead player's experience, and drops some of it where they died.
+async fn drop_experience_points(state: &GlobalState, conn_id: ConnectionId) -> Result<()> {
+    let experience = std::mem::take(
+        &mut *state
+            .world
+            .get_component_storage()
+            .get_mut_or_insert_with::<Experience>(conn_id, Default::default)
+            .await,
+    );
+    send_experience(state, conn_id).await?;
+
+    let position = state.world.get_component::<Position>(conn_id).await?.clone();
+    let position = (
+        position.x as f64 + 0.5,
+        position.y as f64,
+        position.z as f64 + 0.5,
+    );
+    let dimension = dimension_of(state, conn_id).await;
+    drop_experience(state, dimension, position, experience.dropped_on_death()).await;
+    Ok(())
+}
+
+/// Brings a dead player back at the world spawn, with full health and hunger.
+pub async fn respawn(state: &GlobalState, conn_id: ConnectionId) -> Result<()> {
+    {
+        let component_storage = state.world.get_component_storage();
+        component_storage
+            .insert(conn_id, Health::default())
+            .insert(conn_id, Food::default());
+        let mut entity_state = component_storage
+            .get_mut_or_insert_with::<EntityState>(conn_id, Default::default)
+            .await;
+        entity_state.sneaking = false;
+        entity_state.sprinting = false;
+    }
+    EntityBroadcaster::respawn_for_viewers(state, conn_id).await?;
+
+    let spawn = state.world.resource::<WorldMeta>().await?.spawn_position();
+    respawn_after_death(conn_id, state.clone(), Dimension::Overworld, spawn).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_death_message() {
+        let attack = DamageCause::Attack { attacker: 2 };
+        assert_eq!(
+            death_message(attack, "Alex", Some("Steve")),
+            TextComponent::translate(
+                "death.attack.player",
+                vec![TextComponent::text("Alex"), TextComponent::text("Steve")]
+            )
+        );
+        // Killed by something that's left
+        assert_eq!(
+            death_message(attack, "Alex", None),
+            TextComponent::translate("death.attack.generic", vec![TextComponent::text("Alex")])
+        );
+        let shot = DamageCause::Projectile {
+            kind: ProjectileKind::Arrow,
+            projectile: 3,
+            shooter: Some(2),
+        };
+        assert_eq!(
+            death_message(shot, "Alex", Some("Steve")),
+            TextComponent::translate(
+                "death.attack.arrow",
+                vec![TextComponent::text("Alex"), TextComponent::text("Steve")]
+            )
+        );
+        assert_eq!(
+            death_message(DamageCause::Void, "Alex", None),
+            TextComponent::translate("death.attack.outOfWorld", vec![TextComponent::text("Alex")])
+        );
+    }
+}
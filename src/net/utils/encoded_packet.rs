@@ -0,0 +1,113 @@
+use std::sync::Mutex;
+
+use bytes::Bytes;
+use ferrumc_codec::enc::{NetEncode, NetEncodeOpts};
+
+use crate::net::{capture, packet_debug, Connection, State};
+use crate::utils::prelude::*;
+
+/// One or more packets encoded once, to be sent to any number of players.
+///
+/// Players on the same version with the same compression get the exact same bytes, so these are
+/// only framed once for each kind of connection they go to. Useful for anything that goes to a lot
+/// of players at once, e.g. chat messages and entity spawns.
+pub struct EncodedPacket {
+    /// `[length][packet id][data]` frames with the server's own ids, uncompressed.
+    native: Bytes,
+    /// The packet's type, for `--packet-debug`.
+    name: &'static str,
+    /// The frames as they were sent to each kind of connection so far. There's rarely more than a
+    /// couple, so a list is enough.
+    framed: Mutex<Vec<(FrameFormat, Bytes)>>,
+}
+
+/// Everything that changes the bytes a packet is sent as.
+#[derive(Clone, PartialEq)]
+struct FrameFormat {
+    protocol_version: i32,
+    /// Packet ids are remapped per state for other versions.
+    state: State,
+    compression: NetEncodeOpts,
+}
+
+impl EncodedPacket {
+    pub async fn new(packet: impl NetEncode) -> Result<Self> {
+        let mut native = Vec::new();
+        packet.net_encode(&mut native).await?;
+        Ok(Self {
+            native: Bytes::from(native),
+            name: packet_debug::type_name(&packet),
+            framed: Mutex::new(Vec::new()),
+        })
+    }
+
+    /// The size of the uncompressed frames, in bytes.
+    pub fn len(&self) -> usize {
+        self.native.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.native.is_empty()
+    }
+
+    /// The frames the way `conn` takes them, framing them the first time they go to a connection
+    /// like it.
+    pub(crate) async fn frames_for(&self, conn: &Connection) -> Result<Bytes> {
+        if packet_debug::enabled() {
+            packet_debug::log_outgoing(conn, self.name, &self.native);
+        }
+        if capture::enabled() {
+            capture::record_outgoing(conn, &self.native);
+        }
+        let format = FrameFormat {
+            protocol_version: conn.metadata.protocol().version,
+            state: conn.state.clone(),
+            compression: conn.metadata.compression,
+        };
+        if let Some(frames) = self.cached(&format) {
+            return Ok(frames);
+        }
+
+        let frames = conn.frame_unlogged(&self.native[..]).await?.into_bytes();
+        self.framed
+            .lock()
+            .expect("Encoded packet cache poisoned")
+            .push((format, frames.clone()));
+        Ok(frames)
+    }
+
+    fn cached(&self, format: &FrameFormat) -> Option<Bytes> {
+        self.framed
+            .lock()
+            .expect("Encoded packet cache poisoned")
+            .iter()
+            .find(|(cached, _)| cached == format)
+            .map(|(_, frames)| frames.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ferrumc_codec::network_types::varint::VarInt;
+
+    use super::*;
+    use crate::net::packets::outgoing::keep_alive::KeepAlivePacketOut;
+
+    #[tokio::test]
+    async fn test_encoded_packet() {
+        let packet = EncodedPacket::new(KeepAlivePacketOut::new_auto(7)).await.unwrap();
+        let mut expected = Vec::new();
+        VarInt::new(9).net_encode(&mut expected).await.unwrap();
+        VarInt::new(0x23).net_encode(&mut expected).await.unwrap();
+        7i64.net_encode(&mut expected).await.unwrap();
+        assert_eq!(packet.native[..], expected[..]);
+        assert_eq!(packet.len(), 10);
+
+        let format = FrameFormat {
+            protocol_version: 763,
+            state: State::Play,
+            compression: NetEncodeOpts::None,
+        };
+        assert!(packet.cached(&format).is_none());
+    }
+}
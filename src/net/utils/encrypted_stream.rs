@@ -0,0 +1,179 @@
+use std::io;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+use aes::cipher::generic_array::GenericArray;
+use aes::cipher::{BlockDecryptMut, BlockEncryptMut};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+use crate::net::utils::encryption::{Decryptor, Encryptor};
+
+/// Wraps the read half of a connection, decrypting everything read from it once a cipher is set.
+/// Without a cipher it's a plain passthrough, so packet handling doesn't care either way.
+pub struct EncryptedReader<R> {
+    inner: R,
+    cipher: Option<Decryptor>,
+}
+
+impl<R> EncryptedReader<R> {
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            cipher: None,
+        }
+    }
+
+    /// Everything read after this call gets decrypted.
+    pub fn enable_encryption(&mut self, cipher: Decryptor) {
+        self.cipher = Some(cipher);
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for EncryptedReader<R> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = &mut *self;
+        let already_filled = buf.filled().len();
+
+        ready!(Pin::new(&mut this.inner).poll_read(cx, buf))?;
+
+        if let Some(cipher) = &mut this.cipher {
+            // CFB8 works on single bytes, so there's never a partial block to keep around
+            for byte in buf.filled_mut()[already_filled..].chunks_mut(1) {
+                cipher.decrypt_block_mut(GenericArray::from_mut_slice(byte));
+            }
+        }
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// Wraps the write half of a connection, encrypting everything written to it once a cipher is set.
+///
+/// The cipher state moves forward as soon as bytes are encrypted, so bytes the socket didn't take
+/// are kept in `pending` and have to go out before anything else. Call `flush` to make sure
+/// nothing is left behind.
+pub struct EncryptedWriter<W> {
+    inner: W,
+    cipher: Option<Encryptor>,
+    pending: Vec<u8>,
+}
+
+impl<W> EncryptedWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner,
+            cipher: None,
+            pending: Vec::new(),
+        }
+    }
+
+    /// Everything written after this call gets encrypted.
+    pub fn enable_encryption(&mut self, cipher: Encryptor) {
+        self.cipher = Some(cipher);
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+}
+
+impl<W: AsyncWrite + Unpin> EncryptedWriter<W> {
+    fn poll_write_pending(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        while !self.pending.is_empty() {
+            let written = ready!(Pin::new(&mut self.inner).poll_write(cx, &self.pending))?;
+            if written == 0 {
+                return Poll::Ready(Err(io::ErrorKind::WriteZero.into()));
+            }
+            self.pending.drain(..written);
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<W: AsyncWrite + Unpin> AsyncWrite for EncryptedWriter<W> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = &mut *self;
+        if this.cipher.is_none() {
+            return Pin::new(&mut this.inner).poll_write(cx, buf);
+        }
+
+        ready!(this.poll_write_pending(cx))?;
+
+        let start = this.pending.len();
+        this.pending.extend_from_slice(buf);
+        if let Some(cipher) = &mut this.cipher {
+            for byte in this.pending[start..].chunks_mut(1) {
+                cipher.encrypt_block_mut(GenericArray::from_mut_slice(byte));
+            }
+        }
+
+        // The bytes are accepted either way, whatever doesn't fit now goes out on the next write or flush
+        if let Poll::Ready(Err(e)) = this.poll_write_pending(cx) {
+            return Poll::Ready(Err(e));
+        }
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        ready!(self.poll_write_pending(cx))?;
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        ready!(self.poll_write_pending(cx))?;
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    use super::*;
+    use crate::net::utils::encryption::create_ciphers;
+
+    #[tokio::test]
+    async fn test_round_trip() {
+        let shared_secret = [7u8; 16];
+        let (encryptor, _) = create_ciphers(&shared_secret).unwrap();
+        let (_, decryptor) = create_ciphers(&shared_secret).unwrap();
+
+        let mut writer = EncryptedWriter::new(Vec::new());
+        writer.enable_encryption(encryptor);
+        writer.write_all(b"Hello, ").await.unwrap();
+        writer.write_all(b"world!").await.unwrap();
+        writer.flush().await.unwrap();
+
+        let encrypted = writer.get_ref().clone();
+        assert_ne!(encrypted, b"Hello, world!");
+
+        let mut reader = EncryptedReader::new(encrypted.as_slice());
+        reader.enable_encryption(decryptor);
+        let mut decrypted = Vec::new();
+        reader.read_to_end(&mut decrypted).await.unwrap();
+        assert_eq!(decrypted, b"Hello, world!");
+    }
+
+    #[tokio::test]
+    async fn test_passthrough_without_cipher() {
+        let mut writer = EncryptedWriter::new(Vec::new());
+        writer.write_all(b"plain").await.unwrap();
+        assert_eq!(writer.get_ref(), b"plain");
+    }
+}
@@ -0,0 +1,102 @@
+use std::sync::OnceLock;
+
+use aes::cipher::KeyIvInit;
+use num_bigint::BigInt;
+use rsa::pkcs8::EncodePublicKey;
+use rsa::rand_core::OsRng;
+use rsa::{Pkcs1v15Encrypt, RsaPrivateKey};
+use sha1::{Digest, Sha1};
+use tracing::debug;
+
+use crate::utils::prelude::*;
+
+pub type Encryptor = cfb8::Encryptor<aes::Aes128>;
+pub type Decryptor = cfb8::Decryptor<aes::Aes128>;
+
+/// The vanilla server uses 1024 bit keys, and the client doesn't accept anything else.
+const KEY_BITS: usize = 1024;
+
+/// The RSA keypair used to exchange the shared secret during login.
+pub struct ServerKey {
+    private_key: RsaPrivateKey,
+    public_key_der: Vec<u8>,
+}
+
+impl ServerKey {
+    pub fn generate() -> Result<Self> {
+        let private_key = RsaPrivateKey::new(&mut OsRng, KEY_BITS)
+            .map_err(|e| Error::EncryptionError(e.to_string()))?;
+        let public_key_der = private_key
+            .to_public_key()
+            .to_public_key_der()
+            .map_err(|e| Error::EncryptionError(e.to_string()))?
+            .into_vec();
+
+        Ok(Self {
+            private_key,
+            public_key_der,
+        })
+    }
+
+    /// The public key in the DER format the client expects.
+    pub fn public_key_der(&self) -> &[u8] {
+        &self.public_key_der
+    }
+
+    /// Decrypts something the client encrypted with our public key.
+    pub fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
+        self.private_key
+            .decrypt(Pkcs1v15Encrypt, data)
+            .map_err(|e| Error::EncryptionError(e.to_string()))
+    }
+}
+
+/// Get the server's RSA keypair, generating it on first use.
+pub fn get_server_key() -> &'static ServerKey {
+    static KEY: OnceLock<ServerKey> = OnceLock::new();
+    KEY.get_or_init(|| {
+        debug!("Generating {KEY_BITS} bit RSA keypair");
+        ServerKey::generate().expect("Failed to generate server keypair")
+    })
+}
+
+/// Creates the AES/CFB8 cipher pair for a connection. The shared secret is both the key and the IV.
+pub fn create_ciphers(shared_secret: &[u8]) -> Result<(Encryptor, Decryptor)> {
+    let encryptor = Encryptor::new_from_slices(shared_secret, shared_secret)
+        .map_err(|e| Error::EncryptionError(e.to_string()))?;
+    let decryptor = Decryptor::new_from_slices(shared_secret, shared_secret)
+        .map_err(|e| Error::EncryptionError(e.to_string()))?;
+    Ok((encryptor, decryptor))
+}
+
+/// Minecraft's take on a SHA-1 hex digest. The hash is read as a signed two's complement
+/// number, so it can be negative and has no leading zeroes.
+pub fn minecraft_digest(server_id: &str, shared_secret: &[u8], public_key: &[u8]) -> String {
+    let mut hasher = Sha1::new();
+    hasher.update(server_id.as_bytes());
+    hasher.update(shared_secret);
+    hasher.update(public_key);
+    BigInt::from_signed_bytes_be(&hasher.finalize()).to_str_radix(16)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_minecraft_digest() {
+        // Known values from wiki.vg
+        assert_eq!(
+            minecraft_digest("Notch", &[], &[]),
+            "4ed1f46bbe04bc756bcb17c0c7ce3e4632f06a48"
+        );
+        assert_eq!(
+            minecraft_digest("jeb_", &[], &[]),
+            "-7c9d5b0044c130109a5d7b5fb5c317c02b4e28c1"
+        );
+        assert_eq!(
+            minecraft_digest("simon", &[], &[]),
+            "88e16a1019277b15d58faf0541e11910eb756f6"
+        );
+    }
+}
@@ -0,0 +1,208 @@
+//! Getting players' real addresses and profiles from a proxy in front of the server. The proxy
+//! authenticates players itself, so without this everyone would show up with the proxy's address
+//! and an offline UUID.
+
+use std::io::Cursor;
+use std::net::IpAddr;
+
+use ferrumc_codec::network_types::varint::VarInt;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::net::packets::outgoing::login_success::Property;
+use crate::net::utils::authentication::ProfileProperty;
+use crate::utils::config::get_global_config;
+use crate::utils::impls::packet_impls::NetDecode;
+use crate::utils::prelude::*;
+
+/// The login plugin channel Velocity answers with the player's details.
+pub const VELOCITY_CHANNEL: &str = "velocity:player_info";
+/// The forwarding version asked for, the one without any chat signing keys.
+pub const VELOCITY_FORWARDING_VERSION: u8 = 1;
+/// Velocity's data starts with an HMAC-SHA256 of the rest of it.
+const VELOCITY_SIGNATURE_LENGTH: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Forwarding {
+    /// Players connect directly.
+    None,
+    /// BungeeCord's IP forwarding, which puts the details in the handshake's server address.
+    BungeeCord,
+    /// Velocity's modern forwarding, which sends the details in a signed login plugin response.
+    Velocity,
+}
+
+impl Forwarding {
+    pub fn from_config(name: &str) -> Self {
+        match name.to_lowercase().as_str() {
+            "none" | "" => Forwarding::None,
+            "bungeecord" | "bungee" => Forwarding::BungeeCord,
+            "velocity" => Forwarding::Velocity,
+            other => {
+                warn!("Unknown proxy forwarding \"{}\", players are expected to connect directly", other);
+                Forwarding::None
+            }
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        *self != Forwarding::None
+    }
+}
+
+/// The forwarding set in the config.
+pub fn get_forwarding() -> Forwarding {
+    Forwarding::from_config(&get_global_config().proxy.forwarding)
+}
+
+/// Who the proxy says is connecting.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ForwardedPlayer {
+    pub address: IpAddr,
+    pub uuid: Uuid,
+    /// Only Velocity sends the name, BungeeCord leaves it to Login Start.
+    pub username: Option<String>,
+    /// The player's skin and cape.
+    pub properties: Vec<Property>,
+}
+
+/// Reads BungeeCord's forwarded details from the handshake's server address, which it replaces
+/// with `host\0address\0uuid\0properties`. The properties are JSON, and can be missing.
+pub fn parse_bungeecord(server_address: &str) -> Result<ForwardedPlayer> {
+    let parts: Vec<&str> = server_address.split('\0').collect();
+    if parts.len() < 3 {
+        return Err(Error::AuthenticationFailed(
+            "The handshake has no forwarded player".to_string(),
+        ));
+    }
+
+    let address = parts[1]
+        .parse()
+        .map_err(|_| Error::AuthenticationFailed(format!("Invalid forwarded address: {}", parts[1])))?;
+    let uuid = Uuid::parse_str(parts[2])
+        .map_err(|_| Error::AuthenticationFailed(format!("Invalid forwarded UUID: {}", parts[2])))?;
+    let properties = match parts.get(3) {
+        Some(properties) => serde_json::from_str::<Vec<ProfileProperty>>(properties)
+            .map_err(|e| Error::AuthenticationFailed(format!("Invalid forwarded properties: {}", e)))?
+            .into_iter()
+            .map(Into::into)
+            .collect(),
+        None => Vec::new(),
+    };
+
+    Ok(ForwardedPlayer {
+        address,
+        uuid,
+        username: None,
+        properties,
+    })
+}
+
+/// Checks Velocity's signature on `data` with the shared `secret` and reads the player from it.
+pub async fn verify_velocity(secret: &str, data: &[u8]) -> Result<ForwardedPlayer> {
+    if data.len() < VELOCITY_SIGNATURE_LENGTH {
+        return Err(Error::AuthenticationFailed(
+            "Velocity's forwarding data is too short".to_string(),
+        ));
+    }
+    let (signature, payload) = data.split_at(VELOCITY_SIGNATURE_LENGTH);
+
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+        .map_err(|e| Error::AuthenticationFailed(e.to_string()))?;
+    mac.update(payload);
+    mac.verify_slice(signature).map_err(|_| {
+        Error::AuthenticationFailed("Velocity's forwarding data has the wrong signature".to_string())
+    })?;
+
+    let mut cursor = Cursor::new(payload);
+    let version = decode::<VarInt, _>(&mut cursor).await?.get_val();
+    if version < VELOCITY_FORWARDING_VERSION as i32 {
+        return Err(Error::AuthenticationFailed(format!(
+            "Unsupported Velocity forwarding version {}",
+            version
+        )));
+    }
+
+    let address = decode::<String, _>(&mut cursor).await?;
+    let address = address
+        .parse()
+        .map_err(|_| Error::AuthenticationFailed(format!("Invalid forwarded address: {}", address)))?;
+    let uuid = Uuid::from_u128(decode::<u128, _>(&mut cursor).await?);
+    let username = decode::<String, _>(&mut cursor).await?;
+
+    let property_count = decode::<VarInt, _>(&mut cursor).await?.get_val();
+    let mut properties = Vec::new();
+    for _ in 0..property_count {
+        let name = decode::<String, _>(&mut cursor).await?;
+        let value = decode::<String, _>(&mut cursor).await?;
+        let is_signed = decode::<bool, _>(&mut cursor).await?;
+        let signature = match is_signed {
+            true => Some(decode::<String, _>(&mut cursor).await?),
+            false => None,
+        };
+        properties.push(Property {
+            name,
+            value,
+            signature,
+        });
+    }
+
+    Ok(ForwardedPlayer {
+        address,
+        uuid,
+        username: Some(username),
+        properties,
+    })
+}
+
+async fn decode<V: NetDecode, T: tokio::io::AsyncRead + Unpin>(cursor: &mut T) -> Result<V> {
+    Ok(*V::net_decode(cursor).await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use ferrumc_codec::enc::NetEncode;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_bungeecord() {
+        let address = "localhost\u{0}127.0.0.1\u{0}069a79f444e94726a5befca90e38aaf5\u{0}[{\"name\":\"textures\",\"value\":\"abc\",\"signature\":\"def\"}]";
+        let player = parse_bungeecord(address).unwrap();
+        assert_eq!(player.address, "127.0.0.1".parse::<IpAddr>().unwrap());
+        assert_eq!(
+            player.uuid,
+            Uuid::parse_str("069a79f4-44e9-4726-a5be-fca90e38aaf5").unwrap()
+        );
+        assert_eq!(player.properties.len(), 1);
+        assert_eq!(player.properties[0].signature.as_deref(), Some("def"));
+
+        // Properties are optional, the rest isn't
+        assert!(parse_bungeecord("localhost\u{0}::1\u{0}069a79f444e94726a5befca90e38aaf5").is_ok());
+        assert!(parse_bungeecord("localhost").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_verify_velocity() {
+        let mut payload = Vec::new();
+        VarInt::new(1).net_encode(&mut payload).await.unwrap();
+        "10.0.0.2".net_encode(&mut payload).await.unwrap();
+        42u128.net_encode(&mut payload).await.unwrap();
+        "Notch".net_encode(&mut payload).await.unwrap();
+        VarInt::new(0).net_encode(&mut payload).await.unwrap();
+
+        let mut mac = Hmac::<Sha256>::new_from_slice(b"secret").unwrap();
+        mac.update(&payload);
+        let mut data = mac.finalize().into_bytes().to_vec();
+        data.extend_from_slice(&payload);
+
+        let player = verify_velocity("secret", &data).await.unwrap();
+        assert_eq!(player.uuid, Uuid::from_u128(42));
+        assert_eq!(player.username.as_deref(), Some("Notch"));
+        assert_eq!(player.address, "10.0.0.2".parse::<IpAddr>().unwrap());
+
+        assert!(verify_velocity("not the secret", &data).await.is_err());
+    }
+}
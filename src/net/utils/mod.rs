@@ -1 +1,11 @@
+pub mod authentication;
+pub mod broadcast;
+pub mod combat;
+pub mod death;
+pub mod encoded_packet;
+pub mod encrypted_stream;
+pub mod encryption;
+pub mod forwarding;
+pub mod movement;
 pub mod packet_queue;
+pub mod skins;
@@ -0,0 +1,454 @@
+use std::time::{Duration, Instant};
+
+use tracing::{debug, trace, warn};
+
+use crate::database::world_meta::WorldMeta;
+use crate::events::health_events::{damage, DamageCause};
+use crate::net::packets::outgoing::player_abilities::PlayerAbilitiesOut;
+use crate::net::packets::outgoing::respawn::Respawn;
+use crate::net::packets::outgoing::set_experience::SetExperience;
+use crate::net::packets::outgoing::set_health::SetHealth;
+use crate::net::packets::outgoing::synchronize_player_position::SynchronizePlayerPosition;
+use crate::net::packets::outgoing::update_time::UpdateTime;
+use crate::net::kick;
+use crate::net::packets::ConnectionId;
+use crate::state::GlobalState;
+use crate::utils::components::abilities::Abilities;
+use crate::utils::components::chunk_tracker::ChunkTracker;
+use crate::utils::components::dimension::{dimension_of, CurrentDimension};
+use crate::utils::components::entity_state::EntityState;
+use crate::utils::components::experience::Experience;
+use crate::utils::components::food::{exhaust, exhaustion, Food};
+use crate::utils::components::game_mode::GameMode;
+use crate::utils::components::grounded::Grounded;
+use crate::utils::components::health::Health;
+use crate::utils::components::rotation::Rotation;
+use crate::utils::components::visible_entities::VisibleEntities;
+use crate::utils::config::get_global_config;
+use crate::utils::constants::init;
+use crate::utils::encoding::position::Position;
+use crate::utils::prelude::*;
+use crate::utils::text_component::TextComponent;
+use crate::world::blocks::get_block;
+use crate::world::dimension::Dimension;
+
+/// Coordinates past this are never valid. Same limit as vanilla.
+const MAX_COORDINATE: f64 = 3.0E7;
+/// The furthest a player may move in one packet before they're put back, squared.
+const MAX_MOVE_DISTANCE_SQUARED: f64 = 100.0;
+/// How far players can fall without getting hurt, in blocks. Same as vanilla.
+const SAFE_FALL_DISTANCE: f32 = 3.0;
+/// Players this far below the bottom of the world are hurt by the void, every move.
+const VOID_DEPTH: i32 = 64;
+const VOID_DAMAGE: f32 = 4.0;
+/// How long a player that can't fly may float in the air before they're kicked. Same as vanilla.
+const MAX_FLOATING_TIME: Duration = Duration::from_secs(4);
+
+/// The outcome of checking a move sent by the client.
+#[derive(Debug, PartialEq)]
+pub enum MoveCheck {
+    Valid,
+    /// Moved further in one packet than any legit client would.
+    TooFast,
+    /// NaN, infinite, or outside the world border.
+    Invalid,
+}
+
+/// Checks a move from the player's current position to the one they sent.
+pub fn check_move(from: &Position, to: (f64, f64, f64)) -> MoveCheck {
+    let (x, y, z) = to;
+    if ![x, y, z].iter().all(|v| v.is_finite()) {
+        return MoveCheck::Invalid;
+    }
+    if x.abs() > MAX_COORDINATE || z.abs() > MAX_COORDINATE {
+        return MoveCheck::Invalid;
+    }
+
+    let dx = x - from.x as f64;
+    let dy = y - from.y as f64;
+    let dz = z - from.z as f64;
+    if dx * dx + dy * dy + dz * dz > MAX_MOVE_DISTANCE_SQUARED {
+        return MoveCheck::TooFast;
+    }
+
+    MoveCheck::Valid
+}
+
+/// Applies a movement packet to the player's components.
+///
+/// `position` and `rotation` are only set if the packet carries them. Moves that fail
+/// [check_move] are dropped and the player is teleported back to where the server thinks they are.
+pub async fn handle_movement(
+    conn_id: ConnectionId,
+    state: GlobalState,
+    position: Option<(f64, f64, f64)>,
+    rotation: Option<(f32, f32)>,
+    on_ground: bool,
+) -> Result<()> {
+    let component_storage = state.world.get_component_storage();
+    let mut descending = false;
+    let mut moved = None;
+    let mut exact_y = None;
+
+    if let Some(new_position) = position {
+        let mut current = component_storage.get_mut::<Position>(conn_id).await?;
+
+        match check_move(&current, new_position) {
+            MoveCheck::Valid => {
+                descending = new_position.1 < current.y as f64;
+                let ascending = new_position.1 > current.y as f64;
+                let from = current.clone();
+                *current = Position {
+                    x: new_position.0.floor() as i32,
+                    y: new_position.1.floor() as i16,
+                    z: new_position.2.floor() as i32,
+                };
+                moved = Some((from, current.clone(), ascending));
+                exact_y = Some(new_position.1);
+            }
+            check => {
+                warn!(
+                    "Rejected move for {} from {} to {:?}: {:?}",
+                    conn_id, *current, new_position, check
+                );
+                let position = current.clone();
+                drop(current);
+                return sync_position(conn_id, state.clone(), &position).await;
+            }
+        }
+    }
+
+    if let Some((yaw, pitch)) = rotation {
+        if !yaw.is_finite() || !pitch.is_finite() {
+            return Err(Error::Generic(format!(
+                "Invalid rotation from {}: {}, {}",
+                conn_id, yaw, pitch
+            )));
+        }
+
+        let mut current = component_storage
+            .get_mut_or_insert_with(conn_id, || Rotation::new(0.0, 0.0))
+            .await;
+        current.yaw = yaw % 360.0;
+        current.pitch = pitch.clamp(-90.0, 90.0);
+    }
+
+    let was_grounded = {
+        let mut grounded = component_storage
+            .get_mut_or_insert_with(conn_id, Grounded::default)
+            .await;
+        let was_grounded = grounded.is_grounded;
+        grounded.set_grounded(on_ground);
+        was_grounded
+    };
+
+    trace!("Moved {} to {:?} {:?}", conn_id, position, rotation);
+
+    if let Some((from, to, ascending)) = moved {
+        let jumped = was_grounded && !on_ground && ascending;
+        exhaust_for_move(conn_id, &state, &from, &to, jumped).await;
+    }
+    handle_fall(conn_id, &state, exact_y, on_ground).await?;
+
+    check_floating(conn_id, state, !on_ground && !descending).await
+}
+
+/// Keeps track of how far a player has fallen, and hurts them when they land or fall out of the
+/// world.
+async fn handle_fall(
+    conn_id: ConnectionId,
+    state: &GlobalState,
+    y: Option<f64>,
+    on_ground: bool,
+) -> Result<()> {
+    let component_storage = state.world.get_component_storage();
+    let flying = component_storage
+        .get::<Abilities>(conn_id)
+        .await
+        .is_ok_and(|abilities| abilities.flying);
+    let position = component_storage.get::<Position>(conn_id).await?.clone();
+    let dimension = dimension_of(state, conn_id).await;
+
+    let (fallen, falling_far) = {
+        let mut grounded = component_storage
+            .get_mut_or_insert_with(conn_id, Grounded::default)
+            .await;
+        if flying {
+            grounded.reset_fall();
+        }
+        let fallen = grounded.record_fall(y, on_ground);
+        (fallen, grounded.fall_distance > SAFE_FALL_DISTANCE)
+    };
+
+    if falling_far {
+        // Falling into water or grabbing a ladder breaks the fall
+        let block = get_block(state, position.x, position.y as i32, position.z, dimension.name())
+            .await?;
+        if fall_damage_multiplier(&block.name) == 0.0 {
+            component_storage
+                .get_mut_or_insert_with(conn_id, Grounded::default)
+                .await
+                .fall_distance = 0.0;
+        }
+    }
+
+    if let Some(fallen) = fallen.filter(|&fallen| fallen > SAFE_FALL_DISTANCE) {
+        let (x, y, z) = (position.x, position.y as i32, position.z);
+        let mut multiplier = 1.0f32;
+        // What they're standing in, e.g. a bed, and what they're standing on
+        for y in [y, y - 1] {
+            let block = get_block(state, x, y, z, dimension.name()).await?;
+            multiplier = multiplier.min(fall_damage_multiplier(&block.name));
+        }
+        let amount = ((fallen - SAFE_FALL_DISTANCE) * multiplier).ceil();
+        trace!("{} fell {} blocks", conn_id, fallen);
+        damage(state, conn_id, amount, DamageCause::Fall).await?;
+    }
+
+    if (position.y as i32) < dimension.min_y() - VOID_DEPTH {
+        damage(state, conn_id, VOID_DAMAGE, DamageCause::Void).await?;
+    }
+    Ok(())
+}
+
+/// How much of the fall damage a player takes for landing in or on a block. Liquids and blocks
+/// players climb break the fall entirely.
+fn fall_damage_multiplier(block: &str) -> f32 {
+    match block.strip_prefix("minecraft:").unwrap_or(block) {
+        "water" | "bubble_column" | "ladder" | "vine" | "scaffolding" | "cobweb" | "slime_block"
+        | "powder_snow" | "twisting_vines" | "twisting_vines_plant" | "weeping_vines"
+        | "weeping_vines_plant" | "cave_vines" | "cave_vines_plant" => 0.0,
+        "hay_block" | "honey_block" => 0.2,
+        name if name.ends_with("_bed") => 0.5,
+        _ => 1.0,
+    }
+}
+
+/// Makes players hungrier for sprinting and jumping.
+async fn exhaust_for_move(
+    conn_id: ConnectionId,
+    state: &GlobalState,
+    from: &Position,
+    to: &Position,
+    jumped: bool,
+) {
+    let sprinting = state
+        .world
+        .get_component::<EntityState>(conn_id)
+        .await
+        .is_ok_and(|entity_state| entity_state.sprinting);
+    let mut amount = 0.0;
+    if sprinting {
+        let (dx, dz) = ((to.x - from.x) as f32, (to.z - from.z) as f32);
+        amount += exhaustion::SPRINTING * (dx * dx + dz * dz).sqrt();
+    }
+    if jumped {
+        amount += if sprinting {
+            exhaustion::SPRINT_JUMP
+        } else {
+            exhaustion::JUMP
+        };
+    }
+    if amount > 0.0 {
+        exhaust(state, conn_id, amount).await;
+    }
+}
+
+/// Kicks players that can't fly but have been floating in the air for too long. Like vanilla,
+/// players next to blocks are let off, since they could be climbing or swimming.
+async fn check_floating(conn_id: ConnectionId, state: GlobalState, floating: bool) -> Result<()> {
+    let component_storage = state.world.get_component_storage();
+    let may_fly = get_global_config().flight.allowed
+        || component_storage
+            .get::<Abilities>(conn_id)
+            .await
+            .is_ok_and(|abilities| abilities.allow_flying);
+    let now = Instant::now();
+    let floating_for = component_storage
+        .get_mut_or_insert_with(conn_id, Grounded::default)
+        .await
+        .floating_for(floating && !may_fly, now);
+    if floating_for <= MAX_FLOATING_TIME {
+        return Ok(());
+    }
+
+    let position = component_storage.get::<Position>(conn_id).await?.clone();
+    if near_blocks(&state, conn_id, &position).await? {
+        // Starts over, so they aren't looked at on every move while they climb
+        component_storage
+            .get_mut_or_insert_with(conn_id, Grounded::default)
+            .await
+            .floating_since = Some(now);
+        return Ok(());
+    }
+
+    debug!("Kicking {} for floating at {} for {:?}", conn_id, position, floating_for);
+    let reason = TextComponent::text("Flying is not enabled on this server");
+    let conn = state.connections.get_connection(conn_id)?;
+    kick(&conn, &reason, state.clone()).await
+}
+
+/// Whether there's anything but air in the blocks around a player or right under them.
+async fn near_blocks(
+    state: &GlobalState,
+    conn_id: ConnectionId,
+    position: &Position,
+) -> Result<bool> {
+    let dimension = dimension_of(state, conn_id).await;
+    for x in position.x - 1..=position.x + 1 {
+        for z in position.z - 1..=position.z + 1 {
+            for y in position.y as i32 - 1..=position.y as i32 + 1 {
+                if !get_block(state, x, y, z, dimension.name()).await?.is_air() {
+                    return Ok(true);
+                }
+            }
+        }
+    }
+    Ok(false)
+}
+
+/// Moves a player, and tells their client about it.
+pub async fn teleport(conn_id: ConnectionId, state: GlobalState, position: Position) -> Result<()> {
+    let component_storage = state.world.get_component_storage();
+    *component_storage.get_mut::<Position>(conn_id).await? = position.clone();
+    component_storage
+        .get_mut_or_insert_with(conn_id, Grounded::default)
+        .await
+        .reset_fall();
+
+    sync_position(conn_id, state, &position).await
+}
+
+/// Moves a player to another dimension, then teleports them to `position` in it.
+///
+/// The client forgets its chunks and entities when it respawns, so the chunk sender and entity
+/// broadcaster start over for the player.
+pub async fn change_dimension(
+    conn_id: ConnectionId,
+    state: GlobalState,
+    dimension: Dimension,
+    position: Position,
+) -> Result<()> {
+    respawn_in(conn_id, state, dimension, position, false).await
+}
+
+/// Brings a player back after dying, at `position` in `dimension`. Like [change_dimension], but
+/// the client starts them over as a new player.
+pub async fn respawn_after_death(
+    conn_id: ConnectionId,
+    state: GlobalState,
+    dimension: Dimension,
+    position: Position,
+) -> Result<()> {
+    respawn_in(conn_id, state, dimension, position, true).await
+}
+
+async fn respawn_in(
+    conn_id: ConnectionId,
+    state: GlobalState,
+    dimension: Dimension,
+    position: Position,
+    died: bool,
+) -> Result<()> {
+    let component_storage = state.world.get_component_storage();
+    let game_mode = component_storage
+        .get::<GameMode>(conn_id)
+        .await
+        .map_or(init::DEFAULT_GAME_MODE, |game_mode| game_mode.mode);
+    let (seed_hash, time, weather) = {
+        let world_meta = state.world.resource::<WorldMeta>().await?;
+        (
+            world_meta.seed_hash(),
+            UpdateTime::new(&world_meta),
+            world_meta.weather.current_events(),
+        )
+    };
+    // The client resets its abilities when it respawns, so they're sent again
+    let abilities = component_storage
+        .get::<Abilities>(conn_id)
+        .await
+        .map_or_else(|_| Abilities::configured(game_mode), |abilities| *abilities);
+
+    {
+        // Held until the dimension is switched, so the chunk sender can't send the old
+        // dimension's chunks after the respawn
+        let mut tracker = component_storage
+            .get_mut_or_insert_with::<ChunkTracker>(conn_id, Default::default)
+            .await;
+
+        let conn = state.connections.get_connection(conn_id)?;
+        let conn = conn.read().await;
+        let protocol = conn.metadata.protocol();
+        let respawn = if died {
+            Respawn::after_death(dimension, seed_hash, game_mode, protocol)
+        } else {
+            Respawn::change_dimension(dimension, seed_hash, game_mode, protocol)
+        };
+        conn.send_packet(respawn).await?;
+        // The client starts the new level without any time or weather
+        conn.send_packet(time).await?;
+        for event in weather {
+            conn.send_packet(event).await?;
+        }
+        conn.send_packet(PlayerAbilitiesOut::new(&abilities))
+            .await?;
+        if let (Ok(health), Ok(food)) = (
+            component_storage.get::<Health>(conn_id).await,
+            component_storage.get::<Food>(conn_id).await,
+        ) {
+            conn.send_packet(SetHealth::new(&health, &food)).await?;
+        }
+        if let Ok(experience) = component_storage.get::<Experience>(conn_id).await {
+            conn.send_packet(SetExperience::new(&experience)).await?;
+        }
+        drop(conn);
+
+        component_storage.insert(conn_id, CurrentDimension::new(dimension));
+        tracker.switch_dimension(dimension);
+    }
+    component_storage
+        .get_mut_or_insert_with::<VisibleEntities>(conn_id, Default::default)
+        .await
+        .entities
+        .clear();
+
+    teleport(conn_id, state, position).await
+}
+
+async fn sync_position(conn_id: ConnectionId, state: GlobalState, position: &Position) -> Result<()> {
+    let rotation = state
+        .world
+        .get_component::<Rotation>(conn_id)
+        .await
+        .map(|rotation| rotation.clone())
+        .unwrap_or_else(|_| Rotation::new(0.0, 0.0));
+
+    let conn = state.connections.get_connection(conn_id)?;
+    let conn = conn.read().await;
+    conn.send_packet(SynchronizePlayerPosition::new(position, &rotation))
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_move() {
+        let from = Position::new(0, 64, 0);
+
+        assert_eq!(check_move(&from, (0.5, 64.0, 0.3)), MoveCheck::Valid);
+        assert_eq!(check_move(&from, (5.0, 66.0, 5.0)), MoveCheck::Valid);
+        assert_eq!(check_move(&from, (50.0, 64.0, 0.0)), MoveCheck::TooFast);
+        assert_eq!(check_move(&from, (f64::NAN, 64.0, 0.0)), MoveCheck::Invalid);
+        assert_eq!(check_move(&from, (0.0, 64.0, 4.0E7)), MoveCheck::Invalid);
+    }
+
+    #[test]
+    fn test_fall_damage_multiplier() {
+        assert_eq!(fall_damage_multiplier("minecraft:stone"), 1.0);
+        assert_eq!(fall_damage_multiplier("minecraft:water"), 0.0);
+        assert_eq!(fall_damage_multiplier("minecraft:hay_block"), 0.2);
+        assert_eq!(fall_damage_multiplier("minecraft:red_bed"), 0.5);
+    }
+}
@@ -16,6 +16,20 @@ impl PacketQueue {
     pub async fn queue(&mut self, packet: impl NetEncode) -> Result<()> {
         packet.net_encode(&mut self.queue).await.map_err(Into::into)
     }
+
+    /// Queue everything from another queue, e.g. packets that go to several players.
+    pub fn append(&mut self, other: &PacketQueue) {
+        self.queue.extend_from_slice(&other.queue);
+    }
+
+    /// The size of all the queued packets, in bytes.
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
 }
 
 impl Default for PacketQueue {
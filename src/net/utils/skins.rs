@@ -0,0 +1,132 @@
+//! Looking up skins for players in offline mode, which don't come with one.
+//!
+//! The skin is looked up by username through Mojang's profile API. It's the skin of whoever owns
+//! the name, which is usually the player, but doesn't have to be. Lookups are cached, since the
+//! API only allows so many requests.
+
+use std::time::{Duration, Instant};
+
+use dashmap::DashMap;
+use serde::Deserialize;
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+use crate::net::packets::outgoing::login_success::Property;
+use crate::net::utils::authentication::GameProfile;
+use crate::utils::config::get_global_config;
+use crate::utils::prelude::*;
+
+const PROFILE_API: &str = "https://api.mojang.com/users/profiles/minecraft";
+const PROFILE_SERVER: &str = "https://sessionserver.mojang.com/session/minecraft/profile";
+/// Names that haven't been looked up for a while are forgotten once this many are cached.
+const MAX_CACHED_PROFILES: usize = 1024;
+
+/// What the profile API answers with, the name's owner.
+#[derive(Debug, Deserialize)]
+struct ProfileId {
+    id: Uuid,
+}
+
+/// Recent lookups by username, see [Skins](crate::utils::config::Skins) for how long they're kept.
+#[derive(Default)]
+pub struct SkinCache {
+    profiles: DashMap<String, (Instant, Vec<Property>)>,
+}
+
+impl SkinCache {
+    /// The signed skin and cape of the account that owns `username`, if fetching skins is turned
+    /// on. Players without an account, and failed lookups, get none, so they show up as Steve or
+    /// Alex.
+    pub async fn properties(&self, username: &str) -> Vec<Property> {
+        let config = &get_global_config().skins;
+        // Anything else can't be a real account's name, and isn't safe to put in the URL
+        let valid = (1..=16).contains(&username.len())
+            && username.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !config.fetch || !valid {
+            return Vec::new();
+        }
+        let max_age = Duration::from_secs(config.cache_duration);
+        if let Some(properties) = self.cached_at(username, max_age, Instant::now()) {
+            return properties;
+        }
+
+        match fetch_properties(username).await {
+            Ok(properties) => {
+                self.insert_at(username, properties.clone(), max_age, Instant::now());
+                properties
+            }
+            Err(e) => {
+                // Not cached, it might work next time
+                warn!("Failed to look up {}'s skin: {}", username, e);
+                Vec::new()
+            }
+        }
+    }
+
+    fn cached_at(&self, username: &str, max_age: Duration, now: Instant) -> Option<Vec<Property>> {
+        let entry = self.profiles.get(&username.to_lowercase())?;
+        let (fetched, properties) = &*entry;
+        (now.duration_since(*fetched) < max_age).then(|| properties.clone())
+    }
+
+    fn insert_at(
+        &self,
+        username: &str,
+        properties: Vec<Property>,
+        max_age: Duration,
+        now: Instant,
+    ) {
+        if self.profiles.len() >= MAX_CACHED_PROFILES {
+            self.profiles
+                .retain(|_, (fetched, _)| now.duration_since(*fetched) < max_age);
+        }
+        self.profiles
+            .insert(username.to_lowercase(), (now, properties));
+    }
+}
+
+/// Asks Mojang who owns `username`, then for their profile with its signed properties. Names no
+/// one owns have none.
+async fn fetch_properties(username: &str) -> Result<Vec<Property>> {
+    let response = reqwest::get(format!("{PROFILE_API}/{username}")).await?;
+    if response.status() == reqwest::StatusCode::NOT_FOUND
+        || response.status() == reqwest::StatusCode::NO_CONTENT
+    {
+        debug!("No account owns the name {}", username);
+        return Ok(Vec::new());
+    }
+    let ProfileId { id } = response.error_for_status()?.json().await?;
+
+    let url = format!("{PROFILE_SERVER}/{}?unsigned=false", id.simple());
+    let profile: GameProfile = reqwest::get(url).await?.error_for_status()?.json().await?;
+    debug!("Fetched the skin of {} ({})", profile.name, profile.id);
+    Ok(profile.properties.into_iter().map(Into::into).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache() {
+        let cache = SkinCache::default();
+        let now = Instant::now();
+        let max_age = Duration::from_secs(60);
+        let textures = vec![Property {
+            name: "textures".to_string(),
+            value: "abc".to_string(),
+            signature: Some("def".to_string()),
+        }];
+
+        assert_eq!(cache.cached_at("Notch", max_age, now), None);
+        cache.insert_at("Notch", textures.clone(), max_age, now);
+        // Names aren't case sensitive
+        assert_eq!(cache.cached_at("notch", max_age, now), Some(textures));
+        let later = now + Duration::from_secs(61);
+        assert_eq!(cache.cached_at("Notch", max_age, later), None);
+
+        // Names no one owns are cached too
+        cache.insert_at("nobody", Vec::new(), max_age, now);
+        assert_eq!(cache.cached_at("nobody", max_age, now), Some(Vec::new()));
+    }
+}
@@ -0,0 +1,351 @@
+use std::any::Any;
+use std::collections::HashMap;
+use std::env::consts::DLL_EXTENSION;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use libloading::Library;
+use parking_lot::RwLock;
+use tracing::{debug, error, info, warn};
+
+use crate::commands::resend_commands;
+use crate::ecs::access::Access;
+use crate::events::creation::registry::EventHandlerWrapper;
+use crate::net::systems::TickedSystem;
+#[cfg(feature = "wasm")]
+use crate::plugins::wasm::WasmPlugin;
+use crate::plugins::{FerrumcPlugin, PluginDeclaration, PluginRegistrar, DECLARATION_SYMBOL};
+use crate::state::GlobalState;
+use crate::utils::config::{get_global_config, Plugins};
+use crate::utils::constants::PLUGINS_DIRECTORY;
+use crate::utils::prelude::*;
+
+/// The plugins that are loaded, see [plugins](crate::plugins).
+#[derive(Default)]
+pub struct PluginManager {
+    plugins: RwLock<HashMap<String, LoadedPlugin>>,
+    /// How many libraries have been loaded, so each copy gets its own file.
+    loads: AtomicUsize,
+}
+
+struct LoadedPlugin {
+    // Fields are dropped in order, so the library goes last, once nothing from it is left
+    plugin: Box<dyn FerrumcPlugin>,
+    systems: Vec<Arc<dyn TickedSystem>>,
+    commands: Vec<String>,
+    /// The file in the plugins directory, which it's reloaded from.
+    path: PathBuf,
+    /// Native plugins only, WebAssembly ones don't need anything kept around.
+    library: Option<NativeLibrary>,
+}
+
+/// A native plugin's library.
+struct NativeLibrary {
+    /// The copy that was actually loaded.
+    copy: PathBuf,
+    library: Arc<Library>,
+}
+
+impl PluginManager {
+    /// The loaded plugins' names and versions, by name.
+    pub fn plugins(&self) -> Vec<(String, String)> {
+        let mut plugins = self
+            .plugins
+            .read()
+            .values()
+            .map(|loaded| {
+                (
+                    loaded.plugin.name().to_string(),
+                    loaded.plugin.version().to_string(),
+                )
+            })
+            .collect::<Vec<_>>();
+        plugins.sort();
+        plugins
+    }
+
+    /// The systems plugins have added, for the [GameLoop](crate::net::systems::game_loop::GameLoop).
+    pub fn ticked_systems(&self) -> Vec<Arc<dyn TickedSystem>> {
+        self.plugins
+            .read()
+            .values()
+            .flat_map(|loaded| loaded.systems.iter().cloned())
+            .collect()
+    }
+
+    /// Loads every library and WebAssembly module in the plugins directory, returning how many were
+    /// loaded. One that fails is skipped.
+    pub async fn load_all(&self, state: &GlobalState) -> Result<usize> {
+        let directory = Path::new(PLUGINS_DIRECTORY);
+        if !tokio::fs::try_exists(directory).await? {
+            return Ok(0);
+        }
+
+        let mut loaded = 0;
+        let mut entries = tokio::fs::read_dir(directory).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            let extension = path.extension().and_then(|extension| extension.to_str());
+            if extension != Some(DLL_EXTENSION) && extension != Some("wasm") {
+                continue;
+            }
+            match self.load(state, &path).await {
+                Ok(_) => loaded += 1,
+                Err(e) => error!("Failed to load plugin {}: {}", path.display(), e),
+            }
+        }
+        Ok(loaded)
+    }
+
+    /// Loads the plugin in a library, or a WebAssembly module if it ends in `.wasm`, returning its
+    /// name.
+    ///
+    /// A library is copied first and the copy is loaded, so the original can be replaced while
+    /// it's loaded, and a reload doesn't get the old one back from the OS.
+    pub async fn load(&self, state: &GlobalState, path: &Path) -> Result<String> {
+        let config = get_global_config();
+        let name = if path.extension().and_then(|extension| extension.to_str()) == Some("wasm") {
+            let plugin = load_wasm(path, &config.plugins).await?;
+            self.install(state, path, None, plugin)?
+        } else {
+            if !config.plugins.native {
+                return Err(Error::Generic("Native plugins are disabled".to_string()));
+            }
+            let copy = self.copy_library(path).await?;
+            let result = self.load_copy(state, path, &copy);
+            if result.is_err() {
+                // The library has been closed by now
+                let _ = tokio::fs::remove_file(&copy).await;
+            }
+            result?
+        };
+        resend_commands(state).await?;
+        Ok(name)
+    }
+
+    fn load_copy(&self, state: &GlobalState, path: &Path, copy: &Path) -> Result<String> {
+        // Safety: loading a library runs its initialisers, which is only as safe as the plugin is
+        let library = unsafe { Library::new(copy) }.map_err(library_error)?;
+        let library = Arc::new(library);
+        let plugin = {
+            // Safety: the symbol is a PluginDeclaration if it was exported with export_plugin, and
+            // its version is checked before anything else in it is used
+            let declaration =
+                unsafe { library.get::<*const PluginDeclaration>(DECLARATION_SYMBOL) }
+                    .map_err(library_error)?;
+            let declaration = unsafe { &**declaration };
+            declaration.plugin()?
+        };
+        let library = NativeLibrary {
+            copy: copy.to_path_buf(),
+            library,
+        };
+        self.install(state, path, Some(library), plugin)
+    }
+
+    // Parameters are dropped in reverse, so the plugin goes before its library if it isn't loaded
+    fn install(
+        &self,
+        state: &GlobalState,
+        path: &Path,
+        library: Option<NativeLibrary>,
+        plugin: Box<dyn FerrumcPlugin>,
+    ) -> Result<String> {
+        let name = plugin.name().to_string();
+        if self.plugins.read().contains_key(&name) {
+            return Err(Error::Generic(format!("{} is already loaded", name)));
+        }
+        let mut registrar = PluginRegistrar::new(state.clone());
+        plugin.on_load(&mut registrar)?;
+
+        let mut plugins = self.plugins.write();
+        let taken = registrar
+            .commands
+            .iter()
+            .find(|command| state.commands.get(&command.name).is_some());
+        if let Some(command) = taken {
+            let message = format!("/{} is already registered", command.name);
+            plugin.on_unload(state);
+            return Err(Error::Generic(message));
+        }
+        if plugins.contains_key(&name) {
+            plugin.on_unload(state);
+            return Err(Error::Generic(format!("{} is already loaded", name)));
+        }
+
+        let commands = registrar
+            .commands
+            .iter()
+            .map(|command| command.name.clone())
+            .collect();
+        let shared = library.as_ref().map(|library| Arc::clone(&library.library));
+        for command in registrar.commands {
+            let command = match &shared {
+                Some(library) => {
+                    command.keep_alive(Arc::clone(library) as Arc<dyn Any + Send + Sync>)
+                }
+                None => command,
+            };
+            state.commands.register_command(command);
+        }
+        for (priority, handler) in registrar.event_handlers {
+            let handler = PluginEventHandler {
+                handler,
+                _library: shared.clone(),
+            };
+            state
+                .event_dispatcher
+                .add_handler(&name, priority, Arc::new(handler));
+        }
+        let systems = registrar
+            .systems
+            .into_iter()
+            .map(|system| {
+                Arc::new(PluginSystem {
+                    system,
+                    _library: shared.clone(),
+                }) as Arc<dyn TickedSystem>
+            })
+            .collect();
+
+        info!("Loaded plugin {} {}", name, plugin.version());
+        let loaded = LoadedPlugin {
+            plugin,
+            systems,
+            commands,
+            path: path.to_path_buf(),
+            library,
+        };
+        plugins.insert(name.clone(), loaded);
+        Ok(name)
+    }
+
+    /// Unloads a plugin, taking away everything it registered. A native plugin's library is closed
+    /// once nothing from it is running any more, e.g. a command that was in the middle of running.
+    pub async fn unload(&self, state: &GlobalState, name: &str) -> Result<()> {
+        let Some(loaded) = self.plugins.write().remove(name) else {
+            return Err(Error::Generic(format!("{} isn't loaded", name)));
+        };
+
+        loaded.plugin.on_unload(state);
+        for command in &loaded.commands {
+            state.commands.unregister(command);
+        }
+        state.event_dispatcher.remove_handlers(name);
+        let copy = loaded.library.as_ref().map(|library| library.copy.clone());
+        drop(loaded);
+
+        if let Some(copy) = copy {
+            if let Err(e) = tokio::fs::remove_file(&copy).await {
+                debug!("Failed to remove {}: {}", copy.display(), e);
+            }
+        }
+        info!("Unloaded plugin {}", name);
+        resend_commands(state).await
+    }
+
+    /// Unloads a plugin and loads it again from its file, which may have been rebuilt since.
+    /// Returns its name, in case the new version has a different one.
+    pub async fn reload(&self, state: &GlobalState, name: &str) -> Result<String> {
+        let path = self
+            .plugins
+            .read()
+            .get(name)
+            .map(|loaded| loaded.path.clone());
+        let Some(path) = path else {
+            return Err(Error::Generic(format!("{} isn't loaded", name)));
+        };
+        self.unload(state, name).await?;
+        self.load(state, &path).await
+    }
+
+    /// Unloads every plugin, for when the server stops.
+    pub async fn unload_all(&self, state: &GlobalState) {
+        let names = self.plugins.read().keys().cloned().collect::<Vec<_>>();
+        for name in names {
+            if let Err(e) = self.unload(state, &name).await {
+                warn!("Failed to unload plugin {}: {}", name, e);
+            }
+        }
+    }
+
+    async fn copy_library(&self, path: &Path) -> Result<PathBuf> {
+        let directory = std::env::temp_dir().join("ferrumc-plugins");
+        tokio::fs::create_dir_all(&directory).await?;
+        let stem = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or("plugin");
+        let load = self.loads.fetch_add(1, Ordering::Relaxed);
+        let file_name = format!("{}-{}-{}.{}", stem, std::process::id(), load, DLL_EXTENSION);
+        let copy = directory.join(file_name);
+        tokio::fs::copy(path, &copy).await?;
+        Ok(copy)
+    }
+}
+
+#[cfg(feature = "wasm")]
+async fn load_wasm(path: &Path, config: &Plugins) -> Result<Box<dyn FerrumcPlugin>> {
+    if !config.wasm {
+        return Err(Error::Generic(
+            "WebAssembly plugins are disabled".to_string(),
+        ));
+    }
+    Ok(Box::new(WasmPlugin::load(path, config).await?))
+}
+
+#[cfg(not(feature = "wasm"))]
+async fn load_wasm(_path: &Path, _config: &Plugins) -> Result<Box<dyn FerrumcPlugin>> {
+    Err(Error::Generic(
+        "WebAssembly plugins need the server to be built with the wasm feature".to_string(),
+    ))
+}
+
+fn library_error(e: libloading::Error) -> Error {
+    Error::Generic(format!("Invalid plugin library: {}", e))
+}
+
+/// An event handler from a plugin, which keeps a native plugin's library loaded while it's around.
+struct PluginEventHandler {
+    handler: Box<dyn EventHandlerWrapper>,
+    _library: Option<Arc<Library>>,
+}
+
+impl EventHandlerWrapper for PluginEventHandler {
+    fn handle(
+        &self,
+        event: Arc<dyn Any + Send + Sync>,
+        state: GlobalState,
+    ) -> Pin<Box<dyn Future<Output = ()> + Send + '_>> {
+        self.handler.handle(event, state)
+    }
+
+    fn event_type_id(&self) -> std::any::TypeId {
+        self.handler.event_type_id()
+    }
+}
+
+/// A ticked system from a plugin, which keeps a native plugin's library loaded while it's around.
+struct PluginSystem {
+    system: Box<dyn TickedSystem>,
+    _library: Option<Arc<Library>>,
+}
+
+#[async_trait]
+impl TickedSystem for PluginSystem {
+    async fn tick(&self, state: GlobalState, tick: u64) -> Result<()> {
+        self.system.tick(state, tick).await
+    }
+
+    fn name(&self) -> &'static str {
+        self.system.name()
+    }
+
+    fn access(&self) -> Access {
+        self.system.access()
+    }
+}
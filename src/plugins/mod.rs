@@ -0,0 +1,241 @@
+//! Plugins, loaded from dynamic libraries in the `plugins` directory when the server starts.
+//!
+//! A plugin is a `cdylib` crate that depends on this one, implements [FerrumcPlugin] and exports
+//! it with [export_plugin](crate::export_plugin). When it's loaded, it registers its commands,
+//! event handlers and ticked systems with a [PluginRegistrar], and they're all taken away again
+//! when it's unloaded. `/plugin` loads, unloads and reloads them while the server's running, so a
+//! rebuilt plugin can be swapped in without a restart.
+//!
+//! Native plugins are same-toolchain only: Rust doesn't have a stable ABI, so a plugin has to be
+//! built with the same compiler and version of the server as the server itself. Only its
+//! [PluginDeclaration] is laid out the same by every compiler, so that it can say which it was
+//! built with, and the plugin isn't loaded if they don't match.
+//!
+//! With the `wasm` feature, plugins can also be WebAssembly modules, which don't have to be
+//! trusted, since they can only do what the config allows them to. Either kind can be turned off in
+//! the config.
+
+use std::future::Future;
+use std::sync::Arc;
+
+use crate::commands::Command;
+use crate::events::creation::event::Event;
+use crate::events::creation::registry::{ClosureEventHandler, EventHandlerWrapper};
+use crate::net::systems::TickedSystem;
+use crate::state::GlobalState;
+use crate::utils::prelude::*;
+
+pub mod manager;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+pub use manager::PluginManager;
+
+/// Changes whenever [PluginDeclaration] does.
+pub const PLUGIN_ABI_VERSION: u32 = 2;
+/// The compiler the server was built with.
+pub const RUSTC_VERSION: &str = env!("FERRUMC_RUSTC_VERSION");
+pub const SERVER_VERSION: &str = env!("CARGO_PKG_VERSION");
+/// What [export_plugin](crate::export_plugin) exports the [PluginDeclaration] as.
+pub const DECLARATION_SYMBOL: &[u8] = b"FERRUMC_PLUGIN\0";
+
+/// What a plugin implements. There's one of these for each loaded plugin, made when it's loaded
+/// and dropped when it's unloaded.
+pub trait FerrumcPlugin: Send + Sync {
+    /// Has to be unique, it's what the plugin's unloaded and reloaded by.
+    fn name(&self) -> &str;
+    fn version(&self) -> &str;
+    /// Registers everything the plugin adds to the server. If it fails, the plugin isn't loaded.
+    fn on_load(&self, registrar: &mut PluginRegistrar) -> Result<()>;
+    /// Called just before the plugin's unloaded. Anything it's started that isn't registered with
+    /// the [PluginRegistrar], like its own tasks and ones it's [scheduled](crate::net::scheduler),
+    /// has to be stopped here, since its code won't be there to run afterwards.
+    fn on_unload(&self, _state: &GlobalState) {}
+}
+
+/// What a plugin's library exports, to say which versions it was built against and how to make the
+/// plugin. It only has C types, so it can be read whichever compiler the plugin was built with.
+#[repr(C)]
+pub struct PluginDeclaration {
+    /// Comes first, so it can be checked before anything else is read.
+    pub abi_version: u32,
+    pub rustc_version: RawStr,
+    pub server_version: RawStr,
+    /// Returns a boxed `Box<dyn FerrumcPlugin>`, which only means anything to the same compiler,
+    /// so it's only called once [check](PluginDeclaration::check) has passed.
+    pub create: extern "C" fn() -> *mut Box<dyn FerrumcPlugin>,
+}
+
+impl PluginDeclaration {
+    /// Checks that the plugin was built the same way as the server.
+    pub fn check(&self) -> Result<()> {
+        if self.abi_version != PLUGIN_ABI_VERSION {
+            return Err(Error::Generic(format!(
+                "Plugin ABI version {} isn't supported, the server is on version {}",
+                self.abi_version, PLUGIN_ABI_VERSION
+            )));
+        }
+        let rustc_version = self.rustc_version.as_str();
+        if rustc_version != RUSTC_VERSION {
+            return Err(Error::Generic(format!(
+                "Built with {}, but the server was built with {}",
+                rustc_version, RUSTC_VERSION
+            )));
+        }
+        let server_version = self.server_version.as_str();
+        if server_version != SERVER_VERSION {
+            return Err(Error::Generic(format!(
+                "Built for version {} of the server, but this is {}",
+                server_version, SERVER_VERSION
+            )));
+        }
+        Ok(())
+    }
+
+    /// Makes the plugin, after checking it was built the same way as the server.
+    pub fn plugin(&self) -> Result<Box<dyn FerrumcPlugin>> {
+        self.check()?;
+        // Safety: create returns a pointer from Box::into_raw, and the plugin was built with the
+        // same compiler, so the box is laid out the same
+        Ok(unsafe { *Box::from_raw((self.create)()) })
+    }
+}
+
+/// A `&'static str` as a pointer and a length, which every compiler lays out the same way.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct RawStr {
+    ptr: *const u8,
+    len: usize,
+}
+
+// Safety: it can only be made from a `&'static str`, which can be shared between threads
+unsafe impl Send for RawStr {}
+unsafe impl Sync for RawStr {}
+
+impl RawStr {
+    pub const fn new(s: &'static str) -> Self {
+        Self {
+            ptr: s.as_ptr(),
+            len: s.len(),
+        }
+    }
+
+    /// Anything that isn't UTF-8 is replaced, so a broken plugin just fails its checks.
+    pub fn as_str(&self) -> &str {
+        // Safety: it was made from a `&'static str`, which lives as long as the library it's in
+        let bytes = unsafe { std::slice::from_raw_parts(self.ptr, self.len) };
+        std::str::from_utf8(bytes).unwrap_or("(not UTF-8)")
+    }
+}
+
+/// Exports a plugin from its library, given an expression that makes it, e.g.
+/// `export_plugin!(MyPlugin::default());`.
+#[macro_export]
+macro_rules! export_plugin {
+    ($plugin:expr) => {
+        #[no_mangle]
+        pub static FERRUMC_PLUGIN: $crate::plugins::PluginDeclaration =
+            $crate::plugins::PluginDeclaration {
+                abi_version: $crate::plugins::PLUGIN_ABI_VERSION,
+                rustc_version: $crate::plugins::RawStr::new($crate::plugins::RUSTC_VERSION),
+                server_version: $crate::plugins::RawStr::new($crate::plugins::SERVER_VERSION),
+                create: {
+                    extern "C" fn create() -> *mut Box<dyn $crate::plugins::FerrumcPlugin> {
+                        Box::into_raw(Box::new(Box::new($plugin)))
+                    }
+                    create
+                },
+            };
+    };
+}
+
+/// Collects what a plugin adds to the server while it's being loaded.
+pub struct PluginRegistrar {
+    state: GlobalState,
+    commands: Vec<Command>,
+    event_handlers: Vec<(u8, Box<dyn EventHandlerWrapper>)>,
+    systems: Vec<Box<dyn TickedSystem>>,
+}
+
+impl PluginRegistrar {
+    fn new(state: GlobalState) -> Self {
+        Self {
+            state,
+            commands: Vec::new(),
+            event_handlers: Vec::new(),
+            systems: Vec::new(),
+        }
+    }
+
+    pub fn state(&self) -> &GlobalState {
+        &self.state
+    }
+
+    /// Adds a command. Plugins can't replace commands that are already registered.
+    pub fn command(&mut self, command: Command) {
+        self.commands.push(command);
+    }
+
+    /// Adds a handler for events of type `E`. See
+    /// [EventPriority](crate::events::creation::registry::EventPriority) for the priority.
+    pub fn event_handler<E, F, Fut>(&mut self, priority: u8, handler: F)
+    where
+        E: Event,
+        F: Fn(Arc<E>, GlobalState) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let handler = ClosureEventHandler::new(handler);
+        self.event_handlers.push((priority, Box::new(handler)));
+    }
+
+    /// Adds a system that's run every tick, after the built-in ones.
+    pub fn ticked_system(&mut self, system: impl TickedSystem + 'static) {
+        self.systems.push(Box::new(system));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TestPlugin;
+
+    impl FerrumcPlugin for TestPlugin {
+        fn name(&self) -> &str {
+            "test"
+        }
+
+        fn version(&self) -> &str {
+            "1.0.0"
+        }
+
+        fn on_load(&self, _registrar: &mut PluginRegistrar) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    extern "C" fn create() -> *mut Box<dyn FerrumcPlugin> {
+        Box::into_raw(Box::new(Box::new(TestPlugin)))
+    }
+
+    #[test]
+    fn test_check_declaration() {
+        let mut declaration = PluginDeclaration {
+            abi_version: PLUGIN_ABI_VERSION,
+            rustc_version: RawStr::new(RUSTC_VERSION),
+            server_version: RawStr::new(SERVER_VERSION),
+            create,
+        };
+        assert!(declaration.check().is_ok());
+        assert_eq!(declaration.plugin().unwrap().name(), "test");
+
+        declaration.rustc_version = RawStr::new("rustc 1.0.0");
+        assert!(declaration.check().is_err());
+        assert!(declaration.plugin().is_err());
+
+        declaration.rustc_version = RawStr::new(RUSTC_VERSION);
+        declaration.abi_version += 1;
+        assert!(declaration.check().is_err());
+    }
+}
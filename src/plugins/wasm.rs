@@ -0,0 +1,712 @@
+//! WebAssembly plugins, for plugins that aren't trusted to run native code.
+//!
+//! They run in wasmtime, with nothing from outside their sandbox but the functions below, each of
+//! which needs a [Capability] the config allows. Every call into a plugin gets the same amount of
+//! fuel, and it can only have so much memory, so a broken plugin can't hold up the server either.
+//! What a plugin asks for during a call, like messages and block changes, is done once it returns.
+//!
+//! A plugin exports its `memory`, and `ferrumc_alloc(len) -> ptr`, which the server uses to hand it
+//! strings. They're the plugin's to free afterwards. It can also export:
+//! - `ferrumc_load()`, called when it's loaded. Commands and events can only be registered here.
+//! - `ferrumc_unload()`, called before it's unloaded.
+//! - `ferrumc_command(sender, name_ptr, name_len, args_ptr, args_len)`, when a player runs one of
+//!   its commands.
+//! - `ferrumc_event(event, entity, data_ptr, data_len) -> cancel`, for the [events](WasmEvent)
+//!   it's subscribed to. Returning 1 cancels the ones that can be cancelled.
+//!
+//! It can import these from the `ferrumc` module, which take strings as a pointer and a length:
+//! - `log(message)`
+//! - `register_command(name)`, with the `commands` capability
+//! - `subscribe(event)`, with `events`
+//! - `send_message(entity, message)` and `broadcast(message)`, with `messaging`
+//! - `set_block(dimension, x, y, z, block) -> ok`, with `world_edits`. The block is written like in
+//!   commands, e.g. `oak_stairs[facing=east]`. Returns 0 if the block or dimension doesn't exist.
+//!
+//! A plugin is named after its file.
+
+use std::path::Path;
+use std::sync::{Arc, OnceLock};
+
+use anyhow::{anyhow, bail};
+use parking_lot::Mutex;
+use tracing::{info, warn};
+use wasmtime::{
+    Caller, Config, Engine, Extern, Instance, Linker, Module, Store, StoreLimits,
+    StoreLimitsBuilder, TypedFunc, WasmParams, WasmResults,
+};
+
+use crate::commands::arguments::{Argument, ArgumentParser};
+use crate::commands::{Command, CommandContext};
+use crate::events::chat_events::ChatMessageEvent;
+use crate::events::creation::event::{Cancellation, Event};
+use crate::events::world_events::{BlockBreakEvent, BlockPlaceEvent, PlayerJoinWorldEvent};
+use crate::net::packets::outgoing::system_chat_message::SystemChatMessage;
+use crate::net::utils::broadcast::broadcast_packet;
+use crate::plugins::{FerrumcPlugin, PluginRegistrar};
+use crate::state::GlobalState;
+use crate::utils::config::Plugins;
+use crate::utils::constants::limits::MAX_STRING_LENGTH;
+use crate::utils::prelude::*;
+use crate::world::blocks::set_block;
+use crate::world::chunk_format::Palette;
+use crate::world::conversions::parse_block_state;
+use crate::world::dimension::Dimension;
+
+/// Plugins' event handlers run before the built-in ones that act on the event, so they can cancel
+/// it.
+const EVENT_PRIORITY: u8 = 128;
+
+/// What a WebAssembly plugin can be allowed to do, set with `wasm_capabilities` in the config.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Capability {
+    Events,
+    Commands,
+    WorldEdits,
+    Messaging,
+}
+
+impl Capability {
+    pub const ALL: [Capability; 4] = [
+        Capability::Events,
+        Capability::Commands,
+        Capability::WorldEdits,
+        Capability::Messaging,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Capability::Events => "events",
+            Capability::Commands => "commands",
+            Capability::WorldEdits => "world_edits",
+            Capability::Messaging => "messaging",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|capability| capability.name() == name)
+    }
+}
+
+/// The events a WebAssembly plugin can subscribe to, by id.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WasmEvent {
+    /// Without any data.
+    PlayerJoin = 0,
+    /// The message. Can be cancelled.
+    ChatMessage = 1,
+    /// `x y z block`. Can be cancelled.
+    BlockBreak = 2,
+    /// `x y z block`. Can be cancelled.
+    BlockPlace = 3,
+}
+
+impl WasmEvent {
+    pub fn from_id(id: i32) -> Option<Self> {
+        match id {
+            0 => Some(WasmEvent::PlayerJoin),
+            1 => Some(WasmEvent::ChatMessage),
+            2 => Some(WasmEvent::BlockBreak),
+            3 => Some(WasmEvent::BlockPlace),
+            _ => None,
+        }
+    }
+}
+
+/// Something a plugin asked for during a call, which is done once the call's over.
+#[derive(Debug)]
+enum Action {
+    Message {
+        entity: usize,
+        message: String,
+    },
+    Broadcast(String),
+    SetBlock {
+        dimension: Dimension,
+        x: i32,
+        y: i32,
+        z: i32,
+        block: Palette,
+    },
+}
+
+/// What a plugin can get at from inside its sandbox.
+struct Host {
+    plugin: String,
+    capabilities: Vec<Capability>,
+    limits: StoreLimits,
+    /// Whether `ferrumc_load` is running, the only time commands and events can be registered.
+    loading: bool,
+    commands: Vec<String>,
+    events: Vec<WasmEvent>,
+    actions: Vec<Action>,
+}
+
+/// A WebAssembly plugin. Its commands and event handlers call into it through the [Runtime] they
+/// share with it.
+pub struct WasmPlugin {
+    name: String,
+    runtime: Arc<Runtime>,
+}
+
+impl WasmPlugin {
+    pub async fn load(path: &Path, config: &Plugins) -> Result<Self> {
+        let wasm = tokio::fs::read(path).await?;
+        let name = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or("plugin")
+            .to_string();
+        let runtime = Runtime::new(&name, &wasm, config)?;
+        Ok(Self {
+            name,
+            runtime: Arc::new(runtime),
+        })
+    }
+
+    fn command(&self, name: &str) -> Command {
+        let runtime = Arc::clone(&self.runtime);
+        let plugin = self.name.clone();
+        let command = name.to_string();
+        Command::new(name, move |ctx: CommandContext| {
+            let runtime = Arc::clone(&runtime);
+            let (plugin, command) = (plugin.clone(), command.clone());
+            async move {
+                let args = ctx.argument("args").unwrap_or_default();
+                let actions = runtime.command(ctx.sender, &command, args)?;
+                run_actions(&ctx.state, &plugin, actions).await;
+                Ok(())
+            }
+        })
+        .usage(vec![])
+        .usage(vec![Argument::new("args", ArgumentParser::GreedyString)])
+    }
+
+    fn forward<E: ForwardedEvent>(&self, registrar: &mut PluginRegistrar) {
+        let runtime = Arc::clone(&self.runtime);
+        let plugin = self.name.clone();
+        registrar.event_handler(EVENT_PRIORITY, move |event: Arc<E>, state| {
+            let runtime = Arc::clone(&runtime);
+            let plugin = plugin.clone();
+            async move {
+                let (cancel, actions) = match runtime.event(E::EVENT, event.entity(), &event.data())
+                {
+                    Ok(result) => result,
+                    Err(e) => {
+                        warn!("{} failed to handle {:?}: {}", plugin, E::EVENT, e);
+                        return;
+                    }
+                };
+                if let (true, Some(cancellation)) = (cancel, event.cancellation()) {
+                    cancellation.cancel();
+                }
+                run_actions(&state, &plugin, actions).await;
+            }
+        });
+    }
+}
+
+impl FerrumcPlugin for WasmPlugin {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn version(&self) -> &str {
+        "(wasm)"
+    }
+
+    fn on_load(&self, registrar: &mut PluginRegistrar) -> Result<()> {
+        let (commands, events, actions) = self.runtime.load()?;
+        spawn_actions(registrar.state(), &self.name, actions);
+
+        for name in commands {
+            registrar.command(self.command(&name));
+        }
+        for event in events {
+            match event {
+                WasmEvent::PlayerJoin => self.forward::<PlayerJoinWorldEvent>(registrar),
+                WasmEvent::ChatMessage => self.forward::<ChatMessageEvent>(registrar),
+                WasmEvent::BlockBreak => self.forward::<BlockBreakEvent>(registrar),
+                WasmEvent::BlockPlace => self.forward::<BlockPlaceEvent>(registrar),
+            }
+        }
+        Ok(())
+    }
+
+    fn on_unload(&self, state: &GlobalState) {
+        match self.runtime.unload() {
+            Ok(actions) => spawn_actions(state, &self.name, actions),
+            Err(e) => warn!("{} failed to unload: {}", self.name, e),
+        }
+    }
+}
+
+/// An event that's passed on to plugins as a [WasmEvent].
+trait ForwardedEvent: Event {
+    const EVENT: WasmEvent;
+
+    fn entity(&self) -> usize;
+
+    fn data(&self) -> String {
+        String::new()
+    }
+
+    fn cancellation(&self) -> Option<&Cancellation> {
+        None
+    }
+}
+
+impl ForwardedEvent for PlayerJoinWorldEvent {
+    const EVENT: WasmEvent = WasmEvent::PlayerJoin;
+
+    fn entity(&self) -> usize {
+        self.entity_id
+    }
+}
+
+impl ForwardedEvent for ChatMessageEvent {
+    const EVENT: WasmEvent = WasmEvent::ChatMessage;
+
+    fn entity(&self) -> usize {
+        self.entity_id
+    }
+
+    fn data(&self) -> String {
+        self.message.clone()
+    }
+
+    fn cancellation(&self) -> Option<&Cancellation> {
+        Some(&self.cancellation)
+    }
+}
+
+impl ForwardedEvent for BlockBreakEvent {
+    const EVENT: WasmEvent = WasmEvent::BlockBreak;
+
+    fn entity(&self) -> usize {
+        self.entity_id
+    }
+
+    fn data(&self) -> String {
+        let position = &self.position;
+        format!(
+            "{} {} {} {}",
+            position.x, position.y, position.z, self.block.name
+        )
+    }
+
+    fn cancellation(&self) -> Option<&Cancellation> {
+        Some(&self.cancellation)
+    }
+}
+
+impl ForwardedEvent for BlockPlaceEvent {
+    const EVENT: WasmEvent = WasmEvent::BlockPlace;
+
+    fn entity(&self) -> usize {
+        self.entity_id
+    }
+
+    fn data(&self) -> String {
+        let position = &self.position;
+        format!(
+            "{} {} {} {}",
+            position.x, position.y, position.z, self.block.name
+        )
+    }
+
+    fn cancellation(&self) -> Option<&Cancellation> {
+        Some(&self.cancellation)
+    }
+}
+
+/// A plugin's instance, which only one thing can call into at a time.
+struct Runtime {
+    store: Mutex<Store<Host>>,
+    instance: Instance,
+    fuel: u64,
+}
+
+impl Runtime {
+    fn new(name: &str, wasm: &[u8], config: &Plugins) -> Result<Self> {
+        let capabilities = config
+            .wasm_capabilities
+            .iter()
+            .map(|capability| {
+                Capability::from_name(capability)
+                    .ok_or_else(|| Error::Generic(format!("Unknown capability: {}", capability)))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let module = Module::new(engine(), wasm).map_err(wasm_error)?;
+
+        let host = Host {
+            plugin: name.to_string(),
+            capabilities,
+            limits: StoreLimitsBuilder::new()
+                .memory_size(config.wasm_memory * 1024 * 1024)
+                .build(),
+            loading: false,
+            commands: Vec::new(),
+            events: Vec::new(),
+            actions: Vec::new(),
+        };
+        let mut store = Store::new(engine(), host);
+        store.limiter(|host| &mut host.limits);
+        // For the start function, if there is one
+        store.set_fuel(config.wasm_fuel).map_err(wasm_error)?;
+        let instance = linker()
+            .and_then(|linker| linker.instantiate(&mut store, &module))
+            .map_err(wasm_error)?;
+
+        Ok(Self {
+            store: Mutex::new(store),
+            instance,
+            fuel: config.wasm_fuel,
+        })
+    }
+
+    /// Calls into the plugin with its fuel topped up, returning the result and what it asked for.
+    /// If the call fails, nothing it asked for is done.
+    fn call<R>(
+        &self,
+        call: impl FnOnce(&mut Store<Host>, Instance) -> anyhow::Result<R>,
+    ) -> Result<(R, Vec<Action>)> {
+        let mut store = self.store.lock();
+        store.set_fuel(self.fuel).map_err(wasm_error)?;
+        let result = call(&mut store, self.instance);
+        let actions = std::mem::take(&mut store.data_mut().actions);
+        Ok((result.map_err(wasm_error)?, actions))
+    }
+
+    /// Returns the commands and events the plugin registered.
+    fn load(&self) -> Result<(Vec<String>, Vec<WasmEvent>, Vec<Action>)> {
+        let ((), actions) = self.call(|store, instance| {
+            let Some(load) = export::<(), ()>(store, instance, "ferrumc_load")? else {
+                return Ok(());
+            };
+            store.data_mut().loading = true;
+            let result = load.call(&mut *store, ());
+            store.data_mut().loading = false;
+            result
+        })?;
+
+        let mut store = self.store.lock();
+        let host = store.data_mut();
+        let commands = std::mem::take(&mut host.commands);
+        let events = std::mem::take(&mut host.events);
+        Ok((commands, events, actions))
+    }
+
+    fn unload(&self) -> Result<Vec<Action>> {
+        let ((), actions) = self.call(|store, instance| {
+            match export::<(), ()>(store, instance, "ferrumc_unload")? {
+                Some(unload) => unload.call(&mut *store, ()),
+                None => Ok(()),
+            }
+        })?;
+        Ok(actions)
+    }
+
+    fn command(&self, sender: usize, name: &str, args: &str) -> Result<Vec<Action>> {
+        let ((), actions) = self.call(|store, instance| {
+            type Params = (i32, i32, i32, i32, i32);
+            let Some(command) = export::<Params, ()>(store, instance, "ferrumc_command")? else {
+                return Ok(());
+            };
+            let (name_ptr, name_len) = write_string(store, instance, name)?;
+            let (args_ptr, args_len) = write_string(store, instance, args)?;
+            let params = (sender as i32, name_ptr, name_len, args_ptr, args_len);
+            command.call(&mut *store, params)
+        })?;
+        Ok(actions)
+    }
+
+    /// Returns whether the plugin wants the event cancelled.
+    fn event(&self, event: WasmEvent, entity: usize, data: &str) -> Result<(bool, Vec<Action>)> {
+        self.call(|store, instance| {
+            type Params = (i32, i32, i32, i32);
+            let Some(handle) = export::<Params, i32>(store, instance, "ferrumc_event")? else {
+                return Ok(false);
+            };
+            let (data_ptr, data_len) = write_string(store, instance, data)?;
+            let cancel = handle.call(
+                &mut *store,
+                (event as i32, entity as i32, data_ptr, data_len),
+            )?;
+            Ok(cancel == 1)
+        })
+    }
+}
+
+fn engine() -> &'static Engine {
+    static ENGINE: OnceLock<Engine> = OnceLock::new();
+    ENGINE.get_or_init(|| {
+        let mut config = Config::new();
+        config.consume_fuel(true);
+        Engine::new(&config).expect("Failed to set up the WebAssembly engine")
+    })
+}
+
+/// The functions plugins can import.
+fn linker() -> anyhow::Result<Linker<Host>> {
+    let mut linker = Linker::new(engine());
+    linker.func_wrap(
+        "ferrumc",
+        "log",
+        |mut caller: Caller<'_, Host>, ptr: i32, len: i32| -> anyhow::Result<()> {
+            let message = read_string(&mut caller, ptr, len)?;
+            info!("[{}] {}", caller.data().plugin, message);
+            Ok(())
+        },
+    )?;
+    linker.func_wrap(
+        "ferrumc",
+        "register_command",
+        |mut caller: Caller<'_, Host>, ptr: i32, len: i32| -> anyhow::Result<()> {
+            require(&caller, Capability::Commands)?;
+            let name = read_string(&mut caller, ptr, len)?;
+            let host = caller.data_mut();
+            if !host.loading {
+                bail!("Commands can only be registered while the plugin's loading");
+            }
+            host.commands.push(name);
+            Ok(())
+        },
+    )?;
+    linker.func_wrap(
+        "ferrumc",
+        "subscribe",
+        |mut caller: Caller<'_, Host>, event: i32| -> anyhow::Result<()> {
+            require(&caller, Capability::Events)?;
+            let event =
+                WasmEvent::from_id(event).ok_or_else(|| anyhow!("Unknown event {}", event))?;
+            let host = caller.data_mut();
+            if !host.loading {
+                bail!("Events can only be subscribed to while the plugin's loading");
+            }
+            host.events.push(event);
+            Ok(())
+        },
+    )?;
+    linker.func_wrap(
+        "ferrumc",
+        "send_message",
+        |mut caller: Caller<'_, Host>, entity: i32, ptr: i32, len: i32| -> anyhow::Result<()> {
+            require(&caller, Capability::Messaging)?;
+            let message = read_string(&mut caller, ptr, len)?;
+            let entity = usize::try_from(entity)?;
+            caller
+                .data_mut()
+                .actions
+                .push(Action::Message { entity, message });
+            Ok(())
+        },
+    )?;
+    linker.func_wrap(
+        "ferrumc",
+        "broadcast",
+        |mut caller: Caller<'_, Host>, ptr: i32, len: i32| -> anyhow::Result<()> {
+            require(&caller, Capability::Messaging)?;
+            let message = read_string(&mut caller, ptr, len)?;
+            caller.data_mut().actions.push(Action::Broadcast(message));
+            Ok(())
+        },
+    )?;
+    linker.func_wrap(
+        "ferrumc",
+        "set_block",
+        |mut caller: Caller<'_, Host>,
+         dimension_ptr: i32,
+         dimension_len: i32,
+         x: i32,
+         y: i32,
+         z: i32,
+         block_ptr: i32,
+         block_len: i32|
+         -> anyhow::Result<i32> {
+            require(&caller, Capability::WorldEdits)?;
+            let dimension = read_string(&mut caller, dimension_ptr, dimension_len)?;
+            let block = read_string(&mut caller, block_ptr, block_len)?;
+            let (Some(dimension), Some(block)) =
+                (Dimension::from_name(&dimension), parse_block_state(&block))
+            else {
+                return Ok(0);
+            };
+            let action = Action::SetBlock {
+                dimension,
+                x,
+                y,
+                z,
+                block,
+            };
+            caller.data_mut().actions.push(action);
+            Ok(1)
+        },
+    )?;
+    Ok(linker)
+}
+
+fn require(caller: &Caller<'_, Host>, capability: Capability) -> anyhow::Result<()> {
+    let host = caller.data();
+    if !host.capabilities.contains(&capability) {
+        bail!(
+            "{} doesn't have the {} capability",
+            host.plugin,
+            capability.name()
+        );
+    }
+    Ok(())
+}
+
+/// An export the plugin doesn't have to have.
+fn export<P: WasmParams, R: WasmResults>(
+    store: &mut Store<Host>,
+    instance: Instance,
+    name: &str,
+) -> anyhow::Result<Option<TypedFunc<P, R>>> {
+    match instance.get_func(&mut *store, name) {
+        Some(func) => Ok(Some(func.typed(&*store)?)),
+        None => Ok(None),
+    }
+}
+
+fn read_string(caller: &mut Caller<'_, Host>, ptr: i32, len: i32) -> anyhow::Result<String> {
+    let Some(Extern::Memory(memory)) = caller.get_export("memory") else {
+        bail!("The plugin doesn't export its memory");
+    };
+    let len = usize::try_from(len)?;
+    // The same limit as strings sent over the network
+    if len > MAX_STRING_LENGTH * 3 {
+        bail!("A string is {} bytes long, over the limit", len);
+    }
+    let mut buffer = vec![0; len];
+    memory.read(&*caller, ptr as u32 as usize, &mut buffer)?;
+    Ok(String::from_utf8(buffer)?)
+}
+
+/// Copies a string into memory the plugin allocated for it, returning where it is.
+fn write_string(
+    store: &mut Store<Host>,
+    instance: Instance,
+    value: &str,
+) -> anyhow::Result<(i32, i32)> {
+    let alloc = instance.get_typed_func::<i32, i32>(&mut *store, "ferrumc_alloc")?;
+    let memory = instance
+        .get_memory(&mut *store, "memory")
+        .ok_or_else(|| anyhow!("The plugin doesn't export its memory"))?;
+    let len = i32::try_from(value.len())?;
+    let ptr = alloc.call(&mut *store, len)?;
+    memory.write(&mut *store, ptr as u32 as usize, value.as_bytes())?;
+    Ok((ptr, len))
+}
+
+fn wasm_error(e: anyhow::Error) -> Error {
+    Error::Generic(format!("WebAssembly plugin error: {:#}", e))
+}
+
+async fn run_actions(state: &GlobalState, plugin: &str, actions: Vec<Action>) {
+    for action in actions {
+        if let Err(e) = run_action(state, action).await {
+            warn!("Failed to do what {} asked: {}", plugin, e);
+        }
+    }
+}
+
+async fn run_action(state: &GlobalState, action: Action) -> Result<()> {
+    match action {
+        Action::Message { entity, message } => {
+            let conn = state.connections.get_connection(entity)?;
+            let conn = conn.read().await;
+            conn.send_packet(SystemChatMessage::text(&message)).await
+        }
+        Action::Broadcast(message) => {
+            broadcast_packet(SystemChatMessage::text(&message), state).await
+        }
+        Action::SetBlock {
+            dimension,
+            x,
+            y,
+            z,
+            block,
+        } => set_block(state.clone(), x, y, z, block, dimension.name())
+            .await
+            .map(|_| ()),
+    }
+}
+
+/// For actions asked for outside of a command or event, which can't wait for them.
+fn spawn_actions(state: &GlobalState, plugin: &str, actions: Vec<Action>) {
+    if actions.is_empty() {
+        return;
+    }
+    let (state, plugin) = (state.clone(), plugin.to_string());
+    tokio::spawn(async move { run_actions(&state, &plugin, actions).await });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLUGIN: &str = r#"
+        (module
+            (import "ferrumc" "register_command" (func $register_command (param i32 i32)))
+            (import "ferrumc" "subscribe" (func $subscribe (param i32)))
+            (import "ferrumc" "broadcast" (func $broadcast (param i32 i32)))
+            (memory (export "memory") 1)
+            (data (i32.const 0) "hello")
+            (global $next (mut i32) (i32.const 1024))
+            (func (export "ferrumc_alloc") (param $len i32) (result i32)
+                (local $ptr i32)
+                (local.set $ptr (global.get $next))
+                (global.set $next (i32.add (global.get $next) (local.get $len)))
+                (local.get $ptr))
+            (func (export "ferrumc_load")
+                (call $register_command (i32.const 0) (i32.const 5))
+                (call $subscribe (i32.const 1)))
+            (func (export "ferrumc_event") (param i32 i32 i32 i32) (result i32)
+                ;; Says the message back to everyone, and cancels it
+                (call $broadcast (local.get 2) (local.get 3))
+                (i32.const 1))
+            (func (export "ferrumc_command") (param i32 i32 i32 i32 i32)
+                (loop $forever (br $forever))))
+    "#;
+
+    fn config(capabilities: &[Capability]) -> Plugins {
+        Plugins {
+            native: false,
+            wasm: true,
+            wasm_capabilities: capabilities.iter().map(|c| c.name().to_string()).collect(),
+            wasm_fuel: 100_000,
+            wasm_memory: 1,
+        }
+    }
+
+    #[test]
+    fn test_load_and_events() {
+        let runtime = Runtime::new("test", PLUGIN.as_bytes(), &config(&Capability::ALL)).unwrap();
+        let (commands, events, actions) = runtime.load().unwrap();
+        assert_eq!(commands, ["hello"]);
+        assert_eq!(events, [WasmEvent::ChatMessage]);
+        assert!(actions.is_empty());
+
+        let (cancel, actions) = runtime.event(WasmEvent::ChatMessage, 1, "hi").unwrap();
+        assert!(cancel);
+        assert!(matches!(&actions[..], [Action::Broadcast(message)] if message == "hi"));
+    }
+
+    #[test]
+    fn test_capabilities() {
+        let runtime = Runtime::new("test", PLUGIN.as_bytes(), &config(&[Capability::Events]));
+        // Registering its command traps
+        assert!(runtime.unwrap().load().is_err());
+    }
+
+    #[test]
+    fn test_runs_out_of_fuel() {
+        let runtime = Runtime::new("test", PLUGIN.as_bytes(), &config(&Capability::ALL)).unwrap();
+        assert!(runtime.command(1, "hello", "").is_err());
+        // It gets more fuel for the next call
+        assert!(runtime.event(WasmEvent::ChatMessage, 1, "hi").is_ok());
+    }
+}
@@ -0,0 +1,343 @@
+//! Rhai scripts, for automating things on the server without writing a [plugin](crate::plugins).
+//!
+//! Scripts are run in game with `/script`, and every `.rhai` file in the `scripts` directory is run
+//! when the server starts. They can get at players, the world and chat with these functions:
+//! - `players()`, every online player's entity id
+//! - `find_player(name)`, a player's entity id, or `()` if they aren't online
+//! - `name(player)`, `health(player)`, `dimension(player)` and `position(player)`, which is a map
+//!   of `x`, `y` and `z`
+//! - `teleport(player, x, y, z)`
+//! - `get_block(x, y, z)` and `set_block(x, y, z, block)`, with blocks written like in commands,
+//!   e.g. `oak_stairs[facing=east]`. They're in the overworld, unless they're given a dimension
+//!   first, e.g. `get_block("the_nether", x, y, z)`.
+//! - `tell(player, message)` and `broadcast(message)`
+//!
+//! `sender` is the entity id of the player who ran the script, or -1 if nobody did. What a script
+//! prints, and what it returns, goes back to whoever ran it, or to the log.
+//!
+//! A script runs on a thread of its own, and waits for the server to do each thing it asks for. It
+//! can only run so many operations, so one that's stuck in a loop is stopped.
+
+use std::future::Future;
+use std::path::Path;
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+use rhai::{Array, Dynamic, Engine, EvalAltResult, Map, Scope, FLOAT, INT};
+use tokio::runtime::Handle;
+use tracing::{error, info};
+
+use crate::net::packets::outgoing::system_chat_message::SystemChatMessage;
+use crate::net::utils::broadcast::broadcast_packet;
+use crate::net::utils::movement::teleport;
+use crate::state::GlobalState;
+use crate::utils::components::dimension::dimension_of;
+use crate::utils::components::health::Health;
+use crate::utils::components::player::Player;
+use crate::utils::config::get_global_config;
+use crate::utils::constants::SCRIPTS_DIRECTORY;
+use crate::utils::encoding::position::Position;
+use crate::utils::prelude::*;
+use crate::world::blocks::{get_block, set_block};
+use crate::world::conversions::parse_block_state;
+use crate::world::dimension::Dimension;
+
+type ScriptResult<T> = std::result::Result<T, Box<EvalAltResult>>;
+
+/// Runs a script, returning what it printed, followed by what it returned if it isn't `()`.
+/// `sender` is the player who ran it, if anyone did.
+pub async fn run(
+    state: &GlobalState,
+    source: String,
+    sender: Option<usize>,
+) -> Result<Vec<String>> {
+    if !get_global_config().scripting.enabled {
+        return Err(Error::Generic("Scripting is disabled".to_string()));
+    }
+
+    let api = Api {
+        state: state.clone(),
+        handle: Handle::current(),
+    };
+    tokio::task::spawn_blocking(move || {
+        let output = Arc::new(Mutex::new(Vec::new()));
+        let max_operations = get_global_config().scripting.max_operations;
+        let mut engine = engine(max_operations, Arc::clone(&output));
+        api.register(&mut engine);
+
+        let mut scope = Scope::new();
+        scope.push_constant("sender", sender.map_or(-1, |sender| sender as INT));
+        let result = engine
+            .eval_with_scope::<Dynamic>(&mut scope, &source)
+            .map_err(|e| Error::Generic(format!("Script error: {}", e)))?;
+
+        let mut output = std::mem::take(&mut *output.lock());
+        if !result.is_unit() {
+            output.push(result.to_string());
+        }
+        Ok(output)
+    })
+    .await?
+}
+
+/// Runs a script from the scripts directory. `.rhai` is added to its name if it doesn't have an
+/// extension.
+pub async fn run_file(
+    state: &GlobalState,
+    name: &str,
+    sender: Option<usize>,
+) -> Result<Vec<String>> {
+    let mut path = Path::new(SCRIPTS_DIRECTORY).join(name);
+    if path.extension().is_none() {
+        path.set_extension("rhai");
+    }
+    let source = tokio::fs::read_to_string(&path)
+        .await
+        .map_err(|e| Error::Generic(format!("Failed to read {}: {}", path.display(), e)))?;
+    run(state, source, sender).await
+}
+
+/// Runs every script in the scripts directory, in order of their names, returning how many were
+/// run. What they print is logged, and one that fails doesn't stop the rest.
+pub async fn run_all(state: &GlobalState) -> Result<usize> {
+    let directory = Path::new(SCRIPTS_DIRECTORY);
+    if !get_global_config().scripting.enabled || !tokio::fs::try_exists(directory).await? {
+        return Ok(0);
+    }
+
+    let mut names = Vec::new();
+    let mut entries = tokio::fs::read_dir(directory).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        if path.extension().and_then(|extension| extension.to_str()) != Some("rhai") {
+            continue;
+        }
+        if let Some(name) = path.file_name().and_then(|name| name.to_str()) {
+            names.push(name.to_string());
+        }
+    }
+    names.sort();
+
+    let mut run = 0;
+    for name in names {
+        match run_file(state, &name, None).await {
+            Ok(output) => {
+                for line in output {
+                    info!("[{}] {}", name, line);
+                }
+                run += 1;
+            }
+            Err(e) => error!("Failed to run script {}: {}", name, e),
+        }
+    }
+    Ok(run)
+}
+
+/// An engine without any of the server's functions, which collects what's printed in `output`.
+fn engine(max_operations: u64, output: Arc<Mutex<Vec<String>>>) -> Engine {
+    let mut engine = Engine::new();
+    engine.set_max_operations(max_operations);
+    engine.on_print(move |text| output.lock().push(text.to_string()));
+    engine
+}
+
+/// The server's functions, which run on the runtime a script's thread was started from.
+#[derive(Clone)]
+struct Api {
+    state: GlobalState,
+    handle: Handle,
+}
+
+impl Api {
+    fn register(&self, engine: &mut Engine) {
+        let api = self.clone();
+        engine.register_fn("players", move || api.players());
+        let api = self.clone();
+        engine.register_fn("find_player", move |name: &str| api.find_player(name));
+        let api = self.clone();
+        engine.register_fn("name", move |player: INT| api.name(player));
+        let api = self.clone();
+        engine.register_fn("health", move |player: INT| api.health(player));
+        let api = self.clone();
+        engine.register_fn("dimension", move |player: INT| api.dimension(player));
+        let api = self.clone();
+        engine.register_fn("position", move |player: INT| api.position(player));
+        let api = self.clone();
+        engine.register_fn("teleport", move |player: INT, x: INT, y: INT, z: INT| {
+            api.teleport(player, x, y, z)
+        });
+        let api = self.clone();
+        engine.register_fn("get_block", move |x: INT, y: INT, z: INT| {
+            api.get_block("overworld", x, y, z)
+        });
+        let api = self.clone();
+        engine.register_fn(
+            "get_block",
+            move |dimension: &str, x: INT, y: INT, z: INT| api.get_block(dimension, x, y, z),
+        );
+        let api = self.clone();
+        engine.register_fn("set_block", move |x: INT, y: INT, z: INT, block: &str| {
+            api.set_block("overworld", x, y, z, block)
+        });
+        let api = self.clone();
+        engine.register_fn(
+            "set_block",
+            move |dimension: &str, x: INT, y: INT, z: INT, block: &str| {
+                api.set_block(dimension, x, y, z, block)
+            },
+        );
+        let api = self.clone();
+        engine.register_fn("tell", move |player: INT, message: &str| {
+            api.tell(player, message)
+        });
+        let api = self.clone();
+        engine.register_fn("broadcast", move |message: &str| api.broadcast(message));
+    }
+
+    /// Waits for the server to do something. Only works on a script's thread, which isn't one of
+    /// the runtime's own.
+    fn block_on<F: Future>(&self, future: F) -> F::Output {
+        self.handle.block_on(future)
+    }
+
+    fn players(&self) -> Array {
+        self.block_on(async {
+            let query = self.state.world.query::<&Player>();
+            let players = query
+                .iter()
+                .await
+                .map(|(id, _)| Dynamic::from(id as INT))
+                .collect();
+            players
+        })
+    }
+
+    fn find_player(&self, name: &str) -> Dynamic {
+        self.block_on(async {
+            let query = self.state.world.query::<&Player>();
+            let found = query
+                .iter()
+                .await
+                .find(|(_, player)| player.username.eq_ignore_ascii_case(name))
+                .map_or(Dynamic::UNIT, |(id, _)| Dynamic::from(id as INT));
+            found
+        })
+    }
+
+    fn name(&self, player: INT) -> ScriptResult<String> {
+        let player = entity(player)?;
+        self.block_on(async {
+            let player = self.state.world.get_component::<Player>(player).await;
+            Ok(player.map_err(script_error)?.username.clone())
+        })
+    }
+
+    fn health(&self, player: INT) -> ScriptResult<FLOAT> {
+        let player = entity(player)?;
+        self.block_on(async {
+            let health = self.state.world.get_component::<Health>(player).await;
+            Ok(health.map_err(script_error)?.health as FLOAT)
+        })
+    }
+
+    fn dimension(&self, player: INT) -> ScriptResult<String> {
+        let player = entity(player)?;
+        let dimension = self.block_on(dimension_of(&self.state, player));
+        Ok(dimension.name().to_string())
+    }
+
+    fn position(&self, player: INT) -> ScriptResult<Map> {
+        let player = entity(player)?;
+        let position = self.block_on(async {
+            let position = self.state.world.get_component::<Position>(player).await;
+            position.map(|position| position.clone())
+        });
+        let position = position.map_err(script_error)?;
+
+        let mut map = Map::new();
+        map.insert("x".into(), (position.x as INT).into());
+        map.insert("y".into(), (position.y as INT).into());
+        map.insert("z".into(), (position.z as INT).into());
+        Ok(map)
+    }
+
+    fn teleport(&self, player: INT, x: INT, y: INT, z: INT) -> ScriptResult<()> {
+        let player = entity(player)?;
+        let position = Position::new(coordinate(x)?, coordinate(y)?, coordinate(z)?);
+        self.block_on(teleport(player, self.state.clone(), position))
+            .map_err(script_error)
+    }
+
+    fn get_block(&self, dimension: &str, x: INT, y: INT, z: INT) -> ScriptResult<String> {
+        let dimension = parse_dimension(dimension)?;
+        let (x, y, z) = (coordinate(x)?, coordinate(y)?, coordinate(z)?);
+        let block = self.block_on(get_block(&self.state, x, y, z, dimension.name()));
+        Ok(block.map_err(script_error)?.to_string())
+    }
+
+    fn set_block(&self, dimension: &str, x: INT, y: INT, z: INT, block: &str) -> ScriptResult<()> {
+        let dimension = parse_dimension(dimension)?;
+        let (x, y, z) = (coordinate(x)?, coordinate(y)?, coordinate(z)?);
+        let block = parse_block_state(block).ok_or_else(|| format!("Unknown block: {}", block))?;
+        self.block_on(set_block(
+            self.state.clone(),
+            x,
+            y,
+            z,
+            block,
+            dimension.name(),
+        ))
+        .map(|_| ())
+        .map_err(script_error)
+    }
+
+    fn tell(&self, player: INT, message: &str) -> ScriptResult<()> {
+        let player = entity(player)?;
+        self.block_on(async {
+            let conn = self.state.connections.get_connection(player)?;
+            let conn = conn.read().await;
+            conn.send_packet(SystemChatMessage::text(message)).await
+        })
+        .map_err(script_error)
+    }
+
+    fn broadcast(&self, message: &str) -> ScriptResult<()> {
+        self.block_on(broadcast_packet(
+            SystemChatMessage::text(message),
+            &self.state,
+        ))
+        .map_err(script_error)
+    }
+}
+
+fn entity(id: INT) -> ScriptResult<usize> {
+    usize::try_from(id).map_err(|_| format!("Invalid entity id: {}", id).into())
+}
+
+fn coordinate<T: TryFrom<INT>>(value: INT) -> ScriptResult<T> {
+    T::try_from(value).map_err(|_| format!("Coordinate out of range: {}", value).into())
+}
+
+fn parse_dimension(name: &str) -> ScriptResult<Dimension> {
+    Dimension::from_name(name).ok_or_else(|| format!("Unknown dimension: {}", name).into())
+}
+
+fn script_error(e: impl std::fmt::Display) -> Box<EvalAltResult> {
+    e.to_string().into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_output_and_limits() {
+        let output = Arc::new(Mutex::new(Vec::new()));
+        let engine = engine(1000, Arc::clone(&output));
+        engine.run(r#"print("hello"); print(1 + 2);"#).unwrap();
+        assert_eq!(*output.lock(), ["hello", "3"]);
+
+        // Stopped once it's run out of operations
+        assert!(engine.run("loop {}").is_err());
+    }
+}
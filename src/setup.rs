@@ -63,6 +63,7 @@ pub(crate) async fn setup() -> Result<(), Error> {
         "Unfortunately plugins are not yet available",
     )
     .await?;
+    fs::create_dir(dir.join("scripts")).await?;
 
     info!("Files setup successfully!");
     Ok(())
@@ -75,21 +76,147 @@ pub static BASE_CONFIG: &str = r#"
 host = "0.0.0.0"
 # The port to bind to. Default is 25565.
 port = 25565
-# The message displayed in the server list.
+# The message displayed in the server list. One is picked at random for each ping.
+# Can be plain text or a JSON text component, e.g. '{"text": "A FerrumC server", "color": "gold"}'.
 motd = ["A supersonic FerrumC server."]
 # The maximum number of players that can be connected at once.
 max_players = 20
+# Shown to everyone still online when the server stops. Can be plain text or a JSON text component.
+shutdown_message = "Server closed"
 # How many network updates to process per second per user. 0 means no limit.
 # This is the number of times per second the server will send updates to the client.
 # Having this too low will cause noticable lag for clients but may improve server performance.
 network_tick_rate = 0
+# Packets at least this many bytes long will be compressed with zlib. -1 disables compression.
+# Lower values save bandwidth at the cost of more CPU time.
+network_compression_threshold = 256
+# Whether to verify players with Mojang's session servers. Only players with a paid account can join if enabled.
+# Leave this off if the server sits behind a proxy that authenticates players itself.
+online_mode = false
+# Answer GameSpy4 Query requests over UDP, which server lists use to get the player list and map name.
+enable_query = false
+# The UDP port to answer queries on. It can be the same number as the server's port.
+query_port = 25565
+# Serve metrics for Prometheus over HTTP at /metrics: TPS, tick times, players, chunks, packets and
+# database latency.
+enable_metrics = false
+# The TCP port to serve metrics on.
+metrics_port = 9225
+# Only let players on the whitelist (whitelist.json) join. It can be managed in game with /whitelist.
+whitelist = false
+# The permission level /op gives players, from 1 to 4. See ops.json for everyone's level.
+op_permission_level = 4
+# How many chunks around each player to send, in every direction. Players with a lower
+# render distance set on their client will be sent less.
+view_distance = 10
+# How chat messages are shown to everyone. {username} and {message} are replaced with the sender's name and their message.
+chat_format = "<{username}> {message}"
 # The default world name. You can switch between mutliple worlds by changing this value.
 world = "world"
+# Where to import a vanilla world from with --import. Leave empty to use the "import" folder next to the server.
+# The --import-path=<dir> flag takes priority over this.
+import_path = ""
 
 [database]
-# The cache size in KB. We recommend leaving this at the default value.
-cache_size = 1024
+# Where chunks are stored: "lmdb" for a single database file, "region" for one file per 32x32
+# chunks, or "memory" to not save any chunks to disk at all.
+backend = "lmdb"
+# How much memory recently used chunks can take up in the cache, in KB. Chunks that aren't in the
+# cache have to be read from disk and decompressed.
+cache_size = 65536
 # The compression algorithm to use. "fast" is recommended for most use cases.
 # "best" is slower but may provide better compression ratio.
 compression = "fast"
+# Changed chunks are kept in memory and written to disk in batches this often, in seconds. Anything
+# changed since the last write is lost if the server crashes.
+flush_interval = 5
+# Players' position, game mode and inventory are saved when they leave, and this often while
+# they're online, in seconds.
+player_save_interval = 60
+
+[generation]
+# How to make chunks that aren't in the database: "multi_noise" for plains, forests, deserts and
+# oceans, "noise" for hills and water, "flat" for a superflat world, or "none" to only ever use
+# imported chunks.
+generator = "multi_noise"
+# Changing the seed only affects chunks that haven't been generated yet.
+seed = 0
+
+[proxy]
+# How a proxy in front of the server passes on players' real addresses, UUIDs and skins:
+# "bungeecord" for BungeeCord's IP forwarding, "velocity" for Velocity's modern forwarding, or
+# "none" if players connect directly. Only turn this on if players can't reach the server except
+# through the proxy, anyone connecting directly could claim to be anyone.
+forwarding = "none"
+# The forwarding secret from Velocity's forwarding.secret file. Only used with "velocity".
+secret = ""
+# Read a PROXY protocol (v1 or v2) header at the start of every connection, which load balancers
+# like HAProxy send to pass on the client's real address. Connections without one are dropped, so
+# only turn this on if everything goes through the load balancer.
+proxy_protocol = false
+
+[throttle]
+# How long one address has to wait between logins, in milliseconds.
+login_interval = 4000
+# How many times one address can connect in a minute, server list pings included.
+max_connections_per_minute = 30
+# Connections sending more packets than this in a second are disconnected.
+max_packets_per_second = 500
+# Setting any of these to 0 turns that limit off. The per-address limits are left to the proxy
+# when [proxy] forwarding is on, since everyone connects from its address.
+
+[skins]
+# Give players in offline mode the skin of the Minecraft account with the same name, looked up
+# through Mojang's API. Online mode and proxies already pass on players' own skins.
+fetch = true
+# How long a looked up skin is kept before it's looked up again, in seconds.
+cache_duration = 3600
+
+[flight]
+# Let players fly in survival and adventure mode, e.g. with a mod. Otherwise players floating in
+# the air for more than a few seconds are kicked.
+allowed = false
+# How fast players fly in creative and spectator mode. Vanilla's is 0.05.
+speed = 0.05
+
+[mobs]
+# Spawn animals and monsters around players on their own. Monsters spawn in the dark, animals on
+# grass in the open. /summon works either way.
+natural_spawning = true
+# The most monsters and animals there can be around each player. Players close together share.
+monster_cap = 70
+creature_cap = 10
+
+[weather]
+# Strike lightning around players during thunderstorms. It doesn't hurt anyone or start fires.
+lightning = true
+
+[server_info]
+# Tell clients that chat is signed and checked, which hides the warning they show about chat
+# messages not being verified. FerrumC doesn't check signatures itself.
+enforce_secure_chat = false
+
+[plugins]
+# Load native plugins, the .so, .dll or .dylib files in the plugins directory. They can do anything
+# the server can, so only use ones you trust.
+native = true
+# Load WebAssembly plugins, the .wasm files in the plugins directory. They run in a sandbox, and can
+# only do what they're allowed to below. These settings only apply if the server was built with the
+# wasm feature.
+wasm = true
+# What WebAssembly plugins can do: "events" (handle and cancel events), "commands" (add commands),
+# "world_edits" (set blocks) and "messaging" (send players messages).
+wasm_capabilities = ["events", "commands", "world_edits", "messaging"]
+# Roughly how many instructions a WebAssembly plugin can run each time it's called, before it's
+# stopped. Keeps a plugin that's stuck in a loop from holding up the server.
+wasm_fuel = 10000000
+# How much memory each WebAssembly plugin can have, in MB.
+wasm_memory = 64
+
+[scripting]
+# Let server owners run Rhai scripts with /script, and run the .rhai files in the scripts directory
+# when the server starts.
+enabled = true
+# How many operations a script can run before it's stopped, so one stuck in a loop doesn't run forever.
+max_operations = 1000000
 "#;
@@ -0,0 +1,73 @@
+//! Stopping the server, on Ctrl+C or `/stop`.
+//!
+//! [request] asks for the server to stop. The [systems](crate::net::systems) are stopped first,
+//! which stops new connections being accepted, then [shutdown] kicks everyone, saving their data,
+//! unloads the plugins, and writes out the chunks that haven't been saved yet.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use tokio::sync::Notify;
+use tracing::{info, warn};
+
+use crate::net::kick;
+use crate::net::systems::kill_all_systems;
+use crate::state::GlobalState;
+use crate::utils::config::get_global_config;
+use crate::utils::prelude::*;
+use crate::utils::text_component::TextComponent;
+
+/// How long the systems get to stop before the server shuts down without them.
+pub const SYSTEMS_TIMEOUT: Duration = Duration::from_secs(10);
+
+static REQUESTED: AtomicBool = AtomicBool::new(false);
+static NOTIFY: Notify = Notify::const_new();
+
+/// Asks for the server to stop. Does nothing if it's already stopping.
+pub fn request() {
+    if !REQUESTED.swap(true, Ordering::SeqCst) {
+        info!("Stopping the server...");
+    }
+    NOTIFY.notify_waiters();
+}
+
+pub fn is_requested() -> bool {
+    REQUESTED.load(Ordering::SeqCst)
+}
+
+/// Waits until the server has been asked to stop, or returns straight away if it already has.
+pub async fn requested() {
+    loop {
+        let notified = NOTIFY.notified();
+        tokio::pin!(notified);
+        // Registers the waiter before checking, so a request in between isn't missed
+        notified.as_mut().enable();
+        if is_requested() {
+            return;
+        }
+        notified.await;
+    }
+}
+
+/// Kicks every player with the configured `shutdown_message`, which saves them, then lets the
+/// systems save everything else. Run once the systems have stopped, so nothing changes while
+/// it's being saved.
+pub async fn shutdown(state: &GlobalState) -> Result<()> {
+    let reason = TextComponent::parse(&get_global_config().shutdown_message);
+    let connections = state
+        .connections
+        .connections
+        .iter()
+        .map(|conn| conn.value().clone())
+        .collect::<Vec<_>>();
+    info!("Disconnecting {} connections", connections.len());
+    for conn in connections {
+        let conn_id = conn.read().await.id;
+        if let Err(e) = kick(&conn, &reason, state.clone()).await {
+            warn!("Failed to disconnect {}: {}", conn_id, e);
+        }
+    }
+
+    state.plugins.unload_all(state).await;
+    kill_all_systems().await
+}
@@ -1,15 +1,73 @@
+use crate::database::players::PlayerStore;
 use crate::database::Database;
 use crate::ecs::world::World;
 use crate::net::ConnectionList;
+use crate::net::throttle::ConnectionThrottle;
+use crate::net::scheduler::Scheduler;
+use crate::net::scoreboard::ScoreboardManager;
+use crate::net::tab_list::TabList;
+use crate::net::utils::skins::SkinCache;
 use std::sync::Arc;
 use crate::events::creation::dispatcher::EventDispatcher;
+use crate::commands::CommandRegistry;
+use crate::access::bans::BanManager;
+use crate::access::ops::{levels, Operators};
+use crate::net::packets::ConnectionId;
+use crate::utils::components::player::Player;
+use uuid::Uuid;
+use crate::access::whitelist::Whitelist;
+use crate::plugins::PluginManager;
+use crate::world::generation::WorldGenerator;
+use crate::world::id_registry::IdRegistry;
+use crate::world::items::ItemRegistry;
+use crate::world::recipes::RecipeBook;
 
 pub struct ServerState {
+    /// The entities and their components, and resources like the
+    /// [WorldMeta](crate::database::world_meta::WorldMeta) with the spawn point, seed, time and
+    /// gamerules.
     pub world: Arc<World>,
     pub connections: ConnectionList,
     pub database: Database,
+    /// Saved player data, see [PlayerData](crate::database::players::PlayerData).
+    pub players: PlayerStore,
     pub server_stream: tokio::net::TcpListener,
     pub event_dispatcher: Arc<EventDispatcher>,
+    pub commands: Arc<CommandRegistry>,
+    pub whitelist: Whitelist,
+    pub bans: BanManager,
+    pub ops: Operators,
+    /// How often each address has been connecting and logging in.
+    pub throttle: ConnectionThrottle,
+    /// Skins looked up for players in offline mode.
+    pub skins: SkinCache,
+    pub tab_list: TabList,
+    /// Objectives and teams, which everyone sees.
+    pub scoreboard: ScoreboardManager,
+    /// Makes the chunks that aren't in the database. `None` if generation is turned off.
+    pub world_generator: Option<Arc<dyn WorldGenerator>>,
+    pub items: ItemRegistry,
+    pub recipes: RecipeBook,
+    pub sounds: IdRegistry,
+    pub particles: IdRegistry,
+    pub plugins: PluginManager,
+    /// Tasks that run on a later tick.
+    pub scheduler: Scheduler,
+}
+
+impl ServerState {
+    /// The permission level of the player on a connection. See [levels](crate::access::ops::levels).
+    pub async fn permission_level(&self, conn_id: ConnectionId) -> u8 {
+        match self.world.get_component::<Player>(conn_id).await {
+            Ok(player) => self.ops.level(Uuid::from_u128(player.uuid), &player.username),
+            Err(_) => levels::ALL,
+        }
+    }
+
+    /// Checks if the player on a connection has at least the given permission level.
+    pub async fn has_permission(&self, conn_id: ConnectionId, level: u8) -> bool {
+        self.permission_level(conn_id).await >= level
+    }
 }
 
 pub type GlobalState = Arc<ServerState>;
@@ -1,4 +1,5 @@
 mod chunk_stuff;
+mod configuration;
 mod nbt_de;
 mod nbt_ser;
 pub mod query;
@@ -0,0 +1,150 @@
+use std::time::Duration;
+
+use ferrumc_codec::enc::{NetEncode, NetEncodeOpts};
+use ferrumc_codec::network_types::varint::VarInt;
+use ferrumc_macros::NetEncode;
+use tokio::io::AsyncWriteExt;
+use tokio::net::{TcpListener, TcpStream};
+
+use crate::create_state;
+use crate::net::packets::incoming::client_info::ClientInfo;
+use crate::net::protocol::V1_20_2;
+use crate::net::{init_connection, State};
+use crate::utils::config::get_global_config;
+
+// What a client sends to get into configuration, and the first things it sends there.
+
+#[derive(NetEncode)]
+struct Handshake {
+    packet_id: VarInt,
+    protocol_version: VarInt,
+    server_address: String,
+    server_port: u16,
+    next_state: VarInt,
+}
+
+#[derive(NetEncode)]
+struct LoginStart {
+    packet_id: VarInt,
+    username: String,
+    uuid: u128,
+}
+
+#[derive(NetEncode)]
+struct LoginAcknowledged {
+    packet_id: VarInt,
+}
+
+#[derive(NetEncode)]
+struct ClientInformation {
+    packet_id: VarInt,
+    locale: String,
+    view_distance: i8,
+    chat_mode: i8,
+    chat_colors: bool,
+    displayed_skin_parts: u8,
+    main_hand: i8,
+}
+
+#[derive(NetEncode)]
+struct PluginMessage {
+    packet_id: VarInt,
+    channel: String,
+    brand: String,
+}
+
+/// Sends everything up to configuration at once, without waiting for the server to answer, so
+/// each packet is read right after the one that changes the state it's read in.
+async fn pipeline_configuration(
+    protocol_version: i32,
+    client_info_id: i32,
+    plugin_message_id: i32,
+) {
+    let state = create_state(TcpListener::bind("0.0.0.0:0").await.unwrap())
+        .await
+        .unwrap();
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let mut client = TcpStream::connect(listener.local_addr().unwrap())
+        .await
+        .unwrap();
+    let (socket, _) = listener.accept().await.unwrap();
+    tokio::spawn(init_connection(socket, state.clone()));
+
+    let mut data = Vec::new();
+    Handshake {
+        packet_id: VarInt::new(0x00),
+        protocol_version: VarInt::new(protocol_version),
+        server_address: "localhost".to_string(),
+        server_port: 25565,
+        next_state: VarInt::new(2),
+    }
+    .net_encode(&mut data)
+    .await
+    .unwrap();
+    LoginStart {
+        packet_id: VarInt::new(0x00),
+        username: "Pipelined".to_string(),
+        uuid: 1,
+    }
+    .net_encode(&mut data)
+    .await
+    .unwrap();
+
+    // Compression is turned on with Login Success
+    let threshold = get_global_config().network_compression_threshold;
+    let opts = if threshold < 0 {
+        NetEncodeOpts::None
+    } else {
+        NetEncodeOpts::Compressed {
+            threshold: threshold as usize,
+        }
+    };
+    LoginAcknowledged {
+        packet_id: VarInt::new(0x03),
+    }
+    .net_encode_with_opts(&mut data, &opts)
+    .await
+    .unwrap();
+    ClientInformation {
+        packet_id: VarInt::new(client_info_id),
+        locale: "en_us".to_string(),
+        view_distance: 7,
+        chat_mode: 0,
+        chat_colors: true,
+        displayed_skin_parts: 0x7F,
+        main_hand: 1,
+    }
+    .net_encode_with_opts(&mut data, &opts)
+    .await
+    .unwrap();
+    PluginMessage {
+        packet_id: VarInt::new(plugin_message_id),
+        channel: "minecraft:brand".to_string(),
+        brand: "vanilla".to_string(),
+    }
+    .net_encode_with_opts(&mut data, &opts)
+    .await
+    .unwrap();
+    client.write_all(&data).await.unwrap();
+
+    let mut client_info = None;
+    for _ in 0..200 {
+        let connection = state.connections.connections.iter().next();
+        if let Some(conn_id) = connection.map(|entry| *entry.key()) {
+            if let Ok(info) = state.world.get_component::<ClientInfo>(conn_id).await {
+                client_info = Some((conn_id, info.view_distance));
+                break;
+            }
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    let (conn_id, view_distance) = client_info.expect("the client information was never handled");
+    assert_eq!(view_distance, 7);
+    let conn = state.connections.get_connection(conn_id).unwrap();
+    assert_eq!(conn.read().await.state, State::Configuration);
+}
+
+#[tokio::test]
+async fn test_pipelined_configuration() {
+    pipeline_configuration(V1_20_2, 0x00, 0x01).await;
+}
@@ -0,0 +1,67 @@
+use ferrumc_macros::Component;
+
+use crate::utils::components::game_mode::GameMode;
+use crate::utils::config::get_global_config;
+use crate::utils::constants::DEFAULT_FLYING_SPEED;
+
+/// What a player's game mode lets them do, and whether they're flying.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Component)]
+pub struct Abilities {
+    pub invulnerable: bool,
+    pub flying: bool,
+    pub allow_flying: bool,
+    /// Blocks break as soon as they're hit.
+    pub instant_break: bool,
+    pub flying_speed: f32,
+    /// Changes the field of view, like walking faster does.
+    pub walking_speed: f32,
+}
+
+impl Abilities {
+    pub const WALKING_SPEED: f32 = 0.1;
+
+    /// The abilities of a game mode. Only spectators are made to fly, in creative it's up to the
+    /// player.
+    pub fn of(mode: u8) -> Self {
+        let creative = mode == GameMode::CREATIVE;
+        let spectator = mode == GameMode::SPECTATOR;
+        Self {
+            invulnerable: creative || spectator,
+            flying: spectator,
+            allow_flying: creative || spectator,
+            instant_break: creative,
+            flying_speed: DEFAULT_FLYING_SPEED,
+            walking_speed: Self::WALKING_SPEED,
+        }
+    }
+
+    /// The abilities of a game mode, flying at the speed set in the config.
+    pub fn configured(mode: u8) -> Self {
+        Self {
+            flying_speed: get_global_config().flight.speed,
+            ..Self::of(mode)
+        }
+    }
+
+    /// As sent to the client.
+    pub fn flags(&self) -> u8 {
+        u8::from(self.invulnerable)
+            | u8::from(self.flying) << 1
+            | u8::from(self.allow_flying) << 2
+            | u8::from(self.instant_break) << 3
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_of() {
+        assert_eq!(Abilities::of(GameMode::SURVIVAL).flags(), 0);
+        assert_eq!(Abilities::of(GameMode::ADVENTURE).flags(), 0);
+        assert_eq!(Abilities::of(GameMode::CREATIVE).flags(), 0x0D);
+        assert_eq!(Abilities::of(GameMode::SPECTATOR).flags(), 0x07);
+        assert_eq!(Abilities::of(GameMode::SURVIVAL).flying_speed, 0.05);
+    }
+}
@@ -0,0 +1,49 @@
+use std::time::Instant;
+
+use ferrumc_macros::Component;
+
+use crate::net::systems::game_loop::TICKS_PER_SECOND;
+
+/// How far a player's attack has recharged since they last attacked or switched items. Attacking
+/// before it's full does less damage.
+#[derive(Debug, Clone, Copy, Default, Component)]
+pub struct AttackCooldown {
+    last_reset: Option<Instant>,
+}
+
+impl AttackCooldown {
+    pub fn reset(&mut self, now: Instant) {
+        self.last_reset = Some(now);
+    }
+
+    /// From 0 to 1, like vanilla's attack strength. `attack_speed` is in attacks per second.
+    pub fn strength(&self, attack_speed: f32, now: Instant) -> f32 {
+        let Some(last_reset) = self.last_reset else {
+            return 1.0;
+        };
+        let ticks = now.duration_since(last_reset).as_secs_f32() * TICKS_PER_SECOND as f32;
+        let cooldown = TICKS_PER_SECOND as f32 / attack_speed;
+        ((ticks + 0.5) / cooldown).clamp(0.0, 1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn test_strength() {
+        let now = Instant::now();
+        let mut cooldown = AttackCooldown::default();
+        assert_eq!(cooldown.strength(4.0, now), 1.0);
+
+        cooldown.reset(now);
+        assert_eq!(cooldown.strength(4.0, now), 0.1);
+        // Fists recharge in 5 ticks
+        let later = now + Duration::from_millis(250);
+        assert_eq!(cooldown.strength(4.0, later), 1.0);
+        assert!(cooldown.strength(1.6, later) < 0.5);
+    }
+}
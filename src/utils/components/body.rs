@@ -0,0 +1,51 @@
+use ferrumc_macros::Component;
+
+use crate::world::collision::Aabb;
+
+/// How big a non-player entity with a [Motion](super::motion::Motion) is and how it moves. See
+/// [physics](crate::world::physics).
+#[derive(Debug, Clone, Copy, PartialEq, Component)]
+pub struct Body {
+    /// In blocks, along both x and z.
+    pub width: f64,
+    /// In blocks.
+    pub height: f64,
+    /// How much faster it falls each tick, in blocks per tick.
+    pub gravity: f64,
+    /// How much of its speed it keeps each tick.
+    pub drag: f64,
+    /// How high a block it can walk straight up onto, like a slab.
+    pub step_height: f64,
+}
+
+impl Body {
+    pub const ITEM: Body = Body {
+        width: 0.25,
+        height: 0.25,
+        gravity: 0.04,
+        drag: 0.98,
+        step_height: 0.0,
+    };
+    pub const EXPERIENCE_ORB: Body = Body {
+        width: 0.5,
+        height: 0.5,
+        gravity: 0.03,
+        ..Body::ITEM
+    };
+
+    /// An animal or monster, which falls like vanilla's do and walks up slabs.
+    pub const fn mob(width: f64, height: f64) -> Self {
+        Self {
+            width,
+            height,
+            gravity: 0.08,
+            drag: 0.98,
+            step_height: 0.6,
+        }
+    }
+
+    /// Its box, when it's standing at `position`.
+    pub fn aabb(&self, position: (f64, f64, f64)) -> Aabb {
+        Aabb::standing_at(position, self.width, self.height)
+    }
+}
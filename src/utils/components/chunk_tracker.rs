@@ -0,0 +1,163 @@
+use std::collections::HashSet;
+
+use ferrumc_macros::{Component, Getter};
+
+use crate::world::dimension::Dimension;
+
+/// Chunks to load and chunks to unload.
+type ChunkDiff = (Vec<(i32, i32)>, Vec<(i32, i32)>);
+
+/// Keeps track of the chunks a player has loaded, so only the difference has to be sent when they move.
+#[derive(Debug, Component, Getter, Default)]
+pub struct ChunkTracker {
+    /// The chunks the client currently has, as chunk coordinates.
+    pub loaded: HashSet<(i32, i32)>,
+    /// The chunks being sent to the client, which count as loaded once they've been sent.
+    pub sending: HashSet<(i32, i32)>,
+    /// The chunk the client was centered on the last time chunks were sent.
+    pub center: Option<(i32, i32)>,
+    /// The dimension the loaded chunks are from.
+    pub dimension: Option<Dimension>,
+}
+
+impl ChunkTracker {
+    /// Forgets every chunk if they're from another dimension. The client drops its chunks when it
+    /// changes dimension, so they don't need to be unloaded.
+    pub fn switch_dimension(&mut self, dimension: Dimension) {
+        if self.dimension != Some(dimension) {
+            self.loaded.clear();
+            self.sending.clear();
+            self.center = None;
+            self.dimension = Some(dimension);
+        }
+    }
+
+    /// Diffs the loaded chunks against the ones within `radius` of `center`.
+    ///
+    /// Returns the chunks to load, closest to `center` first, and the chunks to unload. Chunks
+    /// that are still being sent are in neither.
+    pub fn diff(&self, center: (i32, i32), radius: i32) -> ChunkDiff {
+        let in_range = chunks_in_range(center, radius);
+
+        let mut to_load: Vec<_> = in_range
+            .iter()
+            .filter(|chunk| !self.loaded.contains(chunk) && !self.sending.contains(chunk))
+            .copied()
+            .collect();
+        to_load.sort_by_key(|(x, z)| (x - center.0).pow(2) + (z - center.1).pow(2));
+
+        let to_unload = self
+            .loaded
+            .iter()
+            .filter(|chunk| !in_range.contains(chunk))
+            .copied()
+            .collect();
+
+        (to_load, to_unload)
+    }
+
+    /// Marks the chunks from a [diff](Self::diff) as being sent and unloaded, so the next diff
+    /// doesn't have them again.
+    pub fn start_sending(
+        &mut self,
+        center: (i32, i32),
+        to_load: &[(i32, i32)],
+        to_unload: &[(i32, i32)],
+    ) {
+        self.sending.extend(to_load);
+        for chunk in to_unload {
+            self.loaded.remove(chunk);
+        }
+        self.center = Some(center);
+    }
+
+    /// Marks a chunk as loaded once it's been sent, if the player is still in the dimension it's
+    /// from.
+    pub fn sent(&mut self, dimension: Dimension, chunk: (i32, i32)) {
+        if self.dimension == Some(dimension) && self.sending.remove(&chunk) {
+            self.loaded.insert(chunk);
+        }
+    }
+
+    /// Forgets about a chunk that couldn't be sent, so it's tried again with the next diff.
+    pub fn failed(&mut self, dimension: Dimension, chunk: (i32, i32)) {
+        if self.dimension == Some(dimension) {
+            self.sending.remove(&chunk);
+        }
+    }
+}
+
+fn chunks_in_range(center: (i32, i32), radius: i32) -> HashSet<(i32, i32)> {
+    let mut chunks = HashSet::new();
+    for x in -radius..=radius {
+        for z in -radius..=radius {
+            chunks.insert((center.0 + x, center.1 + z));
+        }
+    }
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_initial_diff_loads_everything() {
+        let tracker = ChunkTracker::default();
+        let (to_load, to_unload) = tracker.diff((0, 0), 2);
+
+        assert_eq!(to_load.len(), 25);
+        assert_eq!(to_load[0], (0, 0));
+        assert!(to_unload.is_empty());
+    }
+
+    fn send_all(tracker: &mut ChunkTracker, center: (i32, i32), radius: i32) {
+        let (to_load, to_unload) = tracker.diff(center, radius);
+        tracker.start_sending(center, &to_load, &to_unload);
+        for chunk in to_load {
+            tracker.sent(Dimension::Overworld, chunk);
+        }
+    }
+
+    #[test]
+    fn test_diff_after_moving() {
+        let mut tracker = ChunkTracker::default();
+        tracker.switch_dimension(Dimension::Overworld);
+        send_all(&mut tracker, (0, 0), 2);
+
+        // One chunk along the x axis: a new column comes in, the old one goes out
+        let (to_load, to_unload) = tracker.diff((1, 0), 2);
+        assert_eq!(to_load.len(), 5);
+        assert!(to_load.iter().all(|(x, _)| *x == 3));
+        assert_eq!(to_unload.len(), 5);
+        assert!(to_unload.iter().all(|(x, _)| *x == -2));
+
+        send_all(&mut tracker, (1, 0), 2);
+        let (to_load, to_unload) = tracker.diff((1, 0), 2);
+        assert!(to_load.is_empty());
+        assert!(to_unload.is_empty());
+    }
+
+    #[test]
+    fn test_failed_chunks_are_sent_again() {
+        let mut tracker = ChunkTracker::default();
+        tracker.switch_dimension(Dimension::Overworld);
+        let (to_load, to_unload) = tracker.diff((0, 0), 1);
+        tracker.start_sending((0, 0), &to_load, &to_unload);
+
+        // Not sent again while they're being sent
+        assert!(tracker.diff((0, 0), 1).0.is_empty());
+
+        tracker.sent(Dimension::Overworld, (0, 0));
+        tracker.failed(Dimension::Overworld, (1, 1));
+        assert_eq!(tracker.diff((0, 0), 1).0, vec![(1, 1)]);
+        assert!(tracker.loaded.contains(&(0, 0)));
+
+        // Ones sent from before switching dimension don't count
+        tracker.switch_dimension(Dimension::Nether);
+        tracker.start_sending((0, 0), &[(1, 0)], &[]);
+        tracker.sent(Dimension::Overworld, (1, 0));
+        assert!(tracker.loaded.is_empty());
+        assert!(tracker.sending.contains(&(1, 0)));
+    }
+}
@@ -0,0 +1,19 @@
+use ferrumc_macros::{Component, Constructor, Getter};
+
+use crate::state::GlobalState;
+use crate::world::dimension::Dimension;
+
+/// The dimension a player is in. Blocks they touch and chunks they're sent come from it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Component, Getter, Constructor)]
+pub struct CurrentDimension {
+    pub dimension: Dimension,
+}
+
+/// The dimension an entity is in, the overworld if it doesn't have one.
+pub async fn dimension_of(state: &GlobalState, entity: usize) -> Dimension {
+    state
+        .world
+        .get_component::<CurrentDimension>(entity)
+        .await
+        .map_or(Dimension::Overworld, |current| current.dimension)
+}
@@ -0,0 +1,10 @@
+use ferrumc_macros::{Component, Constructor};
+
+use crate::utils::text_component::TextComponent;
+
+/// What a player is shown as in the tab list instead of their username. See
+/// [set_display_name](crate::net::tab_list::set_display_name).
+#[derive(Debug, Clone, Component, Constructor)]
+pub struct DisplayName {
+    pub name: TextComponent,
+}
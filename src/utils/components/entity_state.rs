@@ -0,0 +1,75 @@
+use ferrumc_macros::{Component, Getter};
+
+use crate::utils::encoding::metadata::{flags, indices, EntityMetadata, MetadataValue, Pose};
+use crate::utils::text_component::TextComponent;
+
+/// The parts of an entity's state that other players see through its
+/// [metadata](EntityMetadata).
+#[derive(Debug, Clone, Default, Component, Getter)]
+pub struct EntityState {
+    pub sneaking: bool,
+    pub sprinting: bool,
+    pub glowing: bool,
+    pub invisible: bool,
+    /// Shown above the entity instead of its name.
+    pub custom_name: Option<TextComponent>,
+}
+
+impl EntityState {
+    pub fn pose(&self) -> Pose {
+        if self.sneaking {
+            Pose::Sneaking
+        } else {
+            Pose::Standing
+        }
+    }
+
+    /// All of the entity's metadata that comes from its state.
+    pub fn metadata(&self) -> EntityMetadata {
+        let mut entity_flags = 0;
+        for (set, flag) in [
+            (self.sneaking, flags::SNEAKING),
+            (self.sprinting, flags::SPRINTING),
+            (self.invisible, flags::INVISIBLE),
+            (self.glowing, flags::GLOWING),
+        ] {
+            if set {
+                entity_flags |= flag;
+            }
+        }
+        let mut metadata = EntityMetadata::new();
+        metadata
+            .set(indices::FLAGS, MetadataValue::Byte(entity_flags))
+            .set(
+                indices::CUSTOM_NAME,
+                MetadataValue::OptionalTextComponent(self.custom_name.clone()),
+            )
+            .set(
+                indices::CUSTOM_NAME_VISIBLE,
+                MetadataValue::Boolean(self.custom_name.is_some()),
+            )
+            .set(indices::POSE, MetadataValue::Pose(self.pose()));
+        metadata
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metadata() {
+        let state = EntityState {
+            sneaking: true,
+            glowing: true,
+            ..Default::default()
+        };
+        let metadata = state.metadata();
+        assert_eq!(metadata.get(indices::FLAGS), Some(&MetadataValue::Byte(0x42)));
+        assert_eq!(metadata.get(indices::POSE), Some(&MetadataValue::Pose(Pose::Sneaking)));
+        assert_eq!(
+            metadata.get(indices::CUSTOM_NAME_VISIBLE),
+            Some(&MetadataValue::Boolean(false))
+        );
+    }
+}
@@ -0,0 +1,162 @@
+use ferrumc_macros::Component;
+
+use crate::net::packets::outgoing::set_experience::SetExperience;
+use crate::net::packets::ConnectionId;
+use crate::state::GlobalState;
+use crate::utils::prelude::*;
+use crate::world::sounds::{names, play_entity_sound, SoundCategory};
+
+/// A player's experience level and how far they are into the next one. Works out the same as
+/// vanilla's, though it's kept in whole points rather than as a fraction of the bar.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Component)]
+pub struct Experience {
+    pub level: i32,
+    /// Towards the next level, from 0 to one less than [Experience::points_to_next_level].
+    pub points: i32,
+    /// Every point the player has collected since they last died.
+    pub total: i32,
+}
+
+impl Experience {
+    /// Players that die drop 7 points for each level they had, but no more than this.
+    const MAX_DROPPED: i32 = 100;
+
+    /// How many points it takes to get from `level` to the one after.
+    pub fn points_to_next_level(level: i32) -> i32 {
+        if level >= 30 {
+            112 + (level - 30) * 9
+        } else if level >= 15 {
+            37 + (level - 15) * 5
+        } else {
+            7 + level * 2
+        }
+    }
+
+    /// How full the bar is, from 0 to 1.
+    pub fn progress(&self) -> f32 {
+        self.points as f32 / Self::points_to_next_level(self.level) as f32
+    }
+
+    /// Gives the player points, going up levels as the bar fills. Taking points away goes back
+    /// down, but never below level 0.
+    pub fn add_points(&mut self, points: i32) {
+        self.total = self.total.saturating_add(points).max(0);
+        self.points = self.points.saturating_add(points);
+        while self.points >= Self::points_to_next_level(self.level) {
+            self.points -= Self::points_to_next_level(self.level);
+            self.level += 1;
+        }
+        while self.points < 0 && self.level > 0 {
+            self.level -= 1;
+            self.points += Self::points_to_next_level(self.level);
+        }
+        self.points = self.points.max(0);
+    }
+
+    /// Gives or takes whole levels, keeping the bar as full as it was.
+    pub fn add_levels(&mut self, levels: i32) {
+        self.set_levels(self.level.saturating_add(levels));
+    }
+
+    pub fn set_levels(&mut self, level: i32) {
+        let progress = self.progress();
+        self.level = level.max(0);
+        self.points = (progress * Self::points_to_next_level(self.level) as f32) as i32;
+    }
+
+    /// Sets how far into the current level the player is. Fails if it's more than fits in the bar,
+    /// like vanilla's `/xp set`.
+    pub fn set_points(&mut self, points: i32) -> bool {
+        if !(0..Self::points_to_next_level(self.level)).contains(&points) {
+            return false;
+        }
+        self.points = points;
+        true
+    }
+
+    /// How many points a player drops when they die.
+    pub fn dropped_on_death(&self) -> i32 {
+        (self.level * 7).min(Self::MAX_DROPPED)
+    }
+}
+
+/// Sends a player their experience bar and level.
+pub async fn send_experience(state: &GlobalState, conn_id: ConnectionId) -> Result<()> {
+    let experience = state
+        .world
+        .get_component::<Experience>(conn_id)
+        .await
+        .map(|experience| *experience)
+        .unwrap_or_default();
+    let conn = state.connections.get_connection(conn_id)?;
+    let conn = conn.read().await;
+    conn.send_packet(SetExperience::new(&experience)).await
+}
+
+/// Gives a player experience points, or takes them away if `points` is negative. Like vanilla,
+/// there's a sound every 5 levels they go up.
+pub async fn give_experience(
+    state: &GlobalState,
+    conn_id: ConnectionId,
+    points: i32,
+) -> Result<()> {
+    let (before, after) = {
+        let mut experience = state
+            .world
+            .get_component_storage()
+            .get_mut_or_insert_with::<Experience>(conn_id, Default::default)
+            .await;
+        let before = experience.level;
+        experience.add_points(points);
+        (before, experience.level)
+    };
+    send_experience(state, conn_id).await?;
+
+    if after > before && after % 5 == 0 {
+        let volume = after.min(30) as f32 / 30.0 * 0.75;
+        let sound = names::PLAYER_LEVELUP;
+        play_entity_sound(state, conn_id, sound, SoundCategory::Players, volume, 1.0).await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_points() {
+        let mut experience = Experience::default();
+        experience.add_points(3);
+        assert_eq!((experience.level, experience.points), (0, 3));
+        // 7 for the first level, 9 for the second, and 5 left over
+        experience.add_points(18);
+        assert_eq!((experience.level, experience.points, experience.total), (2, 5, 21));
+        assert_eq!(experience.progress(), 5.0 / 11.0);
+
+        experience.add_points(-10);
+        assert_eq!((experience.level, experience.points), (1, 4));
+        experience.add_points(-100);
+        assert_eq!((experience.level, experience.points, experience.total), (0, 0, 0));
+
+        assert_eq!(Experience::points_to_next_level(15), 37);
+        assert_eq!(Experience::points_to_next_level(31), 121);
+    }
+
+    #[test]
+    fn test_levels() {
+        let mut experience = Experience::default();
+        experience.add_points(3);
+        experience.add_levels(30);
+        // Still about a third of the way
+        assert_eq!((experience.level, experience.points), (30, 48));
+        assert_eq!(experience.dropped_on_death(), 100);
+        experience.add_levels(-40);
+        assert_eq!(experience.level, 0);
+
+        assert!(experience.set_points(6));
+        assert!(!experience.set_points(7));
+        assert!(!experience.set_points(-1));
+        assert_eq!(experience.points, 6);
+    }
+}
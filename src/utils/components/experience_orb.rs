@@ -0,0 +1,20 @@
+use ferrumc_macros::Component;
+
+/// An orb of experience, drifting towards the nearest player. See
+/// [experience_orbs](crate::world::experience_orbs).
+#[derive(Debug, Clone, Copy, PartialEq, Component)]
+pub struct ExperienceOrb {
+    /// How many points the player who picks it up gets.
+    pub value: i32,
+    /// Ticks since it was spawned, it despawns at [ExperienceOrb::DESPAWN_AGE].
+    pub age: u32,
+}
+
+impl ExperienceOrb {
+    /// Five minutes, same as vanilla.
+    pub const DESPAWN_AGE: u32 = 6000;
+
+    pub fn new(value: i32) -> Self {
+        Self { value, age: 0 }
+    }
+}
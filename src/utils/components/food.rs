@@ -0,0 +1,170 @@
+use ferrumc_macros::Component;
+
+use crate::net::packets::ConnectionId;
+use crate::state::GlobalState;
+use crate::utils::components::abilities::Abilities;
+use crate::utils::components::health::Health;
+
+/// How full a player is. Works like vanilla's, on normal difficulty: a full bar heals the player,
+/// and an empty one hurts them down to half a heart.
+#[derive(Debug, Clone, Copy, PartialEq, Component)]
+pub struct Food {
+    /// From 0 to [Food::MAX], the drumsticks on the hunger bar.
+    pub level: i32,
+    /// Used up before the level goes down. Never more than the level.
+    pub saturation: f32,
+    /// Built up by sprinting, jumping etc. Every 4 takes a point of saturation or food.
+    pub exhaustion: f32,
+    tick_timer: u32,
+}
+
+/// What a tick of [Food::tick] does to the player's health.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FoodEffect {
+    Heal(f32),
+    Starve,
+}
+
+/// How much a player has to do before they get hungrier, same as vanilla.
+pub mod exhaustion {
+    /// Per block.
+    pub const SPRINTING: f32 = 0.1;
+    pub const JUMP: f32 = 0.05;
+    pub const SPRINT_JUMP: f32 = 0.2;
+    pub const BREAK_BLOCK: f32 = 0.005;
+    pub const ATTACK: f32 = 0.1;
+    pub const DAMAGE: f32 = 0.1;
+    pub const REGENERATION: f32 = 6.0;
+}
+
+impl Default for Food {
+    fn default() -> Self {
+        Self {
+            level: Self::MAX,
+            saturation: 5.0,
+            exhaustion: 0.0,
+            tick_timer: 0,
+        }
+    }
+}
+
+impl Food {
+    pub const MAX: i32 = 20;
+    const MAX_EXHAUSTION: f32 = 40.0;
+    /// Below this the player doesn't heal.
+    const REGENERATION_LEVEL: i32 = 18;
+    /// How often the player heals from food or starves, in ticks.
+    const SLOW_INTERVAL: u32 = 80;
+    /// How often a player with a full bar and saturation left heals, in ticks.
+    const FAST_INTERVAL: u32 = 10;
+    /// Starving doesn't take players' health below this, on normal difficulty.
+    const MIN_STARVING_HEALTH: f32 = 1.0;
+
+    pub fn exhaust(&mut self, amount: f32) {
+        self.exhaustion = (self.exhaustion + amount).min(Self::MAX_EXHAUSTION);
+    }
+
+    /// Runs every tick: uses up exhaustion, then works out whether the player heals or starves.
+    pub fn tick(&mut self, health: &Health) -> Option<FoodEffect> {
+        if self.exhaustion > 4.0 {
+            self.exhaustion -= 4.0;
+            if self.saturation > 0.0 {
+                self.saturation = (self.saturation - 1.0).max(0.0);
+            } else {
+                self.level = (self.level - 1).max(0);
+            }
+        }
+
+        let hurt = health.is_hurt();
+        let (interval, effect) = if hurt && self.saturation > 0.0 && self.level >= Self::MAX {
+            let amount = self.saturation.min(6.0);
+            (Self::FAST_INTERVAL, Some((FoodEffect::Heal(amount / 6.0), amount)))
+        } else if hurt && self.level >= Self::REGENERATION_LEVEL {
+            let heal = FoodEffect::Heal(1.0);
+            (Self::SLOW_INTERVAL, Some((heal, exhaustion::REGENERATION)))
+        } else if self.level <= 0 {
+            let starve = health.health > Self::MIN_STARVING_HEALTH;
+            (Self::SLOW_INTERVAL, starve.then_some((FoodEffect::Starve, 0.0)))
+        } else {
+            self.tick_timer = 0;
+            return None;
+        };
+
+        self.tick_timer += 1;
+        if self.tick_timer < interval {
+            return None;
+        }
+        self.tick_timer = 0;
+        let (effect, cost) = effect?;
+        self.exhaust(cost);
+        Some(effect)
+    }
+}
+
+/// Makes a player hungrier, unless they can't be hurt, like in creative.
+pub async fn exhaust(state: &GlobalState, conn_id: ConnectionId, amount: f32) {
+    let invulnerable = state
+        .world
+        .get_component::<Abilities>(conn_id)
+        .await
+        .is_ok_and(|abilities| abilities.invulnerable);
+    if invulnerable {
+        return;
+    }
+    if let Ok(mut food) = state.world.get_component_storage().get_mut::<Food>(conn_id).await {
+        food.exhaust(amount);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ticks(food: &mut Food, health: &Health, ticks: u32) -> Vec<FoodEffect> {
+        (0..ticks).filter_map(|_| food.tick(health)).collect()
+    }
+
+    #[test]
+    fn test_regeneration() {
+        let hurt = Health {
+            health: 10.0,
+            ..Default::default()
+        };
+        // Full with saturation heals fast, using the saturation up
+        let mut food = Food::default();
+        assert_eq!(ticks(&mut food, &hurt, 10), vec![FoodEffect::Heal(5.0 / 6.0)]);
+        assert_eq!(food.exhaustion, 5.0);
+        food.tick(&hurt);
+        assert_eq!((food.saturation, food.exhaustion), (4.0, 1.0));
+
+        // Without saturation it's slow
+        let mut food = Food {
+            level: 18,
+            saturation: 0.0,
+            ..Default::default()
+        };
+        assert_eq!(ticks(&mut food, &hurt, 80), vec![FoodEffect::Heal(1.0)]);
+        food.tick(&hurt);
+        assert_eq!(food.level, 17);
+        assert_eq!(ticks(&mut food, &hurt, 200), vec![]);
+
+        // Nothing happens to players that aren't hurt
+        let mut food = Food::default();
+        assert_eq!(ticks(&mut food, &Health::default(), 200), vec![]);
+    }
+
+    #[test]
+    fn test_starvation() {
+        let mut food = Food {
+            level: 0,
+            saturation: 0.0,
+            ..Default::default()
+        };
+        assert_eq!(ticks(&mut food, &Health::default(), 160).len(), 2);
+        let weak = Health {
+            health: 1.0,
+            ..Default::default()
+        };
+        assert_eq!(ticks(&mut food, &weak, 160), vec![]);
+    }
+}
@@ -0,0 +1,61 @@
+use ferrumc_macros::{Component, Constructor, Getter};
+
+use crate::net::packets::outgoing::game_event::{events, GameEvent};
+use crate::net::packets::outgoing::player_abilities::PlayerAbilitiesOut;
+use crate::net::packets::ConnectionId;
+use crate::net::tab_list;
+use crate::state::GlobalState;
+use crate::utils::components::abilities::Abilities;
+use crate::utils::prelude::*;
+
+/// The game mode a player is in, numbered the same as on the network.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Component, Getter, Constructor)]
+pub struct GameMode {
+    pub mode: u8,
+}
+
+impl GameMode {
+    pub const SURVIVAL: u8 = 0;
+    pub const CREATIVE: u8 = 1;
+    pub const ADVENTURE: u8 = 2;
+    pub const SPECTATOR: u8 = 3;
+
+    /// Whether blocks break as soon as the player starts digging them.
+    pub fn breaks_instantly(&self) -> bool {
+        self.mode == Self::CREATIVE
+    }
+
+    /// Whether the player can break and place blocks. Adventure mode is for maps that don't want
+    /// them to, and spectators can't touch anything.
+    pub fn can_build(&self) -> bool {
+        self.mode == Self::SURVIVAL || self.mode == Self::CREATIVE
+    }
+}
+
+/// Puts a player in another game mode, with its abilities, and shows it in the tab list. A player
+/// that was flying keeps flying if they still can.
+pub async fn set_game_mode(state: &GlobalState, conn_id: ConnectionId, mode: u8) -> Result<()> {
+    let was_flying = state
+        .world
+        .get_component::<Abilities>(conn_id)
+        .await
+        .is_ok_and(|abilities| abilities.flying);
+    let mut abilities = Abilities::configured(mode);
+    abilities.flying |= was_flying && abilities.allow_flying;
+
+    state
+        .world
+        .get_component_storage()
+        .insert(conn_id, GameMode::new(mode))
+        .insert(conn_id, abilities);
+
+    let conn = state.connections.get_connection(conn_id)?;
+    let conn = conn.read().await;
+    conn.send_packet(GameEvent::new_auto(events::CHANGE_GAME_MODE, mode as f32))
+        .await?;
+    conn.send_packet(PlayerAbilitiesOut::new(&abilities))
+        .await?;
+    drop(conn);
+
+    tab_list::update_game_mode(state, conn_id, mode).await
+}
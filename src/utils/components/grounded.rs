@@ -1,8 +1,18 @@
+use std::time::{Duration, Instant};
+
 use ferrumc_macros::{Component, Constructor, Getter};
 
 #[derive(Debug, Default, Component, Getter, Constructor)]
 pub struct Grounded {
     pub is_grounded: bool,
+    /// When the player started floating in the air, if they are.
+    pub floating_since: Option<Instant>,
+    /// How far the player has fallen since they were last on the ground, in blocks.
+    pub fall_distance: f32,
+    /// Exactly how high the player was at their last move, which their [Position] is rounded from.
+    ///
+    /// [Position]: crate::utils::encoding::position::Position
+    pub last_y: Option<f64>,
 }
 
 
@@ -14,4 +24,69 @@ impl Grounded {
     pub fn flip_grounded(&mut self) {
         self.is_grounded = !self.is_grounded;
     }
+
+    /// Follows the player down as they fall. `y` is where they moved to, if they did. Returns how
+    /// far they fell when they land.
+    pub fn record_fall(&mut self, y: Option<f64>, on_ground: bool) -> Option<f32> {
+        if let Some(y) = y {
+            if let Some(last_y) = self.last_y.replace(y) {
+                if y < last_y {
+                    self.fall_distance += (last_y - y) as f32;
+                }
+            }
+        }
+        if !on_ground {
+            return None;
+        }
+        let fallen = std::mem::take(&mut self.fall_distance);
+        (fallen > 0.0).then_some(fallen)
+    }
+
+    /// Forgets the fall so far, e.g. when the player is teleported or lands in water.
+    pub fn reset_fall(&mut self) {
+        self.fall_distance = 0.0;
+        self.last_y = None;
+    }
+
+    /// Notes whether the player is floating, and returns how long they've been floating for.
+    pub fn floating_for(&mut self, floating: bool, now: Instant) -> Duration {
+        if !floating {
+            self.floating_since = None;
+            return Duration::ZERO;
+        }
+        now.duration_since(*self.floating_since.get_or_insert(now))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_floating_for() {
+        let mut grounded = Grounded::default();
+        let now = Instant::now();
+        assert_eq!(grounded.floating_for(true, now), Duration::ZERO);
+        let later = now + Duration::from_secs(3);
+        assert_eq!(grounded.floating_for(true, later), Duration::from_secs(3));
+        // Landing starts it over
+        assert_eq!(grounded.floating_for(false, later), Duration::ZERO);
+        assert_eq!(grounded.floating_for(true, later), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_record_fall() {
+        let mut grounded = Grounded::default();
+        assert_eq!(grounded.record_fall(Some(80.0), true), None);
+        // Going up on the way doesn't count against it
+        assert_eq!(grounded.record_fall(Some(81.5), false), None);
+        assert_eq!(grounded.record_fall(Some(75.5), false), None);
+        assert_eq!(grounded.record_fall(None, false), None);
+        assert_eq!(grounded.record_fall(Some(70.0), true), Some(11.5));
+        assert_eq!(grounded.record_fall(Some(70.0), true), None);
+
+        grounded.record_fall(Some(60.0), false);
+        grounded.reset_fall();
+        assert_eq!(grounded.record_fall(Some(50.0), true), None);
+    }
 }
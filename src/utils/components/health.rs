@@ -0,0 +1,72 @@
+use std::time::{Duration, Instant};
+
+use ferrumc_macros::Component;
+
+use crate::net::packets::outgoing::set_health::SetHealth;
+use crate::net::packets::ConnectionId;
+use crate::state::GlobalState;
+use crate::utils::components::food::Food;
+use crate::utils::prelude::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Component)]
+pub struct Health {
+    /// In half hearts, from 0 to [Health::MAX].
+    pub health: f32,
+    /// When the entity was last hurt, for [Health::recently_hurt].
+    pub last_hurt: Option<Instant>,
+}
+
+impl Default for Health {
+    fn default() -> Self {
+        Self {
+            health: Self::MAX,
+            last_hurt: None,
+        }
+    }
+}
+
+impl Health {
+    pub const MAX: f32 = 20.0;
+    /// How long an entity can't be attacked again for after being hurt. Same as vanilla.
+    pub const INVULNERABLE_TIME: Duration = Duration::from_millis(500);
+
+    pub fn is_hurt(&self) -> bool {
+        self.health > 0.0 && self.health < Self::MAX
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.health <= 0.0
+    }
+
+    /// Nothing comes back to life by healing.
+    pub fn heal(&mut self, amount: f32) {
+        if !self.is_dead() {
+            self.health = (self.health + amount).min(Self::MAX);
+        }
+    }
+
+    pub fn damage(&mut self, amount: f32, now: Instant) {
+        self.health = (self.health - amount).max(0.0);
+        self.last_hurt = Some(now);
+    }
+
+    /// Whether the entity was hurt too recently to be attacked again.
+    pub fn recently_hurt(&self, now: Instant) -> bool {
+        self.last_hurt
+            .is_some_and(|last_hurt| now.duration_since(last_hurt) < Self::INVULNERABLE_TIME)
+    }
+}
+
+/// Sends a player their health and hunger.
+pub async fn send_health(state: &GlobalState, conn_id: ConnectionId) -> Result<()> {
+    let health = *state.world.get_component::<Health>(conn_id).await?;
+    let food = state
+        .world
+        .get_component::<Food>(conn_id)
+        .await
+        .map(|food| *food)
+        .unwrap_or_default();
+    let conn = state.connections.get_connection(conn_id)?;
+    let conn = conn.read().await;
+    conn.send_packet(SetHealth::new(&health, &food)).await
+}
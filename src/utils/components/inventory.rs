@@ -0,0 +1,230 @@
+use std::collections::HashMap;
+use std::ops::Range;
+
+use ferrumc_macros::Component;
+
+use crate::utils::encoding::slot::ItemStack;
+
+/// What a player has in their inventory, as far as the server knows.
+#[derive(Debug, Default, Component)]
+pub struct Inventory {
+    /// The hotbar slot the player has selected, 0 to 8.
+    pub selected_slot: i16,
+    /// Indexed by the player inventory window's slot numbers, see [Inventory::HOTBAR_START].
+    pub slots: HashMap<i16, ItemStack>,
+    /// The item the player is moving around with the mouse.
+    pub carried: Option<ItemStack>,
+    /// Sent with every update of the inventory, and sent back with clicks so the server can tell
+    /// the client clicked on something it hasn't seen yet. See [Inventory::next_state_id].
+    pub state_id: i32,
+    /// The id of the last container window the player opened, see [Inventory::next_window_id].
+    pub window_id: u8,
+}
+
+impl Inventory {
+    /// Slots in the player inventory window: crafting, armor, the main inventory, the hotbar and
+    /// the offhand.
+    pub const SLOTS: i16 = 46;
+    /// What the crafting grid makes, see [RecipeBook](crate::world::recipes::RecipeBook).
+    pub const CRAFTING_RESULT: i16 = 0;
+    /// The 2x2 crafting grid, row by row.
+    pub const CRAFTING_GRID: Range<i16> = 1..5;
+    /// The window slot of the first of the 27 main inventory slots, above the hotbar.
+    pub const MAIN_START: i16 = 9;
+    /// The window slot of the first hotbar slot. The other 8 follow it.
+    pub const HOTBAR_START: i16 = 36;
+    pub const OFFHAND: i16 = 45;
+
+    /// The item in the main hand, or in the offhand if `offhand` is set.
+    pub fn held_item(&self, offhand: bool) -> Option<&ItemStack> {
+        let slot = if offhand {
+            Self::OFFHAND
+        } else {
+            Self::HOTBAR_START + self.selected_slot
+        };
+        self.slots.get(&slot)
+    }
+
+    /// The first slot with the item `id` in it, looking in the hands first and then everywhere
+    /// [Inventory::first_free_slot] does, like vanilla looking for arrows to shoot.
+    pub fn find_item(&self, id: i32) -> Option<i16> {
+        [Self::OFFHAND, Self::HOTBAR_START + self.selected_slot]
+            .into_iter()
+            .chain(Self::storage_slots())
+            .find(|slot| self.slots.get(slot).is_some_and(|item| item.id == id))
+    }
+
+    /// Takes one item out of a slot, emptying it if it was the last. Returns whether there was
+    /// anything there.
+    pub fn take_one(&mut self, slot: i16) -> bool {
+        let Some(mut item) = self.slots.remove(&slot) else {
+            return false;
+        };
+        item.count -= 1;
+        self.set_slot(slot, Some(item));
+        true
+    }
+
+    /// The first empty slot an item can go in, the hotbar first and then the main inventory, like
+    /// when picking something up.
+    pub fn first_free_slot(&self) -> Option<i16> {
+        Self::storage_slots().find(|slot| !self.slots.contains_key(slot))
+    }
+
+    /// Puts a whole stack in the inventory, topping up stacks of the same item before using empty
+    /// slots, in the same order as [Inventory::first_free_slot]. Does nothing and returns false if
+    /// it doesn't all fit.
+    pub fn add_item(&mut self, item: &ItemStack, max_stack_size: i8) -> bool {
+        let space: i32 = Self::storage_slots()
+            .map(|slot| match self.slots.get(&slot) {
+                Some(stack) if stack.stacks_with(item) => (max_stack_size - stack.count).max(0),
+                Some(_) => 0,
+                None => max_stack_size,
+            } as i32)
+            .sum();
+        if space < item.count as i32 {
+            return false;
+        }
+        self.add_as_much(item, max_stack_size);
+        true
+    }
+
+    /// Puts as much of a stack in the inventory as fits, the same way as [Inventory::add_item].
+    /// Returns how many went in.
+    pub fn add_as_much(&mut self, item: &ItemStack, max_stack_size: i8) -> i8 {
+        let mut remaining = item.count;
+        for slot in Self::storage_slots() {
+            if let Some(stack) = self.slots.get_mut(&slot).filter(|stack| stack.stacks_with(item)) {
+                let added = remaining.min((max_stack_size - stack.count).max(0));
+                stack.count += added;
+                remaining -= added;
+            }
+        }
+        while remaining > 0 {
+            let Some(slot) = self.first_free_slot() else {
+                break;
+            };
+            let added = remaining.min(max_stack_size);
+            self.set_slot(slot, Some(ItemStack { count: added, ..item.clone() }));
+            remaining -= added;
+        }
+        item.count - remaining
+    }
+
+    /// The hotbar and then the main inventory.
+    fn storage_slots() -> impl Iterator<Item = i16> {
+        (Self::HOTBAR_START..Self::OFFHAND).chain(Self::MAIN_START..Self::HOTBAR_START)
+    }
+
+    /// Moves the state on, for when the server changes the inventory and sends it to the client.
+    pub fn next_state_id(&mut self) -> i32 {
+        // Vanilla wraps around at 15 bits
+        self.state_id = (self.state_id + 1) & 0x7FFF;
+        self.state_id
+    }
+
+    /// The id for a new container window. 0 is the player's inventory, and vanilla counts the rest
+    /// from 1 to 100.
+    pub fn next_window_id(&mut self) -> u8 {
+        self.window_id = self.window_id % 100 + 1;
+        self.window_id
+    }
+
+    pub fn set_slot(&mut self, slot: i16, item: Option<ItemStack>) {
+        match item {
+            Some(item) if item.count > 0 => {
+                self.slots.insert(slot, item);
+            }
+            _ => {
+                self.slots.remove(&slot);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_held_item() {
+        let stone = ItemStack::new(1, 64);
+        let mut inventory = Inventory::default();
+        inventory.set_slot(Inventory::HOTBAR_START + 2, Some(stone.clone()));
+        assert_eq!(inventory.held_item(false), None);
+
+        inventory.selected_slot = 2;
+        assert_eq!(inventory.held_item(false), Some(&stone));
+        assert_eq!(inventory.held_item(true), None);
+
+        inventory.set_slot(Inventory::HOTBAR_START + 2, None);
+        assert_eq!(inventory.held_item(false), None);
+    }
+
+    #[test]
+    fn test_find_item() {
+        let mut inventory = Inventory::default();
+        inventory.set_slot(Inventory::MAIN_START + 4, Some(ItemStack::new(5, 2)));
+        inventory.set_slot(Inventory::HOTBAR_START + 7, Some(ItemStack::new(5, 1)));
+        assert_eq!(inventory.find_item(5), Some(Inventory::HOTBAR_START + 7));
+        assert_eq!(inventory.find_item(6), None);
+        // The offhand comes first
+        inventory.set_slot(Inventory::OFFHAND, Some(ItemStack::new(5, 1)));
+        assert_eq!(inventory.find_item(5), Some(Inventory::OFFHAND));
+
+        assert!(inventory.take_one(Inventory::OFFHAND));
+        assert!(!inventory.take_one(Inventory::OFFHAND));
+        assert!(inventory.take_one(Inventory::MAIN_START + 4));
+        assert_eq!(inventory.slots[&(Inventory::MAIN_START + 4)].count, 1);
+    }
+
+    #[test]
+    fn test_first_free_slot() {
+        let stone = ItemStack::new(1, 1);
+        let mut inventory = Inventory::default();
+        assert_eq!(inventory.first_free_slot(), Some(Inventory::HOTBAR_START));
+        for slot in Inventory::HOTBAR_START..Inventory::OFFHAND {
+            inventory.set_slot(slot, Some(stone.clone()));
+        }
+        assert_eq!(inventory.first_free_slot(), Some(Inventory::MAIN_START));
+        for slot in Inventory::MAIN_START..Inventory::HOTBAR_START {
+            inventory.set_slot(slot, Some(stone.clone()));
+        }
+        // Armor and the offhand are never picked
+        assert_eq!(inventory.first_free_slot(), None);
+
+        inventory.state_id = 0x7FFF;
+        assert_eq!(inventory.next_state_id(), 0);
+    }
+
+    #[test]
+    fn test_add_item() {
+        let mut inventory = Inventory::default();
+        inventory.set_slot(Inventory::HOTBAR_START + 3, Some(ItemStack::new(1, 60)));
+        inventory.set_slot(Inventory::HOTBAR_START, Some(ItemStack::new(2, 1)));
+
+        // Tops up the stack, then goes in the first empty slot
+        assert!(inventory.add_item(&ItemStack::new(1, 10), 64));
+        assert_eq!(inventory.slots[&(Inventory::HOTBAR_START + 3)].count, 64);
+        assert_eq!(inventory.slots[&(Inventory::HOTBAR_START + 1)], ItemStack::new(1, 6));
+
+        for slot in Inventory::storage_slots() {
+            inventory.slots.entry(slot).or_insert(ItemStack::new(3, 1));
+        }
+        assert!(!inventory.add_item(&ItemStack::new(1, 60), 64));
+        assert!(inventory.add_item(&ItemStack::new(1, 58), 64));
+        assert_eq!(inventory.slots[&(Inventory::HOTBAR_START + 1)].count, 64);
+    }
+
+    #[test]
+    fn test_add_as_much() {
+        let mut inventory = Inventory::default();
+        for slot in Inventory::storage_slots() {
+            inventory.set_slot(slot, Some(ItemStack::new(3, 1)));
+        }
+        inventory.set_slot(Inventory::MAIN_START, Some(ItemStack::new(1, 60)));
+        assert_eq!(inventory.add_as_much(&ItemStack::new(1, 10), 64), 4);
+        assert_eq!(inventory.slots[&Inventory::MAIN_START].count, 64);
+        assert_eq!(inventory.add_as_much(&ItemStack::new(1, 10), 64), 0);
+    }
+}
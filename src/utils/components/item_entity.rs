@@ -0,0 +1,86 @@
+use ferrumc_macros::Component;
+
+use crate::utils::encoding::metadata::{EntityMetadata, MetadataValue};
+use crate::utils::encoding::slot::{ItemStack, Slot};
+
+/// Where an item entity keeps its item in its metadata.
+const ITEM_INDEX: u8 = 8;
+
+/// An item lying on the ground, or flying through the air after being dropped. See
+/// [item_entities](crate::world::item_entities).
+#[derive(Debug, Clone, PartialEq, Component)]
+pub struct ItemEntity {
+    pub item: ItemStack,
+    /// Ticks since it was dropped, it despawns at [ItemEntity::DESPAWN_AGE].
+    pub age: u32,
+    /// Ticks until it can be picked up.
+    pub pickup_delay: u32,
+}
+
+impl ItemEntity {
+    /// Five minutes, same as vanilla.
+    pub const DESPAWN_AGE: u32 = 6000;
+    /// For items players drop, so they don't pick them straight back up.
+    pub const THROWN_PICKUP_DELAY: u32 = 40;
+
+    pub fn new(item: ItemStack, pickup_delay: u32) -> Self {
+        Self {
+            item,
+            age: 0,
+            pickup_delay,
+        }
+    }
+
+    pub fn metadata(&self) -> EntityMetadata {
+        let mut metadata = EntityMetadata::new();
+        let slot = Slot {
+            item: Some(self.item.clone()),
+        };
+        metadata.set(ITEM_INDEX, MetadataValue::Slot(slot));
+        metadata
+    }
+
+    /// Moves as much of `other`'s stack into this one as fits, if they're the same item. Only
+    /// the smaller stack goes into the bigger one, like vanilla. Returns whether anything moved.
+    pub fn merge(&mut self, other: &mut ItemEntity, max_stack_size: i8) -> bool {
+        if !self.item.stacks_with(&other.item) || other.item.count > self.item.count {
+            return false;
+        }
+        let moved = other.item.count.min(max_stack_size - self.item.count);
+        if moved <= 0 {
+            return false;
+        }
+        self.item.count += moved;
+        other.item.count -= moved;
+        // The merged item waits as long as either would have, and lives as long as the newer one
+        self.pickup_delay = self.pickup_delay.max(other.pickup_delay);
+        self.age = self.age.min(other.age);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge() {
+        let mut big = ItemEntity::new(ItemStack::new(1, 40), 0);
+        let mut small = ItemEntity {
+            age: 100,
+            ..ItemEntity::new(ItemStack::new(1, 30), 10)
+        };
+        // The smaller stack doesn't take the bigger one
+        assert!(!small.merge(&mut big, 64));
+        assert!(big.merge(&mut small, 64));
+        assert_eq!(big.item.count, 64);
+        assert_eq!(small.item.count, 6);
+        assert_eq!((big.age, big.pickup_delay), (0, 10));
+        // Full
+        assert!(!big.merge(&mut small, 64));
+
+        let mut dirt = ItemEntity::new(ItemStack::new(2, 1), 0);
+        assert!(!big.merge(&mut dirt, 64));
+        assert!(big.metadata().get(ITEM_INDEX).is_some());
+    }
+}
@@ -0,0 +1,11 @@
+use ferrumc_macros::{Component, Constructor, Getter};
+
+use crate::utils::components::rotation::Rotation;
+use crate::utils::encoding::position::Position;
+
+/// Where every other client last saw this entity. Movement is sent relative to this.
+#[derive(Debug, Component, Getter, Constructor, Clone)]
+pub struct LastBroadcastPosition {
+    pub position: Position,
+    pub rotation: Rotation,
+}
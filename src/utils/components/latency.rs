@@ -0,0 +1,42 @@
+use std::time::Duration;
+
+use ferrumc_macros::Component;
+
+/// How long a player's client takes to answer, measured from keep alives. Shown as the ping bars
+/// in the tab list.
+#[derive(Debug, Clone, Copy, Default, Component)]
+pub struct Latency {
+    /// In milliseconds.
+    pub millis: i32,
+    measured: bool,
+}
+
+impl Latency {
+    /// Adds a round trip. Each one only counts for a quarter, like vanilla, so one slow answer
+    /// doesn't make the bars jump.
+    pub fn record(&mut self, round_trip: Duration) {
+        let sample = round_trip.as_millis().min(i32::MAX as u128) as i32;
+        self.millis = if self.measured {
+            ((self.millis as i64 * 3 + sample as i64) / 4) as i32
+        } else {
+            sample
+        };
+        self.measured = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record() {
+        let mut latency = Latency::default();
+        latency.record(Duration::from_millis(100));
+        assert_eq!(latency.millis, 100);
+        latency.record(Duration::from_millis(500));
+        assert_eq!(latency.millis, 200);
+        latency.record(Duration::from_millis(200));
+        assert_eq!(latency.millis, 200);
+    }
+}
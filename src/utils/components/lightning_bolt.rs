@@ -0,0 +1,14 @@
+use ferrumc_macros::Component;
+
+/// A bolt of lightning, which only sticks around long enough for clients to see it. See
+/// [weather](crate::world::weather).
+#[derive(Debug, Clone, Copy, PartialEq, Default, Component)]
+pub struct LightningBolt {
+    /// Ticks since it struck, it's removed at [LightningBolt::LIFETIME].
+    pub age: u32,
+}
+
+impl LightningBolt {
+    /// Long enough for the flash, clients fade it out by themselves.
+    pub const LIFETIME: u32 = 10;
+}
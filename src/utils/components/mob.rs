@@ -0,0 +1,26 @@
+use ferrumc_macros::Component;
+
+use crate::world::mobs::MobType;
+
+/// An animal or monster, moved around by its AI. See [mobs](crate::world::mobs).
+#[derive(Debug, Clone, Component)]
+pub struct Mob {
+    pub mob_type: &'static MobType,
+    /// The x and z it's walking to, if it's going anywhere.
+    pub target: Option<(f64, f64)>,
+    /// Ticks it's been walking towards its target for.
+    pub walking_for: u32,
+    /// Ticks since it died, if it has.
+    pub dead_for: Option<u32>,
+}
+
+impl Mob {
+    pub fn new(mob_type: &'static MobType) -> Self {
+        Self {
+            mob_type,
+            target: None,
+            walking_for: 0,
+            dead_for: None,
+        }
+    }
+}
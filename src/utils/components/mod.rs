@@ -1,5 +1,29 @@
+pub mod abilities;
+pub mod attack_cooldown;
+pub mod body;
+pub mod chunk_tracker;
+pub mod dimension;
+pub mod display_name;
+pub mod entity_state;
+pub mod experience;
+pub mod experience_orb;
+pub mod food;
+pub mod game_mode;
 pub mod grounded;
+pub mod health;
+pub mod inventory;
+pub mod item_entity;
 pub mod keep_alive;
-pub mod last_chunk_tx_pos;
+pub mod last_broadcast_position;
+pub mod latency;
+pub mod lightning_bolt;
+pub mod mob;
+pub mod motion;
+pub mod object_entity;
+pub mod open_container;
 pub mod player;
+pub mod projectile;
 pub mod rotation;
+pub mod sign_editor;
+pub mod using_item;
+pub mod visible_entities;
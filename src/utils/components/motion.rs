@@ -0,0 +1,30 @@
+use ferrumc_macros::Component;
+
+/// Where a non-player entity is, to a fraction of a block, and how fast it's moving. Players
+/// don't have one, their clients move them.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Component)]
+pub struct Motion {
+    pub position: (f64, f64, f64),
+    /// In blocks per tick.
+    pub velocity: (f64, f64, f64),
+    pub on_ground: bool,
+    /// Whether it ran into something to its side last tick.
+    pub blocked: bool,
+}
+
+impl Motion {
+    pub fn new(position: (f64, f64, f64), velocity: (f64, f64, f64)) -> Self {
+        Self {
+            position,
+            velocity,
+            on_ground: false,
+            blocked: false,
+        }
+    }
+
+    /// The block it's in.
+    pub fn block(&self) -> (i32, i32, i32) {
+        let (x, y, z) = self.position;
+        (x.floor() as i32, y.floor() as i32, z.floor() as i32)
+    }
+}
@@ -0,0 +1,35 @@
+use ferrumc_macros::Component;
+
+use crate::utils::encoding::metadata::EntityMetadata;
+
+/// An entity that isn't a player, which clients are sent with
+/// [SpawnEntity](crate::net::packets::outgoing::spawn_entity::SpawnEntity). It moves by its
+/// [Motion](crate::utils::components::motion::Motion).
+#[derive(Debug, Clone, Component)]
+pub struct ObjectEntity {
+    pub uuid: u128,
+    /// From [entity_types](crate::net::packets::outgoing::spawn_entity::entity_types).
+    pub entity_type: i32,
+    /// Sent when it's spawned. What it means depends on the type.
+    pub data: i32,
+    /// Sent when it's spawned. Changes after that have to be sent to everyone that can see it too.
+    pub metadata: EntityMetadata,
+    /// Where clients were last told it is. Movement is sent relative to this.
+    pub last_broadcast: Option<(f64, f64, f64)>,
+    /// The yaw and pitch clients were last told it's facing, for entities with a
+    /// [Rotation](crate::utils::components::rotation::Rotation).
+    pub last_rotation: (f32, f32),
+}
+
+impl ObjectEntity {
+    pub fn new(entity_type: i32, metadata: EntityMetadata) -> Self {
+        Self {
+            uuid: rand::random(),
+            entity_type,
+            data: 0,
+            metadata,
+            last_broadcast: None,
+            last_rotation: (0.0, 0.0),
+        }
+    }
+}
@@ -0,0 +1,15 @@
+use ferrumc_macros::Component;
+
+use crate::utils::encoding::position::Position;
+use crate::world::containers::ContainerType;
+
+/// The container window a player has open, see
+/// [open_container](crate::world::containers::open_container).
+#[derive(Debug, Component)]
+pub struct OpenContainer {
+    pub window_id: u8,
+    /// Where the container is.
+    pub position: Position,
+    pub dimension: String,
+    pub container: &'static ContainerType,
+}
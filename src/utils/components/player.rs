@@ -1,9 +1,22 @@
 use ferrumc_macros::{Component, Constructor};
 
+use crate::net::packets::outgoing::clear_titles::ClearTitles;
+use crate::net::packets::outgoing::login_success::Property;
+use crate::net::packets::outgoing::set_action_bar_text::SetActionBarText;
+use crate::net::packets::outgoing::set_subtitle_text::SetSubtitleText;
+use crate::net::packets::outgoing::set_title_animation_times::SetTitleAnimationTimes;
+use crate::net::packets::outgoing::set_title_text::SetTitleText;
+use crate::net::packets::ConnectionId;
+use crate::state::GlobalState;
+use crate::utils::prelude::*;
+use crate::utils::text_component::TextComponent;
+
 #[derive(Component, Constructor, Debug)]
 pub struct Player {
     pub uuid: u128,
     pub username: String,
+    /// The player's skin and cape, sent to everyone who sees them.
+    pub properties: Vec<Property>,
 }
 
 impl Player {
@@ -14,4 +27,58 @@ impl Player {
     pub fn get_username(&self) -> &str {
         &self.username
     }
+
+    /// Shows a title in the middle of a player's screen, with a subtitle below it if there is one.
+    /// It fades in and out with whatever times were last set, see [Player::set_title_times].
+    pub async fn send_title(
+        state: &GlobalState,
+        conn_id: ConnectionId,
+        title: &TextComponent,
+        subtitle: Option<&TextComponent>,
+    ) -> Result<()> {
+        let conn = state.connections.get_connection(conn_id)?;
+        let conn = conn.read().await;
+        // Otherwise the last one would show again
+        let subtitle = subtitle.cloned().unwrap_or_else(|| TextComponent::text(""));
+        conn.send_packet(SetSubtitleText::new(&subtitle)).await?;
+        conn.send_packet(SetTitleText::new(title)).await
+    }
+
+    /// Changes how long a player's titles take to fade in, stay up and fade out, in ticks.
+    /// Vanilla starts them at 10, 70 and 20.
+    pub async fn set_title_times(
+        state: &GlobalState,
+        conn_id: ConnectionId,
+        fade_in: i32,
+        stay: i32,
+        fade_out: i32,
+    ) -> Result<()> {
+        let conn = state.connections.get_connection(conn_id)?;
+        let conn = conn.read().await;
+        conn.send_packet(SetTitleAnimationTimes::new_auto(fade_in, stay, fade_out))
+            .await
+    }
+
+    /// Takes down a player's title. With `reset`, their subtitle and times go back to the
+    /// defaults as well.
+    pub async fn clear_title(
+        state: &GlobalState,
+        conn_id: ConnectionId,
+        reset: bool,
+    ) -> Result<()> {
+        let conn = state.connections.get_connection(conn_id)?;
+        let conn = conn.read().await;
+        conn.send_packet(ClearTitles::new_auto(reset)).await
+    }
+
+    /// Shows text above a player's hotbar for a few seconds.
+    pub async fn send_action_bar(
+        state: &GlobalState,
+        conn_id: ConnectionId,
+        text: &TextComponent,
+    ) -> Result<()> {
+        let conn = state.connections.get_connection(conn_id)?;
+        let conn = conn.read().await;
+        conn.send_packet(SetActionBarText::new(text)).await
+    }
 }
@@ -0,0 +1,33 @@
+use ferrumc_macros::Component;
+
+use crate::world::projectiles::ProjectileKind;
+
+/// Something shot or thrown, like an arrow. See [projectiles](crate::world::projectiles).
+#[derive(Debug, Clone, Copy, PartialEq, Component)]
+pub struct Projectile {
+    pub kind: ProjectileKind,
+    /// Whoever shot or threw it, if anyone did.
+    pub shooter: Option<usize>,
+    /// Ticks since it was shot.
+    pub age: u32,
+    /// Critical arrows, from a fully drawn bow, leave a trail and do more damage.
+    pub critical: bool,
+    /// Ticks since it stuck in a block, if it has. Only arrows stick, anything else breaks.
+    pub stuck_for: Option<u32>,
+    /// Whether players can pick it back up once it's stuck, which they can't for arrows shot in
+    /// creative.
+    pub pickup: bool,
+}
+
+impl Projectile {
+    pub fn new(kind: ProjectileKind, shooter: Option<usize>) -> Self {
+        Self {
+            kind,
+            shooter,
+            age: 0,
+            critical: false,
+            stuck_for: None,
+            pickup: false,
+        }
+    }
+}
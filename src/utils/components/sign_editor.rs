@@ -0,0 +1,11 @@
+use ferrumc_macros::Component;
+
+use crate::utils::encoding::position::Position;
+
+/// The sign a player has been sent the editor for, the only one they can change the text of. See
+/// [open_sign_editor](crate::world::signs::open_sign_editor).
+#[derive(Debug, Component)]
+pub struct SignEditor {
+    pub position: Position,
+    pub dimension: String,
+}
@@ -0,0 +1,11 @@
+use std::time::Instant;
+
+use ferrumc_macros::Component;
+
+/// An item a player is holding right click with, like a bow they're drawing. It's used once they
+/// let go.
+#[derive(Debug, Clone, Copy, Component)]
+pub struct UsingItem {
+    pub since: Instant,
+    pub offhand: bool,
+}
@@ -0,0 +1,9 @@
+use std::collections::HashSet;
+
+use ferrumc_macros::{Component, Getter};
+
+/// The entities a player's client currently has spawned.
+#[derive(Debug, Component, Getter, Default)]
+pub struct VisibleEntities {
+    pub entities: HashSet<usize>,
+}
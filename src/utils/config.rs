@@ -1,32 +1,172 @@
 use std::io::ErrorKind::NotFound;
 use std::io::Write;
-use std::sync::OnceLock;
+use std::sync::{Arc, OnceLock};
 
+use crate::net::utils::forwarding::Forwarding;
+#[cfg(feature = "wasm")]
+use crate::plugins::wasm::Capability;
 use crate::utils::constants::{
-    DEFAULT_CONFIG_FILE, DEFAULT_MAX_PLAYERS, DEFAULT_MOTD, DEFAULT_SERVER_HOST,
-    DEFAULT_SERVER_PORT,
+    DEFAULT_CHAT_FORMAT, DEFAULT_COMPRESSION_THRESHOLD, DEFAULT_CONFIG_FILE, DEFAULT_CREATURE_CAP,
+    DEFAULT_FLUSH_INTERVAL, DEFAULT_FLYING_SPEED, DEFAULT_LOGIN_INTERVAL,
+    DEFAULT_MAX_CONNECTIONS_PER_MINUTE, DEFAULT_MAX_PACKETS_PER_SECOND, DEFAULT_MAX_PLAYERS,
+    DEFAULT_METRICS_PORT, DEFAULT_MONSTER_CAP, DEFAULT_MOTD, DEFAULT_OP_PERMISSION_LEVEL,
+    DEFAULT_PLAYER_SAVE_INTERVAL, DEFAULT_SCRIPT_MAX_OPERATIONS, DEFAULT_SERVER_HOST,
+    DEFAULT_SERVER_PORT, DEFAULT_SHUTDOWN_MESSAGE, DEFAULT_SKIN_CACHE_DURATION,
+    DEFAULT_VIEW_DISTANCE,
 };
+#[cfg(feature = "wasm")]
+use crate::utils::constants::{DEFAULT_WASM_FUEL, DEFAULT_WASM_MEMORY};
 use crate::utils::error::Error;
 use config::{Config, ConfigError};
+use parking_lot::RwLock;
 use serde::{Deserialize, Serialize};
 use tracing::{error, info};
 use crate::setup::BASE_CONFIG;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ServerConfig {
     pub host: String,
     pub port: u32,
     pub motd: Vec<String>,
     pub max_players: i32,
+    pub shutdown_message: String,
     pub network_tick_rate: u32,
+    pub network_compression_threshold: i32,
+    pub online_mode: bool,
+    pub enable_query: bool,
+    pub query_port: u32,
+    /// Whether [metrics](crate::net::metrics) are served for Prometheus.
+    pub enable_metrics: bool,
+    pub metrics_port: u32,
+    pub whitelist: bool,
+    pub op_permission_level: u8,
+    pub view_distance: u32,
+    pub chat_format: String,
     pub database: Database,
+    pub generation: Generation,
+    pub proxy: Proxy,
+    pub throttle: Throttle,
+    pub skins: Skins,
+    pub flight: Flight,
+    pub mobs: Mobs,
+    pub weather: Weather,
+    pub server_info: ServerInfo,
+    pub plugins: Plugins,
+    pub scripting: Scripting,
     pub world: String,
+    pub import_path: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Database {
+    /// Where chunks are kept, see [WorldStorage](crate::database::storage::WorldStorage).
+    pub backend: String,
     pub cache_size: u32,
     pub compression: String,
+    /// How often changed chunks get written to disk, in seconds.
+    pub flush_interval: u64,
+    /// How often the online players' data gets saved, in seconds.
+    pub player_save_interval: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Generation {
+    pub generator: String,
+    pub seed: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Proxy {
+    /// How the proxy in front of the server passes on players' addresses and profiles, see
+    /// [Forwarding](crate::net::utils::forwarding::Forwarding).
+    pub forwarding: String,
+    /// The secret shared with Velocity, which its forwarded data is signed with.
+    pub secret: String,
+    /// Whether connections start with a PROXY protocol header, see
+    /// [proxy_protocol](crate::net::proxy_protocol).
+    pub proxy_protocol: bool,
+}
+
+/// See [throttle](crate::net::throttle). 0 turns a limit off.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Throttle {
+    /// How long one address has to wait between logins, in milliseconds.
+    pub login_interval: u64,
+    /// How many times one address can connect in a minute, counting server list pings.
+    pub max_connections_per_minute: u32,
+    /// How many packets one connection can send in a second.
+    pub max_packets_per_second: u32,
+}
+
+/// See [SkinCache](crate::net::utils::skins::SkinCache).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Skins {
+    /// Whether players in offline mode get the skin of the account with their name.
+    pub fetch: bool,
+    /// How long a looked up skin is kept, in seconds.
+    pub cache_duration: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Flight {
+    /// Whether players that can't fly in their game mode may do so anyway, instead of being kicked
+    /// for floating.
+    pub allowed: bool,
+    /// How fast players fly, given to them when their abilities are sent.
+    pub speed: f32,
+}
+
+/// See [mob_spawning](crate::world::mob_spawning).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Mobs {
+    /// Whether mobs spawn around players on their own.
+    pub natural_spawning: bool,
+    /// The most monsters there can be around a single player.
+    pub monster_cap: u32,
+    /// The most animals there can be around a single player.
+    pub creature_cap: u32,
+}
+
+/// See [weather](crate::world::weather).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Weather {
+    /// Whether lightning strikes around players during thunderstorms.
+    pub lightning: bool,
+}
+
+/// What joined clients are told about the server, see
+/// [ServerData](crate::net::packets::outgoing::server_data::ServerData).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ServerInfo {
+    /// Whether clients are told chat is signed and checked, which hides their warning that it
+    /// can't be verified.
+    pub enforce_secure_chat: bool,
+}
+
+/// Which kinds of [plugins](crate::plugins) get loaded, and what WebAssembly ones can do. The
+/// WebAssembly settings are only there with the `wasm` feature.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Plugins {
+    pub native: bool,
+    #[cfg(feature = "wasm")]
+    pub wasm: bool,
+    /// See [Capability](crate::plugins::wasm::Capability).
+    #[cfg(feature = "wasm")]
+    pub wasm_capabilities: Vec<String>,
+    /// Roughly how many instructions a WebAssembly plugin can run each time it's called.
+    #[cfg(feature = "wasm")]
+    pub wasm_fuel: u64,
+    /// How much memory each WebAssembly plugin can have, in MB.
+    #[cfg(feature = "wasm")]
+    pub wasm_memory: usize,
+}
+
+/// See [scripting](crate::scripting).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Scripting {
+    pub enabled: bool,
+    /// How many operations a script can run before it's stopped.
+    pub max_operations: u64,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -75,8 +215,58 @@ impl ServerConfig {
             Err(Error::from(e))
         })?;
 
+        de_settings.validate()?;
         Ok(de_settings)
     }
+
+    /// Load the config file as it is now, without creating it or asking about missing fields.
+    /// Used when reloading, where there's no one at the console to answer.
+    pub fn load() -> Result<Self, Error> {
+        let config: ServerConfig = Config::builder()
+            .add_source(config::File::with_name("config"))
+            .build()?
+            .try_deserialize()?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Checks for settings that deserialize fine but can't be run with.
+    pub fn validate(&self) -> Result<(), Error> {
+        let forwarding = Forwarding::from_config(&self.proxy.forwarding);
+        if forwarding == Forwarding::Velocity && self.proxy.secret.is_empty() {
+            return Err(Error::Generic(
+                "Velocity forwarding is enabled, but no proxy secret is set".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// The settings that differ from `other` but only take effect after a restart.
+    pub fn restart_required(&self, other: &ServerConfig) -> Vec<&'static str> {
+        let mut changed = Vec::new();
+        if self.host != other.host {
+            changed.push("host");
+        }
+        if self.port != other.port {
+            changed.push("port");
+        }
+        if self.enable_query != other.enable_query || self.query_port != other.query_port {
+            changed.push("query");
+        }
+        if self.enable_metrics != other.enable_metrics || self.metrics_port != other.metrics_port {
+            changed.push("metrics");
+        }
+        if self.world != other.world {
+            changed.push("world");
+        }
+        if self.database != other.database {
+            changed.push("database");
+        }
+        if self.generation != other.generation {
+            changed.push("generation");
+        }
+        changed
+    }
 }
 
 /// Check if the error is a not found error
@@ -127,18 +317,125 @@ impl Default for ServerConfig {
             port: DEFAULT_SERVER_PORT,
             motd: vec![DEFAULT_MOTD.to_string()],
             max_players: DEFAULT_MAX_PLAYERS as i32,
+            shutdown_message: DEFAULT_SHUTDOWN_MESSAGE.to_string(),
             network_tick_rate: 0,
+            network_compression_threshold: DEFAULT_COMPRESSION_THRESHOLD,
+            online_mode: false,
+            enable_query: false,
+            query_port: DEFAULT_SERVER_PORT,
+            enable_metrics: false,
+            metrics_port: DEFAULT_METRICS_PORT,
+            whitelist: false,
+            op_permission_level: DEFAULT_OP_PERMISSION_LEVEL,
+            view_distance: DEFAULT_VIEW_DISTANCE,
+            chat_format: DEFAULT_CHAT_FORMAT.to_string(),
             world: "world".to_string(),
+            import_path: String::new(),
             database: Database {
-                cache_size: 1024,
+                backend: "lmdb".to_string(),
+                cache_size: 65536,
                 compression: "fast".to_string(),
+                flush_interval: DEFAULT_FLUSH_INTERVAL,
+                player_save_interval: DEFAULT_PLAYER_SAVE_INTERVAL,
+            },
+            generation: Generation {
+                generator: "multi_noise".to_string(),
+                seed: 0,
+            },
+            proxy: Proxy {
+                forwarding: "none".to_string(),
+                secret: String::new(),
+                proxy_protocol: false,
+            },
+            throttle: Throttle {
+                login_interval: DEFAULT_LOGIN_INTERVAL,
+                max_connections_per_minute: DEFAULT_MAX_CONNECTIONS_PER_MINUTE,
+                max_packets_per_second: DEFAULT_MAX_PACKETS_PER_SECOND,
+            },
+            skins: Skins {
+                fetch: true,
+                cache_duration: DEFAULT_SKIN_CACHE_DURATION,
+            },
+            flight: Flight {
+                allowed: false,
+                speed: DEFAULT_FLYING_SPEED,
+            },
+            mobs: Mobs {
+                natural_spawning: true,
+                monster_cap: DEFAULT_MONSTER_CAP,
+                creature_cap: DEFAULT_CREATURE_CAP,
+            },
+            weather: Weather { lightning: true },
+            server_info: ServerInfo {
+                enforce_secure_chat: false,
+            },
+            plugins: Plugins {
+                native: true,
+                #[cfg(feature = "wasm")]
+                wasm: true,
+                #[cfg(feature = "wasm")]
+                wasm_capabilities: Capability::ALL
+                    .iter()
+                    .map(|capability| capability.name().to_string())
+                    .collect(),
+                #[cfg(feature = "wasm")]
+                wasm_fuel: DEFAULT_WASM_FUEL,
+                #[cfg(feature = "wasm")]
+                wasm_memory: DEFAULT_WASM_MEMORY,
+            },
+            scripting: Scripting {
+                enabled: true,
+                max_operations: DEFAULT_SCRIPT_MAX_OPERATIONS,
             },
         }
     }
 }
 
+fn global_config() -> &'static RwLock<Arc<ServerConfig>> {
+    static CONFIG: OnceLock<RwLock<Arc<ServerConfig>>> = OnceLock::new();
+    CONFIG.get_or_init(|| RwLock::new(Arc::new(ServerConfig::new().expect("Failed to load config"))))
+}
+
 /// Get the global server configuration
-pub fn get_global_config() -> &'static ServerConfig {
-    static CONFIG: OnceLock<ServerConfig> = OnceLock::new();
-    CONFIG.get_or_init(|| ServerConfig::new().expect("Failed to load config"))
+///
+/// This is a snapshot, so read it again rather than holding on to it if you want to see reloads.
+pub fn get_global_config() -> Arc<ServerConfig> {
+    Arc::clone(&global_config().read())
+}
+
+/// Read the config file again and make it the global config. Returns the old and the new config.
+///
+/// See [reload_config](crate::events::config_events::reload_config), which also lets the rest of
+/// the server know.
+pub fn reload_global_config() -> Result<(Arc<ServerConfig>, Arc<ServerConfig>), Error> {
+    let new = Arc::new(ServerConfig::load()?);
+    let old = std::mem::replace(&mut *global_config().write(), Arc::clone(&new));
+    Ok((old, new))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_restart_required() {
+        let old = ServerConfig::default();
+        let mut new = old.clone();
+        new.motd = vec!["Something else".to_string()];
+        new.view_distance = 4;
+        assert!(new.restart_required(&old).is_empty());
+
+        new.port += 1;
+        new.database.cache_size *= 2;
+        assert_eq!(new.restart_required(&old), vec!["port", "database"]);
+    }
+
+    #[test]
+    fn test_velocity_needs_a_secret() {
+        let mut config = ServerConfig::default();
+        config.proxy.forwarding = "velocity".to_string();
+        assert!(config.validate().is_err());
+        config.proxy.secret = "hunter2".to_string();
+        assert!(config.validate().is_ok());
+    }
 }
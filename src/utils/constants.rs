@@ -1,10 +1,80 @@
 pub const DEFAULT_LOG_LEVEL: &str = "debug";
 pub const DEFAULT_CONFIG_FILE: &str = "config.toml";
+pub const WHITELIST_FILE: &str = "whitelist.json";
+pub const BANNED_PLAYERS_FILE: &str = "banned-players.json";
+pub const BANNED_IPS_FILE: &str = "banned-ips.json";
+pub const OPS_FILE: &str = "ops.json";
+/// The vanilla data generator's registry report, see [ItemRegistry](crate::world::items::ItemRegistry).
+pub const ITEM_REGISTRY_FILE: &str = "registries.json";
+/// Replacements and additions to the registries sent to clients, see
+/// [registry_data](crate::world::registry_data).
+pub const REGISTRY_DATA_DIRECTORY: &str = "registry_data";
+/// The recipes and item tags from the vanilla server's data, see
+/// [recipes](crate::world::recipes).
+pub const RECIPE_DATA_DIRECTORY: &str = "recipe_data";
+/// Where player data is saved, in the world's directory.
+pub const PLAYER_DATA_DIRECTORY: &str = "playerdata";
+/// Where [plugins](crate::plugins) are loaded from.
+pub const PLUGINS_DIRECTORY: &str = "plugins";
+/// Where [scripts](crate::scripting) are run from when the server starts.
+pub const SCRIPTS_DIRECTORY: &str = "scripts";
+/// Where `/profile` saves its reports, see [profiler](crate::net::profiler).
+pub const PROFILES_DIRECTORY: &str = "profiles";
+/// Where `--capture` records connections to, see [capture](crate::net::capture).
+pub const CAPTURES_DIRECTORY: &str = "captures";
 pub const DEFAULT_SERVER_HOST: &str = "localhost";
 // Default port for a Minecraft server
 pub const DEFAULT_SERVER_PORT: u32 = 25565;
+// Prometheus' default port for Minecraft server exporters.
+pub const DEFAULT_METRICS_PORT: u32 = 9225;
 pub const DEFAULT_MOTD: &str = "A FerrumC Server";
 pub const DEFAULT_MAX_PLAYERS: u32 = 20;
+pub const DEFAULT_SHUTDOWN_MESSAGE: &str = "Server closed";
+// Packets at least this many bytes long get compressed. Same as vanilla.
+pub const DEFAULT_COMPRESSION_THRESHOLD: i32 = 256;
+// In chunks. Clients with a lower view distance get sent less.
+pub const DEFAULT_VIEW_DISTANCE: u32 = 10;
+pub const DEFAULT_CHAT_FORMAT: &str = "<{username}> {message}";
+// The level /op gives, which is every permission. Same as vanilla.
+pub const DEFAULT_OP_PERMISSION_LEVEL: u8 = 4;
+// In seconds. How long changed chunks wait before being written to the database.
+pub const DEFAULT_FLUSH_INTERVAL: u64 = 5;
+// In seconds. How often the online players' data is saved, on top of when they leave.
+pub const DEFAULT_PLAYER_SAVE_INTERVAL: u64 = 60;
+// In milliseconds. Same as Bukkit's connection throttle.
+pub const DEFAULT_LOGIN_INTERVAL: u64 = 4000;
+pub const DEFAULT_MAX_CONNECTIONS_PER_MINUTE: u32 = 30;
+pub const DEFAULT_MAX_PACKETS_PER_SECOND: u32 = 500;
+// In seconds. Skins rarely change, and Mojang's API limits how often it's asked.
+pub const DEFAULT_SKIN_CACHE_DURATION: u64 = 3600;
+// Same as vanilla.
+pub const DEFAULT_FLYING_SPEED: f32 = 0.05;
+// Same as vanilla.
+pub const DEFAULT_MONSTER_CAP: u32 = 70;
+pub const DEFAULT_CREATURE_CAP: u32 = 10;
+// Fuel is roughly one per WebAssembly instruction.
+pub const DEFAULT_WASM_FUEL: u64 = 10_000_000;
+// In MB.
+pub const DEFAULT_WASM_MEMORY: usize = 64;
+// Enough for a script to edit a good few thousand blocks.
+pub const DEFAULT_SCRIPT_MAX_OPERATIONS: u64 = 1_000_000;
+
+/// The most a client can send, so a packet can't make the server allocate as much as it likes.
+pub mod limits {
+    /// In bytes, the most a 3 byte VarInt can hold. Same as vanilla.
+    pub const MAX_PACKET_LENGTH: usize = 2097151;
+    /// In bytes, for compressed packets once they're decompressed. Same as vanilla.
+    pub const MAX_DECOMPRESSED_LENGTH: usize = ferrumc_codec::dec::MAX_DECOMPRESSED_LENGTH;
+    /// In characters. Strings are sent as UTF-8, so they can take up to 3 times as many bytes.
+    pub const MAX_STRING_LENGTH: usize = 32767;
+    /// How deeply compounds and lists can be nested in NBT, e.g. an item's. Same as vanilla.
+    pub const MAX_NBT_DEPTH: usize = 512;
+    /// In characters, for each line of a sign. Same as vanilla.
+    pub const MAX_SIGN_LINE_LENGTH: usize = 384;
+    /// How far away blocks and entities can be reached, squared. A bit further than vanilla's 6
+    /// blocks, since positions are rounded to blocks.
+    pub const MAX_REACH_SQUARED: i64 = 49;
+}
 
 pub mod init {
     pub const DEFAULT_SPAWN_X_POS: i32 = 0;
@@ -12,4 +82,6 @@ pub mod init {
     pub const DEFAULT_SPAWN_Z_POS: i32 = 0;
     pub const DEFAULT_SPAWN_YAW: f32 = 0.0;
     pub const DEFAULT_SPAWN_PITCH: f32 = 0.0;
+    /// Creative, see [GameMode](crate::utils::components::game_mode::GameMode).
+    pub const DEFAULT_GAME_MODE: u8 = 1;
 }
@@ -0,0 +1,229 @@
+//! Entity metadata, the typed values that decide how an entity looks, e.g. whether it's sneaking
+//! or glowing.
+//!
+//! Each entry is its index, the type of its value, then the value. Which index means what depends
+//! on the entity's type, the ones every entity has are in [indices].
+
+use std::io::Cursor;
+
+use tokio::io::AsyncWrite;
+
+use ferrumc_codec::enc::NetEncode;
+use ferrumc_codec::network_types::varint::VarInt;
+
+use crate::net::protocol::Protocol;
+use crate::utils::encoding::slot::{copy_bytes, copy_varint, network_slot, Slot};
+use crate::utils::error::Error;
+use crate::utils::text_component::TextComponent;
+
+/// Marks the end of the entries.
+const END: u8 = 0xFF;
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct EntityMetadata {
+    entries: Vec<(u8, MetadataValue)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MetadataValue {
+    Byte(i8),
+    VarInt(i32),
+    Float(f32),
+    String(String),
+    TextComponent(TextComponent),
+    OptionalTextComponent(Option<TextComponent>),
+    Slot(Slot),
+    Boolean(bool),
+    Pose(Pose),
+}
+
+/// How an entity is standing, which sets its hitbox and animation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Pose {
+    #[default]
+    Standing = 0,
+    FallFlying = 1,
+    Sleeping = 2,
+    Swimming = 3,
+    SpinAttack = 4,
+    Sneaking = 5,
+    LongJumping = 6,
+    Dying = 7,
+}
+
+/// The indices every entity has.
+pub mod indices {
+    /// A byte of [flags](super::flags).
+    pub const FLAGS: u8 = 0;
+    pub const AIR_TICKS: u8 = 1;
+    pub const CUSTOM_NAME: u8 = 2;
+    pub const CUSTOM_NAME_VISIBLE: u8 = 3;
+    pub const SILENT: u8 = 4;
+    pub const NO_GRAVITY: u8 = 5;
+    pub const POSE: u8 = 6;
+}
+
+pub mod flags {
+    pub const ON_FIRE: i8 = 0x01;
+    pub const SNEAKING: i8 = 0x02;
+    pub const SPRINTING: i8 = 0x08;
+    pub const SWIMMING: i8 = 0x10;
+    pub const INVISIBLE: i8 = 0x20;
+    pub const GLOWING: i8 = 0x40;
+    pub const FALL_FLYING: i8 = 0x80u8 as i8;
+}
+
+impl MetadataValue {
+    /// The value's type in the protocol.
+    fn type_id(&self) -> i32 {
+        match self {
+            MetadataValue::Byte(_) => 0,
+            MetadataValue::VarInt(_) => 1,
+            MetadataValue::Float(_) => 3,
+            MetadataValue::String(_) => 4,
+            MetadataValue::TextComponent(_) => 5,
+            MetadataValue::OptionalTextComponent(_) => 6,
+            MetadataValue::Slot(_) => 7,
+            MetadataValue::Boolean(_) => 8,
+            MetadataValue::Pose(_) => 20,
+        }
+    }
+}
+
+impl EntityMetadata {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the value at an index, replacing what was there.
+    pub fn set(&mut self, index: u8, value: MetadataValue) -> &mut Self {
+        match self.entries.iter_mut().find(|(i, _)| *i == index) {
+            Some(entry) => entry.1 = value,
+            None => self.entries.push((index, value)),
+        }
+        self
+    }
+
+    pub fn get(&self, index: u8) -> Option<&MetadataValue> {
+        self.entries
+            .iter()
+            .find(|(i, _)| *i == index)
+            .map(|(_, value)| value)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl NetEncode for EntityMetadata {
+    async fn net_encode<T>(&self, bytes: &mut T) -> Result<(), ferrumc_codec::CodecError>
+    where
+        T: AsyncWrite + Unpin,
+    {
+        for (index, value) in &self.entries {
+            index.net_encode(bytes).await?;
+            value.net_encode(bytes).await?;
+        }
+        END.net_encode(bytes).await
+    }
+}
+
+impl NetEncode for MetadataValue {
+    /// The type, then the value.
+    async fn net_encode<T>(&self, bytes: &mut T) -> Result<(), ferrumc_codec::CodecError>
+    where
+        T: AsyncWrite + Unpin,
+    {
+        VarInt::new(self.type_id()).net_encode(bytes).await?;
+        match self {
+            MetadataValue::Byte(value) => value.net_encode(bytes).await,
+            MetadataValue::VarInt(value) => VarInt::new(*value).net_encode(bytes).await,
+            MetadataValue::Float(value) => value.net_encode(bytes).await,
+            MetadataValue::String(value) => value.net_encode(bytes).await,
+            MetadataValue::TextComponent(value) => value.to_json().net_encode(bytes).await,
+            MetadataValue::OptionalTextComponent(value) => {
+                value.is_some().net_encode(bytes).await?;
+                match value {
+                    Some(value) => value.to_json().net_encode(bytes).await,
+                    None => Ok(()),
+                }
+            }
+            MetadataValue::Slot(slot) => slot.net_encode(bytes).await,
+            MetadataValue::Boolean(value) => value.net_encode(bytes).await,
+            MetadataValue::Pose(pose) => VarInt::new(*pose as i32).net_encode(bytes).await,
+        }
+    }
+}
+
+/// Copies encoded metadata with the NBT in its slots the way `protocol` sends it, see
+/// [network_slots](crate::utils::encoding::slot::network_slots).
+pub(crate) async fn network_metadata(
+    cursor: &mut Cursor<&[u8]>,
+    out: &mut Vec<u8>,
+    protocol: &Protocol,
+) -> Result<(), Error> {
+    loop {
+        copy_bytes(cursor, out, 1)?;
+        if out.last() == Some(&END) {
+            return Ok(());
+        }
+        match copy_varint(cursor, out).await? {
+            0 | 8 => copy_bytes(cursor, out, 1)?,
+            1 | 20 => {
+                copy_varint(cursor, out).await?;
+            }
+            3 => copy_bytes(cursor, out, 4)?,
+            4 | 5 => copy_string(cursor, out).await?,
+            6 => {
+                copy_bytes(cursor, out, 1)?;
+                if out.last() == Some(&1) {
+                    copy_string(cursor, out).await?;
+                }
+            }
+            7 => network_slot(cursor, out, protocol).await?,
+            type_id => {
+                return Err(Error::Generic(format!(
+                    "Can't copy metadata of type {type_id}"
+                )))
+            }
+        }
+    }
+}
+
+async fn copy_string(cursor: &mut Cursor<&[u8]>, out: &mut Vec<u8>) -> Result<(), Error> {
+    let length = copy_varint(cursor, out).await?;
+    copy_bytes(cursor, out, length.max(0) as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_encode() {
+        let mut metadata = EntityMetadata::new();
+        metadata
+            .set(indices::FLAGS, MetadataValue::Byte(flags::SNEAKING))
+            .set(indices::POSE, MetadataValue::Pose(Pose::Sneaking))
+            .set(indices::CUSTOM_NAME, MetadataValue::OptionalTextComponent(None))
+            .set(indices::FLAGS, MetadataValue::Byte(flags::SNEAKING | flags::GLOWING));
+        assert_eq!(
+            metadata.get(indices::FLAGS),
+            Some(&MetadataValue::Byte(0x42))
+        );
+
+        let mut bytes = Vec::new();
+        metadata.net_encode(&mut bytes).await.unwrap();
+        assert_eq!(bytes, vec![0, 0, 0x42, 6, 20, 5, 2, 6, 0, 0xFF]);
+
+        let mut bytes = Vec::new();
+        let name = MetadataValue::OptionalTextComponent(Some(TextComponent::text("a")));
+        name.net_encode(&mut bytes).await.unwrap();
+        assert_eq!(bytes, [&[6, 1, 12][..], br#"{"text":"a"}"#].concat());
+
+        let mut bytes = Vec::new();
+        EntityMetadata::new().net_encode(&mut bytes).await.unwrap();
+        assert_eq!(bytes, vec![0xFF]);
+    }
+}
@@ -9,7 +9,7 @@ use ferrumc_macros::Component;
 ///
 /// Check out the [Position::net_encode] and [Position::net_decode]
 /// implementations for more information on how this struct is encoded and decoded
-#[derive(Clone, Component, Debug)]
+#[derive(Clone, Component, Debug, PartialEq, Eq)]
 pub struct Position {
     // Encoded as a 26 bit int
     pub x: i32,
@@ -30,6 +30,14 @@ impl Position {
     pub fn new(x: i32, y: i16, z: i32) -> Self {
         Position { x, y, z }
     }
+
+    /// How far away another position is, squared, in blocks.
+    pub fn distance_squared(&self, other: &Position) -> i64 {
+        let dx = other.x as i64 - self.x as i64;
+        let dy = other.y as i64 - self.y as i64;
+        let dz = other.z as i64 - self.z as i64;
+        dx * dx + dy * dy + dz * dz
+    }
 }
 
 impl NetEncode for Position {
@@ -93,4 +101,14 @@ mod tests {
                 .to_be_bytes()
         );
     }
+
+    #[test]
+    fn test_distance_squared() {
+        let from = Position::new(-1, 64, 2);
+        assert_eq!(from.distance_squared(&from), 0);
+        assert_eq!(from.distance_squared(&Position::new(2, 60, 2)), 25);
+        // Across the whole world doesn't overflow
+        let edge = Position::new(-30_000_000, -2048, -30_000_000);
+        assert!(edge.distance_squared(&Position::new(30_000_000, 2047, 30_000_000)) > 0);
+    }
 }
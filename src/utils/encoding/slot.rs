@@ -0,0 +1,474 @@
+use std::io::Cursor;
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use ferrumc_codec::enc::NetEncode;
+use ferrumc_codec::network_types::varint::VarInt;
+
+use crate::net::protocol::{decoding_protocol, Protocol, NATIVE_PROTOCOL};
+use crate::utils::constants::limits::{MAX_NBT_DEPTH, MAX_PACKET_LENGTH};
+use crate::utils::encoding::metadata::network_metadata;
+use crate::utils::error::{Error, NetDecodeError};
+use crate::utils::impls::packet_impls::NetDecode;
+
+/// An inventory slot as sent in packets.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Slot {
+    /// `None` if the slot is empty.
+    pub item: Option<ItemStack>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ItemStack {
+    pub id: i32,
+    pub count: i8,
+    /// The item's NBT, e.g. enchantments, kept as it was sent.
+    pub nbt: Vec<u8>,
+}
+
+impl ItemStack {
+    /// `count` of an item without any NBT.
+    pub fn new(id: i32, count: i8) -> Self {
+        Self {
+            id,
+            count,
+            nbt: vec![TAG_END],
+        }
+    }
+
+    /// The NBT, as an empty tag if the item doesn't have any. It always has a named root, whichever
+    /// version it came from.
+    pub fn tag(&self) -> &[u8] {
+        if self.nbt.is_empty() {
+            &[TAG_END]
+        } else {
+            &self.nbt
+        }
+    }
+
+    /// Whether the two can go in the same slot, which takes the same item with the same NBT.
+    pub fn stacks_with(&self, other: &ItemStack) -> bool {
+        self.id == other.id && self.tag() == other.tag()
+    }
+}
+
+impl NetDecode for Slot {
+    /// Decodes a slot from the [version being read](decoding_protocol). The NBT isn't parsed,
+    /// only walked through to find where it ends and to make sure it isn't nested deeper than
+    /// [MAX_NBT_DEPTH], since it gets sent on to other players.
+    async fn net_decode<T>(bytes: &mut T) -> Result<Box<Self>, Error>
+    where
+        T: AsyncRead + Unpin,
+    {
+        read_slot(bytes, decoding_protocol()).await.map(Box::new)
+    }
+}
+
+async fn read_slot<T>(bytes: &mut T, protocol: &Protocol) -> Result<Slot, Error>
+where
+    T: AsyncRead + Unpin,
+{
+    let present = *bool::net_decode(bytes).await?;
+    if !present {
+        return Ok(Slot { item: None });
+    }
+
+    let id = VarInt::net_decode(bytes).await?.get_val();
+    let count = *i8::net_decode(bytes).await?;
+    let nbt = read_nbt(bytes, !protocol.nameless_nbt()).await?;
+
+    Ok(Slot {
+        item: Some(ItemStack { id, count, nbt }),
+    })
+}
+
+impl Slot {
+    /// The slot with its NBT the way `protocol` sends it, see [Protocol::network_nbt].
+    fn network(mut self, protocol: &Protocol) -> Self {
+        if let Some(item) = &mut self.item {
+            item.nbt = protocol.network_nbt(std::mem::take(&mut item.nbt));
+        }
+        self
+    }
+}
+
+/// Reads a root tag as it was sent, checking its depth on the way, failing if it's cut short too.
+/// A root without a name is given an empty one, so stored NBT looks the same whoever sent it.
+async fn read_nbt<T>(bytes: &mut T, named: bool) -> Result<Vec<u8>, Error>
+where
+    T: AsyncRead + Unpin,
+{
+    let mut reader = NbtReader {
+        bytes,
+        nbt: Vec::new(),
+    };
+    let tag = reader.take(1).await?[0];
+    if tag == TAG_END {
+        return Ok(reader.nbt);
+    }
+    if named {
+        let name_length = reader.length_u16().await?;
+        reader.take(name_length).await?;
+    } else {
+        reader.nbt.extend_from_slice(&[0, 0]);
+    }
+
+    // Kept on the heap instead of recursing, so deep NBT can't overflow the stack before the
+    // depth is checked
+    let mut containers = Vec::new();
+    reader.skip_payload(tag, &mut containers).await?;
+    while let Some(container) = containers.last_mut() {
+        let tag = match container {
+            Container::Compound => {
+                let tag = reader.take(1).await?[0];
+                if tag == TAG_END {
+                    containers.pop();
+                    continue;
+                }
+                let name_length = reader.length_u16().await?;
+                reader.take(name_length).await?;
+                tag
+            }
+            Container::List { element, remaining } => {
+                if *remaining == 0 {
+                    containers.pop();
+                    continue;
+                }
+                *remaining -= 1;
+                *element
+            }
+        };
+        reader.skip_payload(tag, &mut containers).await?;
+    }
+    Ok(reader.nbt)
+}
+
+const TAG_END: u8 = 0;
+const TAG_LIST: u8 = 9;
+const TAG_COMPOUND: u8 = 10;
+
+/// A compound or list whose entries are still being read.
+enum Container {
+    Compound,
+    List { element: u8, remaining: usize },
+}
+
+/// Reads NBT a piece at a time, keeping everything it's read.
+struct NbtReader<'a, T> {
+    bytes: &'a mut T,
+    nbt: Vec<u8>,
+}
+
+impl<T: AsyncRead + Unpin> NbtReader<'_, T> {
+    async fn take(&mut self, length: usize) -> Result<&[u8], Error> {
+        let start = self.nbt.len();
+        if start + length > MAX_PACKET_LENGTH {
+            return Err(NetDecodeError::LimitExceeded {
+                what: "Item NBT",
+                length: start + length,
+                max: MAX_PACKET_LENGTH,
+            }
+            .into());
+        }
+        // Read through `take` rather than into a buffer of `length`, so a made up length can't
+        // allocate more than is actually there
+        let read = (&mut *self.bytes)
+            .take(length as u64)
+            .read_to_end(&mut self.nbt)
+            .await?;
+        if read < length {
+            return Err(Error::InvalidNbt("Item NBT is cut short".to_string()));
+        }
+        Ok(&self.nbt[start..])
+    }
+
+    async fn length_u16(&mut self) -> Result<usize, Error> {
+        let bytes = self.take(2).await?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]) as usize)
+    }
+
+    async fn length_i32(&mut self) -> Result<usize, Error> {
+        let bytes = self.take(4).await?;
+        let length = i32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        // Every entry takes at least a byte, so nothing longer than a packet can be real. Also
+        // keeps a huge list of empty tags from looping for ages
+        Ok(NetDecodeError::check_length("NBT array", length, MAX_PACKET_LENGTH)?)
+    }
+
+    /// Skips over a tag's payload. Compounds and lists are only started, their entries are left
+    /// to [read_nbt].
+    async fn skip_payload(&mut self, tag: u8, containers: &mut Vec<Container>) -> Result<(), Error> {
+        match tag {
+            1 => self.take(1).await.map(drop)?,
+            2 => self.take(2).await.map(drop)?,
+            3 | 5 => self.take(4).await.map(drop)?,
+            4 | 6 => self.take(8).await.map(drop)?,
+            7 => {
+                let length = self.length_i32().await?;
+                self.take(length).await.map(drop)?
+            }
+            8 => {
+                let length = self.length_u16().await?;
+                self.take(length).await.map(drop)?
+            }
+            TAG_LIST => {
+                let element = self.take(1).await?[0];
+                let remaining = self.length_i32().await?;
+                containers.push(Container::List { element, remaining });
+            }
+            TAG_COMPOUND => containers.push(Container::Compound),
+            11 => {
+                let length = self.length_i32().await?;
+                self.take(length * 4).await.map(drop)?
+            }
+            12 => {
+                let length = self.length_i32().await?;
+                self.take(length * 8).await.map(drop)?
+            }
+            tag => return Err(Error::InvalidNbt(format!("Unknown tag type {}", tag))),
+        }
+
+        if containers.len() > MAX_NBT_DEPTH {
+            return Err(NetDecodeError::LimitExceeded {
+                what: "NBT depth",
+                length: containers.len(),
+                max: MAX_NBT_DEPTH,
+            }
+            .into());
+        }
+        Ok(())
+    }
+}
+
+impl NetEncode for Slot {
+    /// The NBT is written back as it was read, or as an empty tag if there isn't any.
+    async fn net_encode<T>(&self, bytes: &mut T) -> Result<(), ferrumc_codec::CodecError>
+    where
+        T: AsyncWrite + Unpin,
+    {
+        let Some(item) = &self.item else {
+            return false.net_encode(bytes).await;
+        };
+        true.net_encode(bytes).await?;
+        VarInt::from(item.id).net_encode(bytes).await?;
+        item.count.net_encode(bytes).await?;
+        bytes
+            .write_all(item.tag())
+            .await
+            .map_err(ferrumc_codec::CodecError::from_external_error)
+    }
+}
+
+/// The data of a clientbound play packet with the NBT in its slots the way `protocol` sends it, or
+/// `None` if the packet doesn't have slots. `packet_id` is the server's own id.
+///
+/// Slots are encoded the same for everyone, so this is done when the packet's
+/// [remapped](Protocol::remap_frames) for the client's version.
+pub(crate) async fn network_slots(
+    protocol: &Protocol,
+    packet_id: u8,
+    data: &[u8],
+) -> Result<Option<Vec<u8>>, Error> {
+    let mut cursor = Cursor::new(data);
+    let mut out = Vec::with_capacity(data.len());
+    match packet_id {
+        // Set Container Content: window id, state id and the slots, then the carried item
+        0x12 => {
+            copy_bytes(&mut cursor, &mut out, 1)?;
+            copy_varint(&mut cursor, &mut out).await?;
+            let count = copy_varint(&mut cursor, &mut out).await?;
+            for _ in 0..=count {
+                network_slot(&mut cursor, &mut out, protocol).await?;
+            }
+        }
+        // Set Container Slot: window id, state id, slot, then the item
+        0x14 => {
+            copy_bytes(&mut cursor, &mut out, 1)?;
+            copy_varint(&mut cursor, &mut out).await?;
+            copy_bytes(&mut cursor, &mut out, 2)?;
+            network_slot(&mut cursor, &mut out, protocol).await?;
+        }
+        // Set Entity Metadata: the entity, then its metadata
+        0x52 => {
+            copy_varint(&mut cursor, &mut out).await?;
+            network_metadata(&mut cursor, &mut out, protocol).await?;
+        }
+        _ => return Ok(None),
+    }
+    // Anything after the slots
+    let rest = cursor.position() as usize;
+    out.extend_from_slice(&data[rest..]);
+    Ok(Some(out))
+}
+
+/// Copies a slot the server encoded, with its NBT the way `protocol` sends it.
+pub(crate) async fn network_slot(
+    cursor: &mut Cursor<&[u8]>,
+    out: &mut Vec<u8>,
+    protocol: &Protocol,
+) -> Result<(), Error> {
+    let slot = read_slot(cursor, &NATIVE_PROTOCOL).await?;
+    slot.network(protocol).net_encode(out).await?;
+    Ok(())
+}
+
+pub(crate) fn copy_bytes(
+    cursor: &mut Cursor<&[u8]>,
+    out: &mut Vec<u8>,
+    length: usize,
+) -> Result<(), Error> {
+    let start = cursor.position() as usize;
+    let bytes = cursor
+        .get_ref()
+        .get(start..start + length)
+        .ok_or_else(|| Error::Generic("Packet is shorter than its slots".to_string()))?;
+    out.extend_from_slice(bytes);
+    cursor.set_position((start + length) as u64);
+    Ok(())
+}
+
+/// Copies a VarInt, returning it.
+pub(crate) async fn copy_varint(
+    cursor: &mut Cursor<&[u8]>,
+    out: &mut Vec<u8>,
+) -> Result<i32, Error> {
+    let value = VarInt::read(cursor).await?;
+    value.net_encode(out).await?;
+    Ok(value.get_val())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::net::protocol::{decode_as, PROTOCOL_1_20_2, V1_20_2};
+
+    #[tokio::test]
+    async fn test_slot_decode() {
+        let mut data = Cursor::new(vec![0]);
+        assert_eq!(*Slot::net_decode(&mut data).await.unwrap(), Slot { item: None });
+
+        // Item 766, 64 of them, with no NBT
+        let mut data = Cursor::new(vec![1, 0xFE, 0x05, 64, 0]);
+        let slot = Slot::net_decode(&mut data).await.unwrap();
+        assert_eq!(
+            slot.item,
+            Some(ItemStack {
+                id: 766,
+                count: 64,
+                nbt: vec![0]
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_slot_nbt_depth() {
+        // An item with a compound holding a list of one compound
+        let mut data = Cursor::new(vec![
+            1, 1, 1, 10, 0, 0, 9, 0, 1, b'a', 10, 0, 0, 0, 1, 0, 0,
+        ]);
+        assert!(Slot::net_decode(&mut data).await.is_ok());
+
+        // Lists of lists, nested deeper than vanilla allows
+        let mut nbt = vec![1, 1, 1, 9, 0, 0];
+        for _ in 0..MAX_NBT_DEPTH + 1 {
+            nbt.extend_from_slice(&[9, 0, 0, 0, 1]);
+        }
+        nbt.extend_from_slice(&[0, 0, 0, 0, 0]);
+        let result = Slot::net_decode(&mut Cursor::new(nbt)).await;
+        assert!(matches!(
+            result,
+            Err(Error::NetDecode(NetDecodeError::LimitExceeded { .. }))
+        ));
+
+        // Cut short
+        assert!(Slot::net_decode(&mut Cursor::new(vec![1, 1, 1, 10, 0, 0, 1])).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_slot_decode_stops_after_nbt() {
+        // An item with an empty compound, an empty slot, then an item without NBT
+        let mut data = Cursor::new(vec![1, 1, 1, 10, 0, 0, 0, 0, 1, 2, 3, 0]);
+        let first = Slot::net_decode(&mut data).await.unwrap();
+        assert_eq!(first.item.unwrap().nbt, vec![10, 0, 0, 0]);
+        assert_eq!(Slot::net_decode(&mut data).await.unwrap().item, None);
+        let third = Slot::net_decode(&mut data).await.unwrap().item.unwrap();
+        assert_eq!((third.id, third.count, third.nbt), (2, 3, vec![0]));
+    }
+
+    #[tokio::test]
+    async fn test_slot_decode_1_20_2() {
+        // An item with a byte `a` of 5 in a root without a name, then an empty slot
+        let mut data = Cursor::new(vec![1, 1, 1, 10, 1, 0, 1, b'a', 5, 0, 0]);
+        let slot = decode_as(V1_20_2, Slot::net_decode(&mut data))
+            .await
+            .unwrap();
+        // Stored with an empty name like any other
+        assert_eq!(slot.item.unwrap().nbt, vec![10, 0, 0, 1, 0, 1, b'a', 5, 0]);
+        assert_eq!(Slot::net_decode(&mut data).await.unwrap().item, None);
+
+        // Without a version it's read as 1.20.1's, which the name makes too long
+        let mut data = Cursor::new(vec![1, 1, 1, 10, 1, 0, 1, b'a', 5, 0]);
+        assert!(Slot::net_decode(&mut data).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_network_slots() {
+        // Set Container Slot for slot 5, the item having a byte `a` of 5
+        let nbt = [1, 0, 1, b'a', 5, 0];
+        let data = [&[0, 1, 0, 5, 1, 1, 1, 10, 0, 0][..], &nbt].concat();
+        assert_eq!(
+            network_slots(&NATIVE_PROTOCOL, 0x14, &data).await.unwrap(),
+            Some(data.clone())
+        );
+        let expected = [&[0, 1, 0, 5, 1, 1, 1, 10][..], &nbt].concat();
+        assert_eq!(
+            network_slots(&PROTOCOL_1_20_2, 0x14, &data).await.unwrap(),
+            Some(expected)
+        );
+
+        // Set Entity Metadata for an item entity, its item at 8 after its flags
+        let data = [&[1, 0, 0, 0x20, 8, 7, 1, 1, 1, 10, 0, 0][..], &nbt, &[0xFF]].concat();
+        let expected = [&[1, 0, 0, 0x20, 8, 7, 1, 1, 1, 10][..], &nbt, &[0xFF]].concat();
+        assert_eq!(
+            network_slots(&PROTOCOL_1_20_2, 0x52, &data).await.unwrap(),
+            Some(expected)
+        );
+
+        // Anything without slots is left alone
+        assert_eq!(
+            network_slots(&PROTOCOL_1_20_2, 0x1E, &data).await.unwrap(),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_slot_encode() {
+        let mut data = Vec::new();
+        Slot { item: None }.net_encode(&mut data).await.unwrap();
+        assert_eq!(data, vec![0]);
+
+        let slot = Slot {
+            item: Some(ItemStack {
+                id: 766,
+                count: 64,
+                nbt: vec![],
+            }),
+        };
+        let mut data = Vec::new();
+        slot.net_encode(&mut data).await.unwrap();
+        assert_eq!(data, vec![1, 0xFE, 0x05, 64, 0]);
+    }
+
+    #[test]
+    fn test_stacks_with() {
+        let stone = ItemStack::new(1, 1);
+        // No NBT at all is the same as an empty tag
+        let mut bare = ItemStack::new(1, 64);
+        bare.nbt = Vec::new();
+        let mut named = ItemStack::new(1, 1);
+        named.nbt = vec![10, 0, 0, 8, 0, 1, b'a', 0, 0, 0];
+        assert!(stone.stacks_with(&bare));
+        assert!(!stone.stacks_with(&named));
+        assert!(!stone.stacks_with(&ItemStack::new(2, 1)));
+    }
+}
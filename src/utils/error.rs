@@ -65,6 +65,10 @@ pub enum Error {
 
     #[error("TCP Error: {0}")]
     TcpError(String),
+    #[error("Invalid PROXY protocol header: {0}")]
+    InvalidProxyHeader(String),
+    #[error(transparent)]
+    NetDecode(#[from] NetDecodeError),
 
     #[error("Invalid NBT: {0}")]
     GenericNbtError(String),
@@ -91,6 +95,13 @@ pub enum Error {
     #[error(transparent)]
     CompressionError(std::io::Error),
 
+    #[error("Encryption error: {0}")]
+    EncryptionError(String),
+    #[error("Authentication failed: {0}")]
+    AuthenticationFailed(String),
+    #[error(transparent)]
+    HttpError(#[from] reqwest::Error),
+
     #[error("Database error: {0}")]
     LmdbError(#[from] heed::Error),
     #[error("(bincode) Encode error")]
@@ -99,6 +110,35 @@ pub enum Error {
     BincodeDecodeError(#[from] bincode::error::DecodeError),
 }
 
+/// Something in a packet breaks one of the [limits](crate::utils::constants::limits). Clients that
+/// send these are disconnected, since vanilla ones never do.
+#[derive(thiserror::Error, Debug)]
+pub enum NetDecodeError {
+    #[error("{what} is {length} long, over the limit of {max}")]
+    LimitExceeded {
+        what: &'static str,
+        length: usize,
+        max: usize,
+    },
+    #[error("{0} has a negative length")]
+    NegativeLength(&'static str),
+}
+
+impl NetDecodeError {
+    /// Checks a length read from a packet before anything gets allocated for it.
+    pub fn check_length(
+        what: &'static str,
+        length: i32,
+        max: usize,
+    ) -> core::result::Result<usize, NetDecodeError> {
+        let length = usize::try_from(length).map_err(|_| NetDecodeError::NegativeLength(what))?;
+        if length > max {
+            return Err(NetDecodeError::LimitExceeded { what, length, max });
+        }
+        Ok(length)
+    }
+}
+
 impl From<Infallible> for Error {
     fn from(e: Infallible) -> Self {
         Error::Generic(format!("{:?}", e))
@@ -1,9 +1,12 @@
+use bytes::{Bytes, BytesMut};
 use ferrumc_codec::network_types::varint::VarInt;
+use ferrumc_codec::network_types::angle::Angle;
 use ferrumc_codec::network_types::varlong::Varlong;
 use tokio::io::{AsyncRead, AsyncReadExt};
 
+use crate::utils::constants::limits::{MAX_PACKET_LENGTH, MAX_STRING_LENGTH};
 use crate::utils::encoding::position::Position;
-use crate::utils::error::Error;
+use crate::utils::error::{Error, NetDecodeError};
 
 /// This trait is used to decode a type from a byte stream. It is implemented for all types that
 /// can be decoded from a byte stream.
@@ -195,15 +198,27 @@ impl NetDecode for f64 {
 impl NetDecode for String {
     /// Decodes a String from a byte stream. The first byte(s) is a VarInt representing the length of
     /// the string, followed by the string itself. The string is expected to be UTF-8 encoded.
-    /// Takes out a variable number of bytes.
+    /// Takes out a variable number of bytes, at most [MAX_STRING_LENGTH] characters' worth.
     async fn net_decode<T>(bytes: &mut T) -> Result<Box<Self>, Error>
     where
         T: AsyncRead + Unpin,
     {
-        let remaining_bytes = VarInt::read(bytes).await?;
-        let mut string_buf = vec![0u8; remaining_bytes.into()];
+        let length = VarInt::read(bytes).await?.get_val();
+        let length = NetDecodeError::check_length("String", length, MAX_STRING_LENGTH * 3)?;
+        let mut string_buf = vec![0u8; length];
         bytes.read_exact(&mut string_buf).await?;
-        Ok(Box::from(String::from_utf8(string_buf)?))
+        let string = String::from_utf8(string_buf)?;
+
+        let characters = string.encode_utf16().count();
+        if characters > MAX_STRING_LENGTH {
+            return Err(NetDecodeError::LimitExceeded {
+                what: "String",
+                length: characters,
+                max: MAX_STRING_LENGTH,
+            }
+            .into());
+        }
+        Ok(Box::from(string))
     }
 }
 
@@ -250,6 +265,27 @@ impl NetDecode for u128 {
     }
 }
 
+impl NetDecode for uuid::Uuid {
+    /// Decodes a UUID from a byte stream. Takes out 16 bytes, read as a big-endian u128.
+    async fn net_decode<T>(bytes: &mut T) -> Result<Box<Self>, Error>
+    where
+        T: AsyncRead + Unpin,
+    {
+        let value = u128::net_decode(bytes).await?;
+        Ok(Box::from(uuid::Uuid::from_u128(*value)))
+    }
+}
+
+impl NetDecode for Angle {
+    /// Decodes an Angle from a byte stream. Takes out 1 byte, 1/256 of a turn per step.
+    async fn net_decode<T>(bytes: &mut T) -> Result<Box<Self>, Error>
+    where
+        T: AsyncRead + Unpin,
+    {
+        Ok(Box::from(Angle::read(bytes).await?))
+    }
+}
+
 impl<V: NetDecode + Unpin> NetDecode for Vec<V> {
     /// Decodes a Vec from a byte stream. The first byte(s) is a VarInt representing the length of the
     /// Vec, followed by the elements of the Vec. The elements are decoded in order, and the Vec is
@@ -269,6 +305,36 @@ impl<V: NetDecode + Unpin> NetDecode for Vec<V> {
     }
 }
 
+impl NetDecode for Bytes {
+    /// Decodes a byte array with a VarInt length in front. Unlike a `Vec<u8>`, which decodes its
+    /// bytes one at a time, the whole array is read at once.
+    async fn net_decode<T>(bytes: &mut T) -> Result<Box<Self>, Error>
+    where
+        T: AsyncRead + Unpin,
+    {
+        let length = VarInt::read(bytes).await?.get_val();
+        let length = NetDecodeError::check_length("Byte array", length, MAX_PACKET_LENGTH)?;
+        let mut data = BytesMut::zeroed(length);
+        bytes.read_exact(&mut data).await?;
+        Ok(Box::from(data.freeze()))
+    }
+}
+
+/// Whatever is left of the packet. For fields that are always last, which don't get a length.
+#[derive(Debug, Default)]
+pub struct RemainingBytes(pub Bytes);
+
+impl NetDecode for RemainingBytes {
+    async fn net_decode<T>(bytes: &mut T) -> Result<Box<Self>, Error>
+    where
+        T: AsyncRead + Unpin,
+    {
+        let mut data = Vec::new();
+        bytes.read_to_end(&mut data).await?;
+        Ok(Box::from(RemainingBytes(Bytes::from(data))))
+    }
+}
+
 impl NetDecode for Position {
     /// Decodes a Position from a byte stream. A Position is a 64-bit integer, where the 26 MSB
     /// are the x coordinate, the next 26 bits are the z coordinate, and the 12 LSB are
@@ -581,3 +647,50 @@ impl<O: Encode> Encode for Option<O> {
     }
 }
 */
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use ferrumc_codec::enc::NetEncode;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_uuid_round_trip() {
+        let uuid = uuid::Uuid::parse_str("069a79f4-44e9-4726-a5be-fca90e38aaf5").unwrap();
+        let mut data = Vec::new();
+        uuid.net_encode(&mut data).await.unwrap();
+        assert_eq!(data[..4], [0x06, 0x9a, 0x79, 0xf4]);
+        let decoded = uuid::Uuid::net_decode(&mut Cursor::new(data)).await.unwrap();
+        assert_eq!(*decoded, uuid);
+    }
+
+    #[tokio::test]
+    async fn test_string_limit() {
+        let mut data = Vec::new();
+        "a".repeat(MAX_STRING_LENGTH).net_encode(&mut data).await.unwrap();
+        assert!(String::net_decode(&mut Cursor::new(data)).await.is_ok());
+
+        let mut data = Vec::new();
+        "a".repeat(MAX_STRING_LENGTH + 1).net_encode(&mut data).await.unwrap();
+        let result = String::net_decode(&mut Cursor::new(data)).await;
+        assert!(matches!(
+            result,
+            Err(Error::NetDecode(NetDecodeError::LimitExceeded { .. }))
+        ));
+
+        // A negative length isn't allocated for
+        let mut data = Vec::new();
+        VarInt::new(-1).net_encode(&mut data).await.unwrap();
+        assert!(String::net_decode(&mut Cursor::new(data)).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_bytes_decode() {
+        let mut data = Cursor::new(vec![3, 1, 2, 3, 4]);
+        let bytes = Bytes::net_decode(&mut data).await.unwrap();
+        assert_eq!(&bytes[..], &[1, 2, 3]);
+        assert_eq!(*u8::net_decode(&mut data).await.unwrap(), 4);
+    }
+}
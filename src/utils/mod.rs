@@ -13,6 +13,7 @@ pub mod error;
 pub mod hash;
 pub mod impls;
 pub mod prelude;
+pub mod text_component;
 
 /// Sets up the logger. Needs to be run before anything else in order for logging to run end.
 pub fn setup_logger() -> Result<()> {
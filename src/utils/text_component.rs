@@ -0,0 +1,276 @@
+//! Text components, the formatted text used for chat, disconnect reasons and the MOTD.
+//!
+//! They're sent as JSON, e.g. `{"text": "Hello", "color": "gold"}`, and as network NBT with the
+//! same structure from 1.20.3 on.
+
+use nbt_lib::NBTSerialize;
+use serde::{Deserialize, Serialize};
+
+use crate::utils::prelude::*;
+use crate::world::registry_data::json_to_nbt;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TextComponent {
+    #[serde(flatten)]
+    pub content: Content,
+    /// A colour name like `gold`, or a hex code like `#FF5555`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bold: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub italic: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub underlined: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub strikethrough: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub obfuscated: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub font: Option<String>,
+    /// Put in the chat box when the text is shift-clicked.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub insertion: Option<String>,
+    #[serde(rename = "clickEvent", skip_serializing_if = "Option::is_none")]
+    pub click_event: Option<ClickEvent>,
+    #[serde(rename = "hoverEvent", skip_serializing_if = "Option::is_none")]
+    pub hover_event: Option<HoverEvent>,
+    /// Components shown after this one, which take on its formatting unless they set their own.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extra: Vec<TextComponent>,
+}
+
+/// What a component shows.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Content {
+    Text {
+        text: String,
+    },
+    /// A translation key from the client's language, e.g. `multiplayer.disconnect.kicked`, with
+    /// the components that fill in its `%s`s.
+    Translate {
+        translate: String,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        with: Vec<TextComponent>,
+    },
+    /// Whatever key the player has bound to something, e.g. `key.jump`.
+    Keybind {
+        keybind: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ClickEvent {
+    pub action: ClickAction,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClickAction {
+    OpenUrl,
+    RunCommand,
+    /// Puts the value in the chat box without sending it.
+    SuggestCommand,
+    /// Only works in books.
+    ChangePage,
+    CopyToClipboard,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "action", content = "contents", rename_all = "snake_case")]
+pub enum HoverEvent {
+    ShowText(Box<TextComponent>),
+}
+
+impl TextComponent {
+    fn new(content: Content) -> Self {
+        Self {
+            content,
+            color: None,
+            bold: None,
+            italic: None,
+            underlined: None,
+            strikethrough: None,
+            obfuscated: None,
+            font: None,
+            insertion: None,
+            click_event: None,
+            hover_event: None,
+            extra: Vec::new(),
+        }
+    }
+
+    pub fn text(text: impl Into<String>) -> Self {
+        Self::new(Content::Text { text: text.into() })
+    }
+
+    pub fn translate(key: impl Into<String>, with: Vec<TextComponent>) -> Self {
+        Self::new(Content::Translate {
+            translate: key.into(),
+            with,
+        })
+    }
+
+    pub fn keybind(key: impl Into<String>) -> Self {
+        Self::new(Content::Keybind {
+            keybind: key.into(),
+        })
+    }
+
+    /// Reads a component from JSON, falling back to plain text if it isn't one, e.g. for text
+    /// from the config. Keys that aren't supported here are dropped.
+    pub fn parse(text: &str) -> Self {
+        serde_json::from_str::<serde_json::Value>(text)
+            .ok()
+            .filter(|value| value.is_object())
+            .and_then(|value| serde_json::from_value(value).ok())
+            .unwrap_or_else(|| Self::text(text))
+    }
+
+    pub fn color(mut self, color: impl Into<String>) -> Self {
+        self.color = Some(color.into());
+        self
+    }
+
+    pub fn bold(mut self, bold: bool) -> Self {
+        self.bold = Some(bold);
+        self
+    }
+
+    pub fn italic(mut self, italic: bool) -> Self {
+        self.italic = Some(italic);
+        self
+    }
+
+    pub fn underlined(mut self, underlined: bool) -> Self {
+        self.underlined = Some(underlined);
+        self
+    }
+
+    pub fn strikethrough(mut self, strikethrough: bool) -> Self {
+        self.strikethrough = Some(strikethrough);
+        self
+    }
+
+    pub fn obfuscated(mut self, obfuscated: bool) -> Self {
+        self.obfuscated = Some(obfuscated);
+        self
+    }
+
+    pub fn insertion(mut self, insertion: impl Into<String>) -> Self {
+        self.insertion = Some(insertion.into());
+        self
+    }
+
+    pub fn on_click(mut self, action: ClickAction, value: impl Into<String>) -> Self {
+        self.click_event = Some(ClickEvent {
+            action,
+            value: value.into(),
+        });
+        self
+    }
+
+    pub fn on_hover(mut self, text: TextComponent) -> Self {
+        self.hover_event = Some(HoverEvent::ShowText(Box::new(text)));
+        self
+    }
+
+    /// Adds a component after this one.
+    pub fn append(mut self, component: TextComponent) -> Self {
+        self.extra.push(component);
+        self
+    }
+
+    /// The text without any formatting, for places that can't show it. Translations and keybinds
+    /// come out as their keys.
+    pub fn plain_text(&self) -> String {
+        let mut text = match &self.content {
+            Content::Text { text } => text.clone(),
+            Content::Translate { translate, .. } => translate.clone(),
+            Content::Keybind { keybind } => keybind.clone(),
+        };
+        for extra in &self.extra {
+            text.push_str(&extra.plain_text());
+        }
+        text
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("Text components always serialize")
+    }
+
+    /// The component as network NBT, which is how it's sent from 1.20.3 on.
+    pub fn to_nbt(&self) -> Result<Vec<u8>> {
+        let value = serde_json::to_value(self).expect("Text components always serialize");
+        let tag = json_to_nbt(&value).map_err(Error::GenericNbtError)?;
+        // The compound's type, network NBT doesn't have a name after it
+        let mut data = vec![10];
+        tag.nbt_serialize(&mut data)?;
+        Ok(data)
+    }
+}
+
+impl From<&str> for TextComponent {
+    fn from(text: &str) -> Self {
+        Self::text(text)
+    }
+}
+
+impl From<String> for TextComponent {
+    fn from(text: String) -> Self {
+        Self::text(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_json() {
+        assert_eq!(TextComponent::text("Hi").to_json(), r#"{"text":"Hi"}"#);
+
+        let component = TextComponent::text("Click me")
+            .color("gold")
+            .bold(true)
+            .on_click(ClickAction::RunCommand, "/help")
+            .on_hover(TextComponent::text("Runs /help"))
+            .append(TextComponent::translate("chat.type.text", vec!["a".into(), "b".into()]));
+        assert_eq!(
+            serde_json::to_value(&component).unwrap(),
+            serde_json::json!({
+                "text": "Click me",
+                "color": "gold",
+                "bold": true,
+                "clickEvent": { "action": "run_command", "value": "/help" },
+                "hoverEvent": { "action": "show_text", "contents": { "text": "Runs /help" } },
+                "extra": [{ "translate": "chat.type.text", "with": [{ "text": "a" }, { "text": "b" }] }]
+            })
+        );
+        assert_eq!(TextComponent::parse(&component.to_json()), component);
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            TextComponent::parse(r#"{"text": "Hi", "color": "gold"}"#),
+            TextComponent::text("Hi").color("gold")
+        );
+        assert_eq!(TextComponent::parse("42"), TextComponent::text("42"));
+        assert_eq!(
+            TextComponent::parse(r#"{"text": "a", "extra": [{"keybind": "key.jump"}]}"#).plain_text(),
+            "akey.jump"
+        );
+    }
+
+    #[test]
+    fn test_to_nbt() {
+        let nbt = TextComponent::text("Hi").bold(true).to_nbt().unwrap();
+        // A nameless compound holding a string and a byte, in either order
+        assert_eq!(nbt[0], 10);
+        assert_eq!(*nbt.last().unwrap(), 0);
+        assert_eq!(nbt.len(), 1 + (1 + 2 + 4 + 2 + 2) + (1 + 2 + 4 + 1) + 1);
+    }
+}
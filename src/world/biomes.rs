@@ -0,0 +1,151 @@
+use ferrumc_codec::enc::NetEncode;
+use ferrumc_codec::network_types::packed_array::PackedArray;
+use ferrumc_codec::network_types::varint::{encode_varints, VarInt};
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+use crate::world::chunk_format::Biomes;
+use crate::world::registry_data::{get_registry_data, BIOME_REGISTRY};
+
+/// Used for sections without biomes and biomes that aren't in the registry.
+pub const DEFAULT_BIOME: &str = "minecraft:plains";
+const BIOMES_PER_SECTION: usize = 4 * 4 * 4;
+/// Indirect biome palettes can use at most 3 bits, after that the IDs are sent directly.
+const MAX_INDIRECT_BITS: u8 = 3;
+
+/// The network ID of a biome, e.g. `minecraft:plains`. Biome IDs are positions in the biome
+/// registry sent in Login (play), see [registry_data](crate::world::registry_data).
+pub fn biome_id(name: &str) -> Option<i32> {
+    get_registry_data().id(BIOME_REGISTRY, name)
+}
+
+/// Bits per entry when biome IDs are sent directly, enough for every biome in the registry.
+fn direct_bits() -> u8 {
+    let biomes = get_registry_data()
+        .registry(BIOME_REGISTRY)
+        .map_or(0, |registry| registry.len());
+    (biomes as f32).log2().ceil() as u8
+}
+
+impl Biomes {
+    /// A section that's one biome all the way through.
+    pub fn single(name: &str) -> Self {
+        Self {
+            palette: vec![name.to_string()],
+            data: None,
+        }
+    }
+}
+
+impl NetEncode for Biomes {
+    async fn net_encode<W>(&self, writer: &mut W) -> ferrumc_codec::Result<()>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        let default_id = biome_id(DEFAULT_BIOME).unwrap_or(0);
+        let ids: Vec<i32> = self
+            .palette
+            .iter()
+            .map(|name| biome_id(name).unwrap_or(default_id))
+            .collect();
+
+        let data = match &self.data {
+            Some(data) if ids.len() > 1 => data,
+            // Single valued: no bits per entry, just the biome and an empty data array
+            _ => {
+                0u8.net_encode(writer).await?;
+                VarInt::from(ids.first().copied().unwrap_or(default_id))
+                    .net_encode(writer)
+                    .await?;
+                return VarInt::from(0).net_encode(writer).await;
+            }
+        };
+
+        let bits = (ids.len() as f32).log2().ceil() as u8;
+        if bits <= MAX_INDIRECT_BITS {
+            // The disk format packs the palette indices the same way, so it can be sent as it is
+            bits.net_encode(writer).await?;
+            let mut palette = Vec::with_capacity(ids.len() + 1);
+            let values = std::iter::once(ids.len() as i32).chain(ids.iter().copied());
+            encode_varints(values, &mut palette);
+            writer.write_all(&palette).await?;
+            PackedArray::from_longs(data, bits).net_encode(writer).await
+        } else {
+            let bits_direct = direct_bits();
+            bits_direct.net_encode(writer).await?;
+            let direct: Vec<u16> = PackedArray::from_longs(data, bits)
+                .unpack(BIOMES_PER_SECTION)
+                .into_iter()
+                .map(|index| ids.get(index as usize).copied().unwrap_or(default_id) as u16)
+                .collect();
+            PackedArray::pack(&direct, bits_direct).net_encode(writer).await
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use ferrumc_codec::network_types::packed_array::{pack_values, unpack_values};
+
+    use super::*;
+
+    #[test]
+    fn test_biome_ids() {
+        assert_eq!(biome_id("minecraft:badlands"), Some(0));
+        assert_eq!(biome_id("minecraft:plains"), Some(39));
+        assert_eq!(biome_id("minecraft:not_a_biome"), None);
+        assert_eq!(direct_bits(), 6);
+    }
+
+    #[tokio::test]
+    async fn test_encode_single_biome() {
+        let mut data = Cursor::new(Vec::new());
+        Biomes::single("minecraft:desert")
+            .net_encode(&mut data)
+            .await
+            .unwrap();
+        assert_eq!(data.into_inner(), vec![0, 14, 0]);
+    }
+
+    #[tokio::test]
+    async fn test_encode_indirect_biomes() {
+        let biomes = Biomes {
+            palette: vec!["minecraft:plains".to_string(), "minecraft:forest".to_string()],
+            data: Some(vec![0b10, 0]),
+        };
+        let mut data = Cursor::new(Vec::new());
+        biomes.net_encode(&mut data).await.unwrap();
+        let data = data.into_inner();
+        // 1 bit per entry, a palette of 2, then 1 long
+        assert_eq!(&data[..5], &[1, 2, 39, 21, 2]);
+        assert_eq!(data.len(), 5 + 16);
+    }
+
+    #[tokio::test]
+    async fn test_encode_direct_biomes() {
+        let palette: Vec<String> = [
+            "plains", "forest", "desert", "ocean", "beach", "river", "swamp", "taiga", "jungle",
+        ]
+        .iter()
+        .map(|name| format!("minecraft:{}", name))
+        .collect();
+        let indices: Vec<u16> = (0..64).map(|index| index % 9).collect();
+        let biomes = Biomes {
+            palette,
+            data: Some(pack_values(&indices, 4)),
+        };
+
+        let mut data = Cursor::new(Vec::new());
+        biomes.net_encode(&mut data).await.unwrap();
+        let data = data.into_inner();
+        // 6 bits per entry, 10 entries per long
+        assert_eq!(&data[..2], &[6, 7]);
+        let longs: Vec<i64> = data[2..]
+            .chunks(8)
+            .map(|long| i64::from_be_bytes(long.try_into().unwrap()))
+            .collect();
+        let ids = unpack_values(&longs, 6, 3);
+        assert_eq!(ids, vec![39, 21, 14]);
+    }
+}
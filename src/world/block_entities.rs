@@ -0,0 +1,146 @@
+//! Block entities, the data some blocks have on top of their state. Only
+//! [containers](crate::world::containers) and [signs](crate::world::signs) are kept, and the client
+//! is only told about those.
+
+use std::collections::HashMap;
+
+use nbt_lib::{NBTSerialize, NBTTag};
+
+use crate::utils::prelude::*;
+use crate::world::chunk_format::{BlockEntity, Chunk, SignText};
+use crate::world::containers::ContainerType;
+use crate::world::signs::{self, SIGN_TYPE, HANGING_SIGN_TYPE};
+
+impl BlockEntity {
+    pub fn new(id: &str, x: i32, y: i32, z: i32) -> Self {
+        Self {
+            id: id.to_string(),
+            x,
+            y,
+            z,
+            items: None,
+            front_text: None,
+            back_text: None,
+            is_waxed: None,
+        }
+    }
+
+    /// The block entity a block starts out with when it's placed, if it has one.
+    pub fn for_block(block: &str, x: i32, y: i32, z: i32) -> Option<Self> {
+        if let Some(container) = ContainerType::get(block) {
+            return Some(Self::new(container.block, x, y, z));
+        }
+        let mut sign = Self::new(signs::block_entity_id(block)?, x, y, z);
+        sign.front_text = Some(SignText::default());
+        sign.back_text = Some(SignText::default());
+        sign.is_waxed = Some(false);
+        Some(sign)
+    }
+
+    /// The block entity's type in the `minecraft:block_entity_type` registry.
+    pub fn type_id(&self) -> Option<i32> {
+        match self.id.as_str() {
+            signs::SIGN => Some(SIGN_TYPE),
+            signs::HANGING_SIGN => Some(HANGING_SIGN_TYPE),
+            id => ContainerType::get(id).map(|container| container.block_entity_type),
+        }
+    }
+
+    /// What the client is sent about the block entity, as a compound with an empty name. Clients
+    /// don't need to know what's in containers, so they get an empty one.
+    pub fn client_data(&self) -> Result<Vec<u8>> {
+        // Built as a tag, since the derive leaves the element type out of lists of strings
+        let mut client_data = HashMap::new();
+        if let Some(front_text) = &self.front_text {
+            client_data.insert("front_text".to_string(), front_text.to_nbt());
+        }
+        if let Some(back_text) = &self.back_text {
+            client_data.insert("back_text".to_string(), back_text.to_nbt());
+        }
+        if let Some(is_waxed) = self.is_waxed {
+            client_data.insert("is_waxed".to_string(), NBTTag::Byte(i8::from(is_waxed)));
+        }
+        // The compound's type and its empty name
+        let mut data = vec![10, 0, 0];
+        NBTTag::Compound(client_data).nbt_serialize(&mut data)?;
+        Ok(data)
+    }
+}
+
+impl Chunk {
+    /// The block entity at a block, in absolute coordinates.
+    pub fn block_entity(&self, x: i32, y: i32, z: i32) -> Option<&BlockEntity> {
+        self.block_entities
+            .as_ref()?
+            .iter()
+            .find(|entity| (entity.x, entity.y, entity.z) == (x, y, z))
+    }
+
+    /// Adds a block entity, replacing the one at the same block if there is one.
+    pub fn set_block_entity(&mut self, block_entity: BlockEntity) {
+        let (x, y, z) = (block_entity.x, block_entity.y, block_entity.z);
+        self.remove_block_entity(x, y, z);
+        self.block_entities
+            .get_or_insert_with(Vec::new)
+            .push(block_entity);
+    }
+
+    pub fn remove_block_entity(&mut self, x: i32, y: i32, z: i32) -> Option<BlockEntity> {
+        let block_entities = self.block_entities.as_mut()?;
+        let index = block_entities
+            .iter()
+            .position(|entity| (entity.x, entity.y, entity.z) == (x, y, z))?;
+        Some(block_entities.remove(index))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::generation::builder::ChunkBuilder;
+
+    #[test]
+    fn test_chunk_block_entities() {
+        let mut chunk = ChunkBuilder::new(0, 0, "overworld").build();
+        chunk.set_block_entity(BlockEntity::new("minecraft:chest", 1, 2, 3));
+        chunk.set_block_entity(BlockEntity::new("minecraft:barrel", 1, 2, 3));
+        assert_eq!(chunk.block_entities.as_ref().unwrap().len(), 1);
+        assert_eq!(chunk.block_entity(1, 2, 3).unwrap().id, "minecraft:barrel");
+        assert!(chunk.remove_block_entity(1, 2, 3).is_some());
+        assert!(chunk.block_entity(1, 2, 3).is_none());
+    }
+
+    #[test]
+    fn test_for_block() {
+        let chest = BlockEntity::for_block("minecraft:chest", 1, 2, 3).unwrap();
+        assert_eq!(chest.id, "minecraft:chest");
+        assert_eq!(chest.type_id(), Some(1));
+        assert_eq!(chest.client_data().unwrap(), vec![10, 0, 0, 0]);
+
+        let sign = BlockEntity::for_block("minecraft:oak_wall_sign", 1, 2, 3).unwrap();
+        assert_eq!(sign.id, "minecraft:sign");
+        assert_eq!(sign.type_id(), Some(SIGN_TYPE));
+        let hanging = BlockEntity::for_block("minecraft:birch_wall_hanging_sign", 1, 2, 3);
+        assert_eq!(hanging.unwrap().id, "minecraft:hanging_sign");
+        assert!(BlockEntity::for_block("minecraft:stone", 1, 2, 3).is_none());
+    }
+
+    #[test]
+    fn test_sign_client_data() {
+        let sign = BlockEntity::for_block("minecraft:oak_sign", 0, 0, 0).unwrap();
+        let data = sign.client_data().unwrap();
+        assert_eq!(data[..3], [10, 0, 0]);
+        // Reads the compound's contents
+        let tag = nbt_lib::read_tag(&mut std::io::Cursor::new(data[3..].to_vec())).unwrap();
+        let NBTTag::Compound(root) = tag else {
+            panic!("Not a compound: {:?}", tag);
+        };
+        assert_eq!(root.len(), 3);
+        assert!(matches!(root.get("is_waxed"), Some(NBTTag::Byte(0))));
+        let Some(NBTTag::Compound(front)) = root.get("front_text") else {
+            panic!("No front text");
+        };
+        assert!(matches!(front.get("color"), Some(NBTTag::String(color)) if color == "black"));
+        assert!(matches!(front.get("messages"), Some(NBTTag::List(lines)) if lines.len() == 4));
+    }
+}
@@ -1,8 +1,25 @@
+use std::sync::Arc;
+
+use ferrumc_codec::network_types::packed_array::{pack_values, unpack_values};
+use ferrumc_codec::network_types::varint::VarInt;
+use tokio::sync::RwLock;
 use tracing::debug;
 
+use crate::net::packets::outgoing::block_update::BlockUpdate;
+use crate::net::{Connection, ConnectionWrapper};
 use crate::state::GlobalState;
 use crate::utils::binary_utils::read_n_bits_u16;
+use crate::utils::components::chunk_tracker::ChunkTracker;
+use crate::utils::encoding::position::Position;
 use crate::utils::error::Error;
+use crate::world::chunk_format::{
+    unpack_heightmap, BlockEntity, BlockStates, Chunk, Palette, Section,
+};
+use crate::world::conversions::BlockId;
+use crate::world::dimension::Dimension;
+use crate::world::generation::get_or_generate_chunk;
+
+const BLOCKS_PER_SECTION: usize = 16 * 16 * 16;
 
 pub async fn read_block(
     state: GlobalState,
@@ -110,13 +127,446 @@ pub async fn read_block(
     }
 }
 
+/// Whether placing a block where this one is replaces it, instead of going against its face.
+pub fn is_replaceable(block: &Palette) -> bool {
+    block.is_air()
+        || matches!(
+            block.name.as_str(),
+            "minecraft:water"
+                | "minecraft:lava"
+                | "minecraft:grass"
+                | "minecraft:tall_grass"
+                | "minecraft:fern"
+                | "minecraft:large_fern"
+                | "minecraft:dead_bush"
+                | "minecraft:seagrass"
+                | "minecraft:tall_seagrass"
+                | "minecraft:vine"
+                | "minecraft:fire"
+        )
+}
+
+/// Gets a block from the world, generating its chunk if it hasn't been yet.
+pub async fn get_block(
+    state: &GlobalState,
+    x: i32,
+    y: i32,
+    z: i32,
+    dimension: &str,
+) -> Result<Palette, Error> {
+    let (chunk_x, chunk_z) = (x >> 4, z >> 4);
+    let chunk = get_or_generate_chunk(state, chunk_x, chunk_z, dimension)
+        .await?
+        .ok_or(Error::ChunkNotFound(chunk_x, chunk_z))?;
+
+    chunk.get_block(x.rem_euclid(16) as usize, y, z.rem_euclid(16) as usize)
+}
+
+/// The y a player can stand at in a column, on top of the highest block that isn't passable.
+/// Empty columns give the bottom of the world.
+pub async fn surface_height(
+    state: &GlobalState,
+    x: i32,
+    z: i32,
+    dimension: Dimension,
+) -> Result<i32, Error> {
+    let (chunk_x, chunk_z) = (x >> 4, z >> 4);
+    let mut chunk = get_or_generate_chunk(state, chunk_x, chunk_z, dimension.name())
+        .await?
+        .ok_or(Error::ChunkNotFound(chunk_x, chunk_z))?;
+    Ok(chunk.surface_height(x.rem_euclid(16) as usize, z.rem_euclid(16) as usize))
+}
+
+/// Sets a block in the world, saves the chunk and sends the change to every player that has the
+/// chunk loaded. Returns the block that was there before.
+pub async fn set_block(
+    state: GlobalState,
+    x: i32,
+    y: i32,
+    z: i32,
+    block: Palette,
+    dimension: &str,
+) -> Result<Palette, Error> {
+    let id = BlockId::from_palette(&block)
+        .ok_or_else(|| Error::Generic(format!("Block {} not found in block mappings", block)))?;
+    let (chunk_x, chunk_z) = (x >> 4, z >> 4);
+    let lock = state.database.lock_chunk(chunk_x, chunk_z, dimension).await;
+    let mut chunk = get_or_generate_chunk(&state, chunk_x, chunk_z, dimension)
+        .await?
+        .ok_or(Error::ChunkNotFound(chunk_x, chunk_z))?;
+
+    let old = chunk.set_block(
+        x.rem_euclid(16) as usize,
+        y,
+        z.rem_euclid(16) as usize,
+        &block,
+    )?;
+    // Whatever was in a container is lost, since nothing drops yet. New blocks get the block
+    // entity they start with, which the client needs to draw some of them
+    if old.name != block.name {
+        chunk.remove_block_entity(x, y, z);
+        if let Some(block_entity) = BlockEntity::for_block(&block.name, x, y, z) {
+            chunk.set_block_entity(block_entity);
+        }
+    }
+    chunk.recalculate_heightmaps();
+
+    state.database.update_chunk(chunk).await?;
+    drop(lock);
+    broadcast_block_update(&state, (x, y, z), id, dimension).await;
+    Ok(old)
+}
+
+/// Sends a Block Update to every player that has the chunk the block is in loaded, from the same
+/// dimension.
+async fn broadcast_block_update(
+    state: &GlobalState,
+    (x, y, z): (i32, i32, i32),
+    id: BlockId,
+    dimension: &str,
+) {
+    for conn in tracking_chunk(state, (x >> 4, z >> 4), dimension).await {
+        let packet = BlockUpdate::new_auto(Position::new(x, y as i16, z), id.into());
+        if let Err(e) = conn.read().await.send_packet(packet).await {
+            debug!("Failed to send block update: {}", e);
+        }
+    }
+}
+
+/// The connections of the players that have a chunk loaded.
+pub async fn tracking_chunk(
+    state: &GlobalState,
+    chunk: (i32, i32),
+    dimension: &str,
+) -> Vec<Arc<RwLock<Connection>>> {
+    let dimension = Dimension::from_name(dimension);
+    let query = state.world.query::<(&ChunkTracker, &ConnectionWrapper)>();
+    let tracking = query
+        .iter()
+        .await
+        .filter(|(_, (tracker, _))| {
+            tracker.dimension == dimension && tracker.loaded.contains(&chunk)
+        })
+        .map(|(_, (_, conn))| conn.0.clone())
+        .collect::<Vec<_>>();
+    tracking
+}
+
+impl Chunk {
+    /// Gets a block from a chunk that's in the network format, with x and z relative to the chunk.
+    /// Anything above or below the sections is air.
+    pub fn get_block(&self, x: usize, y: i32, z: usize) -> Result<Palette, Error> {
+        match self.section(y) {
+            Some(section) => section.get_block(x, y.rem_euclid(16) as usize, z),
+            None => Ok(Palette::new("minecraft:air")),
+        }
+    }
+
+    /// Sets a block in a chunk that's in the network format, with x and z relative to the chunk.
+    /// Returns the block that was there before. Doesn't update the heightmaps, see
+    /// [recalculate_heightmaps](Chunk::recalculate_heightmaps).
+    pub fn set_block(
+        &mut self,
+        x: usize,
+        y: i32,
+        z: usize,
+        block: &Palette,
+    ) -> Result<Palette, Error> {
+        let (chunk_x, chunk_z) = (self.x_pos, self.z_pos);
+        let section_y = y.div_euclid(16) as i8;
+        let section = self
+            .sections
+            .as_mut()
+            .and_then(|sections| sections.iter_mut().find(|section| section.y == section_y))
+            .ok_or_else(|| {
+                Error::Generic(format!(
+                    "Chunk {} {} does not have a section at y {}",
+                    chunk_x, chunk_z, y
+                ))
+            })?;
+        section.set_block(x, y.rem_euclid(16) as usize, z, block)
+    }
+
+    /// Like [surface_height], with x and z relative to the chunk. Works out the heightmaps if the
+    /// chunk doesn't have them yet.
+    pub fn surface_height(&mut self, x: usize, z: usize) -> i32 {
+        if self.heightmaps.is_none() {
+            self.recalculate_heightmaps();
+        }
+        let heights = self
+            .heightmaps
+            .as_ref()
+            .and_then(|heightmaps| heightmaps.motion_blocking.as_ref())
+            .map(|heightmap| unpack_heightmap(heightmap))
+            .unwrap_or_default();
+        let height = heights.get(z * 16 + x).copied().unwrap_or(0);
+        self.y_pos * 16 + height as i32
+    }
+
+    fn section(&self, y: i32) -> Option<&Section> {
+        let section_y = y.div_euclid(16) as i8;
+        self.sections
+            .as_ref()?
+            .iter()
+            .find(|section| section.y == section_y)
+    }
+}
+
+impl Section {
+    /// Gets a block from a section that's in the network format, with coordinates relative to the
+    /// section.
+    pub fn get_block(&self, x: usize, y: usize, z: usize) -> Result<Palette, Error> {
+        if x >= 16 || y >= 16 || z >= 16 {
+            return Err(Error::Generic(format!(
+                "Block {} {} {} is outside the section",
+                x, y, z
+            )));
+        }
+        let air = Palette::new("minecraft:air");
+        let Some(states) = &self.block_states else {
+            return Ok(air);
+        };
+        let Some(palette) = &states.palette else {
+            return Ok(air);
+        };
+        let index = match &states.data {
+            Some(data) => {
+                let index = (y * 16 + z) * 16 + x;
+                unpack_values(data, bits_for(palette.len()), index + 1)[index] as usize
+            }
+            None => 0,
+        };
+        Ok(palette.get(index).cloned().unwrap_or(air))
+    }
+
+    /// Sets a block in a section that's in the network format, with coordinates relative to the
+    /// section. Returns the block that was there before.
+    pub fn set_block(
+        &mut self,
+        x: usize,
+        y: usize,
+        z: usize,
+        block: &Palette,
+    ) -> Result<Palette, Error> {
+        if x >= 16 || y >= 16 || z >= 16 {
+            return Err(Error::Generic(format!(
+                "Block {} {} {} is outside the section",
+                x, y, z
+            )));
+        }
+        let states = self.block_states.get_or_insert(BlockStates {
+            non_air_blocks: None,
+            bits_per_block: None,
+            data: None,
+            palette: None,
+            net_palette: None,
+        });
+
+        // Empty sections don't have a palette, they're all air
+        let mut palette = states
+            .palette
+            .take()
+            .unwrap_or_else(|| vec![Palette::new("minecraft:air")]);
+        let mut indices = match &states.data {
+            Some(data) => unpack_values(data, bits_for(palette.len()), BLOCKS_PER_SECTION),
+            None => vec![0; BLOCKS_PER_SECTION],
+        };
+
+        let index = (y * 16 + z) * 16 + x;
+        let old = palette[indices[index] as usize].clone();
+        indices[index] = match palette.iter().position(|known| known == block) {
+            Some(known) => known as u16,
+            None => {
+                palette.push(block.clone());
+                (palette.len() - 1) as u16
+            }
+        };
+        compact_palette(&mut palette, &mut indices);
+
+        let net_palette = palette
+            .iter()
+            .map(|block| {
+                BlockId::from_palette(block).map(VarInt::from).ok_or_else(|| {
+                    Error::Generic(format!("Block {} not found in block mappings", block))
+                })
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        let non_air_blocks = indices
+            .iter()
+            .filter(|&&index| !palette[index as usize].is_air())
+            .count();
+
+        if palette.len() == 1 {
+            // Only one block left, so the section goes back to a single value palette
+            states.bits_per_block = Some(0);
+            states.data = None;
+        } else {
+            let bits = bits_for(palette.len());
+            states.bits_per_block = Some(bits as i8);
+            states.data = Some(pack_values(&indices, bits));
+        }
+        states.non_air_blocks = Some(non_air_blocks as i16);
+        states.net_palette = Some(net_palette);
+        states.palette = Some(palette);
+        Ok(old)
+    }
+}
+
+/// Bits per block for a palette, the same as [convert_to_net_mode](crate::world::chunk_format::Chunk::convert_to_net_mode) uses.
+fn bits_for(palette_len: usize) -> u8 {
+    ((palette_len as f32).log2().ceil() as u8).max(4)
+}
+
+/// Removes palette entries that aren't used anymore, so replacing blocks doesn't keep growing it.
+fn compact_palette(palette: &mut Vec<Palette>, indices: &mut [u16]) {
+    let mut used = vec![false; palette.len()];
+    for &index in indices.iter() {
+        used[index as usize] = true;
+    }
+    if used.iter().all(|&used| used) {
+        return;
+    }
+
+    let mut remap = vec![0u16; palette.len()];
+    let mut next = 0;
+    for (old, &used) in used.iter().enumerate() {
+        if used {
+            remap[old] = next;
+            next += 1;
+        }
+    }
+    let mut old = 0;
+    palette.retain(|_| {
+        old += 1;
+        used[old - 1]
+    });
+    for index in indices.iter_mut() {
+        *index = remap[*index as usize];
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use ferrumc_codec::enc::NetEncode;
+    use ferrumc_codec::network_types::varint::VarInt;
     use tokio::net::TcpListener;
     use tracing::{info, warn};
 
     use crate::utils::setup_logger;
     use crate::world::blocks::read_block;
+    use crate::world::chunk_format::Palette;
+    use crate::world::conversions::BlockId;
+    use crate::world::generation::flat::FlatGenerator;
+    use crate::world::generation::WorldGenerator;
+
+    #[test]
+    fn test_set_block_in_empty_section() {
+        let mut chunk = FlatGenerator::default()
+            .generate_chunk(0, 0, "overworld")
+            .unwrap();
+        chunk.convert_to_net_mode().unwrap();
+        let section = &mut chunk.sections.as_mut().unwrap()[5];
+
+        let stone = Palette::new("minecraft:stone");
+        let old = section.set_block(1, 2, 3, &stone).unwrap();
+        assert!(old.is_air());
+
+        let states = section.block_states.as_ref().unwrap();
+        assert_eq!(states.non_air_blocks, Some(1));
+        assert_eq!(states.palette.as_ref().unwrap().len(), 2);
+        assert_eq!(states.net_palette.as_ref().unwrap()[1], VarInt::from(1));
+
+        // Putting the air back drops stone from the palette again
+        let old = section
+            .set_block(1, 2, 3, &Palette::new("minecraft:air"))
+            .unwrap();
+        assert_eq!(old, stone);
+        let states = section.block_states.as_ref().unwrap();
+        assert_eq!(states.non_air_blocks, Some(0));
+        assert_eq!(states.palette.as_ref().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_set_block_keeps_other_blocks() {
+        let mut chunk = FlatGenerator::default()
+            .generate_chunk(0, 0, "overworld")
+            .unwrap();
+        chunk.convert_to_net_mode().unwrap();
+        let section = &mut chunk.sections.as_mut().unwrap()[0];
+
+        let dirt = Palette::new("minecraft:dirt");
+        let old = section.set_block(4, 3, 4, &dirt).unwrap();
+        assert_eq!(old.name, "minecraft:grass_block");
+        // The bedrock layer is still there
+        let old = section.set_block(4, 0, 4, &dirt).unwrap();
+        assert_eq!(old.name, "minecraft:bedrock");
+        assert_eq!(
+            section.set_block(15, 0, 15, &dirt).unwrap().name,
+            "minecraft:bedrock"
+        );
+        assert!(section.set_block(16, 0, 0, &dirt).is_err());
+
+        assert_eq!(section.get_block(4, 0, 4).unwrap(), dirt);
+        assert_eq!(section.get_block(5, 0, 4).unwrap().name, "minecraft:bedrock");
+        assert!(section.get_block(5, 10, 4).unwrap().is_air());
+    }
+
+    #[test]
+    fn test_single_value_sections() {
+        let mut chunk = FlatGenerator::default()
+            .generate_chunk(0, 0, "overworld")
+            .unwrap();
+        chunk.convert_to_net_mode().unwrap();
+        let stone = Palette::new("minecraft:stone");
+        let air = Palette::new("minecraft:air");
+
+        // Filling a section leaves a single value palette with no data
+        for index in 0..4096 {
+            let (x, y, z) = (index % 16, index / 256, (index / 16) % 16);
+            chunk.set_block(x, 16 + y as i32, z, &stone).unwrap();
+        }
+        let section = &chunk.sections.as_ref().unwrap()[5];
+        let states = section.block_states.as_ref().unwrap();
+        assert_eq!(states.bits_per_block, Some(0));
+        assert!(states.data.is_none());
+        assert_eq!(states.palette.as_ref().unwrap(), &vec![stone.clone()]);
+        assert_eq!(states.non_air_blocks, Some(4096));
+
+        // And changing one block goes back to an indirect palette
+        assert_eq!(chunk.set_block(1, 20, 1, &air).unwrap(), stone);
+        assert!(chunk.get_block(1, 20, 1).unwrap().is_air());
+        assert_eq!(chunk.get_block(2, 20, 1).unwrap(), stone);
+        let states = chunk.sections.as_ref().unwrap()[5].block_states.as_ref().unwrap();
+        assert_eq!(states.bits_per_block, Some(4));
+        assert_eq!(states.non_air_blocks, Some(4095));
+
+        // Outside the world is air, but can't be set
+        assert!(chunk.get_block(0, 1000, 0).unwrap().is_air());
+        assert!(chunk.set_block(0, 1000, 0, &stone).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_encode_large_palette_directly() {
+        let mut chunk = FlatGenerator::default()
+            .generate_chunk(0, 0, "overworld")
+            .unwrap();
+        chunk.convert_to_net_mode().unwrap();
+        let section = &mut chunk.sections.as_mut().unwrap()[5];
+        for id in 1..=300 {
+            let block = BlockId(id).to_palette().unwrap();
+            let (x, y, z) = (id as usize % 16, id as usize / 256, (id as usize / 16) % 16);
+            section.set_block(x, y, z, &block).unwrap();
+        }
+        assert_eq!(section.block_states.as_ref().unwrap().bits_per_block, Some(9));
+
+        let mut encoded = Vec::new();
+        section.net_encode(&mut encoded).await.unwrap();
+        // Non-air count, then the bits per block, with no palette after it
+        assert_eq!(encoded[2], 15);
+        let mut cursor = std::io::Cursor::new(encoded[3..].to_vec());
+        let longs = VarInt::read(&mut cursor).await.unwrap();
+        assert_eq!(longs, VarInt::from(4096 / 4));
+    }
 
     #[tokio::test]
     #[ignore]
@@ -1,7 +1,9 @@
 use bincode::{Decode, Encode};
+use ferrumc_codec::network_types::packed_array::{pack_values, unpack_values};
 use ferrumc_codec::network_types::varint::VarInt;
 use serde_derive::{Deserialize, Serialize};
 use std::collections::BTreeMap;
+use std::fmt;
 
 attribute_alias! {
     #[apply(ChunkDerives)] = #[derive(nbt_lib::NBTSerialize, nbt_lib::NBTDeserialize,
@@ -42,6 +44,8 @@ pub struct Chunk {
     #[nbt(rename = "LastUpdate")]
     pub last_update: Option<i64>,
     pub sections: Option<Vec<Section>>,
+    /// Extra data for blocks like chests, see [containers](crate::world::containers).
+    pub block_entities: Option<Vec<BlockEntity>>,
 }
 
 #[apply(ChunkDerives)]
@@ -58,6 +62,57 @@ pub struct Heightmaps {
     pub world_surface: Option<Vec<i64>>,
 }
 
+impl Heightmaps {
+    /// Works out both heightmaps from the blocks in the sections, for a world starting at `min_y`.
+    pub fn compute(sections: &[Section], min_y: i32) -> Self {
+        let mut world_surface = [0u16; 256];
+        let mut motion_blocking = [0u16; 256];
+
+        let mut sections: Vec<&Section> = sections.iter().collect();
+        sections.sort_by_key(|section| std::cmp::Reverse(section.y));
+        for section in sections {
+            let Some((palette, indices)) = section.block_indices() else {
+                continue;
+            };
+            let base = section.y as i32 * 16 - min_y;
+            for column in 0..256 {
+                if motion_blocking[column] != 0 {
+                    // Anything that blocks motion is also the surface, so this column is done
+                    continue;
+                }
+                for y in (0..16).rev() {
+                    let block = &palette[indices[y * 256 + column] as usize];
+                    let height = (base + y as i32 + 1).max(0) as u16;
+                    if world_surface[column] == 0 && !block.is_air() {
+                        world_surface[column] = height;
+                    }
+                    if block.blocks_motion() {
+                        motion_blocking[column] = height;
+                        break;
+                    }
+                }
+            }
+        }
+
+        Self {
+            motion_blocking: Some(pack_heightmap(&motion_blocking)),
+            world_surface: Some(pack_heightmap(&world_surface)),
+        }
+    }
+}
+
+/// Heightmaps are packed like block states, with 9 bits per column, enough for the 384 block tall
+/// world. Each value is the height above the bottom of the world of the top block, or 0 if the
+/// column is empty.
+pub fn pack_heightmap(heights: &[u16]) -> Vec<i64> {
+    pack_values(heights, 9)
+}
+
+/// The opposite of [pack_heightmap].
+pub fn unpack_heightmap(data: &[i64]) -> Vec<u16> {
+    unpack_values(data, 9, 256)
+}
+
 #[apply(ChunkDerives)]
 #[derive(deepsize::DeepSizeOf)]
 pub struct Structures {
@@ -74,6 +129,52 @@ pub struct Starts {}
 #[derive(deepsize::DeepSizeOf)]
 pub struct References {}
 
+/// The data that goes with a block that has more to it than its state, in absolute coordinates.
+/// Only what's needed for containers and signs is kept, anything else vanilla stores is dropped.
+/// See [block_entities](crate::world::block_entities).
+#[apply(ChunkDerives)]
+#[derive(deepsize::DeepSizeOf)]
+pub struct BlockEntity {
+    /// The block entity type, e.g. `minecraft:chest`.
+    pub id: String,
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+    #[nbt(rename = "Items")]
+    pub items: Option<Vec<ContainerItem>>,
+    pub front_text: Option<SignText>,
+    pub back_text: Option<SignText>,
+    /// Waxed signs can't be edited.
+    pub is_waxed: Option<bool>,
+}
+
+/// One side of a sign.
+#[apply(ChunkDerives)]
+#[derive(deepsize::DeepSizeOf)]
+pub struct SignText {
+    /// The four lines, each a JSON text component.
+    pub messages: Vec<String>,
+    /// The dye the text is, e.g. `black`.
+    pub color: String,
+    pub has_glowing_text: bool,
+}
+
+/// An item in a container. Empty slots aren't stored.
+#[apply(ChunkDerives)]
+#[derive(deepsize::DeepSizeOf)]
+pub struct ContainerItem {
+    #[nbt(rename = "Slot")]
+    pub slot: i8,
+    /// The item's name, e.g. `minecraft:stone`.
+    pub id: String,
+    #[nbt(rename = "Count")]
+    pub count: i8,
+    /// The item's NBT as it was sent to the server. Vanilla keeps it as a compound under `tag`,
+    /// which isn't read yet, so imported items come without it.
+    #[nbt(rename = "ferrumc:nbt")]
+    pub nbt: Option<Vec<i8>>,
+}
+
 #[apply(ChunkDerives)]
 #[derive(deepsize::DeepSizeOf)]
 pub struct Section {
@@ -88,6 +189,22 @@ pub struct Section {
     pub sky_light: Option<Vec<i8>>,
 }
 
+impl Section {
+    /// The palette and the palette index of every block, or None if the section is empty.
+    fn block_indices(&self) -> Option<(&[Palette], Vec<u16>)> {
+        let states = self.block_states.as_ref()?;
+        let palette = states.palette.as_deref()?;
+        let indices = match &states.data {
+            Some(data) => {
+                let bits = ((palette.len() as f32).log2().ceil() as u8).max(4);
+                unpack_values(data, bits, 4096)
+            }
+            None => vec![0; 4096],
+        };
+        Some((palette, indices))
+    }
+}
+
 #[apply(ChunkDerives)]
 #[derive(deepsize::DeepSizeOf)]
 pub struct BlockStates {
@@ -111,6 +228,92 @@ pub struct Palette {
     pub properties: Option<BTreeMap<String, String>>,
 }
 
+impl Palette {
+    /// A block state without any properties, e.g. `minecraft:stone`.
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            properties: None,
+        }
+    }
+
+    pub fn with_property(mut self, key: &str, value: &str) -> Self {
+        self.properties
+            .get_or_insert_with(BTreeMap::new)
+            .insert(key.to_string(), value.to_string());
+        self
+    }
+
+    pub fn is_air(&self) -> bool {
+        matches!(
+            self.name.as_str(),
+            "minecraft:air" | "minecraft:cave_air" | "minecraft:void_air"
+        )
+    }
+
+    /// Whether the block counts for the MOTION_BLOCKING heightmap, which is anything solid or with
+    /// fluid in it. Plants and other blocks you can walk through don't.
+    pub fn blocks_motion(&self) -> bool {
+        if self.is_air() {
+            return false;
+        }
+        let waterlogged = self
+            .properties
+            .as_ref()
+            .is_some_and(|properties| properties.get("waterlogged").is_some_and(|w| w == "true"));
+        if waterlogged {
+            return true;
+        }
+        let name = self.name.trim_start_matches("minecraft:");
+        let passable = matches!(
+            name,
+            "grass"
+                | "tall_grass"
+                | "fern"
+                | "large_fern"
+                | "dead_bush"
+                | "dandelion"
+                | "poppy"
+                | "blue_orchid"
+                | "allium"
+                | "azure_bluet"
+                | "oxeye_daisy"
+                | "cornflower"
+                | "lily_of_the_valley"
+                | "sunflower"
+                | "lilac"
+                | "rose_bush"
+                | "peony"
+                | "torch"
+                | "wall_torch"
+                | "redstone_wire"
+                | "vine"
+                | "snow"
+                | "sugar_cane"
+                | "fire"
+        ) || ["_sapling", "_tulip", "_rail", "_button", "_carpet", "_mushroom"]
+            .iter()
+            .any(|suffix| name.ends_with(suffix))
+            || name == "rail";
+        !passable
+    }
+}
+
+impl fmt::Display for Palette {
+    /// Like a block in a command, e.g. `minecraft:oak_log[axis=y]`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)?;
+        if let Some(properties) = self.properties.as_ref().filter(|p| !p.is_empty()) {
+            let properties = properties
+                .iter()
+                .map(|(key, value)| format!("{}={}", key, value))
+                .collect::<Vec<_>>();
+            write!(f, "[{}]", properties.join(","))?;
+        }
+        Ok(())
+    }
+}
+
 #[apply(ChunkDerives)]
 #[derive(deepsize::DeepSizeOf)]
 pub struct Properties {
@@ -132,4 +335,74 @@ pub struct Properties {
 #[derive(deepsize::DeepSizeOf)]
 pub struct Biomes {
     pub palette: Vec<String>,
+    /// Indices into the palette for each 4x4x4 cell, packed the same way as block states. Missing if
+    /// there's only one biome in the section.
+    pub data: Option<Vec<i64>>,
+}
+
+impl Chunk {
+    /// Recomputes the heightmaps from the sections, e.g. after blocks have been changed.
+    pub fn recalculate_heightmaps(&mut self) {
+        let sections = self.sections.as_deref().unwrap_or_default();
+        self.heightmaps = Some(Heightmaps::compute(sections, self.y_pos * 16));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn section(y: i8, palette: Vec<Palette>, indices: &[u16]) -> Section {
+        Section {
+            block_states: Some(BlockStates {
+                non_air_blocks: None,
+                bits_per_block: None,
+                data: Some(pack_values(indices, 4)),
+                palette: Some(palette),
+                net_palette: None,
+            }),
+            biomes: None,
+            y,
+            block_light: None,
+            sky_light: None,
+        }
+    }
+
+    #[test]
+    fn test_pack_heightmap() {
+        assert_eq!(pack_heightmap(&[0; 256]).len(), 37);
+        assert_eq!(unpack_heightmap(&pack_heightmap(&[300; 256])), vec![300; 256]);
+    }
+
+    #[test]
+    fn test_compute_heightmaps() {
+        let mut lower = vec![0u16; 4096];
+        let mut upper = vec![0u16; 4096];
+        // Stone all the way up to y 0 in column 0, with grass on top
+        for y in 0..16 {
+            lower[y * 256] = 1;
+        }
+        upper[0] = 2;
+        // Just stone at y 3 in column 1
+        upper[3 * 256 + 1] = 1;
+        let palette = vec![
+            Palette::new("minecraft:air"),
+            Palette::new("minecraft:stone"),
+            Palette::new("minecraft:grass"),
+        ];
+        let sections = vec![
+            section(0, palette.clone(), &upper),
+            section(-1, palette, &lower),
+        ];
+
+        let heightmaps = Heightmaps::compute(&sections, -64);
+        let surface = unpack_heightmap(&heightmaps.world_surface.unwrap());
+        let motion = unpack_heightmap(&heightmaps.motion_blocking.unwrap());
+        assert_eq!(surface[0], 65);
+        assert_eq!(motion[0], 64);
+        assert_eq!(surface[1], 68);
+        assert_eq!(motion[1], 68);
+        assert_eq!(surface[2], 0);
+        assert_eq!(motion[2], 0);
+    }
 }
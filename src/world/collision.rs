@@ -0,0 +1,316 @@
+//! What blocks entities bump into.
+//!
+//! The block mappings only have names and properties, so shapes are worked out from those. The
+//! blocks entities run into most have their real shape: slabs, stairs, snow, carpets, fences and
+//! the like. Anything else solid is a full cube, and anything that can be walked through has no
+//! shape at all.
+
+use crate::world::chunk_format::Palette;
+
+/// A box, in blocks. For a block's shape it's relative to the block's corner, otherwise it's in
+/// the world.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb {
+    pub min: [f64; 3],
+    pub max: [f64; 3],
+}
+
+impl Aabb {
+    pub const fn new(min: [f64; 3], max: [f64; 3]) -> Self {
+        Self { min, max }
+    }
+
+    /// The box of something `width` wide and `height` tall, standing at `position`.
+    pub fn standing_at(position: (f64, f64, f64), width: f64, height: f64) -> Self {
+        let (x, y, z) = position;
+        let radius = width / 2.0;
+        Self::new(
+            [x - radius, y, z - radius],
+            [x + radius, y + height, z + radius],
+        )
+    }
+
+    pub fn offset(&self, (x, y, z): (f64, f64, f64)) -> Self {
+        Self::new(
+            [self.min[0] + x, self.min[1] + y, self.min[2] + z],
+            [self.max[0] + x, self.max[1] + y, self.max[2] + z],
+        )
+    }
+
+    /// The box grown by `by` on every side.
+    pub fn inflate(&self, by: f64) -> Self {
+        Self::new(self.min.map(|min| min - by), self.max.map(|max| max + by))
+    }
+
+    /// Whether the boxes overlap, rather than just touching.
+    pub fn intersects(&self, other: &Aabb) -> bool {
+        (0..3).all(|axis| self.overlaps_on(other, axis))
+    }
+
+    fn overlaps_on(&self, other: &Aabb, axis: usize) -> bool {
+        self.min[axis] < other.max[axis] - EPSILON && other.min[axis] < self.max[axis] - EPSILON
+    }
+
+    /// How far this box can move along `axis`, up to `distance`, before it runs into `other`.
+    pub fn clip(&self, other: &Aabb, axis: usize, distance: f64) -> f64 {
+        let mut others = (0..3).filter(|&other_axis| other_axis != axis);
+        if !others.all(|other_axis| self.overlaps_on(other, other_axis)) {
+            return distance;
+        }
+        if distance > 0.0 && other.min[axis] >= self.max[axis] - EPSILON {
+            distance.min(other.min[axis] - self.max[axis])
+        } else if distance < 0.0 && other.max[axis] <= self.min[axis] + EPSILON {
+            distance.max(other.max[axis] - self.min[axis])
+        } else {
+            distance
+        }
+    }
+
+    /// How far along the line from `from` to `to` it first goes into this box, from 0 to 1, or
+    /// nothing if it misses.
+    pub fn ray_hit(&self, from: (f64, f64, f64), to: (f64, f64, f64)) -> Option<f64> {
+        let (from, to) = ([from.0, from.1, from.2], [to.0, to.1, to.2]);
+        let (mut enter, mut exit) = (0.0_f64, 1.0_f64);
+        for axis in 0..3 {
+            let delta = to[axis] - from[axis];
+            if delta.abs() < EPSILON {
+                // Parallel to this side, so it's either always between them or never
+                if from[axis] < self.min[axis] || from[axis] > self.max[axis] {
+                    return None;
+                }
+                continue;
+            }
+            let a = (self.min[axis] - from[axis]) / delta;
+            let b = (self.max[axis] - from[axis]) / delta;
+            enter = enter.max(a.min(b));
+            exit = exit.min(a.max(b));
+        }
+        (enter <= exit).then_some(enter)
+    }
+
+    /// The blocks this box is in or touches, and the ones below those, which might have something
+    /// sticking up out of them like a fence.
+    pub fn blocks(&self) -> Vec<(i32, i32, i32)> {
+        let from = self.min.map(|min| min.floor() as i32);
+        let to = self.max.map(|max| (max - EPSILON).floor() as i32);
+        let mut blocks = Vec::new();
+        for x in from[0]..=to[0] {
+            for y in from[1] - 1..=to[1] {
+                for z in from[2]..=to[2] {
+                    blocks.push((x, y, z));
+                }
+            }
+        }
+        blocks
+    }
+}
+
+/// Boxes this close are touching.
+const EPSILON: f64 = 1e-7;
+/// A sixteenth of a block, the size of a pixel in a block's texture.
+const PIXEL: f64 = 1.0 / 16.0;
+
+pub static FULL_BLOCK: &[Aabb] = &[Aabb::new([0.0; 3], [1.0; 3])];
+static BOTTOM_SLAB: &[Aabb] = &[Aabb::new([0.0; 3], [1.0, 0.5, 1.0])];
+static TOP_SLAB: &[Aabb] = &[Aabb::new([0.0, 0.5, 0.0], [1.0; 3])];
+static BOTTOM_TRAPDOOR: &[Aabb] = &[Aabb::new([0.0; 3], [1.0, 3.0 * PIXEL, 1.0])];
+static TOP_TRAPDOOR: &[Aabb] = &[Aabb::new([0.0, 13.0 * PIXEL, 0.0], [1.0; 3])];
+/// Fences and walls are taller than a block, so they can't be jumped over.
+static FENCE: &[Aabb] = &[Aabb::new([0.0; 3], [1.0, 1.5, 1.0])];
+static CHEST: &[Aabb] = &[Aabb::new([PIXEL, 0.0, PIXEL], [1.0 - PIXEL, 0.875, 1.0 - PIXEL])];
+static CACTUS: &[Aabb] = &[Aabb::new([PIXEL, 0.0, PIXEL], [1.0 - PIXEL, 1.0, 1.0 - PIXEL])];
+static CARPET: &[Aabb] = &[Aabb::new([0.0; 3], [1.0, PIXEL, 1.0])];
+static BED: &[Aabb] = &[Aabb::new([0.0; 3], [1.0, 9.0 * PIXEL, 1.0])];
+static SOUL_SAND: &[Aabb] = &[Aabb::new([0.0; 3], [1.0, 14.0 * PIXEL, 1.0])];
+static FARMLAND: &[Aabb] = &[Aabb::new([0.0; 3], [1.0, 15.0 * PIXEL, 1.0])];
+/// Snow, by how many layers there are. The first layer has no shape.
+static SNOW: [Aabb; 8] = {
+    let mut layers = [Aabb::new([0.0; 3], [1.0, 0.0, 1.0]); 8];
+    let mut layer = 1;
+    while layer < 8 {
+        layers[layer].max[1] = layer as f64 / 8.0;
+        layer += 1;
+    }
+    layers
+};
+/// Thin panels along one side, like a ladder or a closed door. North, south, west, east.
+static PANELS: [Aabb; 4] = [
+    Aabb::new([0.0; 3], [1.0, 1.0, 3.0 * PIXEL]),
+    Aabb::new([0.0, 0.0, 13.0 * PIXEL], [1.0; 3]),
+    Aabb::new([0.0; 3], [3.0 * PIXEL, 1.0, 1.0]),
+    Aabb::new([13.0 * PIXEL, 0.0, 0.0], [1.0; 3]),
+];
+/// Stairs facing north, south, west and east, the right way up then upside down. Their corner
+/// shapes are left out.
+static STAIRS: [[Aabb; 2]; 8] = {
+    let mut stairs = [[Aabb::new([0.0; 3], [1.0; 3]); 2]; 8];
+    let mut i = 0;
+    while i < 8 {
+        let top = i >= 4;
+        let (slab_y, step_y) = if top { (0.5, 0.0) } else { (0.0, 0.5) };
+        stairs[i][0] = Aabb::new([0.0, slab_y, 0.0], [1.0, slab_y + 0.5, 1.0]);
+        let mut step = Aabb::new([0.0, step_y, 0.0], [1.0, step_y + 0.5, 1.0]);
+        // The step is on the side the stairs face
+        match i % 4 {
+            0 => step.max[2] = 0.5,
+            1 => step.min[2] = 0.5,
+            2 => step.max[0] = 0.5,
+            _ => step.min[0] = 0.5,
+        }
+        stairs[i][1] = step;
+        i += 1;
+    }
+    stairs
+};
+
+/// The boxes a block is made of, relative to its corner.
+pub fn collision_shape(block: &Palette) -> &'static [Aabb] {
+    let name = block.name.trim_start_matches("minecraft:");
+    let property = |key: &str| {
+        block
+            .properties
+            .as_ref()
+            .and_then(|properties| properties.get(key))
+            .map(String::as_str)
+    };
+    let facing = || match property("facing") {
+        Some("south") => 1,
+        Some("west") => 2,
+        Some("east") => 3,
+        _ => 0,
+    };
+
+    if name.ends_with("_carpet") {
+        return CARPET;
+    }
+    if name == "snow" {
+        let layers = property("layers").and_then(|layers| layers.parse::<usize>().ok());
+        let layers = layers.unwrap_or(1).clamp(1, 8);
+        return std::slice::from_ref(&SNOW[layers - 1]);
+    }
+    if !block.blocks_motion()
+        || matches!(
+            name,
+            "water"
+                | "lava"
+                | "bubble_column"
+                | "seagrass"
+                | "tall_seagrass"
+                | "kelp"
+                | "kelp_plant"
+                | "cobweb"
+                | "light"
+                | "structure_void"
+                | "nether_portal"
+                | "end_portal"
+                | "end_gateway"
+                | "lever"
+                | "tripwire"
+                | "sweet_berry_bush"
+        )
+        || ["_sign", "_banner", "_pressure_plate", "_torch", "_coral", "_coral_fan"]
+            .iter()
+            .any(|suffix| name.ends_with(suffix))
+    {
+        return &[];
+    }
+
+    match name {
+        _ if name.ends_with("_slab") => match property("type") {
+            Some("top") => TOP_SLAB,
+            Some("double") => FULL_BLOCK,
+            _ => BOTTOM_SLAB,
+        },
+        _ if name.ends_with("_stairs") => {
+            let top = property("half") == Some("top");
+            &STAIRS[facing() + if top { 4 } else { 0 }]
+        }
+        _ if name.ends_with("_fence_gate") => match property("open") {
+            Some("true") => &[],
+            _ => FENCE,
+        },
+        _ if name.ends_with("_fence") || name.ends_with("_wall") => FENCE,
+        // Open doors and trapdoors swing out of the way
+        _ if name.ends_with("_trapdoor") => match (property("open"), property("half")) {
+            (Some("true"), _) => &[],
+            (_, Some("top")) => TOP_TRAPDOOR,
+            _ => BOTTOM_TRAPDOOR,
+        },
+        // Doors and ladders sit against the side opposite the way they face
+        _ if name.ends_with("_door") || name == "ladder" => match property("open") {
+            Some("true") => &[],
+            _ => std::slice::from_ref(&PANELS[facing() ^ 1]),
+        },
+        _ if name.ends_with("_bed") => BED,
+        "chest" | "trapped_chest" | "ender_chest" => CHEST,
+        "cactus" => CACTUS,
+        "soul_sand" | "mud" => SOUL_SAND,
+        "farmland" | "dirt_path" => FARMLAND,
+        _ => FULL_BLOCK,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clip() {
+        let entity = Aabb::standing_at((0.5, 64.0, 0.5), 0.6, 1.8);
+        let floor = Aabb::new([0.0, 63.0, 0.0], [1.0, 64.0, 1.0]);
+        assert_eq!(entity.clip(&floor, 1, -0.5), 0.0);
+        assert_eq!(entity.clip(&floor, 1, 0.5), 0.5);
+        // Off to the side, so it can fall past
+        let beside = floor.offset((1.0, 0.0, 0.0));
+        assert_eq!(entity.clip(&beside, 1, -0.5), -0.5);
+        assert_eq!(entity.offset((0.0, 0.5, 0.0)).clip(&floor, 1, -2.0), -0.5);
+
+        let wall = Aabb::new([1.5, 64.0, 0.0], [2.5, 65.0, 1.0]);
+        assert!((entity.clip(&wall, 0, 1.0) - 0.7).abs() < 1e-9);
+        assert!(!entity.intersects(&wall) && !entity.intersects(&floor));
+        assert!(entity.intersects(&floor.offset((0.0, 0.5, 0.0))));
+    }
+
+    #[test]
+    fn test_ray_hit() {
+        let target = Aabb::new([0.0; 3], [1.0; 3]);
+        assert_eq!(target.ray_hit((-1.0, 0.5, 0.5), (1.0, 0.5, 0.5)), Some(0.5));
+        // Already inside
+        assert_eq!(target.ray_hit((0.5, 0.5, 0.5), (3.0, 0.5, 0.5)), Some(0.0));
+        // Stops short, passes over, and runs alongside
+        assert_eq!(target.ray_hit((-2.0, 0.5, 0.5), (-0.5, 0.5, 0.5)), None);
+        assert_eq!(target.ray_hit((-1.0, 1.5, 0.5), (2.0, 1.2, 0.5)), None);
+        assert_eq!(target.ray_hit((-1.0, 2.0, 0.5), (2.0, 2.0, 0.5)), None);
+        let diagonal = target.ray_hit((-1.0, 2.0, 0.5), (1.0, 0.0, 0.5)).unwrap();
+        assert!((diagonal - 0.5).abs() < 1e-9);
+        assert_eq!(target.inflate(0.5).ray_hit((-1.0, 1.2, 0.5), (1.0, 1.2, 0.5)), Some(0.25));
+    }
+
+    #[test]
+    fn test_collision_shape() {
+        let shape = |block: Palette| collision_shape(&block);
+        assert_eq!(shape(Palette::new("minecraft:stone")), FULL_BLOCK);
+        assert!(shape(Palette::new("minecraft:air")).is_empty());
+        assert!(shape(Palette::new("minecraft:water")).is_empty());
+        assert!(shape(Palette::new("minecraft:poppy")).is_empty());
+        assert!(shape(Palette::new("minecraft:oak_sign")).is_empty());
+
+        let slab = Palette::new("minecraft:oak_slab");
+        assert_eq!(shape(slab.clone().with_property("type", "bottom")), BOTTOM_SLAB);
+        assert_eq!(shape(slab.with_property("type", "top")), TOP_SLAB);
+        let snow = shape(Palette::new("minecraft:snow").with_property("layers", "4"));
+        assert_eq!(snow[0].max[1], 0.375);
+        assert_eq!(shape(Palette::new("minecraft:oak_fence"))[0].max[1], 1.5);
+
+        let stairs = Palette::new("minecraft:oak_stairs")
+            .with_property("facing", "east")
+            .with_property("half", "bottom");
+        let stairs = shape(stairs);
+        assert_eq!(stairs.len(), 2);
+        assert_eq!((stairs[1].min, stairs[1].max), ([0.5, 0.5, 0.0], [1.0; 3]));
+
+        let door = Palette::new("minecraft:oak_door").with_property("facing", "north");
+        assert_eq!(shape(door.clone())[0].min[2], 13.0 * PIXEL);
+        assert!(shape(door.with_property("open", "true")).is_empty());
+    }
+}
@@ -0,0 +1,340 @@
+//! Blocks that hold items, like chests and furnaces.
+//!
+//! What's in a container is kept in its chunk as a [BlockEntity], which is made when the container
+//! is placed or first opened, and removed when the block is replaced. Furnaces only hold their
+//! items, nothing smelts in them yet.
+
+use std::collections::HashMap;
+
+use crate::net::packets::outgoing::open_screen::OpenScreen;
+use crate::net::packets::outgoing::set_container_content::SetContainerContent;
+use crate::net::packets::ConnectionId;
+use crate::state::GlobalState;
+use crate::utils::components::inventory::Inventory;
+use crate::utils::components::open_container::OpenContainer;
+use crate::utils::encoding::position::Position;
+use crate::utils::encoding::slot::ItemStack;
+use crate::utils::prelude::*;
+use crate::utils::text_component::TextComponent;
+use crate::world::chunk_format::{BlockEntity, ContainerItem};
+use crate::world::generation::get_or_generate_chunk;
+use crate::world::items::ItemRegistry;
+
+#[derive(Debug, PartialEq)]
+pub struct ContainerType {
+    /// The block, which is also the id of its block entity.
+    pub block: &'static str,
+    pub slots: i16,
+    /// The window it opens, from the `minecraft:menu` registry.
+    pub menu: i32,
+    /// The translation key of the window's title.
+    pub title: &'static str,
+    /// From the `minecraft:block_entity_type` registry.
+    pub block_entity_type: i32,
+}
+
+const CONTAINERS: &[ContainerType] = &[
+    ContainerType {
+        block: "minecraft:chest",
+        slots: 27,
+        menu: 2,
+        title: "container.chest",
+        block_entity_type: 1,
+    },
+    ContainerType {
+        block: "minecraft:trapped_chest",
+        slots: 27,
+        menu: 2,
+        title: "container.chest",
+        block_entity_type: 2,
+    },
+    ContainerType {
+        block: "minecraft:barrel",
+        slots: 27,
+        menu: 2,
+        title: "container.barrel",
+        block_entity_type: 26,
+    },
+    ContainerType {
+        block: "minecraft:furnace",
+        slots: 3,
+        menu: 13,
+        title: "container.furnace",
+        block_entity_type: 0,
+    },
+    ContainerType {
+        block: "minecraft:smoker",
+        slots: 3,
+        menu: 21,
+        title: "container.smoker",
+        block_entity_type: 27,
+    },
+    ContainerType {
+        block: "minecraft:blast_furnace",
+        slots: 3,
+        menu: 9,
+        title: "container.blast_furnace",
+        block_entity_type: 28,
+    },
+];
+
+/// Slots in the player's inventory that show up under a container: the main inventory, then the
+/// hotbar.
+const INVENTORY_SLOTS: i16 = 36;
+
+impl ContainerType {
+    /// The container a block or block entity is, from its name.
+    pub fn get(name: &str) -> Option<&'static Self> {
+        CONTAINERS.iter().find(|container| container.block == name)
+    }
+
+    /// Slots in the window, the container's and the player's.
+    pub fn window_slots(&self) -> i16 {
+        self.slots + INVENTORY_SLOTS
+    }
+
+    /// The slot in the player's inventory that a slot of the window shows, if it's not one of the
+    /// container's.
+    pub fn inventory_slot(&self, window_slot: i16) -> Option<i16> {
+        let slot = window_slot - self.slots;
+        (0..INVENTORY_SLOTS)
+            .contains(&slot)
+            .then_some(Inventory::MAIN_START + slot)
+    }
+
+    /// The window's slots, keyed by their number in the window.
+    pub fn window(
+        &self,
+        container: &[Option<ItemStack>],
+        inventory: &Inventory,
+    ) -> HashMap<i16, ItemStack> {
+        let mut window = HashMap::new();
+        for (slot, item) in container.iter().enumerate() {
+            if let Some(item) = item {
+                window.insert(slot as i16, item.clone());
+            }
+        }
+        for slot in self.slots..self.window_slots() {
+            let item = self
+                .inventory_slot(slot)
+                .and_then(|slot| inventory.slots.get(&slot));
+            if let Some(item) = item {
+                window.insert(slot, item.clone());
+            }
+        }
+        window
+    }
+
+    /// Puts the slots of a [window](ContainerType::window) back in the player's inventory, and
+    /// returns the container's.
+    pub fn split_window(
+        &self,
+        mut window: HashMap<i16, ItemStack>,
+        inventory: &mut Inventory,
+    ) -> Vec<Option<ItemStack>> {
+        for slot in self.slots..self.window_slots() {
+            if let Some(inventory_slot) = self.inventory_slot(slot) {
+                inventory.set_slot(inventory_slot, window.remove(&slot));
+            }
+        }
+        (0..self.slots).map(|slot| window.remove(&slot)).collect()
+    }
+}
+
+impl BlockEntity {
+    /// What's in each of the first `slots` slots. Items the server doesn't know come out empty.
+    pub fn container_slots(&self, slots: i16, items: &ItemRegistry) -> Vec<Option<ItemStack>> {
+        let mut container = vec![None; slots.max(0) as usize];
+        for item in self.items.iter().flatten() {
+            let (Some(slot), Some(id)) = (container.get_mut(item.slot as usize), items.id(&item.id))
+            else {
+                continue;
+            };
+            let mut stack = ItemStack::new(id, item.count);
+            if let Some(nbt) = &item.nbt {
+                stack.nbt = nbt.iter().map(|&byte| byte as u8).collect();
+            }
+            *slot = Some(stack);
+        }
+        container
+    }
+
+    /// Replaces what's in the container. Items are stored by name, so ones without a name in the
+    /// registry are dropped. Stored items the server doesn't know are kept, unless something has
+    /// been put in their slot.
+    pub fn set_container_slots(&mut self, container: &[Option<ItemStack>], items: &ItemRegistry) {
+        let unknown = self
+            .items
+            .take()
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|item| {
+                items.id(&item.id).is_none()
+                    && container
+                        .get(item.slot as usize)
+                        .is_none_or(|slot| slot.is_none())
+            });
+        let known = container.iter().enumerate().filter_map(|(slot, item)| {
+            let item = item.as_ref()?;
+            Some(ContainerItem {
+                slot: slot as i8,
+                id: items.name(item.id)?.to_string(),
+                count: item.count,
+                // Only if it isn't just an empty tag
+                nbt: (item.tag() != [0]).then(|| item.nbt.iter().map(|&byte| byte as i8).collect()),
+            })
+        });
+        let mut all = known.chain(unknown).collect::<Vec<_>>();
+        all.sort_by_key(|item| item.slot);
+        self.items = Some(all);
+    }
+}
+
+/// Opens the container at `position` for a player, if there's one there. Returns whether there
+/// was.
+pub async fn open_container(
+    state: &GlobalState,
+    conn_id: ConnectionId,
+    position: &Position,
+    dimension: &str,
+) -> Result<bool> {
+    // Items are stored by name, which takes the item registry
+    if state.items.is_empty() {
+        return Ok(false);
+    }
+    let Position { x, y, z } = *position;
+    let (chunk_x, chunk_z) = (x >> 4, z >> 4);
+    let chunk = get_or_generate_chunk(state, chunk_x, chunk_z, dimension)
+        .await?
+        .ok_or(Error::ChunkNotFound(chunk_x, chunk_z))?;
+    let block = chunk.get_block(x.rem_euclid(16) as usize, y as i32, z.rem_euclid(16) as usize)?;
+    let Some(container) = ContainerType::get(&block.name) else {
+        return Ok(false);
+    };
+    let slots = match chunk.block_entity(x, y as i32, z) {
+        Some(block_entity) => block_entity.container_slots(container.slots, &state.items),
+        None => vec![None; container.slots as usize],
+    };
+
+    let (window_id, content) = {
+        let mut inventory = state
+            .world
+            .get_component_storage()
+            .get_mut_or_insert_with::<Inventory>(conn_id, Default::default)
+            .await;
+        let window_id = inventory.next_window_id();
+        inventory.next_state_id();
+        let content = SetContainerContent::container(window_id, container, &slots, &inventory);
+        (window_id, content)
+    };
+    state.world.get_component_storage().insert(
+        conn_id,
+        OpenContainer {
+            window_id,
+            position: position.clone(),
+            dimension: dimension.to_string(),
+            container,
+        },
+    );
+
+    let title = TextComponent::translate(container.title, Vec::new());
+    let conn = state.connections.get_connection(conn_id)?;
+    let conn = conn.read().await;
+    conn.send_packet(OpenScreen::new(window_id, container.menu, &title))
+        .await?;
+    conn.send_packet(content).await?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn items() -> ItemRegistry {
+        ItemRegistry::parse(
+            r#"{
+                "minecraft:item": {
+                    "entries": {
+                        "minecraft:stone": { "protocol_id": 1 },
+                        "minecraft:dirt": { "protocol_id": 2 }
+                    }
+                }
+            }"#,
+        )
+        .unwrap()
+    }
+
+    fn stored(slot: i8, id: &str, count: i8) -> ContainerItem {
+        ContainerItem {
+            slot,
+            id: id.to_string(),
+            count,
+            nbt: None,
+        }
+    }
+
+    #[test]
+    fn test_container_slots() {
+        let items = items();
+        let mut chest = BlockEntity::new("minecraft:chest", 1, 2, 3);
+        chest.items = Some(vec![
+            stored(0, "minecraft:stone", 5),
+            stored(3, "minecraft:moon_rock", 1),
+            stored(40, "minecraft:dirt", 1),
+        ]);
+
+        let mut slots = chest.container_slots(27, &items);
+        assert_eq!(slots.len(), 27);
+        assert_eq!(slots[0], Some(ItemStack::new(1, 5)));
+        assert_eq!(slots[3], None);
+        assert_eq!(slots.iter().flatten().count(), 1);
+
+        // Unknown items stay where they are until something replaces them
+        slots[0] = None;
+        slots[1] = Some(ItemStack::new(2, 7));
+        chest.set_container_slots(&slots, &items);
+        assert_eq!(
+            chest.items,
+            Some(vec![
+                stored(1, "minecraft:dirt", 7),
+                stored(3, "minecraft:moon_rock", 1)
+            ])
+        );
+        slots[3] = Some(ItemStack {
+            nbt: vec![10, 0, 0, 0],
+            ..ItemStack::new(1, 1)
+        });
+        chest.set_container_slots(&slots, &items);
+        assert_eq!(chest.items.as_ref().unwrap()[1].nbt, Some(vec![10, 0, 0, 0]));
+        assert_eq!(chest.container_slots(27, &items), slots);
+    }
+
+    #[test]
+    fn test_window() {
+        let chest = ContainerType::get("minecraft:chest").unwrap();
+        assert_eq!(chest.window_slots(), 63);
+        assert_eq!(chest.inventory_slot(26), None);
+        assert_eq!(chest.inventory_slot(27), Some(Inventory::MAIN_START));
+        assert_eq!(chest.inventory_slot(54), Some(Inventory::HOTBAR_START));
+        assert_eq!(chest.inventory_slot(63), None);
+
+        let mut inventory = Inventory::default();
+        inventory.set_slot(Inventory::HOTBAR_START, Some(ItemStack::new(1, 1)));
+        inventory.set_slot(Inventory::OFFHAND, Some(ItemStack::new(2, 1)));
+        let mut container = vec![None; 27];
+        container[5] = Some(ItemStack::new(2, 3));
+
+        let mut window = chest.window(&container, &inventory);
+        assert_eq!(window.len(), 2);
+        assert_eq!(window.get(&54), Some(&ItemStack::new(1, 1)));
+        // Moving the item from the hotbar into the chest
+        let item = window.remove(&54).unwrap();
+        window.insert(0, item);
+        let container = chest.split_window(window, &mut inventory);
+        assert_eq!(container[0], Some(ItemStack::new(1, 1)));
+        assert_eq!(container[5], Some(ItemStack::new(2, 3)));
+        assert_eq!(inventory.slots.get(&Inventory::HOTBAR_START), None);
+        // The offhand isn't in the window, so it's left alone
+        assert_eq!(inventory.slots.get(&Inventory::OFFHAND), Some(&ItemStack::new(2, 1)));
+    }
+}
@@ -1,30 +1,163 @@
 use crate::utils::error::Error;
 use crate::world::chunk_format::{BlockStates, Chunk, Palette, Section};
 use ferrumc_codec::enc::NetEncode;
-use ferrumc_codec::network_types::varint::VarInt;
+use ferrumc_codec::network_types::packed_array::PackedArray;
+use ferrumc_codec::network_types::varint::{encode_varints, VarInt};
 use hashbrown::HashMap;
 use lazy_static::lazy_static;
+use serde_derive::Deserialize;
 use std::io::Read;
-use tokio::io::AsyncWrite;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
 use tracing::trace;
 
 const BLOCKSFILE: &[u8] = include_bytes!("../../.etc/blockmappings.bz2");
 
+/// An entry in the block mappings, which also marks the state each block is in by default.
+#[derive(Deserialize)]
+struct BlockMapping {
+    #[serde(flatten)]
+    palette: Palette,
+    #[serde(default)]
+    default: bool,
+}
+
 lazy_static! {
-    static ref ID2BLOCK: HashMap<i32, Palette> = {
+    static ref MAPPINGS: HashMap<i32, BlockMapping> = {
         let mut bzipreader = bzip2::read::BzDecoder::new(BLOCKSFILE);
         let mut output = String::new();
         bzipreader.read_to_string(&mut output).unwrap();
-        let string_keys: HashMap<String, Palette> = serde_json::from_str(&output).unwrap();
+        let string_keys: HashMap<String, BlockMapping> = serde_json::from_str(&output).unwrap();
         string_keys
-            .iter()
-            .map(|(k, v)| (k.parse::<i32>().unwrap(), v.clone()))
+            .into_iter()
+            .map(|(k, v)| (k.parse::<i32>().unwrap(), v))
             .collect()
     };
+    static ref ID2BLOCK: HashMap<i32, Palette> = MAPPINGS
+        .iter()
+        .map(|(k, v)| (*k, v.palette.clone()))
+        .collect();
     static ref BLOCK2ID: HashMap<Palette, i32> =
         ID2BLOCK.iter().map(|(k, v)| (v.clone(), *k)).collect();
+    static ref DEFAULT_STATES: HashMap<String, Palette> = {
+        let mut ids: Vec<_> = MAPPINGS.keys().copied().collect();
+        ids.sort();
+
+        let mut defaults: HashMap<String, (usize, Palette)> = HashMap::new();
+        for id in ids {
+            let mapping = &MAPPINGS[&id];
+            // The mappings only mark the default for blocks without properties
+            let score = if mapping.default {
+                usize::MAX
+            } else {
+                default_property_score(&mapping.palette)
+            };
+            let name = mapping.palette.name.clone();
+            match defaults.get(&name) {
+                Some((best, _)) if *best >= score => {}
+                _ => {
+                    defaults.insert(name, (score, mapping.palette.clone()));
+                }
+            }
+        }
+        defaults.into_iter().map(|(name, (_, block))| (name, block)).collect()
+    };
+}
+
+/// Property values most blocks have by default. The state with the most of them is used as the
+/// default, with the lowest ID winning ties.
+const DEFAULT_PROPERTIES: &[(&str, &str)] = &[
+    ("axis", "y"),
+    ("waterlogged", "false"),
+    ("half", "bottom"),
+    ("type", "bottom"),
+    ("powered", "false"),
+    ("open", "false"),
+    ("lit", "false"),
+    ("snowy", "false"),
+    ("persistent", "true"),
+    ("age", "0"),
+];
+
+fn default_property_score(block: &Palette) -> usize {
+    let Some(properties) = &block.properties else {
+        return 0;
+    };
+    DEFAULT_PROPERTIES
+        .iter()
+        .filter(|(key, value)| properties.get(*key).is_some_and(|actual| actual == value))
+        .count()
+}
+
+/// The state a block is in when nothing else is said about it, e.g. when it's placed. For blocks
+/// with properties this is a best guess, see [DEFAULT_PROPERTIES].
+pub fn default_state(name: &str) -> Option<Palette> {
+    DEFAULT_STATES.get(name).cloned()
 }
 
+/// A block state's network ID, which is how the client knows blocks. They change between
+/// versions, so blocks are kept by name everywhere else and only turned into IDs to be sent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BlockId(pub i32);
+
+impl BlockId {
+    pub const AIR: BlockId = BlockId(0);
+
+    /// The ID of a block state written like in commands, e.g. `minecraft:oak_log[axis=y]`. The
+    /// namespace can be left out, and so can properties, which then take their default value.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::from_palette(&parse_block_state(name)?)
+    }
+
+    pub fn from_palette(block: &Palette) -> Option<Self> {
+        BLOCK2ID.get(block).copied().map(BlockId)
+    }
+
+    /// The block state with this ID, if there's one.
+    pub fn to_palette(self) -> Option<Palette> {
+        ID2BLOCK.get(&self.0).cloned()
+    }
+}
+
+impl From<BlockId> for VarInt {
+    fn from(id: BlockId) -> Self {
+        VarInt::from(id.0)
+    }
+}
+
+/// Reads a block state written like in commands, see [BlockId::from_name]. Returns `None` for
+/// blocks that don't exist and properties they don't have.
+pub fn parse_block_state(state: &str) -> Option<Palette> {
+    let (name, properties) = match state.split_once('[') {
+        Some((name, properties)) => (name, Some(properties.strip_suffix(']')?)),
+        None => (state, None),
+    };
+    let name = if name.contains(':') {
+        name.to_string()
+    } else {
+        format!("minecraft:{}", name)
+    };
+
+    let mut block = default_state(&name)?;
+    for property in properties.into_iter().flat_map(|p| p.split(',')) {
+        if property.is_empty() {
+            continue;
+        }
+        let (key, value) = property.split_once('=')?;
+        let (key, value) = (key.trim(), value.trim());
+        if !block.properties.as_ref()?.contains_key(key) {
+            return None;
+        }
+        block = block.with_property(key, value);
+    }
+    // Catches values the property can't have
+    BLOCK2ID.contains_key(&block).then_some(block)
+}
+
+/// Block states with more bits than this don't fit in an indirect palette on the network.
+const MAX_INDIRECT_BITS: i8 = 8;
+/// Bits per block when sending global block IDs instead of a palette.
+const DIRECT_BITS: i8 = 15;
+
 impl Section {
     pub fn set_empty(&mut self) {
         self.block_states = Some(BlockStates {
@@ -32,7 +165,7 @@ impl Section {
             bits_per_block: Some(0),
             data: None,
             palette: None,
-            net_palette: Some(vec![VarInt::from(0)]),
+            net_palette: Some(vec![BlockId::AIR.into()]),
         });
     }
 }
@@ -86,10 +219,13 @@ impl Chunk {
 
                     let palette = block_states.palette.as_mut().unwrap();
 
-                    // TODO: Adapt this for single block sections
                     if block_states.data.is_some() {
                         let bits_per_entry = (palette.len() as f32).log2().ceil() as i8;
                         block_states.bits_per_block = Some(bits_per_entry.max(4));
+                    } else if palette.len() == 1 {
+                        // The whole section is one block, which is sent as a single value palette
+                        block_states.bits_per_block = Some(0);
+                        non_air_blocks = if palette[0].is_air() { 0 } else { 4096 };
                     } else {
                         trace!("No data found in section at {}", section.y);
                         set_empty = true;
@@ -109,7 +245,7 @@ impl Chunk {
                                     .get(palette_entry)
                                     .expect("Block not found in block mappings");
                                 // If the block is air, decrease the non-air blocks count
-                                if block_id == air_id {
+                                if block_id == air_id && block_states.data.is_some() {
                                     non_air_blocks -= 1;
                                 }
                                 checked_palette.push(VarInt::from(block_id));
@@ -161,27 +297,44 @@ impl NetEncode for Section {
 
             // Blocks
             let bpe = block_states.bits_per_block.unwrap_or(15);
+            let net_palette = block_states.net_palette.as_ref().expect("Palette is missing");
+            if bpe > MAX_INDIRECT_BITS {
+                // Too many different blocks for a palette, so the global IDs are sent directly
+                let indices =
+                    PackedArray::from_longs(block_states.data.as_deref().unwrap_or_default(), bpe as u8)
+                        .unpack(4096);
+                let ids: Vec<u16> = indices
+                    .iter()
+                    .map(|&index| {
+                        net_palette
+                            .get(index as usize)
+                            .map_or(0, |id| id.get_val() as u16)
+                    })
+                    .collect();
+                DIRECT_BITS.net_encode(writer).await?;
+                return PackedArray::pack(&ids, DIRECT_BITS as u8)
+                    .net_encode(writer)
+                    .await;
+            }
             bpe.net_encode(writer).await?;
+            if bpe == 0 {
+                // Single valued: just the block, without the palette's length, and no data
+                let block = net_palette.first().map_or(0, VarInt::get_val);
+                VarInt::from(block).net_encode(writer).await?;
+                return VarInt::from(0).net_encode(writer).await;
+            }
 
-            VarInt::from(block_states.net_palette.as_ref().unwrap().len() as i32)
-                .net_encode(writer)
-                .await?;
-            block_states
-                .net_palette
-                .as_ref()
-                .expect("Palette is missing")
+            // The palette's length then its IDs, all VarInts, so they're written in one go
+            let mut palette = Vec::with_capacity(net_palette.len() + 1);
+            let ids = net_palette.iter().map(VarInt::get_val);
+            let values = std::iter::once(net_palette.len() as i32).chain(ids);
+            encode_varints(values, &mut palette);
+            writer.write_all(&palette).await?;
+
+            PackedArray::from_longs(block_states.data.as_deref().unwrap_or_default(), bpe as u8)
                 .net_encode(writer)
                 .await?;
 
-            if let Some(data) = &block_states.data {
-                VarInt::from(data.len() as i32).net_encode(writer).await?;
-                for long in block_states.data.as_ref().unwrap() {
-                    long.net_encode(writer).await?;
-                }
-            } else {
-                VarInt::from(0).net_encode(writer).await?;
-            }
-
             /*// Biomes
             // For now just write 3 0s
             writer.write_all(&[0; 3]).await?;*/
@@ -189,3 +342,72 @@ impl NetEncode for Section {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_encode_single_block_section() {
+        let section = Section {
+            block_states: Some(BlockStates {
+                non_air_blocks: Some(4096),
+                bits_per_block: Some(0),
+                data: None,
+                palette: None,
+                net_palette: Some(vec![VarInt::new(1)]),
+            }),
+            biomes: None,
+            y: 0,
+            block_light: None,
+            sky_light: None,
+        };
+        let mut data = Cursor::new(Vec::new());
+        section.net_encode(&mut data).await.unwrap();
+        // 4096 blocks, no bits per entry, stone, then an empty data array
+        assert_eq!(data.into_inner(), vec![0x10, 0x00, 0, 1, 0]);
+    }
+
+    #[test]
+    fn test_default_state() {
+        assert_eq!(default_state("minecraft:stone"), Some(Palette::new("minecraft:stone")));
+        assert_eq!(
+            default_state("minecraft:oak_log"),
+            Some(Palette::new("minecraft:oak_log").with_property("axis", "y"))
+        );
+        let slab = default_state("minecraft:oak_slab").unwrap();
+        let properties = slab.properties.unwrap();
+        assert_eq!(properties["type"], "bottom");
+        assert_eq!(properties["waterlogged"], "false");
+        assert_eq!(default_state("minecraft:not_a_block"), None);
+    }
+
+    #[test]
+    fn test_block_id_from_name() {
+        assert_eq!(BlockId::from_name("minecraft:air"), Some(BlockId::AIR));
+        assert_eq!(BlockId::from_name("stone"), Some(BlockId(1)));
+        let log = BlockId::from_name("minecraft:oak_log[axis=x]").unwrap();
+        assert_eq!(
+            log.to_palette(),
+            Some(Palette::new("minecraft:oak_log").with_property("axis", "x"))
+        );
+        assert_eq!(
+            BlockId::from_name("minecraft:oak_log"),
+            BlockId::from_name("minecraft:oak_log[axis=y]")
+        );
+        assert_eq!(BlockId::from_name("minecraft:oak_log[axis=w]"), None);
+        assert_eq!(BlockId::from_name("minecraft:oak_log[colour=red]"), None);
+        assert_eq!(BlockId::from_name("minecraft:stone[axis=y]"), None);
+        assert_eq!(BlockId::from_name("minecraft:not_a_block"), None);
+
+        // Written back the same way
+        let slab = parse_block_state("oak_slab[type=top]").unwrap();
+        assert_eq!(
+            slab.to_string(),
+            "minecraft:oak_slab[type=top,waterlogged=false]"
+        );
+        assert_eq!(parse_block_state(&slab.to_string()), Some(slab));
+    }
+}
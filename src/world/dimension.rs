@@ -0,0 +1,96 @@
+use std::fmt::Display;
+
+/// The dimensions a world has. Each has its own chunks, stored under [Dimension::name].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Dimension {
+    #[default]
+    Overworld,
+    Nether,
+    End,
+}
+
+impl Dimension {
+    pub const ALL: [Dimension; 3] = [Dimension::Overworld, Dimension::Nether, Dimension::End];
+
+    /// What the dimension's chunks are stored under, the same as vanilla's folder names.
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Dimension::Overworld => "overworld",
+            Dimension::Nether => "the_nether",
+            Dimension::End => "the_end",
+        }
+    }
+
+    /// The dimension's id on the network, which is also its dimension type in the registry codec.
+    pub const fn id(&self) -> &'static str {
+        match self {
+            Dimension::Overworld => "minecraft:overworld",
+            Dimension::Nether => "minecraft:the_nether",
+            Dimension::End => "minecraft:the_end",
+        }
+    }
+
+    /// Accepts both the storage name and the namespaced id, e.g. `the_nether` and
+    /// `minecraft:the_nether`. `nether` and `end` work too.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.strip_prefix("minecraft:").unwrap_or(name) {
+            "overworld" => Some(Dimension::Overworld),
+            "the_nether" | "nether" => Some(Dimension::Nether),
+            "the_end" | "end" => Some(Dimension::End),
+            _ => None,
+        }
+    }
+
+    /// The lowest block, from the dimension types in the registry codec.
+    pub const fn min_y(&self) -> i32 {
+        match self {
+            Dimension::Overworld => -64,
+            Dimension::Nether | Dimension::End => 0,
+        }
+    }
+
+    /// How many 16 block tall sections a chunk has.
+    pub const fn section_count(&self) -> usize {
+        match self {
+            Dimension::Overworld => 24,
+            Dimension::Nether | Dimension::End => 16,
+        }
+    }
+
+    /// The highest block.
+    pub const fn max_y(&self) -> i32 {
+        self.min_y() + self.section_count() as i32 * 16 - 1
+    }
+
+    /// The biome chunks get when nothing else is set.
+    pub const fn default_biome(&self) -> &'static str {
+        match self {
+            Dimension::Overworld => "minecraft:plains",
+            Dimension::Nether => "minecraft:nether_wastes",
+            Dimension::End => "minecraft:the_end",
+        }
+    }
+}
+
+impl Display for Dimension {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dimension_names() {
+        for dimension in Dimension::ALL {
+            assert_eq!(Dimension::from_name(dimension.name()), Some(dimension));
+            assert_eq!(Dimension::from_name(dimension.id()), Some(dimension));
+        }
+        assert_eq!(Dimension::from_name("nether"), Some(Dimension::Nether));
+        assert_eq!(Dimension::from_name("minecraft:moon"), None);
+        assert_eq!(Dimension::Overworld.max_y(), 319);
+        assert_eq!(Dimension::End.max_y(), 255);
+    }
+}
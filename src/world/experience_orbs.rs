@@ -0,0 +1,130 @@
+//! Experience orbs.
+//!
+//! Experience is dropped as orbs, split up the same way vanilla does it. Orbs fall and slide like
+//! dropped items, except that they drift towards the nearest player within [ATTRACTION_RANGE], who
+//! gets the points once they touch it. See
+//! [ExperienceOrbTicker](crate::net::systems::experience_orb_ticker) for what happens to them
+//! each tick.
+
+use crate::net::packets::outgoing::spawn_entity::entity_types;
+use crate::state::GlobalState;
+use crate::utils::components::body::Body;
+use crate::utils::components::dimension::CurrentDimension;
+use crate::utils::components::experience_orb::ExperienceOrb;
+use crate::utils::components::motion::Motion;
+use crate::utils::components::object_entity::ObjectEntity;
+use crate::utils::encoding::metadata::EntityMetadata;
+use crate::world::dimension::Dimension;
+
+/// Orbs further than this many blocks from every player stay where they are.
+pub const ATTRACTION_RANGE: f64 = 8.0;
+/// Orbs head for the middle of the player, rather than their feet.
+const PLAYER_MIDDLE: f64 = 1.62 / 2.0;
+/// The most an orb speeds up by in a tick, when it's right next to the player.
+const ATTRACTION: f64 = 0.1;
+
+/// Values orbs come in, biggest first. Experience is split into as few orbs as these allow.
+const ORB_SIZES: [i32; 10] = [2477, 1237, 617, 307, 149, 73, 37, 17, 7, 3];
+
+/// Spawns a single orb worth `value` points. Returns its entity.
+pub async fn spawn_orb(
+    state: &GlobalState,
+    value: i32,
+    dimension: Dimension,
+    position: (f64, f64, f64),
+    velocity: (f64, f64, f64),
+) -> usize {
+    // Orbs are spawned with their value, and have no metadata of their own
+    let object = ObjectEntity {
+        data: value,
+        ..ObjectEntity::new(entity_types::EXPERIENCE_ORB, EntityMetadata::new())
+    };
+    state
+        .world
+        .create_entity()
+        .await
+        .with(ExperienceOrb::new(value))
+        .with(object)
+        .with(Motion::new(position, velocity))
+        .with(Body::EXPERIENCE_ORB)
+        .with(CurrentDimension::new(dimension))
+        .build()
+}
+
+/// Drops `points` of experience as orbs, which pop out in random directions like vanilla's.
+pub async fn drop_experience(
+    state: &GlobalState,
+    dimension: Dimension,
+    position: (f64, f64, f64),
+    points: i32,
+) {
+    for value in split_experience(points) {
+        let spread = || (rand::random::<f64>() * 0.2 - 0.1) * 2.0;
+        let velocity = (spread(), rand::random::<f64>() * 0.4, spread());
+        spawn_orb(state, value, dimension, position, velocity).await;
+    }
+}
+
+/// Splits experience into orbs, biggest first.
+pub fn split_experience(mut points: i32) -> Vec<i32> {
+    let mut orbs = Vec::new();
+    while points > 0 {
+        let value = ORB_SIZES
+            .into_iter()
+            .find(|&size| points >= size)
+            .unwrap_or(1);
+        orbs.push(value);
+        points -= value;
+    }
+    orbs
+}
+
+/// Speeds an orb up towards a player standing at `player`, more the closer it is. Returns false if
+/// the player's too far away to pull it.
+pub fn attract(motion: &mut Motion, player: (f64, f64, f64)) -> bool {
+    let (x, y, z) = motion.position;
+    let (dx, dy, dz) = (player.0 - x, player.1 + PLAYER_MIDDLE - y, player.2 - z);
+    let distance = (dx * dx + dy * dy + dz * dz).sqrt();
+    if distance >= ATTRACTION_RANGE {
+        return false;
+    }
+    if distance > 0.0 {
+        let pull = (1.0 - distance / ATTRACTION_RANGE).powi(2) * ATTRACTION / distance;
+        let (vx, vy, vz) = motion.velocity;
+        motion.velocity = (vx + dx * pull, vy + dy * pull, vz + dz * pull);
+        motion.on_ground = false;
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_experience() {
+        assert_eq!(split_experience(0), Vec::<i32>::new());
+        assert_eq!(split_experience(2), vec![1, 1]);
+        assert_eq!(split_experience(100), vec![73, 17, 7, 3]);
+        assert_eq!(split_experience(5000).iter().sum::<i32>(), 5000);
+    }
+
+    #[test]
+    fn test_attract() {
+        let player = (0.5, 64.0, 0.5);
+        let mut motion = Motion::new((0.5, 64.0 + PLAYER_MIDDLE, 4.5), (0.0, 0.0, 0.0));
+        assert!(attract(&mut motion, player));
+        let (vx, vy, vz) = motion.velocity;
+        assert_eq!((vx, vy), (0.0, 0.0));
+        assert!((vz + 0.025).abs() < 1e-9);
+
+        // Pulled harder from closer
+        let mut close = Motion::new((0.5, 64.0 + PLAYER_MIDDLE, 2.5), (0.0, 0.0, 0.0));
+        attract(&mut close, player);
+        assert!(close.velocity.2 < vz);
+
+        let mut far = Motion::new((0.5, 64.0, 8.5), (0.0, 0.0, 0.0));
+        assert!(!attract(&mut far, player));
+        assert_eq!(far.velocity, (0.0, 0.0, 0.0));
+    }
+}
@@ -0,0 +1,110 @@
+//! Gamerules, the settings for how the game plays that are kept with the world rather than in the
+//! config, e.g. `keepInventory`.
+//!
+//! Values are kept in [WorldMeta::game_rules](crate::database::world_meta::WorldMeta::game_rules)
+//! as strings, the same as vanilla's `level.dat`, so rules the server doesn't know about survive
+//! an import. [GAME_RULES] are the ones it does know, with their types and defaults, and only
+//! those can be changed with `/gamerule`. Not all of them do anything yet.
+
+use std::fmt::{Display, Formatter};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GameRuleValue {
+    Bool(bool),
+    Int(i32),
+}
+
+impl GameRuleValue {
+    /// Parses a value of the same type as this one, as it's saved or typed into `/gamerule`.
+    pub fn parse_same(&self, value: &str) -> Option<GameRuleValue> {
+        match self {
+            GameRuleValue::Bool(_) => value.parse().ok().map(GameRuleValue::Bool),
+            GameRuleValue::Int(_) => value.parse().ok().map(GameRuleValue::Int),
+        }
+    }
+}
+
+impl Display for GameRuleValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GameRuleValue::Bool(value) => write!(f, "{}", value),
+            GameRuleValue::Int(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GameRule {
+    /// What vanilla calls it, e.g. `doDaylightCycle`.
+    pub name: &'static str,
+    /// What it is when it isn't set, which is also what type it is.
+    pub default: GameRuleValue,
+}
+
+impl GameRule {
+    const fn bool(name: &'static str, default: bool) -> Self {
+        Self {
+            name,
+            default: GameRuleValue::Bool(default),
+        }
+    }
+
+    const fn int(name: &'static str, default: i32) -> Self {
+        Self {
+            name,
+            default: GameRuleValue::Int(default),
+        }
+    }
+
+    /// One of [GAME_RULES] by name.
+    pub fn get(name: &str) -> Option<&'static GameRule> {
+        GAME_RULES.iter().find(|rule| rule.name == name)
+    }
+
+    pub fn is_bool(&self) -> bool {
+        matches!(self.default, GameRuleValue::Bool(_))
+    }
+}
+
+pub const DO_DAYLIGHT_CYCLE: GameRule = GameRule::bool("doDaylightCycle", true);
+pub const DO_MOB_SPAWNING: GameRule = GameRule::bool("doMobSpawning", true);
+pub const DO_WEATHER_CYCLE: GameRule = GameRule::bool("doWeatherCycle", true);
+pub const KEEP_INVENTORY: GameRule = GameRule::bool("keepInventory", false);
+pub const RANDOM_TICK_SPEED: GameRule = GameRule::int("randomTickSpeed", 3);
+pub const SHOW_DEATH_MESSAGES: GameRule = GameRule::bool("showDeathMessages", true);
+pub const SPAWN_RADIUS: GameRule = GameRule::int("spawnRadius", 10);
+
+/// Every gamerule the server knows, by name.
+pub static GAME_RULES: &[GameRule] = &[
+    DO_DAYLIGHT_CYCLE,
+    DO_MOB_SPAWNING,
+    DO_WEATHER_CYCLE,
+    KEEP_INVENTORY,
+    RANDOM_TICK_SPEED,
+    SHOW_DEATH_MESSAGES,
+    SPAWN_RADIUS,
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_same() {
+        let flag = GameRuleValue::Bool(false);
+        assert_eq!(flag.parse_same("true"), Some(GameRuleValue::Bool(true)));
+        assert_eq!(flag.parse_same("1"), None);
+
+        let number = GameRuleValue::Int(3);
+        assert_eq!(number.parse_same("-20"), Some(GameRuleValue::Int(-20)));
+        assert_eq!(number.parse_same("false"), None);
+        assert_eq!(GameRuleValue::Int(-20).to_string(), "-20");
+    }
+
+    #[test]
+    fn test_get() {
+        assert_eq!(GameRule::get("keepInventory"), Some(&KEEP_INVENTORY));
+        assert!(GameRule::get("keepinventory").is_none());
+        assert!(!GameRule::get("spawnRadius").unwrap().is_bool());
+    }
+}
@@ -0,0 +1,308 @@
+use ferrumc_codec::network_types::packed_array::pack_values;
+use crate::world::chunk_format::{
+    Biomes, BlockStates, Chunk, Palette, References, Section, Starts, Structures,
+};
+use crate::world::dimension::Dimension;
+
+/// The lowest block in the overworld.
+pub const MIN_Y: i32 = Dimension::Overworld.min_y();
+/// 16 block tall sections, from y -64 to 319.
+pub const SECTION_COUNT: usize = Dimension::Overworld.section_count();
+pub const MAX_Y: i32 = Dimension::Overworld.max_y();
+
+/// The data version chunks are saved with, the same as 1.20.1.
+const DATA_VERSION: i32 = 3465;
+const BLOCKS_PER_SECTION: usize = 16 * 16 * 16;
+
+/// Builds a chunk in the disk format one block at a time, taking care of the palettes and the
+/// packing of the block data.
+///
+/// Everything starts out as air. The chunk is as tall as its dimension, so blocks above or below
+/// it are ignored.
+pub struct ChunkBuilder {
+    chunk_x: i32,
+    chunk_z: i32,
+    dimension: Dimension,
+    /// Every biome used in the chunk, and which one each 4x4 column of the chunk is.
+    biome_types: Vec<String>,
+    biomes: [u16; 16],
+    /// Every block type used in the chunk. The blocks are indices into this.
+    block_types: Vec<Palette>,
+    sections: Vec<Vec<u16>>,
+}
+
+impl ChunkBuilder {
+    /// Unknown dimensions are built as tall as the overworld.
+    pub fn new(chunk_x: i32, chunk_z: i32, dimension: &str) -> Self {
+        let dimension = Dimension::from_name(dimension).unwrap_or_default();
+        Self {
+            chunk_x,
+            chunk_z,
+            dimension,
+            biome_types: vec![dimension.default_biome().to_string()],
+            biomes: [0; 16],
+            block_types: vec![Palette::new("minecraft:air")],
+            sections: vec![vec![0; BLOCKS_PER_SECTION]; dimension.section_count()],
+        }
+    }
+
+    /// The lowest block of the chunk.
+    pub fn min_y(&self) -> i32 {
+        self.dimension.min_y()
+    }
+
+    /// Sets the biome of the 4x4 column the block at `x` and `z` is in, from the bottom of the
+    /// world to the top. Biomes are only stored at that resolution.
+    pub fn set_biome(&mut self, x: usize, z: usize, biome: &str) {
+        if x >= 16 || z >= 16 {
+            return;
+        }
+        let biome = match self.biome_types.iter().position(|known| known == biome) {
+            Some(index) => index,
+            None => {
+                self.biome_types.push(biome.to_string());
+                self.biome_types.len() - 1
+            }
+        };
+        self.biomes[(z / 4) * 4 + x / 4] = biome as u16;
+    }
+
+    pub fn get_biome(&self, x: usize, z: usize) -> Option<&str> {
+        if x >= 16 || z >= 16 {
+            return None;
+        }
+        Some(&self.biome_types[self.biomes[(z / 4) * 4 + x / 4] as usize])
+    }
+
+    /// Sets a block, with `x` and `z` relative to the chunk (0 to 15) and `y` absolute.
+    /// Blocks outside the chunk are ignored.
+    pub fn set_block(&mut self, x: usize, y: i32, z: usize, block: &Palette) {
+        let Some((section, index)) = self.position(x, y, z) else {
+            return;
+        };
+        let block = self.block_type(block);
+        self.sections[section][index] = block;
+    }
+
+    /// Sets every block of a column from `from_y` to `to_y`, both included.
+    pub fn fill_column(&mut self, x: usize, z: usize, from_y: i32, to_y: i32, block: &Palette) {
+        let block_type = self.block_type(block);
+        for y in from_y.max(self.dimension.min_y())..=to_y.min(self.dimension.max_y()) {
+            if let Some((section, index)) = self.position(x, y, z) {
+                self.sections[section][index] = block_type;
+            }
+        }
+    }
+
+    pub fn get_block(&self, x: usize, y: i32, z: usize) -> Option<&Palette> {
+        let (section, index) = self.position(x, y, z)?;
+        Some(&self.block_types[self.sections[section][index] as usize])
+    }
+
+    pub fn build(self) -> Chunk {
+        // Biomes only change from column to column, so every section gets the same ones
+        let biomes = self.section_biomes();
+        let bottom_section = self.dimension.min_y() / 16;
+
+        let sections = self
+            .sections
+            .iter()
+            .enumerate()
+            .map(|(index, blocks)| Section {
+                block_states: self.block_states(blocks),
+                biomes: Some(biomes.clone()),
+                y: (bottom_section + index as i32) as i8,
+                block_light: None,
+                // There's no lighting engine yet, so everything is fully lit
+                sky_light: Some(vec![-1; 2048]),
+            })
+            .collect();
+
+        let mut chunk = Chunk {
+            dimension: Some(self.dimension.name().to_string()),
+            status: "full".to_string(),
+            data_version: DATA_VERSION,
+            heightmaps: None,
+            is_light_on: Some(1),
+            inhabited_time: Some(0),
+            y_pos: bottom_section,
+            x_pos: self.chunk_x,
+            z_pos: self.chunk_z,
+            structures: Some(Structures {
+                starts: Starts {},
+                references: References {},
+            }),
+            last_update: Some(0),
+            sections: Some(sections),
+            block_entities: None,
+        };
+        chunk.recalculate_heightmaps();
+        chunk
+    }
+
+    fn position(&self, x: usize, y: i32, z: usize) -> Option<(usize, usize)> {
+        let (min_y, max_y) = (self.dimension.min_y(), self.dimension.max_y());
+        if x >= 16 || z >= 16 || !(min_y..=max_y).contains(&y) {
+            return None;
+        }
+        let y = (y - min_y) as usize;
+        Some((y / 16, ((y % 16) * 16 + z) * 16 + x))
+    }
+
+    fn block_type(&mut self, block: &Palette) -> u16 {
+        match self.block_types.iter().position(|known| known == block) {
+            Some(index) => index as u16,
+            None => {
+                self.block_types.push(block.clone());
+                (self.block_types.len() - 1) as u16
+            }
+        }
+    }
+
+    fn section_biomes(&self) -> Biomes {
+        let mut palette: Vec<String> = Vec::new();
+        let mut columns = [0u16; 16];
+        for (column, &biome) in columns.iter_mut().zip(self.biomes.iter()) {
+            let name = &self.biome_types[biome as usize];
+            *column = match palette.iter().position(|used| used == name) {
+                Some(index) => index as u16,
+                None => {
+                    palette.push(name.clone());
+                    (palette.len() - 1) as u16
+                }
+            };
+        }
+        if palette.len() == 1 {
+            return Biomes::single(&palette[0]);
+        }
+
+        // Biome cells are indexed by y, then z, then x, the same as blocks
+        let indices: Vec<u16> = (0..64).map(|cell| columns[cell % 16]).collect();
+        let bits = (palette.len() as f32).log2().ceil() as u8;
+        Biomes {
+            palette,
+            data: Some(pack_values(&indices, bits)),
+        }
+    }
+
+    /// Gives the section its own palette with just the blocks it uses, and packs the indices into it.
+    fn block_states(&self, blocks: &[u16]) -> Option<BlockStates> {
+        let mut used = Vec::new();
+        let mut indices = Vec::with_capacity(blocks.len());
+        for &block in blocks {
+            let index = match used.iter().position(|&used| used == block) {
+                Some(index) => index,
+                None => {
+                    used.push(block);
+                    used.len() - 1
+                }
+            };
+            indices.push(index as u16);
+        }
+
+        let palette: Vec<Palette> = used
+            .iter()
+            .map(|&block| self.block_types[block as usize].clone())
+            .collect();
+        if palette.iter().all(Palette::is_air) {
+            // Sections without block states are sent as empty
+            return None;
+        }
+
+        // The same number of bits the network conversion picks from the palette size
+        let bits = ((palette.len() as f32).log2().ceil() as u8).max(4);
+        Some(BlockStates {
+            non_air_blocks: None,
+            bits_per_block: None,
+            data: Some(pack_values(&indices, bits)),
+            palette: Some(palette),
+            net_palette: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ferrumc_codec::network_types::packed_array::unpack_values;
+
+    fn unpack(data: &[i64], bits: u8, index: usize) -> u16 {
+        unpack_values(data, bits, index + 1)[index]
+    }
+
+    #[test]
+    fn test_build_chunk() {
+        let stone = Palette::new("minecraft:stone");
+        let grass = Palette::new("minecraft:grass_block").with_property("snowy", "false");
+
+        let mut builder = ChunkBuilder::new(1, -2, "overworld");
+        builder.fill_column(3, 4, MIN_Y, 10, &stone);
+        builder.set_block(3, 11, 4, &grass);
+        builder.set_block(16, 0, 0, &stone);
+        assert_eq!(builder.get_block(3, 11, 4), Some(&grass));
+        assert!(builder.get_block(0, 0, 0).unwrap().is_air());
+
+        let chunk = builder.build();
+        assert_eq!((chunk.x_pos, chunk.z_pos), (1, -2));
+        let sections = chunk.sections.unwrap();
+        assert_eq!(sections.len(), SECTION_COUNT);
+        assert_eq!(sections[0].y, -4);
+
+        // y 11 is in the 5th section from the bottom, which has air, stone and grass in it
+        let block_states = sections[4].block_states.as_ref().unwrap();
+        assert_eq!(block_states.palette.as_ref().unwrap().len(), 3);
+        let index = (((11 - MIN_Y) as usize % 16) * 16 + 4) * 16 + 3;
+        let palette_index = unpack(block_states.data.as_ref().unwrap(), 4, index);
+        assert_eq!(block_states.palette.as_ref().unwrap()[palette_index as usize], grass);
+
+        // Empty sections don't have any block states
+        assert!(sections[10].block_states.is_none());
+
+        let heightmap = chunk.heightmaps.unwrap().world_surface.unwrap();
+        assert_eq!(unpack(&heightmap, 9, 4 * 16 + 3), (11 - MIN_Y + 1) as u16);
+        assert_eq!(unpack(&heightmap, 9, 0), 0);
+    }
+
+    #[test]
+    fn test_build_nether_chunk() {
+        let mut builder = ChunkBuilder::new(0, 0, "the_nether");
+        builder.set_block(0, -1, 0, &Palette::new("minecraft:netherrack"));
+        builder.set_block(0, 0, 0, &Palette::new("minecraft:bedrock"));
+        assert!(builder.get_block(0, -1, 0).is_none());
+
+        let chunk = builder.build();
+        assert_eq!(chunk.dimension.as_deref(), Some("the_nether"));
+        assert_eq!(chunk.y_pos, 0);
+        let sections = chunk.sections.unwrap();
+        assert_eq!(sections.len(), 16);
+        assert_eq!(
+            sections[0].biomes,
+            Some(Biomes::single("minecraft:nether_wastes"))
+        );
+        let palette = sections[0].block_states.as_ref().unwrap().palette.as_ref().unwrap();
+        assert_eq!(palette[0].name, "minecraft:bedrock");
+    }
+
+    #[test]
+    fn test_build_biomes() {
+        let mut builder = ChunkBuilder::new(0, 0, "overworld");
+        assert_eq!(
+            builder.section_biomes(),
+            Biomes::single("minecraft:plains")
+        );
+
+        builder.set_biome(5, 14, "minecraft:desert");
+        assert_eq!(builder.get_biome(7, 12), Some("minecraft:desert"));
+        assert_eq!(builder.get_biome(8, 12), Some("minecraft:plains"));
+
+        let biomes = builder.section_biomes();
+        assert_eq!(biomes.palette, vec!["minecraft:plains", "minecraft:desert"]);
+        let indices = unpack_values(biomes.data.as_ref().unwrap(), 1, 64);
+        // The column at x 1, z 3 of every layer of cells
+        for y in 0..4 {
+            assert_eq!(indices[y * 16 + 3 * 4 + 1], 1);
+            assert_eq!(indices[y * 16], 0);
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use crate::utils::prelude::*;
+use crate::world::chunk_format::{Chunk, Palette};
+use crate::world::dimension::Dimension;
+use crate::world::generation::builder::ChunkBuilder;
+use crate::world::generation::WorldGenerator;
+
+/// Lays the same layers down everywhere, starting at the bottom of the world.
+pub struct FlatGenerator {
+    /// Each block and how many blocks thick its layer is, from the bottom up.
+    layers: Vec<(Palette, u32)>,
+}
+
+impl FlatGenerator {
+    pub fn new(layers: Vec<(Palette, u32)>) -> Self {
+        Self { layers }
+    }
+
+    /// Plain ground for the nether and the end, which the other generators don't make terrain for.
+    /// The overworld gets the default layers.
+    pub fn for_dimension(dimension: Dimension) -> Self {
+        match dimension {
+            Dimension::Overworld => Self::default(),
+            Dimension::Nether => Self::new(vec![
+                (Palette::new("minecraft:bedrock"), 1),
+                (Palette::new("minecraft:netherrack"), 63),
+            ]),
+            Dimension::End => Self::new(vec![(Palette::new("minecraft:end_stone"), 64)]),
+        }
+    }
+}
+
+impl Default for FlatGenerator {
+    /// The same layers as vanilla's classic flat world.
+    fn default() -> Self {
+        Self::new(vec![
+            (Palette::new("minecraft:bedrock"), 1),
+            (Palette::new("minecraft:dirt"), 2),
+            (
+                Palette::new("minecraft:grass_block").with_property("snowy", "false"),
+                1,
+            ),
+        ])
+    }
+}
+
+impl WorldGenerator for FlatGenerator {
+    fn generate_chunk(&self, chunk_x: i32, chunk_z: i32, dimension: &str) -> Result<Chunk> {
+        let mut builder = ChunkBuilder::new(chunk_x, chunk_z, dimension);
+        let mut bottom = builder.min_y();
+        for (block, thickness) in &self.layers {
+            let top = bottom + *thickness as i32 - 1;
+            for x in 0..16 {
+                for z in 0..16 {
+                    builder.fill_column(x, z, bottom, top, block);
+                }
+            }
+            bottom = top + 1;
+        }
+        Ok(builder.build())
+    }
+
+    fn name(&self) -> &'static str {
+        "flat"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flat_layers() {
+        let chunk = FlatGenerator::default()
+            .generate_chunk(5, 5, "overworld")
+            .unwrap();
+        let sections = chunk.sections.unwrap();
+        let palette = sections[0]
+            .block_states
+            .as_ref()
+            .unwrap()
+            .palette
+            .as_ref()
+            .unwrap();
+        let names = palette.iter().map(|p| p.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec![
+                "minecraft:bedrock",
+                "minecraft:dirt",
+                "minecraft:grass_block",
+                "minecraft:air"
+            ]
+        );
+        assert!(sections[1..].iter().all(|s| s.block_states.is_none()));
+    }
+
+    #[test]
+    fn test_nether_layers() {
+        let chunk = FlatGenerator::for_dimension(Dimension::Nether)
+            .generate_chunk(0, 0, "the_nether")
+            .unwrap();
+        let sections = chunk.sections.unwrap();
+        assert_eq!(sections.len(), 16);
+        let top = sections[3].block_states.as_ref().unwrap();
+        assert_eq!(top.palette.as_ref().unwrap()[0].name, "minecraft:netherrack");
+        assert!(sections[4].block_states.is_none());
+    }
+}
@@ -0,0 +1,129 @@
+//! Generates the chunks that aren't in the database, so the server can run without importing a
+//! vanilla world first.
+//!
+//! Generators run on the rayon threadpool, and what they generate is saved to the database like
+//! any other chunk, so each chunk is only ever generated once.
+
+use std::sync::Arc;
+
+use tokio::sync::oneshot;
+use tracing::debug;
+
+use crate::state::GlobalState;
+use crate::utils::config::Generation;
+use crate::utils::prelude::*;
+use crate::world::chunk_format::Chunk;
+use crate::world::dimension::Dimension;
+
+pub mod builder;
+pub mod flat;
+pub mod multi_noise;
+pub mod noise;
+
+pub use builder::ChunkBuilder;
+
+/// Makes the terrain for chunks that have never been loaded.
+pub trait WorldGenerator: Send + Sync {
+    /// Generates a chunk in the disk format. It's converted to the network format before it's saved.
+    ///
+    /// Has to give the same chunk for the same coordinates every time, since neighbouring chunks may
+    /// be generated long after each other.
+    fn generate_chunk(&self, chunk_x: i32, chunk_z: i32, dimension: &str) -> Result<Chunk>;
+
+    fn name(&self) -> &'static str;
+}
+
+/// Creates the generator picked in the config, or `None` if generation is turned off.
+pub fn create_generator(config: &Generation) -> Result<Option<Arc<dyn WorldGenerator>>> {
+    let generator: Arc<dyn WorldGenerator> = match config.generator.as_str() {
+        "none" => return Ok(None),
+        "flat" => Arc::new(flat::FlatGenerator::default()),
+        "noise" => Arc::new(noise::NoiseGenerator::new(config.seed)),
+        "multi_noise" => Arc::new(multi_noise::MultiNoiseGenerator::new(config.seed)),
+        other => {
+            return Err(Error::Generic(format!(
+                "Unknown world generator \"{}\". Use \"multi_noise\", \"noise\", \"flat\" or \"none\"",
+                other
+            )))
+        }
+    };
+    Ok(Some(generator))
+}
+
+/// Gets a chunk from the database, generating and saving it first if it isn't there.
+///
+/// The configured generator only makes the overworld, the nether and the end get
+/// [FlatGenerator](flat::FlatGenerator) ground instead.
+///
+/// Returns `None` only if the chunk is missing and world generation is turned off.
+pub async fn get_or_generate_chunk(
+    state: &GlobalState,
+    chunk_x: i32,
+    chunk_z: i32,
+    dimension: &str,
+) -> Result<Option<Chunk>> {
+    if let Some(chunk) = state
+        .database
+        .get_chunk(chunk_x, chunk_z, dimension.to_string())
+        .await?
+    {
+        return Ok(Some(chunk));
+    }
+
+    let Some(generator) = state.world_generator.clone() else {
+        return Ok(None);
+    };
+    let generator = match Dimension::from_name(dimension) {
+        Some(Dimension::Overworld) | None => generator,
+        Some(other) => Arc::new(flat::FlatGenerator::for_dimension(other)),
+    };
+
+    // Whoever got here first generates the chunk, and anyone that was waiting gets theirs instead
+    // of overwriting it, along with any edits made to it since
+    let _lock = state
+        .database
+        .lock_chunk_generation(chunk_x, chunk_z, dimension)
+        .await;
+    if let Some(chunk) = state
+        .database
+        .get_chunk(chunk_x, chunk_z, dimension.to_string())
+        .await?
+    {
+        return Ok(Some(chunk));
+    }
+
+    let chunk = generate_chunk(generator, chunk_x, chunk_z, dimension.to_string()).await?;
+    state.database.insert_chunk(chunk.clone()).await?;
+    Ok(Some(chunk))
+}
+
+/// Generates a chunk on the rayon threadpool, so it doesn't hold up the async runtime.
+async fn generate_chunk(
+    generator: Arc<dyn WorldGenerator>,
+    chunk_x: i32,
+    chunk_z: i32,
+    dimension: String,
+) -> Result<Chunk> {
+    let (tx, rx) = oneshot::channel();
+    rayon::spawn(move || {
+        let start = std::time::Instant::now();
+        let chunk = generator
+            .generate_chunk(chunk_x, chunk_z, &dimension)
+            .and_then(|mut chunk| {
+                chunk.convert_to_net_mode()?;
+                Ok(chunk)
+            });
+        debug!(
+            "Generated chunk {} {} with the {} generator in {:?}",
+            chunk_x,
+            chunk_z,
+            generator.name(),
+            start.elapsed()
+        );
+        // The receiver only goes away if whoever asked for the chunk stopped waiting for it
+        let _ = tx.send(chunk);
+    });
+
+    rx.await
+        .map_err(|_| Error::Generic("Chunk generation was cancelled".to_string()))?
+}
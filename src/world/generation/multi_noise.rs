@@ -0,0 +1,325 @@
+use crate::utils::prelude::*;
+use crate::world::chunk_format::{Chunk, Palette};
+use crate::world::generation::builder::{ChunkBuilder, MIN_Y};
+use crate::world::generation::noise::{mix, PerlinNoise, SEA_LEVEL};
+use crate::world::generation::WorldGenerator;
+
+/// How many blocks one unit of noise is stretched over for each of the noise maps.
+const CONTINENT_SCALE: f64 = 384.0;
+const HILLS_SCALE: f64 = 96.0;
+const TEMPERATURE_SCALE: f64 = 512.0;
+const HUMIDITY_SCALE: f64 = 384.0;
+
+/// Deserts are picked above this temperature, as long as it's dry enough.
+const DESERT_TEMPERATURE: f64 = 0.15;
+const DESERT_MAX_HUMIDITY: f64 = 0.1;
+/// Forests are picked above this humidity.
+const FOREST_HUMIDITY: f64 = 0.05;
+
+const TREE_CHANCE: f64 = 0.02;
+const PLANT_CHANCE: f64 = 0.1;
+const CACTUS_CHANCE: f64 = 0.005;
+const DEAD_BUSH_CHANCE: f64 = 0.01;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Biome {
+    Plains,
+    Forest,
+    Desert,
+    Ocean,
+}
+
+impl Biome {
+    pub fn name(self) -> &'static str {
+        match self {
+            Biome::Plains => "minecraft:plains",
+            Biome::Forest => "minecraft:forest",
+            Biome::Desert => "minecraft:desert",
+            Biome::Ocean => "minecraft:ocean",
+        }
+    }
+}
+
+/// Terrain shaped by a few independent noise maps: continentalness decides between land and sea,
+/// hills add detail on top, and temperature and humidity pick the biome on land.
+pub struct MultiNoiseGenerator {
+    seed: u64,
+    continentalness: PerlinNoise,
+    hills: PerlinNoise,
+    temperature: PerlinNoise,
+    humidity: PerlinNoise,
+    blocks: Blocks,
+}
+
+struct Blocks {
+    bedrock: Palette,
+    stone: Palette,
+    dirt: Palette,
+    grass_block: Palette,
+    sand: Palette,
+    sandstone: Palette,
+    gravel: Palette,
+    water: Palette,
+    oak_log: Palette,
+    short_grass: Palette,
+    cactus: Palette,
+    dead_bush: Palette,
+}
+
+impl MultiNoiseGenerator {
+    pub fn new(seed: i64) -> Self {
+        let seed = seed as u64;
+        Self {
+            seed,
+            continentalness: PerlinNoise::new(seed),
+            hills: PerlinNoise::new(mix(seed.wrapping_add(1))),
+            temperature: PerlinNoise::new(mix(seed.wrapping_add(2))),
+            humidity: PerlinNoise::new(mix(seed.wrapping_add(3))),
+            blocks: Blocks {
+                bedrock: Palette::new("minecraft:bedrock"),
+                stone: Palette::new("minecraft:stone"),
+                dirt: Palette::new("minecraft:dirt"),
+                grass_block: Palette::new("minecraft:grass_block").with_property("snowy", "false"),
+                sand: Palette::new("minecraft:sand"),
+                sandstone: Palette::new("minecraft:sandstone"),
+                gravel: Palette::new("minecraft:gravel"),
+                water: Palette::new("minecraft:water").with_property("level", "0"),
+                oak_log: Palette::new("minecraft:oak_log").with_property("axis", "y"),
+                // Short grass is still called grass in 1.20.1
+                short_grass: Palette::new("minecraft:grass"),
+                cactus: Palette::new("minecraft:cactus").with_property("age", "0"),
+                dead_bush: Palette::new("minecraft:dead_bush"),
+            },
+        }
+    }
+
+    /// The y of the top block of the terrain at a block position.
+    pub fn height(&self, x: i32, z: i32) -> i32 {
+        let (x, z) = (x as f64, z as f64);
+        let continent = self
+            .continentalness
+            .fractal(x / CONTINENT_SCALE, z / CONTINENT_SCALE, 4, 0.5);
+        let hills = self.hills.fractal(x / HILLS_SCALE, z / HILLS_SCALE, 3, 0.5);
+        // Hills are flattened out under the sea
+        let hill_height = if continent > 0.0 { 10.0 } else { 3.0 };
+        (SEA_LEVEL as f64 + 4.0 + continent * 48.0 + hills * hill_height).round() as i32
+    }
+
+    /// The biome at a block position. Chunks use the biome at the middle of each 4x4 column.
+    pub fn biome(&self, x: i32, z: i32) -> Biome {
+        if self.height(x, z) < SEA_LEVEL {
+            return Biome::Ocean;
+        }
+        let (x, z) = (x as f64, z as f64);
+        let temperature =
+            self.temperature
+                .fractal(x / TEMPERATURE_SCALE, z / TEMPERATURE_SCALE, 2, 0.5);
+        let humidity = self
+            .humidity
+            .fractal(x / HUMIDITY_SCALE, z / HUMIDITY_SCALE, 2, 0.5);
+
+        if temperature > DESERT_TEMPERATURE && humidity < DESERT_MAX_HUMIDITY {
+            Biome::Desert
+        } else if humidity > FOREST_HUMIDITY {
+            Biome::Forest
+        } else {
+            Biome::Plains
+        }
+    }
+
+    /// A random number from 0 to 1 that's always the same for a block position and seed.
+    fn chance(&self, x: i32, z: i32) -> f64 {
+        let hash = mix(self.seed
+            ^ (x as u64).wrapping_mul(0x9E3779B97F4A7C15)
+            ^ (z as u64).wrapping_mul(0xC2B2AE3D27D4EB4F));
+        (hash >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    fn fill_terrain(&self, builder: &mut ChunkBuilder, x: usize, z: usize, height: i32, biome: Biome) {
+        let blocks = &self.blocks;
+        builder.set_block(x, MIN_Y, z, &blocks.bedrock);
+
+        if height < SEA_LEVEL {
+            // Sea floor, with gravel in the deeper parts
+            let floor = if height < SEA_LEVEL - 8 {
+                &blocks.gravel
+            } else {
+                &blocks.sand
+            };
+            builder.fill_column(x, z, MIN_Y + 1, height - 4, &blocks.stone);
+            builder.fill_column(x, z, height - 3, height, floor);
+            builder.fill_column(x, z, height + 1, SEA_LEVEL, &blocks.water);
+            return;
+        }
+
+        if biome == Biome::Desert {
+            builder.fill_column(x, z, MIN_Y + 1, height - 7, &blocks.stone);
+            builder.fill_column(x, z, height - 6, height - 4, &blocks.sandstone);
+            builder.fill_column(x, z, height - 3, height, &blocks.sand);
+        } else if height <= SEA_LEVEL + 1 {
+            // Beaches
+            builder.fill_column(x, z, MIN_Y + 1, height - 4, &blocks.stone);
+            builder.fill_column(x, z, height - 3, height, &blocks.sand);
+        } else {
+            builder.fill_column(x, z, MIN_Y + 1, height - 4, &blocks.stone);
+            builder.fill_column(x, z, height - 3, height - 1, &blocks.dirt);
+            builder.set_block(x, height, z, &blocks.grass_block);
+        }
+    }
+
+    fn decorate(
+        &self,
+        builder: &mut ChunkBuilder,
+        (x, z): (usize, usize),
+        (world_x, world_z): (i32, i32),
+        height: i32,
+        biome: Biome,
+    ) {
+        if height <= SEA_LEVEL + 1 && biome != Biome::Desert {
+            return;
+        }
+        let chance = self.chance(world_x, world_z);
+        let above = height + 1;
+        let blocks = &self.blocks;
+
+        match biome {
+            Biome::Forest if chance < TREE_CHANCE => {
+                let trunk_height = 4 + (mix(chance.to_bits()) % 3) as i32;
+                place_tree(builder, x, z, above, trunk_height, &blocks.oak_log);
+            }
+            Biome::Plains | Biome::Forest if chance < PLANT_CHANCE => {
+                set_if_air(builder, x, above, z, &blocks.short_grass);
+            }
+            Biome::Desert if chance < CACTUS_CHANCE => {
+                let cactus_height = 1 + (mix(chance.to_bits()) % 3) as i32;
+                for y in above..above + cactus_height {
+                    set_if_air(builder, x, y, z, &blocks.cactus);
+                }
+            }
+            Biome::Desert if chance < DEAD_BUSH_CHANCE => {
+                set_if_air(builder, x, above, z, &blocks.dead_bush);
+            }
+            _ => {}
+        }
+    }
+}
+
+impl WorldGenerator for MultiNoiseGenerator {
+    fn generate_chunk(&self, chunk_x: i32, chunk_z: i32, dimension: &str) -> Result<Chunk> {
+        let mut builder = ChunkBuilder::new(chunk_x, chunk_z, dimension);
+        let (start_x, start_z) = (chunk_x * 16, chunk_z * 16);
+
+        let mut biomes = [Biome::Plains; 16];
+        for (cell, biome) in biomes.iter_mut().enumerate() {
+            let (x, z) = ((cell % 4) * 4, (cell / 4) * 4);
+            *biome = self.biome(start_x + x as i32 + 2, start_z + z as i32 + 2);
+            builder.set_biome(x, z, biome.name());
+        }
+
+        let mut heights = [[0; 16]; 16];
+        for x in 0..16 {
+            for z in 0..16 {
+                let height = self.height(start_x + x as i32, start_z + z as i32);
+                heights[x][z] = height;
+                self.fill_terrain(&mut builder, x, z, height, biomes[(z / 4) * 4 + x / 4]);
+            }
+        }
+
+        // Decorations go on after all the terrain, so trees don't get overwritten by it
+        for x in 0..16 {
+            for z in 0..16 {
+                let (world_x, world_z) = (start_x + x as i32, start_z + z as i32);
+                let biome = biomes[(z / 4) * 4 + x / 4];
+                self.decorate(&mut builder, (x, z), (world_x, world_z), heights[x][z], biome);
+            }
+        }
+
+        Ok(builder.build())
+    }
+
+    fn name(&self) -> &'static str {
+        "multi_noise"
+    }
+}
+
+fn set_if_air(builder: &mut ChunkBuilder, x: usize, y: i32, z: usize, block: &Palette) {
+    if builder.get_block(x, y, z).is_some_and(Palette::is_air) {
+        builder.set_block(x, y, z, block);
+    }
+}
+
+/// Places an oak tree with its trunk starting at `y`. Trees whose leaves would cross into another
+/// chunk are skipped, since that chunk may already have been generated.
+fn place_tree(builder: &mut ChunkBuilder, x: usize, z: usize, y: i32, trunk_height: i32, log: &Palette) {
+    if !(2..14).contains(&x) || !(2..14).contains(&z) {
+        return;
+    }
+    let top = y + trunk_height - 1;
+    builder.fill_column(x, z, y, top, log);
+
+    for dy in -2..=1 {
+        let radius: i32 = if dy < 0 { 2 } else { 1 };
+        for dx in -radius..=radius {
+            for dz in -radius..=radius {
+                // Round the corners off
+                if dx.abs() == radius && dz.abs() == radius {
+                    continue;
+                }
+                // Leaves store how far they are from the nearest log, going above the trunk
+                let distance = dx.abs() + dz.abs() + dy.max(0);
+                if distance == 0 {
+                    continue;
+                }
+                let leaves = Palette::new("minecraft:oak_leaves")
+                    .with_property("distance", &distance.min(7).to_string())
+                    .with_property("persistent", "false")
+                    .with_property("waterlogged", "false");
+                let (leaf_x, leaf_z) = ((x as i32 + dx) as usize, (z as i32 + dz) as usize);
+                set_if_air(builder, leaf_x, top + dy, leaf_z, &leaves);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_multi_noise_has_every_biome() {
+        let generator = MultiNoiseGenerator::new(0);
+        let mut found = Vec::new();
+        for x in -64..64 {
+            for z in -64..64 {
+                let biome = generator.biome(x * 64, z * 64);
+                if !found.contains(&biome) {
+                    found.push(biome);
+                }
+            }
+        }
+        for biome in [Biome::Plains, Biome::Forest, Biome::Desert, Biome::Ocean] {
+            assert!(found.contains(&biome), "{:?} was never generated", biome);
+        }
+    }
+
+    #[test]
+    fn test_multi_noise_biome_palette() {
+        let generator = MultiNoiseGenerator::new(0);
+        let mut chunk = generator.generate_chunk(4, 9, "overworld").unwrap();
+        let sections = chunk.sections.as_ref().unwrap();
+        let biomes = sections[0].biomes.as_ref().unwrap();
+        let expected = generator.biome(4 * 16 + 2, 9 * 16 + 2).name();
+        assert_eq!(biomes.palette[0], expected);
+        assert!(sections.iter().all(|section| section.biomes == sections[0].biomes));
+
+        chunk.convert_to_net_mode().unwrap();
+    }
+
+    #[test]
+    fn test_multi_noise_is_deterministic() {
+        assert_eq!(
+            MultiNoiseGenerator::new(42).generate_chunk(-3, 5, "overworld").unwrap(),
+            MultiNoiseGenerator::new(42).generate_chunk(-3, 5, "overworld").unwrap()
+        );
+    }
+}
@@ -0,0 +1,217 @@
+use crate::utils::prelude::*;
+use crate::world::chunk_format::{Chunk, Palette};
+use crate::world::generation::builder::{ChunkBuilder, MIN_Y};
+use crate::world::generation::WorldGenerator;
+
+/// Water fills everything below this that the terrain doesn't.
+pub const SEA_LEVEL: i32 = 62;
+const BASE_HEIGHT: f64 = 64.0;
+/// How far the terrain goes above and below the base height, at most.
+const HEIGHT_VARIATION: f64 = 28.0;
+/// How many blocks one unit of noise is stretched over. Larger means smoother hills.
+const TERRAIN_SCALE: f64 = 160.0;
+const DIRT_DEPTH: i32 = 3;
+
+/// Rolling hills from layered perlin noise, with water and beaches below sea level.
+pub struct NoiseGenerator {
+    terrain: PerlinNoise,
+    bedrock: Palette,
+    stone: Palette,
+    dirt: Palette,
+    grass: Palette,
+    sand: Palette,
+    water: Palette,
+}
+
+impl NoiseGenerator {
+    pub fn new(seed: i64) -> Self {
+        Self {
+            terrain: PerlinNoise::new(seed as u64),
+            bedrock: Palette::new("minecraft:bedrock"),
+            stone: Palette::new("minecraft:stone"),
+            dirt: Palette::new("minecraft:dirt"),
+            grass: Palette::new("minecraft:grass_block").with_property("snowy", "false"),
+            sand: Palette::new("minecraft:sand"),
+            water: Palette::new("minecraft:water").with_property("level", "0"),
+        }
+    }
+
+    /// The y of the top block of the terrain at a block position.
+    pub fn height(&self, x: i32, z: i32) -> i32 {
+        let noise = self.terrain.fractal(
+            x as f64 / TERRAIN_SCALE,
+            z as f64 / TERRAIN_SCALE,
+            4,
+            0.5,
+        );
+        (BASE_HEIGHT + noise * HEIGHT_VARIATION).round() as i32
+    }
+}
+
+impl WorldGenerator for NoiseGenerator {
+    fn generate_chunk(&self, chunk_x: i32, chunk_z: i32, dimension: &str) -> Result<Chunk> {
+        let mut builder = ChunkBuilder::new(chunk_x, chunk_z, dimension);
+        for x in 0..16 {
+            for z in 0..16 {
+                let height = self.height(chunk_x * 16 + x as i32, chunk_z * 16 + z as i32);
+                // Beaches and the sea floor are sand, everything else is grass
+                let top = if height <= SEA_LEVEL + 1 {
+                    &self.sand
+                } else {
+                    &self.grass
+                };
+
+                builder.set_block(x, MIN_Y, z, &self.bedrock);
+                builder.fill_column(x, z, MIN_Y + 1, height - DIRT_DEPTH - 1, &self.stone);
+                builder.fill_column(x, z, height - DIRT_DEPTH, height - 1, &self.dirt);
+                builder.set_block(x, height, z, top);
+                builder.fill_column(x, z, height + 1, SEA_LEVEL, &self.water);
+            }
+        }
+        Ok(builder.build())
+    }
+
+    fn name(&self) -> &'static str {
+        "noise"
+    }
+}
+
+/// 2D perlin noise, shuffled by a seed so each seed gives different terrain.
+pub struct PerlinNoise {
+    permutation: [u8; 512],
+}
+
+impl PerlinNoise {
+    pub fn new(seed: u64) -> Self {
+        let mut table: [u8; 256] = std::array::from_fn(|i| i as u8);
+        let mut state = seed;
+        // Fisher-Yates shuffle with splitmix64 as the random numbers
+        for i in (1..table.len()).rev() {
+            state = state.wrapping_add(0x9E3779B97F4A7C15);
+            table.swap(i, (mix(state) % (i as u64 + 1)) as usize);
+        }
+
+        let mut permutation = [0; 512];
+        for (i, value) in permutation.iter_mut().enumerate() {
+            *value = table[i % 256];
+        }
+        Self { permutation }
+    }
+
+    /// Noise at a point, roughly between -1 and 1. Whole numbers always give 0.
+    pub fn get(&self, x: f64, z: f64) -> f64 {
+        let (cell_x, cell_z) = (x.floor(), z.floor());
+        let (x, z) = (x - cell_x, z - cell_z);
+        let (cell_x, cell_z) = (cell_x as i64 as usize & 255, cell_z as i64 as usize & 255);
+
+        let p = &self.permutation;
+        let corner = |dx: usize, dz: usize| p[p[cell_x + dx] as usize + cell_z + dz];
+        let (u, v) = (fade(x), fade(z));
+
+        lerp(
+            v,
+            lerp(
+                u,
+                gradient(corner(0, 0), x, z),
+                gradient(corner(1, 0), x - 1.0, z),
+            ),
+            lerp(
+                u,
+                gradient(corner(0, 1), x, z - 1.0),
+                gradient(corner(1, 1), x - 1.0, z - 1.0),
+            ),
+        )
+    }
+
+    /// Adds up octaves of noise, each at twice the frequency and `persistence` times the amplitude
+    /// of the last, then scales the total back to roughly between -1 and 1.
+    pub fn fractal(&self, x: f64, z: f64, octaves: u32, persistence: f64) -> f64 {
+        let (mut total, mut max) = (0.0, 0.0);
+        let (mut frequency, mut amplitude) = (1.0, 1.0);
+        for _ in 0..octaves {
+            total += self.get(x * frequency, z * frequency) * amplitude;
+            max += amplitude;
+            frequency *= 2.0;
+            amplitude *= persistence;
+        }
+        total / max
+    }
+}
+
+/// Scrambles the bits of a number, so that numbers close together give very different results.
+/// The finalizer from splitmix64.
+pub fn mix(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    z ^ (z >> 31)
+}
+
+fn fade(t: f64) -> f64 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(t: f64, a: f64, b: f64) -> f64 {
+    a + t * (b - a)
+}
+
+fn gradient(hash: u8, x: f64, z: f64) -> f64 {
+    match hash & 7 {
+        0 => x + z,
+        1 => x - z,
+        2 => -x + z,
+        3 => -x - z,
+        4 => x,
+        5 => -x,
+        6 => z,
+        _ => -z,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_noise_is_deterministic() {
+        let first = NoiseGenerator::new(1234);
+        let second = NoiseGenerator::new(1234);
+        let other = NoiseGenerator::new(4321);
+
+        let heights = |generator: &NoiseGenerator| {
+            (0..64)
+                .map(|i| generator.height(i * 7, -i * 13))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(heights(&first), heights(&second));
+        assert_ne!(heights(&first), heights(&other));
+        assert_eq!(
+            first.generate_chunk(3, -7, "overworld").unwrap(),
+            second.generate_chunk(3, -7, "overworld").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_noise_range() {
+        let noise = PerlinNoise::new(99);
+        for i in 0..1000 {
+            let value = noise.fractal(i as f64 * 0.37, i as f64 * -0.11, 4, 0.5);
+            assert!((-1.0..=1.0).contains(&value));
+        }
+        assert_eq!(noise.get(3.0, 5.0), 0.0);
+    }
+
+    #[test]
+    fn test_generated_chunk_converts_to_net_mode() {
+        let mut chunk = NoiseGenerator::new(0)
+            .generate_chunk(0, 0, "overworld")
+            .unwrap();
+        chunk.convert_to_net_mode().unwrap();
+        let sections = chunk.sections.unwrap();
+        assert!(sections[0]
+            .block_states
+            .as_ref()
+            .unwrap()
+            .net_palette
+            .is_some());
+    }
+}
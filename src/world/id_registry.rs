@@ -0,0 +1,119 @@
+//! IDs the client has built in for things besides items, like sounds and particles, read from the
+//! same data generator report as [items](crate::world::items).
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use serde::Deserialize;
+use tracing::{debug, info};
+
+use crate::utils::prelude::*;
+
+#[derive(Deserialize)]
+struct Registry {
+    entries: HashMap<String, RegistryEntry>,
+}
+
+#[derive(Deserialize)]
+struct RegistryEntry {
+    protocol_id: i32,
+}
+
+/// Maps the names in one of the report's registries, e.g. `minecraft:entity.cat.ambient` in
+/// `minecraft:sound_event`, to their IDs and back.
+#[derive(Default)]
+pub struct IdRegistry {
+    names: HashMap<i32, String>,
+    ids: HashMap<String, i32>,
+}
+
+impl IdRegistry {
+    /// Reads `registry` from a data generator report. Without one it's empty, which
+    /// [ItemRegistry](crate::world::items::ItemRegistry) already warns about.
+    pub async fn load(path: impl AsRef<Path>, registry: &str) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = match tokio::fs::read_to_string(path).await {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                debug!("{} wasn't found, so {} is empty", path.display(), registry);
+                return Ok(Self::default());
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        let loaded = Self::parse(&contents, registry)
+            .map_err(|e| Error::DeserializationError(format!("{}: {}", path.display(), e)))?;
+        info!("Loaded {} entries from {}", loaded.len(), registry);
+        Ok(loaded)
+    }
+
+    pub(crate) fn parse(contents: &str, registry: &str) -> serde_json::Result<Self> {
+        let mut registries: HashMap<String, Registry> = serde_json::from_str(contents)?;
+        let Some(registry) = registries.remove(registry) else {
+            return Ok(Self::default());
+        };
+        let ids: HashMap<String, i32> = registry
+            .entries
+            .into_iter()
+            .map(|(name, entry)| (name, entry.protocol_id))
+            .collect();
+        let names = ids.iter().map(|(name, &id)| (id, name.clone())).collect();
+        Ok(Self { names, ids })
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    pub fn name(&self, id: i32) -> Option<&str> {
+        self.names.get(&id).map(String::as_str)
+    }
+
+    /// The ID of an entry by its name. The `minecraft:` namespace can be left out.
+    pub fn id(&self, name: &str) -> Option<i32> {
+        match name.contains(':') {
+            true => self.ids.get(name).copied(),
+            false => self.ids.get(&format!("minecraft:{}", name)).copied(),
+        }
+    }
+
+    /// Every name in the registry, in no particular order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.ids.keys().map(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_id_registry() {
+        let contents = r#"{
+            "minecraft:particle_type": {
+                "entries": {
+                    "minecraft:ambient_entity_effect": { "protocol_id": 0 },
+                    "minecraft:flame": { "protocol_id": 26 }
+                },
+                "protocol_id": 9
+            },
+            "minecraft:item": {
+                "entries": { "minecraft:air": { "protocol_id": 0 } },
+                "protocol_id": 6
+            }
+        }"#;
+        let registry = IdRegistry::parse(contents, "minecraft:particle_type").unwrap();
+        assert_eq!(registry.len(), 2);
+        assert_eq!(registry.id("minecraft:flame"), Some(26));
+        assert_eq!(registry.id("flame"), Some(26));
+        assert_eq!(registry.name(26), Some("minecraft:flame"));
+        assert_eq!(registry.id("minecraft:air"), None);
+
+        let missing = IdRegistry::parse(contents, "minecraft:sound_event").unwrap();
+        assert!(missing.is_empty());
+    }
+}
@@ -1,36 +1,129 @@
 use crate::database::encoding::ZstdCodec;
+use crate::database::storage::ChunkKey;
+use crate::database::world_directory;
+use crate::database::world_meta::WorldMeta;
 use crate::state::GlobalState;
-use crate::utils::hash::hash;
+use crate::utils::config::get_global_config;
+use crate::utils::constants::PLAYER_DATA_DIRECTORY;
 use crate::utils::prelude::*;
 use crate::world::chunk_format::Chunk;
+use crate::world::dimension::Dimension;
 use fastanvil::{ChunkData, Region};
 use indicatif::{ProgressBar, ProgressStyle};
 use nbt_lib::NBTDeserializeBytes;
 use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::env;
 use std::fs::File;
-use std::io::Cursor;
-use std::path::PathBuf;
+use std::io::{Cursor, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tracing::{debug, info, warn};
 
 const DEFAULT_BATCH_SIZE: u8 = 150;
 
-/// A serialized chunk is a tuple of the chunk's hash and the compressed chunk data
-/// (hash, compressed_chunk_data)
-pub struct SerializedChunk(u64, Vec<u8>);
+/// Region files hold 32x32 chunks.
+const CHUNKS_PER_REGION: usize = 32 * 32;
+/// The chunk timestamps come after the 4KiB table of chunk locations.
+const TIMESTAMPS_OFFSET: u64 = 4096;
+const PROGRESS_FILE: &str = "import-progress.json";
+const LEVEL_FILE: &str = "level.dat";
+
+/// Where each dimension's region files are in a vanilla world folder, and the dimension they go
+/// into. `nether` and `end` folders are accepted too, for worlds that were split up by hand.
+const DIMENSION_FOLDERS: [(&str, &[&str]); 3] = [
+    (Dimension::Overworld.name(), &["region", "overworld"]),
+    (Dimension::Nether.name(), &["DIM-1/region", "nether", "the_nether"]),
+    (Dimension::End.name(), &["DIM1/region", "end", "the_end"]),
+];
+
+/// A serialized chunk is a tuple of where the chunk is and the compressed chunk data
+/// (key, compressed_chunk_data)
+pub struct SerializedChunk(ChunkKey, Vec<u8>);
 
 impl SerializedChunk {
-    pub fn new(hash: u64, data: Vec<u8>) -> Self {
-        Self(hash, data)
+    pub fn new(key: ChunkKey, data: Vec<u8>) -> Self {
+        Self(key, data)
+    }
+    pub fn key(&self) -> &ChunkKey {
+        &self.0
     }
     pub fn hash(&self) -> u64 {
-        self.0
+        self.0.hash()
     }
 
     pub fn data(&self) -> &Vec<u8> {
         self.1.as_ref()
     }
+
+    pub fn into_parts(self) -> (ChunkKey, Vec<u8>) {
+        (self.0, self.1)
+    }
+}
+
+/// Which chunks have been imported so far, so an interrupted import can carry on where it left off,
+/// and importing a world again only imports the chunks that changed.
+///
+/// For each region file, keeps the timestamp each chunk had in it when it was imported. It's saved
+/// next to the database, so it goes away with it.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ImportProgress {
+    regions: HashMap<String, Vec<u32>>,
+}
+
+impl ImportProgress {
+    pub fn load(path: &Path) -> Result<Self> {
+        match std::fs::read_to_string(path) {
+            Ok(contents) => serde_json::from_str(&contents)
+                .map_err(|e| Error::DeserializationError(format!("{}: {}", path.display(), e))),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let contents = serde_json::to_string(self)
+            .map_err(|e| Error::SerializationError(format!("{}: {}", path.display(), e)))?;
+        std::fs::write(path, contents)?;
+        Ok(())
+    }
+
+    /// Whether the chunk was imported when it had this timestamp, i.e. it hasn't changed since.
+    pub fn is_imported(&self, region: &str, index: usize, timestamp: u32) -> bool {
+        timestamp != 0
+            && self
+                .regions
+                .get(region)
+                .and_then(|timestamps| timestamps.get(index))
+                .is_some_and(|&imported| imported == timestamp)
+    }
+
+    pub fn record(&mut self, region: &str, index: usize, timestamp: u32) {
+        let timestamps = self
+            .regions
+            .entry(region.to_string())
+            .or_insert_with(|| vec![0; CHUNKS_PER_REGION]);
+        timestamps[index] = timestamp;
+    }
+}
+
+/// Where a chunk's location and timestamp are in a region file's header.
+fn chunk_index(chunk: &ChunkData) -> usize {
+    (chunk.x % 32) + (chunk.z % 32) * 32
+}
+
+/// Reads when each chunk in a region file was last saved, from its header.
+fn read_chunk_timestamps(path: &Path) -> Result<Vec<u32>> {
+    let mut file = File::open(path)?;
+    file.seek(SeekFrom::Start(TIMESTAMPS_OFFSET))?;
+    let mut header = vec![0u8; CHUNKS_PER_REGION * 4];
+    file.read_exact(&mut header)?;
+
+    Ok(header
+        .chunks_exact(4)
+        .map(|timestamp| u32::from_be_bytes([timestamp[0], timestamp[1], timestamp[2], timestamp[3]]))
+        .collect())
 }
 
 fn get_batch_size() -> i32 {
@@ -66,6 +159,27 @@ fn format_duration(duration: std::time::Duration) -> String {
     }
 }
 
+/// Finds the region folder of each dimension in the import directory.
+///
+/// A directory with region files right in it, rather than a whole world, is the overworld.
+fn find_dimensions(dir: &Path) -> Vec<(&'static str, PathBuf)> {
+    let mut dimensions = Vec::new();
+    for (dimension, folders) in DIMENSION_FOLDERS {
+        let found = folders
+            .iter()
+            .map(|folder| dir.join(folder))
+            .find(|folder| folder.is_dir());
+        if let Some(folder) = found {
+            dimensions.push((dimension, folder));
+        }
+    }
+
+    if dimensions.is_empty() {
+        dimensions.push((Dimension::Overworld.name(), dir.to_path_buf()));
+    }
+    dimensions
+}
+
 async fn get_total_chunks(dir: &PathBuf) -> Result<usize> {
     let files = std::fs::read_dir(dir)?;
     let regions: Vec<Region<File>> = files
@@ -92,6 +206,7 @@ async fn get_total_chunks(dir: &PathBuf) -> Result<usize> {
 async fn process_chunk(
     chunk_data: Vec<u8>,
     file_name: &str,
+    dimension: &str,
     bar: Arc<ProgressBar>,
 ) -> Result<SerializedChunk> {
     let mut chunk = Chunk::read_from_bytes(&mut Cursor::new(chunk_data)).map_err(|e| {
@@ -110,21 +225,14 @@ async fn process_chunk(
         ))
     })?;
 
-    chunk.dimension = Some("overworld".to_string());
+    chunk.dimension = Some(dimension.to_string());
 
-    let hash = hash((
-        chunk
-            .dimension
-            .as_ref()
-            .unwrap_or_else(|| panic!("Invalid chunk @ ({},{})", chunk.x_pos, chunk.z_pos)),
-        chunk.x_pos,
-        chunk.z_pos,
-    ));
+    let key = ChunkKey::new(dimension, chunk.x_pos, chunk.z_pos);
     let chunk_data = ZstdCodec::compress_data(chunk)
         .await
         .expect("Failed to compress chunk");
 
-    Ok(SerializedChunk::new(hash, chunk_data))
+    Ok(SerializedChunk::new(key, chunk_data))
 }
 
 //noinspection RsBorrowChecker
@@ -135,66 +243,174 @@ pub async fn import_regions(state: GlobalState) -> Result<()> {
     let start = std::time::Instant::now();
     info!("Analyzing world data... (this won't take long)");
 
-    let total_chunks = get_total_chunks(&dir).await?;
+    let dimensions = find_dimensions(&dir);
+    let mut total_chunks = 0;
+    for (dimension, regions) in &dimensions {
+        let chunks = get_total_chunks(regions).await?;
+        debug!("Found {} chunks in the {}", chunks, dimension);
+        total_chunks += chunks;
+    }
     info!("Preparing to import {} chunks", total_chunks);
     info!("This process may take a while for large worlds. Please be patient.");
 
-    let batch_size = get_batch_size() as usize;
-    let bar = Arc::new(create_progress_bar(total_chunks));
+    let progress_path = world_directory()?.join(PROGRESS_FILE);
+    let mut progress = if env::args().any(|arg| arg == "--full-import") {
+        info!("Importing every chunk, even the ones that were already imported");
+        ImportProgress::default()
+    } else {
+        ImportProgress::load(&progress_path)?
+    };
+
+    let mut import = DimensionImport {
+        state: &state,
+        batch_size: get_batch_size() as usize,
+        bar: Arc::new(create_progress_bar(total_chunks)),
+        progress: &mut progress,
+        progress_path: &progress_path,
+        skipped: 0,
+    };
+    for (dimension, regions) in &dimensions {
+        info!("Importing the {} from {}", dimension, regions.display());
+        import.import_dimension(dimension, regions).await?;
+    }
 
-    let mut region_files = tokio::fs::read_dir(dir)
-        .await
-        .map_err(|_| Error::Generic("Could not read the imports directory".to_string()))?;
-
-    while let Some(dir_file) = region_files.next_entry().await? {
-        let file_name = dir_file.file_name();
-        let file_name = file_name.to_str().unwrap_or("unknown file");
-        let file = File::open(dir_file.path())?;
-        let mut region = Region::from_stream(file)?;
-
-        let mut chunks: Vec<ChunkData> = region.iter().filter_map(|chunk| chunk.ok()).collect();
-        while !chunks.is_empty() {
-            let chunk_batch: Vec<ChunkData> = chunks
-                .drain(..std::cmp::min(batch_size, chunks.len()))
-                .collect();
+    finalize_import(&import.bar, total_chunks, import.skipped, start.elapsed());
+
+    let players = state
+        .players
+        .import_vanilla(&dir.join(PLAYER_DATA_DIRECTORY), &state.items)
+        .await?;
+    if players > 0 {
+        info!("Imported the data of {} players", players);
+    }
+
+    import_level(&state, &dir.join(LEVEL_FILE)).await
+}
 
-            let processed_chunks_futures: Vec<_> = chunk_batch
+/// Takes the spawn point, seed, time and gamerules from the world's `level.dat`, if it has one.
+async fn import_level(state: &GlobalState, path: &Path) -> Result<()> {
+    if !tokio::fs::try_exists(path).await? {
+        debug!("No {} to import", path.display());
+        return Ok(());
+    }
+    let meta = WorldMeta::import_vanilla(path).await?;
+    state.database.save_world_meta(&meta).await?;
+    info!(
+        "Imported the world's settings, spawning at {} {} {}",
+        meta.spawn_x, meta.spawn_y, meta.spawn_z
+    );
+    *state.world.resource_mut::<WorldMeta>().await? = meta;
+    Ok(())
+}
+
+/// What's shared between importing each dimension.
+struct DimensionImport<'a> {
+    state: &'a GlobalState,
+    batch_size: usize,
+    bar: Arc<ProgressBar>,
+    progress: &'a mut ImportProgress,
+    progress_path: &'a Path,
+    skipped: usize,
+}
+
+impl DimensionImport<'_> {
+    async fn import_dimension(&mut self, dimension: &'static str, dir: &Path) -> Result<()> {
+        let mut region_files = tokio::fs::read_dir(dir)
+            .await
+            .map_err(|_| Error::Generic("Could not read the imports directory".to_string()))?;
+
+        while let Some(dir_file) = region_files.next_entry().await? {
+            let path = dir_file.path();
+            if path.extension() != Some("mca".as_ref()) {
+                continue;
+            }
+            let file_name = dir_file.file_name();
+            let file_name = file_name.to_str().unwrap_or("unknown file");
+            // Region file names repeat across dimensions
+            let progress_key = format!("{}/{}", dimension, file_name);
+            let timestamps = read_chunk_timestamps(&path)?;
+            let file = File::open(&path)?;
+            let mut region = Region::from_stream(file)?;
+
+            let region_chunks: Vec<ChunkData> = region.iter().filter_map(|chunk| chunk.ok()).collect();
+            let region_chunk_count = region_chunks.len();
+            let mut chunks: Vec<ChunkData> = region_chunks
                 .into_iter()
-                .map(|chunk| {
-                    let data = chunk.data.clone();
-                    let bar_clone = Arc::clone(&bar);
-                    let file_name = file_name.to_string();
-                    tokio::spawn(async move {
-                        match process_chunk(data, &file_name, Arc::clone(&bar_clone)).await {
-                            Ok(processed) => {
-                                bar_clone.inc(1);
-                                Some(processed)
-                            }
-                            Err(e) => {
-                                warn!("Failed to process chunk: {}. Skipping.", e);
-                                None
-                            }
-                        }
-                    })
+                .filter(|chunk| {
+                    let index = chunk_index(chunk);
+                    !self.progress.is_imported(&progress_key, index, timestamps[index])
                 })
                 .collect();
 
-            let processed_chunks: Vec<SerializedChunk> =
-                futures::future::join_all(processed_chunks_futures)
-                    .await
+            let unchanged = region_chunk_count - chunks.len();
+            if unchanged > 0 {
+                debug!("Skipping {} unchanged chunks in {}", unchanged, progress_key);
+                self.bar.inc(unchanged as u64);
+                self.skipped += unchanged;
+            }
+
+            while !chunks.is_empty() {
+                let chunk_batch: Vec<ChunkData> = chunks
+                    .drain(..std::cmp::min(self.batch_size, chunks.len()))
+                    .collect();
+
+                let processed_chunks_futures: Vec<_> = chunk_batch
                     .into_iter()
-                    .filter_map(|result| result.ok().flatten())
+                    .map(|chunk| {
+                        let index = chunk_index(&chunk);
+                        let data = chunk.data.clone();
+                        let bar_clone = Arc::clone(&self.bar);
+                        let file_name = file_name.to_string();
+                        tokio::spawn(async move {
+                            match process_chunk(data, &file_name, dimension, Arc::clone(&bar_clone)).await {
+                                Ok(processed) => {
+                                    bar_clone.inc(1);
+                                    Some((index, processed))
+                                }
+                                Err(e) => {
+                                    warn!("Failed to process chunk: {}. Skipping.", e);
+                                    None
+                                }
+                            }
+                        })
+                    })
                     .collect();
 
-            insert_chunks(&state, processed_chunks, &bar).await?;
+                let (indices, processed_chunks): (Vec<usize>, Vec<SerializedChunk>) =
+                    futures::future::join_all(processed_chunks_futures)
+                        .await
+                        .into_iter()
+                        .filter_map(|result| result.ok().flatten())
+                        .unzip();
+
+                insert_chunks(self.state, processed_chunks, &self.bar).await?;
+
+                // Only once they're in the database, so a crash never leaves chunks marked as done
+                for index in indices {
+                    self.progress.record(&progress_key, index, timestamps[index]);
+                }
+                self.progress.save(self.progress_path)?;
+            }
         }
-    }
 
-    finalize_import(&bar, total_chunks, start.elapsed());
-    Ok(())
+        Ok(())
+    }
 }
 
+/// Where the world to import is. In order, the `--import-path=<dir>` flag, the `import_path`
+/// config entry, or the `import` folder under FERRUMC_ROOT or the exe's directory.
 fn get_import_directory() -> Result<PathBuf> {
+    let flag = env::args()
+        .find_map(|arg| arg.strip_prefix("--import-path=").map(PathBuf::from));
+    if let Some(path) = flag {
+        return Ok(path);
+    }
+
+    let configured = &get_global_config().import_path;
+    if !configured.is_empty() {
+        return Ok(PathBuf::from(configured));
+    }
+
     if let Ok(root) = env::var("FERRUMC_ROOT") {
         Ok(PathBuf::from(root).join("import"))
     } else {
@@ -233,20 +449,32 @@ async fn insert_chunks(
     Ok(())
 }
 
-fn finalize_import(bar: &ProgressBar, total_chunks: usize, elapsed: std::time::Duration) {
+fn finalize_import(
+    bar: &ProgressBar,
+    total_chunks: usize,
+    skipped: usize,
+    elapsed: std::time::Duration,
+) {
     bar.finish_with_message(format!(
         "Import complete! {} chunks processed.",
         total_chunks
     ));
     info!(
         "Successfully imported {} chunks in {}",
-        total_chunks,
+        total_chunks.saturating_sub(skipped),
         format_duration(elapsed)
     );
+    if skipped > 0 {
+        info!(
+            "Skipped {} chunks that were already imported. Use --full-import to import them again",
+            skipped
+        );
+    }
 }
 
 #[cfg(test)]
 mod test {
+    use super::{find_dimensions, read_chunk_timestamps, ImportProgress};
     use crate::create_state;
     use crate::utils::prelude::*;
     use crate::utils::setup_logger;
@@ -270,4 +498,62 @@ mod test {
 
         Ok(())
     }
+
+    #[test]
+    fn test_import_progress() {
+        let path = std::env::temp_dir().join(format!("ferrumc_import_progress_{}.json", std::process::id()));
+        let mut progress = ImportProgress::default();
+
+        assert!(!progress.is_imported("r.0.0.mca", 5, 1000));
+        progress.record("r.0.0.mca", 5, 1000);
+        progress.save(&path).unwrap();
+
+        let progress = ImportProgress::load(&path).unwrap();
+        assert!(progress.is_imported("r.0.0.mca", 5, 1000));
+        // The chunk was saved again since
+        assert!(!progress.is_imported("r.0.0.mca", 5, 2000));
+        assert!(!progress.is_imported("r.0.0.mca", 6, 1000));
+        assert!(!progress.is_imported("r.1.0.mca", 5, 1000));
+        // Chunks that were never saved don't have a timestamp to compare
+        assert!(!progress.is_imported("r.0.0.mca", 7, 0));
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_read_chunk_timestamps() {
+        let path = std::env::temp_dir().join(format!("ferrumc_region_{}.mca", std::process::id()));
+        let mut header = vec![0u8; 8192];
+        header[4096 + 4..4096 + 8].copy_from_slice(&1234u32.to_be_bytes());
+        std::fs::write(&path, header).unwrap();
+
+        let timestamps = read_chunk_timestamps(&path).unwrap();
+        assert_eq!(timestamps.len(), 1024);
+        assert_eq!(timestamps[0], 0);
+        assert_eq!(timestamps[1], 1234);
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_find_dimensions() {
+        let dir = std::env::temp_dir().join(format!("ferrumc_import_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        // Just region files
+        assert_eq!(find_dimensions(&dir), vec![("overworld", dir.clone())]);
+
+        // A vanilla world
+        std::fs::create_dir_all(dir.join("region")).unwrap();
+        std::fs::create_dir_all(dir.join("DIM1/region")).unwrap();
+        assert_eq!(
+            find_dimensions(&dir),
+            vec![("overworld", dir.join("region")), ("the_end", dir.join("DIM1/region"))]
+        );
+
+        std::fs::create_dir_all(dir.join("nether")).unwrap();
+        assert_eq!(find_dimensions(&dir)[1], ("the_nether", dir.join("nether")));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
 }
@@ -0,0 +1,238 @@
+//! Items on the ground.
+//!
+//! Players drop items by throwing them, through their inventory, or by dying. Dropped items fall
+//! and slide to a stop, stacks of the same item that end up together merge, and players walking
+//! over them pick them up. See [ItemEntityTicker](crate::net::systems::item_entity_ticker) for
+//! what happens to them each tick, and [physics](crate::world::physics) for how they move.
+
+use std::f64::consts::TAU;
+
+use crate::net::packets::outgoing::set_entity_metadata::SetEntityMetadata;
+use crate::net::packets::outgoing::spawn_entity::entity_types;
+use crate::net::packets::ConnectionId;
+use crate::net::utils::broadcast::broadcast_to_viewers;
+use crate::state::GlobalState;
+use crate::utils::components::body::Body;
+use crate::utils::components::dimension::{dimension_of, CurrentDimension};
+use crate::utils::components::game_mode::GameMode;
+use crate::utils::components::health::Health;
+use crate::utils::components::item_entity::ItemEntity;
+use crate::utils::components::motion::Motion;
+use crate::utils::components::object_entity::ObjectEntity;
+use crate::utils::components::player::Player;
+use crate::utils::components::rotation::Rotation;
+use crate::utils::encoding::position::Position;
+use crate::utils::encoding::slot::ItemStack;
+use crate::utils::prelude::*;
+use crate::world::dimension::Dimension;
+
+/// Half the width of an item.
+pub const ITEM_RADIUS: f64 = 0.125;
+/// Items are dropped from a bit below the player's eyes.
+const DROP_HEIGHT: f64 = 1.62 - 0.3;
+const THROW_SPEED: f64 = 0.3;
+
+/// Drops an item into the world, moving at `velocity` blocks per tick. Returns its entity.
+pub async fn spawn_item(
+    state: &GlobalState,
+    item: ItemStack,
+    dimension: Dimension,
+    position: (f64, f64, f64),
+    velocity: (f64, f64, f64),
+    pickup_delay: u32,
+) -> usize {
+    let item = ItemEntity::new(item, pickup_delay);
+    let object = ObjectEntity::new(entity_types::ITEM, item.metadata());
+    state
+        .world
+        .create_entity()
+        .await
+        .with(item)
+        .with(object)
+        .with(Motion::new(position, velocity))
+        .with(Body::ITEM)
+        .with(CurrentDimension::new(dimension))
+        .build()
+}
+
+/// Throws an item the way a player is looking, like pressing the drop key.
+pub async fn throw_item(state: &GlobalState, conn_id: ConnectionId, item: ItemStack) -> Result<()> {
+    let (position, rotation, dimension) = drop_point(state, conn_id).await?;
+    let velocity = throw_velocity(&rotation, spread());
+    spawn_item(state, item, dimension, position, velocity, ItemEntity::THROWN_PICKUP_DELAY).await;
+    Ok(())
+}
+
+/// Drops items around a player in every direction, like when they die.
+pub async fn scatter_items(
+    state: &GlobalState,
+    conn_id: ConnectionId,
+    items: Vec<ItemStack>,
+) -> Result<()> {
+    let (position, _, dimension) = drop_point(state, conn_id).await?;
+    for item in items {
+        let speed = rand::random::<f64>() * 0.5;
+        let angle = rand::random::<f64>() * TAU;
+        let velocity = (-angle.sin() * speed, 0.2, angle.cos() * speed);
+        spawn_item(state, item, dimension, position, velocity, ItemEntity::THROWN_PICKUP_DELAY)
+            .await;
+    }
+    Ok(())
+}
+
+/// Where a player drops things from, which way they're looking and the dimension they're in.
+async fn drop_point(
+    state: &GlobalState,
+    conn_id: ConnectionId,
+) -> Result<((f64, f64, f64), Rotation, Dimension)> {
+    let position = state.world.get_component::<Position>(conn_id).await?.clone();
+    let rotation = state
+        .world
+        .get_component::<Rotation>(conn_id)
+        .await
+        .map(|rotation| rotation.clone())
+        .unwrap_or_else(|_| Rotation::new(0.0, 0.0));
+    let dimension = dimension_of(state, conn_id).await;
+    // Positions are whole blocks, so the player is taken to be in the middle of theirs
+    let position = (
+        position.x as f64 + 0.5,
+        position.y as f64 + DROP_HEIGHT,
+        position.z as f64 + 0.5,
+    );
+    Ok((position, rotation, dimension))
+}
+
+/// A little randomness for thrown items, so they don't all land in the same spot: an angle, how
+/// far off to the side they go, and how much higher or lower.
+fn spread() -> (f64, f64, f64) {
+    (
+        rand::random::<f64>() * TAU,
+        rand::random::<f64>() * 0.02,
+        (rand::random::<f64>() - rand::random::<f64>()) * 0.1,
+    )
+}
+
+/// How fast an item is thrown, the same as vanilla.
+fn throw_velocity(rotation: &Rotation, (angle, sideways, up): (f64, f64, f64)) -> (f64, f64, f64) {
+    let yaw = (rotation.yaw as f64).to_radians();
+    let pitch = (rotation.pitch as f64).to_radians();
+    (
+        -yaw.sin() * pitch.cos() * THROW_SPEED + angle.cos() * sideways,
+        -pitch.sin() * THROW_SPEED + 0.1 + up,
+        yaw.cos() * pitch.cos() * THROW_SPEED + angle.sin() * sideways,
+    )
+}
+
+/// Replaces an item entity's item and timers, and shows everyone who can see it the new item.
+pub async fn update_item(state: &GlobalState, entity_id: usize, item: ItemEntity) -> Result<()> {
+    let metadata = item.metadata();
+    {
+        let component_storage = state.world.get_component_storage();
+        *component_storage.get_mut::<ItemEntity>(entity_id).await? = item;
+        component_storage
+            .get_mut::<ObjectEntity>(entity_id)
+            .await?
+            .metadata = metadata.clone();
+    }
+    broadcast_to_viewers(SetEntityMetadata::new(entity_id, metadata), entity_id, state).await
+}
+
+/// Players that can pick things up, which is everyone that isn't a spectator or dead. Returns
+/// their entity, where they're standing and the dimension they're in.
+pub async fn collectors(state: &GlobalState) -> Vec<(usize, (f64, f64, f64), Dimension)> {
+    let query = state.world.query::<(
+        &Player,
+        &Position,
+        Option<&CurrentDimension>,
+        Option<&GameMode>,
+        Option<&Health>,
+    )>();
+    query
+        .iter()
+        .await
+        .filter(|(_, (_, _, _, game_mode, health))| {
+            game_mode
+                .as_ref()
+                .is_none_or(|game_mode| game_mode.mode != GameMode::SPECTATOR)
+                && health.as_ref().is_none_or(|health| !health.is_dead())
+        })
+        .map(|(id, (_, position, dimension, _, _))| {
+            // Positions are whole blocks, so the player is taken to be in the middle of theirs
+            let position = (
+                position.x as f64 + 0.5,
+                position.y as f64,
+                position.z as f64 + 0.5,
+            );
+            (id, position, dimension.map_or(Dimension::Overworld, |d| d.dimension))
+        })
+        .collect()
+}
+
+/// Where the closest of the [collectors] in `dimension` to `position` is standing, if there are
+/// any.
+pub fn nearest(
+    collectors: &[(usize, (f64, f64, f64), Dimension)],
+    dimension: Dimension,
+    position: (f64, f64, f64),
+) -> Option<(f64, f64, f64)> {
+    let distance = |(x, y, z): (f64, f64, f64)| {
+        (x - position.0).powi(2) + (y - position.1).powi(2) + (z - position.2).powi(2)
+    };
+    collectors
+        .iter()
+        .filter(|(_, _, collector_dimension)| *collector_dimension == dimension)
+        .map(|(_, collector, _)| *collector)
+        .min_by(|a, b| distance(*a).total_cmp(&distance(*b)))
+}
+
+/// Whether a player standing at `player` can pick up an item at `item`. Vanilla reaches a block
+/// out from the player's hitbox, and half a block above and below it.
+pub fn in_pickup_reach(player: (f64, f64, f64), item: (f64, f64, f64)) -> bool {
+    const PLAYER_RADIUS: f64 = 0.3;
+    const PLAYER_HEIGHT: f64 = 1.8;
+    let reach = PLAYER_RADIUS + 1.0 + ITEM_RADIUS;
+    let above = item.1 - player.1;
+    (item.0 - player.0).abs() <= reach
+        && (item.2 - player.2).abs() <= reach
+        && (-0.5 - 2.0 * ITEM_RADIUS..=PLAYER_HEIGHT + 0.5).contains(&above)
+}
+
+/// Whether two items are close enough to merge, which vanilla has as half a block apart to the
+/// side and touching vertically.
+pub fn in_merge_reach(a: (f64, f64, f64), b: (f64, f64, f64)) -> bool {
+    let reach = 2.0 * ITEM_RADIUS + 0.5;
+    (a.0 - b.0).abs() <= reach
+        && (a.2 - b.2).abs() <= reach
+        && (a.1 - b.1).abs() < 2.0 * ITEM_RADIUS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reach() {
+        let player = (0.5, 64.0, 0.5);
+        assert!(in_pickup_reach(player, (1.5, 64.0, -0.5)));
+        assert!(in_pickup_reach(player, (0.5, 66.0, 0.5)));
+        assert!(!in_pickup_reach(player, (2.5, 64.0, 0.5)));
+        assert!(!in_pickup_reach(player, (0.5, 63.0, 0.5)));
+
+        assert!(in_merge_reach((0.5, 64.0, 0.5), (1.0, 64.1, 0.0)));
+        assert!(!in_merge_reach((0.5, 64.0, 0.5), (0.5, 64.5, 0.5)));
+        assert!(!in_merge_reach((0.5, 64.0, 0.5), (1.5, 64.0, 0.5)));
+    }
+
+    #[test]
+    fn test_throw_velocity() {
+        // Straight ahead, which is south at yaw 0
+        let (x, y, z) = throw_velocity(&Rotation::new(0.0, 0.0), (0.0, 0.0, 0.0));
+        assert!(x.abs() < 1e-9);
+        assert!((y - 0.1).abs() < 1e-9);
+        assert!((z - THROW_SPEED).abs() < 1e-9);
+        // Looking straight down
+        let (_, y, z) = throw_velocity(&Rotation::new(0.0, 90.0), (0.0, 0.0, 0.0));
+        assert!((y + 0.2).abs() < 1e-9);
+        assert!(z.abs() < 1e-9);
+    }
+}
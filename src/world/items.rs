@@ -0,0 +1,255 @@
+//! Item IDs, which the client uses for everything in its inventory.
+//!
+//! They aren't embedded like the block mappings, but read from `registries.json`, the report the
+//! vanilla server's data generator makes:
+//! `java -DbundlerMainClass=net.minecraft.data.Main -jar server.jar --reports`.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use serde::Deserialize;
+use tracing::{info, warn};
+
+use crate::utils::encoding::slot::ItemStack;
+use crate::utils::prelude::*;
+use crate::world::chunk_format::Palette;
+use crate::world::conversions::default_state;
+
+#[derive(Deserialize)]
+struct Registries {
+    #[serde(rename = "minecraft:item")]
+    item: Registry,
+}
+
+#[derive(Deserialize)]
+struct Registry {
+    entries: HashMap<String, RegistryEntry>,
+}
+
+#[derive(Deserialize)]
+struct RegistryEntry {
+    protocol_id: i32,
+}
+
+/// Maps item IDs to their names, e.g. `minecraft:stone`, and back.
+#[derive(Default)]
+pub struct ItemRegistry {
+    names: HashMap<i32, String>,
+    ids: HashMap<String, i32>,
+}
+
+impl ItemRegistry {
+    /// Reads the item registry from a data generator report. Without one, no items are known, so
+    /// players can't place blocks.
+    pub async fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = match tokio::fs::read_to_string(path).await {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                warn!(
+                    "{} wasn't found, so players won't be able to place blocks. Generate it with the vanilla server's data generator.",
+                    path.display()
+                );
+                return Ok(Self::default());
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        let registry = Self::parse(&contents)
+            .map_err(|e| Error::DeserializationError(format!("{}: {}", path.display(), e)))?;
+        info!("Loaded {} items", registry.names.len());
+        Ok(registry)
+    }
+
+    pub(crate) fn parse(contents: &str) -> serde_json::Result<Self> {
+        let registries: Registries = serde_json::from_str(contents)?;
+        let ids: HashMap<String, i32> = registries
+            .item
+            .entries
+            .into_iter()
+            .map(|(name, entry)| (name, entry.protocol_id))
+            .collect();
+        let names = ids.iter().map(|(name, &id)| (id, name.clone())).collect();
+        Ok(Self { names, ids })
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    pub fn name(&self, id: i32) -> Option<&str> {
+        self.names.get(&id).map(String::as_str)
+    }
+
+    /// The ID of an item by its name.
+    pub fn id(&self, name: &str) -> Option<i32> {
+        self.ids.get(name).copied()
+    }
+
+    /// How many of an item fit in one slot. Items that aren't known can have a full stack, so
+    /// nothing is held back when there's no registry.
+    pub fn max_stack_size(&self, id: i32) -> i8 {
+        self.name(id).map_or(64, max_stack_size)
+    }
+
+    /// Whether a stack could exist: the item is known, unless there's no registry at all, and
+    /// there's between 1 and a full stack of it.
+    pub fn is_valid(&self, item: &ItemStack) -> bool {
+        (self.is_empty() || self.names.contains_key(&item.id))
+            && item.count > 0
+            && item.count <= self.max_stack_size(item.id)
+    }
+
+    /// The block an item places, if it's a block item. Block items share their block's name.
+    pub fn block(&self, id: i32) -> Option<Palette> {
+        let name = self.name(id)?;
+        default_state(name).filter(|block| !block.is_air())
+    }
+}
+
+/// Items that can't be stacked at all, besides the ones [max_stack_size] finds by their suffix.
+const UNSTACKABLE: &[&str] = &[
+    "bow",
+    "crossbow",
+    "trident",
+    "shield",
+    "elytra",
+    "fishing_rod",
+    "carrot_on_a_stick",
+    "warped_fungus_on_a_stick",
+    "flint_and_steel",
+    "shears",
+    "brush",
+    "spyglass",
+    "potion",
+    "splash_potion",
+    "lingering_potion",
+    "saddle",
+    "totem_of_undying",
+    "enchanted_book",
+    "writable_book",
+    "written_book",
+    "knowledge_book",
+    "debug_stick",
+    "bundle",
+    "cake",
+    "goat_horn",
+    "minecart",
+    "bamboo_raft",
+    "bamboo_chest_raft",
+];
+
+const UNSTACKABLE_SUFFIXES: &[&str] = &[
+    "_sword",
+    "_pickaxe",
+    "_axe",
+    "_shovel",
+    "_hoe",
+    "_helmet",
+    "_chestplate",
+    "_leggings",
+    "_boots",
+    "_horse_armor",
+    // Water, lava, milk, fish etc. Empty buckets stack to 16
+    "_bucket",
+    "_stew",
+    "_soup",
+    "_minecart",
+    "_boat",
+    "_bed",
+    "shulker_box",
+];
+
+const STACK_OF_16: &[&str] = &[
+    "ender_pearl",
+    "snowball",
+    "egg",
+    "bucket",
+    "honey_bottle",
+    "armor_stand",
+];
+
+const STACK_OF_16_SUFFIXES: &[&str] = &["_sign", "_banner"];
+
+/// How many of an item fit in one slot, by its name. The registry report doesn't have them, so
+/// these are vanilla's, found by the kind of item.
+pub fn max_stack_size(name: &str) -> i8 {
+    let name = name.strip_prefix("minecraft:").unwrap_or(name);
+    if name.starts_with("music_disc_")
+        || UNSTACKABLE.contains(&name)
+        || UNSTACKABLE_SUFFIXES
+            .iter()
+            .any(|suffix| name.ends_with(suffix))
+    {
+        1
+    } else if STACK_OF_16.contains(&name)
+        || STACK_OF_16_SUFFIXES
+            .iter()
+            .any(|suffix| name.ends_with(suffix))
+    {
+        16
+    } else {
+        64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_item_registry() {
+        let registry = ItemRegistry::parse(
+            r#"{
+                "minecraft:item": {
+                    "default": "minecraft:air",
+                    "entries": {
+                        "minecraft:air": { "protocol_id": 0 },
+                        "minecraft:stone": { "protocol_id": 1 },
+                        "minecraft:oak_log": { "protocol_id": 110 },
+                        "minecraft:diamond": { "protocol_id": 766 }
+                    },
+                    "protocol_id": 6
+                }
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(registry.name(766), Some("minecraft:diamond"));
+        assert_eq!(registry.id("minecraft:diamond"), Some(766));
+        assert_eq!(registry.id("minecraft:moon_rock"), None);
+        assert_eq!(registry.block(1), Some(Palette::new("minecraft:stone")));
+        assert_eq!(
+            registry.block(110),
+            Some(Palette::new("minecraft:oak_log").with_property("axis", "y"))
+        );
+        assert_eq!(registry.block(766), None);
+        assert_eq!(registry.block(0), None);
+        assert_eq!(registry.block(5), None);
+        assert_eq!(registry.max_stack_size(766), 64);
+        assert_eq!(registry.max_stack_size(5), 64);
+        assert!(registry.is_valid(&ItemStack::new(766, 64)));
+        assert!(!registry.is_valid(&ItemStack::new(766, 65)));
+        assert!(!registry.is_valid(&ItemStack::new(766, 0)));
+        assert!(!registry.is_valid(&ItemStack::new(5, 1)));
+        assert!(ItemRegistry::default().is_valid(&ItemStack::new(5, 1)));
+    }
+
+    #[test]
+    fn test_max_stack_size() {
+        assert_eq!(max_stack_size("minecraft:stone"), 64);
+        assert_eq!(max_stack_size("minecraft:diamond_sword"), 1);
+        assert_eq!(max_stack_size("minecraft:water_bucket"), 1);
+        assert_eq!(max_stack_size("minecraft:music_disc_cat"), 1);
+        assert_eq!(max_stack_size("minecraft:red_shulker_box"), 1);
+        assert_eq!(max_stack_size("minecraft:bucket"), 16);
+        assert_eq!(max_stack_size("minecraft:oak_hanging_sign"), 16);
+        assert_eq!(max_stack_size("minecraft:white_banner"), 16);
+        // Not a hanging sign or anything else that ends in one of the suffixes
+        assert_eq!(max_stack_size("minecraft:bedrock"), 64);
+    }
+}
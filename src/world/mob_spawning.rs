@@ -0,0 +1,348 @@
+//! Mobs spawning on their own around players.
+//!
+//! Every so often each [MobCategory] gets a few tries at spawning a pack of mobs in the chunks
+//! players have loaded close by, as long as there aren't already as many of them there as its cap
+//! allows. Like vanilla, the cap grows with the number of chunks, so it works out per player rather
+//! than for the whole server. Monsters spawn in the dark on any solid block, at a random height,
+//! and animals on grass in the light, on the surface. Only the overworld has mobs spawning for now.
+//!
+//! There's no light engine yet, so a block's light is 15 if it can see the sky and otherwise
+//! whatever block light the chunk has stored, which generated chunks don't have.
+//!
+//! Monsters that end up far away from every player despawn, the ones that were summoned too.
+
+use crate::state::GlobalState;
+use crate::utils::components::chunk_tracker::ChunkTracker;
+use crate::utils::components::dimension::CurrentDimension;
+use crate::utils::components::mob::Mob;
+use crate::utils::components::motion::Motion;
+use crate::utils::config::get_global_config;
+use crate::utils::prelude::*;
+use crate::world::blocks::is_replaceable;
+use crate::world::chunk_format::{Chunk, Palette};
+use crate::world::dimension::Dimension;
+use crate::world::generation::get_or_generate_chunk;
+use crate::world::item_entities::{collectors, nearest};
+use crate::world::mobs::{spawn_mob, MobCategory, MOB_TYPES};
+
+/// Monsters get a go at spawning this often, in ticks.
+pub const MONSTER_INTERVAL: u64 = 20;
+/// Animals get a go at spawning this often, in ticks. They don't despawn, so they don't need
+/// replacing as often.
+pub const CREATURE_INTERVAL: u64 = 400;
+/// Mobs spawn in chunks at most this far from a player, in chunks.
+const SPAWN_RADIUS: i32 = 8;
+/// Caps are for this many chunks, which is how many are within [SPAWN_RADIUS] of one player.
+const CAP_CHUNKS: usize = 17 * 17;
+/// Mobs don't spawn any closer to a player than this, in blocks.
+const MIN_PLAYER_DISTANCE: f64 = 24.0;
+/// Monsters further than this from every player despawn, in blocks.
+const DESPAWN_DISTANCE: f64 = 128.0;
+/// Packs a category tries to spawn each time it gets a go.
+const PACKS_PER_GO: usize = 8;
+/// The most mobs in a pack.
+const PACK_SIZE: usize = 4;
+/// How far the mobs in a pack spread out from the first, in blocks. They stay in its chunk.
+const PACK_SPREAD: i32 = 3;
+/// Animals need at least this much light to spawn.
+const MIN_CREATURE_LIGHT: u8 = 9;
+
+impl MobCategory {
+    /// The most of this category there can be around a single player.
+    fn base_cap(self) -> u32 {
+        let config = &get_global_config().mobs;
+        match self {
+            MobCategory::Monster => config.monster_cap,
+            MobCategory::Creature => config.creature_cap,
+        }
+    }
+}
+
+/// How many mobs of a category with a cap of `base` there can be among `chunks` chunks.
+pub fn mob_cap(base: u32, chunks: usize) -> usize {
+    base as usize * chunks / CAP_CHUNKS
+}
+
+/// Whether a mob of `category` can spawn with its feet in `feet`, standing on `below`. `light` is
+/// the light level where its feet are.
+pub fn can_spawn_at(
+    category: MobCategory,
+    below: &Palette,
+    feet: &Palette,
+    head: &Palette,
+    light: u8,
+) -> bool {
+    let clear = |block: &Palette| is_replaceable(block) && !is_liquid(block);
+    if !clear(feet) || !clear(head) {
+        return false;
+    }
+    match category {
+        MobCategory::Monster => light == 0 && !is_replaceable(below),
+        MobCategory::Creature => {
+            light >= MIN_CREATURE_LIGHT && below.name == "minecraft:grass_block"
+        }
+    }
+}
+
+fn is_liquid(block: &Palette) -> bool {
+    matches!(block.name.as_str(), "minecraft:water" | "minecraft:lava")
+}
+
+/// The light level of a block in a chunk, with x and z relative to the chunk. See the
+/// [module docs](self) for how it's worked out.
+pub fn light_level(chunk: &mut Chunk, x: usize, y: i32, z: usize) -> u8 {
+    if y >= chunk.surface_height(x, z) {
+        return 15;
+    }
+    let section_y = y.div_euclid(16) as i8;
+    let block_light = chunk
+        .sections
+        .as_ref()
+        .and_then(|sections| sections.iter().find(|section| section.y == section_y))
+        .and_then(|section| section.block_light.as_ref());
+    let Some(block_light) = block_light else {
+        return 0;
+    };
+    // Two blocks to a byte, the first in the low half
+    let index = y.rem_euclid(16) as usize * 256 + z * 16 + x;
+    let byte = block_light.get(index / 2).copied().unwrap_or(0) as u8;
+    if index.is_multiple_of(2) {
+        byte & 0xF
+    } else {
+        byte >> 4
+    }
+}
+
+/// Gives `category` a go at spawning mobs around the players in the overworld.
+pub async fn spawn_mobs(state: &GlobalState, category: MobCategory) -> Result<()> {
+    let players = collectors(state)
+        .await
+        .into_iter()
+        .filter(|(_, _, dimension)| *dimension == Dimension::Overworld)
+        .collect::<Vec<_>>();
+    if players.is_empty() {
+        return Ok(());
+    }
+
+    let chunks = spawn_chunks(state, &players).await;
+    let cap = mob_cap(category.base_cap(), chunks.len());
+    let mut count = {
+        let query = state
+            .world
+            .query::<(&Mob, &Motion, Option<&CurrentDimension>)>();
+        query
+            .iter()
+            .await
+            .filter(|(_, (mob, motion, dimension))| {
+                let (x, _, z) = motion.position;
+                let chunk = ((x.floor() as i32) >> 4, (z.floor() as i32) >> 4);
+                mob.mob_type.category == category
+                    && mob.dead_for.is_none()
+                    && dimension.as_ref().is_none_or(|d| d.dimension == Dimension::Overworld)
+                    && chunks.contains(&chunk)
+            })
+            .count()
+    };
+
+    let positions = players
+        .iter()
+        .map(|(_, position, _)| *position)
+        .collect::<Vec<_>>();
+    for _ in 0..PACKS_PER_GO {
+        if count >= cap {
+            break;
+        }
+        let chunk = chunks[random_below(chunks.len() as i32) as usize];
+        count += spawn_pack(state, category, chunk, &positions, cap - count).await?;
+    }
+    Ok(())
+}
+
+/// The chunks mobs can spawn in, which are the ones players have loaded within [SPAWN_RADIUS] of
+/// them.
+async fn spawn_chunks(
+    state: &GlobalState,
+    players: &[(usize, (f64, f64, f64), Dimension)],
+) -> Vec<(i32, i32)> {
+    let radius = SPAWN_RADIUS.min(get_global_config().view_distance as i32);
+    let query = state.world.query::<&ChunkTracker>();
+    let mut chunks = query
+        .iter()
+        .await
+        .filter_map(|(id, tracker)| {
+            let (_, (x, _, z), _) = players.iter().find(|(player, _, _)| *player == id)?;
+            let center = ((x.floor() as i32) >> 4, (z.floor() as i32) >> 4);
+            let chunks = tracker
+                .loaded
+                .iter()
+                .filter(|(chunk_x, chunk_z)| {
+                    (chunk_x - center.0).abs() <= radius && (chunk_z - center.1).abs() <= radius
+                })
+                .copied()
+                .collect::<Vec<_>>();
+            Some(chunks)
+        })
+        .flatten()
+        .collect::<Vec<_>>();
+    chunks.sort_unstable();
+    chunks.dedup();
+    chunks
+}
+
+/// Tries to spawn a pack of one kind of mob somewhere in a chunk, but no more than `room` of them.
+/// Returns how many were spawned.
+async fn spawn_pack(
+    state: &GlobalState,
+    category: MobCategory,
+    (chunk_x, chunk_z): (i32, i32),
+    players: &[(f64, f64, f64)],
+    room: usize,
+) -> Result<usize> {
+    let dimension = Dimension::Overworld;
+    let Some(mut chunk) = get_or_generate_chunk(state, chunk_x, chunk_z, dimension.name()).await?
+    else {
+        return Ok(0);
+    };
+    let mob_types = MOB_TYPES
+        .iter()
+        .filter(|mob_type| mob_type.category == category)
+        .collect::<Vec<_>>();
+    let mob_type = mob_types[random_below(mob_types.len() as i32) as usize];
+
+    let (x, z) = (random_below(16), random_below(16));
+    let surface = chunk.surface_height(x as usize, z as usize);
+    let y = match category {
+        MobCategory::Monster => dimension.min_y() + random_below(surface - dimension.min_y()),
+        MobCategory::Creature => surface,
+    };
+
+    let mut spawned = 0;
+    for _ in 0..PACK_SIZE {
+        if spawned >= room {
+            break;
+        }
+        let spread = || random_below(PACK_SPREAD * 2 + 1) - PACK_SPREAD;
+        let (x, z) = ((x + spread()).clamp(0, 15), (z + spread()).clamp(0, 15));
+        let position = (
+            (chunk_x * 16 + x) as f64 + 0.5,
+            y as f64,
+            (chunk_z * 16 + z) as f64 + 0.5,
+        );
+        let too_close = players
+            .iter()
+            .any(|&player| distance(player, position) < MIN_PLAYER_DISTANCE);
+        if too_close || !can_spawn_in(&mut chunk, category, x as usize, y, z as usize) {
+            continue;
+        }
+        let yaw = (rand::random::<f64>() * 360.0) as f32;
+        spawn_mob(state, mob_type, dimension, position, yaw).await;
+        spawned += 1;
+    }
+    Ok(spawned)
+}
+
+fn can_spawn_in(chunk: &mut Chunk, category: MobCategory, x: usize, y: i32, z: usize) -> bool {
+    let (Ok(below), Ok(feet), Ok(head)) = (
+        chunk.get_block(x, y - 1, z),
+        chunk.get_block(x, y, z),
+        chunk.get_block(x, y + 1, z),
+    ) else {
+        return false;
+    };
+    let light = light_level(chunk, x, y, z);
+    can_spawn_at(category, &below, &feet, &head, light)
+}
+
+/// Despawns monsters that are further than [DESPAWN_DISTANCE] from every player in their
+/// dimension. Dimensions without anyone in them are left alone.
+pub async fn despawn_far_monsters(state: &GlobalState) -> Result<()> {
+    let players = collectors(state).await;
+    let far = {
+        let query = state
+            .world
+            .query::<(&Mob, &Motion, Option<&CurrentDimension>)>();
+        query
+            .iter()
+            .await
+            .filter(|(_, (mob, motion, dimension))| {
+                let dimension = dimension.as_ref().map_or(Dimension::Overworld, |d| d.dimension);
+                let Some(player) = nearest(&players, dimension, motion.position) else {
+                    return false;
+                };
+                mob.mob_type.category == MobCategory::Monster
+                    && distance(player, motion.position) > DESPAWN_DISTANCE
+            })
+            .map(|(id, _)| id)
+            .collect::<Vec<_>>()
+    };
+    for id in far {
+        state.world.delete_entity(id).await?;
+    }
+    Ok(())
+}
+
+fn distance(a: (f64, f64, f64), b: (f64, f64, f64)) -> f64 {
+    ((a.0 - b.0).powi(2) + (a.1 - b.1).powi(2) + (a.2 - b.2).powi(2)).sqrt()
+}
+
+/// A random number from 0 up to, but not including, `n`. Gives 0 if `n` isn't positive.
+fn random_below(n: i32) -> i32 {
+    (rand::random::<f64>() * n.max(0) as f64) as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::generation::builder::ChunkBuilder;
+
+    #[test]
+    fn test_mob_cap() {
+        assert_eq!(mob_cap(70, CAP_CHUNKS), 70);
+        // Two players far apart have twice the chunks
+        assert_eq!(mob_cap(70, CAP_CHUNKS * 2), 140);
+        assert_eq!(mob_cap(10, 100), 3);
+        assert_eq!(mob_cap(70, 0), 0);
+    }
+
+    #[test]
+    fn test_can_spawn_at() {
+        let (air, grass, stone, water) = (
+            Palette::new("minecraft:air"),
+            Palette::new("minecraft:grass_block"),
+            Palette::new("minecraft:stone"),
+            Palette::new("minecraft:water"),
+        );
+        let monster = MobCategory::Monster;
+        let creature = MobCategory::Creature;
+        assert!(can_spawn_at(monster, &stone, &air, &air, 0));
+        assert!(!can_spawn_at(monster, &stone, &air, &air, 7));
+        assert!(!can_spawn_at(monster, &air, &air, &air, 0));
+        assert!(!can_spawn_at(monster, &stone, &water, &air, 0));
+        assert!(!can_spawn_at(monster, &stone, &air, &stone, 0));
+
+        assert!(can_spawn_at(creature, &grass, &air, &air, 15));
+        assert!(!can_spawn_at(creature, &grass, &air, &air, 4));
+        assert!(!can_spawn_at(creature, &stone, &air, &air, 15));
+    }
+
+    #[test]
+    fn test_light_level() {
+        // Stone up to y 15, with a torch's worth of light stored for the bottom corner of y 0
+        let mut builder = ChunkBuilder::new(0, 0, "minecraft:overworld");
+        let stone = Palette::new("minecraft:stone");
+        for (x, z) in (0..16).flat_map(|x| (0..16).map(move |z| (x, z))) {
+            builder.fill_column(x, z, builder.min_y(), 15, &stone);
+        }
+        let mut chunk = builder.build();
+        let mut block_light = vec![0i8; 2048];
+        block_light[0] = 0x7E;
+        let section = chunk.sections.as_mut().unwrap().iter_mut().find(|s| s.y == 0);
+        section.unwrap().block_light = Some(block_light);
+
+        assert_eq!(light_level(&mut chunk, 0, 16, 0), 15);
+        assert_eq!(light_level(&mut chunk, 0, 0, 0), 14);
+        assert_eq!(light_level(&mut chunk, 1, 0, 0), 7);
+        assert_eq!(light_level(&mut chunk, 0, 1, 0), 0);
+        assert_eq!(light_level(&mut chunk, 0, -10, 0), 0);
+    }
+}
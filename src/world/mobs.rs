@@ -0,0 +1,302 @@
+//! Mobs, the animals and monsters that wander around the world.
+//!
+//! Every kind of mob the server knows is in [MOB_TYPES]. Their AI is kept minimal for now: they
+//! stroll to a random spot nearby every so often, jump up blocks in their way, and look at players
+//! standing close by when they're not walking anywhere. Hostile ones don't attack yet. See
+//! [MobTicker](crate::net::systems::mob_ticker) for what happens to them each tick.
+
+use std::f64::consts::TAU;
+
+use crate::net::packets::outgoing::entity_event::{statuses, EntityEvent};
+use crate::net::utils::broadcast::broadcast_to_viewers;
+use crate::state::GlobalState;
+use crate::utils::components::body::Body;
+use crate::utils::components::dimension::CurrentDimension;
+use crate::utils::components::health::Health;
+use crate::utils::components::mob::Mob;
+use crate::utils::components::motion::Motion;
+use crate::utils::components::object_entity::ObjectEntity;
+use crate::utils::components::rotation::Rotation;
+use crate::utils::encoding::metadata::{EntityMetadata, MetadataValue};
+use crate::utils::prelude::*;
+use crate::world::dimension::Dimension;
+
+/// A kind of mob, e.g. a pig.
+#[derive(Debug, PartialEq)]
+pub struct MobType {
+    /// Its name in the `minecraft:entity_type` registry, without the namespace.
+    pub name: &'static str,
+    /// Its id in the `minecraft:entity_type` registry.
+    pub entity_type: i32,
+    /// In half hearts, never more than [Health::MAX].
+    pub max_health: f32,
+    /// How wide it is, in blocks.
+    pub width: f64,
+    /// How tall it is, in blocks. It looks out from a bit below the top.
+    pub height: f64,
+    /// How fast it walks, in blocks per tick.
+    pub speed: f64,
+    /// Which cap it counts towards.
+    pub category: MobCategory,
+    /// How many experience points it drops when it's killed.
+    pub experience: i32,
+}
+
+/// Which cap a mob counts towards, and where it spawns naturally. See
+/// [mob_spawning](crate::world::mob_spawning).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MobCategory {
+    /// Hostile mobs, which spawn in the dark.
+    Monster,
+    /// Animals, which spawn on grass in the light.
+    Creature,
+}
+
+pub static MOB_TYPES: &[MobType] = &[
+    MobType::passive("chicken", 15, 4.0, (0.4, 0.7)),
+    MobType::passive("cow", 18, 10.0, (0.9, 1.4)),
+    MobType::hostile("creeper", 19, 20.0, (0.6, 1.7)),
+    MobType::passive("pig", 72, 10.0, (0.9, 0.9)),
+    MobType::passive("sheep", 82, 8.0, (0.9, 1.3)),
+    MobType::hostile("skeleton", 86, 20.0, (0.6, 1.99)),
+    MobType::hostile("spider", 95, 16.0, (1.4, 0.9)),
+    MobType::hostile("zombie", 118, 20.0, (0.6, 1.95)),
+];
+
+/// Mobs that don't have a walk target pick one about every this many ticks, like vanilla.
+const WANDER_INTERVAL: f64 = 120.0;
+/// The furthest away a mob wanders to in one go, in blocks.
+const WANDER_DISTANCE: f64 = 10.0;
+/// Mobs that haven't got where they were going after this many ticks give up.
+const MAX_WALKING_TICKS: u32 = 200;
+/// Players further away than this many blocks aren't looked at.
+const LOOK_RANGE: f64 = 8.0;
+/// Vanilla's 0.42, which is enough to get up a block, plus the gravity [physics] takes off before
+/// it moves.
+///
+/// [physics]: crate::world::physics
+const JUMP_VELOCITY: f64 = 0.42 + 0.08;
+/// How long a dead mob lies there for before it's removed, in ticks.
+pub const DEATH_TICKS: u32 = 20;
+/// Where a living entity keeps its health in its metadata.
+const HEALTH_INDEX: u8 = 9;
+
+impl MobType {
+    /// `size` is its width and height.
+    const fn passive(
+        name: &'static str,
+        entity_type: i32,
+        max_health: f32,
+        size: (f64, f64),
+    ) -> Self {
+        Self {
+            name,
+            entity_type,
+            max_health,
+            width: size.0,
+            height: size.1,
+            speed: 0.1,
+            category: MobCategory::Creature,
+            experience: 2,
+        }
+    }
+
+    const fn hostile(
+        name: &'static str,
+        entity_type: i32,
+        max_health: f32,
+        size: (f64, f64),
+    ) -> Self {
+        Self {
+            category: MobCategory::Monster,
+            speed: 0.08,
+            experience: 5,
+            ..Self::passive(name, entity_type, max_health, size)
+        }
+    }
+
+    /// Looks a mob up by name, e.g. `minecraft:pig` or just `pig`.
+    pub fn from_name(name: &str) -> Option<&'static MobType> {
+        let name = name.strip_prefix("minecraft:").unwrap_or(name);
+        MOB_TYPES.iter().find(|mob_type| mob_type.name == name)
+    }
+
+    fn eye_height(&self) -> f64 {
+        self.height * 0.85
+    }
+
+    pub fn body(&self) -> Body {
+        Body::mob(self.width, self.height)
+    }
+}
+
+/// Spawns a mob at full health, facing `yaw`. Returns its entity.
+pub async fn spawn_mob(
+    state: &GlobalState,
+    mob_type: &'static MobType,
+    dimension: Dimension,
+    position: (f64, f64, f64),
+    yaw: f32,
+) -> usize {
+    let health = Health {
+        health: mob_type.max_health,
+        ..Default::default()
+    };
+    let object = ObjectEntity::new(mob_type.entity_type, metadata(&health));
+    state
+        .world
+        .create_entity()
+        .await
+        .with(Mob::new(mob_type))
+        .with(health)
+        .with(object)
+        .with(Motion::new(position, (0.0, 0.0, 0.0)))
+        .with(mob_type.body())
+        .with(Rotation::new(yaw, 0.0))
+        .with(CurrentDimension::new(dimension))
+        .build()
+}
+
+pub fn metadata(health: &Health) -> EntityMetadata {
+    let mut metadata = EntityMetadata::new();
+    metadata.set(HEALTH_INDEX, MetadataValue::Float(health.health));
+    metadata
+}
+
+/// Plays a mob's death animation for everyone who can see it. It's removed once that's done.
+pub async fn kill_mob(state: &GlobalState, entity_id: usize) -> Result<()> {
+    state.world.get_component_storage().get_mut::<Mob>(entity_id).await?.dead_for = Some(0);
+    let event = EntityEvent::new_auto(entity_id as i32, statuses::DEATH);
+    broadcast_to_viewers(event, entity_id, state).await
+}
+
+/// Runs a mob's AI for a tick, which sets where it's heading for [physics](crate::world::physics)
+/// to move it. `player` is where the nearest player is standing, if there's one in the same
+/// dimension, and `random` gives numbers from 0 to 1.
+pub fn tick(
+    mob: &mut Mob,
+    motion: &mut Motion,
+    rotation: &mut Rotation,
+    player: Option<(f64, f64, f64)>,
+    mut random: impl FnMut() -> f64,
+) {
+    if mob.dead_for.is_some() {
+        return;
+    }
+
+    if mob.target.is_none() && random() * WANDER_INTERVAL < 1.0 {
+        let angle = random() * TAU;
+        let distance = (0.5 + random() * 0.5) * WANDER_DISTANCE;
+        let (x, _, z) = motion.position;
+        mob.target = Some((x - angle.sin() * distance, z + angle.cos() * distance));
+        mob.walking_for = 0;
+    }
+
+    let (x, y, z) = motion.position;
+    match mob.target {
+        Some((target_x, target_z)) => {
+            let (dx, dz) = (target_x - x, target_z - z);
+            let distance = (dx * dx + dz * dz).sqrt();
+            mob.walking_for += 1;
+            if distance < 0.5 || mob.walking_for > MAX_WALKING_TICKS {
+                mob.target = None;
+            } else {
+                let speed = mob.mob_type.speed.min(distance);
+                motion.velocity.0 = dx / distance * speed;
+                motion.velocity.2 = dz / distance * speed;
+                // Ran into something last tick, so it tries jumping over it
+                if motion.blocked && motion.on_ground {
+                    motion.velocity.1 = JUMP_VELOCITY;
+                }
+                *rotation = Rotation::new(yaw_towards(dx, dz), 0.0);
+            }
+        }
+        None => {
+            let eyes = y + mob.mob_type.eye_height();
+            if let Some((player_x, player_y, player_z)) = player {
+                // Players' eyes are 1.62 blocks up
+                let (dx, dy, dz) = (player_x - x, player_y + 1.62 - eyes, player_z - z);
+                if (dx * dx + dy * dy + dz * dz).sqrt() < LOOK_RANGE {
+                    let pitch = -dy.atan2((dx * dx + dz * dz).sqrt()).to_degrees();
+                    *rotation = Rotation::new(yaw_towards(dx, dz), pitch as f32);
+                }
+            }
+        }
+    }
+}
+
+/// The yaw that faces along `dx` and `dz`. 0 is south, towards +z, and 90 is west.
+fn yaw_towards(dx: f64, dz: f64) -> f32 {
+    (-dx).atan2(dz).to_degrees() as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::collision::{Aabb, FULL_BLOCK};
+    use crate::world::physics::step;
+
+    /// A floor at y 63, with a step up at x 3 and above.
+    fn shapes(x: i32, y: i32, _z: i32) -> &'static [Aabb] {
+        if y <= 63 || (x >= 3 && y == 64) {
+            FULL_BLOCK
+        } else {
+            &[]
+        }
+    }
+
+    #[test]
+    fn test_from_name() {
+        assert_eq!(MobType::from_name("minecraft:pig").unwrap().entity_type, 72);
+        assert_eq!(MobType::from_name("zombie").unwrap().category, MobCategory::Monster);
+        assert!(MobType::from_name("minecraft:ender_dragon").is_none());
+    }
+
+    #[test]
+    fn test_yaw_towards() {
+        assert_eq!(yaw_towards(0.0, 1.0), 0.0);
+        assert_eq!(yaw_towards(-1.0, 0.0), 90.0);
+        assert_eq!(yaw_towards(1.0, 0.0), -90.0);
+    }
+
+    #[test]
+    fn test_wander() {
+        let pig = MobType::from_name("pig").unwrap();
+        let mut mob = Mob::new(pig);
+        let mut motion = Motion::new((0.5, 64.0, 0.5), (0.0, 0.0, 0.0));
+        motion.on_ground = true;
+        let mut rotation = Rotation::new(0.0, 0.0);
+
+        // Sets off east, towards the step, and jumps up it
+        let mut rolls = [0.0, 0.75, 1.0].into_iter();
+        tick(&mut mob, &mut motion, &mut rotation, None, || rolls.next().unwrap());
+        let (x, z) = mob.target.unwrap();
+        assert!((x - 10.5).abs() < 1e-9 && (z - 0.5).abs() < 1e-9);
+        assert_eq!(rotation.yaw, -90.0);
+        for _ in 0..150 {
+            step(&mut motion, &pig.body(), shapes);
+            tick(&mut mob, &mut motion, &mut rotation, None, || 1.0);
+        }
+        assert!(mob.target.is_none());
+        assert!((motion.position.0 - 10.5).abs() < 0.5);
+        assert_eq!(motion.position.1, 65.0);
+    }
+
+    #[test]
+    fn test_look_at_player() {
+        let zombie = MobType::from_name("zombie").unwrap();
+        let mut mob = Mob::new(zombie);
+        let mut motion = Motion::new((0.5, 64.0, 0.5), (0.0, 0.0, 0.0));
+        let mut rotation = Rotation::new(0.0, 0.0);
+
+        let player = Some((-3.5, 64.0, 0.5));
+        tick(&mut mob, &mut motion, &mut rotation, player, || 1.0);
+        assert_eq!(rotation.yaw, 90.0);
+        // Its eyes are a bit higher than a player's
+        assert!(rotation.pitch > 0.0 && rotation.pitch < 5.0);
+
+        let far = Some((20.5, 64.0, 0.5));
+        tick(&mut mob, &mut motion, &mut rotation, far, || 1.0);
+        assert_eq!(rotation.yaw, 90.0);
+    }
+}
@@ -1,7 +1,28 @@
+pub mod biomes;
+pub mod block_entities;
 pub mod blocks;
 pub mod chunk_format;
+pub mod collision;
+pub mod containers;
 pub mod conversions;
+pub mod dimension;
+pub mod experience_orbs;
+pub mod game_rules;
+pub mod generation;
+pub mod id_registry;
 pub mod importing;
+pub mod item_entities;
+pub mod items;
+pub mod mob_spawning;
+pub mod mobs;
+pub mod particles;
+pub mod physics;
+pub mod projectiles;
+pub mod recipes;
+pub mod registry_data;
+pub mod signs;
+pub mod sounds;
+pub mod weather;
 
 /// Since we don't know the exact amount of bytes, the first byte is the number of u8s in the last i64,
 /// so we know when to stop reading bytes from the last i64
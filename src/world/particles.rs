@@ -0,0 +1,83 @@
+//! Particles, shown to players close enough to see them.
+//!
+//! They're sent by their ID in the particle registry, see [IdRegistry], so none can be shown
+//! without the registry report.
+
+use crate::net::packets::outgoing::particle::{Particle, ParticleData};
+use crate::net::utils::broadcast::broadcast_near;
+use crate::state::GlobalState;
+use crate::utils::prelude::*;
+use crate::world::conversions::BlockId;
+use crate::world::dimension::Dimension;
+
+/// How far away particles are shown, the same as the client's limit.
+pub const RANGE: f64 = 32.0;
+/// How far away [Particle::long_distance] particles are shown.
+pub const LONG_RANGE: f64 = 512.0;
+
+impl ParticleData {
+    /// What a particle shows when nothing's said about it: stone for block particles and red for
+    /// dust. `None` for particles that need something that isn't supported, like an item.
+    pub fn default_for(name: &str) -> Option<Self> {
+        let data = match name.strip_prefix("minecraft:").unwrap_or(name) {
+            "block" | "block_marker" | "falling_dust" => {
+                ParticleData::Block(BlockId::from_name("minecraft:stone")?.into())
+            }
+            "dust" => ParticleData::Dust {
+                red: 1.0,
+                green: 0.0,
+                blue: 0.0,
+                scale: 1.0,
+            },
+            "sculk_charge" => ParticleData::SculkCharge(0.0),
+            "shriek" => ParticleData::Shriek(0.into()),
+            "dust_color_transition" | "item" | "vibration" => return None,
+            _ => ParticleData::Empty,
+        };
+        Some(data)
+    }
+}
+
+/// A particle's ID by its name.
+pub fn particle_id(state: &GlobalState, name: &str) -> Result<i32> {
+    state
+        .particles
+        .id(name)
+        .ok_or_else(|| Error::Generic(format!("Unknown particle: {}", name)))
+}
+
+/// Shows particles to everyone in a dimension close enough to see them.
+pub async fn spawn_particles(
+    state: &GlobalState,
+    dimension: Dimension,
+    particles: Particle,
+) -> Result<()> {
+    let range = match particles.long_distance {
+        true => LONG_RANGE,
+        false => RANGE,
+    };
+    let position = (particles.x, particles.y, particles.z);
+    broadcast_near(particles, state, dimension, position, range).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_data() {
+        assert_eq!(
+            ParticleData::default_for("minecraft:flame"),
+            Some(ParticleData::Empty)
+        );
+        assert!(matches!(
+            ParticleData::default_for("dust"),
+            Some(ParticleData::Dust { red, .. }) if red == 1.0
+        ));
+        assert!(matches!(
+            ParticleData::default_for("minecraft:block"),
+            Some(ParticleData::Block(_))
+        ));
+        assert_eq!(ParticleData::default_for("minecraft:item"), None);
+    }
+}
@@ -0,0 +1,298 @@
+//! Physics for entities that aren't players, which is anything with a [Motion] and a [Body].
+//!
+//! Every tick they fall, slow down and move by their velocity, stopping against the
+//! [collision shapes](crate::world::collision) of the blocks in the way. Like vanilla, they move
+//! along y first, then x, then z. Things that set velocities, like the AI of mobs, do that before
+//! [EntityPhysics](crate::net::systems::entity_physics) moves them, and the
+//! [EntityBroadcaster](crate::net::systems::entity_broadcaster) sends everyone where they ended
+//! up.
+
+use std::collections::HashMap;
+
+use crate::state::GlobalState;
+use crate::utils::components::body::Body;
+use crate::utils::components::motion::Motion;
+use crate::world::chunk_format::Chunk;
+use crate::world::collision::{collision_shape, Aabb};
+use crate::world::dimension::Dimension;
+use crate::world::generation::get_or_generate_chunk;
+
+/// How much of its speed something sliding along the ground keeps on top of its drag, which is
+/// vanilla's for most blocks.
+const GROUND_FRICTION: f64 = 0.6;
+/// Anything slower stops, so things come to rest instead of creeping along forever.
+const MIN_SPEED: f64 = 0.003;
+
+/// Moves an entity on by a tick: it falls, stops against blocks and slows down. `shapes` gives the
+/// [collision_shape] of the block at a position, and is only asked about [blocks_in_reach].
+/// Returns which axes it was stopped on, x, y and z.
+pub fn step(
+    motion: &mut Motion,
+    body: &Body,
+    shapes: impl Fn(i32, i32, i32) -> &'static [Aabb],
+) -> [bool; 3] {
+    let reach = reach(motion, body);
+    let blocks = reach
+        .blocks()
+        .into_iter()
+        .flat_map(|(x, y, z)| {
+            let corner = (x as f64, y as f64, z as f64);
+            shapes(x, y, z).iter().map(move |shape| shape.offset(corner))
+        })
+        .collect::<Vec<_>>();
+
+    let (vx, mut vy, vz) = motion.velocity;
+    let aabb = body.aabb(motion.position);
+    let stuck_in = blocks
+        .iter()
+        .filter(|block| block.intersects(&aabb))
+        .map(|block| block.max[1])
+        .max_by(f64::total_cmp);
+    let mut position = motion.position;
+    if let Some(top) = stuck_in {
+        // Stuck in a block, e.g. one placed on top of it, so it's pushed out of the top
+        position.1 = top;
+        vy = 0.0;
+    } else {
+        vy -= body.gravity;
+    }
+
+    let start = position;
+    let moved = move_through(&mut position, body, &blocks, (vx, vy, vz));
+    let landed = vy < 0.0 && moved[1];
+    if body.step_height > 0.0 && (motion.on_ground || landed) && (moved[0] || moved[2]) {
+        // Walks up onto the block in the way instead, if it's low enough and that gets further
+        let mut stepped = start;
+        move_through(&mut stepped, body, &blocks, (0.0, body.step_height, 0.0));
+        let stepped_moved = move_through(&mut stepped, body, &blocks, (vx, 0.0, vz));
+        let risen = stepped.1 - start.1;
+        move_through(&mut stepped, body, &blocks, (0.0, -risen, 0.0));
+        let distance = |to: (f64, f64, f64)| (to.0 - start.0).powi(2) + (to.2 - start.2).powi(2);
+        if distance(stepped) > distance(position) + 1e-7 && stepped.1 >= start.1 {
+            position = stepped;
+            return finish(motion, body, position, (vx, 0.0, vz), stepped_moved, true);
+        }
+    }
+    if moved[1] {
+        vy = 0.0;
+    }
+    finish(motion, body, position, (vx, vy, vz), moved, landed)
+}
+
+fn finish(
+    motion: &mut Motion,
+    body: &Body,
+    position: (f64, f64, f64),
+    (vx, vy, vz): (f64, f64, f64),
+    blocked: [bool; 3],
+    on_ground: bool,
+) -> [bool; 3] {
+    let friction = if on_ground {
+        body.drag * GROUND_FRICTION
+    } else {
+        body.drag
+    };
+    let slow = |v: f64, drag: f64| if (v * drag).abs() < MIN_SPEED { 0.0 } else { v * drag };
+    let vx = if blocked[0] { 0.0 } else { vx };
+    let vz = if blocked[2] { 0.0 } else { vz };
+    motion.position = position;
+    motion.velocity = (slow(vx, friction), slow(vy, body.drag), slow(vz, friction));
+    motion.on_ground = on_ground;
+    motion.blocked = blocked[0] || blocked[2];
+    blocked
+}
+
+/// Moves `position` by `velocity` along y, x then z, stopping against `blocks`. Returns which
+/// axes it was stopped on.
+fn move_through(
+    position: &mut (f64, f64, f64),
+    body: &Body,
+    blocks: &[Aabb],
+    velocity: (f64, f64, f64),
+) -> [bool; 3] {
+    let mut stopped = [false; 3];
+    for axis in [1, 0, 2] {
+        let wanted = [velocity.0, velocity.1, velocity.2][axis];
+        if wanted == 0.0 {
+            continue;
+        }
+        let aabb = body.aabb(*position);
+        let mut distance = wanted;
+        let mut contact = None;
+        for block in blocks {
+            let clipped = aabb.clip(block, axis, distance);
+            if clipped != distance {
+                distance = clipped;
+                // Lined up exactly with the block, rather than a rounding error away from it
+                contact = Some(if wanted > 0.0 {
+                    block.min[axis] - (aabb.max[axis] - aabb.min[axis])
+                } else {
+                    block.max[axis]
+                });
+            }
+        }
+        let radius = body.width / 2.0;
+        match (axis, contact) {
+            (0, Some(min)) => position.0 = min + radius,
+            (1, Some(min)) => position.1 = min,
+            (_, Some(min)) => position.2 = min + radius,
+            (0, None) => position.0 += distance,
+            (1, None) => position.1 += distance,
+            (_, None) => position.2 += distance,
+        }
+        stopped[axis] = contact.is_some();
+    }
+    stopped
+}
+
+/// Everywhere an entity could get to this tick, for working out which blocks it could run into.
+fn reach(motion: &Motion, body: &Body) -> Aabb {
+    let aabb = body.aabb(motion.position);
+    let (vx, vy, vz) = motion.velocity;
+    let vy = vy - body.gravity;
+    let mut reach = aabb;
+    for (axis, velocity) in [vx, vy, vz].into_iter().enumerate() {
+        if velocity < 0.0 {
+            reach.min[axis] += velocity;
+        } else {
+            reach.max[axis] += velocity;
+        }
+    }
+    reach.max[1] += body.step_height;
+    reach
+}
+
+/// The blocks [step] could run into this tick.
+pub fn blocks_in_reach(motion: &Motion, body: &Body) -> Vec<(i32, i32, i32)> {
+    reach(motion, body).blocks()
+}
+
+/// Looks up the collision shapes of blocks, loading each chunk only once. Meant to be kept for a
+/// tick, since the blocks can change.
+pub struct BlockShapes<'a> {
+    state: &'a GlobalState,
+    chunks: HashMap<(Dimension, i32, i32), Option<Chunk>>,
+}
+
+impl<'a> BlockShapes<'a> {
+    pub fn new(state: &'a GlobalState) -> Self {
+        Self {
+            state,
+            chunks: HashMap::new(),
+        }
+    }
+
+    pub async fn shape(
+        &mut self,
+        dimension: Dimension,
+        (x, y, z): (i32, i32, i32),
+    ) -> &'static [Aabb] {
+        let (chunk_x, chunk_z) = (x >> 4, z >> 4);
+        let key = (dimension, chunk_x, chunk_z);
+        if !self.chunks.contains_key(&key) {
+            // Chunks that can't be loaded are empty
+            let chunk = get_or_generate_chunk(self.state, chunk_x, chunk_z, dimension.name())
+                .await
+                .ok()
+                .flatten();
+            self.chunks.insert(key, chunk);
+        }
+        let block = self.chunks[&key].as_ref().and_then(|chunk| {
+            chunk
+                .get_block(x.rem_euclid(16) as usize, y, z.rem_euclid(16) as usize)
+                .ok()
+        });
+        block.map_or(&[], |block| collision_shape(&block))
+    }
+
+    /// The shapes of the blocks an entity could run into this tick, leaving out ones without any.
+    pub async fn in_reach(
+        &mut self,
+        dimension: Dimension,
+        motion: &Motion,
+        body: &Body,
+    ) -> HashMap<(i32, i32, i32), &'static [Aabb]> {
+        let mut shapes = HashMap::new();
+        for block in blocks_in_reach(motion, body) {
+            let shape = self.shape(dimension, block).await;
+            if !shape.is_empty() {
+                shapes.insert(block, shape);
+            }
+        }
+        shapes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::collision::FULL_BLOCK;
+
+    /// A floor at y 63, with a wall at x 2 and slabs from x -2 down.
+    fn shapes(x: i32, y: i32, _z: i32) -> &'static [Aabb] {
+        static SLAB: &[Aabb] = &[Aabb::new([0.0; 3], [1.0, 0.5, 1.0])];
+        if y <= 63 || x == 2 {
+            FULL_BLOCK
+        } else if x <= -2 && y == 64 {
+            SLAB
+        } else {
+            &[]
+        }
+    }
+
+    #[test]
+    fn test_step() {
+        let item = Body::ITEM;
+        // Falls, then lands on the floor
+        let mut motion = Motion::new((0.5, 66.0, 0.5), (0.0, 0.0, 0.0));
+        for _ in 0..20 {
+            step(&mut motion, &item, shapes);
+        }
+        assert_eq!(motion.position, (0.5, 64.0, 0.5));
+        assert!(motion.on_ground);
+        assert_eq!(motion.velocity, (0.0, 0.0, 0.0));
+
+        // Slides up against the wall, then slows to a stop along it
+        motion.position.0 = 1.5;
+        motion.velocity = (0.5, 0.0, 0.2);
+        assert_eq!(step(&mut motion, &item, shapes), [true, true, false]);
+        assert_eq!(motion.position.0, 2.0 - 0.125);
+        assert_eq!(motion.velocity.0, 0.0);
+        assert!(motion.blocked);
+        assert!(motion.position.2 > 0.6);
+        for _ in 0..20 {
+            step(&mut motion, &item, shapes);
+        }
+        assert_eq!(motion.velocity, (0.0, 0.0, 0.0));
+
+        // Falling too fast to stop in one block still lands
+        let mut motion = Motion::new((0.5, 65.5, 0.5), (0.0, -3.0, 0.0));
+        step(&mut motion, &item, shapes);
+        assert_eq!(motion.position.1, 64.0);
+        assert_eq!(blocks_in_reach(&motion, &item).len(), 3);
+
+        // Stuck in the floor
+        let mut motion = Motion::new((0.5, 63.5, 0.5), (0.0, 0.0, 0.0));
+        step(&mut motion, &item, shapes);
+        assert_eq!(motion.position.1, 64.0);
+    }
+
+    #[test]
+    fn test_step_up() {
+        // Walks up onto the slab without jumping
+        let mob = Body::mob(0.6, 1.8);
+        let mut motion = Motion::new((-0.5, 64.0, 0.5), (0.0, 0.0, 0.0));
+        motion.on_ground = true;
+        for _ in 0..10 {
+            motion.velocity.0 = -0.2;
+            step(&mut motion, &mob, shapes);
+        }
+        assert_eq!(motion.position.1, 64.5);
+        assert!(motion.position.0 < -1.5);
+
+        // Items don't
+        let mut motion = Motion::new((-0.5, 64.0, 0.5), (-0.5, 0.0, 0.0));
+        step(&mut motion, &Body::ITEM, shapes);
+        assert_eq!(motion.position, (-1.0 + 0.125, 64.0, 0.5));
+    }
+}
@@ -0,0 +1,377 @@
+//! Arrows and snowballs.
+//!
+//! Players throw snowballs by using them, and shoot arrows by drawing a bow and letting go, which
+//! takes an arrow from their inventory unless they're in creative. Like vanilla, the longer the bow
+//! is drawn the faster the arrow flies, up to a second for a critical shot. Projectiles fly under
+//! [physics](crate::world::physics), and whatever they hit is hurt through a
+//! [ProjectileHitEvent](crate::events::projectile_events::ProjectileHitEvent). Arrows that hit a
+//! block stick in it until they're picked up or despawn, snowballs just break. See
+//! [ProjectileTicker](crate::net::systems::projectile_ticker) for what happens to them each tick.
+
+use std::time::Instant;
+
+use crate::net::packets::outgoing::set_container_slot::SetContainerSlot;
+use crate::net::packets::outgoing::spawn_entity::entity_types;
+use crate::net::packets::ConnectionId;
+use crate::net::systems::game_loop::TICKS_PER_SECOND;
+use crate::state::GlobalState;
+use crate::utils::components::body::Body;
+use crate::utils::components::dimension::{dimension_of, CurrentDimension};
+use crate::utils::components::game_mode::GameMode;
+use crate::utils::components::inventory::Inventory;
+use crate::utils::components::motion::Motion;
+use crate::utils::components::object_entity::ObjectEntity;
+use crate::utils::components::projectile::Projectile;
+use crate::utils::components::rotation::Rotation;
+use crate::utils::components::using_item::UsingItem;
+use crate::utils::encoding::metadata::{EntityMetadata, MetadataValue};
+use crate::utils::encoding::position::Position;
+use crate::utils::prelude::*;
+use crate::world::collision::Aabb;
+use crate::world::dimension::Dimension;
+
+/// Something that can be shot or thrown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ProjectileKind {
+    Arrow,
+    Snowball,
+}
+
+impl ProjectileKind {
+    /// Its id in the `minecraft:entity_type` registry.
+    pub fn entity_type(&self) -> i32 {
+        match self {
+            ProjectileKind::Arrow => entity_types::ARROW,
+            ProjectileKind::Snowball => entity_types::SNOWBALL,
+        }
+    }
+
+    /// How big it is and how it flies, the same as vanilla.
+    pub fn body(&self) -> Body {
+        match self {
+            ProjectileKind::Arrow => Body {
+                width: 0.5,
+                height: 0.5,
+                gravity: 0.05,
+                drag: 0.99,
+                step_height: 0.0,
+            },
+            ProjectileKind::Snowball => Body {
+                width: 0.25,
+                height: 0.25,
+                gravity: 0.03,
+                drag: 0.99,
+                step_height: 0.0,
+            },
+        }
+    }
+}
+
+/// Projectiles leave from a bit below the player's eyes.
+const LAUNCH_HEIGHT: f64 = 1.62 - 0.1;
+const SNOWBALL_SPEED: f64 = 1.5;
+/// How fast an arrow from a fully drawn bow flies.
+const ARROW_SPEED: f64 = 3.0;
+/// How long it takes to draw a bow all the way, in ticks.
+const BOW_DRAW_TICKS: f64 = 20.0;
+/// Bows let go before they're drawn this far don't shoot.
+const MIN_BOW_POWER: f64 = 0.1;
+/// Arrows do this much damage for every block per tick they're flying at.
+const ARROW_DAMAGE: f64 = 2.0;
+/// Where an arrow keeps whether it's critical in its metadata.
+const ARROW_FLAGS_INDEX: u8 = 8;
+const CRITICAL_FLAG: i8 = 0x01;
+
+/// Shoots a projectile into the world, moving at `velocity` blocks per tick. Returns its entity.
+pub async fn spawn_projectile(
+    state: &GlobalState,
+    projectile: Projectile,
+    dimension: Dimension,
+    position: (f64, f64, f64),
+    velocity: (f64, f64, f64),
+) -> usize {
+    let mut object = ObjectEntity::new(projectile.kind.entity_type(), metadata(&projectile));
+    if projectile.kind == ProjectileKind::Arrow {
+        // Arrows are sent with who shot them, plus one
+        object.data = projectile.shooter.map_or(0, |shooter| shooter as i32 + 1);
+    }
+    state
+        .world
+        .create_entity()
+        .await
+        .with(projectile)
+        .with(object)
+        .with(Motion::new(position, velocity))
+        .with(facing(velocity))
+        .with(CurrentDimension::new(dimension))
+        .build()
+}
+
+fn metadata(projectile: &Projectile) -> EntityMetadata {
+    let mut metadata = EntityMetadata::new();
+    if projectile.kind == ProjectileKind::Arrow && projectile.critical {
+        metadata.set(ARROW_FLAGS_INDEX, MetadataValue::Byte(CRITICAL_FLAG));
+    }
+    metadata
+}
+
+/// A player uses the item in their hand: snowballs are thrown straight away, and bows start being
+/// drawn. Anything else isn't handled yet.
+pub async fn use_item(state: &GlobalState, conn_id: ConnectionId, offhand: bool) -> Result<()> {
+    let game_mode = game_mode(state, conn_id).await;
+    if game_mode == GameMode::SPECTATOR {
+        return Ok(());
+    }
+    match held_item(state, conn_id, offhand).await.as_deref() {
+        Some("snowball") => {
+            if game_mode != GameMode::CREATIVE {
+                let slot = held_slot(state, conn_id, offhand).await;
+                take_one(state, conn_id, slot).await?;
+            }
+            let projectile = Projectile::new(ProjectileKind::Snowball, Some(conn_id));
+            launch(state, conn_id, projectile, SNOWBALL_SPEED).await
+        }
+        Some("bow") => {
+            let using = UsingItem {
+                since: Instant::now(),
+                offhand,
+            };
+            state.world.get_component_storage().insert(conn_id, using);
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// A player lets go of the item they were using, which shoots an arrow if it's a bow that's been
+/// drawn far enough and they've got one.
+pub async fn release_item(state: &GlobalState, conn_id: ConnectionId) -> Result<()> {
+    let Ok(using) = state
+        .world
+        .get_component::<UsingItem>(conn_id)
+        .await
+        .map(|using| *using)
+    else {
+        return Ok(());
+    };
+    state.world.get_component_storage().remove::<UsingItem>(conn_id)?;
+    // They might have switched to something else since
+    if held_item(state, conn_id, using.offhand).await.as_deref() != Some("bow") {
+        return Ok(());
+    }
+    let ticks = using.since.elapsed().as_secs_f64() * TICKS_PER_SECOND as f64;
+    let power = bow_power(ticks);
+    if power < MIN_BOW_POWER {
+        return Ok(());
+    }
+
+    let creative = game_mode(state, conn_id).await == GameMode::CREATIVE;
+    if !creative {
+        let Some(arrow) = state.items.id("minecraft:arrow") else {
+            return Ok(());
+        };
+        let slot = state
+            .world
+            .get_component::<Inventory>(conn_id)
+            .await
+            .ok()
+            .and_then(|inventory| inventory.find_item(arrow));
+        let Some(slot) = slot else {
+            return Ok(());
+        };
+        take_one(state, conn_id, slot).await?;
+    }
+    let mut projectile = Projectile::new(ProjectileKind::Arrow, Some(conn_id));
+    projectile.critical = power >= 1.0;
+    projectile.pickup = !creative;
+    launch(state, conn_id, projectile, power * ARROW_SPEED).await
+}
+
+/// Shoots a projectile the way a player is looking.
+async fn launch(
+    state: &GlobalState,
+    conn_id: ConnectionId,
+    projectile: Projectile,
+    speed: f64,
+) -> Result<()> {
+    let position = state.world.get_component::<Position>(conn_id).await?.clone();
+    let rotation = state
+        .world
+        .get_component::<Rotation>(conn_id)
+        .await
+        .map(|rotation| rotation.clone())
+        .unwrap_or_else(|_| Rotation::new(0.0, 0.0));
+    let dimension = dimension_of(state, conn_id).await;
+    // Positions are whole blocks, so the player is taken to be in the middle of theirs
+    let position = (
+        position.x as f64 + 0.5,
+        position.y as f64 + LAUNCH_HEIGHT,
+        position.z as f64 + 0.5,
+    );
+    let velocity = aim(&rotation, speed);
+    spawn_projectile(state, projectile, dimension, position, velocity).await;
+    Ok(())
+}
+
+async fn game_mode(state: &GlobalState, conn_id: ConnectionId) -> u8 {
+    state
+        .world
+        .get_component::<GameMode>(conn_id)
+        .await
+        .map_or(GameMode::SURVIVAL, |game_mode| game_mode.mode)
+}
+
+/// The name of the item a player has in their hand, without the namespace.
+async fn held_item(state: &GlobalState, conn_id: ConnectionId, offhand: bool) -> Option<String> {
+    let id = state
+        .world
+        .get_component::<Inventory>(conn_id)
+        .await
+        .ok()?
+        .held_item(offhand)?
+        .id;
+    let name = state.items.name(id)?;
+    Some(name.strip_prefix("minecraft:").unwrap_or(name).to_string())
+}
+
+async fn held_slot(state: &GlobalState, conn_id: ConnectionId, offhand: bool) -> i16 {
+    if offhand {
+        return Inventory::OFFHAND;
+    }
+    let selected = state
+        .world
+        .get_component::<Inventory>(conn_id)
+        .await
+        .map_or(0, |inventory| inventory.selected_slot);
+    Inventory::HOTBAR_START + selected
+}
+
+/// Uses up one of the items in a slot, and tells the player.
+async fn take_one(state: &GlobalState, conn_id: ConnectionId, slot: i16) -> Result<()> {
+    let update = {
+        let mut inventory = state
+            .world
+            .get_component_storage()
+            .get_mut_or_insert_with::<Inventory>(conn_id, Default::default)
+            .await;
+        if !inventory.take_one(slot) {
+            return Ok(());
+        }
+        inventory.next_state_id();
+        SetContainerSlot::player_inventory(&inventory, slot)
+    };
+    let conn = state.connections.get_connection(conn_id)?;
+    conn.read().await.send_packet(update).await?;
+    Ok(())
+}
+
+/// The velocity of something shot at `speed` blocks per tick the way `rotation` faces.
+pub fn aim(rotation: &Rotation, speed: f64) -> (f64, f64, f64) {
+    let yaw = (rotation.yaw as f64).to_radians();
+    let pitch = (rotation.pitch as f64).to_radians();
+    (
+        -yaw.sin() * pitch.cos() * speed,
+        -pitch.sin() * speed,
+        yaw.cos() * pitch.cos() * speed,
+    )
+}
+
+/// How far a bow drawn for `ticks` is pulled back, from 0 to 1, the same curve as vanilla's.
+pub fn bow_power(ticks: f64) -> f64 {
+    let drawn = ticks / BOW_DRAW_TICKS;
+    ((drawn * drawn + drawn * 2.0) / 3.0).min(1.0)
+}
+
+/// How much an arrow flying at `velocity` hurts what it hits, in half hearts. Critical arrows do a
+/// random amount more, so `random` gives a number from 0 to 1.
+pub fn arrow_damage(velocity: (f64, f64, f64), critical: bool, random: f64) -> f32 {
+    let (x, y, z) = velocity;
+    let speed = (x * x + y * y + z * z).sqrt();
+    let damage = (speed * ARROW_DAMAGE).ceil();
+    if critical {
+        let bonus = (random * (damage / 2.0 + 2.0).floor()).floor();
+        (damage + bonus) as f32
+    } else {
+        damage as f32
+    }
+}
+
+/// The rotation a projectile flying at `velocity` is shown with. Arrows point where they're going,
+/// which for them is a yaw of 0 towards +z and 90 towards +x.
+pub fn facing((x, y, z): (f64, f64, f64)) -> Rotation {
+    let yaw = x.atan2(z).to_degrees();
+    let pitch = y.atan2((x * x + z * z).sqrt()).to_degrees();
+    Rotation::new(yaw as f32, pitch as f32)
+}
+
+/// The yaw that faces along `velocity`, the way players and mobs measure it, for knocking back
+/// what a projectile hits.
+pub fn heading((x, _, z): (f64, f64, f64)) -> f32 {
+    (-x).atan2(z).to_degrees() as f32
+}
+
+/// The first of `targets` a projectile `radius` wide hits going from `from` to `to`, with how far
+/// along the way it hits it, from 0 to 1.
+pub fn first_hit(
+    from: (f64, f64, f64),
+    to: (f64, f64, f64),
+    radius: f64,
+    targets: &[(usize, Aabb)],
+) -> Option<(usize, f64)> {
+    targets
+        .iter()
+        .filter_map(|(target, aabb)| Some((*target, aabb.inflate(radius).ray_hit(from, to)?)))
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_aim() {
+        let (x, y, z) = aim(&Rotation::new(0.0, 0.0), SNOWBALL_SPEED);
+        assert!(x.abs() < 1e-9 && y.abs() < 1e-9);
+        assert_eq!(z, SNOWBALL_SPEED);
+        // Looking west and straight up
+        let (x, _, _) = aim(&Rotation::new(90.0, 0.0), 1.0);
+        assert!((x + 1.0).abs() < 1e-9);
+        let (_, y, _) = aim(&Rotation::new(0.0, -90.0), 1.0);
+        assert!((y - 1.0).abs() < 1e-9);
+
+        let arrow = facing((1.0, 0.0, 0.0));
+        assert_eq!((arrow.yaw, arrow.pitch), (90.0, 0.0));
+        assert_eq!(heading((1.0, 0.0, 0.0)), -90.0);
+    }
+
+    #[test]
+    fn test_bow_power() {
+        assert_eq!(bow_power(0.0), 0.0);
+        assert!(bow_power(2.0) < MIN_BOW_POWER);
+        assert!(bow_power(3.0) > MIN_BOW_POWER);
+        assert!((bow_power(10.0) - 0.4167).abs() < 1e-3);
+        assert_eq!(bow_power(20.0), 1.0);
+        assert_eq!(bow_power(100.0), 1.0);
+    }
+
+    #[test]
+    fn test_arrow_damage() {
+        // A fully drawn bow
+        assert_eq!(arrow_damage((0.0, 0.0, 3.0), false, 0.0), 6.0);
+        assert_eq!(arrow_damage((0.0, 0.0, 3.0), true, 0.0), 6.0);
+        assert_eq!(arrow_damage((0.0, 0.0, 3.0), true, 0.99), 10.0);
+        assert_eq!(arrow_damage((0.0, -0.3, 0.4), false, 0.0), 1.0);
+    }
+
+    #[test]
+    fn test_first_hit() {
+        let near = Aabb::standing_at((0.5, 64.0, 5.5), 0.6, 1.8);
+        let far = Aabb::standing_at((0.5, 64.0, 8.5), 0.6, 1.8);
+        let targets = [(2, far), (1, near)];
+        let (hit, along) = first_hit((0.5, 65.0, 0.0), (0.5, 65.0, 10.0), 0.25, &targets).unwrap();
+        assert_eq!(hit, 1);
+        assert!((along - 0.495).abs() < 1e-9);
+        // Passes overhead
+        assert_eq!(first_hit((0.5, 67.0, 0.0), (0.5, 67.0, 10.0), 0.25, &targets), None);
+    }
+}
@@ -0,0 +1,751 @@
+//! Crafting and smelting recipes.
+//!
+//! They aren't embedded, but read from JSON files laid out like the `data` directory of a datapack
+//! or the vanilla server jar, in the `recipe_data` directory: `<namespace>/recipes/<name>.json`,
+//! and the item tags they use in `<namespace>/tags/items/<name>.json`. Copying `data` out of the
+//! server jar gives every vanilla recipe.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use serde::Deserialize;
+use tracing::{debug, info, warn};
+
+use crate::utils::components::inventory::Inventory;
+use crate::utils::encoding::slot::ItemStack;
+use crate::utils::prelude::*;
+use crate::world::items::ItemRegistry;
+
+/// Tags can include other tags, but not this deeply unless they include themselves.
+const MAX_TAG_DEPTH: usize = 16;
+
+#[derive(Deserialize)]
+#[serde(tag = "type")]
+enum RecipeJson {
+    #[serde(rename = "minecraft:crafting_shaped")]
+    Shaped {
+        #[serde(default)]
+        group: String,
+        category: Option<String>,
+        pattern: Vec<String>,
+        key: HashMap<String, IngredientJson>,
+        result: ResultJson,
+        #[serde(default = "show_notification")]
+        show_notification: bool,
+    },
+    #[serde(rename = "minecraft:crafting_shapeless")]
+    Shapeless {
+        #[serde(default)]
+        group: String,
+        category: Option<String>,
+        ingredients: Vec<IngredientJson>,
+        result: ResultJson,
+    },
+    #[serde(rename = "minecraft:smelting")]
+    Smelting {
+        #[serde(default)]
+        group: String,
+        category: Option<String>,
+        ingredient: IngredientJson,
+        result: ResultJson,
+        #[serde(default)]
+        experience: f32,
+        #[serde(default = "cooking_time")]
+        cookingtime: i32,
+    },
+    /// Special recipes like dyeing armor, which need code of their own, and the other kinds of
+    /// cooking, stonecutting and smithing.
+    #[serde(other)]
+    Unsupported,
+}
+
+fn show_notification() -> bool {
+    true
+}
+
+fn cooking_time() -> i32 {
+    200
+}
+
+/// One item or tag, or a list of them.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum IngredientJson {
+    One(IngredientEntry),
+    Any(Vec<IngredientEntry>),
+}
+
+#[derive(Deserialize)]
+struct IngredientEntry {
+    item: Option<String>,
+    tag: Option<String>,
+}
+
+/// Just an item's name for smelting, or an item and a count.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ResultJson {
+    Name(String),
+    Stack {
+        #[serde(alias = "id")]
+        item: String,
+        #[serde(default = "one")]
+        count: i8,
+    },
+}
+
+fn one() -> i8 {
+    1
+}
+
+/// Matches any one of a few items. An empty ingredient matches an empty slot, like the spaces in
+/// a shaped recipe's pattern.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Ingredient(pub Vec<i32>);
+
+impl Ingredient {
+    pub fn matches(&self, item: Option<&ItemStack>) -> bool {
+        match item {
+            Some(item) => self.0.contains(&item.id),
+            None => self.0.is_empty(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RecipeKind {
+    /// The ingredients have to be laid out like the pattern, or its mirror image, anywhere in the
+    /// grid.
+    Shaped {
+        width: usize,
+        height: usize,
+        /// Row by row.
+        ingredients: Vec<Ingredient>,
+        /// Whether the client shows a toast when the recipe is unlocked.
+        show_notification: bool,
+    },
+    /// The ingredients can be anywhere in the grid.
+    Shapeless { ingredients: Vec<Ingredient> },
+    Smelting {
+        ingredient: Ingredient,
+        experience: f32,
+        /// In ticks.
+        cooking_time: i32,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Recipe {
+    /// E.g. `minecraft:oak_planks`.
+    pub id: String,
+    /// Recipes in the same group are shown together in the recipe book.
+    pub group: String,
+    /// The recipe book tab the recipe goes in, see [Recipe::category_id].
+    pub category: String,
+    pub kind: RecipeKind,
+    pub result: ItemStack,
+}
+
+impl Recipe {
+    /// The recipe's type, as the client knows it.
+    pub fn type_name(&self) -> &'static str {
+        match self.kind {
+            RecipeKind::Shaped { .. } => "minecraft:crafting_shaped",
+            RecipeKind::Shapeless { .. } => "minecraft:crafting_shapeless",
+            RecipeKind::Smelting { .. } => "minecraft:smelting",
+        }
+    }
+
+    /// The category's network ID, which counts from 0 separately for crafting and cooking.
+    /// Unknown categories go under misc.
+    pub fn category_id(&self) -> i32 {
+        let categories: &[&str] = match self.kind {
+            RecipeKind::Smelting { .. } => &["food", "blocks", "misc"],
+            _ => &["building", "redstone", "equipment", "misc"],
+        };
+        categories
+            .iter()
+            .position(|&category| category == self.category)
+            .unwrap_or(categories.len() - 1) as i32
+    }
+
+    /// Whether the items in a crafting grid of `width` columns make this recipe. Always false for
+    /// smelting.
+    pub fn matches_grid(&self, grid: &[Option<&ItemStack>], width: usize) -> bool {
+        match &self.kind {
+            RecipeKind::Shaped {
+                width: recipe_width,
+                height: recipe_height,
+                ingredients,
+                ..
+            } => {
+                let Some((left, top, right, bottom)) = bounds(grid, width) else {
+                    return false;
+                };
+                if right - left != *recipe_width || bottom - top != *recipe_height {
+                    return false;
+                }
+                let fits = |mirrored: bool| {
+                    (0..*recipe_height).all(|y| {
+                        (0..*recipe_width).all(|x| {
+                            let column = if mirrored { recipe_width - 1 - x } else { x };
+                            let item = grid[(top + y) * width + left + column];
+                            ingredients[y * recipe_width + x].matches(item)
+                        })
+                    })
+                };
+                fits(false) || fits(true)
+            }
+            RecipeKind::Shapeless { ingredients } => {
+                let items = grid.iter().flatten().copied().collect::<Vec<_>>();
+                items.len() == ingredients.len()
+                    && match_shapeless(&items, ingredients, &mut vec![false; ingredients.len()])
+            }
+            RecipeKind::Smelting { .. } => false,
+        }
+    }
+}
+
+/// The columns and rows with something in them, as `(left, top, right, bottom)` with the right
+/// and bottom exclusive. `None` for an empty grid.
+fn bounds(grid: &[Option<&ItemStack>], width: usize) -> Option<(usize, usize, usize, usize)> {
+    let filled = grid
+        .iter()
+        .enumerate()
+        .filter(|(_, item)| item.is_some())
+        .map(|(index, _)| (index % width, index / width))
+        .collect::<Vec<_>>();
+    let left = filled.iter().map(|&(x, _)| x).min()?;
+    let right = filled.iter().map(|&(x, _)| x).max()? + 1;
+    let top = filled.iter().map(|&(_, y)| y).min()?;
+    let bottom = filled.iter().map(|&(_, y)| y).max()? + 1;
+    Some((left, top, right, bottom))
+}
+
+/// Finds an ingredient for every item, each used once. There are at most 9 of them, so trying
+/// every way is quick enough.
+fn match_shapeless(items: &[&ItemStack], ingredients: &[Ingredient], used: &mut [bool]) -> bool {
+    let Some((item, rest)) = items.split_first() else {
+        return true;
+    };
+    for (index, ingredient) in ingredients.iter().enumerate() {
+        if used[index] || !ingredient.matches(Some(item)) {
+            continue;
+        }
+        used[index] = true;
+        if match_shapeless(rest, ingredients, used) {
+            return true;
+        }
+        used[index] = false;
+    }
+    false
+}
+
+/// Every recipe the server knows.
+#[derive(Debug, Default)]
+pub struct RecipeBook {
+    recipes: Vec<Recipe>,
+}
+
+impl RecipeBook {
+    /// Reads the recipes in `directory`, see the [module docs](self). Without any, nothing can be
+    /// crafted.
+    pub fn load(directory: impl AsRef<Path>, items: &ItemRegistry) -> Result<Self> {
+        let directory = directory.as_ref();
+        let recipe_files = read_data_files(directory, "recipes")?;
+        if recipe_files.is_empty() {
+            warn!(
+                "No recipes were found in {}, so nothing can be crafted. Copy the data directory out of the vanilla server jar there.",
+                directory.display()
+            );
+            return Ok(Self::default());
+        }
+        let tags = read_data_files(directory, "tags/items")?
+            .into_iter()
+            .map(|(name, contents)| {
+                let tag: TagJson = serde_json::from_str(&contents)
+                    .map_err(|e| Error::DeserializationError(format!("Tag {}: {}", name, e)))?;
+                Ok((name, tag.values))
+            })
+            .collect::<Result<HashMap<_, _>>>()?;
+
+        let mut recipes = Vec::new();
+        for (id, contents) in recipe_files {
+            match parse_recipe(&id, &contents, &tags, items) {
+                Ok(Some(recipe)) => recipes.push(recipe),
+                Ok(None) => {}
+                Err(e) => debug!("Skipping recipe {}: {}", id, e),
+            }
+        }
+        info!("Loaded {} recipes", recipes.len());
+        Ok(Self::new(recipes))
+    }
+
+    pub fn new(mut recipes: Vec<Recipe>) -> Self {
+        // Files are read in no particular order
+        recipes.sort_by(|a, b| a.id.cmp(&b.id));
+        Self { recipes }
+    }
+
+    pub fn recipes(&self) -> &[Recipe] {
+        &self.recipes
+    }
+
+    pub fn len(&self) -> usize {
+        self.recipes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.recipes.is_empty()
+    }
+
+    /// The recipe the items in a crafting grid of `width` columns make, if any.
+    pub fn crafting_recipe(&self, grid: &[Option<&ItemStack>], width: usize) -> Option<&Recipe> {
+        self.recipes
+            .iter()
+            .find(|recipe| recipe.matches_grid(grid, width))
+    }
+
+    /// Puts what the player's 2x2 crafting grid makes in the result slot. Returns whether it
+    /// changed.
+    pub fn update_crafting_result(&self, inventory: &mut Inventory) -> bool {
+        let grid = Inventory::CRAFTING_GRID
+            .map(|slot| inventory.slots.get(&slot))
+            .collect::<Vec<_>>();
+        let result = self
+            .crafting_recipe(&grid, 2)
+            .map(|recipe| recipe.result.clone());
+        if inventory.slots.get(&Inventory::CRAFTING_RESULT) == result.as_ref() {
+            return false;
+        }
+        inventory.set_slot(Inventory::CRAFTING_RESULT, result);
+        true
+    }
+}
+
+#[derive(Deserialize)]
+struct TagJson {
+    values: Vec<TagEntry>,
+}
+
+/// An item, `#` and another tag, or either of those with whether it has to exist.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum TagEntry {
+    Name(String),
+    Optional { id: String },
+}
+
+impl TagEntry {
+    fn name(&self) -> &str {
+        match self {
+            TagEntry::Name(name) => name,
+            TagEntry::Optional { id } => id,
+        }
+    }
+}
+
+/// The `<namespace>/<kind>/<name>.json` files in `directory`, by `<namespace>:<name>`. Names can
+/// have folders in them, e.g. `minecraft:dyes/red`.
+fn read_data_files(directory: &Path, kind: &str) -> Result<Vec<(String, String)>> {
+    let namespaces = match std::fs::read_dir(directory) {
+        Ok(namespaces) => namespaces,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut files = Vec::new();
+    for namespace in namespaces {
+        let namespace = namespace?;
+        if !namespace.file_type()?.is_dir() {
+            continue;
+        }
+        let namespace_name = namespace.file_name().to_string_lossy().to_string();
+        let root = namespace.path().join(kind);
+        let mut folders = vec![root.clone()];
+        while let Some(folder) = folders.pop() {
+            let Ok(entries) = std::fs::read_dir(&folder) else {
+                continue;
+            };
+            for entry in entries {
+                let path = entry?.path();
+                if path.is_dir() {
+                    folders.push(path);
+                    continue;
+                }
+                if path.extension().is_none_or(|extension| extension != "json") {
+                    continue;
+                }
+                let stem = path.with_extension("");
+                let Ok(relative) = stem.strip_prefix(&root) else {
+                    continue;
+                };
+                let name = relative
+                    .components()
+                    .map(|component| component.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/");
+                files.push((
+                    format!("{}:{}", namespace_name, name),
+                    std::fs::read_to_string(&path)?,
+                ));
+            }
+        }
+    }
+    Ok(files)
+}
+
+/// Reads a recipe. `None` for the kinds that aren't supported, and an error for ones that use
+/// items or tags that don't exist.
+fn parse_recipe(
+    id: &str,
+    contents: &str,
+    tags: &HashMap<String, Vec<TagEntry>>,
+    items: &ItemRegistry,
+) -> std::result::Result<Option<Recipe>, String> {
+    let json: RecipeJson = serde_json::from_str(contents).map_err(|e| e.to_string())?;
+    let ingredient = |json: &IngredientJson| resolve_ingredient(json, tags, items);
+
+    let (group, category, kind, result) = match json {
+        RecipeJson::Shaped {
+            group,
+            category,
+            pattern,
+            key,
+            result,
+            show_notification,
+        } => {
+            let pattern = shrink_pattern(&pattern);
+            let width = pattern.iter().map(|row| row.chars().count()).max().unwrap_or(0);
+            let mut ingredients = Vec::with_capacity(width * pattern.len());
+            for row in &pattern {
+                for x in 0..width {
+                    let symbol = row.chars().nth(x).unwrap_or(' ');
+                    if symbol == ' ' {
+                        ingredients.push(Ingredient::default());
+                        continue;
+                    }
+                    let json = key
+                        .get(&symbol.to_string())
+                        .ok_or_else(|| format!("'{}' isn't in the key", symbol))?;
+                    ingredients.push(ingredient(json)?);
+                }
+            }
+            let kind = RecipeKind::Shaped {
+                width,
+                height: pattern.len(),
+                ingredients,
+                show_notification,
+            };
+            (group, category, kind, result)
+        }
+        RecipeJson::Shapeless {
+            group,
+            category,
+            ingredients,
+            result,
+        } => {
+            let ingredients = ingredients
+                .iter()
+                .map(ingredient)
+                .collect::<std::result::Result<Vec<_>, _>>()?;
+            (group, category, RecipeKind::Shapeless { ingredients }, result)
+        }
+        RecipeJson::Smelting {
+            group,
+            category,
+            ingredient: json,
+            result,
+            experience,
+            cookingtime,
+        } => {
+            let kind = RecipeKind::Smelting {
+                ingredient: ingredient(&json)?,
+                experience,
+                cooking_time: cookingtime,
+            };
+            (group, category, kind, result)
+        }
+        RecipeJson::Unsupported => return Ok(None),
+    };
+
+    let (name, count) = match result {
+        ResultJson::Name(name) => (name, 1),
+        ResultJson::Stack { item, count } => (item, count),
+    };
+    let result_id = items
+        .id(&name)
+        .ok_or_else(|| format!("Unknown item {}", name))?;
+    Ok(Some(Recipe {
+        id: id.to_string(),
+        group,
+        category: category.unwrap_or_else(|| "misc".to_string()),
+        kind,
+        result: ItemStack::new(result_id, count),
+    }))
+}
+
+/// Takes off rows and columns that are empty all the way through, which vanilla ignores.
+fn shrink_pattern(pattern: &[String]) -> Vec<String> {
+    let rows = pattern
+        .iter()
+        .filter(|row| row.chars().any(|symbol| symbol != ' '))
+        .collect::<Vec<_>>();
+    let filled = |x: usize| rows.iter().any(|row| row.chars().nth(x).is_some_and(|s| s != ' '));
+    let width = rows.iter().map(|row| row.chars().count()).max().unwrap_or(0);
+    let left = (0..width).find(|&x| filled(x)).unwrap_or(0);
+    let right = (0..width).rev().find(|&x| filled(x)).map_or(0, |x| x + 1);
+    rows.iter()
+        .map(|row| {
+            (left..right)
+                .map(|x| row.chars().nth(x).unwrap_or(' '))
+                .collect()
+        })
+        .collect()
+}
+
+fn resolve_ingredient(
+    json: &IngredientJson,
+    tags: &HashMap<String, Vec<TagEntry>>,
+    items: &ItemRegistry,
+) -> std::result::Result<Ingredient, String> {
+    let entries = match json {
+        IngredientJson::One(entry) => std::slice::from_ref(entry),
+        IngredientJson::Any(entries) => entries.as_slice(),
+    };
+    let mut ids = Vec::new();
+    for entry in entries {
+        match (&entry.item, &entry.tag) {
+            (Some(item), _) => {
+                ids.push(items.id(item).ok_or_else(|| format!("Unknown item {}", item))?)
+            }
+            (None, Some(tag)) => resolve_tag(tag, tags, items, 0, &mut ids)?,
+            (None, None) => return Err("An ingredient has no item or tag".to_string()),
+        }
+    }
+    if ids.is_empty() {
+        return Err("An ingredient matches no items".to_string());
+    }
+    ids.sort_unstable();
+    ids.dedup();
+    Ok(Ingredient(ids))
+}
+
+/// Adds the items in a tag to `ids`. Items that don't exist are left out, so tags from a newer
+/// version still work.
+fn resolve_tag(
+    tag: &str,
+    tags: &HashMap<String, Vec<TagEntry>>,
+    items: &ItemRegistry,
+    depth: usize,
+    ids: &mut Vec<i32>,
+) -> std::result::Result<(), String> {
+    if depth > MAX_TAG_DEPTH {
+        return Err(format!("Tag {} includes itself", tag));
+    }
+    let entries = tags.get(tag).ok_or_else(|| format!("Unknown tag {}", tag))?;
+    for entry in entries {
+        match entry.name().strip_prefix('#') {
+            Some(inner) => resolve_tag(inner, tags, items, depth + 1, ids)?,
+            None => ids.extend(items.id(entry.name())),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn items() -> ItemRegistry {
+        ItemRegistry::parse(
+            r#"{
+                "minecraft:item": {
+                    "entries": {
+                        "minecraft:stick": { "protocol_id": 1 },
+                        "minecraft:oak_planks": { "protocol_id": 2 },
+                        "minecraft:birch_planks": { "protocol_id": 3 },
+                        "minecraft:wooden_pickaxe": { "protocol_id": 4 },
+                        "minecraft:beef": { "protocol_id": 5 },
+                        "minecraft:cooked_beef": { "protocol_id": 6 },
+                        "minecraft:crafting_table": { "protocol_id": 7 },
+                        "minecraft:flint": { "protocol_id": 8 },
+                        "minecraft:iron_ingot": { "protocol_id": 9 },
+                        "minecraft:flint_and_steel": { "protocol_id": 10 }
+                    }
+                }
+            }"#,
+        )
+        .unwrap()
+    }
+
+    fn tags() -> HashMap<String, Vec<TagEntry>> {
+        let planks = vec![
+            TagEntry::Name("minecraft:oak_planks".to_string()),
+            TagEntry::Name("#minecraft:pale_planks".to_string()),
+            TagEntry::Optional {
+                id: "minecraft:cherry_planks".to_string(),
+            },
+        ];
+        let pale = vec![TagEntry::Name("minecraft:birch_planks".to_string())];
+        HashMap::from([
+            ("minecraft:planks".to_string(), planks),
+            ("minecraft:pale_planks".to_string(), pale),
+        ])
+    }
+
+    fn recipe(json: &str) -> Recipe {
+        parse_recipe("test:recipe", json, &tags(), &items())
+            .unwrap()
+            .unwrap()
+    }
+
+    fn stack(id: i32) -> Option<ItemStack> {
+        Some(ItemStack::new(id, 1))
+    }
+
+    #[test]
+    fn test_shaped_recipe() {
+        let pickaxe = recipe(
+            r##"{
+                "type": "minecraft:crafting_shaped",
+                "category": "equipment",
+                "key": { "#": { "item": "minecraft:stick" }, "X": { "tag": "minecraft:planks" } },
+                "pattern": ["XXX", " # ", " # "],
+                "result": { "item": "minecraft:wooden_pickaxe" }
+            }"##,
+        );
+        assert_eq!(pickaxe.category_id(), 2);
+        assert_eq!(pickaxe.result, ItemStack::new(4, 1));
+        let RecipeKind::Shaped { ingredients, .. } = &pickaxe.kind else {
+            panic!("Not shaped");
+        };
+        // Through the nested tag, without the planks that don't exist
+        assert_eq!(ingredients[0], Ingredient(vec![2, 3]));
+        assert_eq!(ingredients[3], Ingredient::default());
+
+        let grid = [stack(2), stack(3), stack(2), None, stack(1), None, None, stack(1), None];
+        let grid = grid.iter().map(Option::as_ref).collect::<Vec<_>>();
+        assert!(pickaxe.matches_grid(&grid, 3));
+        let missing_stick = [stack(2), stack(3), stack(2), None, stack(1), None, None, None, None];
+        let missing_stick = missing_stick.iter().map(Option::as_ref).collect::<Vec<_>>();
+        assert!(!pickaxe.matches_grid(&missing_stick, 3));
+    }
+
+    #[test]
+    fn test_shaped_recipe_anywhere_and_mirrored() {
+        let table = recipe(
+            r#"{
+                "type": "minecraft:crafting_shaped",
+                "key": { "P": { "item": "minecraft:oak_planks" }, "S": { "item": "minecraft:stick" } },
+                "pattern": ["  ", "PS", "P "],
+                "result": { "item": "minecraft:crafting_table", "count": 2 }
+            }"#,
+        );
+        // The empty row at the top is dropped
+        assert!(matches!(table.kind, RecipeKind::Shaped { width: 2, height: 2, .. }));
+        assert_eq!(table.category_id(), 3);
+
+        let matches = |grid: [Option<ItemStack>; 4]| {
+            let grid = grid.iter().map(Option::as_ref).collect::<Vec<_>>();
+            table.matches_grid(&grid, 2)
+        };
+        assert!(matches([stack(2), stack(1), stack(2), None]));
+        assert!(matches([stack(1), stack(2), None, stack(2)]));
+        assert!(!matches([stack(2), stack(1), None, stack(2)]));
+
+        // In the corner of a 3x3 grid
+        let grid = [None, None, None, None, stack(2), stack(1), None, stack(2), None];
+        let grid = grid.iter().map(Option::as_ref).collect::<Vec<_>>();
+        assert!(table.matches_grid(&grid, 3));
+    }
+
+    #[test]
+    fn test_shapeless_recipe() {
+        let flint_and_steel = recipe(
+            r#"{
+                "type": "minecraft:crafting_shapeless",
+                "ingredients": [{ "item": "minecraft:iron_ingot" }, [{ "item": "minecraft:flint" }]],
+                "result": { "item": "minecraft:flint_and_steel" }
+            }"#,
+        );
+        let matches = |grid: [Option<ItemStack>; 4]| {
+            let grid = grid.iter().map(Option::as_ref).collect::<Vec<_>>();
+            flint_and_steel.matches_grid(&grid, 2)
+        };
+        assert!(matches([None, stack(8), stack(9), None]));
+        assert!(matches([stack(9), None, None, stack(8)]));
+        assert!(!matches([stack(9), stack(9), None, None]));
+        assert!(!matches([stack(9), stack(8), stack(8), None]));
+    }
+
+    #[test]
+    fn test_smelting_and_unsupported_recipes() {
+        let beef = recipe(
+            r#"{
+                "type": "minecraft:smelting",
+                "category": "food",
+                "cookingtime": 200,
+                "experience": 0.35,
+                "ingredient": { "item": "minecraft:beef" },
+                "result": "minecraft:cooked_beef"
+            }"#,
+        );
+        assert_eq!(beef.result, ItemStack::new(6, 1));
+        assert_eq!(beef.category_id(), 0);
+        assert_eq!(beef.type_name(), "minecraft:smelting");
+
+        let special = r#"{ "type": "minecraft:crafting_special_armordye", "category": "misc" }"#;
+        assert_eq!(parse_recipe("test:dye", special, &tags(), &items()), Ok(None));
+        let unknown = r#"{
+            "type": "minecraft:crafting_shapeless",
+            "ingredients": [{ "tag": "minecraft:moon_rocks" }],
+            "result": { "item": "minecraft:stick" }
+        }"#;
+        assert!(parse_recipe("test:moon", unknown, &tags(), &items()).is_err());
+    }
+
+    #[test]
+    fn test_load() {
+        let directory =
+            std::env::temp_dir().join(format!("ferrumc-recipes-{}", std::process::id()));
+        let recipes = directory.join("minecraft").join("recipes");
+        let tags = directory.join("minecraft").join("tags").join("items");
+        std::fs::create_dir_all(&recipes).unwrap();
+        std::fs::create_dir_all(&tags).unwrap();
+        std::fs::write(
+            tags.join("planks.json"),
+            r#"{ "values": ["minecraft:oak_planks", "minecraft:birch_planks"] }"#,
+        )
+        .unwrap();
+        std::fs::write(
+            recipes.join("stick.json"),
+            r##"{
+                "type": "minecraft:crafting_shaped",
+                "key": { "#": { "tag": "minecraft:planks" } },
+                "pattern": ["#", "#"],
+                "result": { "item": "minecraft:stick", "count": 4 }
+            }"##,
+        )
+        .unwrap();
+
+        let book = RecipeBook::load(&directory, &items()).unwrap();
+        assert_eq!(book.len(), 1);
+        assert_eq!(book.recipes()[0].id, "minecraft:stick");
+
+        let mut inventory = Inventory::default();
+        inventory.set_slot(2, stack(3));
+        inventory.set_slot(4, stack(2));
+        assert!(book.update_crafting_result(&mut inventory));
+        assert_eq!(
+            inventory.slots.get(&Inventory::CRAFTING_RESULT),
+            Some(&ItemStack::new(1, 4))
+        );
+        assert!(!book.update_crafting_result(&mut inventory));
+        inventory.set_slot(4, None);
+        assert!(book.update_crafting_result(&mut inventory));
+        assert_eq!(inventory.slots.get(&Inventory::CRAFTING_RESULT), None);
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+}
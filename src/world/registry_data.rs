@@ -0,0 +1,313 @@
+//! The registries the client has to be sent before it can join: dimension types, biomes, damage
+//! types, chat types and armor trims. They're sent together as the registry codec in Login (play),
+//! and biome and chat type IDs on the network are positions in them.
+//!
+//! The vanilla registries are embedded from `.etc/codec.json`. Entries can be replaced, or new ones
+//! added, with JSON files laid out like a datapack's: `<namespace>/<registry>/<name>.json` in the
+//! `registry_data` directory. For example `registry_data/minecraft/worldgen/biome/plains.json`
+//! replaces the plains biome. New entries go after the vanilla ones, so the vanilla IDs don't
+//! change.
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::sync::OnceLock;
+
+use nbt_lib::{NBTSerialize, NBTTag};
+use serde::Deserialize;
+use serde_json::Value;
+use tracing::{debug, info};
+
+use crate::utils::prelude::*;
+
+const EMBEDDED_CODEC: &str = include_str!("../../.etc/codec.json");
+
+pub const BIOME_REGISTRY: &str = "minecraft:worldgen/biome";
+pub const CHAT_TYPE_REGISTRY: &str = "minecraft:chat_type";
+pub const DIMENSION_TYPE_REGISTRY: &str = "minecraft:dimension_type";
+
+static REGISTRY_DATA: OnceLock<RegistryData> = OnceLock::new();
+
+#[derive(Deserialize)]
+struct RegistryJson {
+    value: Vec<EntryJson>,
+}
+
+/// Entries also have an `id`, but it's always their position in the list, so it isn't read.
+#[derive(Deserialize)]
+struct EntryJson {
+    name: String,
+    element: Value,
+}
+
+/// A registry's entries, in ID order.
+#[derive(Debug, Default)]
+pub struct Registry {
+    entries: Vec<(String, Value)>,
+    ids: HashMap<String, i32>,
+}
+
+impl Registry {
+    /// Replaces the entry with the same name, or adds it to the end.
+    pub fn set(&mut self, name: &str, element: Value) {
+        match self.ids.get(name) {
+            Some(&id) => self.entries[id as usize].1 = element,
+            None => {
+                self.ids.insert(name.to_string(), self.entries.len() as i32);
+                self.entries.push((name.to_string(), element));
+            }
+        }
+    }
+
+    pub fn id(&self, name: &str) -> Option<i32> {
+        self.ids.get(name).copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Every registry, and the codec made from them. See the [module](self) docs.
+#[derive(Debug)]
+pub struct RegistryData {
+    registries: BTreeMap<String, Registry>,
+    codec: Vec<u8>,
+}
+
+impl RegistryData {
+    /// Just the vanilla registries.
+    pub fn embedded() -> Self {
+        Self::from_registries(parse(EMBEDDED_CODEC).expect("The embedded registry codec is invalid"))
+            .expect("The embedded registry codec is invalid")
+    }
+
+    /// The vanilla registries, with the overrides in `directory` if there is one.
+    pub fn load(directory: impl AsRef<Path>) -> Result<Self> {
+        let directory = directory.as_ref();
+        let mut registries = parse(EMBEDDED_CODEC)?;
+        let overrides = apply_overrides(&mut registries, directory)?;
+        if overrides > 0 {
+            info!(
+                "Loaded {} registry entries from {}",
+                overrides,
+                directory.display()
+            );
+        }
+        Self::from_registries(registries)
+    }
+
+    fn from_registries(registries: BTreeMap<String, Registry>) -> Result<Self> {
+        let codec = build_codec(&registries)?;
+        Ok(Self { registries, codec })
+    }
+
+    /// The registry codec for Login (play), an NBT compound with an empty name.
+    pub fn codec(&self) -> &[u8] {
+        &self.codec
+    }
+
+    pub fn registry(&self, name: &str) -> Option<&Registry> {
+        self.registries.get(name)
+    }
+
+    /// The network ID of an entry, e.g. `id(BIOME_REGISTRY, "minecraft:plains")`.
+    pub fn id(&self, registry: &str, name: &str) -> Option<i32> {
+        self.registry(registry)?.id(name)
+    }
+}
+
+/// Loads the registry data, with the overrides in `directory`. Until this is called, only the
+/// vanilla registries are used. The IDs can't change once clients have them, so only the first
+/// call does anything.
+pub fn init_registry_data(directory: impl AsRef<Path>) -> Result<()> {
+    if REGISTRY_DATA.get().is_some() {
+        debug!("The registry data has already been loaded");
+        return Ok(());
+    }
+    let _ = REGISTRY_DATA.set(RegistryData::load(directory)?);
+    Ok(())
+}
+
+pub fn get_registry_data() -> &'static RegistryData {
+    REGISTRY_DATA.get_or_init(RegistryData::embedded)
+}
+
+fn parse(contents: &str) -> Result<BTreeMap<String, Registry>> {
+    let json: BTreeMap<String, RegistryJson> = serde_json::from_str(contents)
+        .map_err(|e| Error::DeserializationError(format!("Invalid registry codec: {}", e)))?;
+    Ok(json
+        .into_iter()
+        .map(|(name, json)| {
+            let mut registry = Registry::default();
+            for entry in json.value {
+                registry.set(&entry.name, entry.element);
+            }
+            (name, registry)
+        })
+        .collect())
+}
+
+/// Reads the `<namespace>/<registry>/<name>.json` files in `directory`, where the registry is
+/// without its `minecraft:`, e.g. `worldgen/biome`. Returns how many entries were read.
+fn apply_overrides(registries: &mut BTreeMap<String, Registry>, directory: &Path) -> Result<usize> {
+    let namespaces = match std::fs::read_dir(directory) {
+        Ok(namespaces) => namespaces,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut count = 0;
+    for namespace in namespaces {
+        let namespace = namespace?;
+        if !namespace.file_type()?.is_dir() {
+            continue;
+        }
+        let namespace_name = namespace.file_name().to_string_lossy().to_string();
+        for (registry_name, registry) in registries.iter_mut() {
+            let Some(path) = registry_name.strip_prefix("minecraft:") else {
+                continue;
+            };
+            let Ok(files) = std::fs::read_dir(namespace.path().join(path)) else {
+                continue;
+            };
+            for file in files {
+                let path = file?.path();
+                if path.extension().is_none_or(|extension| extension != "json") {
+                    continue;
+                }
+                let Some(stem) = path.file_stem() else {
+                    continue;
+                };
+                let name = format!("{}:{}", namespace_name, stem.to_string_lossy());
+                let element = serde_json::from_str(&std::fs::read_to_string(&path)?)
+                    .map_err(|e| Error::DeserializationError(format!("{}: {}", path.display(), e)))?;
+                debug!("Setting {} in {}", name, registry_name);
+                registry.set(&name, element);
+                count += 1;
+            }
+        }
+    }
+    Ok(count)
+}
+
+fn build_codec(registries: &BTreeMap<String, Registry>) -> Result<Vec<u8>> {
+    let mut root = HashMap::new();
+    for (name, registry) in registries {
+        let mut entries = Vec::with_capacity(registry.len());
+        for (id, (entry_name, element)) in registry.entries.iter().enumerate() {
+            let element = json_to_nbt(element).map_err(|e| {
+                Error::GenericNbtError(format!("{} in {}: {}", entry_name, name, e))
+            })?;
+            entries.push(NBTTag::Compound(HashMap::from([
+                ("name".to_string(), NBTTag::String(entry_name.clone())),
+                ("id".to_string(), NBTTag::Int(id as i32)),
+                ("element".to_string(), element),
+            ])));
+        }
+        root.insert(
+            name.clone(),
+            NBTTag::Compound(HashMap::from([
+                ("type".to_string(), NBTTag::String(name.clone())),
+                ("value".to_string(), NBTTag::List(entries)),
+            ])),
+        );
+    }
+
+    // The compound's header, it's written without a name
+    let mut codec = vec![10, 0, 0];
+    NBTTag::Compound(root).nbt_serialize(&mut codec)?;
+    Ok(codec)
+}
+
+/// Whole numbers become longs and the rest doubles, since the client converts numbers to whatever
+/// type it needs. Booleans become bytes, which is how NBT stores them.
+pub(crate) fn json_to_nbt(value: &Value) -> std::result::Result<NBTTag, String> {
+    Ok(match value {
+        Value::Null => return Err("null isn't allowed".to_string()),
+        Value::Bool(value) => NBTTag::Byte(i8::from(*value)),
+        Value::Number(number) => match number.as_i64() {
+            Some(number) => NBTTag::Long(number),
+            None => NBTTag::Double(number.as_f64().unwrap_or_default()),
+        },
+        Value::String(value) => NBTTag::String(value.clone()),
+        Value::Array(values) => {
+            let tags = values.iter().map(json_to_nbt).collect::<std::result::Result<Vec<_>, _>>()?;
+            if tags.windows(2).any(|pair| pair[0].tag_type() != pair[1].tag_type()) {
+                return Err("lists can only have one type of value in them".to_string());
+            }
+            NBTTag::List(tags)
+        }
+        Value::Object(values) => NBTTag::Compound(
+            values
+                .iter()
+                .map(|(key, value)| Ok((key.clone(), json_to_nbt(value)?)))
+                .collect::<std::result::Result<_, String>>()?,
+        ),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::world::dimension::Dimension;
+
+    #[test]
+    fn test_embedded_registries() {
+        let data = RegistryData::embedded();
+        assert_eq!(data.id(BIOME_REGISTRY, "minecraft:badlands"), Some(0));
+        assert_eq!(data.id(BIOME_REGISTRY, "minecraft:plains"), Some(39));
+        assert_eq!(data.registry(BIOME_REGISTRY).unwrap().len(), 64);
+        assert_eq!(data.id(CHAT_TYPE_REGISTRY, "minecraft:chat"), Some(0));
+        for dimension in Dimension::ALL {
+            assert!(data.id(DIMENSION_TYPE_REGISTRY, dimension.id()).is_some());
+        }
+
+        // The codec reads back as one compound with every registry in it
+        let mut codec = nbt_lib::read_tag(&mut Cursor::new(data.codec().to_vec())).unwrap();
+        let mut biomes = codec.get("").unwrap().get(BIOME_REGISTRY).unwrap();
+        match biomes.get("value") {
+            Some(NBTTag::List(entries)) => assert_eq!(entries.len(), 64),
+            other => panic!("Expected a list of biomes, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_overrides() {
+        let directory =
+            std::env::temp_dir().join(format!("ferrumc-registry-data-{}", std::process::id()));
+        let biomes = directory.join("minecraft").join("worldgen").join("biome");
+        let custom = directory.join("custom").join("worldgen").join("biome");
+        std::fs::create_dir_all(&biomes).unwrap();
+        std::fs::create_dir_all(&custom).unwrap();
+        let element = r#"{"has_precipitation": false, "temperature": 2.0, "downfall": 0,
+            "effects": {"fog_color": 1, "sky_color": 2, "water_color": 3, "water_fog_color": 4}}"#;
+        std::fs::write(biomes.join("plains.json"), element).unwrap();
+        std::fs::write(custom.join("ash_fields.json"), element).unwrap();
+
+        let data = RegistryData::load(&directory).unwrap();
+        // Replacing an entry keeps its ID, new ones go at the end
+        assert_eq!(data.id(BIOME_REGISTRY, "minecraft:plains"), Some(39));
+        assert_eq!(data.id(BIOME_REGISTRY, "custom:ash_fields"), Some(64));
+        assert!(data.codec().len() > RegistryData::embedded().codec().len());
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn test_to_nbt() {
+        let value = serde_json::json!({"a": [1, 2], "b": true, "c": 0.5});
+        let NBTTag::Compound(mut compound) = json_to_nbt(&value).unwrap() else {
+            panic!("Expected a compound");
+        };
+        assert!(matches!(compound.remove("b"), Some(NBTTag::Byte(1))));
+        assert!(matches!(compound.remove("c"), Some(NBTTag::Double(_))));
+        assert!(json_to_nbt(&serde_json::json!([1, "two"])).is_err());
+        assert!(json_to_nbt(&Value::Null).is_err());
+    }
+}
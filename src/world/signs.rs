@@ -0,0 +1,193 @@
+//! Signs, and what's written on them.
+//!
+//! The text is kept in the sign's [BlockEntity]. Placing a sign opens the editor for the player who
+//! placed it, and what they write is saved with the chunk and sent to everyone who can see it.
+
+use std::collections::HashMap;
+
+use ferrumc_codec::network_types::varint::VarInt;
+use nbt_lib::NBTTag;
+use tracing::debug;
+
+use crate::net::packets::outgoing::block_entity_data::BlockEntityData;
+use crate::net::packets::outgoing::open_sign_editor::OpenSignEditor;
+use crate::net::packets::ConnectionId;
+use crate::state::GlobalState;
+use crate::utils::components::sign_editor::SignEditor;
+use crate::utils::encoding::position::Position;
+use crate::utils::prelude::*;
+use crate::utils::text_component::TextComponent;
+use crate::world::blocks::tracking_chunk;
+use crate::world::chunk_format::{BlockEntity, SignText};
+use crate::world::generation::get_or_generate_chunk;
+
+/// The block entity of standing and wall signs, of every kind of wood.
+pub const SIGN: &str = "minecraft:sign";
+pub const HANGING_SIGN: &str = "minecraft:hanging_sign";
+/// From the `minecraft:block_entity_type` registry.
+pub const SIGN_TYPE: i32 = 7;
+pub const HANGING_SIGN_TYPE: i32 = 8;
+
+/// The id of a sign block's block entity, or None if it isn't a sign.
+pub fn block_entity_id(block: &str) -> Option<&'static str> {
+    if block.ends_with("_hanging_sign") {
+        Some(HANGING_SIGN)
+    } else if block.ends_with("_sign") {
+        Some(SIGN)
+    } else {
+        None
+    }
+}
+
+impl Default for SignText {
+    /// Four empty lines in black, like a newly placed sign.
+    fn default() -> Self {
+        Self {
+            messages: vec![TextComponent::text("").to_json(); 4],
+            color: "black".to_string(),
+            has_glowing_text: false,
+        }
+    }
+}
+
+impl SignText {
+    pub fn to_nbt(&self) -> NBTTag {
+        let messages = self.messages.iter().cloned().map(NBTTag::String).collect();
+        NBTTag::Compound(HashMap::from([
+            ("messages".to_string(), NBTTag::List(messages)),
+            ("color".to_string(), NBTTag::String(self.color.clone())),
+            (
+                "has_glowing_text".to_string(),
+                NBTTag::Byte(i8::from(self.has_glowing_text)),
+            ),
+        ]))
+    }
+}
+
+/// Lets a player write on the sign at `position`, and opens the editor for its front.
+pub async fn open_sign_editor(
+    state: &GlobalState,
+    conn_id: ConnectionId,
+    position: &Position,
+    dimension: &str,
+) -> Result<()> {
+    state.world.get_component_storage().insert(
+        conn_id,
+        SignEditor {
+            position: position.clone(),
+            dimension: dimension.to_string(),
+        },
+    );
+    let conn = state.connections.get_connection(conn_id)?;
+    let conn = conn.read().await;
+    conn.send_packet(OpenSignEditor::new_auto(position.clone(), true))
+        .await
+}
+
+/// Writes on one side of the sign at `position`. Lines are plain text, formatting codes are taken
+/// out. Returns false if there's no sign there, or it's been waxed.
+pub async fn write_on_sign(
+    state: &GlobalState,
+    position: &Position,
+    dimension: &str,
+    front: bool,
+    lines: &[String],
+) -> Result<bool> {
+    let Position { x, y, z } = *position;
+    let (chunk_x, chunk_z) = (x >> 4, z >> 4);
+    let mut chunk = get_or_generate_chunk(state, chunk_x, chunk_z, dimension)
+        .await?
+        .ok_or(Error::ChunkNotFound(chunk_x, chunk_z))?;
+    let block = chunk.get_block(
+        x.rem_euclid(16) as usize,
+        y as i32,
+        z.rem_euclid(16) as usize,
+    )?;
+    let Some(id) = block_entity_id(&block.name) else {
+        return Ok(false);
+    };
+    let mut sign = chunk
+        .block_entity(x, y as i32, z)
+        .filter(|sign| sign.id == id)
+        .cloned()
+        .unwrap_or_else(|| BlockEntity::new(id, x, y as i32, z));
+    if sign.is_waxed == Some(true) {
+        return Ok(false);
+    }
+
+    let side = if front {
+        &mut sign.front_text
+    } else {
+        &mut sign.back_text
+    };
+    let text = side.get_or_insert_with(SignText::default);
+    text.messages = lines
+        .iter()
+        .map(|line| TextComponent::text(strip_formatting(line)).to_json())
+        .collect();
+
+    chunk.set_block_entity(sign.clone());
+    state.database.update_chunk(chunk).await?;
+    broadcast_block_entity(state, &sign, dimension).await;
+    Ok(true)
+}
+
+/// Sends a block entity to every player that has its chunk loaded.
+async fn broadcast_block_entity(state: &GlobalState, block_entity: &BlockEntity, dimension: &str) {
+    let Some(type_id) = block_entity.type_id() else {
+        return;
+    };
+    let data = match block_entity.client_data() {
+        Ok(data) => data,
+        Err(e) => {
+            debug!("Failed to encode a block entity: {}", e);
+            return;
+        }
+    };
+    let (x, y, z) = (block_entity.x, block_entity.y, block_entity.z);
+    for conn in tracking_chunk(state, (x >> 4, z >> 4), dimension).await {
+        let conn = conn.read().await;
+        let packet = BlockEntityData::new_auto(
+            Position::new(x, y as i16, z),
+            VarInt::new(type_id),
+            conn.metadata.protocol().network_nbt(data.clone()),
+        );
+        if let Err(e) = conn.send_packet(packet).await {
+            debug!("Failed to send block entity data: {}", e);
+        }
+    }
+}
+
+/// Takes out `§` formatting codes, like vanilla does with what's written on signs.
+fn strip_formatting(line: &str) -> String {
+    let mut stripped = String::with_capacity(line.len());
+    let mut chars = line.chars();
+    while let Some(char) = chars.next() {
+        if char == '§' {
+            chars.next();
+        } else {
+            stripped.push(char);
+        }
+    }
+    stripped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_block_entity_id() {
+        assert_eq!(block_entity_id("minecraft:oak_sign"), Some(SIGN));
+        assert_eq!(block_entity_id("minecraft:cherry_wall_sign"), Some(SIGN));
+        assert_eq!(block_entity_id("minecraft:oak_hanging_sign"), Some(HANGING_SIGN));
+        assert_eq!(block_entity_id("minecraft:oak_planks"), None);
+    }
+
+    #[test]
+    fn test_strip_formatting() {
+        assert_eq!(strip_formatting("§cRed§r and plain"), "Red and plain");
+        assert_eq!(strip_formatting("Ends with §"), "Ends with ");
+        assert_eq!(strip_formatting("Näher"), "Näher");
+    }
+}
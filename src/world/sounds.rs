@@ -0,0 +1,125 @@
+//! Sounds, played at a position or following an entity.
+//!
+//! Sounds are sent by their ID in the sound registry when it's known, see [IdRegistry], and by
+//! name otherwise, which also works for sounds from resource packs.
+
+use ferrumc_codec::network_types::varint::VarInt;
+use rand::random;
+
+use crate::net::packets::outgoing::entity_sound_effect::EntitySoundEffect;
+use crate::net::packets::outgoing::sound_effect::{SoundEffect, SoundId};
+use crate::net::utils::broadcast::{broadcast_near, broadcast_to_viewers};
+use crate::state::GlobalState;
+use crate::utils::prelude::*;
+use crate::world::dimension::Dimension;
+
+/// Sounds that are played by the server, rather than by clients when they're told something
+/// happened.
+pub mod names {
+    pub const PLAYER_LEVELUP: &str = "minecraft:entity.player.levelup";
+    pub const NOTE_BLOCK_PLING: &str = "minecraft:block.note_block.pling";
+    pub const UI_BUTTON_CLICK: &str = "minecraft:ui.button.click";
+}
+
+/// Which volume slider a sound is turned up or down by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SoundCategory {
+    Master = 0,
+    Music = 1,
+    Records = 2,
+    Weather = 3,
+    Blocks = 4,
+    Hostile = 5,
+    Neutral = 6,
+    Players = 7,
+    Ambient = 8,
+    Voice = 9,
+}
+
+impl SoundCategory {
+    /// By the name `/playsound` uses.
+    pub fn from_name(name: &str) -> Option<Self> {
+        let category = match name {
+            "master" => SoundCategory::Master,
+            "music" => SoundCategory::Music,
+            "record" => SoundCategory::Records,
+            "weather" => SoundCategory::Weather,
+            "block" => SoundCategory::Blocks,
+            "hostile" => SoundCategory::Hostile,
+            "neutral" => SoundCategory::Neutral,
+            "player" => SoundCategory::Players,
+            "ambient" => SoundCategory::Ambient,
+            "voice" => SoundCategory::Voice,
+            _ => return None,
+        };
+        Some(category)
+    }
+}
+
+/// The names `/playsound` takes, in the same order as the categories.
+pub const CATEGORY_NAMES: [&str; 10] = [
+    "master", "music", "record", "weather", "block", "hostile", "neutral", "player", "ambient",
+    "voice",
+];
+
+/// How far away a sound can be heard, which is further the louder it is.
+pub fn range(volume: f32) -> f64 {
+    16.0 * volume.max(1.0) as f64
+}
+
+/// A sound by name, using its ID if it's in the registry.
+pub fn sound_id(state: &GlobalState, name: &str) -> SoundId {
+    match state.sounds.id(name) {
+        Some(id) => SoundId::registered(id),
+        None if name.contains(':') => SoundId::named(name),
+        None => SoundId::named(&format!("minecraft:{}", name)),
+    }
+}
+
+/// Plays a sound at a position for everyone close enough to hear it.
+pub async fn play_sound(
+    state: &GlobalState,
+    name: &str,
+    category: SoundCategory,
+    dimension: Dimension,
+    position: (f64, f64, f64),
+    volume: f32,
+    pitch: f32,
+) -> Result<()> {
+    let sound = sound_id(state, name);
+    let packet = SoundEffect::new(sound, category as i32, position, volume, pitch, random());
+    broadcast_near(packet, state, dimension, position, range(volume)).await
+}
+
+/// Plays a sound on an entity, which follows it as it moves, for everyone who can see it.
+pub async fn play_entity_sound(
+    state: &GlobalState,
+    entity_id: usize,
+    name: &str,
+    category: SoundCategory,
+    volume: f32,
+    pitch: f32,
+) -> Result<()> {
+    let packet = EntitySoundEffect::new_auto(
+        sound_id(state, name),
+        VarInt::new(category as i32),
+        VarInt::new(entity_id as i32),
+        volume,
+        pitch,
+        random(),
+    );
+    broadcast_to_viewers(packet, entity_id, state).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_category_names() {
+        for (id, name) in CATEGORY_NAMES.iter().enumerate() {
+            assert_eq!(SoundCategory::from_name(name).map(|c| c as usize), Some(id));
+        }
+        assert_eq!(SoundCategory::from_name("blocks"), None);
+    }
+}
@@ -0,0 +1,269 @@
+//! Rain and thunderstorms.
+//!
+//! Like vanilla, the weather runs on two timers, one for rain and one for thunder, which flip it
+//! on or off when they run out and then start again with a random length. `/weather` sets it for a
+//! while instead, and `doWeatherCycle` stops the timers. Clients don't switch straight over, the
+//! rain and thunder fade in and out over a few seconds, and they're kept up to date with
+//! [GameEvent]s as they do.
+//!
+//! While it's thundering, lightning strikes around players in the overworld, unless it's turned
+//! off in the config. It's just for show for now, it doesn't hurt anything or start fires.
+
+use bincode::{Decode, Encode};
+use rand::Rng;
+
+use crate::net::packets::outgoing::game_event::{events, GameEvent};
+use crate::net::packets::outgoing::spawn_entity::entity_types;
+use crate::state::GlobalState;
+use crate::utils::components::dimension::CurrentDimension;
+use crate::utils::components::lightning_bolt::LightningBolt;
+use crate::utils::components::motion::Motion;
+use crate::utils::components::object_entity::ObjectEntity;
+use crate::utils::encoding::metadata::EntityMetadata;
+use crate::utils::prelude::*;
+use crate::world::blocks::surface_height;
+use crate::world::dimension::Dimension;
+
+/// How long `/weather` lasts when it isn't given a duration, in ticks.
+pub const DEFAULT_DURATION: i32 = 6000;
+/// How much the rain and thunder fade in or out each tick.
+const FADE_SPEED: f32 = 0.01;
+/// How long each kind of weather lasts, and how long until it comes back once it's over, in ticks.
+/// Same as vanilla.
+const RAIN_DURATION: (i32, i32) = (12000, 24000);
+const RAIN_DELAY: (i32, i32) = (12000, 180000);
+const THUNDER_DURATION: (i32, i32) = (3600, 15600);
+const THUNDER_DELAY: (i32, i32) = (12000, 180000);
+/// Lightning strikes near each player about once in this many ticks during a thunderstorm.
+const LIGHTNING_CHANCE: u32 = 400;
+/// How far from a player lightning can strike, in blocks.
+const LIGHTNING_RADIUS: i32 = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Encode, Decode)]
+pub struct Weather {
+    /// Ticks left of the clear weather `/weather clear` asked for. The timers don't run until it's
+    /// over.
+    pub clear_time: i32,
+    /// Ticks until [Weather::raining] flips, or 0 if it hasn't been picked yet.
+    pub rain_time: i32,
+    pub raining: bool,
+    /// Ticks until [Weather::thundering] flips, or 0 if it hasn't been picked yet. It only
+    /// thunders while it's raining too.
+    pub thunder_time: i32,
+    pub thundering: bool,
+    /// How heavy the rain is as clients see it, from 0 to 1. Fades towards [Weather::raining].
+    pub rain_level: f32,
+    /// How heavy the thunder is as clients see it, from 0 to 1. Fades towards
+    /// [Weather::thundering].
+    pub thunder_level: f32,
+}
+
+/// What `/weather` can set.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WeatherKind {
+    Clear,
+    Rain,
+    Thunder,
+}
+
+impl Weather {
+    /// Moves the weather on by a tick, returning what clients need to be told about it.
+    /// `cycle` is whether the timers run, from `doWeatherCycle`.
+    pub fn tick(&mut self, cycle: bool) -> Vec<GameEvent> {
+        let before = *self;
+        if cycle {
+            self.advance_timers();
+        }
+        self.rain_level = fade(self.rain_level, self.raining);
+        self.thunder_level = fade(self.thunder_level, self.thundering);
+        self.changes(&before)
+    }
+
+    fn advance_timers(&mut self) {
+        if self.clear_time > 0 {
+            self.clear_time -= 1;
+            self.raining = false;
+            self.thundering = false;
+            // Picked fresh once the clear weather is over
+            self.rain_time = 0;
+            self.thunder_time = 0;
+            return;
+        }
+
+        advance_timer(
+            &mut self.thunder_time,
+            &mut self.thundering,
+            THUNDER_DURATION,
+            THUNDER_DELAY,
+        );
+        advance_timer(&mut self.rain_time, &mut self.raining, RAIN_DURATION, RAIN_DELAY);
+    }
+
+    /// Sets the weather for `duration` ticks, after which the timers take over again.
+    pub fn set(&mut self, kind: WeatherKind, duration: i32) {
+        let (clear_time, time, raining, thundering) = match kind {
+            WeatherKind::Clear => (duration, 0, false, false),
+            WeatherKind::Rain => (0, duration, true, false),
+            WeatherKind::Thunder => (0, duration, true, true),
+        };
+        self.clear_time = clear_time;
+        self.rain_time = time;
+        self.thunder_time = time;
+        self.raining = raining;
+        self.thundering = thundering;
+    }
+
+    /// Whether clients show rain, rather than it having only just started fading in.
+    pub fn is_raining(&self) -> bool {
+        self.rain_level > 0.2
+    }
+
+    /// Whether there's a proper thunderstorm going, which is when lightning strikes.
+    pub fn is_thundering(&self) -> bool {
+        self.rain_level * self.thunder_level > 0.9
+    }
+
+    /// What a client that's just joined or respawned needs to be told to catch up.
+    pub fn current_events(&self) -> Vec<GameEvent> {
+        if !self.is_raining() {
+            return Vec::new();
+        }
+        vec![
+            GameEvent::new_auto(events::BEGIN_RAINING, 0.0),
+            GameEvent::new_auto(events::RAIN_LEVEL_CHANGE, self.rain_level),
+            GameEvent::new_auto(events::THUNDER_LEVEL_CHANGE, self.thunder_level),
+        ]
+    }
+
+    fn changes(&self, before: &Weather) -> Vec<GameEvent> {
+        let mut changes = Vec::new();
+        if before.is_raining() != self.is_raining() {
+            let event = match self.is_raining() {
+                true => events::BEGIN_RAINING,
+                false => events::END_RAINING,
+            };
+            changes.push(GameEvent::new_auto(event, 0.0));
+        }
+        if before.rain_level != self.rain_level {
+            changes.push(GameEvent::new_auto(events::RAIN_LEVEL_CHANGE, self.rain_level));
+        }
+        if before.thunder_level != self.thunder_level {
+            changes.push(GameEvent::new_auto(events::THUNDER_LEVEL_CHANGE, self.thunder_level));
+        }
+        changes
+    }
+}
+
+/// Counts a timer down, flipping `on` when it runs out, or starts it again if it already has.
+fn advance_timer(time: &mut i32, on: &mut bool, duration: (i32, i32), delay: (i32, i32)) {
+    if *time > 0 {
+        *time -= 1;
+        if *time == 0 {
+            *on = !*on;
+        }
+        return;
+    }
+    let (min, max) = if *on { duration } else { delay };
+    *time = rand::rng().random_range(min..=max);
+}
+
+fn fade(level: f32, towards: bool) -> f32 {
+    let step = if towards { FADE_SPEED } else { -FADE_SPEED };
+    (level + step).clamp(0.0, 1.0)
+}
+
+/// Whether lightning strikes near a player this tick.
+pub fn lightning_strikes() -> bool {
+    rand::rng().random_range(0..LIGHTNING_CHANCE) == 0
+}
+
+/// Strikes lightning somewhere near `around`, on top of the highest block there.
+pub async fn strike_near(state: &GlobalState, around: (f64, f64, f64)) -> Result<usize> {
+    let (x, z) = {
+        let mut rng = rand::rng();
+        (
+            around.0 as i32 + rng.random_range(-LIGHTNING_RADIUS..=LIGHTNING_RADIUS),
+            around.2 as i32 + rng.random_range(-LIGHTNING_RADIUS..=LIGHTNING_RADIUS),
+        )
+    };
+    let y = surface_height(state, x, z, Dimension::Overworld).await?;
+    Ok(spawn_lightning(state, (x as f64 + 0.5, y as f64, z as f64 + 0.5)).await)
+}
+
+/// Spawns a lightning bolt in the overworld. Clients play the thunder themselves when it appears.
+/// Returns its entity.
+pub async fn spawn_lightning(state: &GlobalState, position: (f64, f64, f64)) -> usize {
+    let object = ObjectEntity::new(entity_types::LIGHTNING_BOLT, EntityMetadata::new());
+    state
+        .world
+        .create_entity()
+        .await
+        .with(LightningBolt::default())
+        .with(object)
+        .with(Motion::new(position, (0.0, 0.0, 0.0)))
+        .with(CurrentDimension::new(Dimension::Overworld))
+        .build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fade_in_and_out() {
+        let mut weather = Weather::default();
+        weather.set(WeatherKind::Rain, 100);
+
+        let sent = weather.tick(false);
+        assert_eq!(weather.rain_level, FADE_SPEED);
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].event, events::RAIN_LEVEL_CHANGE);
+
+        let sent = (0..20).flat_map(|_| weather.tick(false)).collect::<Vec<_>>();
+        assert!(weather.is_raining());
+        assert!(sent.iter().any(|event| event.event == events::BEGIN_RAINING));
+
+        weather.set(WeatherKind::Clear, 100);
+        let sent = (0..200).flat_map(|_| weather.tick(false)).collect::<Vec<_>>();
+        assert_eq!(weather.rain_level, 0.0);
+        assert!(sent.iter().any(|event| event.event == events::END_RAINING));
+        // Nothing left to change
+        assert!(weather.tick(false).is_empty());
+    }
+
+    #[test]
+    fn test_timers() {
+        let mut weather = Weather::default();
+        weather.set(WeatherKind::Thunder, 2);
+        weather.tick(true);
+        assert!(weather.raining && weather.thundering);
+        weather.tick(true);
+        assert!(!weather.raining && !weather.thundering);
+
+        // Starts a random delay before it rains again
+        weather.tick(true);
+        assert!(weather.rain_time >= RAIN_DELAY.0 && weather.rain_time <= RAIN_DELAY.1);
+        assert!(!weather.raining);
+    }
+
+    #[test]
+    fn test_clear_stops_timers() {
+        let mut weather = Weather::default();
+        weather.set(WeatherKind::Clear, 2);
+        weather.tick(true);
+        weather.tick(true);
+        assert_eq!((weather.clear_time, weather.rain_time), (0, 0));
+        weather.tick(true);
+        assert!(weather.rain_time > 0);
+    }
+
+    #[test]
+    fn test_current_events() {
+        let mut weather = Weather::default();
+        assert!(weather.current_events().is_empty());
+        weather.rain_level = 1.0;
+        weather.thunder_level = 1.0;
+        assert_eq!(weather.current_events().len(), 3);
+        assert!(weather.is_thundering());
+    }
+}